@@ -0,0 +1,116 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::distilbert::{DistilBertConfig, DistilBertModelClassifier};
+use rust_bert::pipelines::chunked_inference::{run_chunked, ChunkScheduling};
+use rust_bert::Activation;
+use std::thread;
+use std::time::{Duration, Instant};
+use tch::{nn, no_grad, Device, Tensor};
+
+static NUM_INPUTS: usize = 512;
+static CHUNK_SIZE: usize = 32;
+static SEQUENCE_LENGTH: i64 = 64;
+//    Artificial delay standing in for the tokenization of a chunk, in the order of magnitude of the
+//    forward pass of the tiny model
+static TOKENIZATION_DELAY: Duration = Duration::from_millis(2);
+
+fn create_model(device: Device) -> (DistilBertModelClassifier, nn::VarStore) {
+    let config = DistilBertConfig {
+        activation: Activation::gelu,
+        attention_dropout: 0.1,
+        dim: 64,
+        dropout: 0.1,
+        hidden_dim: 128,
+        id2label: None,
+        initializer_range: 0.02,
+        is_decoder: None,
+        label2id: None,
+        max_position_embeddings: 128,
+        n_heads: 4,
+        n_layers: 2,
+        output_attentions: None,
+        output_hidden_states: None,
+        output_past: None,
+        problem_type: None,
+        qa_dropout: 0.1,
+        seq_classif_dropout: 0.2,
+        sinusoidal_pos_embds: false,
+        tie_weights_: false,
+        torchscript: None,
+        use_bfloat16: None,
+        vocab_size: 1000,
+    };
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(device);
+    let model = DistilBertModelClassifier::new(&vs.root(), &config);
+    (model, vs)
+}
+
+fn classify(
+    iters: u64,
+    model: &DistilBertModelClassifier,
+    inputs: &[i64],
+    device: Device,
+    scheduling: ChunkScheduling,
+) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = run_chunked(
+            inputs,
+            CHUNK_SIZE,
+            scheduling,
+            |chunk: &[i64]| {
+                thread::sleep(TOKENIZATION_DELAY);
+                Ok(Tensor::of_slice(chunk)
+                    .unsqueeze(1)
+                    .repeat(&[1, SEQUENCE_LENGTH])
+                    .remainder(1000))
+            },
+            |input_tensor| {
+                let logits = no_grad(|| {
+                    model
+                        .forward_t(Some(input_tensor.to(device)), None, None, false)
+                        .unwrap()
+                        .logits
+                        .to(Device::Cpu)
+                });
+                Ok(logits.argmax(-1, false).iter::<i64>().unwrap().collect())
+            },
+        )
+        .unwrap();
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_chunked_inference(c: &mut Criterion) {
+    //    Set-up model
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    let device = Device::cuda_if_available();
+    let (model, _vs) = create_model(device);
+    let inputs = (0..NUM_INPUTS as i64).collect::<Vec<i64>>();
+
+    for &(name, scheduling) in [
+        ("sequential", ChunkScheduling::Sequential),
+        ("pipelined", ChunkScheduling::Pipelined { prefetch: 2 }),
+    ]
+    .iter()
+    {
+        c.bench_function(&format!("chunked inference ({})", name), |b| {
+            b.iter_custom(|iters| black_box(classify(iters, &model, &inputs, device, scheduling)))
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_chunked_inference
+}
+
+criterion_main!(benches);
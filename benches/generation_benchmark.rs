@@ -35,8 +35,10 @@ fn create_text_generation_model() -> TextGenerationModel {
         no_repeat_ngram_size: 3,
         num_beam_groups: None,
         diversity_penalty: None,
+        decoding_strategy: None,
         num_return_sequences: 5,
         device: Device::cuda_if_available(),
+        output_denylist: None,
     };
     TextGenerationModel::new(config).unwrap()
 }
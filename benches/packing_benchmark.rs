@@ -0,0 +1,140 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::sequence_classification::{
+    PackingMode, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_bert::resources::{LocalResource, Resource};
+use rust_bert::Config;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tch::{nn, Device};
+
+static NUM_INPUTS: usize = 512;
+static VOCAB_SIZE: usize = 1000;
+static MAX_LENGTH: usize = 128;
+
+//    Randomly initialized BERT model: the benchmark measures the throughput, not the predictions
+fn create_model(directory: &Path, device: Device) -> SequenceClassificationModel {
+    let mut vocab = vec![
+        "[PAD]".to_string(),
+        "[UNK]".to_string(),
+        "[CLS]".to_string(),
+        "[SEP]".to_string(),
+        "[MASK]".to_string(),
+    ];
+    vocab.extend((vocab.len()..VOCAB_SIZE).map(|index| format!("word{}", index)));
+    let vocab_path = directory.join("vocab.txt");
+    std::fs::write(&vocab_path, vocab.join("\n")).unwrap();
+    let config_path = directory.join("config.json");
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"{{
+                "hidden_act": "gelu",
+                "attention_probs_dropout_prob": 0.1,
+                "hidden_dropout_prob": 0.1,
+                "hidden_size": 256,
+                "initializer_range": 0.02,
+                "intermediate_size": 1024,
+                "max_position_embeddings": 512,
+                "num_attention_heads": 4,
+                "num_hidden_layers": 4,
+                "type_vocab_size": 2,
+                "vocab_size": {},
+                "id2label": {{"0": "NEGATIVE", "1": "POSITIVE"}},
+                "label2id": {{"NEGATIVE": 0, "POSITIVE": 1}}
+            }}"#,
+            VOCAB_SIZE
+        ),
+    )
+    .unwrap();
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = BertForSequenceClassification::new(&vs.root(), &BertConfig::from_file(&config_path));
+    let weights_path = directory.join("rust_model.ot");
+    vs.save(&weights_path).unwrap();
+
+    let mut config = SequenceClassificationConfig::new(
+        ModelType::Bert,
+        Resource::Local(LocalResource {
+            local_path: weights_path,
+        }),
+        Resource::Local(LocalResource {
+            local_path: config_path,
+        }),
+        Resource::Local(LocalResource {
+            local_path: vocab_path,
+        }),
+        None,
+        true,
+        None,
+        None,
+    );
+    config.device = device;
+    SequenceClassificationModel::new(config).unwrap()
+}
+
+//    Short inputs of 2 to 12 words (8 tokens on average with the special tokens)
+fn create_inputs() -> Vec<String> {
+    (0..NUM_INPUTS)
+        .map(|index| {
+            (0..2 + index % 11)
+                .map(|word| format!("word{}", 5 + (index * 7 + word * 13) % (VOCAB_SIZE - 5)))
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+fn classify(
+    iters: u64,
+    model: &SequenceClassificationModel,
+    inputs: &[&str],
+    mode: Option<PackingMode>,
+) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = match mode {
+            Some(mode) => model.predict_packed(inputs, MAX_LENGTH, mode).unwrap(),
+            None => model.predict(inputs).unwrap(),
+        };
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_packing(c: &mut Criterion) {
+    //    Set-up model
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    let directory = tempfile::tempdir().unwrap();
+    let model = create_model(directory.path(), Device::cuda_if_available());
+    let inputs = create_inputs();
+    let inputs = inputs.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    for &(name, mode) in [
+        ("unpacked", None),
+        ("packed, shared attention", Some(PackingMode::Shared)),
+        ("packed, block-diagonal", Some(PackingMode::BlockDiagonal)),
+    ]
+    .iter()
+    {
+        c.bench_function(&format!("short inputs classification ({})", name), |b| {
+            b.iter_custom(|iters| black_box(classify(iters, &model, &inputs, mode)))
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_packing
+}
+
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::distilbert::{
+    DistilBertConfig, DistilBertConfigResources, DistilBertModelClassifier,
+    DistilBertModelResources,
+};
+use rust_bert::pipelines::common::get_padded_length;
+use rust_bert::resources::{RemoteResource, Resource};
+use rust_bert::Config;
+use std::time::{Duration, Instant};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+static BATCH_SIZE: i64 = 64;
+static SEQUENCE_LENGTH: usize = 61;
+
+fn create_model(device: Device) -> (DistilBertModelClassifier, nn::VarStore) {
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertConfigResources::DISTIL_BERT_SST2,
+    ));
+    let weights_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertModelResources::DISTIL_BERT_SST2,
+    ));
+    let config = DistilBertConfig::from_file(config_resource.get_local_path().unwrap());
+    let mut vs = nn::VarStore::new(device);
+    let model = DistilBertModelClassifier::new(&vs.root(), &config);
+    vs.load(weights_resource.get_local_path().unwrap()).unwrap();
+    vs.half();
+    (model, vs)
+}
+
+fn forward_pass(
+    iters: u64,
+    model: &DistilBertModelClassifier,
+    input: &Tensor,
+    mask: &Tensor,
+) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = no_grad(|| {
+            model
+                .forward_t(Some(input.copy()), Some(mask.copy()), None, false)
+                .unwrap()
+                .logits
+                .to(Device::Cpu)
+        });
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_padding(c: &mut Criterion) {
+    //    Set-up model (fp16, only relevant on GPU)
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    if !tch::Cuda::is_available() {
+        println!("CUDA is not available, skipping the padding benchmark");
+        return;
+    }
+    let device = Device::Cuda(0);
+    let (model, _vs) = create_model(device);
+
+    //    Define inputs
+    let padded_length = get_padded_length(SEQUENCE_LENGTH, Some(8));
+    for &sequence_length in [SEQUENCE_LENGTH, padded_length].iter() {
+        let input = Tensor::randint(
+            1000,
+            &[BATCH_SIZE, sequence_length as i64],
+            (Kind::Int64, device),
+        );
+        let mut mask = vec![1i64; SEQUENCE_LENGTH];
+        mask.extend(vec![0i64; sequence_length - SEQUENCE_LENGTH]);
+        let mask = Tensor::of_slice(&mask)
+            .unsqueeze(0)
+            .repeat(&[BATCH_SIZE, 1])
+            .to(device);
+        c.bench_function(
+            &format!("fp16 forward pass (sequence length {})", sequence_length),
+            |b| b.iter_custom(|iters| black_box(forward_pass(iters, &model, &input, &mask))),
+        );
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_padding
+}
+
+criterion_main!(benches);
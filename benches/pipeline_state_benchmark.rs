@@ -0,0 +1,66 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::bart::BartModelResources;
+use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+use rust_bert::resources::{RemoteResource, Resource};
+use std::time::{Duration, Instant};
+use tch::Device;
+
+fn load_from_sources(iters: u64) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = black_box(ZeroShotClassificationModel::new(Default::default()).unwrap());
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn load_from_state(iters: u64, state_path: &std::path::Path) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = black_box(
+            ZeroShotClassificationModel::load_from_state(
+                state_path,
+                Resource::Remote(RemoteResource::from_pretrained(
+                    BartModelResources::BART_MNLI,
+                )),
+                Device::cuda_if_available(),
+            )
+            .unwrap(),
+        );
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_pipeline_state(c: &mut Criterion) {
+    //    Set-up the state, downloading the resources if needed
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    let directory = tempfile::tempdir().unwrap();
+    let state_path = directory.path().join("zero_shot.state");
+    ZeroShotClassificationModel::new(Default::default())
+        .unwrap()
+        .save_state(&state_path)
+        .unwrap();
+
+    c.bench_function("zero-shot cold start (sources)", |b| {
+        b.iter_custom(load_from_sources)
+    });
+    c.bench_function("zero-shot cold start (state)", |b| {
+        b.iter_custom(|iters| load_from_state(iters, &state_path))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_pipeline_state
+}
+
+criterion_main!(benches);
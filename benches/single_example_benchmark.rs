@@ -0,0 +1,74 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::pipelines::ner::NERModel;
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+use std::time::{Duration, Instant};
+use tch::Device;
+
+//    Latency of batch-of-1 predictions. Compare against a previous version with
+//    `cargo bench --bench single_example_benchmark -- --save-baseline <name>` and `--baseline <name>`.
+
+static INPUT: &str = "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.";
+
+fn sequence_classification_single(iters: u64, model: &SequenceClassificationModel) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = model.predict(&[INPUT]);
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn ner_single(iters: u64, model: &NERModel) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        let _ = model.predict(&[INPUT]);
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_single_example(c: &mut Criterion) {
+    //    Set-up models
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    let sequence_classification_model =
+        SequenceClassificationModel::new(SequenceClassificationConfig {
+            device: Device::cuda_if_available(),
+            ..Default::default()
+        })
+        .unwrap();
+    let ner_model = NERModel::new(TokenClassificationConfig {
+        device: Device::cuda_if_available(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    c.bench_function("Sequence classification batch of 1", |b| {
+        b.iter_custom(|iters| {
+            black_box(sequence_classification_single(
+                iters,
+                &sequence_classification_model,
+            ))
+        })
+    });
+    c.bench_function("NER batch of 1", |b| {
+        b.iter_custom(|iters| black_box(ner_single(iters, &ner_model)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(100);
+    targets = bench_single_example
+}
+
+criterion_main!(benches);
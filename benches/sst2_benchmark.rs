@@ -29,13 +29,15 @@ fn sst2_forward_pass(iters: u64, model: &SentimentModel, sst2_data: &[String]) -
         let start = Instant::now();
         for batch in sst2_data.chunks(batch_size) {
             output.push(
-                model.predict(
-                    batch
-                        .iter()
-                        .map(|v| v.as_str())
-                        .collect::<Vec<&str>>()
-                        .as_slice(),
-                ),
+                model
+                    .predict(
+                        batch
+                            .iter()
+                            .map(|v| v.as_str())
+                            .collect::<Vec<&str>>()
+                            .as_slice(),
+                    )
+                    .unwrap(),
             );
         }
         duration = duration.checked_add(start.elapsed()).unwrap();
@@ -25,7 +25,7 @@ fn main() -> anyhow::Result<()> {
     ];
 
     //    Run model
-    let output = ner_model.predict(&input);
+    let output = ner_model.predict(&input)?;
     for entity in output {
         println!("{:?}", entity);
     }
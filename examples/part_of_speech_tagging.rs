@@ -22,7 +22,7 @@ fn main() -> anyhow::Result<()> {
     let input = ["My name is Bob"];
 
     //    Run model
-    let output = pos_model.predict(&input);
+    let output = pos_model.predict(&input)?;
     for pos_tag in output {
         println!("{:?}", pos_tag);
     }
@@ -0,0 +1,546 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal HTTP server exposing the NER and zero-shot classification pipelines. This is a reference
+//! integration of the pipelines in a service, to be adapted rather than used as is.
+//!
+//! Endpoints:
+//! - `POST /ner` with `{"inputs": ["..."]}`, returning the entities of each input
+//! - `POST /zero-shot` with `{"inputs": ["..."], "labels": ["..."]}`, returning the top label of each input
+//! - `GET /healthz`, returning the runtime settings, the served models and the queue depth of each pipeline
+//! - `POST /shutdown`, stopping the server once the requests in flight are answered. The request
+//! must carry an `Authorization: Bearer <token>` header matching the `SERVE_SHUTDOWN_TOKEN`
+//! environment variable, the endpoint is disabled if the variable is not set.
+//!
+//! NER requests are batched through a `FairScheduler`, the `X-Tenant` header selecting the tenant.
+//! Concurrent zero-shot requests are batched together by a worker thread owning the model, each
+//! request keeping its own candidate labels (`predict_varied`).
+//!
+//! ```sh
+//! SERVE_SHUTDOWN_TOKEN=secret cargo run --example serve --features "ner zero-shot serde" -- 127.0.0.1:8000
+//! curl -X POST -d '{"inputs": ["My name is Amy. I live in Paris."]}' 127.0.0.1:8000/ner
+//! curl 127.0.0.1:8000/healthz
+//! curl -X POST -H 'Authorization: Bearer secret' 127.0.0.1:8000/shutdown
+//! ```
+
+extern crate anyhow;
+
+use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::fair_scheduler::{FairScheduler, FairSchedulerConfig};
+use rust_bert::pipelines::ner::{Entity, NERModel};
+use rust_bert::pipelines::sequence_classification::Label;
+use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+use rust_bert::pipelines::zero_shot_classification::{
+    ZeroShotClassificationConfig, ZeroShotClassificationModel,
+};
+use rust_bert::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use tch::Device;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Configuration of the server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Number of threads handling the HTTP requests
+    pub num_workers: usize,
+    /// Configuration of the scheduler batching the NER requests
+    pub scheduler: FairSchedulerConfig,
+    /// Maximum sequence length of the zero-shot classification inputs
+    pub zero_shot_max_length: usize,
+    /// Maximum number of inputs of the zero-shot requests batched in a single prediction. A larger
+    /// request is processed in its own batch.
+    pub zero_shot_max_batch_inputs: usize,
+    /// Token expected in the `Authorization: Bearer` header of `POST /shutdown` requests. The
+    /// endpoint is disabled if `None`.
+    pub shutdown_token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            num_workers: 4,
+            scheduler: FairSchedulerConfig::default(),
+            zero_shot_max_length: 128,
+            zero_shot_max_batch_inputs: 64,
+            shutdown_token: None,
+        }
+    }
+}
+
+/// Model served by a pipeline, as reported by `GET /healthz`
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub model_type: String,
+    pub device: String,
+}
+
+impl ModelInfo {
+    fn new(model_type: ModelType, device: Device) -> ModelInfo {
+        ModelInfo {
+            model_type: format!("{:?}", model_type),
+            device: format!("{:?}", device),
+        }
+    }
+}
+
+/// Pipelines served by the `InferenceServer`, with the description of their models
+pub struct Pipelines {
+    pub ner: NERModel,
+    pub ner_info: ModelInfo,
+    pub zero_shot: ZeroShotClassificationModel,
+    pub zero_shot_info: ModelInfo,
+}
+
+impl Pipelines {
+    /// Builds the pipelines from their configuration
+    pub fn new(
+        ner_config: TokenClassificationConfig,
+        zero_shot_config: ZeroShotClassificationConfig,
+    ) -> Result<Pipelines, RustBertError> {
+        let ner_info = ModelInfo::new(ner_config.model_type, ner_config.device);
+        let zero_shot_info = ModelInfo::new(zero_shot_config.model_type, zero_shot_config.device);
+        Ok(Pipelines {
+            ner: NERModel::new(ner_config)?,
+            ner_info,
+            zero_shot: ZeroShotClassificationModel::new(zero_shot_config)?,
+            zero_shot_info,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct NerRequest {
+    inputs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ZeroShotRequest {
+    inputs: Vec<String>,
+    labels: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RuntimeInfo {
+    rust_bert_version: &'static str,
+    num_threads: i32,
+    cuda_available: bool,
+    cuda_device_count: i64,
+    cudnn_available: bool,
+    uptime_seconds: u64,
+}
+
+#[derive(Serialize)]
+struct ModelsInfo {
+    ner: ModelInfo,
+    zero_shot: ModelInfo,
+}
+
+#[derive(Serialize)]
+struct QueueDepth {
+    queued: usize,
+    in_flight: usize,
+}
+
+#[derive(Serialize)]
+struct QueuesInfo {
+    ner: QueueDepth,
+    zero_shot: QueueDepth,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    runtime: RuntimeInfo,
+    models: ModelsInfo,
+    queues: QueuesInfo,
+}
+
+/// Increments a counter for the lifetime of the guard
+struct CounterGuard<'a>(&'a AtomicUsize);
+
+impl<'a> CounterGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> CounterGuard<'a> {
+        counter.fetch_add(1, Ordering::SeqCst);
+        CounterGuard(counter)
+    }
+}
+
+impl Drop for CounterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct ZeroShotJob {
+    inputs: Vec<String>,
+    labels: Vec<String>,
+    reply: Sender<Result<Vec<Label>, String>>,
+}
+
+/// Runs the queued zero-shot requests in batches until all senders are dropped. The requests
+/// queued at that point are still processed.
+fn run_zero_shot_worker(
+    model: ZeroShotClassificationModel,
+    jobs: Receiver<ZeroShotJob>,
+    queued: Arc<AtomicUsize>,
+    max_length: usize,
+    max_batch_inputs: usize,
+) {
+    let mut pending = None;
+    while let Some(first_job) = pending.take().or_else(|| jobs.recv().ok()) {
+        queued.fetch_sub(1, Ordering::SeqCst);
+        let mut num_inputs = first_job.inputs.len();
+        let mut batch = vec![first_job];
+        while let Ok(job) = jobs.try_recv() {
+            if num_inputs + job.inputs.len() > max_batch_inputs {
+                pending = Some(job);
+                break;
+            }
+            queued.fetch_sub(1, Ordering::SeqCst);
+            num_inputs += job.inputs.len();
+            batch.push(job);
+        }
+        match predict_zero_shot_batch(&model, &batch, max_length) {
+            Ok(outputs) => {
+                for (job, output) in batch.iter().zip(outputs) {
+                    let _ = job.reply.send(Ok(output));
+                }
+            }
+            //    An invalid request fails the whole batch: the requests are retried one by one to
+            //    return the error to the request causing it only
+            Err(_) => {
+                for job in batch.iter() {
+                    let output =
+                        predict_zero_shot_batch(&model, std::slice::from_ref(job), max_length)
+                            .map(|mut outputs| outputs.remove(0))
+                            .map_err(|error| error.to_string());
+                    let _ = job.reply.send(output);
+                }
+            }
+        }
+    }
+}
+
+/// Classifies the inputs of all jobs in a single prediction, returning the labels of each job with
+/// the `sentence` index relative to the inputs of the job
+fn predict_zero_shot_batch(
+    model: &ZeroShotClassificationModel,
+    batch: &[ZeroShotJob],
+    max_length: usize,
+) -> Result<Vec<Vec<Label>>, RustBertError> {
+    let labels = batch
+        .iter()
+        .map(|job| job.labels.iter().map(String::as_str).collect::<Vec<&str>>())
+        .collect::<Vec<Vec<&str>>>();
+    let mut inputs = vec![];
+    let mut labels_per_input = vec![];
+    for (job, job_labels) in batch.iter().zip(labels.iter()) {
+        for input in job.inputs.iter() {
+            inputs.push(input.as_str());
+            labels_per_input.push(job_labels.as_slice());
+        }
+    }
+    let mut output = model
+        .predict_varied(&inputs, &labels_per_input, None, max_length)?
+        .into_iter();
+    let mut offset = 0;
+    Ok(batch
+        .iter()
+        .map(|job| {
+            let job_output = output
+                .by_ref()
+                .take(job.inputs.len())
+                .map(|mut label| {
+                    label.sentence -= offset;
+                    label
+                })
+                .collect::<Vec<Label>>();
+            offset += job.inputs.len();
+            job_output
+        })
+        .collect())
+}
+
+struct AppState {
+    ner: FairScheduler<NERModel>,
+    ner_in_flight: AtomicUsize,
+    zero_shot_jobs: SyncSender<ZeroShotJob>,
+    zero_shot_queued: Arc<AtomicUsize>,
+    zero_shot_in_flight: AtomicUsize,
+    models: ModelsInfo,
+    shutdown_token: Option<String>,
+    num_workers: usize,
+    started: Instant,
+    shutting_down: AtomicBool,
+}
+
+impl AppState {
+    fn health(&self) -> HealthResponse {
+        HealthResponse {
+            status: if self.shutting_down.load(Ordering::SeqCst) {
+                "shutting_down"
+            } else {
+                "ok"
+            },
+            runtime: RuntimeInfo {
+                rust_bert_version: env!("CARGO_PKG_VERSION"),
+                num_threads: tch::get_num_threads(),
+                cuda_available: tch::Cuda::is_available(),
+                cuda_device_count: tch::Cuda::device_count(),
+                cudnn_available: tch::Cuda::cudnn_is_available(),
+                uptime_seconds: self.started.elapsed().as_secs(),
+            },
+            models: ModelsInfo {
+                ner: self.models.ner.clone(),
+                zero_shot: self.models.zero_shot.clone(),
+            },
+            queues: QueuesInfo {
+                ner: QueueDepth {
+                    queued: self.ner.queue_length(),
+                    in_flight: self.ner_in_flight.load(Ordering::SeqCst),
+                },
+                zero_shot: QueueDepth {
+                    queued: self.zero_shot_queued.load(Ordering::SeqCst),
+                    in_flight: self.zero_shot_in_flight.load(Ordering::SeqCst),
+                },
+            },
+        }
+    }
+
+    fn is_authorized(&self, request: &Request, token: &str) -> bool {
+        let expected = format!("Bearer {}", token);
+        request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Authorization") && header.value.as_str() == expected)
+    }
+
+    fn shutdown(&self, server: &Server) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        //    Wakes up the workers blocked on `recv`, the requests being processed complete normally
+        for _ in 0..self.num_workers {
+            server.unblock();
+        }
+    }
+
+    fn predict_ner(&self, request: &mut Request) -> Result<Vec<Vec<Entity>>, String> {
+        let body: NerRequest = read_body(request)?;
+        let tenant = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("X-Tenant"))
+            .map(|header| header.value.as_str().to_string())
+            .unwrap_or_else(|| "default".to_string());
+        let inputs = body
+            .inputs
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        let _in_flight = CounterGuard::new(&self.ner_in_flight);
+        self.ner
+            .submit(&tenant, &inputs)
+            .map_err(|error| error.to_string())
+    }
+
+    fn predict_zero_shot(&self, request: &mut Request) -> Result<Vec<Label>, String> {
+        let body: ZeroShotRequest = read_body(request)?;
+        let (reply, output) = channel();
+        let _in_flight = CounterGuard::new(&self.zero_shot_in_flight);
+        self.zero_shot_queued.fetch_add(1, Ordering::SeqCst);
+        self.zero_shot_jobs
+            .send(ZeroShotJob {
+                inputs: body.inputs,
+                labels: body.labels,
+                reply,
+            })
+            .map_err(|_| "The zero-shot classification worker stopped".to_string())?;
+        output
+            .recv()
+            .map_err(|_| "The zero-shot classification worker stopped".to_string())?
+    }
+
+    fn handle(&self, server: &Server, mut request: Request) {
+        let response = match (request.method(), request.url()) {
+            (Method::Post, "/ner") => match self.predict_ner(&mut request) {
+                Ok(entities) => json_response(200, &entities),
+                Err(error) => error_response(400, &error),
+            },
+            (Method::Post, "/zero-shot") => match self.predict_zero_shot(&mut request) {
+                Ok(labels) => json_response(200, &labels),
+                Err(error) => error_response(400, &error),
+            },
+            (Method::Get, "/healthz") => json_response(200, &self.health()),
+            (Method::Post, "/shutdown") => match &self.shutdown_token {
+                Some(token) if self.is_authorized(&request, token) => {
+                    self.shutdown(server);
+                    json_response(202, &serde_json::json!({ "status": "shutting_down" }))
+                }
+                Some(_) => error_response(401, "Unauthorized"),
+                None => error_response(404, "Not found"),
+            },
+            _ => error_response(404, "Not found"),
+        };
+        let _ = request.respond(response);
+    }
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    Response::from_data(serde_json::to_vec(body).unwrap())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error_response(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
+
+fn read_body<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, String> {
+    serde_json::from_reader(request.as_reader()).map_err(|error| error.to_string())
+}
+
+/// HTTP server exposing the NER and zero-shot classification pipelines. Dropping the server stops
+/// it, waiting for the requests in flight and the queued zero-shot requests to be answered.
+pub struct InferenceServer {
+    server: Arc<Server>,
+    state: Option<Arc<AppState>>,
+    workers: Vec<JoinHandle<()>>,
+    zero_shot_worker: Option<JoinHandle<()>>,
+}
+
+impl InferenceServer {
+    /// Binds the server to `address` (port 0 selecting a free port) and starts the worker threads
+    pub fn start<A: ToSocketAddrs>(
+        address: A,
+        pipelines: Pipelines,
+        config: ServerConfig,
+    ) -> Result<InferenceServer, RustBertError> {
+        if (config.num_workers == 0) | (config.zero_shot_max_batch_inputs == 0) {
+            return Err(RustBertError::ValueError(
+                "The number of workers and the zero-shot batch size must be strictly positive"
+                    .to_string(),
+            ));
+        }
+        let server = Arc::new(
+            Server::http(address).map_err(|error| RustBertError::IOError(error.to_string()))?,
+        );
+
+        let (zero_shot_jobs, jobs) = sync_channel(config.num_workers);
+        let zero_shot_queued = Arc::new(AtomicUsize::new(0));
+        let zero_shot_worker = {
+            let model = pipelines.zero_shot;
+            let queued = zero_shot_queued.clone();
+            let max_length = config.zero_shot_max_length;
+            let max_batch_inputs = config.zero_shot_max_batch_inputs;
+            thread::spawn(move || {
+                run_zero_shot_worker(model, jobs, queued, max_length, max_batch_inputs)
+            })
+        };
+
+        let state = Arc::new(AppState {
+            ner: FairScheduler::new(pipelines.ner, config.scheduler),
+            ner_in_flight: AtomicUsize::new(0),
+            zero_shot_jobs,
+            zero_shot_queued,
+            zero_shot_in_flight: AtomicUsize::new(0),
+            models: ModelsInfo {
+                ner: pipelines.ner_info,
+                zero_shot: pipelines.zero_shot_info,
+            },
+            shutdown_token: config.shutdown_token,
+            num_workers: config.num_workers,
+            started: Instant::now(),
+            shutting_down: AtomicBool::new(false),
+        });
+
+        let workers = (0..config.num_workers)
+            .map(|_| {
+                let state = state.clone();
+                let server = server.clone();
+                thread::spawn(move || {
+                    while !state.shutting_down.load(Ordering::SeqCst) {
+                        match server.recv() {
+                            Ok(request) => state.handle(&server, request),
+                            Err(_) => break,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Ok(InferenceServer {
+            server,
+            state: Some(state),
+            workers,
+            zero_shot_worker: Some(zero_shot_worker),
+        })
+    }
+
+    /// Returns the address the server listens on
+    pub fn address(&self) -> SocketAddr {
+        self.server.server_addr()
+    }
+
+    /// Blocks until a shutdown is requested and the requests in flight are answered
+    pub fn wait(mut self) {
+        self.stop_workers();
+    }
+
+    fn stop_workers(&mut self) {
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        //    Dropping the last reference to the state closes the zero-shot queue: the worker
+        //    answers the queued requests and stops
+        self.state = None;
+        if let Some(worker) = self.zero_shot_worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for InferenceServer {
+    fn drop(&mut self) {
+        if let Some(state) = &self.state {
+            state.shutdown(&self.server);
+        }
+        self.stop_workers();
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8000".to_string());
+
+    //    Set-up models
+    let pipelines = Pipelines::new(Default::default(), Default::default())?;
+    let config = ServerConfig {
+        shutdown_token: std::env::var("SERVE_SHUTDOWN_TOKEN").ok(),
+        ..Default::default()
+    };
+
+    let server = InferenceServer::start(&address, pipelines, config)?;
+    println!("Listening on {}", server.address());
+
+    //    Returns once `POST /shutdown` was received and the requests in flight are answered
+    server.wait();
+    println!("Server stopped");
+    Ok(())
+}
@@ -43,7 +43,7 @@ fn main() -> anyhow::Result<()> {
         "My name is Amélie. I live in Москва.",
         "Chongqing is a city in China.",
     ];
-    let token_outputs = token_classification_model.predict(&input, true, false); //ignore_first_label = true (only returns the NER parts, ignoring first label O)
+    let token_outputs = token_classification_model.predict(&input, true, false)?; //ignore_first_label = true (only returns the NER parts, ignoring first label O)
 
     for token in token_outputs {
         println!("{:?}", token);
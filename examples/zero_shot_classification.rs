@@ -29,7 +29,7 @@ fn main() -> anyhow::Result<()> {
             format!("This example is about {}.", label)
         })),
         128,
-    );
+    )?;
 
     println!("{:?}", output);
 
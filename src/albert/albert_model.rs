@@ -15,7 +15,7 @@ use crate::albert::encoder::AlbertTransformer;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::{albert::embeddings::AlbertEmbeddings, common::activations::TensorFunction};
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Borrow, collections::HashMap};
 use tch::nn::Module;
@@ -86,6 +86,7 @@ pub struct AlbertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config<AlbertConfig> for AlbertConfig {}
@@ -27,7 +27,7 @@ use crate::pipelines::generation_utils::private_generation_utils::{
 use crate::pipelines::generation_utils::{
     Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
 };
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use rust_tokenizers::tokenizer::{RobertaTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::{RobertaVocab, Vocab};
 use serde::{Deserialize, Serialize};
@@ -206,6 +206,7 @@ pub struct BartConfig {
     pub pad_token_id: Option<i64>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
     pub init_std: f64,
     pub is_decoder: Option<bool>,
     pub is_encoder_decoder: Option<bool>,
@@ -267,11 +268,17 @@ pub(crate) fn _make_causal_mask(
 pub(crate) fn _expand_mask(mask: &Tensor, target_length: Option<i64>) -> Tensor {
     let (batch_size, source_length) = mask.size2().unwrap();
     let target_length = target_length.unwrap_or(source_length);
+    //    Floating point masks keep their precision (e.g. half precision inference)
+    let kind = if mask.is_floating_point() {
+        mask.kind()
+    } else {
+        Kind::Float
+    };
     let expanded_mask = mask
         .unsqueeze(1)
         .unsqueeze(1)
         .expand(&[batch_size, 1, target_length, source_length], true)
-        .totype(Kind::Float);
+        .totype(kind);
     let inverted_mask: Tensor = 1 - expanded_mask;
     inverted_mask.masked_fill(&inverted_mask.to_kind(Kind::Bool), f64::NEG_INFINITY)
 }
@@ -808,20 +815,23 @@ impl BartForSequenceClassification {
             None,
             train,
         );
-        let eos_mask = input_ids.eq(self.eos_token_id);
-        let reshape = eos_mask.sum1(&[1], true, Int64);
+        //    Pool the representation of the last EOS token of each sequence, located from the
+        //    input ids so that padded sequences are not pooled at a padding position
+        let sequence_length = *input_ids.size().last().unwrap();
+        let eos_positions = (input_ids.eq(self.eos_token_id).to_kind(Int64)
+            * Tensor::arange(sequence_length, (Int64, input_ids.device())))
+        .argmax(-1, false);
+        let hidden_size = *base_model_output.decoder_output.size().last().unwrap();
         let sentence_representation = base_model_output
             .decoder_output
-            .permute(&[2, 0, 1])
-            .masked_select(&eos_mask)
-            .view((-1, reshape.size()[0] * reshape.int64_value(&[0, 0])))
-            .transpose(0, 1)
-            .view((
-                base_model_output.decoder_output.size()[0],
-                -1,
-                *base_model_output.decoder_output.size().last().unwrap(),
-            ))
-            .select(1, -1);
+            .gather(
+                1,
+                &eos_positions
+                    .view((-1, 1, 1))
+                    .expand(&[-1, 1, hidden_size], true),
+                false,
+            )
+            .squeeze1(1);
 
         let logits = self
             .classification_head
@@ -1067,7 +1077,9 @@ impl BartGenerator {
             None,
             false,
         )?;
-        let config = BartConfig::from_file(config_path);
+        let mut config = BartConfig::from_file(config_path);
+        //    The generation loop only feeds the last generated token to the decoder, which requires the past states to be cached
+        config.output_past = Some(true);
         let model = BartForConditionalGeneration::new(&var_store.root(), &config);
         var_store.load(weights_path)?;
 
@@ -1172,15 +1184,15 @@ impl PrivateLanguageGenerator<BartForConditionalGeneration, RobertaVocab, Robert
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
         match past {
-            Cache::BARTCache(past) => PreparedInput {
+            Cache::BARTCache(Some(past)) => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
                 prepared_decoder_input: Some(input_ids.narrow(1, -1, 1)),
                 prepared_position_ids: None,
-                prepared_past: Cache::BARTCache(past),
+                prepared_past: Cache::BARTCache(Some(past)),
             },
-            Cache::None => PreparedInput {
+            Cache::BARTCache(None) | Cache::None => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
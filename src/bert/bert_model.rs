@@ -19,7 +19,7 @@ use crate::{
     bert::embeddings::{BertEmbedding, BertEmbeddings},
     common::activations::TensorFunction,
 };
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -110,6 +110,7 @@ pub struct BertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config<BertConfig> for BertConfig {}
@@ -742,6 +743,62 @@ impl BertForSequenceClassification {
             all_attentions: base_model_output.all_attentions,
         }
     }
+
+    /// Forward pass through the model for rows packing several sequences, each sequence starting
+    /// with its own classification token. Each sequence is classified from the pooled hidden state
+    /// of its first token, as done for sequences encoded separately.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*) containing the packed sequences
+    /// * `mask` - Mask of shape (*batch size*, *sequence_length*), or of shape (*batch size*, *sequence_length*, *sequence_length*) to restrict the attention of the tokens of each sequence to their own sequence. Masked position have value 0, non-masked value 1.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*), e.g. restarting from 0 at the start of each sequence. If None, will be incremented from 0.
+    /// * `sequence_starts` - Positions of the first token of the sequences of each row, of shape (*batch size*, *max sequences per row*)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `logits` - `Tensor` of shape (*batch size*, *max sequences per row*, *num_labels*)
+    pub fn forward_packed_t(
+        &self,
+        input_ids: &Tensor,
+        mask: &Tensor,
+        position_ids: Option<Tensor>,
+        sequence_starts: &Tensor,
+        train: bool,
+    ) -> Tensor {
+        let hidden_state = self
+            .bert
+            .forward_t(
+                Some(input_ids.copy()),
+                Some(mask.copy()),
+                None,
+                position_ids,
+                None,
+                &None,
+                &None,
+                train,
+            )
+            .unwrap()
+            .hidden_state;
+        let hidden_size = hidden_state.size()[2];
+        let (batch_size, max_sequences) = sequence_starts.size2().unwrap();
+        let first_token_states = hidden_state.gather(
+            1,
+            &sequence_starts
+                .unsqueeze(-1)
+                .expand(&[batch_size, max_sequences, hidden_size], true),
+            false,
+        );
+        self.bert
+            .pooler
+            .as_ref()
+            .unwrap()
+            .forward(&first_token_states.view((-1, 1, hidden_size)))
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier)
+            .view((batch_size, max_sequences, -1))
+    }
 }
 
 /// # BERT for multiple choices
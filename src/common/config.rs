@@ -9,7 +9,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -43,3 +43,17 @@ where
         config
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// # Training objective of a classification head
+/// Read from the `problem_type` field of the configuration files, indicating the activation the
+/// head was trained with.
+pub enum ProblemType {
+    /// Regression, the outputs are used without activation
+    Regression,
+    /// Single label classification, trained with a softmax over the labels
+    SingleLabelClassification,
+    /// Multi-label classification, trained with an independent sigmoid for each label
+    MultiLabelClassification,
+}
@@ -0,0 +1,107 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Borrow;
+use tch::nn::Init;
+use tch::{nn, no_grad, Device, Tensor};
+
+/// # Linear-chain conditional random field decoding layer
+/// Holds the transition scores between the labels of consecutive tokens (`transitions[i][j]`
+/// being the score of label `j` following label `i`), stored as `transitions` in the variable
+/// store. The best sequence of labels is decoded with the Viterbi algorithm.
+#[derive(Debug)]
+pub struct Crf {
+    transitions: Tensor,
+}
+
+impl Crf {
+    pub fn new<'p, P>(p: P, num_labels: i64) -> Crf
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let transitions = p.var("transitions", &[num_labels, num_labels], Init::Const(0.0));
+        Crf { transitions }
+    }
+
+    /// Returns the sequence of labels maximizing the sum of the emission scores of the tokens and
+    /// of the transition scores between consecutive labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `emissions` - Emission scores (e.g. logits or log-probabilities) of shape (*sequence length*, *num_labels*)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<i64>` label index of each token
+    pub fn decode(&self, emissions: &Tensor) -> Vec<i64> {
+        let sequence_length = emissions.size()[0];
+        if sequence_length == 0 {
+            return vec![];
+        }
+        no_grad(|| {
+            let transitions = self.transitions.to(Device::Cpu);
+            let emissions = emissions.to(Device::Cpu);
+            let mut scores = emissions.get(0);
+            //    Best previous label for each label of each position
+            let mut back_pointers = Vec::with_capacity(sequence_length as usize - 1);
+            for position in 1..sequence_length {
+                let (best_scores, best_previous) =
+                    (scores.unsqueeze(1) + &transitions).max2(0, false);
+                scores = best_scores + emissions.get(position);
+                back_pointers.push(best_previous);
+            }
+            let mut label = scores.argmax(-1, false).int64_value(&[]);
+            let mut path = vec![label];
+            for best_previous in back_pointers.iter().rev() {
+                label = best_previous.int64_value(&[label]);
+                path.push(label);
+            }
+            path.reverse();
+            path
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tch::nn::VarStore;
+
+    #[test]
+    fn test_viterbi_decode() {
+        let var_store = VarStore::new(Device::Cpu);
+        let crf = Crf::new(var_store.root(), 3);
+        //    Labels O, B-PER and I-PER: I-PER cannot follow O
+        let transitions =
+            Tensor::of_slice(&[0.0f32, 0.0, -10.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]).view([3, 3]);
+        no_grad(|| crf.transitions.shallow_clone().copy_(&transitions));
+
+        let emissions = Tensor::of_slice(&[
+            2.0f32, 0.0, 0.0, // O
+            0.0, 1.0, 1.5, // I-PER preferred by argmax, but cannot follow O
+            0.0, 0.0, 2.0, // I-PER
+            2.0, 0.0, 0.0, // O
+        ])
+        .view([4, 3]);
+        let labels = emissions.argmax(-1, false);
+        let argmax = (0..4)
+            .map(|position| labels.int64_value(&[position]))
+            .collect::<Vec<i64>>();
+        assert_eq!(argmax, [0, 2, 2, 0]);
+        assert_eq!(crf.decode(&emissions), [0, 1, 2, 0]);
+
+        //    Without transition scores the decoded path is the argmax of each token
+        let crf = Crf::new(VarStore::new(Device::Cpu).root(), 3);
+        assert_eq!(crf.decode(&emissions), argmax);
+        assert!(crf.decode(&emissions.narrow(0, 0, 0)).is_empty());
+    }
+}
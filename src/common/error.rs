@@ -21,6 +21,52 @@ pub enum RustBertError {
 
     #[error("Value error: {0}")]
     ValueError(String),
+
+    #[error("Unsupported model type: {0}")]
+    UnsupportedModelType(String),
+
+    #[error("Empty input error: at least one input must be provided")]
+    EmptyInputError,
+
+    #[error("Empty labels error: at least one candidate label must be provided")]
+    EmptyLabelsError,
+
+    #[error("Missing padding token error: the tokenizer does not define a padding token")]
+    MissingPadTokenError,
+
+    #[error("Input too long error: input {input_index} has {tokens} tokens, exceeding the limit of {limit} tokens ({reserved_for_hypothesis} reserved for the hypothesis, {reserved_special} reserved for special tokens)")]
+    InputTooLong {
+        /// Index of the input in the batch
+        input_index: usize,
+        /// Number of tokens of the input
+        tokens: usize,
+        /// Maximum number of tokens of the model inputs
+        limit: usize,
+        /// Number of tokens reserved for the hypothesis (zero-shot classification)
+        reserved_for_hypothesis: usize,
+        /// Number of special tokens added to the input
+        reserved_special: usize,
+    },
+
+    #[error("Invalid pipeline state error: {0}")]
+    InvalidStateError(String),
+
+    #[error("Export error: {0}")]
+    ExportError(String),
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow_crate::error::ArrowError> for RustBertError {
+    fn from(error: arrow_crate::error::ArrowError) -> Self {
+        RustBertError::ExportError(error.to_string())
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<parquet::errors::ParquetError> for RustBertError {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        RustBertError::ExportError(error.to_string())
+    }
 }
 
 impl From<cached_path::Error> for RustBertError {
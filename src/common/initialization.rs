@@ -0,0 +1,236 @@
+//! # Global initialization of the libtorch settings
+//!
+//! The number of threads, cuDNN benchmarking and random seed are process-wide libtorch settings.
+//! They are set once, either explicitly by calling `initialize` before building any pipeline, or
+//! implicitly with the default `InitOptions` by the first pipeline constructed. Calling
+//! `initialize` again with the same options is a no-op, conflicting options return an error.
+//!
+//! Deterministic algorithms cannot be enforced: libtorch's `use_deterministic_algorithms` is not
+//! exposed by the tch 0.4 bindings. Leaving `cudnn_benchmark` disabled (the default) keeps the
+//! selection of the cuDNN convolution algorithms stable across runs, but some CUDA kernels remain
+//! non-deterministic.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sentiment::SentimentModel;
+//! use rust_bert::{initialize, InitOptions};
+//!
+//! initialize(InitOptions {
+//!     intra_op_threads: Some(4),
+//!     seed: Some(42),
+//!     ..Default::default()
+//! })?;
+//! let sentiment_model = SentimentModel::new(Default::default())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// # Process-wide libtorch settings
+pub struct InitOptions {
+    /// Number of threads used for intra-op parallelism (default: libtorch default)
+    pub intra_op_threads: Option<i32>,
+    /// Number of threads used for inter-op parallelism (default: libtorch default). This can only
+    /// be set before libtorch starts any parallel work.
+    pub inter_op_threads: Option<i32>,
+    /// Let cuDNN benchmark and select the fastest convolution algorithms (default: false)
+    pub cudnn_benchmark: bool,
+    /// Seed of the libtorch random number generators (default: None, unseeded)
+    pub seed: Option<i64>,
+}
+
+impl InitOptions {
+    fn validate(&self) -> Result<(), RustBertError> {
+        for (name, threads) in [
+            ("intra_op_threads", self.intra_op_threads),
+            ("inter_op_threads", self.inter_op_threads),
+        ]
+        .iter()
+        {
+            if matches!(threads, Some(value) if *value < 1) {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "{} must be strictly positive",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self) {
+        if let Some(threads) = self.intra_op_threads {
+            tch::set_num_threads(threads);
+        }
+        if let Some(threads) = self.inter_op_threads {
+            tch::set_num_interop_threads(threads);
+        }
+        tch::Cuda::cudnn_set_benchmark(self.cudnn_benchmark);
+        if let Some(seed) = self.seed {
+            tch::manual_seed(seed);
+        }
+    }
+}
+
+/// Holds the options the settings were initialized with, applying them at most once.
+struct Initializer {
+    options: Mutex<Option<InitOptions>>,
+}
+
+impl Initializer {
+    fn new() -> Initializer {
+        Initializer {
+            options: Mutex::new(None),
+        }
+    }
+
+    fn initialize<F>(&self, options: InitOptions, apply: F) -> Result<(), RustBertError>
+    where
+        F: FnOnce(&InitOptions),
+    {
+        options.validate()?;
+        let mut current_options = self.options.lock().unwrap();
+        match current_options.as_ref() {
+            Some(current) if *current == options => Ok(()),
+            Some(current) => Err(RustBertError::InvalidConfigurationError(format!(
+                "libtorch settings were already initialized with {:?}, cannot initialize them with {:?}",
+                current, options
+            ))),
+            None => {
+                apply(&options);
+                *current_options = Some(options);
+                Ok(())
+            }
+        }
+    }
+
+    fn ensure_initialized<F>(&self, apply: F)
+    where
+        F: FnOnce(&InitOptions),
+    {
+        let mut current_options = self.options.lock().unwrap();
+        if current_options.is_none() {
+            let options = InitOptions::default();
+            apply(&options);
+            *current_options = Some(options);
+        }
+    }
+
+    fn options(&self) -> Option<InitOptions> {
+        self.options.lock().unwrap().clone()
+    }
+}
+
+lazy_static! {
+    static ref INITIALIZER: Initializer = Initializer::new();
+}
+
+/// Initializes the process-wide libtorch settings. Must be called before building any pipeline,
+/// which would otherwise initialize the settings with the default `InitOptions`.
+///
+/// # Arguments
+///
+/// * `options` - `InitOptions` to apply
+///
+/// # Returns
+///
+/// * `Ok(())` if the settings were initialized, or were already initialized with the same options
+/// * `RustBertError::InvalidConfigurationError` if the options are invalid or if the settings were already initialized with different options
+pub fn initialize(options: InitOptions) -> Result<(), RustBertError> {
+    INITIALIZER.initialize(options, InitOptions::apply)
+}
+
+/// Returns the options the libtorch settings were initialized with, if they were initialized.
+pub fn initialized_options() -> Option<InitOptions> {
+    INITIALIZER.options()
+}
+
+/// Initializes the libtorch settings with the default `InitOptions` if `initialize` was not called.
+pub(crate) fn ensure_initialized() {
+    INITIALIZER.ensure_initialized(InitOptions::apply)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_initialize_once() {
+        let initializer = Initializer::new();
+        let applied = Cell::new(0);
+        let options = InitOptions {
+            intra_op_threads: Some(2),
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        initializer
+            .initialize(options.clone(), |_| applied.set(applied.get() + 1))
+            .unwrap();
+        initializer
+            .initialize(options.clone(), |_| applied.set(applied.get() + 1))
+            .unwrap();
+        initializer.ensure_initialized(|_| applied.set(applied.get() + 1));
+
+        assert_eq!(applied.get(), 1);
+        assert_eq!(initializer.options(), Some(options));
+    }
+
+    #[test]
+    fn test_implicit_initialization() {
+        let initializer = Initializer::new();
+        let applied = Cell::new(0);
+
+        initializer.ensure_initialized(|_| applied.set(applied.get() + 1));
+        initializer.ensure_initialized(|_| applied.set(applied.get() + 1));
+        assert_eq!(applied.get(), 1);
+        assert_eq!(initializer.options(), Some(InitOptions::default()));
+
+        initializer
+            .initialize(InitOptions::default(), |_| applied.set(applied.get() + 1))
+            .unwrap();
+        assert_eq!(applied.get(), 1);
+    }
+
+    #[test]
+    fn test_conflicting_initialization() {
+        let initializer = Initializer::new();
+        initializer.ensure_initialized(|_| {});
+
+        let result = initializer.initialize(
+            InitOptions {
+                cudnn_benchmark: true,
+                ..Default::default()
+            },
+            |_| panic!("the settings should not be applied twice"),
+        );
+        assert!(matches!(
+            result,
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        assert_eq!(initializer.options(), Some(InitOptions::default()));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let initializer = Initializer::new();
+        let invalid_options = [
+            InitOptions {
+                intra_op_threads: Some(0),
+                ..Default::default()
+            },
+            InitOptions {
+                inter_op_threads: Some(-1),
+                ..Default::default()
+            },
+        ];
+        for options in invalid_options.iter() {
+            assert!(initializer.initialize(options.clone(), |_| {}).is_err());
+        }
+        assert_eq!(initializer.options(), None);
+    }
+}
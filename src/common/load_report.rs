@@ -0,0 +1,462 @@
+//! # Report of the weights loaded in a model
+//!
+//! Hand-converted checkpoints may not match the variables expected by the model: the output
+//! projection of language models may be untied from the word embeddings (or tied where it should
+//! not be), some tensors may have been saved with a different shape, or be left unused. Loading the
+//! weights with `load_with_report` verifies the checkpoint against the model and returns a
+//! `LoadReport` describing these inconsistencies. The report of the pipelines is available through
+//! their `last_load_report` accessor.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! for warning in model.last_load_report().warnings() {
+//!     println!("{}", warning);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tch::nn::VarStore;
+use tch::{no_grad, Tensor};
+
+/// Output projection and word embeddings variables tied in the original architectures
+const TIED_VARIABLES: [(&str, &str); 8] = [
+    (
+        "cls.predictions.decoder.weight",
+        "bert.embeddings.word_embeddings.weight",
+    ),
+    (
+        "lm_head.decoder.weight",
+        "roberta.embeddings.word_embeddings.weight",
+    ),
+    (
+        "vocab_projector.weight",
+        "distilbert.embeddings.word_embeddings.weight",
+    ),
+    (
+        "predictions.decoder.weight",
+        "albert.embeddings.word_embeddings.weight",
+    ),
+    (
+        "generator_lm_head.weight",
+        "electra.embeddings.word_embeddings.weight",
+    ),
+    ("lm_head.weight", "tokens_embed.weight"),
+    ("lm_head.weight", "transformer.word_embedding.weight"),
+    ("lm_head.weight", "prophetnet.word_embeddings.weight"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Tying status of an output projection found in the checkpoint
+pub enum TyingStatus {
+    /// The output projection is equal to the word embeddings
+    Tied,
+    /// The output projection differs from the word embeddings
+    Untied,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// # Tying verification of an output projection
+pub struct TiedWeightsCheck {
+    /// Name of the output projection variable
+    pub output: String,
+    /// Name of the word embeddings variable
+    pub embeddings: String,
+    /// Tying expected from the model configuration
+    pub expected_tied: bool,
+    /// Tying status of the loaded weights
+    pub status: TyingStatus,
+}
+
+impl TiedWeightsCheck {
+    /// Returns true if the tying status matches the model configuration
+    pub fn is_consistent(&self) -> bool {
+        self.expected_tied == (self.status == TyingStatus::Tied)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// # Checkpoint tensor reshaped to the shape of the model variable
+pub struct ShapeRepair {
+    /// Name of the variable
+    pub name: String,
+    /// Shape of the tensor in the checkpoint
+    pub checkpoint_shape: Vec<i64>,
+    /// Shape of the model variable
+    pub model_shape: Vec<i64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// # Report of the weights loaded in a model
+pub struct LoadReport {
+    /// Tying verification of the output projections of the model
+    pub tied_weights: Vec<TiedWeightsCheck>,
+    /// Checkpoint tensors reshaped to the shape of the model variables
+    pub shape_repairs: Vec<ShapeRepair>,
+    /// Names of the checkpoint tensors not used by the model, in alphabetical order
+    pub unused_tensors: Vec<String>,
+}
+
+impl LoadReport {
+    /// Returns true if the tying of all output projections matches the model configuration
+    pub fn is_consistent(&self) -> bool {
+        self.tied_weights
+            .iter()
+            .all(TiedWeightsCheck::is_consistent)
+    }
+
+    /// Returns a human-readable warning for each inconsistency found while loading the weights
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self
+            .tied_weights
+            .iter()
+            .filter(|check| !check.is_consistent())
+            .map(|check| {
+                format!(
+                    "`{}` is expected to be {} to `{}` but is {}",
+                    check.output,
+                    if check.expected_tied {
+                        "tied"
+                    } else {
+                        "untied"
+                    },
+                    check.embeddings,
+                    match check.status {
+                        TyingStatus::Tied => "tied",
+                        TyingStatus::Untied => "untied",
+                    }
+                )
+            })
+            .collect::<Vec<String>>();
+        warnings.extend(self.shape_repairs.iter().map(|repair| {
+            format!(
+                "`{}` was reshaped from {:?} to {:?}",
+                repair.name, repair.checkpoint_shape, repair.model_shape
+            )
+        }));
+        if !self.unused_tensors.is_empty() {
+            warnings.push(format!(
+                "Unused tensors in the checkpoint: {}",
+                self.unused_tensors.join(", ")
+            ));
+        }
+        warnings
+    }
+}
+
+/// Loads the weights of a checkpoint in a `VarStore`, reporting the inconsistencies between the
+/// checkpoint and the model variables. Checkpoint tensors differing from the variable shape only by
+/// dimensions of size 1 are reshaped, other shape mismatches and missing variables are errors.
+///
+/// The whole checkpoint is validated before any variable is overwritten: if the loading fails, the
+/// variables keep their previous values. This allows replacing the weights of a model in use.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` holding the model variables
+/// * `weights_path` - path to the checkpoint
+/// * `tie_word_embeddings` - whether the configuration of the model expects the output projection to be tied to the word embeddings
+///
+/// # Returns
+///
+/// * `LoadReport` describing the tying status of the output projections, the repaired shapes and the unused tensors
+pub fn load_with_report<P: AsRef<Path>>(
+    var_store: &mut VarStore,
+    weights_path: P,
+    tie_word_embeddings: bool,
+) -> Result<LoadReport, RustBertError> {
+    let named_tensors = Tensor::load_multi_with_device(weights_path.as_ref(), var_store.device())?;
+    let variables = var_store.variables();
+    let checkpoint = named_tensors
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<BTreeSet<String>>();
+    let (staged_tensors, shape_repairs) = stage_tensors(&variables, named_tensors)?;
+    no_grad(|| -> Result<(), RustBertError> {
+        for (name, tensor) in staged_tensors {
+            variables[&name].shallow_clone().f_copy_(&tensor)?;
+        }
+        Ok(())
+    })?;
+    let unused_tensors = checkpoint
+        .difference(&variables.keys().cloned().collect())
+        .cloned()
+        .collect();
+    Ok(LoadReport {
+        tied_weights: check_tied_weights(&variables, tie_word_embeddings),
+        shape_repairs,
+        unused_tensors,
+    })
+}
+
+/// Matches the checkpoint tensors with the model variables, returning the tensors to copy in each
+/// variable (reshaped if needed) and the repaired shapes. The variables are left untouched.
+fn stage_tensors(
+    variables: &HashMap<String, Tensor>,
+    named_tensors: Vec<(String, Tensor)>,
+) -> Result<(Vec<(String, Tensor)>, Vec<ShapeRepair>), RustBertError> {
+    let named_tensors = named_tensors
+        .into_iter()
+        .collect::<HashMap<String, Tensor>>();
+    let mut variable_names = variables.keys().collect::<Vec<&String>>();
+    variable_names.sort();
+    let mut staged_tensors = Vec::with_capacity(variable_names.len());
+    let mut shape_repairs = vec![];
+    for name in variable_names {
+        let variable = &variables[name];
+        let tensor = named_tensors.get(name).ok_or_else(|| {
+            RustBertError::ValueError(format!("Cannot find `{}` in the checkpoint", name))
+        })?;
+        let (checkpoint_shape, model_shape) = (tensor.size(), variable.size());
+        let tensor = if checkpoint_shape == model_shape {
+            tensor.shallow_clone()
+        } else if without_unit_dimensions(&checkpoint_shape)
+            == without_unit_dimensions(&model_shape)
+        {
+            shape_repairs.push(ShapeRepair {
+                name: name.clone(),
+                checkpoint_shape,
+                model_shape: model_shape.clone(),
+            });
+            tensor.reshape(&model_shape)
+        } else {
+            return Err(RustBertError::ValueError(format!(
+                "Shape mismatch for `{}`: {:?} in the checkpoint, {:?} expected by the model",
+                name, checkpoint_shape, model_shape
+            )));
+        };
+        staged_tensors.push((name.clone(), tensor));
+    }
+    Ok((staged_tensors, shape_repairs))
+}
+
+fn without_unit_dimensions(shape: &[i64]) -> Vec<i64> {
+    shape.iter().filter(|&&dim| dim != 1).copied().collect()
+}
+
+fn check_tied_weights(
+    variables: &HashMap<String, Tensor>,
+    tie_word_embeddings: bool,
+) -> Vec<TiedWeightsCheck> {
+    TIED_VARIABLES
+        .iter()
+        .filter_map(|(output_name, embeddings_name)| {
+            let output = variables.get(*output_name)?;
+            let embeddings = variables.get(*embeddings_name)?;
+            let tied = (output.size() == embeddings.size()) && output.equal(embeddings);
+            Some(TiedWeightsCheck {
+                output: output_name.to_string(),
+                embeddings: embeddings_name.to_string(),
+                expected_tied: tie_word_embeddings,
+                status: if tied {
+                    TyingStatus::Tied
+                } else {
+                    TyingStatus::Untied
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tch::{Device, Kind};
+
+    fn build_var_store(tied: bool) -> VarStore {
+        let var_store = VarStore::new(Device::Cpu);
+        let root = var_store.root();
+        let embeddings =
+            (&root / "transformer" / "word_embedding").randn("weight", &[8, 4], 0.0, 1.0);
+        let output = (&root / "lm_head").randn("weight", &[8, 4], 0.0, 1.0);
+        if tied {
+            no_grad(|| output.shallow_clone().copy_(&embeddings));
+        }
+        let _ = (&root / "transformer").zeros("position_ids", &[6]);
+        var_store
+    }
+
+    fn save_checkpoint(
+        directory: &Path,
+        tensors: &[(&str, Tensor)],
+    ) -> anyhow::Result<std::path::PathBuf> {
+        let path = directory.join("rust_model.ot");
+        Tensor::save_multi(tensors, &path)?;
+        Ok(path)
+    }
+
+    #[test]
+    fn test_tied_weights() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let embeddings = Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu));
+        let position_ids = Tensor::zeros(&[6], (Kind::Float, Device::Cpu));
+        let checkpoint = save_checkpoint(
+            directory.path(),
+            &[
+                ("transformer.word_embedding.weight", embeddings.copy()),
+                ("lm_head.weight", embeddings.copy()),
+                ("transformer.position_ids", position_ids),
+            ],
+        )?;
+
+        //    Tied checkpoint loaded in a model expecting tied weights
+        let mut var_store = build_var_store(false);
+        let report = load_with_report(&mut var_store, &checkpoint, true)?;
+        assert_eq!(
+            report.tied_weights,
+            vec![TiedWeightsCheck {
+                output: "lm_head.weight".to_string(),
+                embeddings: "transformer.word_embedding.weight".to_string(),
+                expected_tied: true,
+                status: TyingStatus::Tied,
+            }]
+        );
+        assert!(report.is_consistent());
+        assert!(report.warnings().is_empty());
+
+        //    Tied checkpoint loaded in a model expecting untied weights
+        let mut var_store = build_var_store(false);
+        let report = load_with_report(&mut var_store, &checkpoint, false)?;
+        assert!(!report.is_consistent());
+        assert_eq!(report.warnings().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_untied_weights() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let checkpoint = save_checkpoint(
+            directory.path(),
+            &[
+                (
+                    "transformer.word_embedding.weight",
+                    Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "lm_head.weight",
+                    Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "transformer.position_ids",
+                    Tensor::zeros(&[6], (Kind::Float, Device::Cpu)),
+                ),
+            ],
+        )?;
+
+        //    The variables of the model are overwritten by the untied checkpoint
+        let mut var_store = build_var_store(true);
+        let report = load_with_report(&mut var_store, &checkpoint, true)?;
+        assert_eq!(report.tied_weights.len(), 1);
+        assert_eq!(report.tied_weights[0].status, TyingStatus::Untied);
+        assert!(!report.is_consistent());
+        assert_eq!(
+            report.warnings(),
+            vec!["`lm_head.weight` is expected to be tied to `transformer.word_embedding.weight` but is untied"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shape_repairs_and_unused_tensors() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let embeddings = Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu));
+        let checkpoint = save_checkpoint(
+            directory.path(),
+            &[
+                ("transformer.word_embedding.weight", embeddings.copy()),
+                ("lm_head.weight", embeddings.copy()),
+                (
+                    "transformer.position_ids",
+                    Tensor::arange(6, (Kind::Float, Device::Cpu)).unsqueeze(0),
+                ),
+                (
+                    "lm_head.bias",
+                    Tensor::zeros(&[8], (Kind::Float, Device::Cpu)),
+                ),
+            ],
+        )?;
+
+        let mut var_store = build_var_store(false);
+        let report = load_with_report(&mut var_store, &checkpoint, true)?;
+        assert_eq!(
+            report.shape_repairs,
+            vec![ShapeRepair {
+                name: "transformer.position_ids".to_string(),
+                checkpoint_shape: vec![1, 6],
+                model_shape: vec![6],
+            }]
+        );
+        assert_eq!(report.unused_tensors, vec!["lm_head.bias".to_string()]);
+        assert_eq!(
+            var_store.variables()["transformer.position_ids"].double_value(&[5]),
+            5.0
+        );
+        assert_eq!(report.warnings().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_checkpoint() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let missing = save_checkpoint(
+            directory.path(),
+            &[(
+                "transformer.word_embedding.weight",
+                Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+            )],
+        )?;
+        let mut var_store = build_var_store(false);
+        assert!(load_with_report(&mut var_store, &missing, true).is_err());
+
+        let mismatch = save_checkpoint(
+            directory.path(),
+            &[
+                (
+                    "transformer.word_embedding.weight",
+                    Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "lm_head.weight",
+                    Tensor::randn(&[4, 8], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "transformer.position_ids",
+                    Tensor::zeros(&[6], (Kind::Float, Device::Cpu)),
+                ),
+            ],
+        )?;
+        let mut var_store = build_var_store(false);
+        assert!(load_with_report(&mut var_store, &mismatch, true).is_err());
+
+        //    A failed loading leaves all variables untouched, including the variables sorted before
+        //    the invalid tensor
+        let late_mismatch = save_checkpoint(
+            directory.path(),
+            &[
+                (
+                    "transformer.word_embedding.weight",
+                    Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "lm_head.weight",
+                    Tensor::randn(&[8, 4], (Kind::Float, Device::Cpu)),
+                ),
+                (
+                    "transformer.position_ids",
+                    Tensor::zeros(&[5], (Kind::Float, Device::Cpu)),
+                ),
+            ],
+        )?;
+        let mut var_store = build_var_store(false);
+        let original = var_store.variables()["lm_head.weight"].copy();
+        assert!(load_with_report(&mut var_store, &late_mismatch, true).is_err());
+        assert!(var_store.variables()["lm_head.weight"].equal(&original));
+        Ok(())
+    }
+}
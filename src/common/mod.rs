@@ -1,10 +1,14 @@
 pub(crate) mod activations;
 pub mod config;
+pub(crate) mod crf;
 pub(crate) mod dropout;
 pub mod error;
+pub(crate) mod initialization;
 pub(crate) mod linear;
+pub mod load_report;
 pub mod resources;
+#[cfg(feature = "xlnet")]
 pub(crate) mod summary;
 
 pub use activations::Activation;
-pub use config::Config;
+pub use config::{Config, ProblemType};
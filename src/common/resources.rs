@@ -12,6 +12,14 @@
 //! - LocalResource: points to a local file
 //! - RemoteResource: points to a remote file via a URL and a local cached file
 //!
+//! Remote resources may list mirror URLs, tried in order if the download from the main URL fails
+//! (connection errors, timeouts or error status codes). A missing file (404 status) at the main URL
+//! is reported without trying the mirrors. Each URL is cached separately with an ETag check, a
+//! resource already cached from its main URL being reused once mirrors are added. Mirrors can also
+//! be added globally to all remote resources, including the pretrained resources of the model
+//! modules, with `set_mirror_hook` or `set_mirror_prefixes` (e.g. to point all resources to an
+//! artifact proxy).
+//!
 //! For both types of resources, the local location of teh file can be retrieved using
 //! `get_local_path`, allowing to reference the resource file location regardless if it is a remote
 //! or local resource. Default implementations for a number of `RemoteResources` are available as
@@ -22,6 +30,8 @@ use cached_path::{Cache, Options, ProgressBar};
 use lazy_static::lazy_static;
 use std::env;
 use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
 
 extern crate dirs;
 
@@ -59,6 +69,10 @@ impl Resource {
         match self {
             Resource::Local(resource) => Ok(resource.local_path.clone()),
             Resource::Remote(resource) => {
+                let urls = resource.candidate_urls();
+                if urls.len() > 1 {
+                    return resource.fetch_from_mirrors(&urls, &CACHE);
+                }
                 let cached_path = CACHE.cached_path_with_options(
                     &resource.url,
                     &Options::default().subdir(&resource.cache_subdir),
@@ -83,6 +97,10 @@ pub struct RemoteResource {
     pub url: String,
     /// Local subdirectory of the cache root where this resource is saved
     pub cache_subdir: String,
+    /// Mirror URLs of the resource, tried in order if the download from `url` fails
+    pub mirrors: Vec<String>,
+    /// Timeout of the download from each URL of a resource with mirrors (default: None, no timeout)
+    pub timeout: Option<Duration>,
 }
 
 impl RemoteResource {
@@ -111,6 +129,8 @@ impl RemoteResource {
         RemoteResource {
             url: url.to_string(),
             cache_subdir: cache_subdir.to_string(),
+            mirrors: vec![],
+            timeout: None,
         }
     }
 
@@ -138,8 +158,181 @@ impl RemoteResource {
     pub fn from_pretrained(name_url_tuple: (&str, &str)) -> RemoteResource {
         let cache_subdir = name_url_tuple.0.to_string();
         let url = name_url_tuple.1.to_string();
-        RemoteResource { url, cache_subdir }
+        RemoteResource {
+            url,
+            cache_subdir,
+            mirrors: vec![],
+            timeout: None,
+        }
+    }
+
+    /// Adds mirror URLs to the resource, tried in order if the download from the main URL fails
+    ///
+    /// # Arguments
+    ///
+    /// * `mirrors` - `&[&str]` mirror URLs of the resource
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::distilbert::DistilBertModelResources;
+    /// use rust_bert::resources::{RemoteResource, Resource};
+    /// use std::time::Duration;
+    /// let model_resource = Resource::Remote(
+    ///     RemoteResource::from_pretrained(DistilBertModelResources::DISTIL_BERT_SST2)
+    ///         .with_mirrors(&["https://artifacts.example.com/distilbert-sst2/rust_model.ot"])
+    ///         .with_timeout(Duration::from_secs(600)),
+    /// );
+    /// ```
+    pub fn with_mirrors(mut self, mirrors: &[&str]) -> RemoteResource {
+        self.mirrors
+            .extend(mirrors.iter().map(|mirror| mirror.to_string()));
+        self
+    }
+
+    /// Sets the timeout of the download from each URL of a resource with mirrors
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - `Duration` maximum duration of the download from a single URL
+    pub fn with_timeout(mut self, timeout: Duration) -> RemoteResource {
+        self.timeout = Some(timeout);
+        self
     }
+
+    /// Returns the URLs the resource is downloaded from, in order: the main URL, the mirrors of
+    /// the resource and the mirrors returned by the global mirror hook.
+    pub fn candidate_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.url.clone()];
+        let global_mirrors = match MIRROR_HOOK.read() {
+            Ok(hook) => hook
+                .as_ref()
+                .map(|hook| hook(&self.url))
+                .unwrap_or_default(),
+            Err(_) => vec![],
+        };
+        for url in self.mirrors.iter().cloned().chain(global_mirrors) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    /// Fetches the resource through the cache from the first URL serving it. Each URL is cached
+    /// under its own key with an ETag check, so that a resource already cached from its main URL
+    /// is not downloaded again once mirrors are added.
+    fn fetch_from_mirrors(&self, urls: &[String], cache: &Cache) -> Result<PathBuf, RustBertError> {
+        let timeout_cache;
+        let cache = match self.timeout {
+            Some(timeout) => {
+                timeout_cache = Cache::builder()
+                    .dir(cache.dir.clone())
+                    .timeout(timeout)
+                    .progress_bar(Some(ProgressBar::Light))
+                    .build()?;
+                &timeout_cache
+            }
+            None => cache,
+        };
+        let options = Options::default().subdir(&self.cache_subdir);
+        let mut failures = vec![];
+        for (index, url) in urls.iter().enumerate() {
+            match cache.cached_path_with_options(url, &options) {
+                Ok(cached_path) => return Ok(cached_path),
+                Err(cached_path::Error::HttpStatusError(404)) if index == 0 => {
+                    return Err(RustBertError::FileDownloadError(format!(
+                        "Resource not found at {}",
+                        url
+                    )));
+                }
+                Err(error) => {
+                    let failure = error.to_string();
+                    if let Some(next_url) = urls.get(index + 1) {
+                        tracing::warn!(
+                            url = url.as_str(),
+                            next_url = next_url.as_str(),
+                            error = failure.as_str(),
+                            "Resource download failed, falling back to the next mirror"
+                        );
+                    }
+                    failures.push(format!("{} ({})", url, failure));
+                }
+            }
+        }
+        Err(RustBertError::FileDownloadError(format!(
+            "Resource could not be downloaded from any mirror: {}",
+            failures.join(", ")
+        )))
+    }
+}
+
+type MirrorHook = Box<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+lazy_static! {
+    static ref MIRROR_HOOK: RwLock<Option<MirrorHook>> = RwLock::new(None);
+}
+
+/// Sets a global hook returning mirror URLs for the main URL of any remote resource. The mirrors
+/// returned are tried after the mirrors of the resource itself.
+///
+/// # Arguments
+///
+/// * `hook` - function returning the mirror URLs of a resource from its main URL
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::resources::set_mirror_hook;
+/// set_mirror_hook(|url| {
+///     vec![url.replace(
+///         "https://huggingface.co/",
+///         "https://artifacts.example.com/huggingface/",
+///     )]
+/// });
+/// ```
+pub fn set_mirror_hook<F>(hook: F)
+where
+    F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+{
+    *MIRROR_HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Sets a global hook rewriting the main URL of remote resources starting with `prefix` into a
+/// mirror URL for each of the `mirror_prefixes`, tried in order.
+///
+/// # Arguments
+///
+/// * `prefix` - `&str` prefix of the main URLs to rewrite (e.g. `https://huggingface.co/`)
+/// * `mirror_prefixes` - `&[&str]` prefixes replacing `prefix` in the mirror URLs
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::resources::set_mirror_prefixes;
+/// set_mirror_prefixes(
+///     "https://huggingface.co/",
+///     &["https://artifacts.example.com/huggingface/"],
+/// );
+/// ```
+pub fn set_mirror_prefixes(prefix: &str, mirror_prefixes: &[&str]) {
+    let prefix = prefix.to_string();
+    let mirror_prefixes = mirror_prefixes
+        .iter()
+        .map(|mirror_prefix| mirror_prefix.to_string())
+        .collect::<Vec<String>>();
+    set_mirror_hook(move |url| match url.strip_prefix(prefix.as_str()) {
+        Some(path) => mirror_prefixes
+            .iter()
+            .map(|mirror_prefix| format!("{}{}", mirror_prefix, path))
+            .collect(),
+        None => vec![],
+    });
+}
+
+/// Removes the global mirror hook
+pub fn clear_mirror_hook() {
+    *MIRROR_HOOK.write().unwrap() = None;
 }
 
 lazy_static! {
@@ -195,3 +388,148 @@ fn _get_cache_directory() -> PathBuf {
 pub fn download_resource(resource: &Resource) -> Result<PathBuf, RustBertError> {
     resource.get_local_path()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+
+    /// Serves `/missing` with a 404, `/unavailable` with a 500 and any other path with `body`
+    fn serve(body: &'static str) -> anyhow::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = format!("http://{}", listener.local_addr()?);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                let _ = reader.read_line(&mut request_line);
+                let mut header = String::new();
+                while reader
+                    .read_line(&mut header)
+                    .map(|n| n > 2)
+                    .unwrap_or(false)
+                {
+                    header.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let (status, content) = match path {
+                    "/missing" => ("404 Not Found", ""),
+                    "/unavailable" => ("500 Internal Server Error", ""),
+                    _ => ("200 OK", body),
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content.len(),
+                    content
+                );
+            }
+        });
+        Ok(address)
+    }
+
+    /// Returns the URL of a port nothing listens on
+    fn unreachable_url() -> anyhow::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        Ok(format!("http://{}/config.json", listener.local_addr()?))
+    }
+
+    fn test_cache(cache_root: &Path) -> anyhow::Result<Cache> {
+        Ok(Cache::builder().dir(cache_root.to_path_buf()).build()?)
+    }
+
+    #[test]
+    fn test_mirror_fallback() -> anyhow::Result<()> {
+        let cache_root = tempfile::tempdir()?;
+        let cache = test_cache(cache_root.path())?;
+        let address = serve("{\"vocab_size\": 8}")?;
+        let canonical_url = format!("{}/unavailable", address);
+        let resource = RemoteResource::new(&canonical_url, "tiny-model/config")
+            .with_mirrors(&[&unreachable_url()?, &format!("{}/config.json", address)])
+            .with_timeout(Duration::from_secs(10));
+        let urls = [
+            canonical_url,
+            resource.mirrors[0].clone(),
+            resource.mirrors[1].clone(),
+        ];
+
+        let path = resource.fetch_from_mirrors(&urls, &cache)?;
+        assert_eq!(std::fs::read_to_string(&path)?, "{\"vocab_size\": 8}");
+        assert!(path.starts_with(cache_root.path().join("tiny-model/config")));
+        //    The content served by the last mirror is cached under the key of the mirror URL
+        let options = Options::default().subdir("tiny-model/config");
+        assert_eq!(cache.cached_path_with_options(&urls[2], &options)?, path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mirror_shared_cache() -> anyhow::Result<()> {
+        let cache_root = tempfile::tempdir()?;
+        let cache = test_cache(cache_root.path())?;
+        let address = serve("content")?;
+        let resource = RemoteResource::new(&format!("{}/config.json", address), "shared");
+        let options = Options::default().subdir("shared");
+        let path = cache.cached_path_with_options(&resource.url, &options)?;
+
+        //    A resource cached without mirrors is reused once mirrors are added
+        let mirrored = resource.with_mirrors(&[&format!("{}/mirror/config.json", address)]);
+        let urls = [mirrored.url.clone(), mirrored.mirrors[0].clone()];
+        assert_eq!(mirrored.fetch_from_mirrors(&urls, &cache)?, path);
+        let cached_files = std::fs::read_dir(cache_root.path().join("shared"))?
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .map_or(true, |ext| ext != "meta" && ext != "lock")
+            })
+            .count();
+        assert_eq!(cached_files, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mirror_errors() -> anyhow::Result<()> {
+        let cache_root = tempfile::tempdir()?;
+        let cache = test_cache(cache_root.path())?;
+        let address = serve("content")?;
+
+        //    A missing file at the main URL is not looked up on the mirrors
+        let missing = RemoteResource::new(&format!("{}/missing", address), "missing");
+        let urls = [missing.url.clone(), format!("{}/config.json", address)];
+        assert!(missing.fetch_from_mirrors(&urls, &cache).is_err());
+        assert_eq!(
+            std::fs::read_dir(cache_root.path().join("missing"))?.count(),
+            0
+        );
+
+        //    A missing file on a mirror is skipped
+        let unavailable = RemoteResource::new(&format!("{}/unavailable", address), "unavailable");
+        let urls = [
+            unavailable.url.clone(),
+            format!("{}/missing", address),
+            format!("{}/config.json", address),
+        ];
+        let path = unavailable.fetch_from_mirrors(&urls, &cache)?;
+        assert_eq!(std::fs::read_to_string(&path)?, "content");
+
+        //    Failures from all URLs are reported
+        let failing = RemoteResource::new(&unreachable_url()?, "failing");
+        let urls = [failing.url.clone(), format!("{}/unavailable", address)];
+        match failing.fetch_from_mirrors(&urls, &cache) {
+            Err(RustBertError::FileDownloadError(message)) => {
+                assert!(message.contains(&urls[0]));
+                assert!(message.contains(&urls[1]));
+            }
+            _ => panic!("Expected a download error"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright 2020, Microsoft and the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::deberta::{DebertaConfig, PositionAttentionType, PositionAttentionTypes};
+use std::borrow::Borrow;
+use tch::nn::Init;
+use tch::{nn, Kind, Tensor};
+
+/// Softmax ignoring the masked positions. Masked positions are set to a probability of 0
+/// (including for fully masked rows, where a standard softmax would return NaN).
+pub fn x_softmax(input: &Tensor, mask: &Tensor, dim: i64) -> Tensor {
+    let reverse_mask = mask.eq(0);
+    input
+        .masked_fill(&reverse_mask, f64::NEG_INFINITY)
+        .softmax(dim, Kind::Float)
+        .masked_fill(&reverse_mask, 0.0)
+        .to_kind(input.kind())
+}
+
+#[derive(Debug)]
+pub struct DisentangledSelfAttention {
+    in_proj: nn::Linear,
+    q_bias: Tensor,
+    v_bias: Tensor,
+    num_attention_heads: i64,
+    pos_att_type: PositionAttentionTypes,
+    max_relative_positions: Option<i64>,
+    pos_dropout: Option<Dropout>,
+    pos_proj: Option<nn::Linear>,
+    pos_q_proj: Option<nn::Linear>,
+    dropout: Dropout,
+    output_attentions: bool,
+}
+
+impl DisentangledSelfAttention {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DisentangledSelfAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        assert_eq!(
+            config.hidden_size % config.num_attention_heads,
+            0,
+            "Hidden size not a multiple of the number of attention heads"
+        );
+        let p = p.borrow();
+
+        let num_attention_heads = config.num_attention_heads;
+        let attention_head_size = config.hidden_size / num_attention_heads;
+        let all_head_size = num_attention_heads * attention_head_size;
+
+        let linear_no_bias_config = nn::LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+        let in_proj = nn::linear(
+            p / "in_proj",
+            config.hidden_size,
+            all_head_size * 3,
+            linear_no_bias_config,
+        );
+        let q_bias = p.var("q_bias", &[all_head_size], Init::Const(0.0));
+        let v_bias = p.var("v_bias", &[all_head_size], Init::Const(0.0));
+        let pos_att_type = config.pos_att_type.clone().unwrap_or_default();
+
+        let (max_relative_positions, pos_dropout, pos_proj, pos_q_proj) =
+            if config.relative_attention.unwrap_or(false) {
+                let max_relative_positions = match config.max_relative_positions {
+                    Some(max_relative_positions) if max_relative_positions > 0 => {
+                        max_relative_positions
+                    }
+                    _ => config.max_position_embeddings,
+                };
+                let pos_dropout = Dropout::new(config.hidden_dropout_prob);
+                let pos_proj = if pos_att_type.has_type(PositionAttentionType::c2p)
+                    || pos_att_type.has_type(PositionAttentionType::p2p)
+                {
+                    Some(nn::linear(
+                        p / "pos_proj",
+                        config.hidden_size,
+                        all_head_size,
+                        linear_no_bias_config,
+                    ))
+                } else {
+                    None
+                };
+                let pos_q_proj = if pos_att_type.has_type(PositionAttentionType::p2c)
+                    || pos_att_type.has_type(PositionAttentionType::p2p)
+                {
+                    Some(nn::linear(
+                        p / "pos_q_proj",
+                        config.hidden_size,
+                        all_head_size,
+                        Default::default(),
+                    ))
+                } else {
+                    None
+                };
+                (
+                    Some(max_relative_positions),
+                    Some(pos_dropout),
+                    pos_proj,
+                    pos_q_proj,
+                )
+            } else {
+                (None, None, None, None)
+            };
+
+        let dropout = Dropout::new(config.attention_probs_dropout_prob);
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        DisentangledSelfAttention {
+            in_proj,
+            q_bias,
+            v_bias,
+            num_attention_heads,
+            pos_att_type,
+            max_relative_positions,
+            pos_dropout,
+            pos_proj,
+            pos_q_proj,
+            dropout,
+            output_attentions,
+        }
+    }
+
+    fn transpose_for_scores(&self, x: &Tensor) -> Tensor {
+        let mut new_shape = x.size();
+        let _ = new_shape.pop();
+        new_shape.extend_from_slice(&[self.num_attention_heads, -1]);
+        x.view(new_shape.as_slice()).permute(&[0, 2, 1, 3])
+    }
+
+    fn disentangled_att_bias(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        relative_pos: &Tensor,
+        rel_embeddings: &Tensor,
+        scale_factor: f64,
+    ) -> Tensor {
+        let relative_pos = relative_pos.unsqueeze(1);
+        let max_relative_positions = self.max_relative_positions.unwrap();
+        let query_size = query_layer.size();
+        let key_length = key_layer.size()[2];
+        let attention_span = query_size[2].max(key_length).min(max_relative_positions);
+        let rel_embeddings = rel_embeddings
+            .slice(
+                0,
+                max_relative_positions - attention_span,
+                max_relative_positions + attention_span,
+                1,
+            )
+            .unsqueeze(0);
+
+        let mut score = Tensor::zeros(&[1], (query_layer.kind(), query_layer.device()));
+
+        if self.pos_att_type.has_type(PositionAttentionType::c2p) {
+            let pos_key_layer =
+                self.transpose_for_scores(&rel_embeddings.apply(self.pos_proj.as_ref().unwrap()));
+            let c2p_att = query_layer.matmul(&pos_key_layer.transpose(-1, -2));
+            let c2p_pos = (&relative_pos + attention_span).clamp(0, attention_span * 2 - 1);
+            let c2p_att = c2p_att.gather(
+                -1,
+                &c2p_pos.expand(
+                    &[
+                        query_size[0],
+                        query_size[1],
+                        query_size[2],
+                        *relative_pos.size().last().unwrap(),
+                    ],
+                    true,
+                ),
+                false,
+            );
+            score = score + c2p_att;
+        }
+
+        if self.pos_att_type.has_type(PositionAttentionType::p2c) {
+            let pos_query_layer =
+                self.transpose_for_scores(&rel_embeddings.apply(self.pos_q_proj.as_ref().unwrap()));
+            let pos_query_layer = &pos_query_layer
+                / ((*pos_query_layer.size().last().unwrap() as f64) * scale_factor).sqrt();
+            let p2c_pos = (-&relative_pos + attention_span).clamp(0, attention_span * 2 - 1);
+            let p2c_att = key_layer
+                .matmul(&pos_query_layer.transpose(-1, -2))
+                .gather(
+                    -1,
+                    &p2c_pos.expand(
+                        &[query_size[0], query_size[1], key_length, key_length],
+                        true,
+                    ),
+                    false,
+                )
+                .transpose(-1, -2);
+            score = score + p2c_att;
+        }
+
+        score
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        relative_pos: Option<&Tensor>,
+        rel_embeddings: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let qkv = self.transpose_for_scores(&hidden_states.apply(&self.in_proj));
+        let mut qkv = qkv.chunk(3, -1);
+        let value_layer = qkv.pop().unwrap();
+        let key_layer = qkv.pop().unwrap();
+        let query_layer = qkv.pop().unwrap();
+
+        let query_layer =
+            query_layer + self.transpose_for_scores(&self.q_bias.unsqueeze(0).unsqueeze(0));
+        let value_layer =
+            value_layer + self.transpose_for_scores(&self.v_bias.unsqueeze(0).unsqueeze(0));
+
+        let scale_factor = 1.0 + self.pos_att_type.len() as f64;
+        let scale = (*query_layer.size().last().unwrap() as f64 * scale_factor).sqrt();
+        let query_layer = query_layer / scale;
+
+        let mut attention_scores = query_layer.matmul(&key_layer.transpose(-1, -2));
+        if let (Some(relative_pos), Some(rel_embeddings), Some(pos_dropout)) =
+            (relative_pos, rel_embeddings, &self.pos_dropout)
+        {
+            let rel_embeddings = rel_embeddings.apply_t(pos_dropout, train);
+            attention_scores = attention_scores
+                + self.disentangled_att_bias(
+                    &query_layer,
+                    &key_layer,
+                    relative_pos,
+                    &rel_embeddings,
+                    scale_factor,
+                );
+        }
+
+        let attention_probs =
+            x_softmax(&attention_scores, attention_mask, -1).apply_t(&self.dropout, train);
+
+        let context_layer = attention_probs.matmul(&value_layer).permute(&[0, 2, 1, 3]);
+        let mut new_context_layer_shape = context_layer.size();
+        let _ = new_context_layer_shape.pop();
+        let _ = new_context_layer_shape.pop();
+        new_context_layer_shape.push(-1);
+        let context_layer = context_layer
+            .contiguous()
+            .view(new_context_layer_shape.as_slice());
+
+        let attention_probs = if self.output_attentions {
+            Some(attention_probs)
+        } else {
+            None
+        };
+        (context_layer, attention_probs)
+    }
+}
+
+/// Dense layer, dropout and residual layer normalization applied to the output of the attention and
+/// of the feed-forward blocks.
+#[derive(Debug)]
+pub struct DebertaSelfOutput {
+    dense: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl DebertaSelfOutput {
+    pub fn new<'p, P>(
+        p: P,
+        config: &DebertaConfig,
+        input_size: i64,
+        output_size: i64,
+    ) -> DebertaSelfOutput
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(p / "dense", input_size, output_size, Default::default());
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-7),
+            ..Default::default()
+        };
+        let layer_norm = nn::layer_norm(p / "LayerNorm", vec![output_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        DebertaSelfOutput {
+            dense,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
+        let hidden_states: Tensor = input_tensor
+            + hidden_states
+                .apply(&self.dense)
+                .apply_t(&self.dropout, train);
+        hidden_states.apply(&self.layer_norm)
+    }
+}
+
+#[derive(Debug)]
+pub struct DebertaAttention {
+    _self: DisentangledSelfAttention,
+    output: DebertaSelfOutput,
+}
+
+impl DebertaAttention {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let _self = DisentangledSelfAttention::new(p / "self", config);
+        let output =
+            DebertaSelfOutput::new(p / "output", config, config.hidden_size, config.hidden_size);
+        DebertaAttention { _self, output }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        relative_pos: Option<&Tensor>,
+        rel_embeddings: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (self_output, attention_weights) = self._self.forward_t(
+            hidden_states,
+            attention_mask,
+            relative_pos,
+            rel_embeddings,
+            train,
+        );
+
+        let self_output = self.output.forward_t(&self_output, hidden_states, train);
+        (self_output, attention_weights)
+    }
+}
@@ -0,0 +1,579 @@
+// Copyright 2020, Microsoft and the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::deberta::embeddings::DebertaEmbeddings;
+use crate::deberta::encoder::DebertaEncoder;
+use crate::{Activation, Config, ProblemType, RustBertError};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tch::nn::ModuleT;
+use tch::{nn, Kind, Tensor};
+
+/// # DeBERTa Pretrained model weight files
+pub struct DebertaModelResources;
+
+/// # DeBERTa Pretrained model config files
+pub struct DebertaConfigResources;
+
+/// # DeBERTa Pretrained model vocab files
+pub struct DebertaVocabResources;
+
+/// # DeBERTa Pretrained model merges files
+pub struct DebertaMergesResources;
+
+impl DebertaModelResources {
+    /// Shared under MIT license by the Microsoft team at https://github.com/microsoft/DeBERTa. Modified with conversion to C-array format.
+    pub const DEBERTA_BASE_MNLI: (&'static str, &'static str) = (
+        "deberta-base-mnli/model",
+        "https://huggingface.co/microsoft/deberta-base-mnli/resolve/main/rust_model.ot",
+    );
+}
+
+impl DebertaConfigResources {
+    /// Shared under MIT license by the Microsoft team at https://github.com/microsoft/DeBERTa. Modified with conversion to C-array format.
+    pub const DEBERTA_BASE_MNLI: (&'static str, &'static str) = (
+        "deberta-base-mnli/config",
+        "https://huggingface.co/microsoft/deberta-base-mnli/resolve/main/config.json",
+    );
+}
+
+impl DebertaVocabResources {
+    /// Shared under MIT license by the Microsoft team at https://github.com/microsoft/DeBERTa. Modified with conversion to C-array format.
+    pub const DEBERTA_BASE_MNLI: (&'static str, &'static str) = (
+        "deberta-base-mnli/vocab",
+        "https://huggingface.co/microsoft/deberta-base-mnli/resolve/main/vocab.json",
+    );
+}
+
+impl DebertaMergesResources {
+    /// Shared under MIT license by the Microsoft team at https://github.com/microsoft/DeBERTa. Modified with conversion to C-array format.
+    pub const DEBERTA_BASE_MNLI: (&'static str, &'static str) = (
+        "deberta-base-mnli/merges",
+        "https://huggingface.co/microsoft/deberta-base-mnli/resolve/main/merges.txt",
+    );
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+/// # Disentangled attention terms between content and relative positions
+pub enum PositionAttentionType {
+    /// Content to position attention
+    c2p,
+    /// Position to content attention
+    p2c,
+    /// Position to position attention
+    p2p,
+}
+
+impl TryFrom<&str> for PositionAttentionType {
+    type Error = RustBertError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "c2p" => Ok(PositionAttentionType::c2p),
+            "p2c" => Ok(PositionAttentionType::p2c),
+            "p2p" => Ok(PositionAttentionType::p2p),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Position attention type {} not in `c2p`, `p2c` or `p2p`",
+                value
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PositionAttentionTypesInput {
+    List(Vec<PositionAttentionType>),
+    Joined(String),
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+/// # Disentangled attention terms used by the model
+/// Deserialized from either a list (`["c2p", "p2c"]`) or a `|`-separated string (`"c2p|p2c"`),
+/// both formats being found in the published configuration files.
+pub struct PositionAttentionTypes(Vec<PositionAttentionType>);
+
+impl<'de> Deserialize<'de> for PositionAttentionTypes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let types = match PositionAttentionTypesInput::deserialize(deserializer)? {
+            PositionAttentionTypesInput::List(types) => types,
+            PositionAttentionTypesInput::Joined(types) => types
+                .split('|')
+                .filter(|value| !value.trim().is_empty())
+                .map(PositionAttentionType::try_from)
+                .collect::<Result<Vec<PositionAttentionType>, RustBertError>>()
+                .map_err(de::Error::custom)?,
+        };
+        Ok(PositionAttentionTypes(types))
+    }
+}
+
+impl PositionAttentionTypes {
+    /// Returns true if the attention term is used by the model
+    pub fn has_type(&self, attention_type: PositionAttentionType) -> bool {
+        self.0.contains(&attention_type)
+    }
+
+    /// Number of disentangled attention terms, excluding the content to content term
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # DeBERTa model configuration
+/// Defines the DeBERTa model architecture (e.g. number of layers, hidden layer size, label mapping...)
+pub struct DebertaConfig {
+    pub hidden_act: Activation,
+    pub attention_probs_dropout_prob: f64,
+    pub hidden_dropout_prob: f64,
+    pub hidden_size: i64,
+    pub initializer_range: f64,
+    pub intermediate_size: i64,
+    pub max_position_embeddings: i64,
+    pub num_attention_heads: i64,
+    pub num_hidden_layers: i64,
+    pub type_vocab_size: i64,
+    pub vocab_size: i64,
+    pub position_biased_input: Option<bool>,
+    pub pos_att_type: Option<PositionAttentionTypes>,
+    pub pooler_dropout: Option<f64>,
+    pub pooler_hidden_act: Option<Activation>,
+    pub pooler_hidden_size: Option<i64>,
+    pub cls_dropout: Option<f64>,
+    pub layer_norm_eps: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    pub relative_attention: Option<bool>,
+    pub max_relative_positions: Option<i64>,
+    pub embedding_size: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
+}
+
+impl Config<DebertaConfig> for DebertaConfig {}
+
+/// # DeBERTa context pooler
+/// Pools the hidden state of the first token (`[CLS]`) of the sequence
+pub struct ContextPooler {
+    dense: nn::Linear,
+    dropout: Dropout,
+    activation: TensorFunction,
+    pub output_dim: i64,
+}
+
+impl ContextPooler {
+    /// Build a new `ContextPooler`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the pooler
+    /// * `config` - `DebertaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::deberta::{ContextPooler, DebertaConfig};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = DebertaConfig::from_file(config_path);
+    /// let pooler = ContextPooler::new(&p.root() / "pooler", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> ContextPooler
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let pooler_hidden_size = config.pooler_hidden_size.unwrap_or(config.hidden_size);
+
+        let dense = nn::linear(
+            p / "dense",
+            pooler_hidden_size,
+            pooler_hidden_size,
+            Default::default(),
+        );
+        let dropout = Dropout::new(config.pooler_dropout.unwrap_or(0.0));
+        let activation = config
+            .pooler_hidden_act
+            .unwrap_or(Activation::gelu)
+            .get_function();
+
+        ContextPooler {
+            dense,
+            dropout,
+            activation,
+            output_dim: pooler_hidden_size,
+        }
+    }
+}
+
+impl ModuleT for ContextPooler {
+    fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        self.activation.get_fn()(
+            &hidden_states
+                .select(1, 0)
+                .apply_t(&self.dropout, train)
+                .apply(&self.dense),
+        )
+    }
+}
+
+/// # DeBERTa Base model
+/// Base architecture for DeBERTa models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `embeddings`: `DebertaEmbeddings` word, (optional) absolute position and (optional) token type embeddings
+/// - `encoder`: `DebertaEncoder` made of a vector of layers with disentangled self-attention over the content and the relative positions
+pub struct DebertaModel {
+    embeddings: DebertaEmbeddings,
+    encoder: DebertaEncoder,
+}
+
+impl DebertaModel {
+    /// Build a new `DebertaModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the DeBERTa model
+    /// * `config` - `DebertaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::deberta::{DebertaConfig, DebertaModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = DebertaConfig::from_file(config_path);
+    /// let model = DebertaModel::new(&p.root() / "deberta", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = DebertaEmbeddings::new(p / "embeddings", config);
+        let encoder = DebertaEncoder::new(p / "encoder", config);
+
+        DebertaModel {
+            embeddings,
+            encoder,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `DebertaModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::deberta::{DebertaConfig, DebertaModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = DebertaConfig::from_file(config_path);
+    /// let model = DebertaModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        attention_mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<DebertaModelOutput, RustBertError> {
+        let (input_shape, device) = match (input_ids, input_embeds) {
+            (Some(_), Some(_)) => {
+                return Err(RustBertError::ValueError(
+                    "Only one of input ids or input embeddings may be set".into(),
+                ));
+            }
+            (Some(input_ids), None) => (input_ids.size(), input_ids.device()),
+            (None, Some(input_embeds)) => {
+                (input_embeds.size()[..2].to_vec(), input_embeds.device())
+            }
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of input ids or input embeddings must be set".into(),
+                ));
+            }
+        };
+
+        let calc_attention_mask = if attention_mask.is_none() {
+            Some(Tensor::ones(input_shape.as_slice(), (Kind::Int64, device)))
+        } else {
+            None
+        };
+        let attention_mask =
+            attention_mask.unwrap_or_else(|| calc_attention_mask.as_ref().unwrap());
+
+        let embedding_output = self.embeddings.forward_t(
+            input_ids,
+            token_type_ids,
+            position_ids,
+            attention_mask,
+            input_embeds,
+            train,
+        )?;
+
+        let encoder_output = self
+            .encoder
+            .forward_t(&embedding_output, attention_mask, train);
+
+        Ok(DebertaModelOutput {
+            hidden_state: encoder_output.hidden_state,
+            all_hidden_states: encoder_output.all_hidden_states,
+            all_attentions: encoder_output.all_attentions,
+        })
+    }
+}
+
+/// # DeBERTa for sequence classification
+/// Base DeBERTa model with a classifier head to perform sentence or document-level classification
+/// It is made of the following blocks:
+/// - `deberta`: Base DeBERTa model
+/// - `pooler`: `ContextPooler` applied to the first token of the sequence
+/// - `classifier`: Linear layer for classification
+pub struct DebertaForSequenceClassification {
+    deberta: DebertaModel,
+    pooler: ContextPooler,
+    classifier: nn::Linear,
+    dropout: Dropout,
+}
+
+impl DebertaForSequenceClassification {
+    /// Build a new `DebertaForSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the DeBERTa model
+    /// * `config` - `DebertaConfig` object defining the model architecture and number of classes
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::deberta::{DebertaConfig, DebertaForSequenceClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = DebertaConfig::from_file(config_path);
+    /// let model = DebertaForSequenceClassification::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaForSequenceClassification
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let deberta = DebertaModel::new(p / "deberta", config);
+        let pooler = ContextPooler::new(p / "pooler", config);
+        let num_labels = config
+            .id2label
+            .as_ref()
+            .expect("num_labels not provided in configuration")
+            .len() as i64;
+        let classifier = nn::linear(
+            p / "classifier",
+            pooler.output_dim,
+            num_labels,
+            Default::default(),
+        );
+        let dropout = Dropout::new(config.cls_dropout.unwrap_or(config.hidden_dropout_prob));
+
+        DebertaForSequenceClassification {
+            deberta,
+            pooler,
+            classifier,
+            dropout,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `DebertaSequenceClassificationOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::deberta::{DebertaConfig, DebertaForSequenceClassification};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = DebertaConfig::from_file(config_path);
+    /// let model = DebertaForSequenceClassification::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     model
+    ///         .forward_t(
+    ///             Some(&input_tensor),
+    ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
+    ///             None,
+    ///             false,
+    ///         )
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        attention_mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<DebertaSequenceClassificationOutput, RustBertError> {
+        let base_model_output = self.deberta.forward_t(
+            input_ids,
+            attention_mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+
+        let logits = base_model_output
+            .hidden_state
+            .apply_t(&self.pooler, train)
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier);
+
+        Ok(DebertaSequenceClassificationOutput {
+            logits,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+/// Container for the DeBERTa model output.
+pub struct DebertaModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the DeBERTa sequence classification model output.
+pub struct DebertaSequenceClassificationOutput {
+    /// Logits for each input (sequence) for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_position_attention_types() -> anyhow::Result<()> {
+        let from_list: PositionAttentionTypes = serde_json::from_str(r#"["c2p", "p2c"]"#)?;
+        let from_string: PositionAttentionTypes = serde_json::from_str(r#""c2p|p2c""#)?;
+        assert_eq!(from_list, from_string);
+        assert_eq!(from_list.len(), 2);
+        assert!(from_list.has_type(PositionAttentionType::c2p));
+        assert!(!from_list.has_type(PositionAttentionType::p2p));
+        assert!(serde_json::from_str::<PositionAttentionTypes>(r#""c2p|x2y""#).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2020, Microsoft and the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::deberta::DebertaConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::nn::EmbeddingConfig;
+use tch::{nn, Kind, Tensor};
+
+pub struct DebertaEmbeddings {
+    word_embeddings: nn::Embedding,
+    position_embeddings: Option<nn::Embedding>,
+    token_type_embeddings: Option<nn::Embedding>,
+    embed_proj: Option<nn::Linear>,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl DebertaEmbeddings {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let embedding_size = config.embedding_size.unwrap_or(config.hidden_size);
+
+        let embeddings_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(0),
+            ..Default::default()
+        };
+        let word_embeddings = nn::embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            embedding_size,
+            embeddings_config,
+        );
+
+        let position_embeddings = if config.position_biased_input.unwrap_or(true) {
+            Some(nn::embedding(
+                p / "position_embeddings",
+                config.max_position_embeddings,
+                embedding_size,
+                Default::default(),
+            ))
+        } else {
+            None
+        };
+
+        let token_type_embeddings = if config.type_vocab_size > 0 {
+            Some(nn::embedding(
+                p / "token_type_embeddings",
+                config.type_vocab_size,
+                embedding_size,
+                Default::default(),
+            ))
+        } else {
+            None
+        };
+
+        let embed_proj = if embedding_size != config.hidden_size {
+            let linear_config = nn::LinearConfig {
+                bias: false,
+                ..Default::default()
+            };
+            Some(nn::linear(
+                p / "embed_proj",
+                embedding_size,
+                config.hidden_size,
+                linear_config,
+            ))
+        } else {
+            None
+        };
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-7),
+            ..Default::default()
+        };
+        let layer_norm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+
+        DebertaEmbeddings {
+            word_embeddings,
+            position_embeddings,
+            token_type_embeddings,
+            embed_proj,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        attention_mask: &Tensor,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (input_shape, calc_input_embeddings) = match (input_ids, input_embeds) {
+            (Some(_), Some(_)) => {
+                return Err(RustBertError::ValueError(
+                    "Only one of input ids or input embeddings may be set".into(),
+                ));
+            }
+            (Some(input_ids), None) => (
+                input_ids.size(),
+                Some(input_ids.apply(&self.word_embeddings)),
+            ),
+            (None, Some(input_embeds)) => (input_embeds.size()[..2].to_vec(), None),
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of input ids or input embeddings must be set".into(),
+                ));
+            }
+        };
+        let input_embeds = input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let device = input_embeds.device();
+
+        let mut embeddings = input_embeds.shallow_clone();
+        if let Some(position_embeddings) = &self.position_embeddings {
+            let calc_position_ids = if position_ids.is_none() {
+                Some(
+                    Tensor::arange(input_shape[1], (Kind::Int64, device))
+                        .unsqueeze(0)
+                        .expand(input_shape.as_slice(), true),
+                )
+            } else {
+                None
+            };
+            let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+            embeddings = embeddings + position_ids.apply(position_embeddings);
+        }
+
+        if let Some(token_type_embeddings) = &self.token_type_embeddings {
+            let calc_token_type_ids = if token_type_ids.is_none() {
+                Some(Tensor::zeros(input_shape.as_slice(), (Kind::Int64, device)))
+            } else {
+                None
+            };
+            let token_type_ids =
+                token_type_ids.unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap());
+            embeddings = embeddings + token_type_ids.apply(token_type_embeddings);
+        }
+
+        if let Some(embed_proj) = &self.embed_proj {
+            embeddings = embeddings.apply(embed_proj);
+        }
+
+        let embeddings = embeddings.apply(&self.layer_norm)
+            * attention_mask.unsqueeze(2).to_kind(input_embeds.kind());
+
+        Ok(embeddings.apply_t(&self.dropout, train))
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright 2020, Microsoft and the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::deberta::attention::{DebertaAttention, DebertaSelfOutput};
+use crate::deberta::DebertaConfig;
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+pub struct DebertaIntermediate {
+    dense: nn::Linear,
+    activation: TensorFunction,
+}
+
+impl DebertaIntermediate {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaIntermediate
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let activation = config.hidden_act.get_function();
+        DebertaIntermediate { dense, activation }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        (self.activation.get_fn())(&hidden_states.apply(&self.dense))
+    }
+}
+
+pub struct DebertaLayer {
+    attention: DebertaAttention,
+    intermediate: DebertaIntermediate,
+    output: DebertaSelfOutput,
+}
+
+impl DebertaLayer {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let attention = DebertaAttention::new(p / "attention", config);
+        let intermediate = DebertaIntermediate::new(p / "intermediate", config);
+        let output = DebertaSelfOutput::new(
+            p / "output",
+            config,
+            config.intermediate_size,
+            config.hidden_size,
+        );
+
+        DebertaLayer {
+            attention,
+            intermediate,
+            output,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        relative_pos: Option<&Tensor>,
+        rel_embeddings: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) = self.attention.forward_t(
+            hidden_states,
+            attention_mask,
+            relative_pos,
+            rel_embeddings,
+            train,
+        );
+        let intermediate_output = self.intermediate.forward(&attention_output);
+        let layer_output = self
+            .output
+            .forward_t(&intermediate_output, &attention_output, train);
+        (layer_output, attention_weights)
+    }
+}
+
+/// # DeBERTa Encoder
+/// Encoder used in DeBERTa models.
+/// It is made of a Vector of `DebertaLayer` through which hidden states will be passed, sharing the
+/// relative position embeddings used by the disentangled attention.
+pub struct DebertaEncoder {
+    layers: Vec<DebertaLayer>,
+    rel_embeddings: Option<nn::Embedding>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl DebertaEncoder {
+    pub fn new<'p, P>(p: P, config: &DebertaConfig) -> DebertaEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let p_layers = p / "layer";
+        let mut layers: Vec<DebertaLayer> = vec![];
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(DebertaLayer::new(&p_layers / layer_index, config));
+        }
+
+        let rel_embeddings = if config.relative_attention.unwrap_or(false) {
+            let max_relative_positions = match config.max_relative_positions {
+                Some(max_relative_positions) if max_relative_positions > 0 => {
+                    max_relative_positions
+                }
+                _ => config.max_position_embeddings,
+            };
+            Some(nn::embedding(
+                p / "rel_embeddings",
+                max_relative_positions * 2,
+                config.hidden_size,
+                Default::default(),
+            ))
+        } else {
+            None
+        };
+
+        DebertaEncoder {
+            layers,
+            rel_embeddings,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+
+    fn get_attention_mask(&self, attention_mask: &Tensor) -> Tensor {
+        if attention_mask.dim() <= 2 {
+            let extended_attention_mask = attention_mask.unsqueeze(1).unsqueeze(2);
+            &extended_attention_mask * extended_attention_mask.squeeze1(-2).unsqueeze(-1)
+        } else if attention_mask.dim() == 3 {
+            attention_mask.unsqueeze(1)
+        } else {
+            attention_mask.shallow_clone()
+        }
+    }
+
+    fn build_relative_position(
+        &self,
+        query_size: i64,
+        key_size: i64,
+        device: tch::Device,
+    ) -> Tensor {
+        let query_ids = Tensor::arange(query_size, (Kind::Int64, device));
+        let key_ids = Tensor::arange(key_size, (Kind::Int64, device));
+        (query_ids.unsqueeze(-1) - key_ids.unsqueeze(0)).unsqueeze(0)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: &Tensor,
+        train: bool,
+    ) -> DebertaEncoderOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let attention_mask = self.get_attention_mask(attention_mask);
+        let (relative_pos, rel_embeddings) = match &self.rel_embeddings {
+            Some(rel_embeddings) => {
+                let sequence_length = hidden_states.size()[1];
+                (
+                    Some(self.build_relative_position(
+                        sequence_length,
+                        sequence_length,
+                        hidden_states.device(),
+                    )),
+                    Some(&rel_embeddings.ws),
+                )
+            }
+            None => (None, None),
+        };
+
+        let mut hidden_state = hidden_states.shallow_clone();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+
+            let (layer_output, attention_weights) = layer.forward_t(
+                &hidden_state,
+                &attention_mask,
+                relative_pos.as_ref(),
+                rel_embeddings,
+                train,
+            );
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+
+        DebertaEncoderOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container for the DeBERTa encoder output.
+pub struct DebertaEncoderOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,70 @@
+//! # DeBERTa : Decoding-enhanced BERT with Disentangled Attention (He et al.)
+//!
+//! Implementation of the DeBERTa language model ([DeBERTa: Decoding-enhanced BERT with Disentangled Attention](https://arxiv.org/abs/2006.03654) He, Liu, Gao, Chen, 2020).
+//! The base model is implemented in the `deberta_model::DebertaModel` struct. The following language model heads have also been implemented:
+//! - Sequence classification: `deberta_model::DebertaForSequenceClassification`
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `DeBERTaTokenizer` using a `vocab.json` vocabulary and `merges.txt` byte pair encoding merges
+//!
+//! # Zero-shot classification example below:
+//!
+//! ```no_run
+//! use rust_bert::deberta::{
+//!     DebertaConfigResources, DebertaMergesResources, DebertaModelResources,
+//!     DebertaVocabResources,
+//! };
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::zero_shot_classification::{
+//!     ZeroShotClassificationConfig, ZeroShotClassificationModel,
+//! };
+//! use rust_bert::resources::{RemoteResource, Resource};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config = ZeroShotClassificationConfig {
+//!         model_type: ModelType::Deberta,
+//!         model_resource: Resource::Remote(RemoteResource::from_pretrained(
+//!             DebertaModelResources::DEBERTA_BASE_MNLI,
+//!         )),
+//!         config_resource: Resource::Remote(RemoteResource::from_pretrained(
+//!             DebertaConfigResources::DEBERTA_BASE_MNLI,
+//!         )),
+//!         vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+//!             DebertaVocabResources::DEBERTA_BASE_MNLI,
+//!         )),
+//!         merges_resource: Some(Resource::Remote(RemoteResource::from_pretrained(
+//!             DebertaMergesResources::DEBERTA_BASE_MNLI,
+//!         ))),
+//!         ..Default::default()
+//!     };
+//!     let sequence_classification_model = ZeroShotClassificationModel::new(config)?;
+//!
+//!     let input_sentence = "Who are you voting for in 2020?";
+//!     let candidate_labels = &["politics", "public health", "economics", "sports"];
+//!
+//!     let output = sequence_classification_model.predict(
+//!         &[input_sentence],
+//!         candidate_labels,
+//!         None,
+//!         128,
+//!     );
+//!     println!("{:?}", output);
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod deberta_model;
+mod embeddings;
+mod encoder;
+
+pub use deberta_model::{
+    ContextPooler, DebertaConfig, DebertaConfigResources, DebertaForSequenceClassification,
+    DebertaMergesResources, DebertaModel, DebertaModelOutput, DebertaModelResources,
+    DebertaSequenceClassificationOutput, DebertaVocabResources, PositionAttentionType,
+    PositionAttentionTypes,
+};
@@ -17,7 +17,7 @@ use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::distilbert::embeddings::DistilBertEmbedding;
 use crate::distilbert::transformer::{DistilBertTransformerOutput, Transformer};
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Borrow, collections::HashMap};
 
@@ -103,6 +103,7 @@ pub struct DistilBertConfig {
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
     pub output_past: Option<bool>,
+    pub problem_type: Option<ProblemType>,
     pub qa_dropout: f64,
     pub seq_classif_dropout: f64,
     pub sinusoidal_pos_embds: bool,
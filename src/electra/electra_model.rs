@@ -17,7 +17,7 @@ use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::electra::embeddings::ElectraEmbeddings;
 use crate::{bert::encoder::BertEncoder, common::activations::TensorFunction};
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Borrow, collections::HashMap};
 use tch::{nn, Kind, Tensor};
@@ -93,6 +93,7 @@ pub struct ElectraConfig {
     pub output_hidden_states: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config<ElectraConfig> for ElectraConfig {}
@@ -166,6 +167,7 @@ impl ElectraModel {
             is_decoder: None,
             id2label: config.id2label.clone(),
             label2id: config.label2id.clone(),
+            problem_type: config.problem_type,
         };
         let encoder = BertEncoder::new(p / "encoder", &bert_config);
         ElectraModel {
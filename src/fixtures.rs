@@ -0,0 +1,391 @@
+//! Tiny models shared by the unit tests of the crate and its integration tests (included from
+//! `tests/common`), one helper per architecture. Their weights are not drawn from the global
+//! random number generator of libtorch, which is shared by the tests running in parallel: every
+//! variable is filled with values derived from a seed and the variable name, so that a given
+//! fixture always yields the same checkpoint.
+#![allow(dead_code)]
+
+use rust_bert::resources::{LocalResource, Resource};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tch::nn::VarStore;
+use tch::{nn, no_grad, Device, Tensor};
+
+/// Seed of the weights written by the fixtures
+pub const SEED: u64 = 42;
+
+/// Bound of the uniform distribution the weights are drawn from
+const WEIGHT_RANGE: f32 = 0.5;
+
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Overwrites the floating point variables of `var_store` with values drawn uniformly from
+/// [-0.5, 0.5), derived from `seed`, the name of the variable and the position of the value.
+/// Constant variables (zero biases, unit layer normalization weights) keep their value.
+pub fn fix_weights(var_store: &VarStore, seed: u64) {
+    let mut variables = var_store.variables().into_iter().collect::<Vec<_>>();
+    variables.sort_by(|(name, _), (other_name, _)| name.cmp(other_name));
+    no_grad(|| {
+        for (name, mut variable) in variables {
+            if !variable.is_floating_point()
+                || variable.min().double_value(&[]) == variable.max().double_value(&[])
+            {
+                continue;
+            }
+            let key = fnv1a(&name) ^ splitmix64(seed);
+            let values = (0..variable.numel() as u64)
+                .map(|index| {
+                    //    24 random bits: the values are exactly representable as `f32`
+                    let unit =
+                        (splitmix64(key.wrapping_add(index)) >> 40) as f32 / (1u64 << 24) as f32;
+                    (2.0 * unit - 1.0) * WEIGHT_RANGE
+                })
+                .collect::<Vec<f32>>();
+            let size = variable.size();
+            variable.copy_(&Tensor::of_slice(&values).view(size.as_slice()));
+        }
+    });
+}
+
+pub fn local_resource(local_path: PathBuf) -> Resource {
+    Resource::Local(LocalResource { local_path })
+}
+
+/// Builds a model with `build`, fixes its weights with `seed` and saves them to `path`
+pub fn write_weights(
+    path: &Path,
+    seed: u64,
+    build: impl FnOnce(&nn::Path),
+) -> anyhow::Result<Resource> {
+    let var_store = VarStore::new(Device::Cpu);
+    build(&var_store.root());
+    fix_weights(&var_store, seed);
+    var_store.save(path)?;
+    Ok(local_resource(path.to_path_buf()))
+}
+
+fn write_config(directory: &Path, config: &impl Serialize) -> anyhow::Result<Resource> {
+    let config_path = directory.join("config.json");
+    std::fs::write(&config_path, serde_json::to_string(config)?)?;
+    Ok(local_resource(config_path))
+}
+
+/// Writes a WordPiece vocabulary, one token per line
+fn write_vocab(directory: &Path, vocab: &[&str]) -> anyhow::Result<Resource> {
+    let vocab_path = directory.join("vocab.txt");
+    std::fs::write(&vocab_path, vocab.join("\n"))?;
+    Ok(local_resource(vocab_path))
+}
+
+/// Writes a byte-level BPE vocabulary made of `special_tokens`, the space marker, the lower case
+/// letters and `extra_tokens`, with no merges
+fn write_bpe_vocab(
+    directory: &Path,
+    special_tokens: &[&str],
+    extra_tokens: &[&str],
+) -> anyhow::Result<(Resource, Resource, usize)> {
+    let mut vocab = serde_json::Map::new();
+    let letters = ('a'..='z')
+        .map(|letter| letter.to_string())
+        .collect::<Vec<_>>();
+    for token in special_tokens
+        .iter()
+        .chain(["Ġ"].iter())
+        .map(|token| token.to_string())
+        .chain(letters)
+        .chain(extra_tokens.iter().map(|token| token.to_string()))
+    {
+        let id = vocab.len();
+        vocab.insert(token, id.into());
+    }
+    let vocab_size = vocab.len();
+    let vocab_path = directory.join("vocab.json");
+    std::fs::write(&vocab_path, serde_json::to_string(&vocab)?)?;
+    let merges_path = directory.join("merges.txt");
+    std::fs::write(&merges_path, "#version: 0.2\n")?;
+    Ok((
+        local_resource(vocab_path),
+        local_resource(merges_path),
+        vocab_size,
+    ))
+}
+
+fn id2label(labels: &[&str]) -> HashMap<i64, String> {
+    labels
+        .iter()
+        .enumerate()
+        .map(|(id, label)| (id as i64, label.to_string()))
+        .collect()
+}
+
+fn label2id(labels: &[&str]) -> HashMap<String, i64> {
+    labels
+        .iter()
+        .enumerate()
+        .map(|(id, label)| (label.to_string(), id as i64))
+        .collect()
+}
+
+/// Configuration of a 2 layers BERT model with a hidden size of 16, and a classification head over
+/// `labels`
+#[cfg(feature = "bert")]
+pub fn tiny_bert_config(vocab_size: usize, labels: &[&str]) -> rust_bert::bert::BertConfig {
+    let mut config: rust_bert::bert::BertConfig = serde_json::from_value(serde_json::json!({
+        "hidden_act": "gelu",
+        "attention_probs_dropout_prob": 0.1,
+        "hidden_dropout_prob": 0.1,
+        "hidden_size": 16,
+        "initializer_range": 0.02,
+        "intermediate_size": 32,
+        "max_position_embeddings": 64,
+        "num_attention_heads": 2,
+        "num_hidden_layers": 2,
+        "type_vocab_size": 2,
+        "vocab_size": vocab_size,
+    }))
+    .unwrap();
+    if !labels.is_empty() {
+        config.id2label = Some(id2label(labels));
+        config.label2id = Some(label2id(labels));
+    }
+    config
+}
+
+/// Writes a tiny BERT model (weights, configuration and vocabulary) to `directory` and returns its
+/// model, configuration and vocabulary resources. The classification head over `labels` serves both
+/// the sequence and the token classification pipelines, the weights also hold a question answering
+/// head.
+#[cfg(feature = "bert")]
+pub fn tiny_bert_resources(
+    directory: &Path,
+    vocab: &[&str],
+    labels: &[&str],
+) -> anyhow::Result<(Resource, Resource, Resource)> {
+    use rust_bert::bert::BertForSequenceClassification;
+
+    let config = tiny_bert_config(vocab.len(), labels);
+    let model_resource = write_weights(&directory.join("rust_model.ot"), SEED, |path| {
+        let _ = BertForSequenceClassification::new(path, &config);
+        let _ = nn::linear(
+            path / "qa_outputs",
+            config.hidden_size,
+            2,
+            Default::default(),
+        );
+    })?;
+    Ok((
+        model_resource,
+        write_config(directory, &config)?,
+        write_vocab(directory, vocab)?,
+    ))
+}
+
+/// Configuration of a 2 layers DistilBERT model with a hidden size of 16. With `labels`, the
+/// configuration defines a sequence classification head over these labels.
+#[cfg(feature = "distilbert")]
+pub fn tiny_distilbert_config(
+    vocab_size: usize,
+    labels: Option<&[&str]>,
+) -> rust_bert::distilbert::DistilBertConfig {
+    let mut config: rust_bert::distilbert::DistilBertConfig =
+        serde_json::from_value(serde_json::json!({
+            "activation": "gelu",
+            "attention_dropout": 0.1,
+            "dim": 16,
+            "dropout": 0.1,
+            "hidden_dim": 32,
+            "initializer_range": 0.02,
+            "max_position_embeddings": 128,
+            "n_heads": 2,
+            "n_layers": 2,
+            "qa_dropout": 0.1,
+            "seq_classif_dropout": 0.2,
+            "sinusoidal_pos_embds": false,
+            "tie_weights_": false,
+            "vocab_size": vocab_size,
+        }))
+        .unwrap();
+    if let Some(labels) = labels {
+        config.id2label = Some(id2label(labels));
+        config.label2id = Some(label2id(labels));
+    }
+    config
+}
+
+/// Writes a tiny DistilBERT model (weights, configuration and vocabulary) to `directory` and
+/// returns its model, configuration and vocabulary resources. With `labels`, the weights are those
+/// of a sequence classifier over these labels, otherwise of a question answering model.
+#[cfg(feature = "distilbert")]
+pub fn tiny_distilbert_resources(
+    directory: &Path,
+    vocab: &[&str],
+    labels: Option<&[&str]>,
+) -> anyhow::Result<(Resource, Resource, Resource)> {
+    use rust_bert::distilbert::{DistilBertForQuestionAnswering, DistilBertModelClassifier};
+
+    let config = tiny_distilbert_config(vocab.len(), labels);
+    let model_resource = write_weights(&directory.join("rust_model.ot"), SEED, |path| {
+        if labels.is_some() {
+            let _ = DistilBertModelClassifier::new(path, &config);
+        } else {
+            let _ = DistilBertForQuestionAnswering::new(path, &config);
+        }
+    })?;
+    Ok((
+        model_resource,
+        write_config(directory, &config)?,
+        write_vocab(directory, vocab)?,
+    ))
+}
+
+/// Writes a tiny GPT-2 language model (weights, configuration, vocabulary and merges) to
+/// `directory` and returns its resources. The byte-level vocabulary holds the lower case letters
+/// and `extra_tokens`.
+#[cfg(feature = "gpt2")]
+pub fn tiny_gpt2_resources(
+    directory: &Path,
+    extra_tokens: &[&str],
+) -> anyhow::Result<(Resource, Resource, Resource, Resource)> {
+    use rust_bert::gpt2::{GPT2LMHeadModel, Gpt2Config};
+
+    let (vocab_resource, merges_resource, vocab_size) =
+        write_bpe_vocab(directory, &["<|endoftext|>"], extra_tokens)?;
+    let config: Gpt2Config = serde_json::from_value(serde_json::json!({
+        "initializer_range": 0.02,
+        "layer_norm_epsilon": 1e-5,
+        "n_ctx": 64,
+        "n_embd": 16,
+        "n_head": 2,
+        "n_layer": 2,
+        "n_positions": 64,
+        "vocab_size": vocab_size,
+    }))?;
+    let model_resource = write_weights(&directory.join("rust_model.ot"), SEED, |path| {
+        let _ = GPT2LMHeadModel::new(path, &config);
+    })?;
+    Ok((
+        model_resource,
+        write_config(directory, &config)?,
+        vocab_resource,
+        merges_resource,
+    ))
+}
+
+/// Configuration of a BART model with 2 encoder and decoder layers and a hidden size of 16, over
+/// the vocabulary written by `tiny_bart_resources`. With `labels`, the configuration defines a
+/// sequence classification head over these labels.
+#[cfg(feature = "bart")]
+pub fn tiny_bart_config(labels: Option<&[&str]>) -> rust_bert::bart::BartConfig {
+    let mut config: rust_bert::bart::BartConfig = serde_json::from_value(serde_json::json!({
+        "activation_dropout": 0.0,
+        "attention_dropout": 0.0,
+        "d_model": 16,
+        "decoder_attention_heads": 2,
+        "decoder_ffn_dim": 32,
+        "decoder_layerdrop": 0.0,
+        "decoder_layers": 2,
+        "dropout": 0.1,
+        "encoder_attention_heads": 2,
+        "encoder_ffn_dim": 32,
+        "encoder_layerdrop": 0.0,
+        "encoder_layers": 2,
+        "bos_token_id": 0,
+        "eos_token_id": 2,
+        "pad_token_id": 1,
+        "init_std": 0.02,
+        "max_position_embeddings": 64,
+        "num_hidden_layers": 2,
+        "vocab_size": 32,
+    }))
+    .unwrap();
+    if let Some(labels) = labels {
+        config.id2label = Some(id2label(labels));
+        config.label2id = Some(label2id(labels));
+    }
+    config
+}
+
+/// Writes a tiny BART conditional generation model (weights, configuration, vocabulary and merges)
+/// to `directory` and returns its resources
+#[cfg(feature = "bart")]
+pub fn tiny_bart_resources(
+    directory: &Path,
+) -> anyhow::Result<(Resource, Resource, Resource, Resource)> {
+    use rust_bert::bart::BartForConditionalGeneration;
+
+    let (vocab_resource, merges_resource, _) =
+        write_bpe_vocab(directory, &["<s>", "<pad>", "</s>", "<unk>", "<mask>"], &[])?;
+    let config = tiny_bart_config(None);
+    let model_resource = write_weights(&directory.join("rust_model.ot"), SEED, |path| {
+        let _ = BartForConditionalGeneration::new(path, &config);
+    })?;
+    Ok((
+        model_resource,
+        write_config(directory, &config)?,
+        vocab_resource,
+        merges_resource,
+    ))
+}
+
+/// Configuration of a 2 layers FNet model with a hidden size of 16 and a vocabulary of 32 tokens
+/// (padding: 3), with a sequence classification head over `labels`
+#[cfg(feature = "fnet")]
+pub fn tiny_fnet_config(labels: &[&str]) -> rust_bert::fnet::FNetConfig {
+    let mut config: rust_bert::fnet::FNetConfig = serde_json::from_value(serde_json::json!({
+        "hidden_act": "gelu_new",
+        "hidden_dropout_prob": 0.1,
+        "hidden_size": 16,
+        "initializer_range": 0.02,
+        "intermediate_size": 32,
+        "max_position_embeddings": 64,
+        "num_hidden_layers": 2,
+        "type_vocab_size": 4,
+        "vocab_size": 32,
+        "pad_token_id": 3,
+    }))
+    .unwrap();
+    config.id2label = Some(id2label(labels));
+    config.label2id = Some(label2id(labels));
+    config
+}
+
+/// Configuration of a 2 layers DeBERTa model with a hidden size of 16 and a vocabulary of 32
+/// tokens (padding: 0), with a sequence classification head over `labels`
+#[cfg(feature = "deberta")]
+pub fn tiny_deberta_config(labels: &[&str]) -> rust_bert::deberta::DebertaConfig {
+    let mut config: rust_bert::deberta::DebertaConfig = serde_json::from_value(serde_json::json!({
+        "attention_probs_dropout_prob": 0.1,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "hidden_size": 16,
+        "initializer_range": 0.02,
+        "intermediate_size": 32,
+        "max_position_embeddings": 64,
+        "relative_attention": true,
+        "position_biased_input": false,
+        "pos_att_type": "c2p|p2c",
+        "layer_norm_eps": 1e-7,
+        "max_relative_positions": -1,
+        "num_attention_heads": 2,
+        "num_hidden_layers": 2,
+        "type_vocab_size": 0,
+        "vocab_size": 32,
+        "pad_token_id": 0,
+    }))
+    .unwrap();
+    config.id2label = Some(id2label(labels));
+    config.label2id = Some(label2id(labels));
+    config
+}
@@ -0,0 +1,145 @@
+// Copyright 2021 Google Research
+// Copyright 2020-present, the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::fnet::FNetConfig;
+use crate::RustBertError;
+use std::borrow::Borrow;
+use tch::nn::{embedding, EmbeddingConfig};
+use tch::{nn, Kind, Tensor};
+
+/// # Embeddings implementation for FNet model
+/// Sum of the word, position and token type embeddings, normalized and projected to the hidden size
+#[derive(Debug)]
+pub struct FNetEmbeddings {
+    word_embeddings: nn::Embedding,
+    position_embeddings: nn::Embedding,
+    token_type_embeddings: nn::Embedding,
+    layer_norm: nn::LayerNorm,
+    projection: nn::Linear,
+    dropout: Dropout,
+}
+
+impl FNetEmbeddings {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetEmbeddings
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embedding_config = EmbeddingConfig {
+            padding_idx: config.pad_token_id.unwrap_or(3),
+            ..Default::default()
+        };
+
+        let word_embeddings: nn::Embedding = embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            embedding_config,
+        );
+
+        let position_embeddings: nn::Embedding = embedding(
+            p / "position_embeddings",
+            config.max_position_embeddings,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let token_type_embeddings: nn::Embedding = embedding(
+            p / "token_type_embeddings",
+            config.type_vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm: nn::LayerNorm =
+            nn::layer_norm(p / "LayerNorm", vec![config.hidden_size], layer_norm_config);
+        let projection = nn::linear(
+            p / "projection",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let dropout: Dropout = Dropout::new(config.hidden_dropout_prob);
+        FNetEmbeddings {
+            word_embeddings,
+            position_embeddings,
+            token_type_embeddings,
+            layer_norm,
+            projection,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        let (calc_input_embeddings, input_shape) = match (input_ids, input_embeds) {
+            (Some(_), Some(_)) => {
+                return Err(RustBertError::ValueError(
+                    "Only one of input ids or input embeddings may be set".into(),
+                ));
+            }
+            (Some(input_ids), None) => (
+                Some(input_ids.apply_t(&self.word_embeddings, train)),
+                input_ids.size(),
+            ),
+            (None, Some(input_embeds)) => (None, input_embeds.size()[..2].to_vec()),
+            (None, None) => {
+                return Err(RustBertError::ValueError(
+                    "At least one of input ids or input embeddings must be set".into(),
+                ));
+            }
+        };
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+        let device = input_embeddings.device();
+
+        let calc_position_ids = if position_ids.is_none() {
+            Some(
+                Tensor::arange(input_shape[1], (Kind::Int64, device))
+                    .unsqueeze(0)
+                    .expand(&input_shape, true),
+            )
+        } else {
+            None
+        };
+        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+
+        let calc_token_type_ids = if token_type_ids.is_none() {
+            Some(Tensor::zeros(&input_shape, (Kind::Int64, device)))
+        } else {
+            None
+        };
+        let token_type_ids =
+            token_type_ids.unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap());
+
+        let embeddings = input_embeddings
+            + position_ids.apply(&self.position_embeddings)
+            + token_type_ids.apply(&self.token_type_embeddings);
+        Ok(embeddings
+            .apply(&self.layer_norm)
+            .apply(&self.projection)
+            .apply_t(&self.dropout, train))
+    }
+}
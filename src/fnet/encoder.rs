@@ -0,0 +1,186 @@
+// Copyright 2021 Google Research
+// Copyright 2020-present, the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::fnet::FNetConfig;
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Tensor};
+
+/// # Fourier transform mixing the tokens of the sequence
+/// Replaces the self-attention of BERT-like models: the real part of the 2D discrete Fourier
+/// transform over the sequence and hidden dimensions is added to the input and normalized.
+pub struct FNetFourierTransform {
+    layer_norm: nn::LayerNorm,
+}
+
+impl FNetFourierTransform {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetFourierTransform
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm = nn::layer_norm(
+            p / "output" / "LayerNorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        FNetFourierTransform { layer_norm }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        let fourier_output = hidden_states
+            .fft_fftn(None::<&[i64]>, &[1, 2][..], "backward")
+            .real();
+        (fourier_output + hidden_states).apply(&self.layer_norm)
+    }
+}
+
+/// # FNet feed-forward block
+pub struct FNetFeedForward {
+    intermediate: nn::Linear,
+    activation: TensorFunction,
+    output: nn::Linear,
+    layer_norm: nn::LayerNorm,
+    dropout: Dropout,
+}
+
+impl FNetFeedForward {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetFeedForward
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let intermediate = nn::linear(
+            p / "intermediate" / "dense",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let output = nn::linear(
+            p / "output" / "dense",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps.unwrap_or(1e-12),
+            ..Default::default()
+        };
+        let layer_norm = nn::layer_norm(
+            p / "output" / "LayerNorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let activation = config.hidden_act.get_function();
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        FNetFeedForward {
+            intermediate,
+            activation,
+            output,
+            layer_norm,
+            dropout,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let output = self.activation.get_fn()(&hidden_states.apply(&self.intermediate))
+            .apply(&self.output)
+            .apply_t(&self.dropout, train);
+        (output + hidden_states).apply(&self.layer_norm)
+    }
+}
+
+/// # FNet layer
+/// Fourier token mixing followed by a feed-forward block
+pub struct FNetLayer {
+    fourier: FNetFourierTransform,
+    feed_forward: FNetFeedForward,
+}
+
+impl FNetLayer {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let fourier = FNetFourierTransform::new(p / "fourier", config);
+        let feed_forward = FNetFeedForward::new(p, config);
+        FNetLayer {
+            fourier,
+            feed_forward,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let hidden_states = self.fourier.forward(hidden_states);
+        self.feed_forward.forward_t(&hidden_states, train)
+    }
+}
+
+/// # FNet encoder
+/// Stack of `FNetLayer`
+pub struct FNetEncoder {
+    output_hidden_states: bool,
+    layers: Vec<FNetLayer>,
+}
+
+impl FNetEncoder {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow() / "layer";
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+        let layers = (0..config.num_hidden_layers)
+            .map(|layer_index| FNetLayer::new(&p / layer_index, config))
+            .collect();
+        FNetEncoder {
+            output_hidden_states,
+            layers,
+        }
+    }
+
+    pub fn forward_t(&self, hidden_states: &Tensor, train: bool) -> FNetEncoderOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut hidden_state = hidden_states.copy();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            hidden_state = layer.forward_t(&hidden_state, train);
+        }
+
+        FNetEncoderOutput {
+            hidden_state,
+            all_hidden_states,
+        }
+    }
+}
+
+/// Container for the FNet encoder output.
+pub struct FNetEncoderOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,391 @@
+// Copyright 2021 Google Research
+// Copyright 2020-present, the HuggingFace Inc. team.
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::fnet::embeddings::FNetEmbeddings;
+use crate::fnet::encoder::FNetEncoder;
+use crate::{Activation, Config, ProblemType, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use tch::nn::Module;
+use tch::{nn, Tensor};
+
+/// # FNet Pretrained model weight files
+pub struct FNetModelResources;
+
+/// # FNet Pretrained model config files
+pub struct FNetConfigResources;
+
+/// # FNet Pretrained model vocab files
+pub struct FNetVocabResources;
+
+impl FNetModelResources {
+    /// Shared under Apache 2.0 license by the Google team at https://github.com/google-research/google-research/tree/master/f_net. Modified with conversion to C-array format.
+    pub const BASE: (&'static str, &'static str) = (
+        "fnet-base/model",
+        "https://huggingface.co/google/fnet-base/resolve/main/rust_model.ot",
+    );
+    /// Shared under Apache 2.0 license at https://huggingface.co/gchhablani/fnet-base-finetuned-sst2. Modified with conversion to C-array format.
+    pub const BASE_SST2: (&'static str, &'static str) = (
+        "fnet-base-sst2/model",
+        "https://huggingface.co/gchhablani/fnet-base-finetuned-sst2/resolve/main/rust_model.ot",
+    );
+}
+
+impl FNetConfigResources {
+    /// Shared under Apache 2.0 license by the Google team at https://github.com/google-research/google-research/tree/master/f_net. Modified with conversion to C-array format.
+    pub const BASE: (&'static str, &'static str) = (
+        "fnet-base/config",
+        "https://huggingface.co/google/fnet-base/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license at https://huggingface.co/gchhablani/fnet-base-finetuned-sst2. Modified with conversion to C-array format.
+    pub const BASE_SST2: (&'static str, &'static str) = (
+        "fnet-base-sst2/config",
+        "https://huggingface.co/gchhablani/fnet-base-finetuned-sst2/resolve/main/config.json",
+    );
+}
+
+impl FNetVocabResources {
+    /// Shared under Apache 2.0 license by the Google team at https://github.com/google-research/google-research/tree/master/f_net. Modified with conversion to C-array format.
+    pub const BASE: (&'static str, &'static str) = (
+        "fnet-base/spiece",
+        "https://huggingface.co/google/fnet-base/resolve/main/spiece.model",
+    );
+    /// Shared under Apache 2.0 license at https://huggingface.co/gchhablani/fnet-base-finetuned-sst2. Modified with conversion to C-array format.
+    pub const BASE_SST2: (&'static str, &'static str) = (
+        "fnet-base-sst2/spiece",
+        "https://huggingface.co/gchhablani/fnet-base-finetuned-sst2/resolve/main/spiece.model",
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # FNet model configuration
+/// Defines the FNet model architecture (e.g. number of layers, hidden layer size, label mapping...)
+pub struct FNetConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub num_hidden_layers: i64,
+    pub intermediate_size: i64,
+    pub hidden_act: Activation,
+    pub hidden_dropout_prob: f64,
+    pub max_position_embeddings: i64,
+    pub type_vocab_size: i64,
+    pub initializer_range: f64,
+    pub layer_norm_eps: Option<f64>,
+    pub pad_token_id: Option<i64>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub output_hidden_states: Option<bool>,
+    pub id2label: Option<HashMap<i64, String>>,
+    pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
+}
+
+impl Config<FNetConfig> for FNetConfig {}
+
+/// # FNet pooler
+/// Pools the hidden state of the first token (`[CLS]`) of the sequence
+pub struct FNetPooler {
+    dense: nn::Linear,
+}
+
+impl FNetPooler {
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetPooler
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let dense = nn::linear(
+            p.borrow() / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        FNetPooler { dense }
+    }
+}
+
+impl Module for FNetPooler {
+    fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        hidden_states.select(1, 0).apply(&self.dense).tanh()
+    }
+}
+
+/// # FNet Base model
+/// Base architecture for FNet models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `embeddings`: `FNetEmbeddings` word, position and token type embeddings
+/// - `encoder`: `FNetEncoder` made of a vector of layers mixing the tokens with a Fourier transform
+/// - `pooler`: Optional `FNetPooler` applied to the first token of the sequence
+///
+/// FNet models do not use an attention mask: padding tokens are mixed with the other tokens of the
+/// sequence and affect the outputs.
+pub struct FNetModel {
+    embeddings: FNetEmbeddings,
+    encoder: FNetEncoder,
+    pooler: Option<FNetPooler>,
+}
+
+impl FNetModel {
+    /// Build a new `FNetModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the FNet model
+    /// * `config` - `FNetConfig` object defining the model architecture
+    /// * `add_pooling_layer` - Enable/Disable an optional pooling layer at the end of the model
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::fnet::{FNetConfig, FNetModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = FNetConfig::from_file(config_path);
+    /// let model = FNetModel::new(&p.root() / "fnet", &config, true);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &FNetConfig, add_pooling_layer: bool) -> FNetModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embeddings = FNetEmbeddings::new(p / "embeddings", config);
+        let encoder = FNetEncoder::new(p / "encoder", config);
+        let pooler = if add_pooling_layer {
+            Some(FNetPooler::new(p / "pooler", config))
+        } else {
+            None
+        };
+
+        FNetModel {
+            embeddings,
+            encoder,
+            pooler,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `FNetModelOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `pooled_output` - Optional `Tensor` of shape (*batch size*, *hidden_size*) if the model was created with a pooling layer
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::fnet::{FNetConfig, FNetModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = FNetConfig::from_file(config_path);
+    /// let model = FNetModel::new(&vs.root(), &config, true);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     model
+    ///         .forward_t(Some(&input_tensor), None, None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<FNetModelOutput, RustBertError> {
+        let embedding_output = self.embeddings.forward_t(
+            input_ids,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            train,
+        )?;
+
+        let encoder_output = self.encoder.forward_t(&embedding_output, train);
+        let pooled_output = self
+            .pooler
+            .as_ref()
+            .map(|pooler| encoder_output.hidden_state.apply(pooler));
+
+        Ok(FNetModelOutput {
+            hidden_state: encoder_output.hidden_state,
+            pooled_output,
+            all_hidden_states: encoder_output.all_hidden_states,
+        })
+    }
+}
+
+/// # FNet for sequence classification
+/// Base FNet model with a classifier head to perform sentence or document-level classification
+/// It is made of the following blocks:
+/// - `fnet`: Base FNet model, with a pooling layer
+/// - `classifier`: Linear layer for classification
+pub struct FNetForSequenceClassification {
+    fnet: FNetModel,
+    dropout: Dropout,
+    classifier: nn::Linear,
+}
+
+impl FNetForSequenceClassification {
+    /// Build a new `FNetForSequenceClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the FNet model
+    /// * `config` - `FNetConfig` object defining the model architecture and number of classes
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::fnet::{FNetConfig, FNetForSequenceClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = FNetConfig::from_file(config_path);
+    /// let model = FNetForSequenceClassification::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &FNetConfig) -> FNetForSequenceClassification
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let fnet = FNetModel::new(p / "fnet", config, true);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let num_labels = config
+            .id2label
+            .as_ref()
+            .expect("num_labels not provided in configuration")
+            .len() as i64;
+        let classifier = nn::linear(
+            p / "classifier",
+            config.hidden_size,
+            num_labels,
+            Default::default(),
+        );
+
+        FNetForSequenceClassification {
+            fnet,
+            dropout,
+            classifier,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `FNetSequenceClassificationOutput` containing:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// use rust_bert::fnet::{FNetConfig, FNetForSequenceClassification};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = FNetConfig::from_file(config_path);
+    /// let model = FNetForSequenceClassification::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     model
+    ///         .forward_t(Some(&input_tensor), None, None, None, false)
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<FNetSequenceClassificationOutput, RustBertError> {
+        let base_model_output =
+            self.fnet
+                .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)?;
+
+        let logits = base_model_output
+            .pooled_output
+            .unwrap()
+            .apply_t(&self.dropout, train)
+            .apply(&self.classifier);
+
+        Ok(FNetSequenceClassificationOutput {
+            logits,
+            all_hidden_states: base_model_output.all_hidden_states,
+        })
+    }
+}
+
+/// Container for the FNet model output.
+pub struct FNetModelOutput {
+    /// Last hidden states from the model
+    pub hidden_state: Tensor,
+    /// Pooled output (hidden state of the first token, projected)
+    pub pooled_output: Option<Tensor>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+}
+
+/// Container for the FNet sequence classification model output.
+pub struct FNetSequenceClassificationOutput {
+    /// Logits for each input (sequence) for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+}
@@ -0,0 +1,57 @@
+//! # FNet : Mixing Tokens with Fourier Transforms (Lee-Thorp et al.)
+//!
+//! Implementation of the FNet language model ([FNet: Mixing Tokens with Fourier Transforms](https://arxiv.org/abs/2105.03824) Lee-Thorp, Ainslie, Eckstein, Ontanon, 2021).
+//! The base model is implemented in the `fnet_model::FNetModel` struct. The following language model heads have also been implemented:
+//! - Sequence classification: `fnet_model::FNetForSequenceClassification`
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `FNetTokenizer` using a `spiece.model` SentencePiece model
+//!
+//! # Sentiment analysis example below:
+//!
+//! ```no_run
+//! use rust_bert::fnet::{FNetConfigResources, FNetModelResources, FNetVocabResources};
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::sequence_classification::{
+//!     SequenceClassificationConfig, SequenceClassificationModel,
+//! };
+//! use rust_bert::resources::{RemoteResource, Resource};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config = SequenceClassificationConfig::new(
+//!         ModelType::FNet,
+//!         Resource::Remote(RemoteResource::from_pretrained(
+//!             FNetModelResources::BASE_SST2,
+//!         )),
+//!         Resource::Remote(RemoteResource::from_pretrained(
+//!             FNetConfigResources::BASE_SST2,
+//!         )),
+//!         Resource::Remote(RemoteResource::from_pretrained(
+//!             FNetVocabResources::BASE_SST2,
+//!         )),
+//!         None,
+//!         false,
+//!         None,
+//!         None,
+//!     );
+//!     let sequence_classification_model = SequenceClassificationModel::new(config)?;
+//!
+//!     let output = sequence_classification_model.predict(&["This film was a real pleasure."])?;
+//!     println!("{:?}", output);
+//!     Ok(())
+//! }
+//! ```
+
+mod embeddings;
+mod encoder;
+mod fnet_model;
+
+pub use embeddings::FNetEmbeddings;
+pub use fnet_model::{
+    FNetConfig, FNetConfigResources, FNetForSequenceClassification, FNetModel, FNetModelOutput,
+    FNetModelResources, FNetPooler, FNetSequenceClassificationOutput, FNetVocabResources,
+};
@@ -26,6 +26,7 @@
 //!     start: 13,
 //!     end: 21,
 //!     answer: String::from("Amsterdam"),
+//!     context_index: 0,
 //! }]
 //! # ;
 //! ```
@@ -67,6 +68,8 @@
 //! ProphetNet| | | |✅ |✅ | | |
 //! Longformer|✅|✅|✅| | | |✅|
 //! Pegasus| | | | |✅| | |
+//! DeBERTa|✅| | | | | | |
+//! FNet|✅| | | | | | |
 //! </details>
 //!
 //! # Getting started
@@ -137,6 +140,7 @@
 //!     start: 13,
 //!     end: 21,
 //!     answer: String::from("Amsterdam"),
+//!     context_index: 0,
 //! }]
 //! # ;
 //! ```
@@ -326,7 +330,7 @@
 //!      candidate_labels,
 //!      None,
 //!      128,
-//!  );
+//!  )?;
 //! # Ok(())
 //! # }
 //! ```
@@ -406,7 +410,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_model.predict(&input);
+//! let output = sentiment_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -449,7 +453,7 @@
 //!     "My name is Amy. I live in Paris.",
 //!     "Paris is a city in France.",
 //! ];
-//! let output = ner_model.predict(&input);
+//! let output = ner_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -463,11 +467,15 @@
 //!             word: String::from("Amy"),
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
+//!             start: 11,
+//!             end: 14,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
+//!             start: 26,
+//!             end: 31,
 //!         },
 //!     ],
 //!     [
@@ -475,11 +483,15 @@
 //!             word: String::from("Paris"),
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
+//!             start: 0,
+//!             end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
+//!             start: 19,
+//!             end: 25,
 //!         },
 //!     ],
 //! ]
@@ -497,7 +509,7 @@
 //! # fn main() -> anyhow::Result<()> {
 //! let pos_model = POSModel::new(Default::default())?;
 //! let input = ["My name is Bob"];
-//! let output = pos_model.predict(&input);
+//! let output = pos_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -570,27 +582,57 @@
 //! Thank you to [Hugging Face](https://huggingface.co) for hosting a set of weights compatible with this Rust library.
 //! The list of ready-to-use pretrained models is listed at [https://huggingface.co/models?filter=rust](https://huggingface.co/models?filter=rust).
 
+#[cfg(feature = "albert")]
 pub mod albert;
+#[cfg(feature = "bart")]
 pub mod bart;
+#[cfg(feature = "bert")]
 pub mod bert;
 mod common;
+#[cfg(feature = "deberta")]
+pub mod deberta;
+#[cfg(feature = "distilbert")]
 pub mod distilbert;
+#[cfg(feature = "electra")]
 pub mod electra;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "fnet")]
+pub mod fnet;
+#[cfg(feature = "gpt2")]
 pub mod gpt2;
+#[cfg(feature = "gpt-neo")]
 pub mod gpt_neo;
+#[cfg(feature = "longformer")]
 pub mod longformer;
+#[cfg(feature = "marian")]
 pub mod marian;
+#[cfg(feature = "mbart")]
 pub mod mbart;
+#[cfg(feature = "mobilebert")]
 pub mod mobilebert;
+#[cfg(feature = "openai-gpt")]
 pub mod openai_gpt;
+#[cfg(feature = "pegasus")]
 pub mod pegasus;
 pub mod pipelines;
+#[cfg(feature = "prophetnet")]
 pub mod prophetnet;
+#[cfg(feature = "reformer")]
 pub mod reformer;
+#[cfg(feature = "roberta")]
 pub mod roberta;
+#[cfg(feature = "t5")]
 pub mod t5;
+#[cfg(feature = "xlnet")]
 pub mod xlnet;
 
+//    The test fixtures refer to the crate by name, to be shared with the integration tests
+#[cfg(test)]
+extern crate self as rust_bert;
+
 pub use common::error::RustBertError;
+pub use common::initialization::{initialize, initialized_options, InitOptions};
+pub use common::load_report;
 pub use common::resources;
-pub use common::{Activation, Config};
+pub use common::{Activation, Config, ProblemType};
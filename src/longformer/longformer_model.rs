@@ -14,7 +14,7 @@ use crate::common::activations::{TensorFunction, _tanh};
 use crate::common::dropout::Dropout;
 use crate::longformer::embeddings::LongformerEmbeddings;
 use crate::longformer::encoder::LongformerEncoder;
-use crate::{Activation, Config, RustBertError};
+use crate::{Activation, Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -118,6 +118,7 @@ pub struct LongformerConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config<LongformerConfig> for LongformerConfig {}
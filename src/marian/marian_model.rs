@@ -815,7 +815,9 @@ impl MarianGenerator {
             None,
         )?;
 
-        let config = BartConfig::from_file(config_path);
+        let mut config = BartConfig::from_file(config_path);
+        //    The generation loop only feeds the last generated token to the decoder, which requires the past states to be cached
+        config.output_past = Some(true);
         let model = MarianForConditionalGeneration::new(&var_store.root(), &config);
         var_store.load(weights_path)?;
 
@@ -921,15 +923,15 @@ impl PrivateLanguageGenerator<MarianForConditionalGeneration, MarianVocab, Maria
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
         match past {
-            Cache::BARTCache(past) => PreparedInput {
+            Cache::BARTCache(Some(past)) => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
                 prepared_decoder_input: Some(input_ids.narrow(1, -1, 1)),
                 prepared_position_ids: None,
-                prepared_past: Cache::BARTCache(past),
+                prepared_past: Cache::BARTCache(Some(past)),
             },
-            Cache::None => PreparedInput {
+            Cache::BARTCache(None) | Cache::None => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
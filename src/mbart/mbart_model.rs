@@ -24,7 +24,7 @@ use crate::pipelines::generation_utils::{
     Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
 };
 use crate::resources::{RemoteResource, Resource};
-use crate::{Activation, Config, RustBertError};
+use crate::{Activation, Config, ProblemType, RustBertError};
 use rust_tokenizers::tokenizer::{MBart50Tokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::{MBart50Vocab, Vocab};
 use serde::{Deserialize, Serialize};
@@ -96,6 +96,7 @@ pub struct MBartConfig {
     pub decoder_start_token_id: Option<i64>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
     pub init_std: f64,
     pub min_length: Option<i64>,
     pub no_repeat_ngram_size: Option<i64>,
@@ -870,7 +871,9 @@ impl MBartGenerator {
             None,
             None,
         )?;
-        let config = MBartConfig::from_file(config_path);
+        let mut config = MBartConfig::from_file(config_path);
+        //    The generation loop only feeds the last generated token to the decoder, which requires the past states to be cached
+        config.output_past = Some(true);
         let model = MBartForConditionalGeneration::new(&var_store.root(), &config);
         var_store.load(weights_path)?;
 
@@ -973,15 +976,15 @@ impl PrivateLanguageGenerator<MBartForConditionalGeneration, MBart50Vocab, MBart
         attention_mask: Tensor,
     ) -> PreparedInput<'a> {
         match past {
-            Cache::BARTCache(past) => PreparedInput {
+            Cache::BARTCache(Some(past)) => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
                 prepared_decoder_input: Some(input_ids.narrow(1, -1, 1)),
                 prepared_position_ids: None,
-                prepared_past: Cache::BARTCache(past),
+                prepared_past: Cache::BARTCache(Some(past)),
             },
-            Cache::None => PreparedInput {
+            Cache::BARTCache(None) | Cache::None => PreparedInput {
                 prepared_input: None,
                 prepared_attention_mask: Some(attention_mask),
                 prepared_encoder_output: encoder_outputs,
@@ -14,7 +14,7 @@ use crate::common::activations::{Activation, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::mobilebert::embeddings::MobileBertEmbeddings;
 use crate::mobilebert::encoder::{MobileBertEncoder, MobileBertPooler};
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -192,6 +192,7 @@ pub struct MobileBertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config<MobileBertConfig> for MobileBertConfig {}
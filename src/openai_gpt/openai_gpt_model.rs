@@ -14,6 +14,7 @@
 
 use crate::common::dropout::Dropout;
 use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{
     Gpt2Config, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
@@ -456,6 +457,7 @@ pub struct OpenAIGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    load_report: LoadReport,
 }
 
 impl OpenAIGenerator {
@@ -544,7 +546,7 @@ impl OpenAIGenerator {
         )?;
         let config = Gpt2Config::from_file(config_path);
         let model = OpenAIGPTLMHeadModel::new(&var_store.root(), &config);
-        var_store.load(weights_path)?;
+        let load_report = load_with_report(&mut var_store, weights_path, true)?;
 
         let bos_token_id = None;
         let eos_token_ids = None;
@@ -558,6 +560,7 @@ impl OpenAIGenerator {
             model,
             tokenizer,
             var_store,
+            load_report,
             generate_config,
             bos_token_id,
             eos_token_ids,
@@ -568,6 +571,12 @@ impl OpenAIGenerator {
             max_position_embeddings,
         })
     }
+
+    /// Returns the report of the weights loaded when building the generator, listing the tying
+    /// status of the language modeling head, the repaired tensor shapes and the unused checkpoint tensors
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
 }
 
 impl PrivateLanguageGenerator<OpenAIGPTLMHeadModel, OpenAiGptVocab, OpenAiGptTokenizer>
@@ -0,0 +1,437 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # JSONL batch runner
+//! Utility to process a corpus stored as JSON lines with a pipeline: the input file is streamed,
+//! the records are batched and each record is written to the output file with the pipeline output
+//! added under a configurable field. Interrupted runs can be resumed: records already present in
+//! the output file are skipped. The pipeline outputs are serialized as JSON: the outputs of the
+//! classification, sentiment, token classification and NER pipelines require the `serde` feature.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::batch_runner::{run_jsonl, JsonlRunnerOptions};
+//! use rust_bert::pipelines::summarization::SummarizationModel;
+//!
+//! let summarization_model = SummarizationModel::new(Default::default())?;
+//! let report = run_jsonl(
+//!     &summarization_model,
+//!     "path/to/corpus.jsonl",
+//!     "path/to/corpus_summaries.jsonl",
+//!     "text",
+//!     &JsonlRunnerOptions {
+//!         output_field: "summary".to_string(),
+//!         ..Default::default()
+//!     },
+//! )?;
+//! println!("{} records processed", report.processed);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+#[cfg(feature = "ner")]
+use crate::pipelines::ner::{Entity, NERModel};
+#[cfg(feature = "sentiment")]
+use crate::pipelines::sentiment::{Sentiment, SentimentModel};
+#[cfg(feature = "sequence-classification")]
+use crate::pipelines::sequence_classification::{Label, SequenceClassificationModel};
+#[cfg(feature = "summarization")]
+use crate::pipelines::summarization::SummarizationModel;
+#[cfg(feature = "token-classification")]
+use crate::pipelines::token_classification::{Token, TokenClassificationModel};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// # Common interface for pipelines processing a batch of texts
+/// Implemented for the pipelines producing a single output per input text, allowing them to be
+/// used with the batch processing utilities.
+pub trait Pipeline {
+    /// Output generated for each input text
+    type Output;
+
+    /// Processes a batch of texts, returning one output per input
+    fn process(&self, inputs: &[&str]) -> Result<Vec<Self::Output>, RustBertError>;
+}
+
+#[cfg(feature = "sequence-classification")]
+impl Pipeline for SequenceClassificationModel {
+    type Output = Label;
+
+    fn process(&self, inputs: &[&str]) -> Result<Vec<Label>, RustBertError> {
+        self.predict(inputs)
+    }
+}
+
+#[cfg(feature = "sentiment")]
+impl Pipeline for SentimentModel {
+    type Output = Sentiment;
+
+    fn process(&self, inputs: &[&str]) -> Result<Vec<Sentiment>, RustBertError> {
+        self.predict(inputs)
+    }
+}
+
+#[cfg(feature = "token-classification")]
+impl Pipeline for TokenClassificationModel {
+    type Output = Vec<Token>;
+
+    fn process(&self, inputs: &[&str]) -> Result<Vec<Vec<Token>>, RustBertError> {
+        self.predict(inputs, true, false)
+    }
+}
+
+#[cfg(feature = "ner")]
+impl Pipeline for NERModel {
+    type Output = Vec<Entity>;
+
+    fn process(&self, inputs: &[&str]) -> Result<Vec<Vec<Entity>>, RustBertError> {
+        self.predict(inputs)
+    }
+}
+
+#[cfg(feature = "summarization")]
+impl Pipeline for SummarizationModel {
+    type Output = String;
+
+    fn process(&self, inputs: &[&str]) -> Result<Vec<String>, RustBertError> {
+        Ok(self.summarize(inputs))
+    }
+}
+
+/// # Options for `run_jsonl`
+#[derive(Clone, Debug)]
+pub struct JsonlRunnerOptions {
+    /// Number of records processed by the pipeline at once (default: 32)
+    pub batch_size: usize,
+    /// Field of the output records holding the pipeline output (default: `prediction`)
+    pub output_field: String,
+    /// Resume from an existing partial output file, skipping the records it already contains. If
+    /// false, the output file is overwritten (default: true)
+    pub resume: bool,
+}
+
+impl Default for JsonlRunnerOptions {
+    fn default() -> JsonlRunnerOptions {
+        JsonlRunnerOptions {
+            batch_size: 32,
+            output_field: "prediction".to_string(),
+            resume: true,
+        }
+    }
+}
+
+/// # Summary of a `run_jsonl` execution
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsonlRunReport {
+    /// Number of records skipped because they were already present in the output file
+    pub skipped: usize,
+    /// Number of records processed and written during this run
+    pub processed: usize,
+}
+
+/// Processes a JSONL corpus with a pipeline
+///
+/// Streams the records of the input file (one JSON object per line, empty lines are ignored),
+/// runs the pipeline on the text field of the records by batches and writes each record to the
+/// output file with the pipeline output added under `options.output_field`. The output file is
+/// flushed after each batch: if the run is interrupted, it can be resumed with `options.resume`
+/// and the records already written are skipped (an incomplete trailing line is discarded).
+///
+/// # Arguments
+///
+/// * `pipeline` - Pipeline implementing the `Pipeline` trait with a serializable output (e.g. `SequenceClassificationModel` or `NERModel` with the `serde` feature)
+/// * `input_path` - Path to the input JSONL file
+/// * `output_path` - Path to the output JSONL file
+/// * `text_field` - Field of the input records containing the text to process
+/// * `options` - `JsonlRunnerOptions` batch size, output field and resume options
+///
+/// # Returns
+///
+/// * `JsonlRunReport` containing the number of records skipped and processed
+pub fn run_jsonl<P, I, O>(
+    pipeline: &P,
+    input_path: I,
+    output_path: O,
+    text_field: &str,
+    options: &JsonlRunnerOptions,
+) -> Result<JsonlRunReport, RustBertError>
+where
+    P: Pipeline,
+    P::Output: Serialize,
+    I: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    if options.batch_size == 0 {
+        return Err(RustBertError::InvalidConfigurationError(
+            "The batch size of the JSONL runner must be greater than 0".to_string(),
+        ));
+    }
+    let skipped = if options.resume && output_path.as_ref().exists() {
+        truncate_to_complete_lines(output_path.as_ref())?
+    } else {
+        File::create(output_path.as_ref())?;
+        0
+    };
+    let input_file = BufReader::new(File::open(input_path)?);
+    let mut output_file = BufWriter::new(OpenOptions::new().append(true).open(output_path)?);
+
+    let mut report = JsonlRunReport {
+        skipped,
+        processed: 0,
+    };
+    let mut records: Vec<Map<String, Value>> = Vec::with_capacity(options.batch_size);
+    let mut record_index = 0;
+    for (line_index, line) in input_file.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        record_index += 1;
+        if record_index <= skipped {
+            continue;
+        }
+        records.push(parse_record(&line, line_index + 1, text_field)?);
+        if records.len() == options.batch_size {
+            report.processed += process_batch(
+                pipeline,
+                &mut records,
+                text_field,
+                &options.output_field,
+                &mut output_file,
+            )?;
+        }
+    }
+    if !records.is_empty() {
+        report.processed += process_batch(
+            pipeline,
+            &mut records,
+            text_field,
+            &options.output_field,
+            &mut output_file,
+        )?;
+    }
+    Ok(report)
+}
+
+fn parse_record(
+    line: &str,
+    line_number: usize,
+    text_field: &str,
+) -> Result<Map<String, Value>, RustBertError> {
+    let record: Value = serde_json::from_str(line).map_err(|error| {
+        RustBertError::ValueError(format!(
+            "Invalid JSON record on line {}: {}",
+            line_number, error
+        ))
+    })?;
+    match record {
+        Value::Object(record) => match record.get(text_field) {
+            Some(Value::String(_)) => Ok(record),
+            _ => Err(RustBertError::ValueError(format!(
+                "Record on line {} has no string field `{}`",
+                line_number, text_field
+            ))),
+        },
+        _ => Err(RustBertError::ValueError(format!(
+            "Record on line {} is not a JSON object",
+            line_number
+        ))),
+    }
+}
+
+fn process_batch<P, W>(
+    pipeline: &P,
+    records: &mut Vec<Map<String, Value>>,
+    text_field: &str,
+    output_field: &str,
+    writer: &mut W,
+) -> Result<usize, RustBertError>
+where
+    P: Pipeline,
+    P::Output: Serialize,
+    W: Write,
+{
+    let texts = records
+        .iter()
+        .map(|record| record[text_field].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    let outputs = pipeline.process(&texts)?;
+    if outputs.len() != records.len() {
+        return Err(RustBertError::ValueError(format!(
+            "The pipeline returned {} outputs for {} inputs",
+            outputs.len(),
+            records.len()
+        )));
+    }
+    let num_records = records.len();
+    for (mut record, output) in records.drain(..).zip(outputs) {
+        let output = serde_json::to_value(output)
+            .map_err(|error| RustBertError::ValueError(error.to_string()))?;
+        record.insert(output_field.to_string(), output);
+        serde_json::to_writer(&mut *writer, &record)
+            .map_err(|error| RustBertError::IOError(error.to_string()))?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(num_records)
+}
+
+/// Discards an incomplete trailing line from a partial output file and returns the number of
+/// complete records it contains.
+fn truncate_to_complete_lines(path: &Path) -> Result<usize, RustBertError> {
+    let content = std::fs::read(path)?;
+    let complete_length = content
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |position| position + 1);
+    if complete_length < content.len() {
+        OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_len(complete_length as u64)?;
+    }
+    Ok(content[..complete_length]
+        .split(|&byte| byte == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .count())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct LengthPipeline {
+        processed: RefCell<Vec<String>>,
+    }
+
+    impl Pipeline for LengthPipeline {
+        type Output = usize;
+
+        fn process(&self, inputs: &[&str]) -> Result<Vec<usize>, RustBertError> {
+            self.processed
+                .borrow_mut()
+                .extend(inputs.iter().map(|input| input.to_string()));
+            Ok(inputs.iter().map(|input| input.len()).collect())
+        }
+    }
+
+    fn read_records(path: &Path) -> Vec<Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_run_jsonl() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let input_path = directory.path().join("input.jsonl");
+        let output_path = directory.path().join("output.jsonl");
+        std::fs::write(
+            &input_path,
+            "{\"id\": 1, \"text\": \"one\"}\n\n{\"id\": 2, \"text\": \"three\"}\n{\"id\": 3, \"text\": \"four\"}\n",
+        )?;
+        let pipeline = LengthPipeline {
+            processed: RefCell::new(vec![]),
+        };
+        let options = JsonlRunnerOptions {
+            batch_size: 2,
+            output_field: "length".to_string(),
+            resume: false,
+        };
+
+        let report = run_jsonl(&pipeline, &input_path, &output_path, "text", &options)?;
+
+        assert_eq!(
+            report,
+            JsonlRunReport {
+                skipped: 0,
+                processed: 3
+            }
+        );
+        let records = read_records(&output_path);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["id"], 1);
+        assert_eq!(records[0]["length"], 3);
+        assert_eq!(records[2]["text"], "four");
+        assert_eq!(records[2]["length"], 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_jsonl_resume() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let input_path = directory.path().join("input.jsonl");
+        let output_path = directory.path().join("output.jsonl");
+        std::fs::write(
+            &input_path,
+            "{\"text\": \"a\"}\n{\"text\": \"bb\"}\n{\"text\": \"ccc\"}\n{\"text\": \"dddd\"}\n",
+        )?;
+        //    Simulated interruption: two records written, the third one partially
+        std::fs::write(
+            &output_path,
+            "{\"text\":\"a\",\"length\":1}\n{\"text\":\"bb\",\"length\":2}\n{\"text\":\"cc",
+        )?;
+        let pipeline = LengthPipeline {
+            processed: RefCell::new(vec![]),
+        };
+        let options = JsonlRunnerOptions {
+            batch_size: 8,
+            output_field: "length".to_string(),
+            resume: true,
+        };
+
+        let report = run_jsonl(&pipeline, &input_path, &output_path, "text", &options)?;
+
+        assert_eq!(
+            report,
+            JsonlRunReport {
+                skipped: 2,
+                processed: 2
+            }
+        );
+        assert_eq!(*pipeline.processed.borrow(), vec!["ccc", "dddd"]);
+        let records = read_records(&output_path);
+        assert_eq!(records.len(), 4);
+        for (record, expected_length) in records.iter().zip(1..) {
+            assert_eq!(record["length"], expected_length);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_jsonl_invalid_record() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let input_path = directory.path().join("input.jsonl");
+        let output_path = directory.path().join("output.jsonl");
+        std::fs::write(&input_path, "{\"text\": \"a\"}\n{\"content\": \"b\"}\n")?;
+        let pipeline = LengthPipeline {
+            processed: RefCell::new(vec![]),
+        };
+
+        let result = run_jsonl(
+            &pipeline,
+            &input_path,
+            &output_path,
+            "text",
+            &Default::default(),
+        );
+
+        assert!(result.is_err());
+        Ok(())
+    }
+}
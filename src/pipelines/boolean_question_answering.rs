@@ -0,0 +1,567 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Boolean (yes/no) question answering pipeline
+//! Answers yes/no questions about a passage (e.g. models fine-tuned on BoolQ). The question and the
+//! passage are encoded as a pair and classified by a 2-class sequence classification head, rather
+//! than extracting an answer span as the extractive question answering pipeline.
+//! The model resources (e.g. a RoBERTa model fine-tuned on BoolQ and converted to the `.ot`
+//! format) are provided in the configuration.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::boolean_question_answering::{
+//!     BooleanQuestionAnsweringConfig, BooleanQuestionAnsweringModel,
+//! };
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::question_answering::QaInput;
+//! use rust_bert::resources::{LocalResource, Resource};
+//! use std::path::PathBuf;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let local_resource = |path: &str| {
+//!     Resource::Local(LocalResource {
+//!         local_path: PathBuf::from(path),
+//!     })
+//! };
+//! let config = BooleanQuestionAnsweringConfig::new(
+//!     ModelType::Roberta,
+//!     local_resource("path/to/rust_model.ot"),
+//!     local_resource("path/to/config.json"),
+//!     local_resource("path/to/vocab.json"),
+//!     Some(local_resource("path/to/merges.txt")),
+//!     false,
+//!     None,
+//!     false,
+//! );
+//! let boolean_qa_model = BooleanQuestionAnsweringModel::new(config)?;
+//!
+//! let qa_input = QaInput {
+//!     question: String::from("Is Amsterdam the capital of the Netherlands?"),
+//!     context: String::from(
+//!         "Amsterdam is the capital and most populous city of the Netherlands.",
+//!     ),
+//! };
+//! let answers = boolean_qa_model.predict_boolean(&[qa_input])?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::boolean_question_answering::BooleanAnswer;
+//! # let output =
+//! [BooleanAnswer {
+//!     answer: true,
+//!     score: 0.9731,
+//! }]
+//! # ;
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
+use crate::common::resources::Resource;
+use crate::pipelines::common::{get_padded_length, ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::question_answering::{validate_qa_input, QaInput};
+use crate::pipelines::sequence_classification::{
+    check_calibration, Calibration, SequenceClassificationOption,
+};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::TokenizedInput;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use tch::nn::VarStore;
+use tch::{no_grad, Device, Kind, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Output for boolean question answering
+pub struct BooleanAnswer {
+    /// Answer to the question (true for yes, false for no)
+    pub answer: bool,
+    /// Probability of the answer (at least 0.5)
+    pub score: f64,
+}
+
+/// # Configuration for boolean (yes/no) question answering
+/// Contains information regarding the model to load and device to place the model on.
+pub struct BooleanQuestionAnsweringConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource
+    pub model_resource: Resource,
+    /// Config resource
+    pub config_resource: Resource,
+    /// Vocab resource
+    pub vocab_resource: Resource,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Resource>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+    /// Pad the input sequences to a multiple of this value to produce tensor shapes that are more
+    /// efficient on GPU (e.g. 8 for fp16 tensor cores). Padded positions are masked (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of tokens of the question and passage pair, the passage being truncated
+    /// first (default: 512)
+    pub max_length: usize,
+    /// Number of question and passage pairs processed in a single forward pass (default: 64)
+    pub batch_size: usize,
+    /// Index of the class of the classifier head answering yes (default: 1, as for BoolQ where the
+    /// label 1 is `True`)
+    pub yes_label_id: i64,
+    /// Calibration of the logits applied before the softmax, reflected in the scores of the
+    /// answers (default: None)
+    pub calibration: Option<Calibration>,
+}
+
+impl BooleanQuestionAnsweringConfig {
+    /// Instantiate a new boolean question answering configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model_resource - The `Resource` pointing to the model to load (e.g.  model.ot)
+    /// * config_resource - The `Resource' pointing to the model configuration to load (e.g. config.json)
+    /// * vocab_resource - The `Resource' pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges_resource - An optional `Resource` tuple (`Option<Resource>`) pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool' indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new(
+        model_type: ModelType,
+        model_resource: Resource,
+        config_resource: Resource,
+        vocab_resource: Resource,
+        merges_resource: Option<Resource>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> BooleanQuestionAnsweringConfig {
+        BooleanQuestionAnsweringConfig {
+            model_type,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            merges_resource,
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            max_length: 512,
+            batch_size: 64,
+            yes_label_id: 1,
+            calibration: None,
+        }
+    }
+}
+
+/// # BooleanQuestionAnsweringModel to answer yes/no questions about a passage
+pub struct BooleanQuestionAnsweringModel {
+    tokenizer: TokenizerOption,
+    classifier: SequenceClassificationOption,
+    var_store: VarStore,
+    pad_to_multiple_of: Option<usize>,
+    max_length: usize,
+    batch_size: usize,
+    yes_label_id: i64,
+    calibration: Option<Calibration>,
+    load_report: LoadReport,
+}
+
+impl BooleanQuestionAnsweringModel {
+    /// Build a new `BooleanQuestionAnsweringModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `BooleanQuestionAnsweringConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Returns
+    ///
+    /// * `RustBertError::InvalidConfigurationError` if the classifier head does not have 2 classes,
+    ///   if the `yes_label_id` is not one of them or if the calibration parameters are invalid
+    pub fn new(
+        config: BooleanQuestionAnsweringConfig,
+    ) -> Result<BooleanQuestionAnsweringModel, RustBertError> {
+        ensure_initialized();
+        let config_path = config.config_resource.get_local_path()?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let merges_path = if let Some(merges_resource) = &config.merges_resource {
+            Some(merges_resource.get_local_path()?)
+        } else {
+            None
+        };
+
+        let tokenizer = TokenizerOption::from_file(
+            config.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            config.lower_case,
+            config.strip_accents,
+            config.add_prefix_space,
+        )?;
+        let model_config = ConfigOption::try_from_file(config.model_type, &config_path)?;
+        let num_labels = model_config
+            .get_id2label()
+            .map_or(0, |id2label| id2label.len());
+        if num_labels != 2 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Boolean question answering requires a classifier head with 2 classes, got {}",
+                num_labels
+            )));
+        }
+        if !(0..2).contains(&config.yes_label_id) {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The yes label id must be 0 or 1, got {}",
+                config.yes_label_id
+            )));
+        }
+        check_calibration(config.calibration.as_ref(), num_labels)?;
+
+        let mut var_store = VarStore::new(config.device);
+        let classifier =
+            SequenceClassificationOption::new(config.model_type, &var_store.root(), &model_config)?;
+        let pad_to_multiple_of = model_config.resolve_pad_to_multiple_of(config.pad_to_multiple_of);
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
+        Ok(BooleanQuestionAnsweringModel {
+            tokenizer,
+            classifier,
+            var_store,
+            pad_to_multiple_of,
+            max_length: config.max_length,
+            batch_size: config.batch_size,
+            yes_label_id: config.yes_label_id,
+            calibration: config.calibration,
+            load_report,
+        })
+    }
+
+    /// Returns the report of the weights loading: the weights of the model missing from the
+    /// checkpoint, and the weights of the checkpoint unused by the model.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Answers yes/no questions about their passage (the context of the `QaInput`)
+    ///
+    /// # Arguments
+    ///
+    /// * `qa_inputs` - `&[QaInput]` Array of question and passage pairs
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<BooleanAnswer>` containing the answer and its probability for each input
+    /// * `RustBertError::ValueError` if a question or passage is empty (or only contains
+    ///   whitespace), `RustBertError::MissingPadTokenError` if the tokenizer does not define a
+    ///   padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::boolean_question_answering::{
+    /// #     BooleanQuestionAnsweringConfig, BooleanQuestionAnsweringModel,
+    /// # };
+    /// # use rust_bert::pipelines::common::ModelType;
+    /// # use rust_bert::pipelines::question_answering::QaInput;
+    /// # use rust_bert::resources::{LocalResource, Resource};
+    /// # use std::path::PathBuf;
+    /// # let local_resource = |path: &str| {
+    /// #     Resource::Local(LocalResource {
+    /// #         local_path: PathBuf::from(path),
+    /// #     })
+    /// # };
+    /// # let config = BooleanQuestionAnsweringConfig::new(
+    /// #     ModelType::Roberta,
+    /// #     local_resource("path/to/rust_model.ot"),
+    /// #     local_resource("path/to/config.json"),
+    /// #     local_resource("path/to/vocab.json"),
+    /// #     Some(local_resource("path/to/merges.txt")),
+    /// #     false,
+    /// #     None,
+    /// #     false,
+    /// # );
+    /// let boolean_qa_model = BooleanQuestionAnsweringModel::new(config)?;
+    /// let qa_inputs = [
+    ///     QaInput {
+    ///         question: String::from("Does Amy live in Amsterdam?"),
+    ///         context: String::from("Amy lives in Amsterdam."),
+    ///     },
+    ///     QaInput {
+    ///         question: String::from("Does Eric live in Amsterdam?"),
+    ///         context: String::from("While Amy lives in Amsterdam, Eric is in The Hague."),
+    ///     },
+    /// ];
+    /// let answers = boolean_qa_model.predict_boolean(&qa_inputs)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_boolean(
+        &self,
+        qa_inputs: &[QaInput],
+    ) -> Result<Vec<BooleanAnswer>, RustBertError> {
+        for (index, qa_input) in qa_inputs.iter().enumerate() {
+            validate_qa_input(qa_input).map_err(|error| match error {
+                RustBertError::ValueError(message) => {
+                    RustBertError::ValueError(format!("Input {}: {}", index, message))
+                }
+                error => error,
+            })?;
+        }
+        let mut answers = Vec::with_capacity(qa_inputs.len());
+        for batch in qa_inputs.chunks(self.batch_size.max(1)) {
+            let (input_ids, mask, token_type_ids) = self.prepare_batch(batch)?;
+            let logits = no_grad(|| {
+                self.classifier.forward_t(
+                    Some(input_ids),
+                    Some(mask),
+                    token_type_ids,
+                    None,
+                    None,
+                    false,
+                )
+            });
+            let logits = match &self.calibration {
+                Some(calibration) => calibration.apply(&logits),
+                None => logits,
+            };
+            let yes_probabilities = logits
+                .softmax(-1, Kind::Float)
+                .select(1, self.yes_label_id)
+                .to(Device::Cpu);
+            for index in 0..batch.len() as i64 {
+                let yes_probability = yes_probabilities.double_value(&[index]);
+                answers.push(if yes_probability >= 0.5 {
+                    BooleanAnswer {
+                        answer: true,
+                        score: yes_probability,
+                    }
+                } else {
+                    BooleanAnswer {
+                        answer: false,
+                        score: 1.0 - yes_probability,
+                    }
+                });
+            }
+        }
+        Ok(answers)
+    }
+
+    /// Encodes a batch of question and passage pairs, returning the padded input ids, the
+    /// attention mask and the token type ids (for the models embedding the segment of each token)
+    fn prepare_batch(
+        &self,
+        qa_inputs: &[QaInput],
+    ) -> Result<(Tensor, Tensor, Option<Tensor>), RustBertError> {
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .ok_or(RustBertError::MissingPadTokenError)?;
+        let pairs = qa_inputs
+            .iter()
+            .map(|qa_input| (qa_input.question.as_str(), qa_input.context.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
+            &pairs,
+            self.max_length,
+            &TruncationStrategy::OnlySecond,
+            0,
+        );
+        let max_len = tokenized_input
+            .iter()
+            .map(|input| input.token_ids.len())
+            .max()
+            .ok_or(RustBertError::EmptyInputError)?;
+        let max_len = get_padded_length(max_len, self.pad_to_multiple_of);
+        let device = self.var_store.device();
+
+        let input_ids = tokenized_input
+            .iter()
+            .map(|input| {
+                let mut token_ids = input.token_ids.clone();
+                token_ids.resize(max_len, pad_id);
+                Tensor::of_slice(&token_ids)
+            })
+            .collect::<Vec<_>>();
+        let input_ids = Tensor::stack(&input_ids, 0).to(device);
+        let mask = input_ids.ne(pad_id).to_kind(Kind::Int64);
+
+        let token_type_ids = match self.classifier.model_type() {
+            ModelType::Bert | ModelType::MobileBert | ModelType::Albert | ModelType::XLNet => {
+                let token_type_ids = tokenized_input
+                    .iter()
+                    .map(|input| {
+                        let mut segment_ids = input
+                            .segment_ids
+                            .iter()
+                            .map(|&segment_id| segment_id as i64)
+                            .collect::<Vec<i64>>();
+                        segment_ids.resize(max_len, 0);
+                        Tensor::of_slice(&segment_ids)
+                    })
+                    .collect::<Vec<_>>();
+                Some(Tensor::stack(&token_type_ids, 0).to(device))
+            }
+            _ => None,
+        };
+        Ok((input_ids, mask, token_type_ids))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::tiny_distilbert_resources;
+    use std::path::Path;
+
+    fn tiny_distilbert_config(
+        directory: &Path,
+        id2label: &[&str],
+    ) -> anyhow::Result<BooleanQuestionAnsweringConfig> {
+        let vocab = [
+            "[PAD]",
+            "[UNK]",
+            "[CLS]",
+            "[SEP]",
+            "[MASK]",
+            "does",
+            "amy",
+            "live",
+            "lives",
+            "in",
+            "amsterdam",
+            "the",
+            "hague",
+            "?",
+            ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) =
+            tiny_distilbert_resources(directory, &vocab, Some(id2label))?;
+        let mut config = BooleanQuestionAnsweringConfig::new(
+            ModelType::DistilBert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        Ok(config)
+    }
+
+    fn qa_inputs() -> Vec<QaInput> {
+        vec![
+            QaInput {
+                question: "does amy live in amsterdam ?".to_string(),
+                context: "amy lives in amsterdam .".to_string(),
+            },
+            QaInput {
+                question: "does amy live in the hague ?".to_string(),
+                context: "amy lives in amsterdam .".to_string(),
+            },
+            QaInput {
+                question: "does amy live in amsterdam ?".to_string(),
+                context: "amy lives in the hague .".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_predict_boolean() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let binary_labels = &["False", "True"];
+        let model = BooleanQuestionAnsweringModel::new(tiny_distilbert_config(
+            directory.path(),
+            binary_labels,
+        )?)?;
+
+        let answers = model.predict_boolean(&qa_inputs())?;
+        assert_eq!(answers.len(), 3);
+        for answer in &answers {
+            assert!((0.5..=1.0).contains(&answer.score));
+        }
+
+        //    Batching does not change the answers
+        let mut config = tiny_distilbert_config(directory.path(), binary_labels)?;
+        config.batch_size = 1;
+        let batched_answers =
+            BooleanQuestionAnsweringModel::new(config)?.predict_boolean(&qa_inputs())?;
+        for (answer, batched_answer) in answers.iter().zip(batched_answers.iter()) {
+            assert_eq!(answer.answer, batched_answer.answer);
+            assert!((answer.score - batched_answer.score).abs() < 1e-5);
+        }
+
+        //    Swapping the yes class inverts the answers
+        let mut config = tiny_distilbert_config(directory.path(), binary_labels)?;
+        config.yes_label_id = 0;
+        let inverted_answers =
+            BooleanQuestionAnsweringModel::new(config)?.predict_boolean(&qa_inputs())?;
+        for (answer, inverted_answer) in answers.iter().zip(inverted_answers.iter()) {
+            if (answer.score - 0.5).abs() > 1e-6 {
+                assert_ne!(answer.answer, inverted_answer.answer);
+            }
+            assert!((answer.score - inverted_answer.score).abs() < 1e-5);
+        }
+
+        //    A temperature above 1 softens the probabilities
+        let mut config = tiny_distilbert_config(directory.path(), binary_labels)?;
+        config.calibration = Some(Calibration::Temperature(4.0));
+        let calibrated_answers =
+            BooleanQuestionAnsweringModel::new(config)?.predict_boolean(&qa_inputs())?;
+        for (answer, calibrated_answer) in answers.iter().zip(calibrated_answers.iter()) {
+            assert_eq!(answer.answer, calibrated_answer.answer);
+            assert!(calibrated_answer.score <= answer.score + 1e-6);
+        }
+
+        let mut qa_inputs = qa_inputs();
+        qa_inputs[1].context = " ".to_string();
+        assert!(matches!(
+            model.predict_boolean(&qa_inputs),
+            Err(RustBertError::ValueError(message)) if message.starts_with("Input 1")
+        ));
+        assert!(model.predict_boolean(&[])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_configuration() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let config = tiny_distilbert_config(directory.path(), &["False", "Maybe", "True"])?;
+        assert!(matches!(
+            BooleanQuestionAnsweringModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        let binary_labels = &["False", "True"];
+        let mut config = tiny_distilbert_config(directory.path(), binary_labels)?;
+        config.yes_label_id = 2;
+        assert!(matches!(
+            BooleanQuestionAnsweringModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        let mut config = tiny_distilbert_config(directory.path(), binary_labels)?;
+        config.calibration = Some(Calibration::PlattPerClass(vec![(1.0, 0.0); 3]));
+        assert!(matches!(
+            BooleanQuestionAnsweringModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+}
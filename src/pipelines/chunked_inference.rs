@@ -0,0 +1,288 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Chunked inference driver
+//! Large inputs are processed in chunks, each chunk going through a CPU-bound preparation stage
+//! (tokenization, padding and tensor creation) followed by the model forward pass. With the
+//! `Pipelined` scheduling, the preparation of the next chunks runs on a worker thread while the
+//! current chunk goes through the forward pass. Prepared chunks are handed over through a bounded
+//! channel, limiting the memory held by chunks waiting for the model.
+//!
+//! Outputs are returned in the input order. An error in either stage stops both stages and is
+//! returned to the caller.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::chunked_inference::ChunkScheduling;
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! let input = ["This is great!", "This is terrible."];
+//! let output = model.predict_batched(&input, 64, ChunkScheduling::Pipelined { prefetch: 2 })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+#[cfg(any(feature = "sequence-classification", feature = "zero-shot"))]
+use tch::{Device, Tensor};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Scheduling of the preparation and forward stages of chunked inference
+pub enum ChunkScheduling {
+    /// Each chunk is prepared and then processed by the model on the calling thread
+    Sequential,
+    /// Chunks are prepared on a worker thread, up to `prefetch` prepared chunks waiting for the
+    /// model. A `prefetch` of 0 still overlaps the preparation of a single chunk with the forward pass.
+    Pipelined { prefetch: usize },
+}
+
+impl Default for ChunkScheduling {
+    fn default() -> ChunkScheduling {
+        ChunkScheduling::Pipelined { prefetch: 2 }
+    }
+}
+
+/// Runs the inputs through the preparation and forward stages in chunks of `chunk_size` inputs.
+///
+/// # Arguments
+///
+/// * `inputs` - inputs to process
+/// * `chunk_size` - maximum number of inputs per chunk
+/// * `scheduling` - `ChunkScheduling` of the two stages
+/// * `prepare` - preparation stage, converting a chunk of inputs into model inputs. Runs on a worker thread for pipelined scheduling.
+/// * `forward` - forward stage, returning one output per input of the prepared chunk. Always runs on the calling thread, in the chunk order.
+///
+/// # Returns
+///
+/// * `Vec<O>` containing the outputs, in the input order
+/// * `RustBertError` returned by either stage, or a `ValueError` if the chunk size is 0 or if the forward stage does not return one output per input
+pub fn run_chunked<I, P, O, PrepareFn, ForwardFn>(
+    inputs: &[I],
+    chunk_size: usize,
+    scheduling: ChunkScheduling,
+    prepare: PrepareFn,
+    mut forward: ForwardFn,
+) -> Result<Vec<O>, RustBertError>
+where
+    I: Sync,
+    P: Send,
+    PrepareFn: Fn(&[I]) -> Result<P, RustBertError> + Send,
+    ForwardFn: FnMut(P) -> Result<Vec<O>, RustBertError>,
+{
+    if chunk_size == 0 {
+        return Err(RustBertError::ValueError(
+            "The chunk size must be strictly positive".to_string(),
+        ));
+    }
+    let chunk_lengths = inputs.chunks(chunk_size).map(|chunk| chunk.len());
+    match scheduling {
+        ChunkScheduling::Sequential => {
+            let prepared_chunks = inputs.chunks(chunk_size).map(|chunk| prepare(chunk));
+            collect_outputs(prepared_chunks, chunk_lengths, &mut forward, inputs.len())
+        }
+        ChunkScheduling::Pipelined { prefetch } => thread::scope(|scope| {
+            let (sender, receiver) = sync_channel(prefetch);
+            let producer = scope.spawn(move || {
+                for chunk in inputs.chunks(chunk_size) {
+                    let prepared = prepare(chunk);
+                    let failed = prepared.is_err();
+                    //    Sending fails once the forward stage stopped and dropped the receiver
+                    if sender.send(prepared).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+            let outputs = consume_outputs(receiver, chunk_lengths, &mut forward, inputs.len());
+            match producer.join() {
+                Ok(()) => outputs,
+                Err(_) => Err(RustBertError::ValueError(
+                    "The chunk preparation stage panicked".to_string(),
+                )),
+            }
+        }),
+    }
+}
+
+/// Takes ownership of the receiver so that it is dropped when the forward stage stops, which
+/// unblocks and stops the preparation stage.
+fn consume_outputs<P, O, ForwardFn>(
+    receiver: Receiver<Result<P, RustBertError>>,
+    chunk_lengths: impl Iterator<Item = usize>,
+    forward: &mut ForwardFn,
+    num_inputs: usize,
+) -> Result<Vec<O>, RustBertError>
+where
+    ForwardFn: FnMut(P) -> Result<Vec<O>, RustBertError>,
+{
+    collect_outputs(receiver.into_iter(), chunk_lengths, forward, num_inputs)
+}
+
+fn collect_outputs<P, O, ForwardFn>(
+    prepared_chunks: impl Iterator<Item = Result<P, RustBertError>>,
+    chunk_lengths: impl Iterator<Item = usize>,
+    forward: &mut ForwardFn,
+    num_inputs: usize,
+) -> Result<Vec<O>, RustBertError>
+where
+    ForwardFn: FnMut(P) -> Result<Vec<O>, RustBertError>,
+{
+    let mut outputs = Vec::with_capacity(num_inputs);
+    for (prepared, chunk_length) in prepared_chunks.zip(chunk_lengths) {
+        let chunk_outputs = forward(prepared?)?;
+        if chunk_outputs.len() != chunk_length {
+            return Err(RustBertError::ValueError(format!(
+                "The forward stage returned {} outputs for a chunk of {} inputs",
+                chunk_outputs.len(),
+                chunk_length
+            )));
+        }
+        outputs.extend(chunk_outputs);
+    }
+    Ok(outputs)
+}
+
+/// Pins a prepared CPU tensor in page-locked memory when it is to be copied to a CUDA device,
+/// speeding up the host to device transfer.
+#[cfg(any(feature = "sequence-classification", feature = "zero-shot"))]
+pub(crate) fn pin_for_device(tensor: Tensor, device: Device) -> Tensor {
+    match device {
+        Device::Cuda(_) => tensor.pin_memory(),
+        Device::Cpu => tensor,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn square_chunks(
+        inputs: &[i64],
+        chunk_size: usize,
+        scheduling: ChunkScheduling,
+    ) -> Result<Vec<i64>, RustBertError> {
+        run_chunked(
+            inputs,
+            chunk_size,
+            scheduling,
+            |chunk: &[i64]| {
+                thread::sleep(Duration::from_millis(1));
+                Ok(chunk.to_vec())
+            },
+            |chunk| Ok(chunk.iter().map(|value| value * value).collect()),
+        )
+    }
+
+    #[test]
+    fn test_pipelined_matches_sequential() -> anyhow::Result<()> {
+        let inputs = (0..103).collect::<Vec<i64>>();
+        let expected = square_chunks(&inputs, 10, ChunkScheduling::Sequential)?;
+        assert_eq!(expected, inputs.iter().map(|v| v * v).collect::<Vec<_>>());
+        for &prefetch in [0, 1, 4].iter() {
+            let outputs = square_chunks(&inputs, 10, ChunkScheduling::Pipelined { prefetch })?;
+            assert_eq!(outputs, expected);
+        }
+        assert!(square_chunks(&[], 10, ChunkScheduling::default())?.is_empty());
+        assert!(square_chunks(&inputs, 0, ChunkScheduling::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepare_error_stops_pipeline() {
+        let inputs = (0..100).collect::<Vec<i64>>();
+        for &scheduling in [
+            ChunkScheduling::Sequential,
+            ChunkScheduling::Pipelined { prefetch: 2 },
+        ]
+        .iter()
+        {
+            let forwarded = AtomicUsize::new(0);
+            let result: Result<Vec<i64>, _> = run_chunked(
+                &inputs,
+                10,
+                scheduling,
+                |chunk: &[i64]| {
+                    if chunk[0] == 30 {
+                        Err(RustBertError::ValueError("invalid chunk".to_string()))
+                    } else {
+                        Ok(chunk.to_vec())
+                    }
+                },
+                |chunk| {
+                    forwarded.fetch_add(1, Ordering::SeqCst);
+                    Ok(chunk)
+                },
+            );
+            assert!(
+                matches!(result, Err(RustBertError::ValueError(message)) if message == "invalid chunk")
+            );
+            assert_eq!(forwarded.load(Ordering::SeqCst), 3);
+        }
+    }
+
+    #[test]
+    fn test_forward_error_stops_pipeline() {
+        let inputs = (0..1000).collect::<Vec<i64>>();
+        let prepared = AtomicUsize::new(0);
+        let result: Result<Vec<i64>, _> = run_chunked(
+            &inputs,
+            10,
+            ChunkScheduling::Pipelined { prefetch: 2 },
+            |chunk: &[i64]| {
+                prepared.fetch_add(1, Ordering::SeqCst);
+                Ok(chunk.to_vec())
+            },
+            |chunk| {
+                if chunk[0] == 20 {
+                    Err(RustBertError::ValueError("forward failed".to_string()))
+                } else {
+                    Ok(chunk)
+                }
+            },
+        );
+        assert!(result.is_err());
+        //    The failing chunk, the prefetched chunks and the chunk blocked on the full channel
+        assert!(prepared.load(Ordering::SeqCst) <= 3 + 2 + 1);
+    }
+
+    #[test]
+    fn test_output_count_mismatch() {
+        let result: Result<Vec<i64>, _> = run_chunked(
+            &[1i64, 2, 3],
+            2,
+            ChunkScheduling::Pipelined { prefetch: 1 },
+            |chunk: &[i64]| Ok(chunk.to_vec()),
+            |_| Ok(vec![0]),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_prepare_panic() {
+        let result: Result<Vec<i64>, _> = run_chunked(
+            &[1i64, 2, 3],
+            1,
+            ChunkScheduling::Pipelined { prefetch: 1 },
+            |chunk: &[i64]| {
+                if chunk[0] == 2 {
+                    panic!("preparation failed");
+                }
+                Ok(chunk.to_vec())
+            },
+            Ok,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -16,39 +16,60 @@
 //! generic pipelines. The model component is defined in the generic pipeline itself as the
 //! pre-processing, forward pass and postprocessing differs between pipelines while basic config and
 //! tokenization objects don't.
+#[cfg(feature = "albert")]
 use crate::albert::AlbertConfig;
+#[cfg(feature = "bart")]
 use crate::bart::BartConfig;
+#[cfg(feature = "bert")]
 use crate::bert::BertConfig;
 use crate::common::error::RustBertError;
+use crate::common::ProblemType;
+#[cfg(feature = "deberta")]
+use crate::deberta::DebertaConfig;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertConfig;
+#[cfg(feature = "electra")]
 use crate::electra::ElectraConfig;
+#[cfg(feature = "fnet")]
+use crate::fnet::FNetConfig;
+#[cfg(feature = "gpt2")]
 use crate::gpt2::Gpt2Config;
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::GptNeoConfig;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerConfig;
+#[cfg(feature = "mbart")]
 use crate::mbart::MBartConfig;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertConfig;
+#[cfg(feature = "pegasus")]
 use crate::pegasus::PegasusConfig;
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::ProphetNetConfig;
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerConfig;
+#[cfg(feature = "t5")]
 use crate::t5::T5Config;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetConfig;
-use crate::Config;
 use rust_tokenizers::tokenizer::{
-    AlbertTokenizer, BertTokenizer, Gpt2Tokenizer, MBart50Tokenizer, MarianTokenizer,
-    MultiThreadedTokenizer, OpenAiGptTokenizer, PegasusTokenizer, ProphetNetTokenizer,
-    ReformerTokenizer, RobertaTokenizer, T5Tokenizer, Tokenizer, TruncationStrategy,
-    XLMRobertaTokenizer, XLNetTokenizer,
+    AlbertTokenizer, BertTokenizer, DeBERTaTokenizer, FNetTokenizer, Gpt2Tokenizer,
+    MBart50Tokenizer, MarianTokenizer, MultiThreadedTokenizer, OpenAiGptTokenizer,
+    PegasusTokenizer, ProphetNetTokenizer, ReformerTokenizer, RobertaTokenizer, T5Tokenizer,
+    Tokenizer, TruncationStrategy, XLMRobertaTokenizer, XLNetTokenizer,
 };
 use rust_tokenizers::vocab::{
-    AlbertVocab, BertVocab, Gpt2Vocab, MBart50Vocab, MarianVocab, OpenAiGptVocab, PegasusVocab,
-    ProphetNetVocab, ReformerVocab, RobertaVocab, T5Vocab, Vocab, XLMRobertaVocab, XLNetVocab,
+    AlbertVocab, BertVocab, DeBERTaVocab, FNetVocab, Gpt2Vocab, MBart50Vocab, MarianVocab,
+    OpenAiGptVocab, PegasusVocab, ProphetNetVocab, ReformerVocab, RobertaVocab, T5Vocab, Vocab,
+    XLMRobertaVocab, XLNetVocab,
 };
 use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput, TokensWithOffsets};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use tch::{Device, Kind, Tensor};
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
 /// # Identifies the type of model
 pub enum ModelType {
     Bart,
@@ -70,42 +91,146 @@ pub enum ModelType {
     Pegasus,
     GPTNeo,
     MBart,
+    Deberta,
+    FNet,
+}
+
+impl ModelType {
+    /// Name of the cargo feature providing support for this model type
+    pub fn feature_name(&self) -> &'static str {
+        match self {
+            ModelType::Bart => "bart",
+            ModelType::Bert => "bert",
+            ModelType::DistilBert => "distilbert",
+            ModelType::Roberta | ModelType::XLMRoberta => "roberta",
+            ModelType::Electra => "electra",
+            ModelType::Marian => "marian",
+            ModelType::MobileBert => "mobilebert",
+            ModelType::T5 => "t5",
+            ModelType::Albert => "albert",
+            ModelType::XLNet => "xlnet",
+            ModelType::GPT2 => "gpt2",
+            ModelType::OpenAiGpt => "openai-gpt",
+            ModelType::Reformer => "reformer",
+            ModelType::ProphetNet => "prophetnet",
+            ModelType::Longformer => "longformer",
+            ModelType::Pegasus => "pegasus",
+            ModelType::GPTNeo => "gpt-neo",
+            ModelType::MBart => "mbart",
+            ModelType::Deberta => "deberta",
+            ModelType::FNet => "fnet",
+        }
+    }
+
+    /// Returns true if support for this model type was compiled in
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ModelType::Bart => cfg!(feature = "bart"),
+            ModelType::Bert => cfg!(feature = "bert"),
+            ModelType::DistilBert => cfg!(feature = "distilbert"),
+            ModelType::Roberta | ModelType::XLMRoberta => cfg!(feature = "roberta"),
+            ModelType::Electra => cfg!(feature = "electra"),
+            ModelType::Marian => cfg!(feature = "marian"),
+            ModelType::MobileBert => cfg!(feature = "mobilebert"),
+            ModelType::T5 => cfg!(feature = "t5"),
+            ModelType::Albert => cfg!(feature = "albert"),
+            ModelType::XLNet => cfg!(feature = "xlnet"),
+            ModelType::GPT2 => cfg!(feature = "gpt2"),
+            ModelType::OpenAiGpt => cfg!(feature = "openai-gpt"),
+            ModelType::Reformer => cfg!(feature = "reformer"),
+            ModelType::ProphetNet => cfg!(feature = "prophetnet"),
+            ModelType::Longformer => cfg!(feature = "longformer"),
+            ModelType::Pegasus => cfg!(feature = "pegasus"),
+            ModelType::GPTNeo => cfg!(feature = "gpt-neo"),
+            ModelType::MBart => cfg!(feature = "mbart"),
+            ModelType::Deberta => cfg!(feature = "deberta"),
+            ModelType::FNet => cfg!(feature = "fnet"),
+        }
+    }
+
+    /// Returns the model type of the tokenizer used by this model type (e.g. `Roberta` for `Bart`)
+    pub fn tokenizer_type(&self) -> ModelType {
+        match self {
+            ModelType::Bert
+            | ModelType::DistilBert
+            | ModelType::Electra
+            | ModelType::MobileBert => ModelType::Bert,
+            ModelType::Roberta | ModelType::Bart | ModelType::Longformer => ModelType::Roberta,
+            ModelType::GPT2 | ModelType::GPTNeo => ModelType::GPT2,
+            _ => *self,
+        }
+    }
+
+    /// Returns an `UnsupportedModelType` error if support for this model type was not compiled in
+    pub fn ensure_enabled(&self) -> Result<(), RustBertError> {
+        if self.is_enabled() {
+            Ok(())
+        } else {
+            Err(RustBertError::UnsupportedModelType(format!(
+                "{:?} support is disabled, enable the `{}` feature of rust-bert",
+                self,
+                self.feature_name()
+            )))
+        }
+    }
 }
 
 /// # Abstraction that holds a model configuration, can be of any of the supported models
 pub enum ConfigOption {
     /// Bart configuration
+    #[cfg(feature = "bart")]
     Bart(BartConfig),
     /// Bert configuration
+    #[cfg(feature = "bert")]
     Bert(BertConfig),
     /// DistilBert configuration
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertConfig),
     /// Electra configuration
+    #[cfg(feature = "electra")]
     Electra(ElectraConfig),
     /// Marian configuration
+    #[cfg(feature = "marian")]
     Marian(BartConfig),
     /// MobileBert configuration
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertConfig),
     /// T5 configuration
+    #[cfg(feature = "t5")]
     T5(T5Config),
     /// Albert configuration
+    #[cfg(feature = "albert")]
     Albert(AlbertConfig),
     /// XLNet configuration
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetConfig),
     /// GPT2 configuration
+    #[cfg(feature = "gpt2")]
     GPT2(Gpt2Config),
     /// Reformer configuration
+    #[cfg(feature = "reformer")]
     Reformer(ReformerConfig),
     /// ProphetNet configuration
+    #[cfg(feature = "prophetnet")]
     ProphetNet(ProphetNetConfig),
     /// Longformer configuration
+    #[cfg(feature = "longformer")]
     Longformer(LongformerConfig),
     /// Pegasus configuration
+    #[cfg(feature = "pegasus")]
     Pegasus(PegasusConfig),
     /// GPT-Neo configuration
+    #[cfg(feature = "gpt-neo")]
     GPTNeo(GptNeoConfig),
     /// MBart configuration
+    #[cfg(feature = "mbart")]
     MBart(MBartConfig),
+    /// DeBERTa configuration
+    #[cfg(feature = "deberta")]
+    Deberta(DebertaConfig),
+    /// FNet configuration
+    #[cfg(feature = "fnet")]
+    FNet(FNetConfig),
 }
 
 /// # Abstraction that holds a particular tokenizer, can be of any of the supported models
@@ -136,74 +261,395 @@ pub enum TokenizerOption {
     Pegasus(PegasusTokenizer),
     /// MBart50 Tokenizer
     MBart50(MBart50Tokenizer),
+    /// DeBERTa Tokenizer
+    Deberta(DeBERTaTokenizer),
+    /// FNet Tokenizer
+    FNet(FNetTokenizer),
 }
 
 impl ConfigOption {
     /// Interface method to load a configuration from file
+    ///
+    /// # Panics
+    ///
+    /// Panics if support for `model_type` was not compiled in (see `try_from_file`)
     pub fn from_file<P: AsRef<Path>>(model_type: ModelType, path: P) -> Self {
-        match model_type {
-            ModelType::Bart => ConfigOption::Bart(BartConfig::from_file(path)),
+        Self::try_from_file(model_type, path).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Interface method to load a configuration from file, returning an
+    /// `UnsupportedModelType` error if the model type was disabled at compile time.
+    pub fn try_from_file<P: AsRef<Path>>(
+        model_type: ModelType,
+        path: P,
+    ) -> Result<Self, RustBertError> {
+        model_type.ensure_enabled()?;
+        Self::try_from_str(model_type, &std::fs::read_to_string(path)?)
+    }
+
+    /// Interface method to load a configuration from its JSON content, returning an
+    /// `UnsupportedModelType` error if the model type was disabled at compile time.
+    pub fn try_from_str(model_type: ModelType, json: &str) -> Result<Self, RustBertError> {
+        model_type.ensure_enabled()?;
+        #[allow(unreachable_patterns)]
+        let config = match model_type {
+            #[cfg(feature = "bart")]
+            ModelType::Bart => ConfigOption::Bart(parse_config::<BartConfig>(json)?),
+            #[cfg(feature = "bert")]
             ModelType::Bert | ModelType::Roberta | ModelType::XLMRoberta => {
-                ConfigOption::Bert(BertConfig::from_file(path))
+                ConfigOption::Bert(parse_config::<BertConfig>(json)?)
+            }
+            #[cfg(feature = "distilbert")]
+            ModelType::DistilBert => {
+                ConfigOption::DistilBert(parse_config::<DistilBertConfig>(json)?)
+            }
+            #[cfg(feature = "electra")]
+            ModelType::Electra => ConfigOption::Electra(parse_config::<ElectraConfig>(json)?),
+            #[cfg(feature = "marian")]
+            ModelType::Marian => ConfigOption::Marian(parse_config::<BartConfig>(json)?),
+            #[cfg(feature = "mobilebert")]
+            ModelType::MobileBert => {
+                ConfigOption::MobileBert(parse_config::<MobileBertConfig>(json)?)
+            }
+            #[cfg(feature = "t5")]
+            ModelType::T5 => ConfigOption::T5(parse_config::<T5Config>(json)?),
+            #[cfg(feature = "albert")]
+            ModelType::Albert => ConfigOption::Albert(parse_config::<AlbertConfig>(json)?),
+            #[cfg(feature = "xlnet")]
+            ModelType::XLNet => ConfigOption::XLNet(parse_config::<XLNetConfig>(json)?),
+            #[cfg(feature = "gpt2")]
+            ModelType::GPT2 | ModelType::GPTNeo => {
+                ConfigOption::GPT2(parse_config::<Gpt2Config>(json)?)
+            }
+            #[cfg(feature = "gpt2")]
+            ModelType::OpenAiGpt => ConfigOption::GPT2(parse_config::<Gpt2Config>(json)?),
+            #[cfg(feature = "reformer")]
+            ModelType::Reformer => ConfigOption::Reformer(parse_config::<ReformerConfig>(json)?),
+            #[cfg(feature = "prophetnet")]
+            ModelType::ProphetNet => {
+                ConfigOption::ProphetNet(parse_config::<ProphetNetConfig>(json)?)
+            }
+            #[cfg(feature = "longformer")]
+            ModelType::Longformer => {
+                ConfigOption::Longformer(parse_config::<LongformerConfig>(json)?)
+            }
+            #[cfg(feature = "pegasus")]
+            ModelType::Pegasus => ConfigOption::Pegasus(parse_config::<PegasusConfig>(json)?),
+            #[cfg(feature = "mbart")]
+            ModelType::MBart => ConfigOption::MBart(parse_config::<MBartConfig>(json)?),
+            #[cfg(feature = "deberta")]
+            ModelType::Deberta => ConfigOption::Deberta(parse_config::<DebertaConfig>(json)?),
+            #[cfg(feature = "fnet")]
+            ModelType::FNet => ConfigOption::FNet(parse_config::<FNetConfig>(json)?),
+            _ => unreachable!("model type availability checked above"),
+        };
+        Ok(config)
+    }
+
+    /// Resolves the sequence length multiple to pad the inputs to. Longformer models pad their
+    /// inputs to a multiple of their attention window: the least common multiple of both values is
+    /// used so that the model does not need to pad the inputs further.
+    pub fn resolve_pad_to_multiple_of(&self, pad_to_multiple_of: Option<usize>) -> Option<usize> {
+        let pad_to_multiple_of = pad_to_multiple_of?;
+        match self {
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => {
+                let attention_window = config.attention_window.iter().max().copied().unwrap_or(1);
+                Some(least_common_multiple(
+                    pad_to_multiple_of,
+                    attention_window as usize,
+                ))
             }
-            ModelType::DistilBert => ConfigOption::DistilBert(DistilBertConfig::from_file(path)),
-            ModelType::Electra => ConfigOption::Electra(ElectraConfig::from_file(path)),
-            ModelType::Marian => ConfigOption::Marian(BartConfig::from_file(path)),
-            ModelType::MobileBert => ConfigOption::MobileBert(MobileBertConfig::from_file(path)),
-            ModelType::T5 => ConfigOption::T5(T5Config::from_file(path)),
-            ModelType::Albert => ConfigOption::Albert(AlbertConfig::from_file(path)),
-            ModelType::XLNet => ConfigOption::XLNet(XLNetConfig::from_file(path)),
-            ModelType::GPT2 | ModelType::GPTNeo => ConfigOption::GPT2(Gpt2Config::from_file(path)),
-            ModelType::OpenAiGpt => ConfigOption::GPT2(Gpt2Config::from_file(path)),
-            ModelType::Reformer => ConfigOption::Reformer(ReformerConfig::from_file(path)),
-            ModelType::ProphetNet => ConfigOption::ProphetNet(ProphetNetConfig::from_file(path)),
-            ModelType::Longformer => ConfigOption::Longformer(LongformerConfig::from_file(path)),
-            ModelType::Pegasus => ConfigOption::Pegasus(PegasusConfig::from_file(path)),
-            ModelType::MBart => ConfigOption::MBart(MBartConfig::from_file(path)),
+            _ => Some(pad_to_multiple_of),
+        }
+    }
+
+    /// Returns true if the configuration expects the output projection of language modeling
+    /// heads to be tied to the word embeddings
+    pub fn tie_word_embeddings(&self) -> bool {
+        match self {
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => config.tie_weights_,
+            _ => true,
+        }
+    }
+
+    /// Returns the name of the configuration variant, used in error messages
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "bart")]
+            Self::Bart(_) => "Bart",
+            #[cfg(feature = "bert")]
+            Self::Bert(_) => "Bert",
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(_) => "DistilBert",
+            #[cfg(feature = "electra")]
+            Self::Electra(_) => "Electra",
+            #[cfg(feature = "marian")]
+            Self::Marian(_) => "Marian",
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(_) => "MobileBert",
+            #[cfg(feature = "t5")]
+            Self::T5(_) => "T5",
+            #[cfg(feature = "albert")]
+            Self::Albert(_) => "Albert",
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(_) => "XLNet",
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(_) => "GPT2",
+            #[cfg(feature = "reformer")]
+            Self::Reformer(_) => "Reformer",
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(_) => "ProphetNet",
+            #[cfg(feature = "longformer")]
+            Self::Longformer(_) => "Longformer",
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(_) => "Pegasus",
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(_) => "GPTNeo",
+            #[cfg(feature = "mbart")]
+            Self::MBart(_) => "MBart",
+            #[cfg(feature = "deberta")]
+            Self::Deberta(_) => "Deberta",
+            #[cfg(feature = "fnet")]
+            Self::FNet(_) => "FNet",
+        }
+    }
+
+    /// Returns the mapping from label names to label ids (`label2id`) of the configuration, if any
+    pub fn get_label2id(&self) -> Option<&HashMap<String, i64>> {
+        match self {
+            #[cfg(feature = "bart")]
+            Self::Bart(config) => config.label2id.as_ref(),
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => config.label2id.as_ref(),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => config.label2id.as_ref(),
+            #[cfg(feature = "electra")]
+            Self::Electra(config) => config.label2id.as_ref(),
+            #[cfg(feature = "marian")]
+            Self::Marian(config) => config.label2id.as_ref(),
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(config) => config.label2id.as_ref(),
+            #[cfg(feature = "albert")]
+            Self::Albert(config) => config.label2id.as_ref(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(config) => config.label2id.as_ref(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(config) => config.label2id.as_ref(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(config) => config.label2id.as_ref(),
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => config.label2id.as_ref(),
+            #[cfg(feature = "mbart")]
+            Self::MBart(config) => config.label2id.as_ref(),
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => config.label2id.as_ref(),
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => config.label2id.as_ref(),
+            #[cfg(feature = "t5")]
+            Self::T5(_) => None,
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(_) => None,
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(_) => None,
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(_) => None,
+        }
+    }
+
+    /// Returns the mapping from label ids to label names (`id2label`) of the configuration, if any
+    pub fn get_id2label(&self) -> Option<&HashMap<i64, String>> {
+        match self {
+            #[cfg(feature = "bart")]
+            Self::Bart(config) => config.id2label.as_ref(),
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => config.id2label.as_ref(),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => config.id2label.as_ref(),
+            #[cfg(feature = "electra")]
+            Self::Electra(config) => config.id2label.as_ref(),
+            #[cfg(feature = "marian")]
+            Self::Marian(config) => config.id2label.as_ref(),
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(config) => config.id2label.as_ref(),
+            #[cfg(feature = "albert")]
+            Self::Albert(config) => config.id2label.as_ref(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(config) => config.id2label.as_ref(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(config) => config.id2label.as_ref(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(config) => config.id2label.as_ref(),
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => config.id2label.as_ref(),
+            #[cfg(feature = "mbart")]
+            Self::MBart(config) => config.id2label.as_ref(),
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => config.id2label.as_ref(),
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => config.id2label.as_ref(),
+            #[cfg(feature = "t5")]
+            Self::T5(_) => None,
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(_) => None,
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(_) => None,
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(_) => None,
+        }
+    }
+
+    /// Returns the training objective of the classification head (`problem_type`) of the configuration, if any
+    pub fn get_problem_type(&self) -> Option<ProblemType> {
+        match self {
+            #[cfg(feature = "bart")]
+            Self::Bart(config) => config.problem_type,
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => config.problem_type,
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => config.problem_type,
+            #[cfg(feature = "electra")]
+            Self::Electra(config) => config.problem_type,
+            #[cfg(feature = "marian")]
+            Self::Marian(config) => config.problem_type,
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(config) => config.problem_type,
+            #[cfg(feature = "albert")]
+            Self::Albert(config) => config.problem_type,
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(config) => config.problem_type,
+            #[cfg(feature = "reformer")]
+            Self::Reformer(config) => config.problem_type,
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(config) => config.problem_type,
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => config.problem_type,
+            #[cfg(feature = "mbart")]
+            Self::MBart(config) => config.problem_type,
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => config.problem_type,
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => config.problem_type,
+            #[cfg(feature = "t5")]
+            Self::T5(_) => None,
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(_) => None,
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(_) => None,
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(_) => None,
+        }
+    }
+
+    /// Returns the maximum number of positions of the configuration, or `None` for models using
+    /// relative positions only
+    pub fn max_position_embeddings(&self) -> Option<i64> {
+        match self {
+            #[cfg(feature = "bart")]
+            Self::Bart(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "bert")]
+            Self::Bert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "electra")]
+            Self::Electra(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "marian")]
+            Self::Marian(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "mobilebert")]
+            Self::MobileBert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "albert")]
+            Self::Albert(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(_) => None,
+            #[cfg(feature = "reformer")]
+            Self::Reformer(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "longformer")]
+            Self::Longformer(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "mbart")]
+            Self::MBart(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "t5")]
+            Self::T5(_) => None,
+            #[cfg(feature = "gpt2")]
+            Self::GPT2(config) => Some(config.n_positions),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(config) => Some(config.max_position_embeddings),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(config) => Some(config.max_position_embeddings),
         }
     }
 
     pub fn get_label_mapping(self) -> HashMap<i64, String> {
         match self {
+            #[cfg(feature = "bart")]
             Self::Bart(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "bert")]
             Self::Bert(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "electra")]
             Self::Electra(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "marian")]
             Self::Marian(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "albert")]
             Self::Albert(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "reformer")]
             Self::Reformer(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "longformer")]
             Self::Longformer(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "mbart")]
             Self::MBart(config) => config
                 .id2label
                 .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "deberta")]
+            Self::Deberta(config) => config
+                .id2label
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "fnet")]
+            Self::FNet(config) => config
+                .id2label
+                .expect("No label dictionary (id2label) provided in configuration file"),
+            #[cfg(feature = "t5")]
             Self::T5(_) => panic!("T5 does not use a label mapping"),
+            #[cfg(feature = "gpt2")]
             Self::GPT2(_) => panic!("GPT2 does not use a label mapping"),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(_) => panic!("GPT-Neo does not use a label mapping"),
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(_) => panic!("Pegasus does not use a label mapping"),
         }
     }
@@ -403,10 +849,57 @@ impl TokenizerOption {
                 }
                 TokenizerOption::MBart50(MBart50Tokenizer::from_file(vocab_path, lower_case)?)
             }
+            ModelType::Deberta => {
+                if add_prefix_space.is_some() {
+                    return Err(RustBertError::InvalidConfigurationError(
+                        format!("Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
+                                add_prefix_space.unwrap(),
+                                model_type)));
+                }
+                if strip_accents.is_some() {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents.unwrap(),
+                        model_type
+                    )));
+                }
+                TokenizerOption::Deberta(DeBERTaTokenizer::from_file(
+                    vocab_path,
+                    merges_path.expect("No merges specified!"),
+                    lower_case,
+                )?)
+            }
+            ModelType::FNet => {
+                if add_prefix_space.is_some() {
+                    return Err(RustBertError::InvalidConfigurationError(
+                        format!("Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
+                                add_prefix_space.unwrap(),
+                                model_type)));
+                }
+                TokenizerOption::FNet(FNetTokenizer::from_file(
+                    vocab_path,
+                    lower_case,
+                    strip_accents.unwrap_or(false),
+                )?)
+            }
         };
         Ok(tokenizer)
     }
 
+    /// Returns an `InvalidConfigurationError` if the tokenizer cannot be used by a model of type `model_type`
+    pub fn ensure_compatible(&self, model_type: ModelType) -> Result<(), RustBertError> {
+        if self.model_type() == model_type.tokenizer_type() {
+            Ok(())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "{:?} tokenizer cannot be used by a {:?} model, expected a {:?} tokenizer",
+                self.model_type(),
+                model_type,
+                model_type.tokenizer_type()
+            )))
+        }
+    }
+
     /// Returns the model type
     pub fn model_type(&self) -> ModelType {
         match *self {
@@ -423,6 +916,8 @@ impl TokenizerOption {
             Self::ProphetNet(_) => ModelType::ProphetNet,
             Self::Pegasus(_) => ModelType::Pegasus,
             Self::MBart50(_) => ModelType::MBart,
+            Self::Deberta(_) => ModelType::Deberta,
+            Self::FNet(_) => ModelType::FNet,
         }
     }
 
@@ -526,6 +1021,20 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
+            Self::Deberta(ref tokenizer) => MultiThreadedTokenizer::encode_list(
+                tokenizer,
+                text_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
+            Self::FNet(ref tokenizer) => MultiThreadedTokenizer::encode_list(
+                tokenizer,
+                text_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
         }
     }
 
@@ -629,6 +1138,20 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
+            Self::Deberta(ref tokenizer) => MultiThreadedTokenizer::encode_pair_list(
+                tokenizer,
+                text_pair_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
+            Self::FNet(ref tokenizer) => MultiThreadedTokenizer::encode_pair_list(
+                tokenizer,
+                text_pair_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
         }
     }
 
@@ -681,6 +1204,12 @@ impl TokenizerOption {
             Self::MBart50(ref tokenizer) => {
                 tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
             }
+            Self::Deberta(ref tokenizer) => {
+                tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
+            }
+            Self::FNet(ref tokenizer) => {
+                tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
+            }
         }
     }
 
@@ -700,6 +1229,8 @@ impl TokenizerOption {
             Self::ProphetNet(ref tokenizer) => tokenizer.tokenize(text),
             Self::Pegasus(ref tokenizer) => tokenizer.tokenize(text),
             Self::MBart50(ref tokenizer) => tokenizer.tokenize(text),
+            Self::Deberta(ref tokenizer) => tokenizer.tokenize(text),
+            Self::FNet(ref tokenizer) => tokenizer.tokenize(text),
         }
     }
 
@@ -719,6 +1250,8 @@ impl TokenizerOption {
             Self::ProphetNet(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::Pegasus(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::MBart50(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
+            Self::Deberta(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
+            Self::FNet(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
         }
     }
 
@@ -744,9 +1277,17 @@ impl TokenizerOption {
             }
             Self::Pegasus(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
             Self::MBart50(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
+            Self::Deberta(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
+            Self::FNet(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
         }
     }
 
+    /// Returns the number of tokens of a text, excluding the special tokens added when encoding it.
+    /// Cheaper than encoding the text: no token ids, offsets or masks are built.
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.tokenize(text).len()
+    }
+
     /// Interface method to decoding
     pub fn decode(
         &self,
@@ -755,45 +1296,81 @@ impl TokenizerOption {
         clean_up_tokenization_spaces: bool,
     ) -> String {
         match *self {
-            Self::Bert(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::Roberta(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::Marian(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::T5(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::XLMRoberta(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::Albert(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::XLNet(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::GPT2(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::OpenAiGpt(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::Reformer(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::ProphetNet(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::Pegasus(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
-            Self::MBart50(ref tokenizer) => {
-                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
-            }
+            Self::Bert(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Roberta(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Marian(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::T5(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::XLMRoberta(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Albert(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::XLNet(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::GPT2(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::OpenAiGpt(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Reformer(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::ProphetNet(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Pegasus(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::MBart50(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::Deberta(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
+            Self::FNet(ref tokenizer) => tokenizer.decode(
+                &token_ids,
+                skip_special_tokens,
+                clean_up_tokenization_spaces,
+            ),
         }
     }
 
@@ -856,6 +1433,14 @@ impl TokenizerOption {
                 token_ids_with_offsets_1,
                 token_ids_with_offsets_2,
             ),
+            Self::Deberta(ref tokenizer) => tokenizer.build_input_with_special_tokens(
+                token_ids_with_offsets_1,
+                token_ids_with_offsets_2,
+            ),
+            Self::FNet(ref tokenizer) => tokenizer.build_input_with_special_tokens(
+                token_ids_with_offsets_1,
+                token_ids_with_offsets_2,
+            ),
         };
         TokenizedInput {
             token_ids: token_ids_with_special_tokens.token_ids,
@@ -876,19 +1461,21 @@ impl TokenizerOption {
         ST: AsRef<str>,
     {
         match *self {
-            Self::Bert(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::Roberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::Marian(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::T5(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::XLMRoberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::Albert(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::XLNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::GPT2(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::OpenAiGpt(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::Reformer(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::ProphetNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::Pegasus(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::MBart50(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
+            Self::Bert(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Roberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Marian(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::T5(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::XLMRoberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Albert(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::XLNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::GPT2(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::OpenAiGpt(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Reformer(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::ProphetNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Pegasus(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::MBart50(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::Deberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
+            Self::FNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens.as_ref()),
         }
     }
 
@@ -947,6 +1534,14 @@ impl TokenizerOption {
                 .special_values
                 .get(MBart50Vocab::unknown_value())
                 .expect("UNK token not found in vocabulary"),
+            Self::Deberta(ref tokenizer) => *MultiThreadedTokenizer::vocab(tokenizer)
+                .special_values
+                .get(DeBERTaVocab::unknown_value())
+                .expect("UNK token not found in vocabulary"),
+            Self::FNet(ref tokenizer) => *MultiThreadedTokenizer::vocab(tokenizer)
+                .special_values
+                .get(FNetVocab::unknown_value())
+                .expect("UNK token not found in vocabulary"),
         }
     }
 
@@ -1013,6 +1608,18 @@ impl TokenizerOption {
                     .get(MBart50Vocab::pad_value())
                     .expect("PAD token not found in vocabulary"),
             ),
+            Self::Deberta(ref tokenizer) => Some(
+                *MultiThreadedTokenizer::vocab(tokenizer)
+                    .special_values
+                    .get(DeBERTaVocab::pad_value())
+                    .expect("PAD token not found in vocabulary"),
+            ),
+            Self::FNet(ref tokenizer) => Some(
+                *MultiThreadedTokenizer::vocab(tokenizer)
+                    .special_values
+                    .get(FNetVocab::pad_value())
+                    .expect("PAD token not found in vocabulary"),
+            ),
             Self::Reformer(_) => None,
             Self::GPT2(_) => None,
             Self::OpenAiGpt(_) => None,
@@ -1064,6 +1671,18 @@ impl TokenizerOption {
                     .get(MBart50Vocab::sep_value())
                     .expect("SEP token not found in vocabulary"),
             ),
+            Self::Deberta(ref tokenizer) => Some(
+                *MultiThreadedTokenizer::vocab(tokenizer)
+                    .special_values
+                    .get(DeBERTaVocab::sep_value())
+                    .expect("SEP token not found in vocabulary"),
+            ),
+            Self::FNet(ref tokenizer) => Some(
+                *MultiThreadedTokenizer::vocab(tokenizer)
+                    .special_values
+                    .get(FNetVocab::sep_value())
+                    .expect("SEP token not found in vocabulary"),
+            ),
             Self::Marian(_) => None,
             Self::T5(_) => None,
             Self::GPT2(_) => None,
@@ -1073,3 +1692,489 @@ impl TokenizerOption {
         }
     }
 }
+
+fn parse_config<T>(json: &str) -> Result<T, RustBertError>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    serde_json::from_str(json).map_err(|error| {
+        RustBertError::InvalidConfigurationError(format!(
+            "Could not parse the model configuration: {}",
+            error
+        ))
+    })
+}
+
+/// Returns the error reported when a pipeline receives a configuration that does not match its
+/// model type, naming the expected and received configuration variants.
+pub(crate) fn config_mismatch_error(
+    pipeline: &str,
+    model_type: ModelType,
+    expected_variant: &str,
+    config: &ConfigOption,
+) -> RustBertError {
+    RustBertError::InvalidConfigurationError(format!(
+        "{} pipeline: {:?} models expect a ConfigOption::{} configuration but received a \
+        ConfigOption::{}. Check that the configuration resource points to the config.json of a {:?} model.",
+        pipeline,
+        model_type,
+        expected_variant,
+        config.variant_name(),
+        model_type
+    ))
+}
+
+/// Extracts the configuration of the variant expected for a model type from a `ConfigOption`,
+/// returning an `InvalidConfigurationError` naming the pipeline, the expected and the received
+/// configuration variants otherwise.
+///
+/// `expect_config!(config, ModelType::XLNet, ConfigOption::XLNet, "Sequence classification")`
+macro_rules! expect_config {
+    ($config:expr, $model_type:expr, ConfigOption::$variant:ident, $pipeline:expr) => {
+        match $config {
+            $crate::pipelines::common::ConfigOption::$variant(config) => Ok(config),
+            #[allow(unreachable_patterns)]
+            other => Err($crate::pipelines::common::config_mismatch_error(
+                $pipeline,
+                $model_type,
+                stringify!($variant),
+                other,
+            )),
+        }
+    };
+}
+
+pub(crate) use expect_config;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # Handling of inputs exceeding the maximum sequence length
+pub enum OverflowStrategy {
+    /// Truncate the inputs to the maximum sequence length
+    Truncate,
+    /// Reject the inputs with a `RustBertError::InputTooLong` error
+    Error,
+}
+
+impl Default for OverflowStrategy {
+    fn default() -> OverflowStrategy {
+        OverflowStrategy::Truncate
+    }
+}
+
+/// Returns the number of special tokens added to a single input
+pub(crate) fn single_special_tokens(tokenizer: &TokenizerOption) -> usize {
+    tokenizer
+        .build_input_with_special_tokens(
+            TokenIdsWithOffsets {
+                ids: vec![],
+                offsets: vec![],
+                reference_offsets: vec![],
+                masks: vec![],
+            },
+            None,
+        )
+        .token_ids
+        .len()
+}
+
+/// Returns the label names of the classifier outputs: the `label_mapping` override if provided,
+/// checked against the number of classes of the classifier head, or the id2label mapping of the
+/// model configuration.
+pub(crate) fn resolve_label_mapping(
+    model_config: &ConfigOption,
+    label_mapping: Option<&HashMap<i64, String>>,
+) -> Result<HashMap<i64, String>, RustBertError> {
+    let id2label = model_config.get_id2label().ok_or_else(|| {
+        RustBertError::InvalidConfigurationError(
+            "No label dictionary (id2label) provided in configuration file".to_string(),
+        )
+    })?;
+    let label_mapping = match label_mapping {
+        Some(label_mapping) => label_mapping,
+        None => return Ok(id2label.clone()),
+    };
+    let num_labels = id2label.len() as i64;
+    let missing_ids = (0..num_labels)
+        .filter(|id| !label_mapping.contains_key(id))
+        .collect::<Vec<i64>>();
+    if !missing_ids.is_empty() {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The label mapping does not cover the class indices {:?} of the classifier head ({} labels)",
+            missing_ids, num_labels
+        )));
+    }
+    let mut extra_ids = label_mapping
+        .keys()
+        .filter(|id| !(0..num_labels).contains(*id))
+        .copied()
+        .collect::<Vec<i64>>();
+    if !extra_ids.is_empty() {
+        extra_ids.sort_unstable();
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The label mapping contains the indices {:?}, outside of the classifier head ({} labels)",
+            extra_ids, num_labels
+        )));
+    }
+    Ok(label_mapping.clone())
+}
+
+/// Returns an `InputTooLong` error if an input of `tokens` tokens does not fit within `limit`
+/// tokens alongside the tokens reserved for the hypothesis and special tokens.
+pub(crate) fn check_input_length(
+    input_index: usize,
+    tokens: usize,
+    limit: usize,
+    reserved_for_hypothesis: usize,
+    reserved_special: usize,
+) -> Result<(), RustBertError> {
+    if tokens + reserved_for_hypothesis + reserved_special > limit {
+        Err(RustBertError::InputTooLong {
+            input_index,
+            tokens,
+            limit,
+            reserved_for_hypothesis,
+            reserved_special,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the sequence length a batch with a maximum length of `max_len` should be padded to,
+/// rounded up to the next multiple of `pad_to_multiple_of` if provided.
+pub fn get_padded_length(max_len: usize, pad_to_multiple_of: Option<usize>) -> usize {
+    match pad_to_multiple_of {
+        Some(multiple) if multiple > 1 => (max_len + multiple - 1) / multiple * multiple,
+        _ => max_len,
+    }
+}
+
+/// Returns an attention mask masking the positions added when padding a batch of sequences with a
+/// maximum length of `max_len` up to `padded_len`, or `None` if no position was added.
+pub(crate) fn get_extra_padding_mask(
+    batch_size: usize,
+    max_len: usize,
+    padded_len: usize,
+    device: Device,
+) -> Option<Tensor> {
+    if padded_len <= max_len {
+        return None;
+    }
+    let mut mask = vec![1i64; max_len];
+    mask.extend(vec![0i64; padded_len - max_len]);
+    Some(
+        Tensor::of_slice(&mask)
+            .to_kind(Kind::Int64)
+            .unsqueeze(0)
+            .repeat(&[batch_size as i64, 1])
+            .to(device),
+    )
+}
+
+/// Converts a character offset, as returned in the offsets of the pipelines outputs, to a byte
+/// offset in `text` (e.g. to slice the `&str` directly). The character offset may be equal to the
+/// number of characters of the text (end of an exclusive range). Returns `None` if it is out of
+/// the bounds of the text.
+pub fn char_to_byte(text: &str, char_index: usize) -> Option<usize> {
+    text.char_indices()
+        .map(|(byte_index, _)| byte_index)
+        .chain(std::iter::once(text.len()))
+        .nth(char_index)
+}
+
+/// Converts a character offset, as returned in the offsets of the pipelines outputs, to an offset
+/// in UTF-16 code units, as used by JavaScript strings. Characters outside of the basic
+/// multilingual plane (e.g. most emoji) are 2 UTF-16 code units long. Returns `None` if the
+/// character offset is out of the bounds of the text.
+pub fn char_to_utf16(text: &str, char_index: usize) -> Option<usize> {
+    let mut utf16_index = 0;
+    let mut chars = text.chars();
+    for _ in 0..char_index {
+        utf16_index += chars.next()?.len_utf16();
+    }
+    Some(utf16_index)
+}
+
+/// Splits a text into sentences on terminal punctuation (`.`, `!`, `?`) followed by a whitespace.
+/// Closing quotes and brackets following the punctuation are kept with the sentence. Abbreviations
+/// followed by a whitespace (e.g. "Dr. Smith") end a sentence, decimal numbers (e.g. "3.5") do not.
+pub(crate) fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, character)) = chars.next() {
+        if !matches!(character, '.' | '!' | '?') {
+            continue;
+        }
+        let mut end = position + character.len_utf8();
+        while let Some(&(next_position, next_character)) = chars.peek() {
+            if matches!(next_character, '.' | '!' | '?' | '"' | '\'' | ')' | ']') {
+                end = next_position + next_character.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if chars
+            .peek()
+            .map_or(true, |(_, next_character)| next_character.is_whitespace())
+        {
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+    }
+    let remainder = text[start..].trim();
+    if !remainder.is_empty() {
+        sentences.push(remainder);
+    }
+    sentences
+}
+
+fn greatest_common_divisor(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        greatest_common_divisor(b, a % b)
+    }
+}
+
+fn least_common_multiple(a: usize, b: usize) -> usize {
+    if (a == 0) | (b == 0) {
+        a.max(b)
+    } else {
+        a / greatest_common_divisor(a, b) * b
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_input_length() {
+        assert!(check_input_length(0, 120, 128, 6, 2).is_ok());
+        let error = check_input_length(3, 121, 128, 6, 2).unwrap_err();
+        assert!(matches!(
+            error,
+            RustBertError::InputTooLong {
+                input_index: 3,
+                tokens: 121,
+                limit: 128,
+                reserved_for_hypothesis: 6,
+                reserved_special: 2,
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Input too long error: input 3 has 121 tokens, exceeding the limit of 128 tokens \
+             (6 reserved for the hypothesis, 2 reserved for special tokens)"
+        );
+    }
+
+    #[test]
+    fn test_char_offset_conversions() {
+        //    Emoji with a variation selector, zero-width joiner sequence and combining mark
+        let text = "a\u{2764}\u{fe0f}b\u{1f469}\u{200d}\u{1f52c}e\u{301}";
+        let chars = text.chars().collect::<Vec<char>>();
+
+        for char_index in 0..=chars.len() {
+            let byte_index = char_to_byte(text, char_index).unwrap();
+            let expected: String = chars[char_index..].iter().collect();
+            assert_eq!(&text[byte_index..], expected);
+
+            let utf16_index = char_to_utf16(text, char_index).unwrap();
+            let prefix: String = chars[..char_index].iter().collect();
+            assert_eq!(utf16_index, prefix.encode_utf16().count());
+        }
+        assert_eq!(char_to_byte(text, 4), Some(8));
+        assert_eq!(char_to_utf16(text, 5), Some(6));
+        assert_eq!(char_to_byte(text, chars.len() + 1), None);
+        assert_eq!(char_to_utf16(text, chars.len() + 1), None);
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let text = " Scientists found water on K2-18b. \"It's the best candidate,\" he said. Is it 3.5 times larger?";
+        assert_eq!(
+            split_sentences(text),
+            [
+                "Scientists found water on K2-18b.",
+                "\"It's the best candidate,\" he said.",
+                "Is it 3.5 times larger?",
+            ]
+        );
+        assert!(split_sentences("  ").is_empty());
+        //    Abbreviations are not recognized
+        assert_eq!(
+            split_sentences("Dr. Smith weighed 3.5 kg."),
+            ["Dr.", "Smith weighed 3.5 kg."]
+        );
+        assert_eq!(
+            split_sentences("No terminal punctuation"),
+            ["No terminal punctuation"]
+        );
+    }
+
+    #[test]
+    fn test_padded_length() {
+        assert_eq!(get_padded_length(13, None), 13);
+        assert_eq!(get_padded_length(13, Some(1)), 13);
+        assert_eq!(get_padded_length(13, Some(8)), 16);
+        assert_eq!(get_padded_length(16, Some(8)), 16);
+        assert_eq!(get_padded_length(0, Some(8)), 0);
+    }
+
+    #[test]
+    fn test_least_common_multiple() {
+        assert_eq!(least_common_multiple(8, 512), 512);
+        assert_eq!(least_common_multiple(6, 8), 24);
+        assert_eq!(least_common_multiple(8, 0), 8);
+    }
+
+    #[test]
+    fn test_extra_padding_mask() {
+        assert!(get_extra_padding_mask(2, 13, 13, Device::Cpu).is_none());
+        let mask = get_extra_padding_mask(2, 13, 16, Device::Cpu).unwrap();
+        assert_eq!(mask.size(), vec![2, 16]);
+        assert_eq!(mask.sum(Kind::Int64).int64_value(&[]), 26);
+        assert_eq!(mask.int64_value(&[1, 12]), 1);
+        assert_eq!(mask.int64_value(&[1, 13]), 0);
+    }
+
+    #[cfg(all(feature = "all-models", feature = "all-pipelines"))]
+    #[test]
+    fn test_config_mismatch_errors() -> anyhow::Result<()> {
+        use crate::pipelines::feature_extraction::FeatureExtractionOption;
+        use crate::pipelines::question_answering::QuestionAnsweringOption;
+        use crate::pipelines::sequence_classification::SequenceClassificationOption;
+        use crate::pipelines::token_classification::TokenClassificationOption;
+        use crate::pipelines::zero_shot_classification::ZeroShotClassificationOption;
+        use tch::nn::VarStore;
+
+        let bert_config = ConfigOption::try_from_str(
+            ModelType::Bert,
+            r#"{"hidden_act": "gelu", "attention_probs_dropout_prob": 0.1, "hidden_dropout_prob": 0.1, "hidden_size": 8, "initializer_range": 0.02, "intermediate_size": 16, "max_position_embeddings": 16, "num_attention_heads": 2, "num_hidden_layers": 1, "type_vocab_size": 2, "vocab_size": 16}"#,
+        )?;
+        let distilbert_config = ConfigOption::try_from_str(
+            ModelType::DistilBert,
+            r#"{"activation": "gelu", "attention_dropout": 0.1, "dim": 8, "dropout": 0.1, "hidden_dim": 16, "initializer_range": 0.02, "max_position_embeddings": 16, "n_heads": 2, "n_layers": 1, "qa_dropout": 0.1, "seq_classif_dropout": 0.2, "sinusoidal_pos_embds": false, "tie_weights_": false, "vocab_size": 16}"#,
+        )?;
+        let vs = VarStore::new(Device::Cpu);
+        let p = vs.root();
+
+        type Constructor<'a> =
+            Box<dyn Fn(ModelType, &ConfigOption) -> Result<(), RustBertError> + 'a>;
+        let pipelines: Vec<(&str, Constructor, Vec<(ModelType, &str)>)> = vec![
+            (
+                "Sequence classification",
+                Box::new(|model_type, config| {
+                    SequenceClassificationOption::new(model_type, &p, config).map(|_| ())
+                }),
+                vec![
+                    (ModelType::Bert, "Bert"),
+                    (ModelType::DistilBert, "DistilBert"),
+                    (ModelType::MobileBert, "MobileBert"),
+                    (ModelType::Roberta, "Bert"),
+                    (ModelType::XLMRoberta, "Bert"),
+                    (ModelType::Albert, "Albert"),
+                    (ModelType::XLNet, "XLNet"),
+                    (ModelType::Bart, "Bart"),
+                    (ModelType::Reformer, "Reformer"),
+                    (ModelType::Longformer, "Longformer"),
+                ],
+            ),
+            (
+                "Zero-shot classification",
+                Box::new(|model_type, config| {
+                    ZeroShotClassificationOption::new(model_type, &p, config).map(|_| ())
+                }),
+                vec![
+                    (ModelType::Bart, "Bart"),
+                    (ModelType::Bert, "Bert"),
+                    (ModelType::DistilBert, "DistilBert"),
+                    (ModelType::MobileBert, "MobileBert"),
+                    (ModelType::Roberta, "Bert"),
+                    (ModelType::XLMRoberta, "Bert"),
+                    (ModelType::Albert, "Albert"),
+                    (ModelType::XLNet, "XLNet"),
+                    (ModelType::Longformer, "Longformer"),
+                ],
+            ),
+            (
+                "Token classification",
+                Box::new(|model_type, config| {
+                    TokenClassificationOption::new(model_type, &p, config).map(|_| ())
+                }),
+                vec![
+                    (ModelType::Bert, "Bert"),
+                    (ModelType::DistilBert, "DistilBert"),
+                    (ModelType::MobileBert, "MobileBert"),
+                    (ModelType::Roberta, "Bert"),
+                    (ModelType::XLMRoberta, "Bert"),
+                    (ModelType::Electra, "Electra"),
+                    (ModelType::Albert, "Albert"),
+                    (ModelType::XLNet, "XLNet"),
+                    (ModelType::Longformer, "Longformer"),
+                ],
+            ),
+            (
+                "Question answering",
+                Box::new(|model_type, config| {
+                    QuestionAnsweringOption::new(model_type, &p, config).map(|_| ())
+                }),
+                vec![
+                    (ModelType::Bert, "Bert"),
+                    (ModelType::DistilBert, "DistilBert"),
+                    (ModelType::MobileBert, "MobileBert"),
+                    (ModelType::Roberta, "Bert"),
+                    (ModelType::XLMRoberta, "Bert"),
+                    (ModelType::Albert, "Albert"),
+                    (ModelType::XLNet, "XLNet"),
+                    (ModelType::Reformer, "Reformer"),
+                    (ModelType::Longformer, "Longformer"),
+                ],
+            ),
+            (
+                "Feature extraction",
+                Box::new(|model_type, config| {
+                    FeatureExtractionOption::new(model_type, &p, config).map(|_| ())
+                }),
+                vec![
+                    (ModelType::Bert, "Bert"),
+                    (ModelType::Roberta, "Bert"),
+                    (ModelType::XLMRoberta, "Bert"),
+                    (ModelType::DistilBert, "DistilBert"),
+                ],
+            ),
+        ];
+
+        for (pipeline, constructor, model_types) in pipelines.iter() {
+            for &(model_type, expected_variant) in model_types.iter() {
+                let (config, received_variant) = if expected_variant == "DistilBert" {
+                    (&bert_config, "Bert")
+                } else {
+                    (&distilbert_config, "DistilBert")
+                };
+                let message = match constructor(model_type, config) {
+                    Err(RustBertError::InvalidConfigurationError(message)) => message,
+                    _ => panic!(
+                        "{} accepted a mismatched configuration for {:?}",
+                        pipeline, model_type
+                    ),
+                };
+                assert!(
+                    message.starts_with(&format!(
+                        "{} pipeline: {:?} models expect a ConfigOption::{} configuration but received a ConfigOption::{}.",
+                        pipeline, model_type, expected_variant, received_variant
+                    )),
+                    "{}",
+                    message
+                );
+            }
+        }
+        Ok(())
+    }
+}
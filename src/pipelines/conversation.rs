@@ -45,13 +45,16 @@
 //! The authors of this repository are not responsible for any generation
 //! from the 3rd party utilization of the pretrained system.
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
 use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{
     GPT2Generator, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::generation_utils::{DecodingStrategy, GenerateConfig, LanguageGenerator};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tch::{Device, Kind, Tensor};
 use uuid::Uuid;
@@ -100,6 +103,8 @@ pub struct ConversationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Explicit decoding strategy. If provided, takes precedence over `do_sample`, `num_beams`, `temperature`, `top_k` and `top_p` (default: None)
+    pub decoding_strategy: Option<DecodingStrategy>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -135,6 +140,7 @@ impl Default for ConversationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
             device: Device::cuda_if_available(),
         }
     }
@@ -161,12 +167,15 @@ impl From<ConversationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            decoding_strategy: config.decoding_strategy,
+            decoder_step_hook: None,
             device: config.device,
         }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Data structure keeping track of a conversation in the system. It contains past user inputs and
 /// generated answers, a history of the tokens generated and a placeholder for new user inputs to be
 /// processed by the system if submitted for prediction
@@ -763,6 +772,7 @@ impl ConversationModel {
     pub fn new(
         conversation_config: ConversationConfig,
     ) -> Result<ConversationModel, RustBertError> {
+        ensure_initialized();
         let max_allowed_length =
             conversation_config.max_length - conversation_config.min_length_for_response;
         let device = conversation_config.device;
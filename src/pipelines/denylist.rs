@@ -0,0 +1,341 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Denylist filter for generated text
+//! Banning token ids during generation does not prevent a model from producing a forbidden term
+//! through a different combination of sub-tokens. The `DenyList` scans the decoded output of the
+//! generation pipelines for the forbidden terms and either redacts them, or regenerates the
+//! offending output with the token sequences spelling the matched terms banned.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::denylist::{DenyList, DenyListPolicy};
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//!
+//! let deny_list = DenyList::new(
+//!     &["darn", "heck"],
+//!     true,
+//!     DenyListPolicy::Regenerate { retries: 3 },
+//! )?;
+//! let model = TextGenerationModel::new(TextGenerationConfig {
+//!     output_denylist: Some(deny_list),
+//!     ..Default::default()
+//! })?;
+//! let output = model.generate(&["The dog"], None);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::TokenizerOption;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use std::ops::Range;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Action taken when a generated output contains a denied term
+pub enum DenyListPolicy {
+    /// Replace every character of the matched terms by `*`
+    Redact,
+    /// Regenerate the offending output with the token sequences of the matched terms banned.
+    /// If the output still contains a denied term after `retries` attempts, it is redacted.
+    Regenerate { retries: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// # Occurrence of a denied term in a text
+pub struct DenyListMatch {
+    /// Denied term matched
+    pub term: String,
+    /// Start byte offset of the match in the text
+    pub start: usize,
+    /// End byte offset (exclusive) of the match in the text
+    pub end: usize,
+}
+
+/// # Denylist of terms that may not appear in generated text
+#[derive(Clone, Debug)]
+pub struct DenyList {
+    terms: Vec<String>,
+    normalize: bool,
+    policy: DenyListPolicy,
+    matcher: AhoCorasick,
+}
+
+impl DenyList {
+    /// Build a new `DenyList`
+    ///
+    /// # Arguments
+    ///
+    /// * `terms` - Terms that may not appear in the generated text
+    /// * `normalize` - if true, the matching is case and diacritic insensitive (`Élan` matches `elan`)
+    /// * `policy` - `DenyListPolicy` action to take when an output contains a denied term
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::denylist::{DenyList, DenyListPolicy};
+    ///
+    /// let deny_list = DenyList::new(&["darn"], true, DenyListPolicy::Redact)?;
+    /// assert_eq!(deny_list.redact("Oh DÀRN it"), "Oh **** it");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<S: AsRef<str>>(
+        terms: &[S],
+        normalize: bool,
+        policy: DenyListPolicy,
+    ) -> Result<DenyList, RustBertError> {
+        let terms = terms
+            .iter()
+            .map(|term| term.as_ref().to_string())
+            .collect::<Vec<String>>();
+        if terms.iter().any(|term| term.is_empty()) {
+            return Err(RustBertError::ValueError(
+                "Denylist terms may not be empty".to_string(),
+            ));
+        }
+        let patterns = if normalize {
+            terms
+                .iter()
+                .map(|term| normalize_with_offsets(term).0)
+                .collect()
+        } else {
+            terms.clone()
+        };
+        let matcher = AhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(&patterns);
+        Ok(DenyList {
+            terms,
+            normalize,
+            policy,
+            matcher,
+        })
+    }
+
+    /// Returns the `DenyListPolicy` of this denylist
+    pub fn policy(&self) -> DenyListPolicy {
+        self.policy
+    }
+
+    /// Finds the non-overlapping occurrences of the denied terms in a text, preferring the longest
+    /// term when several terms match at the same position.
+    pub fn find_matches(&self, text: &str) -> Vec<DenyListMatch> {
+        if self.normalize {
+            let (normalized, offsets) = normalize_with_offsets(text);
+            self.matcher
+                .find_iter(&normalized)
+                .map(|found| DenyListMatch {
+                    term: self.terms[found.pattern()].clone(),
+                    start: offsets[found.start()].start,
+                    end: offsets[found.end() - 1].end,
+                })
+                .collect()
+        } else {
+            self.matcher
+                .find_iter(text)
+                .map(|found| DenyListMatch {
+                    term: self.terms[found.pattern()].clone(),
+                    start: found.start(),
+                    end: found.end(),
+                })
+                .collect()
+        }
+    }
+
+    /// Replaces every character of the denied terms found in the text by `*`
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut position = 0;
+        for found in self.find_matches(text) {
+            redacted.push_str(&text[position..found.start]);
+            redacted.extend(text[found.start..found.end].chars().map(|_| '*'));
+            position = found.end;
+        }
+        redacted.push_str(&text[position..]);
+        redacted
+    }
+
+    /// Applies the denylist policy to generated outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `outputs` - generated outputs to filter
+    /// * `tokenizer` - tokenizer used to convert the matched terms into token sequences to ban
+    /// * `regenerate` - function regenerating an output given its index, the current output, the start byte offset of the
+    /// first denied term in this output and the sequences to ban. Returns `None` if the output cannot be regenerated.
+    pub(crate) fn filter_outputs<F>(
+        &self,
+        outputs: Vec<String>,
+        tokenizer: &TokenizerOption,
+        regenerate: F,
+    ) -> Vec<String>
+    where
+        F: Fn(usize, &str, usize, &BannedSequences) -> Option<String>,
+    {
+        match self.policy {
+            DenyListPolicy::Redact => outputs.iter().map(|output| self.redact(output)).collect(),
+            DenyListPolicy::Regenerate { retries } => outputs
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut output)| {
+                    let mut banned_sequences = BannedSequences::default();
+                    for _ in 0..retries {
+                        let matches = self.find_matches(&output);
+                        if matches.is_empty() {
+                            break;
+                        }
+                        for found in matches.iter() {
+                            banned_sequences.add_text(tokenizer, &output[found.start..found.end]);
+                            banned_sequences.add_text(tokenizer, &found.term);
+                        }
+                        match regenerate(index, &output, matches[0].start, &banned_sequences) {
+                            Some(regenerated) => output = regenerated,
+                            None => break,
+                        }
+                    }
+                    self.redact(&output)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// # Token sequences that may not be generated
+pub struct BannedSequences {
+    sequences: Vec<Vec<i64>>,
+}
+
+impl BannedSequences {
+    /// Bans a token sequence
+    pub fn add_ids(&mut self, token_ids: Vec<i64>) {
+        if !token_ids.is_empty() && !self.sequences.contains(&token_ids) {
+            self.sequences.push(token_ids);
+        }
+    }
+
+    /// Bans the token sequences of a text, both at the start of a word and following a space
+    pub fn add_text(&mut self, tokenizer: &TokenizerOption, text: &str) {
+        for variant in [text.to_string(), format!(" {}", text)].iter() {
+            let tokens = tokenizer.tokenize(variant);
+            self.add_ids(tokenizer.convert_tokens_to_ids(&tokens));
+        }
+    }
+
+    /// Returns the token ids completing a banned sequence after the previous tokens
+    pub fn banned_tokens(&self, previous_token_ids: &[i64]) -> Vec<i64> {
+        self.sequences
+            .iter()
+            .filter(|sequence| previous_token_ids.ends_with(&sequence[..sequence.len() - 1]))
+            .map(|sequence| sequence[sequence.len() - 1])
+            .collect()
+    }
+
+    /// Returns the token ids of the vocabulary that may follow the previous tokens
+    pub fn allowed_tokens(&self, previous_token_ids: &[i64], vocab_size: i64) -> Vec<i64> {
+        let banned_tokens = self.banned_tokens(previous_token_ids);
+        (0..vocab_size)
+            .filter(|token_id| !banned_tokens.contains(token_id))
+            .collect()
+    }
+}
+
+/// Lower cases the text and strips its diacritics, returning the normalized text and for each of
+/// its bytes the byte range of the original character.
+fn normalize_with_offsets(text: &str) -> (String, Vec<Range<usize>>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len());
+    for (start, character) in text.char_indices() {
+        let end = start + character.len_utf8();
+        decompose_canonical(character, |decomposed| {
+            if !is_combining_mark(decomposed) {
+                for lower_case in decomposed.to_lowercase() {
+                    normalized.push(lower_case);
+                    offsets.extend(std::iter::repeat(start..end).take(lower_case.len_utf8()));
+                }
+            }
+        });
+    }
+    (normalized, offsets)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() -> anyhow::Result<()> {
+        let deny_list = DenyList::new(&["cat", "category"], false, DenyListPolicy::Redact)?;
+        let matches = deny_list.find_matches("A category of Cats and a cat.");
+        assert_eq!(
+            matches,
+            vec![
+                DenyListMatch {
+                    term: "category".to_string(),
+                    start: 2,
+                    end: 10
+                },
+                DenyListMatch {
+                    term: "cat".to_string(),
+                    start: 25,
+                    end: 28
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_matches_normalized() -> anyhow::Result<()> {
+        let deny_list = DenyList::new(&["creme brulee"], true, DenyListPolicy::Redact)?;
+        let text = "Une CRÈME brûlée, s'il vous plaît";
+        let matches = deny_list.find_matches(text);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(&text[matches[0].start..matches[0].end], "CRÈME brûlée");
+
+        let decomposed = "cre\u{0300}me brule\u{0301}e";
+        let matches = deny_list.find_matches(decomposed);
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].start, matches[0].end), (0, decomposed.len()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact() -> anyhow::Result<()> {
+        let deny_list = DenyList::new(&["darn", "heck"], true, DenyListPolicy::Redact)?;
+        assert_eq!(
+            deny_list.redact("Darn, what the HÉCK. Nothing to see here."),
+            "****, what the ****. Nothing to see here."
+        );
+        assert_eq!(deny_list.redact("Clean text"), "Clean text");
+        assert!(DenyList::new(&[""], true, DenyListPolicy::Redact).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_banned_sequences() {
+        let mut banned_sequences = BannedSequences::default();
+        banned_sequences.add_ids(vec![5, 6, 7]);
+        banned_sequences.add_ids(vec![9]);
+        banned_sequences.add_ids(vec![9]);
+        banned_sequences.add_ids(vec![]);
+
+        assert_eq!(banned_sequences.banned_tokens(&[1, 2]), vec![9]);
+        assert_eq!(banned_sequences.banned_tokens(&[1, 5, 6]), vec![7, 9]);
+        assert_eq!(
+            banned_sequences.allowed_tokens(&[5, 6], 10),
+            vec![0, 1, 2, 3, 4, 5, 6, 8]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Export of pipeline outputs to Arrow and Parquet
+//! Converts the outputs of the pipelines to Arrow record batches, that can be written to Parquet
+//! files with `write_parquet` (requires the `arrow` feature). The conversion does not depend on the
+//! models, and the same outputs always produce the same record batches and files. Rows follow the
+//! order of the outputs, and nested values keep their order within each row.
+//!
+//! The schemas of the record batches are:
+//!
+//! | **Output** | **Function** | **Schema** (one row per ...) |
+//! :-----:|:----:|:----:
+//! `Vec<Label>` (classification) | `labels_to_record_batch` | one row per label: `sentence: UInt64`, `id: Int64`, `text: Utf8`, `score: Float64` |
+//! `Vec<Vec<Label>>` (multi-label classification) | `multilabel_to_record_batch` | one row per input: `input: UInt64`, `labels: List<Struct<sentence: UInt64, id: Int64, text: Utf8, score: Float64>>` |
+//! `Vec<Vec<Entity>>` (NER) | `entities_to_record_batch` | one row per input: `input: UInt64`, `entities: List<Struct<word: Utf8, label: Utf8, start: UInt64, end: UInt64, score: Float64>>` |
+//! `Vec<Vec<Answer>>` (question answering) | `answers_to_record_batch` | one row per question: `input: UInt64`, `answers: List<Struct<answer: Utf8, start: UInt64, end: UInt64, score: Float64>>` |
+//!
+//! The `input` column is the index of the input in the output of the pipeline. Inputs without any
+//! label, entity or answer get a row with an empty list.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::export::{multilabel_to_record_batch, write_parquet};
+//! use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+//!
+//! let model = ZeroShotClassificationModel::new(Default::default())?;
+//! let output = model.predict_multilabel(
+//!     &["Who are you voting for in 2020?"],
+//!     &["politics", "public health", "economics", "sports"],
+//!     None,
+//!     128,
+//! )?;
+//! let record_batch = multilabel_to_record_batch(&output)?;
+//! write_parquet("predictions.parquet", &record_batch)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+#[cfg(feature = "ner")]
+use crate::pipelines::ner::Entity;
+#[cfg(feature = "question-answering")]
+use crate::pipelines::question_answering::Answer;
+#[cfg(feature = "sequence-classification")]
+use crate::pipelines::sequence_classification::Label;
+use arrow_crate::array::{
+    Array, ArrayData, ArrayRef, Float64Array, Int64Array, ListArray, StringArray, StructArray,
+    UInt64Array,
+};
+use arrow_crate::buffer::Buffer;
+use arrow_crate::datatypes::{DataType, Field, Schema};
+use arrow_crate::error::ArrowError;
+use arrow_crate::record_batch::RecordBatch;
+use parquet::arrow::{ArrowReader, ArrowWriter, ParquetFileArrowReader};
+use parquet::file::serialized_reader::SerializedFileReader;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn utf8_column<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(values.map(Some).collect::<StringArray>())
+}
+
+fn float64_column(values: impl Iterator<Item = f64>) -> ArrayRef {
+    Arc::new(values.map(Some).collect::<Float64Array>())
+}
+
+fn int64_column(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(values.map(Some).collect::<Int64Array>())
+}
+
+fn uint64_column(values: impl Iterator<Item = usize>) -> ArrayRef {
+    Arc::new(
+        values
+            .map(|value| Some(value as u64))
+            .collect::<UInt64Array>(),
+    )
+}
+
+/// Builds a list column from the fields of all items, flattened over the rows, and the number of
+/// items of each row
+fn list_column(item_fields: Vec<(Field, ArrayRef)>, row_lengths: &[usize]) -> ArrayRef {
+    let items = StructArray::from(item_fields);
+    let mut offsets = Vec::with_capacity(row_lengths.len() + 1);
+    let mut offset = 0i32;
+    offsets.push(offset);
+    for row_length in row_lengths {
+        offset += *row_length as i32;
+        offsets.push(offset);
+    }
+    let data = ArrayData::builder(DataType::List(Box::new(Field::new(
+        "item",
+        items.data_type().clone(),
+        false,
+    ))))
+    .len(row_lengths.len())
+    .add_buffer(Buffer::from_slice_ref(&offsets))
+    .add_child_data(items.data().clone())
+    .build();
+    Arc::new(ListArray::from(data))
+}
+
+/// Builds a record batch with one row per output row: the index of the row and the list of its items
+fn nested_record_batch(
+    list_name: &str,
+    item_fields: Vec<(Field, ArrayRef)>,
+    row_lengths: &[usize],
+) -> Result<RecordBatch, RustBertError> {
+    let list = list_column(item_fields, row_lengths);
+    let schema = Schema::new(vec![
+        Field::new("input", DataType::UInt64, false),
+        Field::new(list_name, list.data_type().clone(), false),
+    ]);
+    Ok(RecordBatch::try_new(
+        Arc::new(schema),
+        vec![uint64_column(0..row_lengths.len()), list],
+    )?)
+}
+
+/// Converts classification labels to a record batch with one row per label.
+///
+/// # Arguments
+///
+/// * `labels` - `&[Label]` labels returned by a classification pipeline
+///
+/// # Returns
+///
+/// * `RecordBatch` with the columns `sentence: UInt64`, `id: Int64`, `text: Utf8`, `score: Float64`
+#[cfg(feature = "sequence-classification")]
+pub fn labels_to_record_batch(labels: &[Label]) -> Result<RecordBatch, RustBertError> {
+    let schema = Schema::new(label_fields());
+    Ok(RecordBatch::try_new(
+        Arc::new(schema),
+        label_columns(labels.iter()),
+    )?)
+}
+
+#[cfg(feature = "sequence-classification")]
+fn label_fields() -> Vec<Field> {
+    vec![
+        Field::new("sentence", DataType::UInt64, false),
+        Field::new("id", DataType::Int64, false),
+        Field::new("text", DataType::Utf8, false),
+        Field::new("score", DataType::Float64, false),
+    ]
+}
+
+#[cfg(feature = "sequence-classification")]
+fn label_columns<'a>(labels: impl Iterator<Item = &'a Label> + Clone) -> Vec<ArrayRef> {
+    vec![
+        uint64_column(labels.clone().map(|label| label.sentence)),
+        int64_column(labels.clone().map(|label| label.id)),
+        utf8_column(labels.clone().map(|label| label.text.as_str())),
+        float64_column(labels.map(|label| label.score)),
+    ]
+}
+
+/// Converts multi-label classification outputs to a record batch with one row per input.
+///
+/// # Arguments
+///
+/// * `labels` - `&[Vec<Label>]` labels of each input returned by a multi-label classification pipeline
+///
+/// # Returns
+///
+/// * `RecordBatch` with the columns `input: UInt64` and
+/// `labels: List<Struct<sentence: UInt64, id: Int64, text: Utf8, score: Float64>>`
+#[cfg(feature = "sequence-classification")]
+pub fn multilabel_to_record_batch(labels: &[Vec<Label>]) -> Result<RecordBatch, RustBertError> {
+    let item_fields = label_fields()
+        .into_iter()
+        .zip(label_columns(labels.iter().flatten()))
+        .collect();
+    let row_lengths = labels.iter().map(Vec::len).collect::<Vec<usize>>();
+    nested_record_batch("labels", item_fields, &row_lengths)
+}
+
+/// Converts a record batch built by `multilabel_to_record_batch` (e.g. read from a Parquet file)
+/// back to multi-label classification outputs.
+///
+/// # Arguments
+///
+/// * `record_batch` - `&RecordBatch` with the schema of `multilabel_to_record_batch`
+///
+/// # Returns
+///
+/// * `Vec<Vec<Label>>` labels of each row of the record batch
+/// * `RustBertError` if the record batch does not have the expected schema
+#[cfg(feature = "sequence-classification")]
+pub fn multilabel_from_record_batch(
+    record_batch: &RecordBatch,
+) -> Result<Vec<Vec<Label>>, RustBertError> {
+    let lists = downcast::<ListArray>(
+        record_batch.column(record_batch.schema().index_of("labels")?),
+        "labels",
+    )?;
+    (0..lists.len())
+        .map(|row| {
+            let items = lists.value(row);
+            let items = downcast::<StructArray>(&items, "labels")?;
+            let sentences = struct_column::<UInt64Array>(items, "sentence")?;
+            let ids = struct_column::<Int64Array>(items, "id")?;
+            let texts = struct_column::<StringArray>(items, "text")?;
+            let scores = struct_column::<Float64Array>(items, "score")?;
+            Ok((0..items.len())
+                .map(|index| Label {
+                    text: texts.value(index).to_string(),
+                    score: scores.value(index),
+                    id: ids.value(index),
+                    sentence: sentences.value(index) as usize,
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Converts NER outputs to a record batch with one row per input.
+///
+/// # Arguments
+///
+/// * `entities` - `&[Vec<Entity>]` entities of each input returned by a NER pipeline
+///
+/// # Returns
+///
+/// * `RecordBatch` with the columns `input: UInt64` and
+/// `entities: List<Struct<word: Utf8, label: Utf8, start: UInt64, end: UInt64, score: Float64>>`
+#[cfg(feature = "ner")]
+pub fn entities_to_record_batch(entities: &[Vec<Entity>]) -> Result<RecordBatch, RustBertError> {
+    let items = entities.iter().flatten();
+    let item_fields = vec![
+        (
+            Field::new("word", DataType::Utf8, false),
+            utf8_column(items.clone().map(|entity| entity.word.as_str())),
+        ),
+        (
+            Field::new("label", DataType::Utf8, false),
+            utf8_column(items.clone().map(|entity| entity.label.as_str())),
+        ),
+        (
+            Field::new("start", DataType::UInt64, false),
+            uint64_column(items.clone().map(|entity| entity.start)),
+        ),
+        (
+            Field::new("end", DataType::UInt64, false),
+            uint64_column(items.clone().map(|entity| entity.end)),
+        ),
+        (
+            Field::new("score", DataType::Float64, false),
+            float64_column(items.map(|entity| entity.score)),
+        ),
+    ];
+    let row_lengths = entities.iter().map(Vec::len).collect::<Vec<usize>>();
+    nested_record_batch("entities", item_fields, &row_lengths)
+}
+
+/// Converts question answering outputs to a record batch with one row per question.
+///
+/// # Arguments
+///
+/// * `answers` - `&[Vec<Answer>]` answers of each question returned by a question answering pipeline
+///
+/// # Returns
+///
+/// * `RecordBatch` with the columns `input: UInt64` and
+/// `answers: List<Struct<answer: Utf8, start: UInt64, end: UInt64, score: Float64>>`
+#[cfg(feature = "question-answering")]
+pub fn answers_to_record_batch(answers: &[Vec<Answer>]) -> Result<RecordBatch, RustBertError> {
+    let items = answers.iter().flatten();
+    let item_fields = vec![
+        (
+            Field::new("answer", DataType::Utf8, false),
+            utf8_column(items.clone().map(|answer| answer.answer.as_str())),
+        ),
+        (
+            Field::new("start", DataType::UInt64, false),
+            uint64_column(items.clone().map(|answer| answer.start)),
+        ),
+        (
+            Field::new("end", DataType::UInt64, false),
+            uint64_column(items.clone().map(|answer| answer.end)),
+        ),
+        (
+            Field::new("score", DataType::Float64, false),
+            float64_column(items.map(|answer| answer.score)),
+        ),
+    ];
+    let row_lengths = answers.iter().map(Vec::len).collect::<Vec<usize>>();
+    nested_record_batch("answers", item_fields, &row_lengths)
+}
+
+/// Writes a record batch to a Parquet file, replacing any existing file.
+///
+/// # Arguments
+///
+/// * `path` - path of the Parquet file to write
+/// * `record_batch` - `&RecordBatch` to write
+pub fn write_parquet<P: AsRef<Path>>(
+    path: P,
+    record_batch: &RecordBatch,
+) -> Result<(), RustBertError> {
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, record_batch.schema(), None)?;
+    writer.write(record_batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Reads the record batches of a Parquet file.
+///
+/// # Arguments
+///
+/// * `path` - path of the Parquet file to read
+/// * `batch_size` - `usize` maximum number of rows of the record batches returned
+pub fn read_parquet<P: AsRef<Path>>(
+    path: P,
+    batch_size: usize,
+) -> Result<Vec<RecordBatch>, RustBertError> {
+    let file_reader = SerializedFileReader::new(File::open(path)?)?;
+    let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
+    Ok(arrow_reader
+        .get_record_reader(batch_size)?
+        .collect::<Result<Vec<RecordBatch>, ArrowError>>()?)
+}
+
+fn downcast<'a, T: 'static>(array: &'a ArrayRef, name: &str) -> Result<&'a T, RustBertError> {
+    array.as_any().downcast_ref::<T>().ok_or_else(|| {
+        RustBertError::ExportError(format!(
+            "Unexpected data type {:?} for column `{}`",
+            array.data_type(),
+            name
+        ))
+    })
+}
+
+fn struct_column<'a, T: 'static>(
+    array: &'a StructArray,
+    name: &str,
+) -> Result<&'a T, RustBertError> {
+    let column = array
+        .column_by_name(name)
+        .ok_or_else(|| RustBertError::ExportError(format!("Missing struct field `{}`", name)))?;
+    downcast::<T>(column, name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "sequence-classification")]
+    fn label(text: &str, score: f64, id: i64, sentence: usize) -> Label {
+        Label {
+            text: text.to_string(),
+            score,
+            id,
+            sentence,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sequence-classification")]
+    fn test_multilabel_parquet_round_trip() -> anyhow::Result<()> {
+        let output = vec![
+            vec![
+                label("politics", 0.972, 0, 0),
+                label("public health", 0.032, 1, 0),
+                label("économie", 0.006, 2, 0),
+            ],
+            vec![],
+            vec![
+                label("economics", 0.852, 2, 2),
+                label("politics", 0.975, 0, 2),
+            ],
+        ];
+        let record_batch = multilabel_to_record_batch(&output)?;
+        assert_eq!(record_batch.num_rows(), 3);
+        assert_eq!(record_batch.num_columns(), 2);
+
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("predictions.parquet");
+        write_parquet(&path, &record_batch)?;
+        let read_batches = read_parquet(&path, 1024)?;
+        assert_eq!(read_batches.len(), 1);
+
+        let read_output = multilabel_from_record_batch(&read_batches[0])?;
+        assert_eq!(read_output.len(), output.len());
+        for (read_labels, labels) in read_output.iter().zip(output.iter()) {
+            assert_eq!(read_labels.len(), labels.len());
+            for (read_label, label) in read_labels.iter().zip(labels.iter()) {
+                assert_eq!(read_label.text, label.text);
+                assert_eq!(read_label.score, label.score);
+                assert_eq!(read_label.id, label.id);
+                assert_eq!(read_label.sentence, label.sentence);
+            }
+        }
+
+        //    Exports are deterministic
+        let second_path = directory.path().join("predictions_2.parquet");
+        write_parquet(&second_path, &multilabel_to_record_batch(&output)?)?;
+        assert_eq!(std::fs::read(&path)?, std::fs::read(&second_path)?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "sequence-classification")]
+    fn test_labels_record_batch() -> anyhow::Result<()> {
+        let labels = vec![label("positive", 0.9, 1, 0), label("negative", 0.8, 0, 1)];
+        let record_batch = labels_to_record_batch(&labels)?;
+        assert_eq!(record_batch.num_rows(), 2);
+        let texts = downcast::<StringArray>(record_batch.column(2), "text")?;
+        assert_eq!(texts.value(0), "positive");
+        assert_eq!(texts.value(1), "negative");
+        let sentences = downcast::<UInt64Array>(record_batch.column(0), "sentence")?;
+        assert_eq!(sentences.value(1), 1);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "ner")]
+    fn test_entities_record_batch() -> anyhow::Result<()> {
+        let entity = |word: &str, label: &str, score: f64| Entity {
+            word: word.to_string(),
+            score,
+            label: label.to_string(),
+            start: 0,
+            end: word.chars().count(),
+        };
+        let entities = vec![
+            vec![entity("Amy", "I-PER", 0.99), entity("Paris", "I-LOC", 0.98)],
+            vec![entity("France", "I-LOC", 0.97)],
+        ];
+        let record_batch = entities_to_record_batch(&entities)?;
+        assert_eq!(record_batch.num_rows(), 2);
+        let lists = downcast::<ListArray>(record_batch.column(1), "entities")?;
+        let second_row = lists.value(1);
+        let second_row = downcast::<StructArray>(&second_row, "entities")?;
+        assert_eq!(second_row.len(), 1);
+        assert_eq!(
+            struct_column::<StringArray>(second_row, "word")?.value(0),
+            "France"
+        );
+        assert_eq!(struct_column::<UInt64Array>(second_row, "end")?.value(0), 6);
+        assert!(record_batch.schema().index_of("labels").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,491 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Fair scheduler for pipelines shared between tenants
+//! Wraps a pipeline owned by a single worker thread and shares it between several tenants. Each
+//! request is split in chunks by the chunked inference driver, and the worker executes the queued
+//! chunks in a round-robin order over the tenants: a large request from one tenant does not delay
+//! the small requests of the other tenants until it completes. The outputs of a request are
+//! reassembled in the input order before `submit` returns.
+//!
+//! The number of requests processed concurrently for a tenant is limited, additional requests
+//! waiting for a slot. The queue state of each tenant is available from `tenant_stats`.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::fair_scheduler::{FairScheduler, FairSchedulerConfig};
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//! use std::sync::Arc;
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! let scheduler = Arc::new(FairScheduler::new(model, FairSchedulerConfig::default()));
+//!
+//! let handle = {
+//!     let scheduler = scheduler.clone();
+//!     std::thread::spawn(move || scheduler.submit("tenant_a", &["This is great!"; 512]))
+//! };
+//! let labels = scheduler.submit("tenant_b", &["This is terrible."])?;
+//! let large_output = handle.join().unwrap()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::batch_runner::Pipeline;
+use crate::pipelines::chunked_inference::{run_chunked, ChunkScheduling};
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Bound::{Excluded, Unbounded};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Configuration for the fair scheduler
+pub struct FairSchedulerConfig {
+    /// Maximum number of inputs per chunk executed by the pipeline
+    pub chunk_size: usize,
+    /// Maximum number of requests processed concurrently for a single tenant
+    pub max_in_flight_per_tenant: usize,
+    /// Maximum number of chunks of a request queued ahead of the chunk being executed
+    pub max_queued_chunks: usize,
+}
+
+impl Default for FairSchedulerConfig {
+    fn default() -> FairSchedulerConfig {
+        FairSchedulerConfig {
+            chunk_size: 16,
+            max_in_flight_per_tenant: 4,
+            max_queued_chunks: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// # Queue state of a tenant
+pub struct TenantStats {
+    /// Number of requests being processed
+    pub in_flight: usize,
+    /// Number of requests waiting for an in-flight slot
+    pub waiting: usize,
+    /// Number of chunks queued for execution
+    pub queued_chunks: usize,
+}
+
+/// Outputs of a chunk, or error returned by the pipeline
+type ChunkResult<O> = Result<Vec<O>, RustBertError>;
+
+struct ChunkJob<O> {
+    inputs: Vec<String>,
+    result: SyncSender<ChunkResult<O>>,
+}
+
+struct TenantQueue<O> {
+    chunks: VecDeque<ChunkJob<O>>,
+    in_flight: usize,
+    waiting: usize,
+}
+
+impl<O> TenantQueue<O> {
+    fn new() -> TenantQueue<O> {
+        TenantQueue {
+            chunks: VecDeque::new(),
+            in_flight: 0,
+            waiting: 0,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.chunks.is_empty() && self.in_flight == 0 && self.waiting == 0
+    }
+}
+
+struct SchedulerState<O> {
+    tenants: BTreeMap<String, TenantQueue<O>>,
+    last_served: Option<String>,
+    shutdown: bool,
+}
+
+impl<O> SchedulerState<O> {
+    /// Pops the next chunk, from the first tenant following the last served tenant that has queued chunks
+    fn next_chunk(&mut self) -> Option<ChunkJob<O>> {
+        let has_chunks = |(_, queue): &(&String, &TenantQueue<O>)| !queue.chunks.is_empty();
+        let next_tenant = match &self.last_served {
+            Some(last_served) => self
+                .tenants
+                .range::<str, _>((Excluded(last_served.as_str()), Unbounded))
+                .find(has_chunks)
+                .or_else(|| self.tenants.iter().find(has_chunks)),
+            None => self.tenants.iter().find(has_chunks),
+        }
+        .map(|(tenant, _)| tenant.clone())?;
+        let chunk = self.tenants.get_mut(&next_tenant)?.chunks.pop_front();
+        self.last_served = Some(next_tenant);
+        chunk
+    }
+}
+
+struct SharedState<O> {
+    state: Mutex<SchedulerState<O>>,
+    chunk_queued: Condvar,
+    slot_released: Condvar,
+}
+
+impl<O> SharedState<O> {
+    fn lock(&self) -> MutexGuard<SchedulerState<O>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn enqueue(&self, tenant: &str, inputs: &[&str]) -> Receiver<ChunkResult<O>> {
+        let (sender, receiver) = sync_channel(1);
+        let mut state = self.lock();
+        state
+            .tenants
+            .entry(tenant.to_string())
+            .or_insert_with(TenantQueue::new)
+            .chunks
+            .push_back(ChunkJob {
+                inputs: inputs.iter().map(|input| input.to_string()).collect(),
+                result: sender,
+            });
+        self.chunk_queued.notify_one();
+        receiver
+    }
+}
+
+/// # Round-robin scheduler sharing a pipeline between tenants
+pub struct FairScheduler<P: Pipeline> {
+    shared: Arc<SharedState<P::Output>>,
+    config: FairSchedulerConfig,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<P> FairScheduler<P>
+where
+    P: Pipeline + Send + 'static,
+    P::Output: Send + 'static,
+{
+    /// Build a new `FairScheduler`, moving the pipeline to a worker thread
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - pipeline executing the chunks
+    /// * `config` - `FairSchedulerConfig` with the chunking and in-flight limits
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::fair_scheduler::{FairScheduler, FairSchedulerConfig};
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let scheduler = FairScheduler::new(
+    ///     NERModel::new(Default::default())?,
+    ///     FairSchedulerConfig {
+    ///         chunk_size: 8,
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(pipeline: P, config: FairSchedulerConfig) -> FairScheduler<P> {
+        let shared = Arc::new(SharedState {
+            state: Mutex::new(SchedulerState {
+                tenants: BTreeMap::new(),
+                last_served: None,
+                shutdown: false,
+            }),
+            chunk_queued: Condvar::new(),
+            slot_released: Condvar::new(),
+        });
+        let worker_state = shared.clone();
+        let worker = thread::spawn(move || Self::run_worker(pipeline, worker_state));
+        FairScheduler {
+            shared,
+            config,
+            worker: Some(worker),
+        }
+    }
+
+    fn run_worker(pipeline: P, shared: Arc<SharedState<P::Output>>) {
+        loop {
+            let chunk = {
+                let mut state = shared.lock();
+                loop {
+                    if let Some(chunk) = state.next_chunk() {
+                        break chunk;
+                    }
+                    if state.shutdown {
+                        return;
+                    }
+                    state = shared
+                        .chunk_queued
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            };
+            let inputs = chunk
+                .inputs
+                .iter()
+                .map(|input| input.as_str())
+                .collect::<Vec<&str>>();
+            //    A panicking chunk drops its result sender, failing the request it belongs to only
+            if let Ok(outputs) = catch_unwind(AssertUnwindSafe(|| pipeline.process(&inputs))) {
+                let _ = chunk.result.send(outputs);
+            }
+        }
+    }
+
+    /// Processes the inputs of a tenant, interleaving its chunks with the chunks of the other tenants.
+    /// Blocks while the tenant already has `max_in_flight_per_tenant` requests in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `tenant` - key of the tenant submitting the request
+    /// * `inputs` - `&[&str]` Array of texts to process
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<P::Output>` containing the pipeline outputs, in the input order
+    /// * `RustBertError` if the configuration is invalid or if the pipeline failed on a chunk of the request
+    pub fn submit(&self, tenant: &str, inputs: &[&str]) -> Result<Vec<P::Output>, RustBertError> {
+        if self.config.max_in_flight_per_tenant == 0 {
+            return Err(RustBertError::ValueError(
+                "The maximum number of requests in flight per tenant must be strictly positive"
+                    .to_string(),
+            ));
+        }
+        self.acquire_slot(tenant);
+        let shared = self.shared.as_ref();
+        let outputs = run_chunked(
+            inputs,
+            self.config.chunk_size,
+            ChunkScheduling::Pipelined {
+                prefetch: self.config.max_queued_chunks,
+            },
+            |chunk| Ok(shared.enqueue(tenant, chunk)),
+            |result: Receiver<ChunkResult<P::Output>>| {
+                result
+                    .recv()
+                    .map_err(|_| {
+                        RustBertError::InvalidStateError(
+                            "The pipeline failed to process a chunk of the request".to_string(),
+                        )
+                    })
+                    .and_then(|outputs| outputs)
+            },
+        );
+        self.release_slot(tenant);
+        outputs
+    }
+
+    fn acquire_slot(&self, tenant: &str) {
+        let mut state = self.shared.lock();
+        state
+            .tenants
+            .entry(tenant.to_string())
+            .or_insert_with(TenantQueue::new)
+            .waiting += 1;
+        while state.tenants[tenant].in_flight >= self.config.max_in_flight_per_tenant {
+            state = self
+                .shared
+                .slot_released
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        let queue = state.tenants.get_mut(tenant).unwrap();
+        queue.waiting -= 1;
+        queue.in_flight += 1;
+    }
+
+    fn release_slot(&self, tenant: &str) {
+        let mut state = self.shared.lock();
+        //    Chunks still queued after a failure are dropped with the request
+        let queue = state.tenants.get_mut(tenant).unwrap();
+        queue.in_flight -= 1;
+        if queue.in_flight == 0 {
+            queue.chunks.clear();
+        }
+        if queue.is_idle() {
+            state.tenants.remove(tenant);
+        }
+        self.shared.slot_released.notify_all();
+    }
+}
+
+impl<P: Pipeline> FairScheduler<P> {
+    /// Returns the queue state of a tenant
+    pub fn tenant_stats(&self, tenant: &str) -> TenantStats {
+        let state = self.shared.lock();
+        state
+            .tenants
+            .get(tenant)
+            .map(|queue| TenantStats {
+                in_flight: queue.in_flight,
+                waiting: queue.waiting,
+                queued_chunks: queue.chunks.len(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the total number of chunks queued for execution, over all tenants
+    pub fn queue_length(&self) -> usize {
+        let state = self.shared.lock();
+        state.tenants.values().map(|queue| queue.chunks.len()).sum()
+    }
+}
+
+impl<P: Pipeline> Drop for FairScheduler<P> {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.chunk_queued.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    struct RecordingPipeline {
+        executed: Arc<Mutex<Vec<String>>>,
+        gate: Mutex<Option<Receiver<()>>>,
+    }
+
+    impl Pipeline for RecordingPipeline {
+        type Output = String;
+
+        fn process(&self, inputs: &[&str]) -> Result<Vec<String>, RustBertError> {
+            if let Some(gate) = self.gate.lock().unwrap().take() {
+                gate.recv().unwrap();
+            }
+            self.executed.lock().unwrap().push(inputs.join(","));
+            Ok(inputs.iter().map(|input| input.to_uppercase()).collect())
+        }
+    }
+
+    /// Builds a scheduler whose first chunk blocks until the returned sender is used
+    fn gated_scheduler(
+        config: FairSchedulerConfig,
+    ) -> (
+        FairScheduler<RecordingPipeline>,
+        Arc<Mutex<Vec<String>>>,
+        SyncSender<()>,
+    ) {
+        let executed = Arc::new(Mutex::new(vec![]));
+        let (release, gate) = sync_channel(1);
+        let pipeline = RecordingPipeline {
+            executed: executed.clone(),
+            gate: Mutex::new(Some(gate)),
+        };
+        (FairScheduler::new(pipeline, config), executed, release)
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        while !condition() {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_small_request_interleaved_with_large_request() {
+        let config = FairSchedulerConfig {
+            chunk_size: 2,
+            max_in_flight_per_tenant: 1,
+            max_queued_chunks: 8,
+        };
+        let (scheduler, executed, release) = gated_scheduler(config);
+
+        let (large_output, small_output) = thread::scope(|scope| {
+            let large = scope
+                .spawn(|| scheduler.submit("a", &["a1", "a2", "a3", "a4", "a5", "a6", "a7", "a8"]));
+            wait_for(|| scheduler.tenant_stats("a").queued_chunks == 3);
+            let small = scope.spawn(|| scheduler.submit("b", &["b1", "b2"]));
+            wait_for(|| scheduler.tenant_stats("b").queued_chunks == 1);
+            assert_eq!(scheduler.queue_length(), 4);
+            release.send(()).unwrap();
+            (large.join().unwrap(), small.join().unwrap())
+        });
+
+        assert_eq!(
+            *executed.lock().unwrap(),
+            ["a1,a2", "b1,b2", "a3,a4", "a5,a6", "a7,a8"]
+        );
+        assert_eq!(
+            large_output.unwrap(),
+            ["A1", "A2", "A3", "A4", "A5", "A6", "A7", "A8"]
+        );
+        assert_eq!(small_output.unwrap(), ["B1", "B2"]);
+        assert_eq!(scheduler.tenant_stats("a"), TenantStats::default());
+        assert_eq!(scheduler.queue_length(), 0);
+    }
+
+    #[test]
+    fn test_round_robin_between_large_requests() {
+        let config = FairSchedulerConfig {
+            chunk_size: 1,
+            max_in_flight_per_tenant: 1,
+            max_queued_chunks: 8,
+        };
+        let (scheduler, executed, release) = gated_scheduler(config);
+
+        thread::scope(|scope| {
+            let first = scope.spawn(|| scheduler.submit("a", &["a1", "a2", "a3"]));
+            wait_for(|| scheduler.tenant_stats("a").queued_chunks == 2);
+            let second = scope.spawn(|| scheduler.submit("b", &["b1", "b2", "b3"]));
+            wait_for(|| scheduler.tenant_stats("b").queued_chunks == 3);
+            release.send(()).unwrap();
+            first.join().unwrap().unwrap();
+            second.join().unwrap().unwrap();
+        });
+
+        assert_eq!(
+            *executed.lock().unwrap(),
+            ["a1", "b1", "a2", "b2", "a3", "b3"]
+        );
+    }
+
+    #[test]
+    fn test_in_flight_limit() {
+        let config = FairSchedulerConfig {
+            chunk_size: 1,
+            max_in_flight_per_tenant: 1,
+            max_queued_chunks: 8,
+        };
+        let (scheduler, executed, release) = gated_scheduler(config);
+
+        thread::scope(|scope| {
+            let first = scope.spawn(|| scheduler.submit("a", &["a1", "a2"]));
+            wait_for(|| scheduler.tenant_stats("a").queued_chunks == 1);
+            let second = scope.spawn(|| scheduler.submit("a", &["a3"]));
+            wait_for(|| scheduler.tenant_stats("a").waiting == 1);
+            assert_eq!(
+                scheduler.tenant_stats("a"),
+                TenantStats {
+                    in_flight: 1,
+                    waiting: 1,
+                    queued_chunks: 1
+                }
+            );
+            release.send(()).unwrap();
+            first.join().unwrap().unwrap();
+            second.join().unwrap().unwrap();
+        });
+
+        assert_eq!(*executed.lock().unwrap(), ["a1", "a2", "a3"]);
+    }
+}
@@ -0,0 +1,811 @@
+// Copyright 2019-present, the HuggingFace Inc. team, The Google AI Language Team and Facebook, Inc.
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Feature extraction pipeline
+//! Extracts the hidden states of selected layers of an encoder for each token (or word) of the
+//! input, for example to probe the representations learned by the model or to use them as features
+//! for a downstream model. The layers are indexed from 0 (output of the embeddings layer) to the
+//! number of layers of the model (output of the last layer). `LayerSelection::SumLastFour` returns
+//! the sum of the hidden states of the last 4 layers.
+//! By default, the pipeline uses a BERT base (uncased) model.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::feature_extraction::{
+//!     FeatureExtractionConfig, FeatureExtractionModel, FeatureExtractionOutput, LayerSelection,
+//! };
+//!
+//! let feature_extraction_model = FeatureExtractionModel::new(FeatureExtractionConfig {
+//!     layers: vec![LayerSelection::Layer(8), LayerSelection::SumLastFour],
+//!     word_pooling: true,
+//!     ..Default::default()
+//! })?;
+//!
+//! let input = ["My name is Amélie. I live in Москва."];
+//! if let FeatureExtractionOutput::Tokens(output) = feature_extraction_model.extract(&input)? {
+//!     for word in &output[0] {
+//!         println!("{}: {} features", word.text, word.features[0].len());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::bert::{
+    BertConfigResources, BertEmbeddings, BertModel, BertModelResources, BertVocabResources,
+};
+use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
+use crate::common::resources::{RemoteResource, Resource};
+#[cfg(feature = "distilbert")]
+use crate::distilbert::DistilBertModel;
+use crate::pipelines::common::{expect_config, ConfigOption, ModelType, TokenizerOption};
+#[cfg(feature = "roberta")]
+use crate::roberta::RobertaEmbeddings;
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::{Mask, Offset, TokenizedInput};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::cmp::min;
+use tch::kind::Kind::{Float, Int64};
+use tch::nn::VarStore;
+use tch::{nn, no_grad, Device, Tensor};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Layer of the model to extract features from
+pub enum LayerSelection {
+    /// Hidden states of a single layer: 0 is the output of the embeddings layer and the number of
+    /// layers of the model the output of the last layer
+    Layer(usize),
+    /// Sum of the hidden states of the last 4 layers
+    SumLastFour,
+}
+
+/// # Configuration for feature extraction
+/// Contains information regarding the model to load, the layers to extract and device placement
+pub struct FeatureExtractionConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: pretrained BERT base model, uncased)
+    pub model_resource: Resource,
+    /// Config resource (default: pretrained BERT base model, uncased)
+    pub config_resource: Resource,
+    /// Vocab resource (default: pretrained BERT base model, uncased)
+    pub vocab_resource: Resource,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Resource>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+    /// Layers to extract the features from (default: sum of the last 4 layers)
+    pub layers: Vec<LayerSelection>,
+    /// Average the features of the sub-tokens of each word and skip special tokens (default: false)
+    pub word_pooling: bool,
+    /// Return the features as a single `Tensor` instead of a `TokenFeatures` for each token (default: false)
+    pub output_tensor: bool,
+    /// Maximum sequence length for the inputs, longer inputs are truncated (default: 128)
+    pub max_length: usize,
+}
+
+impl FeatureExtractionConfig {
+    /// Instantiate a new feature extraction configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `Resource` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `Resource' pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `Resource' pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `Resource` tuple (`Option<Resource>`) pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool' indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    /// * layers - `Vec<LayerSelection>` layers to extract the features from
+    pub fn new(
+        model_type: ModelType,
+        model_resource: Resource,
+        config_resource: Resource,
+        vocab_resource: Resource,
+        merges_resource: Option<Resource>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+        layers: Vec<LayerSelection>,
+    ) -> FeatureExtractionConfig {
+        FeatureExtractionConfig {
+            model_type,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            merges_resource,
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: Device::cuda_if_available(),
+            layers,
+            word_pooling: false,
+            output_tensor: false,
+            max_length: 128,
+        }
+    }
+}
+
+impl Default for FeatureExtractionConfig {
+    /// Provides a BERT base (uncased) model, returning the sum of the last 4 layers for each token
+    fn default() -> FeatureExtractionConfig {
+        FeatureExtractionConfig::new(
+            ModelType::Bert,
+            Resource::Remote(RemoteResource::from_pretrained(BertModelResources::BERT)),
+            Resource::Remote(RemoteResource::from_pretrained(BertConfigResources::BERT)),
+            Resource::Remote(RemoteResource::from_pretrained(BertVocabResources::BERT)),
+            None,
+            true,
+            None,
+            None,
+            vec![LayerSelection::SumLastFour],
+        )
+    }
+}
+
+/// # Abstraction that holds one particular encoder model, for any of the supported models
+/// The models are created with `output_hidden_states` enabled.
+pub enum FeatureExtractionOption {
+    /// Bert encoder
+    Bert(BertModel<BertEmbeddings>),
+    /// Roberta encoder
+    #[cfg(feature = "roberta")]
+    Roberta(BertModel<RobertaEmbeddings>),
+    /// XLMRoberta encoder
+    #[cfg(feature = "roberta")]
+    XLMRoberta(BertModel<RobertaEmbeddings>),
+    /// DistilBert encoder
+    #[cfg(feature = "distilbert")]
+    DistilBert(DistilBertModel),
+}
+
+impl FeatureExtractionOption {
+    /// Instantiate a new encoder of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded)
+    /// * `p` - `tch::nn::Path` path to the model file to load (e.g. model.ot)
+    /// * `config` - A configuration (the model type of the configuration must be compatible with the value for
+    /// `model_type`)
+    pub fn new<'p, P>(
+        model_type: ModelType,
+        p: P,
+        config: &ConfigOption,
+    ) -> Result<Self, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        model_type.ensure_enabled()?;
+        let p = p.borrow();
+        match model_type {
+            ModelType::Bert => {
+                let config = expect_config!(
+                    config,
+                    ModelType::Bert,
+                    ConfigOption::Bert,
+                    "Feature extraction"
+                )?;
+                let mut config = config.clone();
+                config.output_hidden_states = Some(true);
+                Ok(FeatureExtractionOption::Bert(BertModel::new(
+                    p / "bert",
+                    &config,
+                )))
+            }
+            #[cfg(feature = "roberta")]
+            ModelType::Roberta | ModelType::XLMRoberta => {
+                let config =
+                    expect_config!(config, model_type, ConfigOption::Bert, "Feature extraction")?;
+                let mut config = config.clone();
+                config.output_hidden_states = Some(true);
+                let model = BertModel::<RobertaEmbeddings>::new_with_optional_pooler(
+                    p / "roberta",
+                    &config,
+                    false,
+                );
+                Ok(match model_type {
+                    ModelType::Roberta => FeatureExtractionOption::Roberta(model),
+                    _ => FeatureExtractionOption::XLMRoberta(model),
+                })
+            }
+            #[cfg(feature = "distilbert")]
+            ModelType::DistilBert => {
+                let config = expect_config!(
+                    config,
+                    ModelType::DistilBert,
+                    ConfigOption::DistilBert,
+                    "Feature extraction"
+                )?;
+                let mut config = config.clone();
+                config.output_hidden_states = Some(true);
+                Ok(FeatureExtractionOption::DistilBert(DistilBertModel::new(
+                    p, &config,
+                )))
+            }
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Feature extraction not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
+    /// Returns the `ModelType` for this FeatureExtractionOption
+    pub fn model_type(&self) -> ModelType {
+        match *self {
+            Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
+            Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
+            Self::XLMRoberta(_) => ModelType::XLMRoberta,
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(_) => ModelType::DistilBert,
+        }
+    }
+
+    /// Forward pass through the encoder, returning the hidden states of all layers: the output of
+    /// the embeddings layer followed by the output of each layer of the model.
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        mask: &Tensor,
+        train: bool,
+    ) -> Result<Vec<Tensor>, RustBertError> {
+        let (hidden_state, all_hidden_states) = match *self {
+            Self::Bert(ref model) => {
+                let output = model.forward_t(
+                    Some(input_ids.copy()),
+                    Some(mask.copy()),
+                    None,
+                    None,
+                    None,
+                    &None,
+                    &None,
+                    train,
+                )?;
+                (output.hidden_state, output.all_hidden_states)
+            }
+            #[cfg(feature = "roberta")]
+            Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
+                let output = model.forward_t(
+                    Some(input_ids.copy()),
+                    Some(mask.copy()),
+                    None,
+                    None,
+                    None,
+                    &None,
+                    &None,
+                    train,
+                )?;
+                (output.hidden_state, output.all_hidden_states)
+            }
+            #[cfg(feature = "distilbert")]
+            Self::DistilBert(ref model) => {
+                let output =
+                    model.forward_t(Some(input_ids.copy()), Some(mask.copy()), None, train)?;
+                (output.hidden_state, output.all_hidden_states)
+            }
+        };
+        let mut hidden_states = all_hidden_states.ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "The model did not return its hidden states".to_string(),
+            )
+        })?;
+        hidden_states.push(hidden_state);
+        Ok(hidden_states)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Features extracted for a token (or a word if word pooling is enabled)
+pub struct TokenFeatures {
+    /// String representation of the token or word
+    pub text: String,
+    /// Character offsets of the token or word in the input (None for special tokens)
+    pub offset: Option<Offset>,
+    /// Features for each requested layer, in the order of `FeatureExtractionConfig::layers`
+    pub features: Vec<Vec<f32>>,
+}
+
+/// # Output of the feature extraction pipeline
+pub enum FeatureExtractionOutput {
+    /// Features for each token (or word) of each input
+    Tokens(Vec<Vec<TokenFeatures>>),
+    /// Features of shape (*batch size*, *number of requested layers*, *sequence_length*, *hidden_size*).
+    /// The sequence length is the number of words if word pooling is enabled, padded positions are set to 0.
+    Tensor(Tensor),
+}
+
+/// # FeatureExtractionModel to extract per-token hidden states
+pub struct FeatureExtractionModel {
+    tokenizer: TokenizerOption,
+    encoder: FeatureExtractionOption,
+    layers: Vec<LayerSelection>,
+    word_pooling: bool,
+    output_tensor: bool,
+    max_length: usize,
+    var_store: VarStore,
+    load_report: LoadReport,
+    tie_word_embeddings: bool,
+}
+
+impl FeatureExtractionModel {
+    /// Build a new `FeatureExtractionModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `FeatureExtractionConfig` object containing the resource references (model, vocabulary, configuration), the layers to extract and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::feature_extraction::FeatureExtractionModel;
+    ///
+    /// let model = FeatureExtractionModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: FeatureExtractionConfig) -> Result<FeatureExtractionModel, RustBertError> {
+        ensure_initialized();
+        if config.layers.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "At least one layer must be selected for feature extraction".to_string(),
+            ));
+        }
+        let config_path = config.config_resource.get_local_path()?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let merges_path = if let Some(merges_resource) = &config.merges_resource {
+            Some(merges_resource.get_local_path()?)
+        } else {
+            None
+        };
+
+        let tokenizer = TokenizerOption::from_file(
+            config.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            config.lower_case,
+            config.strip_accents,
+            config.add_prefix_space,
+        )?;
+        let mut var_store = VarStore::new(config.device);
+        let model_config = ConfigOption::try_from_file(config.model_type, config_path)?;
+        let encoder =
+            FeatureExtractionOption::new(config.model_type, &var_store.root(), &model_config)?;
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
+        Ok(FeatureExtractionModel {
+            tokenizer,
+            encoder,
+            layers: config.layers,
+            word_pooling: config.word_pooling,
+            output_tensor: config.output_tensor,
+            max_length: config.max_length,
+            var_store,
+            load_report,
+            tie_word_embeddings,
+        })
+    }
+
+    /// Returns the report of the weights loaded when building the model, listing the tying status
+    /// of the output projections, the repaired tensor shapes and the unused checkpoint tensors.
+    /// The report is updated when the weights are replaced with `swap_weights`.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Replaces the weights of the model with a checkpoint sharing its architecture (e.g. another
+    /// fine-tuning of the same base model), keeping the tokenizer and configuration. The checkpoint
+    /// is validated against the model variables before any weight is overwritten: if the swap
+    /// fails, the current weights remain in use. The load report is replaced by the report of the
+    /// new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `Resource` pointing to the new weights
+    pub fn swap_weights(&mut self, model_resource: Resource) -> Result<(), RustBertError> {
+        let weights_path = model_resource.get_local_path()?;
+        self.load_report =
+            load_with_report(&mut self.var_store, weights_path, self.tie_word_embeddings)?;
+        Ok(())
+    }
+
+    /// Returns the device the model is placed on
+    pub fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+
+    /// Moves the model to another device. The input tensors of the subsequent predictions are
+    /// created on the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - `Device` to move the model to
+    pub fn set_device(&mut self, device: Device) {
+        self.var_store.set_device(device);
+    }
+
+    /// Extracts the features of the requested layers for each token (or word) of the inputs
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract features from.
+    ///
+    /// # Returns
+    ///
+    /// * `FeatureExtractionOutput` containing the features for each token (or word) of each input
+    /// as `TokenFeatures`, or a single `Tensor` if `output_tensor` was set in the configuration.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::feature_extraction::FeatureExtractionModel;
+    /// let feature_extraction_model = FeatureExtractionModel::new(Default::default())?;
+    /// let input = ["Hello, world!"];
+    /// let output = feature_extraction_model.extract(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract<'a, S>(&self, input: S) -> Result<FeatureExtractionOutput, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        if input.is_empty() {
+            return Ok(if self.output_tensor {
+                FeatureExtractionOutput::Tensor(Tensor::zeros(&[0], (Float, Device::Cpu)))
+            } else {
+                FeatureExtractionOutput::Tokens(vec![])
+            });
+        }
+        let tokenized_input = self.tokenizer.encode_list(
+            input,
+            self.max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let (input_ids, mask) = self.prepare_for_model(&tokenized_input);
+
+        let features = no_grad(|| -> Result<Tensor, RustBertError> {
+            let hidden_states = self.encoder.forward_t(&input_ids, &mask, false)?;
+            //    Select the layers on the model device before moving the features to the CPU
+            let features =
+                select_layers(&hidden_states, &self.layers)? * mask.unsqueeze(1).unsqueeze(-1);
+            Ok(features.to(Device::Cpu))
+        })?;
+
+        let token_groups = tokenized_input
+            .iter()
+            .map(|sentence_tokens| {
+                if self.word_pooling {
+                    word_groups(&sentence_tokens.mask)
+                } else {
+                    (0..sentence_tokens.token_ids.len())
+                        .map(|position| vec![position])
+                        .collect()
+                }
+            })
+            .collect::<Vec<Vec<Vec<usize>>>>();
+        let features = if self.word_pooling {
+            let max_words = token_groups
+                .iter()
+                .map(|groups| groups.len())
+                .max()
+                .unwrap_or(0);
+            let pooled_features = token_groups
+                .iter()
+                .enumerate()
+                .map(|(sentence_idx, groups)| {
+                    let sentence_features = pool_words(&features.get(sentence_idx as i64), groups);
+                    let padding = max_words as i64 - groups.len() as i64;
+                    sentence_features.constant_pad_nd(&[0, 0, 0, padding])
+                })
+                .collect::<Vec<Tensor>>();
+            Tensor::stack(&pooled_features, 0)
+        } else {
+            features
+        };
+
+        if self.output_tensor {
+            return Ok(FeatureExtractionOutput::Tensor(features));
+        }
+        let output = input
+            .iter()
+            .zip(tokenized_input.iter())
+            .zip(token_groups.iter())
+            .enumerate()
+            .map(|(sentence_idx, ((text, sentence_tokens), groups))| {
+                let original_chars = text.chars().collect::<Vec<char>>();
+                let sentence_features = features.get(sentence_idx as i64);
+                groups
+                    .iter()
+                    .enumerate()
+                    .map(|(position, group)| TokenFeatures {
+                        text: self.group_text(&original_chars, sentence_tokens, group),
+                        offset: group_offset(sentence_tokens, group),
+                        features: (0..self.layers.len() as i64)
+                            .map(|layer_idx| {
+                                sentence_features
+                                    .get(layer_idx)
+                                    .get(position as i64)
+                                    .iter::<f64>()
+                                    .unwrap()
+                                    .map(|value| value as f32)
+                                    .collect::<Vec<f32>>()
+                            })
+                            .collect(),
+                    })
+                    .collect::<Vec<TokenFeatures>>()
+            })
+            .collect();
+        Ok(FeatureExtractionOutput::Tokens(output))
+    }
+
+    fn prepare_for_model(&self, tokenized_input: &[TokenizedInput]) -> (Tensor, Tensor) {
+        let max_len = tokenized_input
+            .iter()
+            .map(|input| input.token_ids.len())
+            .max()
+            .unwrap();
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .expect("The Tokenizer used for feature extraction should contain a PAD id");
+        let (input_ids, masks): (Vec<Tensor>, Vec<Tensor>) = tokenized_input
+            .iter()
+            .map(|input| {
+                let mut token_ids = input.token_ids.clone();
+                let mut mask = vec![1i64; token_ids.len()];
+                token_ids.extend(vec![pad_id; max_len - input.token_ids.len()]);
+                mask.extend(vec![0i64; max_len - input.token_ids.len()]);
+                (Tensor::of_slice(&token_ids), Tensor::of_slice(&mask))
+            })
+            .unzip();
+        (
+            Tensor::stack(&input_ids, 0).to(self.var_store.device()),
+            Tensor::stack(&masks, 0)
+                .to_kind(Int64)
+                .to(self.var_store.device()),
+        )
+    }
+
+    fn group_text(
+        &self,
+        original_chars: &[char],
+        sentence_tokens: &TokenizedInput,
+        group: &[usize],
+    ) -> String {
+        match group_offset(sentence_tokens, group) {
+            Some(offset) => {
+                let end_char = min(offset.end as usize, original_chars.len());
+                original_chars[offset.begin as usize..end_char]
+                    .iter()
+                    .collect()
+            }
+            None => self.tokenizer.decode(
+                group
+                    .iter()
+                    .map(|&position| sentence_tokens.token_ids[position])
+                    .collect(),
+                false,
+                false,
+            ),
+        }
+    }
+}
+
+/// Stacks the hidden states of the selected layers, returning a tensor of shape
+/// (*batch size*, *number of selected layers*, *sequence_length*, *hidden_size*).
+fn select_layers(
+    hidden_states: &[Tensor],
+    layers: &[LayerSelection],
+) -> Result<Tensor, RustBertError> {
+    let num_layers = hidden_states.len() - 1;
+    let selected = layers
+        .iter()
+        .map(|layer| match *layer {
+            LayerSelection::Layer(index) if index <= num_layers => {
+                Ok(hidden_states[index].shallow_clone())
+            }
+            LayerSelection::Layer(index) => Err(RustBertError::ValueError(format!(
+                "Layer {} requested but the model has {} layers (layer 0 is the embeddings layer)",
+                index, num_layers
+            ))),
+            LayerSelection::SumLastFour if num_layers >= 4 => Ok(hidden_states[num_layers - 3..]
+                .iter()
+                .fold(hidden_states[num_layers - 4].zeros_like(), |sum, layer| {
+                    sum + layer
+                })),
+            LayerSelection::SumLastFour => Err(RustBertError::ValueError(format!(
+                "The sum of the last 4 layers was requested but the model has {} layers",
+                num_layers
+            ))),
+        })
+        .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+    Ok(Tensor::stack(&selected, 1))
+}
+
+/// Groups the token positions by word: continuation sub-tokens are attached to the previous token
+/// and special tokens are skipped.
+fn word_groups(masks: &[Mask]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = vec![];
+    for (position, mask) in masks.iter().enumerate() {
+        match mask {
+            Mask::Special => {}
+            Mask::Continuation if !groups.is_empty() => groups.last_mut().unwrap().push(position),
+            _ => groups.push(vec![position]),
+        }
+    }
+    groups
+}
+
+/// Averages the features of shape (*number of layers*, *sequence_length*, *hidden_size*) over the
+/// positions of each group, returning a tensor of shape (*number of layers*, *number of groups*, *hidden_size*).
+fn pool_words(features: &Tensor, groups: &[Vec<usize>]) -> Tensor {
+    let device = features.device();
+    let pooled = groups
+        .iter()
+        .map(|group| {
+            let positions = group
+                .iter()
+                .map(|&position| position as i64)
+                .collect::<Vec<i64>>();
+            features
+                .index_select(1, &Tensor::of_slice(&positions).to(device))
+                .mean1(&[1], false, Float)
+        })
+        .collect::<Vec<Tensor>>();
+    if pooled.is_empty() {
+        let size = features.size();
+        Tensor::zeros(&[size[0], 0, size[2]], (Float, device))
+    } else {
+        Tensor::stack(&pooled, 1)
+    }
+}
+
+fn group_offset(sentence_tokens: &TokenizedInput, group: &[usize]) -> Option<Offset> {
+    let offsets = group
+        .iter()
+        .filter_map(|&position| sentence_tokens.token_offsets[position])
+        .collect::<Vec<Offset>>();
+    Some(Offset {
+        begin: offsets.iter().map(|offset| offset.begin).min()?,
+        end: offsets.iter().map(|offset| offset.end).max()?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bert::BertConfig;
+    #[cfg(feature = "distilbert")]
+    use crate::distilbert::DistilBertConfig;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = FeatureExtractionConfig::default();
+        let _: Box<dyn Send> = Box::new(FeatureExtractionModel::new(config));
+    }
+
+    fn tiny_bert_config() -> BertConfig {
+        BertConfig {
+            num_hidden_layers: 5,
+            ..crate::fixtures::tiny_bert_config(100, &[])
+        }
+    }
+
+    fn check_hidden_states(encoder: &FeatureExtractionOption, num_layers: usize, hidden_size: i64) {
+        let input_ids = Tensor::randint1(2, 100, &[2, 7], (Int64, Device::Cpu));
+        let mask = Tensor::ones(&[2, 7], (Int64, Device::Cpu));
+        let hidden_states = no_grad(|| encoder.forward_t(&input_ids, &mask, false)).unwrap();
+        assert_eq!(hidden_states.len(), num_layers + 1);
+        for hidden_state in hidden_states.iter() {
+            assert_eq!(hidden_state.size(), vec![2, 7, hidden_size]);
+        }
+        let layers = [
+            LayerSelection::Layer(0),
+            LayerSelection::Layer(num_layers),
+            LayerSelection::SumLastFour,
+        ];
+        let features = select_layers(&hidden_states, &layers).unwrap();
+        assert_eq!(features.size(), vec![2, 3, 7, hidden_size]);
+    }
+
+    #[test]
+    fn test_bert_hidden_states_shape() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let config = ConfigOption::Bert(tiny_bert_config());
+        let encoder = FeatureExtractionOption::new(ModelType::Bert, &vs.root(), &config).unwrap();
+        check_hidden_states(&encoder, 5, 16);
+    }
+
+    #[test]
+    #[cfg(feature = "roberta")]
+    fn test_roberta_hidden_states_shape() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let config = ConfigOption::Bert(tiny_bert_config());
+        let encoder =
+            FeatureExtractionOption::new(ModelType::Roberta, &vs.root(), &config).unwrap();
+        check_hidden_states(&encoder, 5, 16);
+    }
+
+    #[test]
+    #[cfg(feature = "distilbert")]
+    fn test_distilbert_hidden_states_shape() {
+        let vs = nn::VarStore::new(Device::Cpu);
+        let config = DistilBertConfig {
+            n_layers: 4,
+            tie_weights_: true,
+            ..crate::fixtures::tiny_distilbert_config(100, None)
+        };
+        let config = ConfigOption::DistilBert(config);
+        let encoder =
+            FeatureExtractionOption::new(ModelType::DistilBert, &vs.root(), &config).unwrap();
+        check_hidden_states(&encoder, 4, 16);
+    }
+
+    #[test]
+    fn test_select_layers() {
+        let hidden_states = (0..6)
+            .map(|layer| Tensor::ones(&[2, 3, 4], (Float, Device::Cpu)) * layer)
+            .collect::<Vec<Tensor>>();
+
+        let features = select_layers(
+            &hidden_states,
+            &[LayerSelection::Layer(2), LayerSelection::SumLastFour],
+        )
+        .unwrap();
+        assert_eq!(features.size(), vec![2, 2, 3, 4]);
+        assert_eq!(features.double_value(&[1, 0, 2, 3]), 2.0);
+        assert_eq!(features.double_value(&[1, 1, 2, 3]), 14.0);
+
+        assert!(select_layers(&hidden_states, &[LayerSelection::Layer(6)]).is_err());
+        assert!(select_layers(&hidden_states[..3], &[LayerSelection::SumLastFour]).is_err());
+    }
+
+    #[test]
+    fn test_word_pooling() {
+        let masks = [
+            Mask::Special,
+            Mask::None,
+            Mask::None,
+            Mask::Continuation,
+            Mask::Continuation,
+            Mask::Punctuation,
+            Mask::Special,
+        ];
+        let groups = word_groups(&masks);
+        assert_eq!(groups, vec![vec![1], vec![2, 3, 4], vec![5]]);
+
+        let features = Tensor::arange(7, (Float, Device::Cpu))
+            .view((1, 7, 1))
+            .repeat(&[2, 1, 3]);
+        let pooled = pool_words(&features, &groups);
+        assert_eq!(pooled.size(), vec![2, 3, 3]);
+        assert_eq!(pooled.double_value(&[0, 0, 0]), 1.0);
+        assert_eq!(pooled.double_value(&[1, 1, 2]), 3.0);
+        assert_eq!(pooled.double_value(&[0, 2, 1]), 5.0);
+    }
+}
@@ -70,22 +70,30 @@
 
 use rust_tokenizers::tokenizer::Tokenizer;
 use rust_tokenizers::vocab::Vocab;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use tch::kind::Kind::Int64;
 use tch::{no_grad, Device, Tensor};
 
+#[cfg(feature = "bart")]
 use crate::bart::LayerState as BartLayerState;
 use crate::common::error::RustBertError;
 use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{
     Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::LayerState as GPTNeoLayerState;
 use crate::pipelines::generation_utils::private_generation_utils::{
     GenerateOptions, PrivateLanguageGenerator,
 };
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::LayerState as ProphetNetLayerState;
+#[cfg(feature = "reformer")]
 use crate::reformer::LayerState as ReformerLayerState;
+#[cfg(feature = "t5")]
 use crate::t5::LayerState as T5LayerState;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::LayerState as XLNetLayerState;
 
 use self::ordered_float::OrderedFloat;
@@ -131,10 +139,46 @@ pub struct GenerateConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Explicit decoding strategy. If provided, takes precedence over `do_sample`, `num_beams`, `temperature`, `top_k` and `top_p` (default: None)
+    pub decoding_strategy: Option<DecodingStrategy>,
+    /// Optional hook called before each forward pass of the decoder during generation (default: None)
+    pub decoder_step_hook: Option<DecoderStepHook>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Decoding strategy for text generation
+/// Explicit alternative to the combination of `do_sample` and `num_beams`. Greedy decoding is the
+/// fastest strategy, generating a single hypothesis and re-using the decoder cache at every step.
+pub enum DecodingStrategy {
+    /// Deterministic decoding selecting the most likely token at each step
+    Greedy,
+    /// Beam search with `num_beams` hypotheses per input
+    Beam { num_beams: i64 },
+    /// Top-k and/or nucleus sampling of the next token
+    Sample {
+        temperature: f64,
+        top_k: i64,
+        top_p: f64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Decoder forward pass performed during generation
+/// Passed to the `decoder_step_hook` of the `GenerateConfig` before each forward pass.
+pub struct DecoderStep {
+    /// Index of the generation step, starting at 0
+    pub step: i64,
+    /// Number of positions fed to the decoder for this step
+    pub input_length: i64,
+    /// Number of positions held in the cache passed to the decoder (0 if no cache is passed)
+    pub cached_length: i64,
+}
+
+/// Hook observing the decoder forward passes performed during generation
+pub type DecoderStepHook = Box<dyn Fn(&DecoderStep) + Send + Sync>;
+
 impl Default for GenerateConfig {
     fn default() -> GenerateConfig {
         GenerateConfig {
@@ -164,18 +208,67 @@ impl Default for GenerateConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
+            decoder_step_hook: None,
             device: Device::cuda_if_available(),
         }
     }
 }
 
+pub(crate) struct DecodingParameters {
+    pub do_sample: bool,
+    pub num_beams: i64,
+    pub temperature: f64,
+    pub top_k: i64,
+    pub top_p: f64,
+}
+
 impl GenerateConfig {
+    /// Resolves the sampling and beam search parameters, using the `decoding_strategy` if provided
+    /// and falling back to the individual parameters otherwise.
+    pub(crate) fn decoding_parameters(&self) -> DecodingParameters {
+        let mut parameters = DecodingParameters {
+            do_sample: self.do_sample,
+            num_beams: self.num_beams,
+            temperature: self.temperature,
+            top_k: self.top_k,
+            top_p: self.top_p,
+        };
+        match self.decoding_strategy {
+            None => {}
+            Some(DecodingStrategy::Greedy) => {
+                parameters.do_sample = false;
+                parameters.num_beams = 1;
+            }
+            Some(DecodingStrategy::Beam { num_beams }) => {
+                parameters.do_sample = false;
+                parameters.num_beams = num_beams;
+            }
+            Some(DecodingStrategy::Sample {
+                temperature,
+                top_k,
+                top_p,
+            }) => {
+                parameters.do_sample = true;
+                parameters.num_beams = 1;
+                parameters.temperature = temperature;
+                parameters.top_k = top_k;
+                parameters.top_p = top_p;
+            }
+        }
+        parameters
+    }
+
     pub(crate) fn validate(&self) {
-        assert!(self.temperature > 0f64, "temperature must positive");
-        assert!(
-            (self.top_p >= 0f64) & (self.top_p <= 1f64),
-            "top_p must be 0 and 1"
-        );
+        let DecodingParameters {
+            do_sample,
+            num_beams,
+            temperature,
+            top_p,
+            ..
+        } = self.decoding_parameters();
+        assert!(temperature > 0f64, "temperature must positive");
+        assert!((top_p >= 0f64) & (top_p <= 1f64), "top_p must be 0 and 1");
         assert!(
             self.repetition_penalty >= 1f64,
             "repetition_penalty must be greater than 1"
@@ -189,19 +282,19 @@ impl GenerateConfig {
             "num_return_sequences must be strictly greater than 0"
         );
         assert!(
-            self.num_beams > 0i64,
+            num_beams > 0i64,
             "num_beams must be strictly greater than 0"
         );
 
-        if !self.do_sample {
-            if self.num_beams == 1 {
+        if !do_sample {
+            if num_beams == 1 {
                 assert_eq!(
                     self.num_return_sequences, 1,
                     "num_return_sequences must be set to 1 for greedy decoding"
                 )
             } else {
                 assert!(
-                    self.num_beams >= self.num_return_sequences,
+                    num_beams >= self.num_return_sequences,
                     "num_return_sequences must be lower than the number of beams"
                 )
             }
@@ -209,7 +302,7 @@ impl GenerateConfig {
         if let Some(num_beam_groups_value) = self.num_beam_groups {
             if num_beam_groups_value > 1 {
                 assert_eq!(
-                    self.num_beams % num_beam_groups_value,
+                    num_beams % num_beam_groups_value,
                     0,
                     "num_beam_groups must be a multiple of num_beam_groups"
                 )
@@ -221,15 +314,63 @@ impl GenerateConfig {
 #[derive(Debug)]
 pub enum Cache {
     GPT2Cache(Option<Vec<Tensor>>),
+    #[cfg(feature = "bart")]
     BARTCache(Option<Vec<(Option<BartLayerState>, Option<BartLayerState>)>>),
+    #[cfg(feature = "t5")]
     T5Cache(Option<Vec<(Option<T5LayerState>, Option<T5LayerState>)>>),
+    #[cfg(feature = "xlnet")]
     XLNetCache(Option<Vec<Option<XLNetLayerState>>>),
+    #[cfg(feature = "reformer")]
     ReformerCache(Option<Vec<Option<ReformerLayerState>>>),
+    #[cfg(feature = "prophetnet")]
     ProphetNetCache(Option<Vec<(Option<ProphetNetLayerState>, Option<ProphetNetLayerState>)>>),
+    #[cfg(feature = "gpt-neo")]
     GPTNeoCache(Option<Vec<Option<GPTNeoLayerState>>>),
     None,
 }
 
+impl Cache {
+    /// Returns the number of positions held in the cache, 0 for an empty cache.
+    pub fn cached_length(&self) -> i64 {
+        match self {
+            Cache::GPT2Cache(Some(layer_states)) => layer_states
+                .first()
+                .map_or(0, |layer_state| layer_state.size()[3]),
+            #[cfg(feature = "bart")]
+            Cache::BARTCache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.0.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_key.size()[2]),
+            #[cfg(feature = "t5")]
+            Cache::T5Cache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.0.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_key.size()[2]),
+            #[cfg(feature = "xlnet")]
+            Cache::XLNetCache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_content.size()[0]),
+            #[cfg(feature = "reformer")]
+            Cache::ReformerCache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_states.size()[1]),
+            #[cfg(feature = "prophetnet")]
+            Cache::ProphetNetCache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.0.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_key.size()[2]),
+            #[cfg(feature = "gpt-neo")]
+            Cache::GPTNeoCache(Some(layer_states)) => layer_states
+                .first()
+                .and_then(|layer_state| layer_state.as_ref())
+                .map_or(0, |layer_state| layer_state.prev_key.size()[2]),
+            _ => 0,
+        }
+    }
+}
+
 pub(crate) mod private_generation_utils {
     use std::cmp::{max, min};
     use std::collections::HashMap;
@@ -241,7 +382,9 @@ pub(crate) mod private_generation_utils {
     use tch::{nn, Device, Kind, Tensor};
 
     use crate::pipelines::common::TokenizerOption;
-    use crate::pipelines::generation_utils::{BeamHypotheses, Cache, GenerateConfig, LMHeadModel};
+    use crate::pipelines::generation_utils::{
+        BeamHypotheses, Cache, DecoderStep, GenerateConfig, LMHeadModel,
+    };
 
     use super::ordered_float::OrderedFloat;
 
@@ -317,6 +460,21 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        fn observe_decoder_step(&self, step: i64, prepared_input: &PreparedInput) {
+            if let Some(decoder_step_hook) = &self.get_config().decoder_step_hook {
+                let input_length = prepared_input
+                    .prepared_decoder_input
+                    .as_ref()
+                    .or_else(|| prepared_input.prepared_input.as_ref())
+                    .map_or(0, |input| *input.size().last().unwrap());
+                decoder_step_hook(&DecoderStep {
+                    step,
+                    input_length,
+                    cached_length: prepared_input.prepared_past.cached_length(),
+                });
+            }
+        }
+
         fn encode_prompt_text<'a, S>(
             &self,
             prompt_text: S,
@@ -614,6 +772,7 @@ pub(crate) mod private_generation_utils {
                     past,
                     attention_mask.copy(),
                 );
+                self.observe_decoder_step(current_length - cur_len, &prepared_input);
                 let temp = self
                     .get_model()
                     .forward_t(
@@ -833,6 +992,7 @@ pub(crate) mod private_generation_utils {
                     past,
                     attention_mask.copy(),
                 );
+                self.observe_decoder_step(current_length - cur_len, &prepared_input);
                 let temp = self
                     .get_model()
                     .forward_t(
@@ -1216,6 +1376,7 @@ pub(crate) mod private_generation_utils {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Generated text output
 /// Contains generated text and an optional log-likelihood score for the generated sequence
 pub struct GeneratedTextOutput {
@@ -1224,6 +1385,7 @@ pub struct GeneratedTextOutput {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Generated indices output
 /// Contains generated indices and an optional log-likelihood score for the generated sequence
 pub struct GeneratedIndicesOutput {
@@ -1589,15 +1751,17 @@ pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
         let eos_token_ids = PrivateLanguageGenerator::get_eos_ids(self).clone();
 
         let config = PrivateLanguageGenerator::get_config(self);
-        let do_sample = config.do_sample;
+        let DecodingParameters {
+            do_sample,
+            num_beams,
+            temperature,
+            top_k,
+            top_p,
+        } = config.decoding_parameters();
         let num_return_sequences = config.num_return_sequences;
-        let num_beams = config.num_beams;
         let min_length = min_length.into().unwrap_or(config.min_length);
         let max_length = max_length.into().unwrap_or(config.max_length);
         let early_stopping = config.early_stopping;
-        let temperature = config.temperature;
-        let top_k = config.top_k;
-        let top_p = config.top_p;
         let repetition_penalty = config.repetition_penalty;
         let length_penalty = config.length_penalty;
         let no_repeat_ngram_size = config.no_repeat_ngram_size;
@@ -1976,3 +2140,60 @@ pub struct LMModelOutput {
     /// cached state for improved efficiency during decoding
     pub cache: Cache,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tch::Kind;
+
+    #[test]
+    fn test_decoding_strategy_parameters() {
+        let legacy_config = GenerateConfig {
+            do_sample: true,
+            num_beams: 4,
+            ..Default::default()
+        };
+        let parameters = legacy_config.decoding_parameters();
+        assert!(parameters.do_sample);
+        assert_eq!(parameters.num_beams, 4);
+
+        let greedy_config = GenerateConfig {
+            decoding_strategy: Some(DecodingStrategy::Greedy),
+            ..legacy_config
+        };
+        let parameters = greedy_config.decoding_parameters();
+        assert!(!parameters.do_sample);
+        assert_eq!(parameters.num_beams, 1);
+
+        let beam_config = GenerateConfig {
+            decoding_strategy: Some(DecodingStrategy::Beam { num_beams: 3 }),
+            ..greedy_config
+        };
+        let parameters = beam_config.decoding_parameters();
+        assert!(!parameters.do_sample);
+        assert_eq!(parameters.num_beams, 3);
+
+        let sample_config = GenerateConfig {
+            decoding_strategy: Some(DecodingStrategy::Sample {
+                temperature: 0.7,
+                top_k: 10,
+                top_p: 0.8,
+            }),
+            ..beam_config
+        };
+        let parameters = sample_config.decoding_parameters();
+        assert!(parameters.do_sample);
+        assert_eq!(parameters.num_beams, 1);
+        assert_eq!(parameters.temperature, 0.7);
+        assert_eq!(parameters.top_k, 10);
+        assert_eq!(parameters.top_p, 0.8);
+    }
+
+    #[test]
+    fn test_cached_length() {
+        let layer_state = Tensor::zeros(&[2, 1, 4, 7, 8], (Kind::Float, Device::Cpu));
+        assert_eq!(Cache::GPT2Cache(Some(vec![layer_state])).cached_length(), 7);
+        assert_eq!(Cache::GPT2Cache(None).cached_length(), 0);
+        assert_eq!(Cache::None.cached_length(), 0);
+    }
+}
@@ -0,0 +1,656 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Evaluation metrics for the classification pipelines
+//! Compares the predictions of a classification pipeline to gold labels, reporting the accuracy,
+//! the macro and micro-averaged F1 scores and the misclassified examples. The `evaluate` and
+//! `evaluate_multilabel` methods of the sequence classification and zero-shot classification
+//! pipelines run the predictions on a labelled dataset and build these reports.
+//!
+//! The F1 scores are computed over the labels found either in the gold labels or in the predictions.
+//! A label never predicted (or never expected) has an F1 score of 0.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! let dataset = [
+//!     ("This is great!".to_string(), "POSITIVE".to_string()),
+//!     ("This is terrible.".to_string(), "NEGATIVE".to_string()),
+//! ];
+//! let report = model.evaluate(&dataset)?;
+//! println!("accuracy: {:.3}, macro F1: {:.3}", report.accuracy, report.macro_f1);
+//! for error in report.errors.iter() {
+//!     println!("{:?}", error);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Large datasets can be evaluated without holding them in memory: `evaluate_iter` predicts the
+//! examples (texts with their gold class id) batch by batch and accumulates the confusion matrix in a
+//! `MetricsAccumulator`, returning `ClassificationMetrics` indexed by class id.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//! use std::fs::File;
+//! use std::io::{BufRead, BufReader};
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! let examples = BufReader::new(File::open("sst2_dev.tsv")?)
+//!     .lines()
+//!     .filter_map(|line| {
+//!         let line = line.ok()?;
+//!         let mut fields = line.split('\t');
+//!         Some((fields.next()?.to_string(), fields.next()?.parse::<i64>().ok()?))
+//!     });
+//! let metrics = model.evaluate_iter(examples, 256)?;
+//! println!("accuracy: {:.3}, macro F1: {:.3}", metrics.accuracy, metrics.macro_f1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::sequence_classification::Label;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Single-label example misclassified by the pipeline
+pub struct Misclassification {
+    /// Index of the example in the dataset
+    pub index: usize,
+    /// Text of the example
+    pub text: String,
+    /// Gold label
+    pub gold: String,
+    /// Predicted label
+    pub predicted: String,
+    /// Score of the predicted label
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Evaluation report of a single-label classification
+pub struct EvalReport {
+    /// Number of evaluated examples
+    pub num_examples: usize,
+    /// Proportion of examples for which the predicted label is the gold label
+    pub accuracy: f64,
+    /// Unweighted mean of the F1 score of each label
+    pub macro_f1: f64,
+    /// F1 score computed on the total true positives, false positives and false negatives
+    pub micro_f1: f64,
+    /// Number of examples for each gold label (first key) and predicted label (second key)
+    pub confusion_matrix: HashMap<String, HashMap<String, usize>>,
+    /// Misclassified examples, in the dataset order
+    pub errors: Vec<Misclassification>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Prediction counts of a label in a multi-label classification
+pub struct LabelCounts {
+    /// Number of examples for which the label is both expected and predicted
+    pub true_positives: usize,
+    /// Number of examples for which the label is predicted but not expected
+    pub false_positives: usize,
+    /// Number of examples for which the label is expected but not predicted
+    pub false_negatives: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Multi-label example for which the predicted labels differ from the gold labels
+pub struct MultilabelMisclassification {
+    /// Index of the example in the dataset
+    pub index: usize,
+    /// Text of the example
+    pub text: String,
+    /// Gold labels
+    pub gold: Vec<String>,
+    /// Predicted labels with their scores
+    pub predicted: Vec<(String, f64)>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Evaluation report of a multi-label classification
+pub struct MultilabelEvalReport {
+    /// Number of evaluated examples
+    pub num_examples: usize,
+    /// Proportion of examples for which the set of predicted labels is exactly the set of gold labels
+    pub accuracy: f64,
+    /// Unweighted mean of the F1 score of each label
+    pub macro_f1: f64,
+    /// F1 score computed on the total true positives, false positives and false negatives
+    pub micro_f1: f64,
+    /// Prediction counts of each label
+    pub label_counts: HashMap<String, LabelCounts>,
+    /// Misclassified examples, in the dataset order
+    pub errors: Vec<MultilabelMisclassification>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Classification metrics indexed by class id
+pub struct ClassificationMetrics {
+    /// Number of evaluated examples
+    pub num_examples: usize,
+    /// Proportion of examples for which the predicted class is the gold class
+    pub accuracy: f64,
+    /// Unweighted mean of the F1 score of each class found either in the gold classes or in the
+    /// predictions
+    pub macro_f1: f64,
+    /// Precision and recall of each class, indexed by class id (0 for a class never predicted,
+    /// respectively never expected)
+    pub per_class_precision_recall: Vec<(f64, f64)>,
+    /// Number of examples for each gold class id (rows) and predicted class id (columns)
+    pub confusion_matrix: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone)]
+/// # Accumulator of the confusion matrix of a single-label classification
+/// Updated with the gold and predicted class id of each example, so that the metrics of a dataset
+/// can be computed batch by batch without holding the dataset in memory.
+pub struct MetricsAccumulator {
+    confusion_matrix: Vec<Vec<usize>>,
+}
+
+impl MetricsAccumulator {
+    /// Creates an empty accumulator for a classification with `num_labels` classes
+    pub fn new(num_labels: usize) -> MetricsAccumulator {
+        MetricsAccumulator {
+            confusion_matrix: vec![vec![0; num_labels]; num_labels],
+        }
+    }
+
+    /// Records an example
+    ///
+    /// # Arguments
+    ///
+    /// * `gold` - gold class id of the example
+    /// * `predicted` - class id predicted for the example
+    ///
+    /// # Returns
+    ///
+    /// * `ValueError` if one of the class ids is out of range
+    pub fn update(&mut self, gold: i64, predicted: i64) -> Result<(), RustBertError> {
+        let num_labels = self.confusion_matrix.len() as i64;
+        for &(name, id) in [("gold", gold), ("predicted", predicted)].iter() {
+            if id < 0 || id >= num_labels {
+                return Err(RustBertError::ValueError(format!(
+                    "The {} class id {} is out of range for {} classes",
+                    name, id, num_labels
+                )));
+            }
+        }
+        self.confusion_matrix[gold as usize][predicted as usize] += 1;
+        Ok(())
+    }
+
+    /// Computes the metrics of the examples recorded so far, all set to 0 if there is none
+    pub fn metrics(&self) -> ClassificationMetrics {
+        let num_labels = self.confusion_matrix.len();
+        let num_examples = self.confusion_matrix.iter().flatten().sum::<usize>();
+        let correct = (0..num_labels)
+            .map(|class| self.confusion_matrix[class][class])
+            .sum::<usize>();
+        let label_counts = (0..num_labels)
+            .map(|class| {
+                let true_positives = self.confusion_matrix[class][class];
+                let expected = self.confusion_matrix[class].iter().sum::<usize>();
+                let predicted = self
+                    .confusion_matrix
+                    .iter()
+                    .map(|row| row[class])
+                    .sum::<usize>();
+                LabelCounts {
+                    true_positives,
+                    false_positives: predicted - true_positives,
+                    false_negatives: expected - true_positives,
+                }
+            })
+            .collect::<Vec<LabelCounts>>();
+        let found_labels = label_counts
+            .iter()
+            .filter(|counts| **counts != LabelCounts::default())
+            .collect::<Vec<&LabelCounts>>();
+        let macro_f1 = if found_labels.is_empty() {
+            0.0
+        } else {
+            found_labels.iter().map(|counts| counts.f1()).sum::<f64>() / found_labels.len() as f64
+        };
+        ClassificationMetrics {
+            num_examples,
+            accuracy: ratio(correct, num_examples),
+            macro_f1,
+            per_class_precision_recall: label_counts
+                .iter()
+                .map(|counts| {
+                    (
+                        ratio(
+                            counts.true_positives,
+                            counts.true_positives + counts.false_positives,
+                        ),
+                        ratio(
+                            counts.true_positives,
+                            counts.true_positives + counts.false_negatives,
+                        ),
+                    )
+                })
+                .collect(),
+            confusion_matrix: self.confusion_matrix.clone(),
+        }
+    }
+}
+
+impl LabelCounts {
+    /// F1 score of the label, 0 if the label is neither expected nor predicted
+    pub fn f1(&self) -> f64 {
+        f1_score(
+            self.true_positives,
+            self.false_positives,
+            self.false_negatives,
+        )
+    }
+}
+
+fn f1_score(true_positives: usize, false_positives: usize, false_negatives: usize) -> f64 {
+    let denominator = 2 * true_positives + false_positives + false_negatives;
+    if denominator > 0 {
+        2.0 * true_positives as f64 / denominator as f64
+    } else {
+        0.0
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// Macro and micro-averaged F1 scores over the labels, both 0 if there is no label
+fn averaged_f1(label_counts: &HashMap<String, LabelCounts>) -> (f64, f64) {
+    let macro_f1 = if label_counts.is_empty() {
+        0.0
+    } else {
+        label_counts.values().map(LabelCounts::f1).sum::<f64>() / label_counts.len() as f64
+    };
+    let total = label_counts
+        .values()
+        .fold(LabelCounts::default(), |total, counts| LabelCounts {
+            true_positives: total.true_positives + counts.true_positives,
+            false_positives: total.false_positives + counts.false_positives,
+            false_negatives: total.false_negatives + counts.false_negatives,
+        });
+    (macro_f1, total.f1())
+}
+
+fn check_lengths(num_examples: usize, num_predictions: usize) -> Result<(), RustBertError> {
+    if num_examples != num_predictions {
+        return Err(RustBertError::ValueError(format!(
+            "Got {} predictions for {} examples",
+            num_predictions, num_examples
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the evaluation report of a single-label classification.
+///
+/// # Arguments
+///
+/// * `dataset` - `&[(String, String)]` evaluated texts with their gold label
+/// * `predictions` - `&[Label]` label predicted for each example, in the dataset order
+///
+/// # Returns
+///
+/// * `EvalReport` of the predictions, with all metrics set to 0 for an empty dataset
+/// * `RustBertError` if the number of predictions differs from the number of examples
+pub fn single_label_report(
+    dataset: &[(String, String)],
+    predictions: &[Label],
+) -> Result<EvalReport, RustBertError> {
+    check_lengths(dataset.len(), predictions.len())?;
+    let mut confusion_matrix: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    let mut label_counts: HashMap<String, LabelCounts> = HashMap::new();
+    let mut errors = vec![];
+    for (index, ((text, gold), prediction)) in dataset.iter().zip(predictions.iter()).enumerate() {
+        *confusion_matrix
+            .entry(gold.clone())
+            .or_default()
+            .entry(prediction.text.clone())
+            .or_insert(0) += 1;
+        if *gold == prediction.text {
+            label_counts.entry(gold.clone()).or_default().true_positives += 1;
+        } else {
+            label_counts
+                .entry(gold.clone())
+                .or_default()
+                .false_negatives += 1;
+            label_counts
+                .entry(prediction.text.clone())
+                .or_default()
+                .false_positives += 1;
+            errors.push(Misclassification {
+                index,
+                text: text.clone(),
+                gold: gold.clone(),
+                predicted: prediction.text.clone(),
+                score: prediction.score,
+            });
+        }
+    }
+    let (macro_f1, micro_f1) = averaged_f1(&label_counts);
+    Ok(EvalReport {
+        num_examples: dataset.len(),
+        accuracy: ratio(dataset.len() - errors.len(), dataset.len()),
+        macro_f1,
+        micro_f1,
+        confusion_matrix,
+        errors,
+    })
+}
+
+/// Builds the evaluation report of a multi-label classification.
+///
+/// # Arguments
+///
+/// * `dataset` - `&[(String, Vec<String>)]` evaluated texts with their gold labels
+/// * `predictions` - `&[Vec<Label>]` labels predicted for each example, in the dataset order
+///
+/// # Returns
+///
+/// * `MultilabelEvalReport` of the predictions, with all metrics set to 0 for an empty dataset
+/// * `RustBertError` if the number of predictions differs from the number of examples
+pub fn multilabel_report(
+    dataset: &[(String, Vec<String>)],
+    predictions: &[Vec<Label>],
+) -> Result<MultilabelEvalReport, RustBertError> {
+    check_lengths(dataset.len(), predictions.len())?;
+    let mut label_counts: HashMap<String, LabelCounts> = HashMap::new();
+    let mut errors = vec![];
+    for (index, ((text, gold), prediction)) in dataset.iter().zip(predictions.iter()).enumerate() {
+        let gold_labels = gold.iter().map(String::as_str).collect::<HashSet<&str>>();
+        let predicted_labels = prediction
+            .iter()
+            .map(|label| label.text.as_str())
+            .collect::<HashSet<&str>>();
+        for &label in gold_labels.union(&predicted_labels) {
+            let counts = label_counts.entry(label.to_string()).or_default();
+            match (
+                gold_labels.contains(label),
+                predicted_labels.contains(label),
+            ) {
+                (true, true) => counts.true_positives += 1,
+                (false, true) => counts.false_positives += 1,
+                _ => counts.false_negatives += 1,
+            }
+        }
+        if gold_labels != predicted_labels {
+            errors.push(MultilabelMisclassification {
+                index,
+                text: text.clone(),
+                gold: gold_labels
+                    .iter()
+                    .map(|label| label.to_string())
+                    .collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect(),
+                predicted: prediction
+                    .iter()
+                    .map(|label| (label.text.clone(), label.score))
+                    .collect(),
+            });
+        }
+    }
+    let (macro_f1, micro_f1) = averaged_f1(&label_counts);
+    Ok(MultilabelEvalReport {
+        num_examples: dataset.len(),
+        accuracy: ratio(dataset.len() - errors.len(), dataset.len()),
+        macro_f1,
+        micro_f1,
+        label_counts,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn label(text: &str, score: f64) -> Label {
+        Label {
+            text: text.to_string(),
+            score,
+            id: 0,
+            sentence: 0,
+        }
+    }
+
+    fn single_label_dataset(gold: &[&str]) -> Vec<(String, String)> {
+        gold.iter()
+            .enumerate()
+            .map(|(index, gold)| (format!("example {}", index), gold.to_string()))
+            .collect()
+    }
+
+    fn multilabel_dataset(gold: &[&[&str]]) -> Vec<(String, Vec<String>)> {
+        gold.iter()
+            .enumerate()
+            .map(|(index, gold)| {
+                (
+                    format!("example {}", index),
+                    gold.iter().map(|label| label.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_label_report() -> anyhow::Result<()> {
+        let dataset = single_label_dataset(&["cat", "cat", "dog", "dog", "bird"]);
+        let predictions = [
+            label("cat", 0.9),
+            label("dog", 0.6),
+            label("dog", 0.8),
+            label("dog", 0.7),
+            label("cat", 0.55),
+        ];
+        let report = single_label_report(&dataset, &predictions)?;
+
+        assert_eq!(report.num_examples, 5);
+        assert!((report.accuracy - 0.6).abs() < 1e-9);
+        //    F1 scores: cat 2 / (2 + 1 + 1), dog 4 / (4 + 1 + 0), bird 0 (never predicted)
+        assert!((report.macro_f1 - (0.5 + 0.8 + 0.0) / 3.0).abs() < 1e-9);
+        //    The micro-averaged F1 score of a single-label classification is its accuracy
+        assert!((report.micro_f1 - 0.6).abs() < 1e-9);
+        assert_eq!(report.confusion_matrix["cat"]["cat"], 1);
+        assert_eq!(report.confusion_matrix["cat"]["dog"], 1);
+        assert_eq!(report.confusion_matrix["dog"]["dog"], 2);
+        assert_eq!(report.confusion_matrix["bird"]["cat"], 1);
+        assert!(!report.confusion_matrix["dog"].contains_key("cat"));
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|error| (error.index, error.predicted.as_str(), error.score))
+                .collect::<Vec<_>>(),
+            vec![(1, "dog", 0.6), (4, "cat", 0.55)]
+        );
+        assert_eq!(report.errors[1].gold, "bird");
+        assert_eq!(report.errors[1].text, "example 4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_label_report_edge_cases() -> anyhow::Result<()> {
+        //    Single class, all correct
+        let dataset = single_label_dataset(&["cat", "cat"]);
+        let report = single_label_report(&dataset, &[label("cat", 0.9), label("cat", 0.8)])?;
+        assert_eq!(report.accuracy, 1.0);
+        assert_eq!(report.macro_f1, 1.0);
+        assert_eq!(report.micro_f1, 1.0);
+        assert!(report.errors.is_empty());
+
+        //    Single gold class, predictions of a label absent from the gold labels
+        let report = single_label_report(&dataset, &[label("dog", 0.9), label("cat", 0.8)])?;
+        assert_eq!(report.accuracy, 0.5);
+        //    F1 scores: cat 2 / (2 + 0 + 1), dog 0
+        assert!((report.macro_f1 - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.micro_f1, 0.5);
+
+        //    Empty dataset
+        let report = single_label_report(&[], &[])?;
+        assert_eq!(report.num_examples, 0);
+        assert_eq!(report.accuracy, 0.0);
+        assert_eq!(report.macro_f1, 0.0);
+        assert_eq!(report.micro_f1, 0.0);
+        assert!(report.confusion_matrix.is_empty());
+
+        //    Mismatched number of predictions
+        assert!(single_label_report(&dataset, &[label("cat", 0.9)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilabel_report() -> anyhow::Result<()> {
+        let dataset = multilabel_dataset(&[&["sports", "politics"], &["economics"], &[], &[]]);
+        let predictions = [
+            vec![label("politics", 0.9), label("sports", 0.7)],
+            vec![label("economics", 0.8), label("politics", 0.6)],
+            vec![],
+            vec![label("sports", 0.55)],
+        ];
+        let report = multilabel_report(&dataset, &predictions)?;
+
+        assert_eq!(report.num_examples, 4);
+        //    The first and third examples are exact matches, regardless of the order of the labels
+        assert_eq!(report.accuracy, 0.5);
+        assert_eq!(
+            report.label_counts["sports"],
+            LabelCounts {
+                true_positives: 1,
+                false_positives: 1,
+                false_negatives: 0,
+            }
+        );
+        assert_eq!(
+            report.label_counts["politics"],
+            LabelCounts {
+                true_positives: 1,
+                false_positives: 1,
+                false_negatives: 0,
+            }
+        );
+        assert_eq!(
+            report.label_counts["economics"],
+            LabelCounts {
+                true_positives: 1,
+                false_positives: 0,
+                false_negatives: 0,
+            }
+        );
+        //    F1 scores: sports 2 / 3, politics 2 / 3, economics 1
+        assert!((report.macro_f1 - (2.0 / 3.0 + 2.0 / 3.0 + 1.0) / 3.0).abs() < 1e-9);
+        //    Micro-averaged: 2 * 3 / (2 * 3 + 2 + 0)
+        assert!((report.micro_f1 - 0.75).abs() < 1e-9);
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|error| error.index)
+                .collect::<Vec<usize>>(),
+            vec![1, 3]
+        );
+        assert_eq!(report.errors[1].gold, Vec::<String>::new());
+        assert_eq!(
+            report.errors[1].predicted,
+            vec![("sports".to_string(), 0.55)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_metrics_accumulator() -> anyhow::Result<()> {
+        //    Same predictions as `test_single_label_report` with cat = 0, dog = 1, bird = 2 and an
+        //    additional class never expected nor predicted
+        let mut accumulator = MetricsAccumulator::new(4);
+        assert_eq!(accumulator.metrics().num_examples, 0);
+        assert_eq!(accumulator.metrics().accuracy, 0.0);
+        assert_eq!(accumulator.metrics().macro_f1, 0.0);
+        for &(gold, predicted) in [(0, 0), (0, 1), (1, 1), (1, 1), (2, 0)].iter() {
+            accumulator.update(gold, predicted)?;
+        }
+        let metrics = accumulator.metrics();
+
+        assert_eq!(metrics.num_examples, 5);
+        assert!((metrics.accuracy - 0.6).abs() < 1e-9);
+        assert!((metrics.macro_f1 - (0.5 + 0.8 + 0.0) / 3.0).abs() < 1e-9);
+        assert_eq!(
+            metrics.per_class_precision_recall,
+            vec![(0.5, 0.5), (2.0 / 3.0, 1.0), (0.0, 0.0), (0.0, 0.0)]
+        );
+        assert_eq!(
+            metrics.confusion_matrix,
+            vec![
+                vec![1, 1, 0, 0],
+                vec![0, 2, 0, 0],
+                vec![1, 0, 0, 0],
+                vec![0, 0, 0, 0]
+            ]
+        );
+
+        assert!(accumulator.update(4, 0).is_err());
+        assert!(accumulator.update(0, -1).is_err());
+        assert_eq!(accumulator.metrics(), metrics);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilabel_report_edge_cases() -> anyhow::Result<()> {
+        //    Gold label never predicted
+        let dataset = multilabel_dataset(&[&["sports"], &["sports"]]);
+        let report = multilabel_report(&dataset, &[vec![], vec![]])?;
+        assert_eq!(report.accuracy, 0.0);
+        assert_eq!(report.label_counts["sports"].false_negatives, 2);
+        assert_eq!(report.macro_f1, 0.0);
+        assert_eq!(report.micro_f1, 0.0);
+        assert_eq!(report.errors.len(), 2);
+
+        //    No label expected or predicted
+        let dataset = multilabel_dataset(&[&[]]);
+        let report = multilabel_report(&dataset, &[vec![]])?;
+        assert_eq!(report.accuracy, 1.0);
+        assert!(report.label_counts.is_empty());
+        assert_eq!(report.macro_f1, 0.0);
+        assert!(report.errors.is_empty());
+
+        //    Duplicated gold labels are counted once
+        let dataset = multilabel_dataset(&[&["sports", "sports"]]);
+        let report = multilabel_report(&dataset, &[vec![label("sports", 0.9)]])?;
+        assert_eq!(report.accuracy, 1.0);
+        assert_eq!(report.label_counts["sports"].true_positives, 1);
+        assert_eq!(report.macro_f1, 1.0);
+
+        assert!(multilabel_report(&dataset, &[]).is_err());
+        Ok(())
+    }
+}
@@ -30,6 +30,7 @@
 //!     start: 13,
 //!     end: 21,
 //!     answer: String::from("Amsterdam"),
+//!     context_index: 0,
 //! }]
 //! # ;
 //! ```
@@ -201,7 +202,7 @@
 //!      candidate_labels,
 //!      None,
 //!      128,
-//!  );
+//!  )?;
 //! # Ok(())
 //! # }
 //! ```
@@ -277,7 +278,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_model.predict(&input);
+//! let output = sentiment_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -316,7 +317,7 @@
 //!     "My name is Amy. I live in Paris.",
 //!     "Paris is a city in France.",
 //! ];
-//! let output = ner_model.predict(&input);
+//! let output = ner_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -330,11 +331,15 @@
 //!             word: String::from("Amy"),
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
+//!             start: 11,
+//!             end: 14,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
+//!             start: 26,
+//!             end: 31,
 //!         },
 //!     ],
 //!     [
@@ -342,11 +347,15 @@
 //!             word: String::from("Paris"),
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
+//!             start: 0,
+//!             end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
+//!             start: 19,
+//!             end: 25,
 //!         },
 //!     ],
 //! ]
@@ -360,7 +369,7 @@
 //! # fn main() -> anyhow::Result<()> {
 //! let pos_model = POSModel::new(Default::default())?;
 //! let input = ["My name is Bob"];
-//! let output = pos_model.predict(&input);
+//! let output = pos_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -393,16 +402,71 @@
 //! # ;
 //! ```
 
+pub mod batch_runner;
+#[cfg(all(feature = "question-answering", feature = "sequence-classification"))]
+pub mod boolean_question_answering;
+pub mod chunked_inference;
 pub mod common;
+#[cfg(feature = "conversation")]
 pub mod conversation;
+pub mod denylist;
+#[cfg(feature = "arrow")]
+pub mod export;
+pub mod fair_scheduler;
+#[cfg(feature = "feature-extraction")]
+pub mod feature_extraction;
+#[cfg(feature = "gpt2")]
 pub mod generation_utils;
+#[cfg(feature = "sequence-classification")]
+pub mod metrics;
+pub mod model_router;
+#[cfg(feature = "ner")]
 pub mod ner;
+#[cfg(feature = "ner")]
+pub mod ner_ensemble;
+#[cfg(feature = "pos-tagging")]
 pub mod pos_tagging;
+#[cfg(feature = "question-answering")]
 pub mod question_answering;
+#[cfg(feature = "sequence-classification")]
+pub mod records;
+#[cfg(feature = "sentiment")]
 pub mod sentiment;
+#[cfg(feature = "sequence-classification")]
 pub mod sequence_classification;
+#[cfg(any(
+    feature = "conversation",
+    feature = "feature-extraction",
+    feature = "question-answering",
+    feature = "sequence-classification",
+    feature = "summarization",
+    feature = "text-generation",
+    feature = "token-classification",
+    feature = "translation",
+))]
+pub mod spec;
+#[cfg(feature = "sequence-classification")]
+pub mod state;
+#[cfg(feature = "summarization")]
 pub mod summarization;
+#[cfg(feature = "text-generation")]
 pub mod text_generation;
+#[cfg(feature = "token-classification")]
 pub mod token_classification;
+#[cfg(feature = "translation")]
 pub mod translation;
+pub mod validation;
+#[cfg(feature = "zero-shot")]
 pub mod zero_shot_classification;
+
+#[cfg(any(
+    feature = "conversation",
+    feature = "feature-extraction",
+    feature = "question-answering",
+    feature = "sequence-classification",
+    feature = "summarization",
+    feature = "text-generation",
+    feature = "token-classification",
+    feature = "translation",
+))]
+pub use spec::from_spec;
@@ -0,0 +1,315 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Length-based routing between pipelines
+//! Dispatches each input to one of several pipelines depending on its number of tokens, e.g. to
+//! process short texts with a small model and long texts with a Longformer model. The inputs of a
+//! batch are grouped by target pipeline, and the outputs are returned in the order of the inputs.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+//! use rust_bert::pipelines::model_router::ModelRouter;
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let short_model = SequenceClassificationModel::new(Default::default())?;
+//! let long_model = SequenceClassificationModel::new(Default::default())?;
+//! let tokenizer = TokenizerOption::from_file(
+//!     ModelType::DistilBert,
+//!     "path/to/vocab.txt",
+//!     None,
+//!     true,
+//!     None,
+//!     None,
+//! )?;
+//!
+//! let mut router = ModelRouter::new(tokenizer);
+//! router.add_route(128, short_model);
+//! router.add_route(4096, long_model);
+//!
+//! let labels = router.predict(&["A short text", "A very long document"])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::batch_runner::Pipeline;
+use crate::pipelines::common::TokenizerOption;
+
+struct Route<O> {
+    max_tokens: usize,
+    pipeline: Box<dyn Pipeline<Output = O>>,
+}
+
+/// # Router dispatching inputs to pipelines according to their length
+/// Each route is registered with a maximum number of tokens. An input is processed by the route
+/// with the smallest maximum number of tokens greater or equal to its token count (as returned by
+/// `TokenizerOption::count_tokens`), or by the route with the largest maximum if it is longer than
+/// all routes (the pipeline truncating it).
+pub struct ModelRouter<O> {
+    tokenizer: TokenizerOption,
+    routes: Vec<Route<O>>,
+}
+
+impl<O> ModelRouter<O> {
+    /// Build a new `ModelRouter` without routes
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenizer` - `TokenizerOption` used to count the tokens of the inputs
+    pub fn new(tokenizer: TokenizerOption) -> ModelRouter<O> {
+        ModelRouter {
+            tokenizer,
+            routes: vec![],
+        }
+    }
+
+    /// Registers a pipeline processing the inputs of at most `max_tokens` tokens that are longer
+    /// than the maximum of the previous route. Registering a route with the same maximum as an
+    /// existing route replaces its pipeline.
+    pub fn add_route<P>(&mut self, max_tokens: usize, pipeline: P) -> &mut Self
+    where
+        P: Pipeline<Output = O> + 'static,
+    {
+        let route = Route {
+            max_tokens,
+            pipeline: Box::new(pipeline),
+        };
+        match self
+            .routes
+            .binary_search_by_key(&max_tokens, |route| route.max_tokens)
+        {
+            Ok(position) => self.routes[position] = route,
+            Err(position) => self.routes.insert(position, route),
+        }
+        self
+    }
+
+    /// Returns the maximum number of tokens of the registered routes, in increasing order
+    pub fn route_limits(&self) -> Vec<usize> {
+        self.routes.iter().map(|route| route.max_tokens).collect()
+    }
+
+    /// Returns the index (in the order of `route_limits`) of the route processing a text, or
+    /// `None` if no route is registered
+    pub fn route_index(&self, text: &str) -> Option<usize> {
+        if self.routes.is_empty() {
+            return None;
+        }
+        let num_tokens = self.tokenizer.count_tokens(text);
+        Some(
+            self.routes
+                .iter()
+                .position(|route| num_tokens <= route.max_tokens)
+                .unwrap_or(self.routes.len() - 1),
+        )
+    }
+
+    /// Processes a batch of texts, each text being dispatched to the route matching its length.
+    /// Each pipeline is called once with all its inputs, and the outputs are returned in the order
+    /// of the inputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - `&[&str]` Array of texts to process
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<O>` outputs of the pipelines, one per input. Returns an `InvalidConfigurationError`
+    /// if no route is registered.
+    pub fn predict(&self, inputs: &[&str]) -> Result<Vec<O>, RustBertError> {
+        if self.routes.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "No route registered in the model router".to_string(),
+            ));
+        }
+
+        let mut route_inputs: Vec<Vec<(usize, &str)>> = vec![vec![]; self.routes.len()];
+        for (position, &input) in inputs.iter().enumerate() {
+            let route_index = self.route_index(input).unwrap();
+            route_inputs[route_index].push((position, input));
+        }
+
+        let mut outputs: Vec<Option<O>> = (0..inputs.len()).map(|_| None).collect();
+        for (route, route_inputs) in self.routes.iter().zip(route_inputs.into_iter()) {
+            if route_inputs.is_empty() {
+                continue;
+            }
+            let texts = route_inputs
+                .iter()
+                .map(|(_, text)| *text)
+                .collect::<Vec<&str>>();
+            let route_outputs = route.pipeline.process(&texts)?;
+            if route_outputs.len() != texts.len() {
+                return Err(RustBertError::ValueError(format!(
+                    "Pipeline of the route with at most {} tokens returned {} outputs for {} inputs",
+                    route.max_tokens,
+                    route_outputs.len(),
+                    texts.len()
+                )));
+            }
+            for ((position, _), output) in route_inputs.into_iter().zip(route_outputs) {
+                outputs[position] = Some(output);
+            }
+        }
+        Ok(outputs.into_iter().map(Option::unwrap).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pipelines::common::ModelType;
+    use std::sync::{Arc, Mutex};
+
+    /// Stand-in for a classification model, recording the batches it processes
+    struct RecordingPipeline {
+        name: &'static str,
+        batches: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl Pipeline for RecordingPipeline {
+        type Output = String;
+
+        fn process(&self, inputs: &[&str]) -> Result<Vec<String>, RustBertError> {
+            self.batches
+                .lock()
+                .unwrap()
+                .push(inputs.iter().map(|input| input.to_string()).collect());
+            Ok(inputs
+                .iter()
+                .map(|input| format!("{}:{}", self.name, input))
+                .collect())
+        }
+    }
+
+    fn test_tokenizer(directory: &tempfile::TempDir) -> anyhow::Result<TokenizerOption> {
+        let vocab_path = directory.path().join("vocab.txt");
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "one", "two", "three", "four", "five",
+            "six", "##s",
+        ];
+        std::fs::write(&vocab_path, vocab.join("\n"))?;
+        Ok(TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn test_router(
+        directory: &tempfile::TempDir,
+    ) -> anyhow::Result<(
+        ModelRouter<String>,
+        Arc<Mutex<Vec<Vec<String>>>>,
+        Arc<Mutex<Vec<Vec<String>>>>,
+    )> {
+        let short_batches = Arc::new(Mutex::new(vec![]));
+        let long_batches = Arc::new(Mutex::new(vec![]));
+        let mut router = ModelRouter::new(test_tokenizer(directory)?);
+        router
+            .add_route(
+                8,
+                RecordingPipeline {
+                    name: "long",
+                    batches: long_batches.clone(),
+                },
+            )
+            .add_route(
+                3,
+                RecordingPipeline {
+                    name: "short",
+                    batches: short_batches.clone(),
+                },
+            );
+        Ok((router, short_batches, long_batches))
+    }
+
+    #[test]
+    fn test_count_tokens() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let tokenizer = test_tokenizer(&directory)?;
+
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        assert_eq!(tokenizer.count_tokens("one two"), 2);
+        assert_eq!(tokenizer.count_tokens("ones twos"), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_routing_boundaries() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let (router, _, _) = test_router(&directory)?;
+
+        assert_eq!(router.route_limits(), vec![3, 8]);
+        assert_eq!(router.route_index("one two three"), Some(0));
+        assert_eq!(router.route_index("one two three four"), Some(1));
+        assert_eq!(router.route_index("ones twos threes four"), Some(1));
+        assert_eq!(
+            router.route_index("one two three four five six one two three"),
+            Some(1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_preserved() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let (router, short_batches, long_batches) = test_router(&directory)?;
+
+        let inputs = [
+            "one two three four",
+            "one",
+            "two three",
+            "one two three four five",
+            "six",
+        ];
+        let outputs = router.predict(&inputs)?;
+
+        assert_eq!(
+            outputs,
+            vec![
+                "long:one two three four",
+                "short:one",
+                "short:two three",
+                "long:one two three four five",
+                "short:six",
+            ]
+        );
+        assert_eq!(
+            *short_batches.lock().unwrap(),
+            vec![vec!["one", "two three", "six"]]
+        );
+        assert_eq!(
+            *long_batches.lock().unwrap(),
+            vec![vec!["one two three four", "one two three four five"]]
+        );
+        assert!(router.predict(&[])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_route() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let router: ModelRouter<String> = ModelRouter::new(test_tokenizer(&directory)?);
+
+        assert_eq!(router.route_index("one"), None);
+        assert!(matches!(
+            router.predict(&["one"]),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+}
@@ -17,6 +17,7 @@
 //! - German
 //! - Spanish
 //! - Dutch
+//! - Multilingual (XLM-RoBERTa)
 //!
 //! The default NER mode is an English BERT cased large model finetuned on CoNNL03, contributed by the [MDZ Digital Library team at the Bavarian State Library](https://github.com/dbmdz)
 //! All resources for this model can be downloaded using the Python utility script included in this repository.
@@ -34,7 +35,7 @@
 //!     "My name is Amy. I live in Paris.",
 //!     "Paris is a city in France.",
 //! ];
-//! let output = ner_model.predict(&input);
+//! let output = ner_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -48,11 +49,15 @@
 //!             word: String::from("Amy"),
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
+//!             start: 11,
+//!             end: 14,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
+//!             start: 26,
+//!             end: 31,
 //!         },
 //!     ],
 //!     [
@@ -60,11 +65,15 @@
 //!             word: String::from("Paris"),
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
+//!             start: 0,
+//!             end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
+//!             start: 19,
+//!             end: 25,
 //!         },
 //!     ],
 //! ]
@@ -107,7 +116,7 @@
 //!     "Mein Name ist Amélie. Ich lebe in Paris.",
 //!     "Paris ist eine Stadt in Frankreich.",
 //! ];
-//! let output = ner_model.predict(&input);
+//! let output = ner_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -119,11 +128,68 @@
 //! German| XLM_ROBERTA_NER_DE |
 //! Spanish| XLM_ROBERTA_NER_ES |
 //! Dutch| XLM_ROBERTA_NER_NL |
+//!
+//! The English model is also available as a preset. It is fine-tuned on the English CoNLL-2003
+//! annotations only: the multilingual pre-training of XLM-RoBERTa provides some transfer to other
+//! languages, but the German, Spanish and Dutch models above should be preferred for these
+//! languages.
+//! ```no_run
+//! use rust_bert::pipelines::ner::NERModel;
+//! use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let ner_model = NERModel::new(TokenClassificationConfig::xlm_roberta_english_ner())?;
+//!
+//! let input = [
+//!     "My name is Amy. I live in Paris.",
+//!     "Paris is a city in France.",
+//! ];
+//! let output = ner_model.predict(&input)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Models converted with generic label names (`LABEL_0`, `LABEL_1`...) in their configuration file
+//! can be given the label names of their training dataset with a `label_mapping` covering every
+//! output of the classifier head. The names are used to decode the entities, for example for a
+//! model fine-tuned on CoNLL-2003:
+//! ```no_run
+//! use rust_bert::pipelines::ner::NERModel;
+//! use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+//! use std::collections::HashMap;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let conll_labels = [
+//!     "O", "B-MISC", "I-MISC", "B-PER", "I-PER", "B-ORG", "I-ORG", "B-LOC", "I-LOC",
+//! ];
+//! let label_mapping = conll_labels
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(id, label)| (id as i64, label.to_string()))
+//!     .collect::<HashMap<i64, String>>();
+//! let ner_model = NERModel::new(TokenClassificationConfig {
+//!     label_mapping: Some(label_mapping),
+//!     ..Default::default()
+//! })?;
+//! let output = ner_model.predict_full_entities(&["My name is Amy. I live in Paris."])?;
+//! # Ok(())
+//! # }
+//! ```
 
 use crate::common::error::RustBertError;
-use crate::pipelines::token_classification::{TokenClassificationConfig, TokenClassificationModel};
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::ner_ensemble::EntitySpan;
+use crate::pipelines::token_classification::{
+    AggregationStrategy, TaggingScheme, Token, TokenClassificationConfig, TokenClassificationModel,
+    TokenScores,
+};
+use rust_tokenizers::{Mask, Offset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Entity generated by a `NERModel`
 pub struct Entity {
     /// String representation of the Entity
@@ -132,6 +198,30 @@ pub struct Entity {
     pub score: f64,
     /// Entity label (e.g. ORG, LOC...)
     pub label: String,
+    /// Offset of the start of the entity in the input text, in characters (not bytes or UTF-16
+    /// code units). See `pipelines::common::char_to_byte` and `pipelines::common::char_to_utf16`
+    /// for conversions. Offsets refer to the original input, before any normalization (e.g.
+    /// lower-casing or accents stripping) by the tokenizer.
+    pub start: usize,
+    /// Offset of the end of the entity in the input text, in characters (exclusive). Entities
+    /// that cannot be located in the input (no offsets returned by the tokenizer) have a `start`
+    /// and `end` of 0.
+    pub end: usize,
+}
+
+impl Entity {
+    fn from_token(token: Token) -> Entity {
+        let (start, end) = token.offset.map_or((0, 0), |offset| {
+            (offset.begin as usize, offset.end as usize)
+        });
+        Entity {
+            word: token.text,
+            score: token.score,
+            label: token.label,
+            start,
+            end,
+        }
+    }
 }
 
 //type alias for some backward compatibility
@@ -166,6 +256,44 @@ impl NERModel {
         })
     }
 
+    /// Build a new `NERModel` with a tokenizer built by the caller. The vocabulary and merges
+    /// resources of the configuration are not used, and the tokenizer must match the model type of
+    /// the configuration (see `TokenClassificationModel::new_with_tokenizer`).
+    ///
+    /// # Arguments
+    ///
+    /// * `ner_config` - `NERConfig` object containing the resource references (model, configuration) and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` used to encode the inputs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::Bert,
+    ///     "path/to/vocab.txt",
+    ///     None,
+    ///     false,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let ner_model = NERModel::new_with_tokenizer(Default::default(), tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        ner_config: NERConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<NERModel, RustBertError> {
+        let model = TokenClassificationModel::new_with_tokenizer(ner_config, tokenizer)?;
+        Ok(NERModel {
+            token_classification_model: model,
+        })
+    }
+
     /// Extract entities from a text
     ///
     /// # Arguments
@@ -175,6 +303,7 @@ impl NERModel {
     /// # Returns
     ///
     /// * `Vec<Vec<Entity>>` containing extracted entities
+    /// * `RustBertError` in the cases described for `TokenClassificationModel::predict`
     ///
     /// # Example
     ///
@@ -187,34 +316,492 @@ impl NERModel {
     ///     "My name is Amy. I live in Paris.",
     ///     "Paris is a city in France.",
     /// ];
-    /// let output = ner_model.predict(&input);
+    /// let output = ner_model.predict(&input)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Vec<Entity>>
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Vec<Entity>>, RustBertError>
     where
         S: AsRef<[&'a str]>,
     {
-        self.token_classification_model
-            .predict(input, true, false)
+        Ok(self
+            .token_classification_model
+            .predict(input, true, false)?
             .into_iter()
             .map(|sequence_tokens| {
                 sequence_tokens
                     .into_iter()
                     .filter(|token| token.label != "O")
-                    .map(|token| Entity {
-                        word: token.text,
-                        score: token.score,
-                        label: token.label,
-                    })
+                    .map(Entity::from_token)
                     .collect::<Vec<Entity>>()
             })
-            .collect::<Vec<Vec<Entity>>>()
+            .collect::<Vec<Vec<Entity>>>())
+    }
+
+    /// Extract entities from a stream of texts, e.g. the lines of a corpus too large to be held in
+    /// memory. The texts are processed in batches of `batch_size` texts as described in
+    /// `TokenClassificationModel::predict_iter`, and the entities of each text are yielded in the
+    /// input order.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Iterator over the texts to extract entities from
+    /// * `batch_size` - Number of texts buffered and processed together
+    ///
+    /// # Returns
+    ///
+    /// * Iterator over the entities (or the error) of each text, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use std::fs::File;
+    /// use std::io::{BufRead, BufReader};
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let lines = BufReader::new(File::open("corpus.txt")?).lines();
+    /// for entities in ner_model.predict_iter(lines.map(Result::unwrap), 32) {
+    ///     println!("{:?}", entities?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_iter<'a, I>(
+        &'a self,
+        inputs: I,
+        batch_size: usize,
+    ) -> impl Iterator<Item = Result<Vec<Entity>, RustBertError>> + 'a
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        self.token_classification_model
+            .predict_iter(inputs, batch_size)
+            .map(|tokens| {
+                Ok(tokens?
+                    .into_iter()
+                    .filter(|token| token.label != "O")
+                    .map(Entity::from_token)
+                    .collect::<Vec<Entity>>())
+            })
+    }
+
+    /// Extract entities from a text, passing the entities of each batch of texts to a callback as
+    /// soon as the batch is processed instead of collecting the entities of all texts (see
+    /// `TokenClassificationModel::predict_with_callback`). The entities are the same as the ones
+    /// returned by `predict`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `f` - Callback called with the index of the text and each of its entities, in the input order
+    ///
+    /// # Returns
+    ///
+    /// * `RustBertError` in the cases described for `TokenClassificationModel::predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// ner_model.predict_with_callback(&input, |index, entity| {
+    ///     println!("{}: {} ({})", index, entity.word, entity.label)
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_callback<'a, S, F>(&self, input: S, mut f: F) -> Result<(), RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+        F: FnMut(usize, Entity),
+    {
+        self.token_classification_model
+            .predict_with_callback(input, |index, token| {
+                if token.label != "O" {
+                    f(index, Entity::from_token(token))
+                }
+            })
+    }
+
+    /// Extract entities from a text at the word level: the predictions of the sub-tokens of each
+    /// word delimited by whitespace are aggregated following the `word_aggregation_strategy` of
+    /// the configuration, and the entities are the full words of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing an entity for each word not labelled `O`
+    /// * `RustBertError` in the cases described for `TokenClassificationModel::predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let output = ner_model.predict_words(&["Amy moved to Saint-Étienne."])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_words<'a, S>(&self, input: S) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        Ok(self
+            .token_classification_model
+            .predict_words(input)?
+            .into_iter()
+            .map(|sequence_words| {
+                sequence_words
+                    .into_iter()
+                    .filter(|word| word.label != "O")
+                    .map(Entity::from_token)
+                    .collect::<Vec<Entity>>()
+            })
+            .collect::<Vec<Vec<Entity>>>())
+    }
+
+    /// Extract full entities from a text, aggregating the tokens following the
+    /// `aggregation_strategy` of the configuration. For all strategies but `None`, consecutive
+    /// tokens sharing the same entity group (the label without its `B-`/`I-` prefix) are merged
+    /// into a single `Entity`, labelled by the entity group. The entities are decoded following
+    /// the `label_aggregation_scheme` of the configuration (e.g. a `B-` token starts a new
+    /// entity), except for the continuations of words (sub-tokens are never split across
+    /// entities).
+    /// The word and score of a merged entity are the input text it spans and the mean score of
+    /// its tokens. The `min_score` of the configuration applies to the score of the merged
+    /// entities, not to the scores of their tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities
+    /// * `RustBertError` in the cases described for `TokenClassificationModel::predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use rust_bert::pipelines::token_classification::{
+    ///     AggregationStrategy, TokenClassificationConfig,
+    /// };
+    ///
+    /// let ner_model = NERModel::new(TokenClassificationConfig {
+    ///     aggregation_strategy: AggregationStrategy::First,
+    ///     ..Default::default()
+    /// })?;
+    /// let input = ["Alice moved to New York City last year."];
+    /// let output = ner_model.predict_full_entities(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_full_entities<'a, S>(&self, input: S) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        let aggregation_strategy = self.token_classification_model.aggregation_strategy();
+        let scheme = self.token_classification_model.label_aggregation_scheme();
+        let min_score = self.token_classification_model.min_score();
+        Ok(self
+            .token_classification_model
+            .predict_entity_tokens(input)?
+            .into_iter()
+            .zip(input.iter())
+            .map(|(sequence_tokens, text)| match aggregation_strategy {
+                AggregationStrategy::None => sequence_tokens
+                    .into_iter()
+                    .filter(|token| token.label != "O")
+                    .map(Entity::from_token)
+                    .collect::<Vec<Entity>>(),
+                _ => group_entities(sequence_tokens, text, scheme),
+            })
+            .map(|mut entities| {
+                if let Some(min_score) = min_score {
+                    entities.retain(|entity| entity.score >= min_score);
+                }
+                entities
+            })
+            .collect::<Vec<Vec<Entity>>>())
+    }
+
+    /// Extract possibly nested or overlapping entities from a text with a multi-label
+    /// classification head (`multi_label` configuration flag), e.g. "breast cancer" inside
+    /// "breast cancer gene BRCA1". Instead of the single best label of each token, every label
+    /// with a score (sigmoid) above the `min_score` of the configuration (0.5 by default) is kept,
+    /// and the entities of each entity group are decoded independently following the
+    /// `label_aggregation_scheme`. The score of an entity is the mean score of its group labels
+    /// over its tokens. The entities are sorted by start offset, enclosing entities first.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing the extracted entities, labelled by their entity group
+    /// * `RustBertError::InvalidConfigurationError` if the classification head is not multi-label,
+    ///   or in the cases described for `TokenClassificationModel::predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    ///
+    /// let ner_model = NERModel::new(TokenClassificationConfig {
+    ///     multi_label: true,
+    ///     ..Default::default()
+    /// })?;
+    /// let input = ["Mutations of the breast cancer gene BRCA1 are common."];
+    /// let output = ner_model.predict_nested(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_nested<'a, S>(&self, input: S) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        if !self.token_classification_model.multi_label() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Nested entities can only be decoded with a multi-label classification head \
+                (`multi_label` configuration flag)"
+                    .to_string(),
+            ));
+        }
+        let input = input.as_ref();
+        let scheme = self.token_classification_model.label_aggregation_scheme();
+        let threshold = self.token_classification_model.min_score().unwrap_or(0.5);
+        Ok(self
+            .token_classification_model
+            .predict_with_scores(input, false)?
+            .into_iter()
+            .zip(input.iter())
+            .map(|(token_scores, text)| decode_nested(token_scores, text, scheme, threshold))
+            .collect::<Vec<Vec<Entity>>>())
+    }
+
+    /// Extract entities from a text, located by their character offsets in the input text.
+    /// Entities without offsets are skipped.
+    pub(crate) fn predict_spans(
+        &self,
+        input: &[&str],
+    ) -> Result<Vec<Vec<EntitySpan>>, RustBertError> {
+        Ok(self
+            .predict(input)?
+            .into_iter()
+            .map(|entities| {
+                entities
+                    .into_iter()
+                    .filter(|entity| entity.end > entity.start)
+                    .map(|entity| EntitySpan {
+                        word: entity.word,
+                        score: entity.score,
+                        label: entity.label,
+                        start: entity.start,
+                        end: entity.end,
+                    })
+                    .collect::<Vec<EntitySpan>>()
+            })
+            .collect::<Vec<Vec<EntitySpan>>>())
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tag {
+    Begin,
+    Inside,
+    Last,
+    Unit,
+    Outside,
+}
+
+/// Splits a label into its tag and entity group following the tagging scheme, e.g. `B-PER` into
+/// `(Tag::Begin, "PER")`. Labels without prefix are considered inside an entity.
+fn split_label(label: &str, scheme: TaggingScheme) -> (Tag, &str) {
+    if label == "O" {
+        return (Tag::Outside, label);
+    }
+    let prefixes: &[(&str, Tag)] = match scheme {
+        TaggingScheme::Iob1 | TaggingScheme::Iob2 => &[("B-", Tag::Begin), ("I-", Tag::Inside)],
+        TaggingScheme::Bilou => &[
+            ("B-", Tag::Begin),
+            ("I-", Tag::Inside),
+            ("L-", Tag::Last),
+            ("U-", Tag::Unit),
+        ],
+        TaggingScheme::None => &[],
+    };
+    prefixes
+        .iter()
+        .find_map(|(prefix, tag)| label.strip_prefix(prefix).map(|group| (*tag, group)))
+        .unwrap_or((Tag::Inside, label))
+}
+
+/// Decodes the entities from the labels of consecutive tokens. A token continues the current
+/// entity if it is tagged inside (or last) an entity of the same group that has not been closed,
+/// and starts a new entity otherwise. Sub-tokens continuing a word always belong to the entity of
+/// the word.
+fn group_entities(tokens: Vec<Token>, input: &str, scheme: TaggingScheme) -> Vec<Entity> {
+    //    Groups of tokens, with their entity group (None outside of entities) and a flag
+    //    indicating if the group can be continued by the following tokens
+    let mut groups: Vec<(Vec<Token>, Option<String>, bool)> = vec![];
+    for token in tokens {
+        if token.mask == Mask::Continuation {
+            if let Some((group_tokens, _, _)) = groups.last_mut() {
+                group_tokens.push(token);
+                continue;
+            }
+        }
+        let (tag, entity_group) = split_label(&token.label, scheme);
+        let entity_group = entity_group.to_string();
+        match groups.last_mut() {
+            Some((group_tokens, Some(previous_group), open))
+                if *open
+                    & (*previous_group == entity_group)
+                    & ((tag == Tag::Inside) | (tag == Tag::Last)) =>
+            {
+                group_tokens.push(token);
+                *open = tag == Tag::Inside;
+            }
+            _ => {
+                let (entity_group, open) = match tag {
+                    Tag::Outside => (None, false),
+                    Tag::Begin | Tag::Inside => (Some(entity_group), true),
+                    Tag::Last | Tag::Unit => (Some(entity_group), false),
+                };
+                groups.push((vec![token], entity_group, open));
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .filter_map(|(group_tokens, entity_group, _)| {
+            entity_group.map(|entity_group| merge_entity(&group_tokens, entity_group, input))
+        })
+        .collect()
+}
+
+/// Decodes the entities of each entity group independently from the label scores of the tokens:
+/// for each group, a token takes the best scoring label of the group if its score reaches the
+/// threshold and is outside of the entities of the group otherwise. The entities of different
+/// groups may overlap.
+fn decode_nested(
+    token_scores: Vec<TokenScores>,
+    input: &str,
+    scheme: TaggingScheme,
+    threshold: f64,
+) -> Vec<Entity> {
+    let mut entity_groups: Vec<&str> = vec![];
+    if let Some(first_token) = token_scores.first() {
+        for (label, _) in first_token.label_scores.iter() {
+            let (tag, entity_group) = split_label(label, scheme);
+            if (tag != Tag::Outside) & !entity_groups.contains(&entity_group) {
+                entity_groups.push(entity_group);
+            }
+        }
+    }
+    let mut entities = vec![];
+    for entity_group in entity_groups {
+        let tokens = token_scores
+            .iter()
+            .map(|token_scores| {
+                let mut token = token_scores.token.clone();
+                let best_label = token_scores
+                    .label_scores
+                    .iter()
+                    .filter(|(label, _)| {
+                        let (tag, label_group) = split_label(label, scheme);
+                        (tag != Tag::Outside) & (label_group == entity_group)
+                    })
+                    .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                match best_label {
+                    Some((label, score)) if *score >= threshold => {
+                        token.label = label.clone();
+                        token.score = *score;
+                    }
+                    _ => token.label = "O".to_string(),
+                }
+                token
+            })
+            .collect::<Vec<Token>>();
+        entities.extend(group_entities(tokens, input, scheme));
+    }
+    entities.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    entities
+}
+
+fn merge_entity(tokens: &[Token], label: String, input: &str) -> Entity {
+    let score = tokens.iter().map(|token| token.score).sum::<f64>() / tokens.len() as f64;
+    let offsets = tokens
+        .iter()
+        .filter_map(|token| token.offset)
+        .collect::<Vec<Offset>>();
+    match (offsets.first(), offsets.last()) {
+        (Some(first_offset), Some(last_offset)) => {
+            let (start, end) = (first_offset.begin as usize, last_offset.end as usize);
+            Entity {
+                word: input
+                    .chars()
+                    .skip(start)
+                    .take(end.saturating_sub(start))
+                    .collect(),
+                score,
+                label,
+                start,
+                end,
+            }
+        }
+        //    Without offsets the word is rebuilt from the tokens, stripping the sub-word markers
+        _ => {
+            let mut word = String::new();
+            for token in tokens {
+                if !word.is_empty()
+                    & (token.mask != Mask::Continuation)
+                    & !token.text.starts_with("##")
+                {
+                    word.push(' ');
+                }
+                word.push_str(
+                    token
+                        .text
+                        .trim_start_matches("##")
+                        .trim_start_matches('\u{0120}'),
+                );
+            }
+            Entity {
+                word,
+                score,
+                label,
+                start: 0,
+                end: 0,
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::pipelines::token_classification::test::tiny_bert_config;
 
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
@@ -222,4 +809,247 @@ mod test {
         let config = NERConfig::default();
         let _: Box<dyn Send> = Box::new(NERModel::new(config));
     }
+
+    fn token(text: &str, label: &str, score: f64, offset: Option<(u32, u32)>, mask: Mask) -> Token {
+        Token {
+            text: text.to_string(),
+            score,
+            label: label.to_string(),
+            label_index: 0,
+            sentence: 0,
+            index: 0,
+            word_index: 0,
+            offset: offset.map(|(begin, end)| Offset::new(begin, end)),
+            mask,
+        }
+    }
+
+    fn summary(entities: &[Entity]) -> Vec<(&str, &str, usize, usize)> {
+        entities
+            .iter()
+            .map(|entity| {
+                (
+                    entity.word.as_str(),
+                    entity.label.as_str(),
+                    entity.start,
+                    entity.end,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_group_entities() {
+        let input = "Alice moved to New York City.";
+        let tokens = vec![
+            token("Alice", "B-PER", 0.9, Some((0, 5)), Mask::None),
+            token("moved", "O", 0.9, Some((6, 11)), Mask::None),
+            token("to", "O", 0.9, Some((12, 14)), Mask::None),
+            token("New", "B-LOC", 0.8, Some((15, 18)), Mask::None),
+            token("York", "I-LOC", 0.6, Some((19, 23)), Mask::None),
+            token("City", "I-LOC", 0.7, Some((24, 28)), Mask::None),
+            token(".", "O", 0.9, Some((28, 29)), Mask::None),
+        ];
+        let entities = group_entities(tokens, input, TaggingScheme::Iob2);
+        assert_eq!(
+            summary(&entities),
+            vec![("Alice", "PER", 0, 5), ("New York City", "LOC", 15, 28)]
+        );
+        assert!((entities[1].score - 0.7).abs() < 1e-9);
+
+        //    B- tags start a new entity unless they continue a word, and entities at the end of
+        //    the input are kept
+        let input = "Paris London Amy";
+        let tokens = vec![
+            token("Par", "B-LOC", 0.9, Some((0, 3)), Mask::Begin),
+            token("##is", "B-LOC", 0.9, Some((3, 5)), Mask::Continuation),
+            token("London", "B-LOC", 0.9, Some((6, 12)), Mask::None),
+            token("Amy", "I-PER", 0.9, Some((13, 16)), Mask::None),
+        ];
+        assert_eq!(
+            summary(&group_entities(tokens, input, TaggingScheme::Iob2)),
+            vec![
+                ("Paris", "LOC", 0, 5),
+                ("London", "LOC", 6, 12),
+                ("Amy", "PER", 13, 16)
+            ]
+        );
+
+        //    Without offsets the words are rebuilt from the sub-word markers
+        let tokens = vec![
+            token("New", "B-LOC", 0.9, None, Mask::Begin),
+            token("##ark", "I-LOC", 0.9, None, Mask::Continuation),
+            token("and", "O", 0.9, None, Mask::None),
+            token("\u{0120}San", "B-LOC", 0.9, None, Mask::Begin),
+            token("\u{0120}Jose", "I-LOC", 0.9, None, Mask::Begin),
+        ];
+        assert_eq!(
+            summary(&group_entities(tokens, "", TaggingScheme::Iob2)),
+            vec![("Newark", "LOC", 0, 0), ("San Jose", "LOC", 0, 0)]
+        );
+    }
+    fn decode(labels: &[&str], scheme: TaggingScheme) -> Vec<(String, String)> {
+        let words = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let input = words[..labels.len()].join(" ");
+        let tokens = labels
+            .iter()
+            .enumerate()
+            .map(|(position, label)| {
+                let begin = 2 * position as u32;
+                token(
+                    words[position],
+                    label,
+                    0.9,
+                    Some((begin, begin + 1)),
+                    Mask::None,
+                )
+            })
+            .collect::<Vec<Token>>();
+        group_entities(tokens, &input, scheme)
+            .into_iter()
+            .map(|entity| (entity.word, entity.label))
+            .collect()
+    }
+
+    fn spans(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(word, label)| (word.to_string(), label.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_nested() {
+        let labels = ["O", "B-DIS", "I-DIS", "B-GENE", "I-GENE"];
+        let input = "breast cancer gene brca1 is mutated";
+        //    Logits of the words of the input for each label
+        let logits = [
+            ("breast", (0, 6), [-4.0, 4.0, -4.0, 3.0, -4.0]),
+            ("cancer", (7, 13), [-4.0, -4.0, 4.0, -4.0, 3.0]),
+            ("gene", (14, 18), [-4.0, -4.0, -4.0, -4.0, 3.0]),
+            ("brca1", (19, 24), [-4.0, -4.0, -4.0, -4.0, 4.0]),
+            ("is", (25, 27), [4.0, -4.0, -4.0, -4.0, -4.0]),
+            ("mutated", (28, 35), [4.0, -4.0, -1.0, -4.0, -4.0]),
+        ];
+        let token_scores = || {
+            logits
+                .iter()
+                .map(|(text, offset, word_logits)| TokenScores {
+                    token: token(text, "O", 0.0, Some(*offset), Mask::None),
+                    label_scores: labels
+                        .iter()
+                        .zip(word_logits.iter())
+                        .map(|(label, logit): (&&str, &f64)| {
+                            (label.to_string(), 1.0 / (1.0 + (-logit).exp()))
+                        })
+                        .collect(),
+                })
+                .collect::<Vec<TokenScores>>()
+        };
+        let entities = decode_nested(token_scores(), input, TaggingScheme::Iob2, 0.5);
+        assert_eq!(
+            summary(&entities),
+            [
+                ("breast cancer gene brca1", "GENE", 0, 24),
+                ("breast cancer", "DIS", 0, 13),
+            ]
+        );
+        let gene_score =
+            (3.0 * 1.0 / (1.0 + (-3.0f64).exp()) + 1.0 / (1.0 + (-4.0f64).exp())) / 4.0;
+        assert!((entities[0].score - gene_score).abs() < 1e-6);
+
+        //    The labels below the threshold are ignored
+        let entities = decode_nested(token_scores(), input, TaggingScheme::Iob2, 0.97);
+        assert_eq!(
+            summary(&entities),
+            [("breast cancer", "DIS", 0, 13), ("brca1", "GENE", 19, 24),]
+        );
+    }
+
+    #[test]
+    fn test_predict_nested() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let classifier_bias = [-5.0, -5.0, 5.0, -5.0, 5.0];
+        let mut config = tiny_bert_config(directory.path(), Some(&classifier_bias))?;
+        config.multi_label = true;
+        let model = NERModel::new(config)?;
+        let input = ["the dog is in paris."];
+        let output = model.predict_nested(&input)?;
+        assert_eq!(
+            summary(&output[0]),
+            [
+                ("the dog is in paris.", "LOC", 0, 20),
+                ("the dog is in paris.", "PER", 0, 20),
+            ]
+        );
+        for entity in output[0].iter() {
+            assert!((entity.score - 1.0 / (1.0 + (-5.0f64).exp())).abs() < 1e-4);
+        }
+
+        let model = NERModel::new(tiny_bert_config(directory.path(), Some(&classifier_bias))?)?;
+        assert!(matches!(
+            model.predict_nested(&input),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tagging_schemes() {
+        //    I- following O or another entity type is repaired as the beginning of an entity
+        assert_eq!(
+            decode(
+                &["B-PER", "I-PER", "O", "I-LOC", "I-LOC", "B-LOC", "B-LOC", "I-PER"],
+                TaggingScheme::Iob2
+            ),
+            spans(&[
+                ("a b", "PER"),
+                ("d e", "LOC"),
+                ("f", "LOC"),
+                ("g", "LOC"),
+                ("h", "PER")
+            ])
+        );
+        assert_eq!(
+            decode(
+                &["I-PER", "I-PER", "B-PER", "O", "I-LOC", "B-LOC", "I-ORG"],
+                TaggingScheme::Iob1
+            ),
+            spans(&[
+                ("a b", "PER"),
+                ("c", "PER"),
+                ("e", "LOC"),
+                ("f", "LOC"),
+                ("g", "ORG")
+            ])
+        );
+        assert_eq!(
+            decode(
+                &["B-PER", "I-PER", "L-PER", "U-LOC", "U-LOC", "I-ORG", "L-ORG", "L-ORG"],
+                TaggingScheme::Bilou
+            ),
+            spans(&[
+                ("a b c", "PER"),
+                ("d", "LOC"),
+                ("e", "LOC"),
+                ("f g", "ORG"),
+                ("h", "ORG")
+            ])
+        );
+        //    Entities not closed by a L- tag end at their last token
+        assert_eq!(
+            decode(
+                &["B-PER", "O", "L-LOC", "B-LOC", "I-LOC", "B-PER", "I-PER"],
+                TaggingScheme::Bilou
+            ),
+            spans(&[("a", "PER"), ("c", "LOC"), ("d e", "LOC"), ("f g", "PER")])
+        );
+        assert_eq!(
+            decode(
+                &["PER", "PER", "O", "LOC", "PER", "B-PER"],
+                TaggingScheme::None
+            ),
+            spans(&[("a b", "PER"), ("d", "LOC"), ("e", "PER"), ("f", "B-PER")])
+        );
+    }
 }
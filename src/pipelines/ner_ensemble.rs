@@ -0,0 +1,626 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Ensembles of NER models
+//! Runs several `NERModel`s on the same inputs (e.g. a general model and a domain-specific one) and
+//! merges the entities they extract. The entities are located by their character offsets in the
+//! input texts, so that models using different tokenizers can be combined.
+//!
+//! Entities are matched across models by their span and entity type, the type being the label
+//! without its tagging scheme prefix (`B-PER` and `I-PER` are both `PER` entities). The candidate
+//! entities are kept according to the `MergeStrategy`, and the remaining overlapping entities are
+//! resolved by the `OverlapResolution` rule so that the output spans never overlap.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::ner::NERModel;
+//! use rust_bert::pipelines::ner_ensemble::{
+//!     MergeStrategy, NEREnsemble, NEREnsembleConfig, OverlapResolution,
+//! };
+//!
+//! let general_model = NERModel::new(Default::default())?;
+//! let domain_model = NERModel::new(Default::default())?;
+//! let ensemble = NEREnsemble::new(
+//!     vec![general_model, domain_model],
+//!     NEREnsembleConfig {
+//!         strategy: MergeStrategy::Vote { min_vote: 0.5 },
+//!         overlap_resolution: OverlapResolution::LongestSpan,
+//!         weights: Some(vec![1.0, 2.0]),
+//!     },
+//! )?;
+//! let output = ensemble.predict(&["My name is Amy. I live in Paris."])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::ner::NERModel;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Entity span extracted by a `NEREnsemble`
+pub struct EntitySpan {
+    /// String representation of the entity
+    pub word: String,
+    /// Confidence score
+    pub score: f64,
+    /// Entity label (e.g. I-ORG, I-LOC...)
+    pub label: String,
+    /// Character offset of the start of the entity in the input text
+    pub start: usize,
+    /// Character offset of the end of the entity in the input text (exclusive)
+    pub end: usize,
+}
+
+impl EntitySpan {
+    fn overlaps(&self, other: &EntitySpan) -> bool {
+        (self.start < other.end) & (other.start < self.end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Selection of the entities extracted by the models of an ensemble
+pub enum MergeStrategy {
+    /// Keeps the entities extracted by any model, scored by their average score over these models
+    Union,
+    /// Keeps the entities extracted by all models, scored by their average score
+    Intersection,
+    /// Keeps the entities whose confidence-weighted vote is at least `min_vote`. The vote for an
+    /// entity is the sum of the scores of the models extracting it, weighted by the model weights
+    /// and divided by the total weight of the models (between 0 and 1). Entities are scored by
+    /// their vote.
+    Vote { min_vote: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Resolution of overlapping entities
+/// Entities are kept in order of priority, skipping the entities overlapping an entity already kept.
+pub enum OverlapResolution {
+    /// The longest entity wins, ties are broken by score
+    LongestSpan,
+    /// The entity with the highest score wins, ties are broken by length
+    HighestScore,
+}
+
+#[derive(Debug, Clone)]
+/// # Configuration for a `NEREnsemble`
+pub struct NEREnsembleConfig {
+    /// Selection of the entities extracted by the models (default: `MergeStrategy::Union`)
+    pub strategy: MergeStrategy,
+    /// Resolution of the overlapping entities (default: `OverlapResolution::HighestScore`)
+    pub overlap_resolution: OverlapResolution,
+    /// Weights of the models in the votes, in the order of the models (default: None, equal weights)
+    pub weights: Option<Vec<f64>>,
+}
+
+impl Default for NEREnsembleConfig {
+    fn default() -> NEREnsembleConfig {
+        NEREnsembleConfig {
+            strategy: MergeStrategy::Union,
+            overlap_resolution: OverlapResolution::HighestScore,
+            weights: None,
+        }
+    }
+}
+
+/// # Ensemble of NER models with span-level merging of their entities
+pub struct NEREnsemble {
+    models: Vec<NERModel>,
+    weights: Vec<f64>,
+    strategy: MergeStrategy,
+    overlap_resolution: OverlapResolution,
+}
+
+impl NEREnsemble {
+    /// Build a new `NEREnsemble`
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - `Vec<NERModel>` models of the ensemble, that may use different tokenizers
+    /// * `config` - `NEREnsembleConfig` merge strategy, overlap resolution rule and model weights
+    ///
+    /// # Returns
+    ///
+    /// * `RustBertError` if no model is provided, if the number of weights does not match the
+    /// number of models, if a weight is negative or all weights are zero, or if the minimum vote
+    /// is not between 0 and 1
+    pub fn new(
+        models: Vec<NERModel>,
+        config: NEREnsembleConfig,
+    ) -> Result<NEREnsemble, RustBertError> {
+        if models.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "A NER ensemble requires at least one model".to_string(),
+            ));
+        }
+        let weights = config.weights.unwrap_or_else(|| vec![1.0; models.len()]);
+        validate_weights(&weights, models.len())?;
+        if let MergeStrategy::Vote { min_vote } = config.strategy {
+            if !(0f64..=1f64).contains(&min_vote) {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "The minimum vote must be between 0 and 1, got {}",
+                    min_vote
+                )));
+            }
+        }
+        Ok(NEREnsemble {
+            models,
+            weights,
+            strategy: config.strategy,
+            overlap_resolution: config.overlap_resolution,
+        })
+    }
+
+    /// Extract entities from a text with all models, merging their entities
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<EntitySpan>>` containing the merged entities of each input, ordered by position
+    /// * `RustBertError` if the prediction of one of the models fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use rust_bert::pipelines::ner_ensemble::NEREnsemble;
+    ///
+    /// let models = vec![NERModel::new(Default::default())?];
+    /// let ensemble = NEREnsemble::new(models, Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ensemble.predict(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Vec<EntitySpan>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        let model_outputs = self
+            .models
+            .iter()
+            .map(|model| model.predict_spans(input))
+            .collect::<Result<Vec<Vec<Vec<EntitySpan>>>, RustBertError>>()?;
+        Ok((0..input.len())
+            .map(|input_index| {
+                let predictions = model_outputs
+                    .iter()
+                    .map(|output| output[input_index].clone())
+                    .collect::<Vec<Vec<EntitySpan>>>();
+                merge_entity_spans(
+                    &predictions,
+                    &self.weights,
+                    self.strategy,
+                    self.overlap_resolution,
+                )
+            })
+            .collect())
+    }
+}
+
+fn validate_weights(weights: &[f64], num_models: usize) -> Result<(), RustBertError> {
+    if weights.len() != num_models {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Expected {} model weights, got {}",
+            num_models,
+            weights.len()
+        )));
+    }
+    if weights
+        .iter()
+        .any(|weight| !weight.is_finite() || (*weight < 0f64))
+        || (weights.iter().sum::<f64>() <= 0f64)
+    {
+        return Err(RustBertError::InvalidConfigurationError(
+            "Model weights must be non-negative, with a positive sum".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the entity type of a label, removing the tagging scheme prefix (e.g. `B-` or `I-`)
+fn entity_type(label: &str) -> &str {
+    let bytes = label.as_bytes();
+    if (bytes.len() >= 2) && (bytes[1] == b'-') && b"BIESLU".contains(&bytes[0]) {
+        &label[2..]
+    } else {
+        label
+    }
+}
+
+struct Candidate {
+    span: EntitySpan,
+    num_models: usize,
+    score_sum: f64,
+    weighted_score_sum: f64,
+}
+
+/// Returns the best scored occurrence of each entity extracted by a model, in order of appearance
+fn unique_spans(spans: &[EntitySpan]) -> Vec<&EntitySpan> {
+    let mut unique: Vec<&EntitySpan> = vec![];
+    for span in spans {
+        match unique.iter_mut().find(|unique_span| {
+            (
+                unique_span.start,
+                unique_span.end,
+                entity_type(&unique_span.label),
+            ) == (span.start, span.end, entity_type(&span.label))
+        }) {
+            Some(unique_span) if span.score > unique_span.score => *unique_span = span,
+            Some(_) => {}
+            None => unique.push(span),
+        }
+    }
+    unique
+}
+
+/// Merges the entities extracted from a single input by the models of an ensemble.
+///
+/// # Arguments
+///
+/// * `predictions` - `&[Vec<EntitySpan>]` entities extracted by each model
+/// * `weights` - `&[f64]` weights of the models, in the order of the predictions
+/// * `strategy` - `MergeStrategy` selection of the entities
+/// * `overlap_resolution` - `OverlapResolution` resolution of the overlapping entities
+///
+/// # Returns
+///
+/// * `Vec<EntitySpan>` non-overlapping merged entities, ordered by position
+pub fn merge_entity_spans(
+    predictions: &[Vec<EntitySpan>],
+    weights: &[f64],
+    strategy: MergeStrategy,
+    overlap_resolution: OverlapResolution,
+) -> Vec<EntitySpan> {
+    let total_weight = weights.iter().sum::<f64>();
+    let mut candidates: Vec<Candidate> = vec![];
+    let mut candidate_indices: HashMap<(usize, usize, String), usize> = HashMap::new();
+    for (spans, weight) in predictions.iter().zip(weights) {
+        //    A model extracting the same entity twice only votes with its best score
+        for span in unique_spans(spans) {
+            let key = (span.start, span.end, entity_type(&span.label).to_string());
+            match candidate_indices.get(&key) {
+                Some(&candidate_index) => {
+                    let candidate = &mut candidates[candidate_index];
+                    candidate.num_models += 1;
+                    candidate.score_sum += span.score;
+                    candidate.weighted_score_sum += weight * span.score;
+                    if span.score > candidate.span.score {
+                        candidate.span.word = span.word.clone();
+                        candidate.span.label = span.label.clone();
+                        candidate.span.score = span.score;
+                    }
+                }
+                None => {
+                    candidate_indices.insert(key, candidates.len());
+                    candidates.push(Candidate {
+                        span: span.clone(),
+                        num_models: 1,
+                        score_sum: span.score,
+                        weighted_score_sum: weight * span.score,
+                    });
+                }
+            }
+        }
+    }
+
+    let selected = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let mean_score = candidate.score_sum / candidate.num_models as f64;
+            let vote = candidate.weighted_score_sum / total_weight;
+            let (keep, score) = match strategy {
+                MergeStrategy::Union => (true, mean_score),
+                MergeStrategy::Intersection => {
+                    (candidate.num_models == predictions.len(), mean_score)
+                }
+                MergeStrategy::Vote { min_vote } => (vote >= min_vote, vote),
+            };
+            if keep {
+                Some(EntitySpan {
+                    score,
+                    ..candidate.span
+                })
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<EntitySpan>>();
+
+    resolve_overlaps(selected, overlap_resolution)
+}
+
+/// Keeps the entities in order of priority, skipping the entities overlapping a kept entity
+fn resolve_overlaps(
+    mut spans: Vec<EntitySpan>,
+    overlap_resolution: OverlapResolution,
+) -> Vec<EntitySpan> {
+    let by_length = |a: &EntitySpan, b: &EntitySpan| (b.end - b.start).cmp(&(a.end - a.start));
+    let by_score =
+        |a: &EntitySpan, b: &EntitySpan| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal);
+    spans.sort_by(|a, b| {
+        match overlap_resolution {
+            OverlapResolution::LongestSpan => by_length(a, b).then_with(|| by_score(a, b)),
+            OverlapResolution::HighestScore => by_score(a, b).then_with(|| by_length(a, b)),
+        }
+        .then_with(|| a.start.cmp(&b.start))
+    });
+    let mut kept: Vec<EntitySpan> = vec![];
+    for span in spans {
+        if !kept.iter().any(|kept_span| kept_span.overlaps(&span)) {
+            kept.push(span);
+        }
+    }
+    kept.sort_by_key(|span| span.start);
+    kept
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn span(start: usize, end: usize, label: &str, score: f64) -> EntitySpan {
+        EntitySpan {
+            word: format!("{}..{}", start, end),
+            score,
+            label: label.to_string(),
+            start,
+            end,
+        }
+    }
+
+    fn summary(spans: &[EntitySpan]) -> Vec<(usize, usize, &str)> {
+        spans
+            .iter()
+            .map(|span| (span.start, span.end, span.label.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_entity_type() {
+        assert_eq!(entity_type("B-PER"), "PER");
+        assert_eq!(entity_type("I-LOC"), "LOC");
+        assert_eq!(entity_type("S-ORG"), "ORG");
+        assert_eq!(entity_type("MISC"), "MISC");
+        assert_eq!(entity_type("I-"), "");
+        assert_eq!(entity_type("X-PER"), "X-PER");
+    }
+
+    #[test]
+    fn test_union() {
+        let predictions = vec![
+            vec![span(0, 3, "I-PER", 0.9), span(10, 15, "I-LOC", 0.8)],
+            vec![span(0, 3, "B-PER", 0.7), span(20, 26, "I-ORG", 0.6)],
+        ];
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::HighestScore,
+        );
+        //    The entities matched across labelling schemes keep the label of the best model
+        assert_eq!(
+            summary(&merged),
+            vec![(0, 3, "I-PER"), (10, 15, "I-LOC"), (20, 26, "I-ORG")]
+        );
+        assert!((merged[0].score - 0.8).abs() < 1e-9);
+        assert!((merged[1].score - 0.8).abs() < 1e-9);
+        assert_eq!(merged[0].word, "0..3");
+    }
+
+    #[test]
+    fn test_intersection() {
+        let predictions = vec![
+            vec![span(0, 3, "I-PER", 0.9), span(10, 15, "I-LOC", 0.8)],
+            vec![span(0, 3, "I-PER", 0.7), span(10, 15, "I-ORG", 0.6)],
+            vec![span(0, 3, "I-PER", 0.5), span(10, 15, "I-LOC", 0.9)],
+        ];
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0, 1.0],
+            MergeStrategy::Intersection,
+            OverlapResolution::LongestSpan,
+        );
+        //    The location is not extracted by the second model
+        assert_eq!(summary(&merged), vec![(0, 3, "I-PER")]);
+        assert!((merged[0].score - 0.7).abs() < 1e-9);
+
+        let merged = merge_entity_spans(
+            &[vec![span(0, 3, "I-PER", 0.9)], vec![]],
+            &[1.0, 1.0],
+            MergeStrategy::Intersection,
+            OverlapResolution::LongestSpan,
+        );
+        assert!(merged.is_empty());
+    }
+
+    #[test]
+    fn test_weighted_vote() {
+        let predictions = vec![
+            vec![span(0, 3, "I-PER", 0.9)],
+            vec![span(0, 3, "I-ORG", 0.9)],
+            vec![span(0, 3, "I-ORG", 0.3)],
+        ];
+        //    Equal weights: ORG gets (0.9 + 0.3) / 3 = 0.4, PER gets 0.3
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0, 1.0],
+            MergeStrategy::Vote { min_vote: 0.35 },
+            OverlapResolution::HighestScore,
+        );
+        assert_eq!(summary(&merged), vec![(0, 3, "I-ORG")]);
+        assert!((merged[0].score - 0.4).abs() < 1e-9);
+
+        //    A heavier first model wins the vote: PER gets 0.9 * 3 / 5 = 0.54, ORG gets 0.24
+        let merged = merge_entity_spans(
+            &predictions,
+            &[3.0, 1.0, 1.0],
+            MergeStrategy::Vote { min_vote: 0.35 },
+            OverlapResolution::HighestScore,
+        );
+        assert_eq!(summary(&merged), vec![(0, 3, "I-PER")]);
+        assert!((merged[0].score - 0.54).abs() < 1e-9);
+
+        //    No entity reaches a high minimum vote
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0, 1.0],
+            MergeStrategy::Vote { min_vote: 0.9 },
+            OverlapResolution::HighestScore,
+        );
+        assert!(merged.is_empty());
+    }
+
+    #[test]
+    fn test_partial_overlaps_with_conflicting_labels() {
+        //    "New York City": LOC over "New York", ORG over "York City"
+        let predictions = vec![
+            vec![span(0, 8, "I-LOC", 0.95)],
+            vec![span(4, 13, "I-ORG", 0.7)],
+        ];
+        let longest = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::LongestSpan,
+        );
+        assert_eq!(summary(&longest), vec![(4, 13, "I-ORG")]);
+        let highest = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::HighestScore,
+        );
+        assert_eq!(summary(&highest), vec![(0, 8, "I-LOC")]);
+    }
+
+    #[test]
+    fn test_overlap_chains() {
+        //    The kept span removes its overlapping neighbours, not the spans beyond them
+        let predictions = vec![
+            vec![span(0, 5, "I-PER", 0.6), span(10, 14, "I-LOC", 0.6)],
+            vec![span(3, 12, "I-ORG", 0.9)],
+            vec![span(14, 20, "I-MISC", 0.5)],
+        ];
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::HighestScore,
+        );
+        assert_eq!(summary(&merged), vec![(3, 12, "I-ORG"), (14, 20, "I-MISC")]);
+
+        //    Adjacent spans do not overlap
+        let predictions = vec![
+            vec![span(0, 5, "I-PER", 0.6)],
+            vec![span(5, 9, "I-LOC", 0.9)],
+        ];
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::LongestSpan,
+        );
+        assert_eq!(summary(&merged), vec![(0, 5, "I-PER"), (5, 9, "I-LOC")]);
+    }
+
+    #[test]
+    fn test_tie_breaks() {
+        //    Same span with conflicting labels and scores: the score decides for both rules
+        let predictions = vec![
+            vec![span(0, 6, "I-LOC", 0.6)],
+            vec![span(0, 6, "I-ORG", 0.8)],
+        ];
+        for overlap_resolution in [
+            OverlapResolution::LongestSpan,
+            OverlapResolution::HighestScore,
+        ]
+        .iter()
+        {
+            let merged = merge_entity_spans(
+                &predictions,
+                &[1.0, 1.0],
+                MergeStrategy::Union,
+                *overlap_resolution,
+            );
+            assert_eq!(summary(&merged), vec![(0, 6, "I-ORG")]);
+        }
+
+        //    Same score: the longest span wins for both rules
+        let predictions = vec![
+            vec![span(0, 6, "I-LOC", 0.8)],
+            vec![span(2, 10, "I-ORG", 0.8)],
+        ];
+        for overlap_resolution in [
+            OverlapResolution::LongestSpan,
+            OverlapResolution::HighestScore,
+        ]
+        .iter()
+        {
+            let merged = merge_entity_spans(
+                &predictions,
+                &[1.0, 1.0],
+                MergeStrategy::Union,
+                *overlap_resolution,
+            );
+            assert_eq!(summary(&merged), vec![(2, 10, "I-ORG")]);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_spans_from_one_model() {
+        let predictions = vec![
+            vec![span(0, 3, "B-PER", 0.4), span(0, 3, "I-PER", 0.9)],
+            vec![],
+        ];
+        let merged = merge_entity_spans(
+            &predictions,
+            &[1.0, 1.0],
+            MergeStrategy::Vote { min_vote: 0.0 },
+            OverlapResolution::HighestScore,
+        );
+        //    The model votes once, with its best score
+        assert_eq!(summary(&merged), vec![(0, 3, "I-PER")]);
+        assert!((merged[0].score - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_weights() {
+        assert!(validate_weights(&[1.0, 1.0], 2).is_ok());
+        assert!(validate_weights(&[1.0, 0.0], 2).is_ok());
+        assert!(validate_weights(&[1.0], 2).is_err());
+        assert!(validate_weights(&[1.0, -1.0], 2).is_err());
+        assert!(validate_weights(&[0.0, 0.0], 2).is_err());
+        assert!(validate_weights(&[1.0, f64::NAN], 2).is_err());
+    }
+
+    #[test]
+    fn test_empty_predictions() {
+        let merged = merge_entity_spans(
+            &[vec![], vec![]],
+            &[1.0, 1.0],
+            MergeStrategy::Union,
+            OverlapResolution::LongestSpan,
+        );
+        assert!(merged.is_empty());
+    }
+}
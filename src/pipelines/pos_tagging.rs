@@ -21,7 +21,7 @@
 //! let pos_model = POSModel::new(Default::default())?;
 //!
 //! let input = ["My name is Amélie. How are you?"];
-//! let output = pos_model.predict(&input);
+//! let output = pos_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -79,7 +79,26 @@
 //! # ;
 //! ```
 //!
-//! To run the pipeline for another language, change the POSModel configuration from its default (see the NER pipeline for an illustration).
+//! XLM-RoBERTa models fine-tuned on Universal Dependencies (e.g. for German or French) can be loaded
+//! from locally converted weights with `POSConfig::xlm_roberta_upos`, predicting Universal POS tags.
+//! To run the pipeline for another language, change the POSModel configuration from its default
+//! (see the NER pipeline for an illustration).
+//!
+//! The tags can be returned in another tagset than the one of the model with `POSConfig::tagset`
+//! when a deterministic mapping exists: Penn Treebank tags (predicted by the English model) are
+//! converted to Universal POS tags, while the opposite conversion is ambiguous and fails when
+//! building the model.
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::pos_tagging::{POSConfig, POSModel, Tagset};
+//! let mut pos_config = POSConfig::default();
+//! pos_config.tagset = Some(Tagset::Upos);
+//! let pos_model = POSModel::new(pos_config)?;
+//!
+//! let output = pos_model.predict(&["My name is Amélie. How are you?"])?;
+//! # Ok(())
+//! # }
+//! ```
 
 use crate::common::error::RustBertError;
 use crate::mobilebert::{
@@ -87,12 +106,15 @@ use crate::mobilebert::{
 };
 use crate::pipelines::common::ModelType;
 use crate::pipelines::token_classification::{
-    LabelAggregationOption, TokenClassificationConfig, TokenClassificationModel,
+    AggregationStrategy, LabelAggregationOption, TaggingScheme, TokenClassificationConfig,
+    TokenClassificationModel, WordAggregationStrategy,
 };
 use crate::resources::{RemoteResource, Resource};
+use serde::{Deserialize, Serialize};
 use tch::Device;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Part of Speech tag
 pub struct POSTag {
     /// String representation of the word
@@ -103,13 +125,88 @@ pub struct POSTag {
     pub label: String,
 }
 
-//type alias for some backward compatibility
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # Part-of-speech tagset
+pub enum Tagset {
+    /// Universal POS tags of the Universal Dependencies project (e.g. NOUN, VERB, ADJ...)
+    Upos,
+    /// Penn Treebank tags (e.g. NN, VBZ, JJ...)
+    Ptb,
+}
+
+/// # Configuration for the part-of-speech tagging pipeline
 pub struct POSConfig {
     token_classification_config: TokenClassificationConfig,
+    /// Tagset of the labels predicted by the model, `None` if unknown (default: `Some(Tagset::Ptb)`)
+    pub model_tagset: Option<Tagset>,
+    /// Tagset of the returned tags, `None` to return the labels of the model. Building the model
+    /// fails if the labels of the model cannot be converted to the requested tagset (default: None)
+    pub tagset: Option<Tagset>,
+}
+
+impl POSConfig {
+    /// Part of speech tagging configuration for an XLM-RoBERTa model fine-tuned on Universal
+    /// Dependencies, predicting Universal POS tags (e.g. `wietsedv/xlm-roberta-base-ft-udpos28-de`
+    /// for German or `wietsedv/xlm-roberta-base-ft-udpos28-fr` for French).
+    ///
+    /// No converted weights are published for these checkpoints: the Pytorch weights must be
+    /// converted with `python ./utils/convert_model.py path/to/pytorch_model.bin` and passed as a
+    /// local resource, along with the configuration and the sentencepiece model of the checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - The `Resource` pointing to the converted weights (`rust_model.ot`)
+    /// * `config_resource` - The `Resource` pointing to the model configuration (`config.json`)
+    /// * `vocab_resource` - The `Resource` pointing to the sentencepiece model (`sentencepiece.bpe.model`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::pos_tagging::{POSConfig, POSModel};
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let local_resource = |path: &str| {
+    ///     Resource::Local(LocalResource {
+    ///         local_path: PathBuf::from(path),
+    ///     })
+    /// };
+    /// let pos_model = POSModel::new(POSConfig::xlm_roberta_upos(
+    ///     local_resource("path/to/rust_model.ot"),
+    ///     local_resource("path/to/config.json"),
+    ///     local_resource("path/to/sentencepiece.bpe.model"),
+    /// ))?;
+    /// let output = pos_model.predict(&["Der Hund schläft im Garten."])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "roberta")]
+    pub fn xlm_roberta_upos(
+        model_resource: Resource,
+        config_resource: Resource,
+        vocab_resource: Resource,
+    ) -> POSConfig {
+        POSConfig {
+            token_classification_config: TokenClassificationConfig::new(
+                ModelType::XLMRoberta,
+                model_resource,
+                config_resource,
+                vocab_resource,
+                None,
+                false,
+                None,
+                None,
+                LabelAggregationOption::First,
+            ),
+            model_tagset: Some(Tagset::Upos),
+            tagset: None,
+        }
+    }
 }
 
 impl Default for POSConfig {
-    /// Provides a Part of speech tagging model (English)
+    /// Provides a Part of speech tagging model (English), predicting Penn Treebank tags
     fn default() -> POSConfig {
         POSConfig {
             token_classification_config: TokenClassificationConfig {
@@ -128,8 +225,21 @@ impl Default for POSConfig {
                 strip_accents: Some(true),
                 add_prefix_space: None,
                 device: Device::cuda_if_available(),
+                pad_to_multiple_of: None,
+                batch_size: 64,
+                max_length: 128,
+                sliding_window_stride: None,
+                label_mapping: None,
                 label_aggregation_function: LabelAggregationOption::First,
+                aggregation_strategy: AggregationStrategy::None,
+                label_aggregation_scheme: TaggingScheme::Iob2,
+                word_aggregation_strategy: WordAggregationStrategy::First,
+                min_score: None,
+                multi_label: false,
+                use_crf: false,
             },
+            model_tagset: Some(Tagset::Ptb),
+            tagset: None,
         }
     }
 }
@@ -140,9 +250,100 @@ impl From<POSConfig> for TokenClassificationConfig {
     }
 }
 
+impl From<TokenClassificationConfig> for POSConfig {
+    fn from(token_classification_config: TokenClassificationConfig) -> Self {
+        POSConfig {
+            token_classification_config,
+            model_tagset: None,
+            tagset: None,
+        }
+    }
+}
+
+/// Returns the Universal POS tag of a Penn Treebank tag (following the mapping of the spaCy
+/// library), or `None` for unknown tags
+fn ptb_to_upos(tag: &str) -> Option<&'static str> {
+    Some(match tag {
+        "#" | "$" | "SYM" => "SYM",
+        "''" | "``" | "," | "-LRB-" | "-RRB-" | "." | ":" | "HYPH" | "NFP" => "PUNCT",
+        "AFX" | "JJ" | "JJR" | "JJS" => "ADJ",
+        "CC" => "CCONJ",
+        "CD" => "NUM",
+        "DT" | "PDT" | "WDT" => "DET",
+        "EX" | "PRP" | "PRP$" | "WP" | "WP$" => "PRON",
+        "FW" | "LS" | "NIL" | "ADD" | "XX" => "X",
+        "IN" | "RP" => "ADP",
+        "MD" | "VB" | "VBD" | "VBG" | "VBN" | "VBP" | "VBZ" => "VERB",
+        "NN" | "NNS" => "NOUN",
+        "NNP" | "NNPS" => "PROPN",
+        "POS" | "TO" => "PART",
+        "RB" | "RBR" | "RBS" | "WRB" => "ADV",
+        "UH" => "INTJ",
+        _ => return None,
+    })
+}
+
+/// Conversion of the labels of the model to the requested tagset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TagConversion {
+    Identity,
+    PtbToUpos,
+}
+
+impl TagConversion {
+    fn new(model_tagset: Option<Tagset>, tagset: Option<Tagset>) -> Result<Self, RustBertError> {
+        match (model_tagset, tagset) {
+            (_, None) => Ok(TagConversion::Identity),
+            (Some(model_tagset), Some(tagset)) if model_tagset == tagset => {
+                Ok(TagConversion::Identity)
+            }
+            (Some(Tagset::Ptb), Some(Tagset::Upos)) => Ok(TagConversion::PtbToUpos),
+            (Some(model_tagset), Some(tagset)) => {
+                Err(RustBertError::InvalidConfigurationError(format!(
+                    "No deterministic mapping from the {:?} tags of the model to the {:?} tagset",
+                    model_tagset, tagset
+                )))
+            }
+            (None, Some(tagset)) => Err(RustBertError::InvalidConfigurationError(format!(
+                "The tagset of the model must be set to convert its labels to the {:?} tagset",
+                tagset
+            ))),
+        }
+    }
+
+    /// Checks that all labels of the model can be converted
+    fn check_labels<'a>(
+        self,
+        labels: impl Iterator<Item = &'a String>,
+    ) -> Result<(), RustBertError> {
+        let mut unknown_labels = labels
+            .filter(|label| self.convert(label).is_none())
+            .cloned()
+            .collect::<Vec<String>>();
+        if unknown_labels.is_empty() {
+            Ok(())
+        } else {
+            unknown_labels.sort();
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "The labels {:?} of the model cannot be converted to the requested tagset",
+                unknown_labels
+            )))
+        }
+    }
+
+    fn convert(self, label: &str) -> Option<String> {
+        match self {
+            TagConversion::Identity => Some(label.to_string()),
+            TagConversion::PtbToUpos => ptb_to_upos(label).map(String::from),
+        }
+    }
+}
+
 /// # POSModel to extract Part of Speech tags
 pub struct POSModel {
     token_classification_model: TokenClassificationModel,
+    model_tagset: Option<Tagset>,
+    tag_conversion: TagConversion,
 }
 
 impl POSModel {
@@ -163,9 +364,14 @@ impl POSModel {
     /// # }
     /// ```
     pub fn new(pos_config: POSConfig) -> Result<POSModel, RustBertError> {
+        let model_tagset = pos_config.model_tagset;
+        let tag_conversion = TagConversion::new(model_tagset, pos_config.tagset)?;
         let model = TokenClassificationModel::new(pos_config.into())?;
+        tag_conversion.check_labels(model.label_mapping().values())?;
         Ok(POSModel {
             token_classification_model: model,
+            model_tagset,
+            tag_conversion,
         })
     }
 
@@ -177,7 +383,9 @@ impl POSModel {
     ///
     /// # Returns
     ///
-    /// * `Vec<Vec<POSTag>>` containing Part of Speech tags for the inputs provided
+    /// * `Vec<Vec<POSTag>>` containing Part of Speech tags for the inputs provided, in the
+    /// requested tagset
+    /// * `RustBertError` in the cases described for `TokenClassificationModel::predict`
     ///
     /// # Example
     ///
@@ -190,16 +398,21 @@ impl POSModel {
     ///     "My name is Amy. I live in Paris.",
     ///     "Paris is a city in France.",
     /// ];
-    /// let output = pos_model.predict(&input);
+    /// let output = pos_model.predict(&input)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Vec<POSTag>>
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Vec<POSTag>>, RustBertError>
     where
         S: AsRef<[&'a str]>,
     {
-        self.token_classification_model
-            .predict(input, true, false)
+        let punctuation_label = match self.model_tagset {
+            Some(Tagset::Upos) => "PUNCT",
+            _ => ".",
+        };
+        Ok(self
+            .token_classification_model
+            .predict(input, true, false)?
             .into_iter()
             .map(|sequence_tokens| {
                 sequence_tokens
@@ -208,19 +421,19 @@ impl POSModel {
                         if (Self::is_punctuation(token.text.as_str()))
                             & ((token.score < 0.5) | token.score.is_nan())
                         {
-                            token.label = String::from(".");
+                            token.label = String::from(punctuation_label);
                             token.score = 1f64;
                         };
                         token
                     })
                     .map(|token| POSTag {
+                        label: self.tag_conversion.convert(&token.label).unwrap(),
                         word: token.text,
                         score: token.score,
-                        label: token.label,
                     })
                     .collect::<Vec<POSTag>>()
             })
-            .collect::<Vec<Vec<POSTag>>>()
+            .collect::<Vec<Vec<POSTag>>>())
     }
 
     fn is_punctuation(string: &str) -> bool {
@@ -231,6 +444,8 @@ impl POSModel {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::pipelines::token_classification::test::tiny_bert_config;
+    use std::collections::HashMap;
 
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
@@ -238,4 +453,74 @@ mod test {
         let config = POSConfig::default();
         let _: Box<dyn Send> = Box::new(POSModel::new(config));
     }
+
+    fn ptb_config(
+        directory: &std::path::Path,
+        tagset: Option<Tagset>,
+    ) -> anyhow::Result<POSConfig> {
+        let mut config = tiny_bert_config(directory, Some(&[0.0, 1.0, 0.0, 0.0, 0.0]))?;
+        config.label_mapping = Some(
+            ["NN", "VBZ", "DT", "IN", "NNP"]
+                .iter()
+                .enumerate()
+                .map(|(id, label)| (id as i64, label.to_string()))
+                .collect::<HashMap<i64, String>>(),
+        );
+        Ok(POSConfig {
+            model_tagset: Some(Tagset::Ptb),
+            tagset,
+            ..POSConfig::from(config)
+        })
+    }
+
+    #[test]
+    fn test_tagset() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let input = ["the dog is in paris."];
+        let tags = |model: &POSModel| -> anyhow::Result<Vec<String>> {
+            Ok(model.predict(&input)?[0]
+                .iter()
+                .map(|tag| tag.label.clone())
+                .collect())
+        };
+
+        //    All words are tagged as VBZ, and the punctuation is tagged in the model tagset
+        let ptb_model = POSModel::new(ptb_config(directory.path(), None)?)?;
+        assert_eq!(tags(&ptb_model)?, ["VBZ", "VBZ", "VBZ", "VBZ", "VBZ", "."]);
+        let ptb_model = POSModel::new(ptb_config(directory.path(), Some(Tagset::Ptb))?)?;
+        assert_eq!(tags(&ptb_model)?, ["VBZ", "VBZ", "VBZ", "VBZ", "VBZ", "."]);
+        let upos_model = POSModel::new(ptb_config(directory.path(), Some(Tagset::Upos))?)?;
+        assert_eq!(
+            tags(&upos_model)?,
+            ["VERB", "VERB", "VERB", "VERB", "VERB", "PUNCT"]
+        );
+
+        //    Universal POS tags cannot be converted to Penn Treebank tags
+        let mut config = ptb_config(directory.path(), Some(Tagset::Ptb))?;
+        config.model_tagset = Some(Tagset::Upos);
+        assert!(matches!(
+            POSModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        let mut config = ptb_config(directory.path(), Some(Tagset::Upos))?;
+        config.model_tagset = None;
+        assert!(matches!(
+            POSModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ptb_to_upos() {
+        assert_eq!(ptb_to_upos("NNS"), Some("NOUN"));
+        assert_eq!(ptb_to_upos("PRP$"), Some("PRON"));
+        assert_eq!(ptb_to_upos("MD"), Some("VERB"));
+        assert_eq!(ptb_to_upos("-LRB-"), Some("PUNCT"));
+        assert_eq!(ptb_to_upos("NOUN"), None);
+        let labels = vec!["NN".to_string(), "B-PER".to_string()];
+        assert!(TagConversion::PtbToUpos
+            .check_labels(labels.iter())
+            .is_err());
+    }
 }
@@ -39,27 +39,45 @@
 //!     start: 13,
 //!     end: 21,
 //!     answer: String::from("Amsterdam"),
+//!     context_index: 0,
 //! }]
 //! # ;
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForQuestionAnswering;
+#[cfg(feature = "bert")]
 use crate::bert::BertForQuestionAnswering;
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::resources::{RemoteResource, Resource};
 use crate::distilbert::{
     DistilBertConfigResources, DistilBertForQuestionAnswering, DistilBertModelResources,
     DistilBertVocabResources,
 };
-use crate::longformer::LongformerForQuestionAnswering;
+#[cfg(feature = "longformer")]
+use crate::longformer::{
+    LongformerConfigResources, LongformerForQuestionAnswering, LongformerMergesResources,
+    LongformerModelResources, LongformerVocabResources,
+};
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForQuestionAnswering;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::common::{
+    char_to_byte, expect_config, get_padded_length, ConfigOption, ModelType, TokenizerOption,
+};
+use crate::pipelines::validation::{validate_resources, PipelineResources, ValidationReport};
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerForQuestionAnswering;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForQuestionAnswering;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForQuestionAnswering;
 use rust_tokenizers::{Offset, TokenIdsWithOffsets, TokenizedInput};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::cmp::min;
+use std::cmp::{max, min, Ordering};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
@@ -82,24 +100,202 @@ struct QaFeature {
     pub offsets: Vec<Option<Offset>>,
     pub p_mask: Vec<i8>,
     pub example_index: i64,
+    pub cls_index: usize,
+}
+
+/// Scores of the null answer (start and end on the CLS token) of an input, and logits score of the
+/// best answer span
+#[derive(Debug, Clone, Copy)]
+struct NullAnswerScores {
+    null_score: f64,
+    null_probability: f64,
+    best_span_score: Option<f64>,
+}
+
+impl NullAnswerScores {
+    /// Merges the scores of another feature of the same input: the null answer of the input is the
+    /// one with the minimum null score, and the best span the one with the maximum score
+    fn merge(&mut self, other: NullAnswerScores) {
+        if other.null_score < self.null_score {
+            self.null_score = other.null_score;
+            self.null_probability = other.null_probability;
+        }
+        self.best_span_score = match (self.best_span_score, other.best_span_score) {
+            (Some(score), Some(other_score)) => Some(score.max(other_score)),
+            (score, other_score) => score.or(other_score),
+        };
+    }
+
+    fn is_impossible(&self, null_score_diff_threshold: f64) -> bool {
+        self.best_span_score.map_or(true, |best_span_score| {
+            self.null_score - best_span_score > null_score_diff_threshold
+        })
+    }
+}
+
+/// Computes the start and end probabilities of a feature, the masked positions being excluded. If
+/// the null answer is handled (`cls_index` provided), the CLS token takes part in the
+/// probabilities and the null answer scores are returned, the probabilities of the CLS token being
+/// then set to 0 to exclude it from the answer spans. The logarithms of the normalizers of the
+/// start and end probabilities are returned to normalize the scores across windows.
+fn feature_probabilities(
+    start_logits: &Tensor,
+    end_logits: &Tensor,
+    p_mask: &[i8],
+    cls_index: Option<usize>,
+) -> (Tensor, Tensor, Option<NullAnswerScores>, (f64, f64)) {
+    let mut p_mask = p_mask.to_vec();
+    if let Some(cls_index) = cls_index {
+        p_mask[cls_index] = 0;
+    }
+    let p_mask = Tensor::of_slice(&p_mask)
+        .to_device(start_logits.device())
+        .eq(1);
+
+    let start = start_logits.masked_fill(&p_mask, -10000);
+    let end = end_logits.masked_fill(&p_mask, -10000);
+
+    let start_normalizer = start.exp().sum(Float);
+    let end_normalizer = end.exp().sum(Float);
+    let log_normalizers = (
+        start_normalizer.log().double_value(&[]),
+        end_normalizer.log().double_value(&[]),
+    );
+    let start = start.exp() / start_normalizer;
+    let end = end.exp() / end_normalizer;
+
+    let null_answer_scores = cls_index.map(|cls_index| {
+        let cls_index = cls_index as i64;
+        let null_answer_scores = NullAnswerScores {
+            null_score: start_logits.double_value(&[cls_index])
+                + end_logits.double_value(&[cls_index]),
+            null_probability: start.double_value(&[cls_index]) * end.double_value(&[cls_index]),
+            best_span_score: None,
+        };
+        let _ = start.narrow(0, cls_index, 1).fill_(0.0);
+        let _ = end.narrow(0, cls_index, 1).fill_(0.0);
+        null_answer_scores
+    });
+    (start, end, null_answer_scores, log_normalizers)
+}
+
+/// Returns the `top_k` answer spans (start and end token positions) of a window with the highest
+/// products of start and end probabilities. Only the valid spans are candidates: starting and
+/// ending in the context (`p_mask` of 0, excluding the question, special tokens and padding), with
+/// the end not preceding the start and at most `max_answer_length` tokens long. Fewer than `top_k`
+/// spans are returned if the window has fewer valid spans.
+fn decode_spans(
+    start: &Tensor,
+    end: &Tensor,
+    p_mask: &[i8],
+    top_k: i64,
+    max_answer_length: usize,
+) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+    let outer = start.unsqueeze(-1).matmul(&end.unsqueeze(0));
+    let end_dim = end.size()[0];
+    let context_positions = Tensor::of_slice(p_mask)
+        .to_device(start.device())
+        .eq(0)
+        .to_kind(Float);
+    let valid_spans = context_positions
+        .unsqueeze(-1)
+        .matmul(&context_positions.unsqueeze(0))
+        .triu(0)
+        .tril(max_answer_length as i64 - 1)
+        .flatten(0, -1);
+    let num_valid_spans = valid_spans.sum(Float).double_value(&[]) as i64;
+    //    The probabilities are positive: the invalid spans are ranked last and never selected
+    let candidates = outer.flatten(0, -1).masked_fill(&valid_spans.eq(0), -1.0);
+    let top_k = min(top_k, num_valid_spans);
+    let idx_sort = if top_k == 1 {
+        candidates.argmax(0, true)
+    } else {
+        candidates.argsort(0, true).slice(0, 0, top_k, 1)
+    };
+    let mut start: Vec<i64> = vec![];
+    let mut end: Vec<i64> = vec![];
+    let mut scores: Vec<f64> = vec![];
+    for flat_index_position in 0..top_k {
+        let flat_index = idx_sort.int64_value(&[flat_index_position]);
+        scores.push(candidates.double_value(&[flat_index]));
+        start.push(flat_index / end_dim);
+        end.push(flat_index % end_dim);
+    }
+    (start, end, scores)
+}
+
+/// Number of spans decoded per window when the overlapping answers are suppressed. Overlapping
+/// candidates are dropped by the suppression, more candidates are decoded to leave `top_k`
+/// distinct answers, up to the number of valid spans of a window (at most `max_answer_length`
+/// spans start at each of its `max_seq_length` tokens).
+fn suppression_candidates(top_k: i64, max_answer_length: usize, max_seq_length: usize) -> i64 {
+    let max_answer_length = min(max_answer_length, max_seq_length) as i64;
+    let max_valid_spans = (max_seq_length as i64).saturating_mul(max_answer_length);
+    min(
+        top_k.saturating_mul(max_answer_length.saturating_mul(max_answer_length)),
+        max_valid_spans,
+    )
+}
+
+/// Logarithm of the sum of the exponentials of the values, computed stably
+fn log_sum_exp(values: &[f64]) -> f64 {
+    let max_value = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max_value == f64::NEG_INFINITY {
+        return max_value;
+    }
+    max_value
+        + values
+            .iter()
+            .map(|value| (value - max_value).exp())
+            .sum::<f64>()
+            .ln()
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Output for Question Answering
 pub struct Answer {
     /// Confidence score
     pub score: f64,
-    /// Start position of answer span
+    /// Start position of answer span, in characters of the context (not bytes or UTF-16 code units)
     pub start: usize,
-    /// End position of answer span
+    /// End position (exclusive) of answer span, in characters of the context
     pub end: usize,
     /// Answer span
     pub answer: String,
+    /// Index of the context the answer was extracted from, for answers ranked across several
+    /// contexts by `predict_multi_context` (0 for the other predictions)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub context_index: usize,
+}
+
+impl Answer {
+    /// Returns the byte offsets of the answer span in the context it was extracted from, so that
+    /// `&context[byte_start..byte_end] == answer.answer`. The `start` and `end` fields are
+    /// character offsets, as for the other pipelines, and cannot be used to slice the `&str`
+    /// directly when the context contains characters beyond ASCII.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Context of the `QaInput` the answer was extracted from
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(usize, usize)>` byte offsets of the start and end (exclusive) of the answer, `None` if they are out of the bounds of the context
+    pub fn byte_offsets(&self, context: &str) -> Option<(usize, usize)> {
+        Some((
+            char_to_byte(context, self.start)?,
+            char_to_byte(context, self.end)?,
+        ))
+    }
 }
 
 impl PartialEq for Answer {
     fn eq(&self, other: &Self) -> bool {
-        (self.start == other.start) && (self.end == other.end) && (self.answer == other.answer)
+        (self.start == other.start)
+            && (self.end == other.end)
+            && (self.answer == other.answer)
+            && (self.context_index == other.context_index)
     }
 }
 
@@ -116,6 +312,26 @@ fn remove_duplicates<T: PartialEq + Clone>(vector: &mut Vec<T>) -> &mut Vec<T> {
     vector
 }
 
+/// # Options of a question answering prediction
+/// Limits specific to a call of `QuestionAnsweringModel::predict_with_options`, the options left
+/// to `None` taking the value of the configuration.
+#[derive(Debug, Clone, Default)]
+pub struct QaOptions {
+    /// Number of answers returned for each input (default: 1). Post-processing only.
+    pub top_k: Option<i64>,
+    /// Maximum length of the answers, in tokens. Post-processing only.
+    pub max_answer_length: Option<usize>,
+    /// Number of overlapping tokens between the windows of a long context. Changes the windows
+    /// the contexts are split into.
+    pub doc_stride: Option<usize>,
+    /// Maximum length of the combined question and context windows, in tokens. Changes the
+    /// windows the contexts are split into.
+    pub max_seq_length: Option<usize>,
+    /// Maximum number of windows in a forward pass (default: the `batch_size` of the
+    /// configuration)
+    pub batch_size: Option<usize>,
+}
+
 /// # Configuration for question answering
 /// Contains information regarding the model to load and device to place the model on.
 pub struct QuestionAnsweringConfig {
@@ -129,6 +345,9 @@ pub struct QuestionAnsweringConfig {
     pub merges_resource: Option<Resource>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Pad the input sequences to a multiple of this value to produce tensor shapes that are more
+    /// efficient on GPU (e.g. 8 for fp16 tensor cores). Padded positions are masked (default: None)
+    pub pad_to_multiple_of: Option<usize>,
     /// Model type
     pub model_type: ModelType,
     /// Flag indicating if the model expects a lower casing of the input
@@ -145,6 +364,27 @@ pub struct QuestionAnsweringConfig {
     pub max_query_length: usize,
     /// Maximum length for the answer
     pub max_answer_length: usize,
+    /// Maximum overlap (intersection over union of the character spans) between an answer and a
+    /// higher-scoring answer of the same input, between 0 and 1. Answers overlapping more are
+    /// dropped, so that the `top_k` answers are distinct spans. `None` keeps all answers
+    /// (default: None)
+    pub answer_overlap_threshold: Option<f64>,
+    /// Flag indicating if the model can predict that the context does not contain the answer
+    /// (e.g. models fine-tuned on SQuAD 2.0). The null answer has its start and end on the CLS
+    /// token (default: false)
+    pub handle_impossible_answer: bool,
+    /// With `handle_impossible_answer`, an empty answer is returned first when the null score
+    /// (sum of the start and end logits of the CLS token) exceeds the score of the best span by
+    /// more than this threshold (default: 0.0)
+    pub null_score_diff_threshold: f64,
+    /// Maximum number of tokens (including padding) of a batch of context windows. The windows of
+    /// all inputs are sorted by length and batched with windows of similar length, each batch
+    /// holding at most `batch_size` windows and, if set, at most this number of tokens
+    /// (default: None)
+    pub max_tokens_per_batch: Option<usize>,
+    /// Maximum number of context windows in a forward pass of the predictions that do not take a
+    /// batch size, `predict_multi_context` and `predict_with_options` (default: 64)
+    pub batch_size: usize,
 }
 
 impl QuestionAnsweringConfig {
@@ -178,10 +418,16 @@ impl QuestionAnsweringConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
             max_seq_length: 384,
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            answer_overlap_threshold: None,
+            handle_impossible_answer: false,
+            null_score_diff_threshold: 0.0,
+            max_tokens_per_batch: None,
+            batch_size: 64,
         }
     }
 
@@ -223,10 +469,46 @@ impl QuestionAnsweringConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
             max_seq_length: max_seq_length.into().unwrap_or(384),
             doc_stride: doc_stride.into().unwrap_or(128),
             max_query_length: max_query_length.into().unwrap_or(64),
             max_answer_length: max_answer_length.into().unwrap_or(15),
+            answer_overlap_threshold: None,
+            handle_impossible_answer: false,
+            null_score_diff_threshold: 0.0,
+            max_tokens_per_batch: None,
+            batch_size: 64,
+        }
+    }
+
+    /// Instantiate a question answering configuration for a Longformer model fine-tuned on SQuAD
+    /// (`longformer-base-4096-finetuned-squadv1`). The query and context are processed up to 4096
+    /// tokens, so that contexts fitting in this window are processed as a single sequence instead
+    /// of overlapping sliding windows. The pipeline sets global attention on the question tokens.
+    #[cfg(feature = "longformer")]
+    pub fn longformer_squad() -> QuestionAnsweringConfig {
+        QuestionAnsweringConfig {
+            max_seq_length: 4096,
+            doc_stride: 512,
+            ..QuestionAnsweringConfig::new(
+                ModelType::Longformer,
+                Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerModelResources::LONGFORMER_BASE_SQUAD1,
+                )),
+                Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerConfigResources::LONGFORMER_BASE_SQUAD1,
+                )),
+                Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerVocabResources::LONGFORMER_BASE_SQUAD1,
+                )),
+                Some(Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerMergesResources::LONGFORMER_BASE_SQUAD1,
+                ))),
+                false,
+                None,
+                false,
+            )
         }
     }
 }
@@ -245,6 +527,7 @@ impl Default for QuestionAnsweringConfig {
             )),
             merges_resource: None,
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
             model_type: ModelType::DistilBert,
             lower_case: false,
             add_prefix_space: None,
@@ -253,6 +536,11 @@ impl Default for QuestionAnsweringConfig {
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            answer_overlap_threshold: None,
+            handle_impossible_answer: false,
+            null_score_diff_threshold: 0.0,
+            max_tokens_per_batch: None,
+            batch_size: 64,
         }
     }
 }
@@ -260,22 +548,30 @@ impl Default for QuestionAnsweringConfig {
 /// # Abstraction that holds one particular question answering model, for any of the supported models
 pub enum QuestionAnsweringOption {
     /// Bert for Question Answering
+    #[cfg(feature = "bert")]
     Bert(BertForQuestionAnswering),
     /// DistilBert for Question Answering
     DistilBert(DistilBertForQuestionAnswering),
     /// MobileBert for Question Answering
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForQuestionAnswering),
     /// Roberta for Question Answering
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForQuestionAnswering),
     /// XLMRoberta for Question Answering
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForQuestionAnswering),
     /// Albert for Question Answering
+    #[cfg(feature = "albert")]
     Albert(AlbertForQuestionAnswering),
     /// XLNet for Question Answering
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForQuestionAnswering),
     /// Reformer for Question Answering
+    #[cfg(feature = "reformer")]
     Reformer(ReformerForQuestionAnswering),
     /// Longformer for Question Answering
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForQuestionAnswering),
 }
 
@@ -296,105 +592,114 @@ impl QuestionAnsweringOption {
     where
         P: Borrow<nn::Path<'p>>,
     {
+        model_type.ensure_enabled()?;
         match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(QuestionAnsweringOption::Bert(
-                        BertForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Bert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bert,
+                    ConfigOption::Bert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::Bert(
+                    BertForQuestionAnswering::new(p, config),
+                ))
             }
             ModelType::DistilBert => {
-                if let ConfigOption::DistilBert(config) = config {
-                    Ok(QuestionAnsweringOption::DistilBert(
-                        DistilBertForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a DistilBertConfig for DistilBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::DistilBert,
+                    ConfigOption::DistilBert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::DistilBert(
+                    DistilBertForQuestionAnswering::new(p, config),
+                ))
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
-                if let ConfigOption::MobileBert(config) = config {
-                    Ok(QuestionAnsweringOption::MobileBert(
-                        MobileBertForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a MobileBertConfig for MobileBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::MobileBert,
+                    ConfigOption::MobileBert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::MobileBert(
+                    MobileBertForQuestionAnswering::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(QuestionAnsweringOption::Roberta(
-                        RobertaForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Roberta,
+                    ConfigOption::Bert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::Roberta(
+                    RobertaForQuestionAnswering::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(QuestionAnsweringOption::XLMRoberta(
-                        RobertaForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLMRoberta,
+                    ConfigOption::Bert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::XLMRoberta(
+                    RobertaForQuestionAnswering::new(p, config),
+                ))
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
-                if let ConfigOption::Albert(config) = config {
-                    Ok(QuestionAnsweringOption::Albert(
-                        AlbertForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Albert,
+                    ConfigOption::Albert,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::Albert(
+                    AlbertForQuestionAnswering::new(p, config),
+                ))
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
-                if let ConfigOption::XLNet(config) = config {
-                    Ok(QuestionAnsweringOption::XLNet(
-                        XLNetForQuestionAnswering::new(p, config)?,
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a XLNetConfig for XLNet!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLNet,
+                    ConfigOption::XLNet,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::XLNet(
+                    XLNetForQuestionAnswering::new(p, config)?,
+                ))
             }
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => {
-                if let ConfigOption::Reformer(config) = config {
-                    Ok(QuestionAnsweringOption::Reformer(
-                        ReformerForQuestionAnswering::new(p, config)?,
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a ReformerConfig for Reformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Reformer,
+                    ConfigOption::Reformer,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::Reformer(
+                    ReformerForQuestionAnswering::new(p, config)?,
+                ))
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
-                if let ConfigOption::Longformer(config) = config {
-                    Ok(QuestionAnsweringOption::Longformer(
-                        LongformerForQuestionAnswering::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a LongformerConfig for Longformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Longformer,
+                    ConfigOption::Longformer,
+                    "Question answering"
+                )?;
+                Ok(QuestionAnsweringOption::Longformer(
+                    LongformerForQuestionAnswering::new(p, config),
+                ))
             }
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "QuestionAnswering not implemented for {:?}!",
@@ -403,30 +708,69 @@ impl QuestionAnsweringOption {
         }
     }
 
+    /// Checks that the model type is implemented by the pipeline, without building the model
+    pub(crate) fn ensure_supported(model_type: ModelType) -> Result<(), RustBertError> {
+        match model_type {
+            #[cfg(feature = "bert")]
+            ModelType::Bert => Ok(()),
+            ModelType::DistilBert => Ok(()),
+            #[cfg(feature = "mobilebert")]
+            ModelType::MobileBert => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::Roberta => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::XLMRoberta => Ok(()),
+            #[cfg(feature = "albert")]
+            ModelType::Albert => Ok(()),
+            #[cfg(feature = "xlnet")]
+            ModelType::XLNet => Ok(()),
+            #[cfg(feature = "reformer")]
+            ModelType::Reformer => Ok(()),
+            #[cfg(feature = "longformer")]
+            ModelType::Longformer => Ok(()),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "QuestionAnswering not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::XLMRoberta,
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
         }
     }
 
-    /// Interface method to forward_t() of the particular models.
+    /// Interface method to forward_t() of the particular models. The `global_attention_mask` is
+    /// only used by Longformer models (computed from the separator tokens if not provided) and
+    /// ignored by the other architectures.
     pub fn forward_t(
         &self,
         input_ids: Option<Tensor>,
         mask: Option<Tensor>,
+        global_attention_mask: Option<Tensor>,
         input_embeds: Option<Tensor>,
         train: bool,
     ) -> (Tensor, Tensor) {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
@@ -437,6 +781,7 @@ impl QuestionAnsweringOption {
                     .expect("Error in distilbert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 let outputs = model
                     .forward_t(
@@ -450,14 +795,17 @@ impl QuestionAnsweringOption {
                     .expect("Error in mobilebert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 let outputs = model.forward_t(
                     input_ids.as_ref(),
@@ -471,24 +819,26 @@ impl QuestionAnsweringOption {
                 );
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => {
                 let outputs = model
                     .forward_t(input_ids.as_ref(), None, None, mask.as_ref(), None, train)
                     .expect("Error in reformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 let outputs = model
                     .forward_t(
                         input_ids.as_ref(),
                         mask.as_ref(),
-                        None,
+                        global_attention_mask.as_ref(),
                         None,
                         None,
                         None,
                         train,
                     )
-                    .expect("Error in reformer forward pass");
+                    .expect("Error in longformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
         }
@@ -504,8 +854,97 @@ pub struct QuestionAnsweringModel {
     doc_stride: usize,
     max_query_length: usize,
     max_answer_len: usize,
+    answer_overlap_threshold: Option<f64>,
+    handle_impossible_answer: bool,
+    null_score_diff_threshold: f64,
+    max_tokens_per_batch: Option<usize>,
+    batch_size: usize,
+    pad_to_multiple_of: Option<usize>,
     qa_model: QuestionAnsweringOption,
     var_store: VarStore,
+    load_report: LoadReport,
+    tie_word_embeddings: bool,
+}
+
+/// Validates a question answering input before its windows are generated: the question and the
+/// context must both contain text
+pub(crate) fn validate_qa_input(qa_input: &QaInput) -> Result<(), RustBertError> {
+    let empty_question = qa_input.question.trim().is_empty();
+    let empty_context = qa_input.context.trim().is_empty();
+    let message = match (empty_question, empty_context) {
+        (false, false) => return Ok(()),
+        (true, false) => "Question is empty or only contains whitespace",
+        (false, true) => "Context is empty or only contains whitespace",
+        (true, true) => "Question and context are empty or only contain whitespace",
+    };
+    Err(RustBertError::ValueError(message.to_string()))
+}
+
+/// Checks that the windows of a context are long enough to progress through the context without
+/// generating an excessive number of windows
+fn check_sliding_window(
+    max_seq_length: usize,
+    max_query_length: usize,
+    doc_stride: usize,
+) -> Result<(), RustBertError> {
+    if max_seq_length < (max_query_length + doc_stride + 24) {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "This configuration could cause an excessive number of sliding windows generated.\
+            Please ensure max_seq_length > max_query_length + doc_stride + 24.\
+            Got max_seq_length: {}, max_query_length: {}, doc_stride: {}",
+            max_seq_length, max_query_length, doc_stride
+        )));
+    }
+    Ok(())
+}
+
+fn check_answer_overlap_threshold(config: &QuestionAnsweringConfig) -> Result<(), RustBertError> {
+    match config.answer_overlap_threshold {
+        Some(threshold) if !(0.0..=1.0).contains(&threshold) => {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "The answer overlap threshold must be between 0 and 1, got {}",
+                threshold
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_batch_size(config: &QuestionAnsweringConfig) -> Result<(), RustBertError> {
+    if config.batch_size == 0 {
+        return Err(RustBertError::InvalidConfigurationError(
+            "The batch size must be strictly positive".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Intersection over union of the character spans of two answers
+fn answer_overlap(answer: &Answer, other: &Answer) -> f64 {
+    let intersection = min(answer.end, other.end).saturating_sub(max(answer.start, other.start));
+    let union = answer.end.saturating_sub(answer.start) + other.end.saturating_sub(other.start)
+        - intersection;
+    if union == 0 {
+        1.0
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+/// Drops the answers overlapping a higher-scoring answer by more than the threshold. The answers
+/// are expected to be sorted by decreasing score. The candidates with a null score (invalid spans
+/// decoded to fill the candidates) are dropped as well.
+fn suppress_overlapping_answers(answers: Vec<Answer>, threshold: f64) -> Vec<Answer> {
+    let mut selected_answers: Vec<Answer> = vec![];
+    for answer in answers.into_iter().filter(|answer| answer.score > 0.0) {
+        if selected_answers
+            .iter()
+            .all(|selected_answer| answer_overlap(&answer, selected_answer) <= threshold)
+        {
+            selected_answers.push(answer);
+        }
+    }
+    selected_answers
 }
 
 impl QuestionAnsweringModel {
@@ -528,16 +967,14 @@ impl QuestionAnsweringModel {
     pub fn new(
         question_answering_config: QuestionAnsweringConfig,
     ) -> Result<QuestionAnsweringModel, RustBertError> {
-        let config_path = question_answering_config.config_resource.get_local_path()?;
+        ensure_initialized();
         let vocab_path = question_answering_config.vocab_resource.get_local_path()?;
-        let weights_path = question_answering_config.model_resource.get_local_path()?;
         let merges_path = if let Some(merges_resource) = &question_answering_config.merges_resource
         {
             Some(merges_resource.get_local_path()?)
         } else {
             None
         };
-        let device = question_answering_config.device;
 
         let tokenizer = TokenizerOption::from_file(
             question_answering_config.model_type,
@@ -547,15 +984,72 @@ impl QuestionAnsweringModel {
             question_answering_config.strip_accents,
             question_answering_config.add_prefix_space,
         )?;
+        QuestionAnsweringModel::from_tokenizer(question_answering_config, tokenizer)
+    }
+
+    /// Build a new `QuestionAnsweringModel` with a tokenizer built by the caller (e.g. with added
+    /// tokens or a custom lower casing). The vocabulary and merges resources of the configuration
+    /// are not used (nor downloaded), and the tokenizer must match the model type of the
+    /// configuration. The tokenizer is used as provided to encode the questions and contexts and
+    /// to compute the offsets of the answers in the contexts.
+    ///
+    /// # Arguments
+    ///
+    /// * `question_answering_config` - `QuestionAnsweringConfig` object containing the model and configuration resources and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` used to encode the inputs
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidConfigurationError` if the tokenizer cannot be used by the model type of the
+    ///   configuration, `MissingPadTokenError` if it does not define a padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::question_answering::QuestionAnsweringModel;
+    ///
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::DistilBert,
+    ///     "path/to/vocab.txt",
+    ///     None,
+    ///     false,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let qa_model = QuestionAnsweringModel::new_with_tokenizer(Default::default(), tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        question_answering_config: QuestionAnsweringConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<QuestionAnsweringModel, RustBertError> {
+        ensure_initialized();
+        tokenizer.ensure_compatible(question_answering_config.model_type)?;
+        QuestionAnsweringModel::from_tokenizer(question_answering_config, tokenizer)
+    }
+
+    fn from_tokenizer(
+        question_answering_config: QuestionAnsweringConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<QuestionAnsweringModel, RustBertError> {
+        let config_path = question_answering_config.config_resource.get_local_path()?;
+        let weights_path = question_answering_config.model_resource.get_local_path()?;
+        let device = question_answering_config.device;
+
         let pad_idx = tokenizer
             .get_pad_id()
-            .expect("The Tokenizer used for Question Answering should contain a PAD id");
-        let sep_idx = tokenizer
-            .get_sep_id()
-            .expect("The Tokenizer used for Question Answering should contain a SEP id");
+            .ok_or(RustBertError::MissingPadTokenError)?;
+        let sep_idx = tokenizer.get_sep_id().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "The tokenizer used for question answering must define a SEP token".to_string(),
+            )
+        })?;
         let mut var_store = VarStore::new(device);
         let mut model_config =
-            ConfigOption::from_file(question_answering_config.model_type, config_path);
+            ConfigOption::try_from_file(question_answering_config.model_type, config_path)?;
 
         if let ConfigOption::DistilBert(ref mut config) = model_config {
             config.sinusoidal_pos_embds = false;
@@ -567,22 +1061,18 @@ impl QuestionAnsweringModel {
             &model_config,
         )?;
 
-        if question_answering_config.max_seq_length
-            < (question_answering_config.max_query_length
-                + question_answering_config.doc_stride
-                + 24)
-        {
-            return Err(RustBertError::InvalidConfigurationError(format!(
-                "This configuration could cause an excessive number of sliding windows generated.\
-                Please ensure max_seq_length > max_query_length + doc_stride + 24.\
-                Got max_seq_length: {}, max_query_length: {}, doc_stride: {}",
-                question_answering_config.max_seq_length,
-                question_answering_config.max_query_length,
-                question_answering_config.doc_stride
-            )));
-        }
+        check_sliding_window(
+            question_answering_config.max_seq_length,
+            question_answering_config.max_query_length,
+            question_answering_config.doc_stride,
+        )?;
+        check_answer_overlap_threshold(&question_answering_config)?;
+        check_batch_size(&question_answering_config)?;
 
-        var_store.load(weights_path)?;
+        let pad_to_multiple_of =
+            model_config.resolve_pad_to_multiple_of(question_answering_config.pad_to_multiple_of);
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
         Ok(QuestionAnsweringModel {
             tokenizer,
             pad_idx,
@@ -591,21 +1081,128 @@ impl QuestionAnsweringModel {
             doc_stride: question_answering_config.doc_stride,
             max_query_length: question_answering_config.max_query_length,
             max_answer_len: question_answering_config.max_answer_length,
+            answer_overlap_threshold: question_answering_config.answer_overlap_threshold,
+            handle_impossible_answer: question_answering_config.handle_impossible_answer,
+            null_score_diff_threshold: question_answering_config.null_score_diff_threshold,
+            max_tokens_per_batch: question_answering_config.max_tokens_per_batch,
+            batch_size: question_answering_config.batch_size,
+            pad_to_multiple_of,
             qa_model,
             var_store,
+            load_report,
+            tie_word_embeddings,
         })
     }
 
+    /// Validates a configuration without downloading or loading the model weights. The configuration
+    /// and vocabulary files are fetched and parsed, and the checks run when building the pipeline
+    /// (supported model type, loadable tokenizer, sliding window and position limits) are collected
+    /// in the returned `ValidationReport` instead of failing on the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `QuestionAnsweringConfig` to validate
+    pub fn validate_config(
+        config: &QuestionAnsweringConfig,
+    ) -> Result<ValidationReport, RustBertError> {
+        let mut report = ValidationReport::new("question answering", config.model_type);
+        if config.model_type.is_enabled() {
+            report.check(QuestionAnsweringOption::ensure_supported(config.model_type));
+        }
+        let (model_config, tokenizer) = validate_resources(
+            &mut report,
+            &PipelineResources {
+                model_type: config.model_type,
+                model_resource: &config.model_resource,
+                config_resource: &config.config_resource,
+                vocab_resource: &config.vocab_resource,
+                merges_resource: config.merges_resource.as_ref(),
+                lower_case: config.lower_case,
+                strip_accents: config.strip_accents,
+                add_prefix_space: config.add_prefix_space,
+            },
+        );
+        if let Some(tokenizer) = &tokenizer {
+            if tokenizer.get_pad_id().is_none() {
+                report.errors.push(
+                    "The Tokenizer used for Question Answering should contain a PAD id".to_string(),
+                );
+            }
+            if tokenizer.get_sep_id().is_none() {
+                report.errors.push(
+                    "The Tokenizer used for Question Answering should contain a SEP id".to_string(),
+                );
+            }
+        }
+        if model_config.is_some() {
+            report.check_max_length("max_seq_length", config.max_seq_length);
+        }
+        report.check(check_sliding_window(
+            config.max_seq_length,
+            config.max_query_length,
+            config.doc_stride,
+        ));
+        report.check(check_answer_overlap_threshold(config));
+        report.check(check_batch_size(config));
+        Ok(report)
+    }
+
+    /// Returns the report of the weights loaded when building the model, listing the tying status
+    /// of the output projections, the repaired tensor shapes and the unused checkpoint tensors.
+    /// The report is updated when the weights are replaced with `swap_weights`.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Replaces the weights of the model with a checkpoint sharing its architecture (e.g. another
+    /// fine-tuning of the same base model), keeping the tokenizer and configuration. The checkpoint
+    /// is validated against the model variables before any weight is overwritten: if the swap
+    /// fails, the current weights remain in use. The load report is replaced by the report of the
+    /// new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `Resource` pointing to the new weights
+    pub fn swap_weights(&mut self, model_resource: Resource) -> Result<(), RustBertError> {
+        let weights_path = model_resource.get_local_path()?;
+        self.load_report =
+            load_with_report(&mut self.var_store, weights_path, self.tie_word_embeddings)?;
+        Ok(())
+    }
+
+    /// Returns the device the model is placed on
+    pub fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+
+    /// Moves the model to another device. The input tensors of the subsequent predictions are
+    /// created on the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - `Device` to move the model to
+    pub fn set_device(&mut self, device: Device) {
+        self.var_store.set_device(device);
+    }
+
     /// Perform extractive question answering given a list of `QaInputs`
     ///
     /// # Arguments
     ///
     /// * `qa_inputs` - `&[QaInput]` Array of Question Answering inputs (context and question pairs)
     /// * `top_k` - return the top-k answers for each QaInput. Set to 1 to return only the best answer.
-    /// * `batch_size` - maximum batch size for the model forward pass.
+    ///   With an `answer_overlap_threshold`, the answers overlapping a better answer are dropped
+    ///   before selecting the top-k answers. With `handle_impossible_answer`, an empty answer
+    ///   (empty text, `start` and `end` of 0) scored by the probability of the null answer comes
+    ///   first if the context is predicted not to contain the answer.
+    /// * `batch_size` - maximum batch size for the model forward pass. The context windows are
+    ///   sorted by length before batching, and the batches are limited to the
+    ///   `max_tokens_per_batch` of the configuration if set.
     ///
     /// # Returns
-    /// * `Vec<Vec<Answer>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`) containing the extracted answers.
+    /// * `Vec<Vec<Answer>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`, or less if a context has fewer valid spans) containing the extracted answers.
+    ///   The inputs with an empty (or whitespace-only) question or context have no answer, see
+    ///   `predict_each` for the reason of each input.
     ///
     /// # Example
     ///
@@ -639,14 +1236,233 @@ impl QuestionAnsweringModel {
         top_k: i64,
         batch_size: usize,
     ) -> Vec<Vec<Answer>> {
-        let mut features: Vec<QaFeature> = qa_inputs
+        self.predict_answers(
+            qa_inputs,
+            top_k,
+            batch_size,
+            self.max_seq_len,
+            self.doc_stride,
+            self.max_answer_len,
+            false,
+        )
+    }
+
+    /// Perform extractive question answering given a list of `QaInputs`, with limits specific to
+    /// this call overriding the ones of the configuration (e.g. longer answers for extraction
+    /// questions than for factoid questions). The options left to `None` take the value of the
+    /// configuration. `max_seq_length` and `doc_stride` change the windows the contexts are
+    /// split into (tokenization and number of forward passes), while `top_k` and
+    /// `max_answer_length` only change the decoding of the answers from the model outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `qa_inputs` - `&[QaInput]` Array of Question Answering inputs (context and question pairs)
+    /// * `options` - `QaOptions` of this prediction
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Answer>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`) containing the extracted answers.
+    /// * `RustBertError::InvalidConfigurationError` if the windows defined by the options cannot
+    ///   progress through the contexts, or if `top_k`, `max_answer_length` or `batch_size` is 0
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::{QaInput, QaOptions, QuestionAnsweringModel};
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let qa_input = QaInput {
+    ///     question: String::from("What does the warranty cover?"),
+    ///     context: String::from(
+    ///         "The warranty covers manufacturing defects and battery failures for two years.",
+    ///     ),
+    /// };
+    /// let options = QaOptions {
+    ///     top_k: Some(3),
+    ///     max_answer_length: Some(30),
+    ///     ..Default::default()
+    /// };
+    /// let answers = qa_model.predict_with_options(&[qa_input], &options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_options(
+        &self,
+        qa_inputs: &[QaInput],
+        options: &QaOptions,
+    ) -> Result<Vec<Vec<Answer>>, RustBertError> {
+        let top_k = options.top_k.unwrap_or(1);
+        let batch_size = options.batch_size.unwrap_or(self.batch_size);
+        let max_seq_length = options.max_seq_length.unwrap_or(self.max_seq_len);
+        let doc_stride = options.doc_stride.unwrap_or(self.doc_stride);
+        let max_answer_length = options.max_answer_length.unwrap_or(self.max_answer_len);
+        if (top_k < 1) | (max_answer_length == 0) | (batch_size == 0) {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "top_k, max_answer_length and batch_size must be strictly positive, \
+                got top_k: {}, max_answer_length: {}, batch_size: {}",
+                top_k, max_answer_length, batch_size
+            )));
+        }
+        check_sliding_window(max_seq_length, self.max_query_length, doc_stride)?;
+        Ok(self.predict_answers(
+            qa_inputs,
+            top_k,
+            batch_size,
+            max_seq_length,
+            doc_stride,
+            max_answer_length,
+            false,
+        ))
+    }
+
+    /// Perform extractive question answering given a list of `QaInputs`, returning an individual
+    /// result for each input
+    ///
+    /// Inputs are validated one by one before batching: invalid inputs (empty or whitespace-only
+    /// question or context) do not fail the entire batch but are excluded from the forward pass
+    /// and carry their own error.
+    ///
+    /// # Arguments
+    ///
+    /// * `qa_inputs` - `&[QaInput]` Array of Question Answering inputs (context and question pairs)
+    /// * `top_k` - return the top-k answers for each QaInput, as for `predict`
+    /// * `batch_size` - maximum batch size for the model forward pass
+    ///
+    /// # Returns
+    /// * `Vec<Result<Vec<Answer>, RustBertError>>` containing the answers or a
+    ///   `RustBertError::ValueError` for each input, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let qa_inputs = [
+    ///     QaInput {
+    ///         question: String::from("Where does Amy live ?"),
+    ///         context: String::from("Amy lives in Amsterdam"),
+    ///     },
+    ///     QaInput {
+    ///         question: String::from("Where does Eric live"),
+    ///         context: String::from(" "),
+    ///     },
+    /// ];
+    /// let answers = qa_model.predict_each(&qa_inputs, 1, 32);
+    /// assert!(answers[1].is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_each(
+        &self,
+        qa_inputs: &[QaInput],
+        top_k: i64,
+        batch_size: usize,
+    ) -> Vec<Result<Vec<Answer>, RustBertError>> {
+        qa_inputs
+            .iter()
+            .zip(self.predict(qa_inputs, top_k, batch_size))
+            .map(|(qa_input, answers)| validate_qa_input(qa_input).map(|_| answers))
+            .collect()
+    }
+
+    /// Perform extractive question answering of a question over several contexts (e.g. the
+    /// passages returned by a retrieval system), ranking the answers of all contexts together.
+    /// The start and end probabilities are normalized over the windows of all contexts (shared
+    /// normalization), so that the scores of answers from different contexts are comparable.
+    /// The windows are processed in batches of the `batch_size` of the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - Question to answer
+    /// * `contexts` - `&[&str]` Array of contexts to extract the answers from
+    /// * `top_k` - Number of answers to return
+    ///
+    /// # Returns
+    /// * `Vec<Answer>` The `top_k` answers of all contexts sorted by decreasing score, the index of
+    ///   the context each answer was extracted from being given by its `context_index`. Empty (or
+    ///   whitespace-only) contexts have no answer, and an empty question has no answer at all.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::QuestionAnsweringModel;
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let contexts = [
+    ///     "Eric lives in The Hague.",
+    ///     "Amy lives in Amsterdam.",
+    ///     "Amy works in The Hague.",
+    /// ];
+    /// let answers = qa_model.predict_multi_context("Where does Amy live ?", &contexts, 3);
+    /// let best_context = contexts[answers[0].context_index];
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multi_context(
+        &self,
+        question: &str,
+        contexts: &[&str],
+        top_k: i64,
+    ) -> Vec<Answer> {
+        let qa_inputs = contexts
+            .iter()
+            .map(|&context| QaInput {
+                question: question.to_string(),
+                context: context.to_string(),
+            })
+            .collect::<Vec<QaInput>>();
+        let mut answers = self
+            .predict_answers(
+                &qa_inputs,
+                top_k,
+                self.batch_size,
+                self.max_seq_len,
+                self.doc_stride,
+                self.max_answer_len,
+                true,
+            )
+            .into_iter()
+            .enumerate()
+            .flat_map(|(context_index, answers)| {
+                answers.into_iter().map(move |answer| Answer {
+                    context_index,
+                    ..answer
+                })
+            })
+            .collect::<Vec<Answer>>();
+        answers.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        answers.truncate(max(top_k, 0) as usize);
+        answers
+    }
+
+    /// Extracts the `top_k` answers of each input. With `shared_normalization`, the probabilities
+    /// of the answers are normalized over the windows of all inputs instead of each window.
+    fn predict_answers(
+        &self,
+        qa_inputs: &[QaInput],
+        top_k: i64,
+        batch_size: usize,
+        max_seq_length: usize,
+        doc_stride: usize,
+        max_answer_length: usize,
+        shared_normalization: bool,
+    ) -> Vec<Vec<Answer>> {
+        //    Invalid inputs generate no window and are left without answer
+        let features: Vec<QaFeature> = qa_inputs
             .iter()
             .enumerate()
+            .filter(|(_, qa_example)| validate_qa_input(qa_example).is_ok())
             .map(|(example_index, qa_example)| {
                 self.generate_features(
                     &qa_example,
-                    self.max_seq_len,
-                    self.doc_stride,
+                    max_seq_length,
+                    doc_stride,
                     self.max_query_length,
                     example_index as i64,
                 )
@@ -654,85 +1470,125 @@ impl QuestionAnsweringModel {
             .flatten()
             .collect();
 
-        let mut example_top_k_answers_map: HashMap<usize, Vec<Answer>> = HashMap::new();
-        let mut start = 0usize;
-        let len_features = features.len();
-
-        while start < len_features {
-            let end = start + min(len_features - start, batch_size);
-            let batch_features = &mut features[start..end];
+        let candidates_k = match self.answer_overlap_threshold {
+            Some(_) => suppression_candidates(top_k, max_answer_length, max_seq_length),
+            None => top_k,
+        };
+        //    The windows are sorted by length and batched with windows of similar length to limit
+        //    the padding. The answers are collected in the original order of the windows.
+        let mut order = (0..features.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&feature_index| features[feature_index].input_ids.len());
+        let mut feature_answers: Vec<Option<(Vec<Answer>, Option<NullAnswerScores>, (f64, f64))>> =
+            (0..features.len()).map(|_| None).collect();
+        for batch in self.bucket_features(&features, &order, batch_size) {
+            let batch_features = batch
+                .iter()
+                .map(|&feature_index| &features[feature_index])
+                .collect::<Vec<&QaFeature>>();
             no_grad(|| {
-                let (input_ids, attention_masks) = self.pad_features(batch_features);
+                let (input_ids, attention_masks) = self.pad_features(&batch_features);
+                let global_attention_mask = self.global_attention_mask(&batch_features, &input_ids);
 
-                let (start_logits, end_logits) =
-                    self.qa_model
-                        .forward_t(Some(input_ids), Some(attention_masks), None, false);
+                let (start_logits, end_logits) = self.qa_model.forward_t(
+                    Some(input_ids),
+                    Some(attention_masks),
+                    global_attention_mask,
+                    None,
+                    false,
+                );
 
                 let start_logits = start_logits.detach();
                 let end_logits = end_logits.detach();
-                let example_index_to_feature_end_position: Vec<(usize, i64)> = batch_features
-                    .iter()
-                    .enumerate()
-                    .map(|(feature_index, feature)| {
-                        (feature.example_index as usize, feature_index as i64 + 1)
-                    })
-                    .collect();
-
-                let mut feature_id_start = 0;
-
-                for (example_id, max_feature_id) in example_index_to_feature_end_position {
-                    let mut answers: Vec<Answer> = vec![];
-                    let example = &qa_inputs[example_id];
-                    for feature_idx in feature_id_start..max_feature_id {
-                        let feature = &batch_features[feature_idx as usize];
-                        let p_mask = (Tensor::of_slice(&feature.p_mask) - 1)
-                            .abs()
-                            .to_device(start_logits.device())
-                            .eq(0);
-
-                        let start = start_logits.get(feature_idx).masked_fill(&p_mask, -10000);
-                        let end = end_logits.get(feature_idx).masked_fill(&p_mask, -10000);
-
-                        let start = start.exp() / start.exp().sum(Float);
-                        let end = end.exp() / end.exp().sum(Float);
-
-                        let (starts, ends, scores) = self.decode(&start, &end, top_k);
-
-                        for idx in 0..starts.len() {
-                            let start_pos = feature.offsets[starts[idx] as usize]
-                                .unwrap_or(Offset { begin: 0, end: 0 })
-                                .begin as usize;
-                            let end_pos = feature.offsets[ends[idx] as usize]
-                                .unwrap_or(Offset { begin: 0, end: 0 })
-                                .end as usize;
-                            let answer = example
-                                .context
-                                .chars()
-                                .take(end_pos)
-                                .skip(start_pos)
-                                .collect::<String>();
-
-                            answers.push(Answer {
-                                score: scores[idx],
-                                start: start_pos,
-                                end: end_pos,
-                                answer,
-                            });
-                        }
-                    }
-                    feature_id_start = max_feature_id;
-                    let example_answers = example_top_k_answers_map
-                        .entry(example_id)
-                        .or_insert_with(Vec::new);
-                    example_answers.extend(answers);
+                for (batch_index, &feature_index) in batch.iter().enumerate() {
+                    let feature = &features[feature_index];
+                    let feature_length = feature.input_ids.len() as i64;
+                    feature_answers[feature_index] = Some(
+                        self.decode_feature(
+                            feature,
+                            &start_logits
+                                .get(batch_index as i64)
+                                .narrow(0, 0, feature_length),
+                            &end_logits
+                                .get(batch_index as i64)
+                                .narrow(0, 0, feature_length),
+                            &qa_inputs[feature.example_index as usize].context,
+                            candidates_k,
+                            max_answer_length,
+                        ),
+                    );
                 }
             });
-            start = end;
+        }
+
+        let feature_answers = feature_answers
+            .into_iter()
+            .map(Option::unwrap)
+            .collect::<Vec<_>>();
+        //    The probabilities of a window are rescaled by the ratio of its normalizers to the sum
+        //    of the normalizers of all windows
+        let global_log_normalizers = if shared_normalization {
+            let (start_log_normalizers, end_log_normalizers): (Vec<f64>, Vec<f64>) =
+                feature_answers
+                    .iter()
+                    .map(|(_, _, log_normalizers)| *log_normalizers)
+                    .unzip();
+            Some((
+                log_sum_exp(&start_log_normalizers),
+                log_sum_exp(&end_log_normalizers),
+            ))
+        } else {
+            None
+        };
+
+        let mut example_top_k_answers_map: HashMap<usize, Vec<Answer>> = HashMap::new();
+        let mut example_null_answer_scores: HashMap<usize, NullAnswerScores> = HashMap::new();
+        for (feature, output) in features.iter().zip(feature_answers.into_iter()) {
+            let (mut answers, mut null_answer_scores, log_normalizers) = output;
+            if let Some((global_start, global_end)) = global_log_normalizers {
+                let scale =
+                    (log_normalizers.0 + log_normalizers.1 - global_start - global_end).exp();
+                for answer in answers.iter_mut() {
+                    answer.score *= scale;
+                }
+                if let Some(null_answer_scores) = null_answer_scores.as_mut() {
+                    null_answer_scores.null_probability *= scale;
+                }
+            }
+            let example_id = feature.example_index as usize;
+            example_top_k_answers_map
+                .entry(example_id)
+                .or_insert_with(Vec::new)
+                .extend(answers);
+            if let Some(null_answer_scores) = null_answer_scores {
+                example_null_answer_scores
+                    .entry(example_id)
+                    .and_modify(|scores: &mut NullAnswerScores| scores.merge(null_answer_scores))
+                    .or_insert(null_answer_scores);
+            }
         }
         let mut all_answers = vec![];
         for example_id in 0..qa_inputs.len() {
             if let Some(answers) = example_top_k_answers_map.get_mut(&example_id) {
-                remove_duplicates(answers).sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+                remove_duplicates(answers)
+                    .sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+                let mut answers = match self.answer_overlap_threshold {
+                    Some(threshold) => suppress_overlapping_answers(answers.clone(), threshold),
+                    None => answers.clone(),
+                };
+                if let Some(null_answer_scores) = example_null_answer_scores.get(&example_id) {
+                    if null_answer_scores.is_impossible(self.null_score_diff_threshold) {
+                        answers.insert(
+                            0,
+                            Answer {
+                                score: null_answer_scores.null_probability,
+                                start: 0,
+                                end: 0,
+                                answer: String::new(),
+                                context_index: 0,
+                            },
+                        );
+                    }
+                }
                 all_answers.push(answers[..min(answers.len(), top_k as usize)].to_vec());
             } else {
                 all_answers.push(vec![]);
@@ -741,31 +1597,103 @@ impl QuestionAnsweringModel {
         all_answers
     }
 
-    fn decode(&self, start: &Tensor, end: &Tensor, top_k: i64) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
-        let outer = start.unsqueeze(-1).matmul(&end.unsqueeze(0));
-        let start_dim = start.size()[0];
-        let end_dim = end.size()[0];
-        let candidates = outer
-            .triu(0)
-            .tril(self.max_answer_len as i64 - 1)
-            .flatten(0, -1);
-        let idx_sort = if top_k == 1 {
-            candidates.argmax(0, true)
-        } else if candidates.size()[0] < top_k {
-            candidates.argsort(0, true)
-        } else {
-            candidates.argsort(0, true).slice(0, 0, top_k, 1)
-        };
-        let mut start: Vec<i64> = vec![];
-        let mut end: Vec<i64> = vec![];
-        let mut scores: Vec<f64> = vec![];
-        for flat_index_position in 0..idx_sort.size()[0] {
-            let flat_index = idx_sort.int64_value(&[flat_index_position]);
-            scores.push(candidates.double_value(&[flat_index]));
-            start.push(flat_index / start_dim);
-            end.push(flat_index % end_dim);
+    /// Splits the windows, sorted by length, into batches of at most `batch_size` windows. With a
+    /// `max_tokens_per_batch`, a batch is also closed before its padded size (number of windows
+    /// times the length of the longest window) exceeds the budget.
+    fn bucket_features(
+        &self,
+        features: &[QaFeature],
+        order: &[usize],
+        batch_size: usize,
+    ) -> Vec<Vec<usize>> {
+        let mut batches: Vec<Vec<usize>> = vec![];
+        let mut batch: Vec<usize> = vec![];
+        for &feature_index in order {
+            let padded_length = get_padded_length(
+                features[feature_index].input_ids.len(),
+                self.pad_to_multiple_of,
+            );
+            let exceeds_budget = self.max_tokens_per_batch.map_or(false, |max_tokens| {
+                (batch.len() + 1) * padded_length > max_tokens
+            });
+            if !batch.is_empty() & ((batch.len() == batch_size) | exceeds_budget) {
+                batches.push(std::mem::take(&mut batch));
+            }
+            batch.push(feature_index);
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+
+    /// Decodes the candidate answers of a window from its start and end logits, returning the
+    /// scores of the null answer if impossible answers are handled and the logarithms of the
+    /// normalizers of the start and end probabilities
+    fn decode_feature(
+        &self,
+        feature: &QaFeature,
+        start_logits: &Tensor,
+        end_logits: &Tensor,
+        context: &str,
+        candidates_k: i64,
+        max_answer_length: usize,
+    ) -> (Vec<Answer>, Option<NullAnswerScores>, (f64, f64)) {
+        let (start, end, null_answer_scores, log_normalizers) = feature_probabilities(
+            start_logits,
+            end_logits,
+            &feature.p_mask,
+            if self.handle_impossible_answer {
+                Some(feature.cls_index)
+            } else {
+                None
+            },
+        );
+
+        let (starts, ends, scores) = decode_spans(
+            &start,
+            &end,
+            &feature.p_mask,
+            candidates_k,
+            max_answer_length,
+        );
+
+        let null_answer_scores = null_answer_scores.map(|mut null_answer_scores| {
+            null_answer_scores.best_span_score =
+                starts.first().zip(ends.first()).map(|(&start, &end)| {
+                    start_logits.double_value(&[start]) + end_logits.double_value(&[end])
+                });
+            null_answer_scores
+        });
+
+        let mut answers: Vec<Answer> = vec![];
+        for idx in 0..starts.len() {
+            let start_pos = feature.offsets[starts[idx] as usize]
+                .unwrap_or(Offset { begin: 0, end: 0 })
+                .begin as usize;
+            let end_pos = feature.offsets[ends[idx] as usize]
+                .unwrap_or(Offset { begin: 0, end: 0 })
+                .end as usize;
+            //    The offsets of the tokenizer point to the original context (before normalization)
+            let answer = match (
+                char_to_byte(context, start_pos),
+                char_to_byte(context, end_pos),
+            ) {
+                (Some(byte_start), Some(byte_end)) if byte_start <= byte_end => {
+                    context[byte_start..byte_end].to_string()
+                }
+                _ => String::new(),
+            };
+
+            answers.push(Answer {
+                score: scores[idx],
+                start: start_pos,
+                end: end_pos,
+                answer,
+                context_index: 0,
+            });
         }
-        (start, end, scores)
+        (answers, null_answer_scores, log_normalizers)
     }
 
     fn generate_features(
@@ -836,11 +1764,16 @@ impl QuestionAnsweringModel {
                 .tokenizer
                 .build_input_with_special_tokens(encoded_query.clone(), Some(sub_encoded_context));
             let p_mask = self.get_mask(&encoded_span);
+            let cls_index = match self.tokenizer.model_type() {
+                ModelType::XLNet => encoded_span.token_ids.len() - 1,
+                _ => 0,
+            };
             let qa_feature = QaFeature {
                 input_ids: encoded_span.token_ids,
                 offsets: encoded_span.token_offsets,
                 p_mask,
                 example_index,
+                cls_index,
             };
             spans.push(qa_feature);
             if end_token == encoded_context.ids.len() {
@@ -851,12 +1784,15 @@ impl QuestionAnsweringModel {
         spans
     }
 
-    fn pad_features(&self, features: &mut [QaFeature]) -> (Tensor, Tensor) {
+    /// Pads the input ids of a batch of windows to the length of the longest window, returning
+    /// the input ids and attention masks. The windows are left unchanged.
+    fn pad_features(&self, features: &[&QaFeature]) -> (Tensor, Tensor) {
         let max_len = features
             .iter()
             .map(|feature| feature.input_ids.len())
             .max()
             .unwrap();
+        let max_len = get_padded_length(max_len, self.pad_to_multiple_of);
 
         let attention_masks = features
             .iter()
@@ -869,21 +1805,13 @@ impl QuestionAnsweringModel {
             .map(|input| Tensor::of_slice(&(input)))
             .collect::<Vec<_>>();
 
-        for feature in features.iter_mut() {
-            feature
-                .offsets
-                .append(&mut vec![None; max_len - feature.input_ids.len()]);
-            feature
-                .p_mask
-                .append(&mut vec![1; max_len - feature.input_ids.len()]);
-            feature
-                .input_ids
-                .append(&mut vec![self.pad_idx; max_len - feature.input_ids.len()]);
-        }
-
         let padded_input_ids = features
-            .iter_mut()
-            .map(|input| Tensor::of_slice(input.input_ids.as_slice()))
+            .iter()
+            .map(|feature| {
+                let mut input_ids = feature.input_ids.clone();
+                input_ids.append(&mut vec![self.pad_idx; max_len - input_ids.len()]);
+                Tensor::of_slice(input_ids.as_slice())
+            })
             .collect::<Vec<_>>();
 
         let input_ids = Tensor::stack(&padded_input_ids, 0).to(self.var_store.device());
@@ -891,6 +1819,31 @@ impl QuestionAnsweringModel {
         (input_ids, attention_masks)
     }
 
+    /// Global attention mask of a batch of windows for Longformer models, setting global attention
+    /// on the CLS and question tokens (all positions before the first separator) so that the
+    /// question attends (and is attended by) the full context. Returns `None` for the other
+    /// architectures.
+    fn global_attention_mask(&self, features: &[&QaFeature], input_ids: &Tensor) -> Option<Tensor> {
+        match self.qa_model.model_type() {
+            ModelType::Longformer => {
+                let global_attention_mask = input_ids.zeros_like();
+                for (batch_index, feature) in features.iter().enumerate() {
+                    let question_end = feature
+                        .input_ids
+                        .iter()
+                        .position(|&token_id| token_id == self.sep_idx)
+                        .unwrap_or(0);
+                    let _ = global_attention_mask
+                        .get(batch_index as i64)
+                        .narrow(0, 0, question_end as i64)
+                        .fill_(1);
+                }
+                Some(global_attention_mask)
+            }
+            _ => None,
+        }
+    }
+
     fn get_mask(&self, encoded_span: &TokenizedInput) -> Vec<i8> {
         let sep_indices: Vec<usize> = encoded_span
             .token_ids
@@ -959,4 +1912,549 @@ mod test {
         let config = QuestionAnsweringConfig::default();
         let _: Box<dyn Send> = Box::new(QuestionAnsweringModel::new(config));
     }
+
+    fn answer(start: usize, end: usize, score: f64) -> Answer {
+        Answer {
+            score,
+            start,
+            end,
+            answer: String::new(),
+            context_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_suppress_overlapping_answers() {
+        //    "Paris", "Paris,", "in Paris", "France" and "Paris" in a second sentence
+        let answers = vec![
+            answer(13, 18, 0.5),
+            answer(13, 19, 0.2),
+            answer(10, 18, 0.1),
+            answer(29, 35, 0.08),
+            answer(50, 55, 0.05),
+        ];
+        assert!((answer_overlap(&answers[0], &answers[1]) - 5.0 / 6.0).abs() < 1e-9);
+        assert!((answer_overlap(&answers[0], &answers[2]) - 5.0 / 8.0).abs() < 1e-9);
+        assert_eq!(answer_overlap(&answers[0], &answers[4]), 0.0);
+
+        let spans = |answers: Vec<Answer>| {
+            answers
+                .iter()
+                .map(|answer| (answer.start, answer.end))
+                .collect::<Vec<(usize, usize)>>()
+        };
+        assert_eq!(
+            spans(suppress_overlapping_answers(answers.clone(), 0.0)),
+            [(13, 18), (29, 35), (50, 55)]
+        );
+        assert_eq!(
+            spans(suppress_overlapping_answers(answers.clone(), 0.7)),
+            [(13, 18), (10, 18), (29, 35), (50, 55)]
+        );
+        assert_eq!(
+            spans(suppress_overlapping_answers(answers.clone(), 1.0)).len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_null_answer_scores() {
+        //    CLS token, question token, SEP token and 2 context tokens
+        let start_logits = Tensor::of_slice(&[5.0f32, 8.0, 8.0, 2.0, 0.0]);
+        let end_logits = Tensor::of_slice(&[5.0f32, 8.0, 8.0, 0.0, 3.0]);
+        let p_mask = [1, 1, 1, 0, 0];
+
+        let (start, end, null_answer_scores, log_normalizers) =
+            feature_probabilities(&start_logits, &end_logits, &p_mask, None);
+        assert!(null_answer_scores.is_none());
+        assert!((log_normalizers.0 - (2.0f64.exp() + 1.0).ln()).abs() < 1e-4);
+        assert!((log_normalizers.1 - (3.0f64.exp() + 1.0).ln()).abs() < 1e-4);
+        assert!((start.double_value(&[3]) - 1.0 / (1.0 + (-2.0f64).exp())).abs() < 1e-6);
+        assert!((end.double_value(&[4]) - 1.0 / (1.0 + (-3.0f64).exp())).abs() < 1e-6);
+
+        let (start, end, null_answer_scores, _) =
+            feature_probabilities(&start_logits, &end_logits, &p_mask, Some(0));
+        let mut null_answer_scores = null_answer_scores.unwrap();
+        assert_eq!(null_answer_scores.null_score, 10.0);
+        let start_normalization = 5.0f64.exp() + 2.0f64.exp() + 1.0;
+        let end_normalization = 5.0f64.exp() + 3.0f64.exp() + 1.0;
+        let null_probability =
+            5.0f64.exp() * 5.0f64.exp() / (start_normalization * end_normalization);
+        assert!((null_answer_scores.null_probability - null_probability).abs() < 1e-6);
+        //    The CLS token is excluded from the answer spans
+        assert_eq!(start.double_value(&[0]), 0.0);
+        assert_eq!(end.double_value(&[0]), 0.0);
+        assert!((start.double_value(&[3]) - 2.0f64.exp() / start_normalization).abs() < 1e-6);
+
+        //    Best span from the 4th to the 5th token, scored 5.0
+        assert!(null_answer_scores.is_impossible(4.0));
+        null_answer_scores.best_span_score = Some(5.0);
+        assert!(null_answer_scores.is_impossible(4.0));
+        assert!(!null_answer_scores.is_impossible(5.0));
+
+        //    A second window with a lower null score and a better span
+        null_answer_scores.merge(NullAnswerScores {
+            null_score: 8.0,
+            null_probability: 0.25,
+            best_span_score: Some(7.0),
+        });
+        assert_eq!(null_answer_scores.null_score, 8.0);
+        assert_eq!(null_answer_scores.null_probability, 0.25);
+        assert_eq!(null_answer_scores.best_span_score, Some(7.0));
+        assert!(null_answer_scores.is_impossible(0.5));
+        assert!(!null_answer_scores.is_impossible(1.0));
+    }
+
+    fn tiny_distilbert_config(
+        directory: &std::path::Path,
+    ) -> anyhow::Result<QuestionAnsweringConfig> {
+        use crate::fixtures::tiny_distilbert_resources;
+
+        let vocab = [
+            "[PAD]",
+            "[UNK]",
+            "[CLS]",
+            "[SEP]",
+            "[MASK]",
+            "where",
+            "does",
+            "amy",
+            "live",
+            "lives",
+            "in",
+            "amsterdam",
+            "and",
+            "works",
+            "the",
+            "hague",
+            "?",
+            ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) =
+            tiny_distilbert_resources(directory, &vocab, None)?;
+        let mut config = QuestionAnsweringConfig::new(
+            ModelType::DistilBert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        config.max_seq_length = 64;
+        config.doc_stride = 16;
+        config.max_query_length = 16;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_predict_with_options() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = QuestionAnsweringModel::new(tiny_distilbert_config(directory.path())?)?;
+        let qa_inputs = || {
+            vec![QaInput {
+                question: "where does amy live ?".to_string(),
+                context: "amy lives in amsterdam and works in the hague .".to_string(),
+            }]
+        };
+
+        let expected = model.predict(&qa_inputs(), 3, 64);
+        let output = model.predict_with_options(
+            &qa_inputs(),
+            &QaOptions {
+                top_k: Some(3),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(output, expected);
+
+        //    Single-token answers
+        let output = model.predict_with_options(
+            &qa_inputs(),
+            &QaOptions {
+                top_k: Some(5),
+                max_answer_length: Some(1),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(output[0].len(), 5);
+        assert!(output[0].iter().all(|answer| !answer.answer.contains(' ')));
+
+        //    Shorter windows, within the limits of the configuration
+        let output = model.predict_with_options(
+            &qa_inputs(),
+            &QaOptions {
+                max_seq_length: Some(48),
+                doc_stride: Some(8),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(output[0].len(), 1);
+
+        for options in &[
+            QaOptions {
+                top_k: Some(0),
+                ..Default::default()
+            },
+            QaOptions {
+                max_answer_length: Some(0),
+                ..Default::default()
+            },
+            QaOptions {
+                doc_stride: Some(32),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                model.predict_with_options(&qa_inputs(), options),
+                Err(RustBertError::InvalidConfigurationError(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_length_bucketing() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.max_tokens_per_batch = Some(40);
+        let mut model = QuestionAnsweringModel::new(config)?;
+
+        let feature = |length: usize| QaFeature {
+            input_ids: vec![0; length],
+            offsets: vec![None; length],
+            p_mask: vec![0; length],
+            example_index: 0,
+            cls_index: 0,
+        };
+        let features = [30, 10, 20, 10, 12]
+            .iter()
+            .map(|&length| feature(length))
+            .collect::<Vec<QaFeature>>();
+        let order = [1, 3, 4, 2, 0];
+        assert_eq!(
+            model.bucket_features(&features, &order, 3),
+            [vec![1, 3, 4], vec![2], vec![0]]
+        );
+        model.max_tokens_per_batch = None;
+        assert_eq!(
+            model.bucket_features(&features, &order, 3),
+            [vec![1, 3, 4], vec![2, 0]]
+        );
+        model.max_tokens_per_batch = Some(40);
+
+        //    The answers do not depend on the batching of the inputs
+        let contexts = [
+            "amy lives in amsterdam and works in the hague . amy lives in amsterdam .",
+            "amy lives in amsterdam .",
+            "amy works in the hague and lives in amsterdam .",
+            "amy lives in the hague .",
+        ];
+        let qa_input = |context: &str| QaInput {
+            question: "where does amy live ?".to_string(),
+            context: context.to_string(),
+        };
+        let output = model.predict(
+            &contexts
+                .iter()
+                .map(|context| qa_input(context))
+                .collect::<Vec<QaInput>>(),
+            3,
+            4,
+        );
+        assert_eq!(output.len(), contexts.len());
+        for (context, answers) in contexts.iter().zip(output.iter()) {
+            let expected_answers = model.predict(&[qa_input(context)], 3, 1).remove(0);
+            assert_eq!(answers, &expected_answers);
+            for (answer, expected_answer) in answers.iter().zip(expected_answers.iter()) {
+                assert!((answer.score - expected_answer.score).abs() < 1e-6);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_valid_spans() {
+        //    CLS token, 2 question tokens, SEP token, 3 context tokens, SEP token and padding
+        let p_mask = [1, 1, 1, 1, 0, 0, 0, 1, 1];
+        //    The question tokens and the end preceding the start score better than the context
+        let start = Tensor::of_slice(&[0.0f32, 0.3, 0.3, 0.0, 0.02, 0.03, 0.3, 0.0, 0.05]);
+        let end = Tensor::of_slice(&[0.0f32, 0.3, 0.3, 0.0, 0.3, 0.03, 0.01, 0.0, 0.05]);
+
+        let (starts, ends, scores) = decode_spans(&start, &end, &p_mask, 1, 15);
+        assert_eq!((starts[0], ends[0]), (4, 4));
+        assert!((scores[0] - 0.02 * 0.3).abs() < 1e-6);
+
+        //    Only the 6 spans of the context are returned
+        let (starts, ends, scores) = decode_spans(&start, &end, &p_mask, 10, 15);
+        assert_eq!(starts.len(), 6);
+        for ((&start, &end), &score) in starts.iter().zip(ends.iter()).zip(scores.iter()) {
+            assert!((4..7).contains(&start) & (4..7).contains(&end));
+            assert!(start <= end);
+            assert!(score >= 0.0);
+        }
+        assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        //    Single-token spans
+        let (starts, ends, _) = decode_spans(&start, &end, &p_mask, 10, 1);
+        assert_eq!(starts.len(), 3);
+        assert_eq!(starts, ends);
+
+        //    No valid span
+        let (starts, _, _) = decode_spans(&start, &end, &[1; 9], 3, 15);
+        assert!(starts.is_empty());
+    }
+
+    #[test]
+    fn test_suppression_candidates() {
+        assert_eq!(suppression_candidates(3, 15, 384), 3 * 15 * 15);
+        //    Capped at the number of valid spans of a window
+        assert_eq!(suppression_candidates(100, 15, 384), 384 * 15);
+        assert_eq!(suppression_candidates(3, 1000, 16), 16 * 16);
+        assert_eq!(
+            suppression_candidates(i64::MAX, usize::MAX, 4096),
+            4096 * 4096
+        );
+    }
+
+    #[test]
+    fn test_predict_multi_context() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = QuestionAnsweringModel::new(tiny_distilbert_config(directory.path())?)?;
+        let question = "where does amy live ?";
+        let context = "amy lives in amsterdam and works in the hague .";
+        let expected = model.predict(
+            &[QaInput {
+                question: question.to_string(),
+                context: context.to_string(),
+            }],
+            3,
+            1,
+        );
+
+        //    A single context is normalized as by `predict`
+        let answers = model.predict_multi_context(question, &[context], 3);
+        assert_eq!(answers.len(), 3);
+        for (answer, expected) in answers.iter().zip(expected[0].iter()) {
+            assert_eq!(answer, expected);
+            assert!((answer.score - expected.score).abs() < 1e-6);
+        }
+
+        //    The probabilities are shared between two identical contexts
+        let answers = model.predict_multi_context(question, &[context, context], 4);
+        assert_eq!(answers.len(), 4);
+        assert!((answers[0].score - expected[0][0].score / 4.0).abs() < 1e-6);
+        assert_eq!(answers[0].answer, expected[0][0].answer);
+        assert_eq!(answers[1].answer, expected[0][0].answer);
+        assert_ne!(answers[0].context_index, answers[1].context_index);
+
+        let contexts = [
+            "the hague .",
+            context,
+            "amy works in the hague and lives in amsterdam .",
+        ];
+        let answers = model.predict_multi_context(question, &contexts, 5);
+        assert_eq!(answers.len(), 5);
+        for pair in answers.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+        for answer in &answers {
+            assert!(answer.context_index < contexts.len());
+            let context = contexts[answer.context_index];
+            let (byte_start, byte_end) = answer.byte_offsets(context).unwrap();
+            assert_eq!(&context[byte_start..byte_end], answer.answer);
+        }
+        assert!(model.predict_multi_context(question, &[], 5).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_inputs() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = QuestionAnsweringModel::new(tiny_distilbert_config(directory.path())?)?;
+        let qa_input = |question: &str, context: &str| QaInput {
+            question: question.to_string(),
+            context: context.to_string(),
+        };
+        let valid_input = || {
+            qa_input(
+                "where does amy live ?",
+                "amy lives in amsterdam and works in the hague .",
+            )
+        };
+        let expected = model.predict(&[valid_input()], 2, 4).remove(0);
+        assert!(!expected.is_empty());
+
+        let qa_inputs = [
+            qa_input("", "amy lives in amsterdam ."),
+            valid_input(),
+            qa_input("where does amy live ?", ""),
+            qa_input(" \t", "\r\n "),
+            qa_input("where does amy live ?", "  \n"),
+        ];
+        let output = model.predict(&qa_inputs, 2, 4);
+        assert_eq!(output.len(), qa_inputs.len());
+        assert_eq!(output[1], expected);
+        for &index in &[0, 2, 3, 4] {
+            assert!(output[index].is_empty());
+        }
+
+        let output = model.predict_each(&qa_inputs, 2, 4);
+        assert_eq!(output.len(), qa_inputs.len());
+        assert_eq!(output[1].as_ref().unwrap(), &expected);
+        for (index, expected_message) in &[
+            (0, "Question is empty"),
+            (2, "Context is empty"),
+            (3, "Question and context are empty"),
+            (4, "Context is empty"),
+        ] {
+            assert!(matches!(
+                &output[*index],
+                Err(RustBertError::ValueError(message)) if message.starts_with(expected_message)
+            ));
+        }
+
+        //    Batches made of invalid inputs only
+        let output = model.predict(&qa_inputs[2..], 2, 4);
+        assert!(output.iter().all(|answers| answers.is_empty()));
+        let answers = model.predict_multi_context(
+            "where does amy live ?",
+            &["", "amy lives in amsterdam .", " "],
+            3,
+        );
+        assert!(!answers.is_empty());
+        assert!(answers.iter().all(|answer| answer.context_index == 1));
+        assert!(model
+            .predict_multi_context(" ", &["amy lives in amsterdam ."], 3)
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_tokenizer() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let reference = QuestionAnsweringModel::new(tiny_distilbert_config(directory.path())?)?;
+        let vocab_path = directory.path().join("vocab.txt");
+        let vocab_path = vocab_path.to_str().unwrap();
+
+        //    The vocabulary resource and the tokenizer options of the configuration are not used
+        //    when the tokenizer is provided
+        let tokenizer =
+            TokenizerOption::from_file(ModelType::DistilBert, vocab_path, None, true, None, None)?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.vocab_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_vocab.txt"),
+        });
+        config.lower_case = false;
+        let model = QuestionAnsweringModel::new_with_tokenizer(config, tokenizer)?;
+
+        let qa_inputs = || {
+            vec![QaInput {
+                question: "Where does Amy live ?".to_string(),
+                context: "Amy lives in  Amsterdam and works in The Hague .".to_string(),
+            }]
+        };
+        let expected = reference.predict(&qa_inputs(), 3, 1);
+        let output = model.predict(&qa_inputs(), 3, 1);
+        assert_eq!(output, expected);
+        let context = &qa_inputs()[0].context;
+        for answer in &output[0] {
+            let (byte_start, byte_end) = answer.byte_offsets(context).unwrap();
+            assert_eq!(&context[byte_start..byte_end], answer.answer);
+        }
+
+        let tokenizer =
+            TokenizerOption::from_file(ModelType::DistilBert, vocab_path, None, true, None, None)?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.model_type = ModelType::Roberta;
+        assert!(matches!(
+            QuestionAnsweringModel::new_with_tokenizer(config, tokenizer),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let vocab_path = directory.path().join("vocab.txt");
+        std::fs::write(
+            &vocab_path,
+            "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nwhere\nparis",
+        )?;
+        let config_path = directory.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{
+                "activation": "gelu",
+                "attention_dropout": 0.1,
+                "dim": 16,
+                "dropout": 0.1,
+                "hidden_dim": 32,
+                "initializer_range": 0.02,
+                "max_position_embeddings": 128,
+                "n_heads": 2,
+                "n_layers": 2,
+                "qa_dropout": 0.1,
+                "seq_classif_dropout": 0.2,
+                "sinusoidal_pos_embds": false,
+                "tie_weights_": false,
+                "vocab_size": 7
+            }"#,
+        )?;
+        let mut config = QuestionAnsweringConfig::new(
+            ModelType::DistilBert,
+            Resource::Local(LocalResource {
+                local_path: directory.path().join("rust_model.ot"),
+            }),
+            Resource::Local(LocalResource {
+                local_path: config_path,
+            }),
+            Resource::Local(LocalResource {
+                local_path: vocab_path,
+            }),
+            None,
+            true,
+            None,
+            None,
+        );
+
+        //    Missing weights, and default windows longer than the position embeddings
+        let report = QuestionAnsweringModel::validate_config(&config)?;
+        assert_eq!(report.max_positions, Some(128));
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[1].contains("max_seq_length (384)"));
+
+        std::fs::write(directory.path().join("rust_model.ot"), "")?;
+        config.max_seq_length = 128;
+        config.doc_stride = 32;
+        config.max_query_length = 32;
+        let report = QuestionAnsweringModel::validate_config(&config)?;
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        //    Windows too short to progress through the context
+        config.doc_stride = 96;
+        let report = QuestionAnsweringModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("sliding windows"));
+
+        config.doc_stride = 32;
+        config.answer_overlap_threshold = Some(1.5);
+        let report = QuestionAnsweringModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("answer overlap threshold"));
+
+        config.answer_overlap_threshold = None;
+        config.batch_size = 0;
+        let report = QuestionAnsweringModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("batch size"));
+        Ok(())
+    }
 }
@@ -0,0 +1,241 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Structured record inputs for the classification pipelines
+//! Records made of several text fields (e.g. a title, a body and tags) are converted to a single
+//! text before classification. Each field is truncated to its own token budget before the fields
+//! are concatenated in the order of the `FieldConfig`, so that a long body cannot push a short
+//! title out of the model input. Budgets are counted with the tokenizer of the pipeline.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::records::{FieldConfig, Record};
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let model = SequenceClassificationModel::new(Default::default())?;
+//! let field_config = FieldConfig::new(&[("title", 16), ("body", 96), ("tags", 8)], " | ");
+//! let records = [Record::new()
+//!     .with_field("title", "Great phone, terrible battery")
+//!     .with_field("body", "I have been using this phone for a month...")
+//!     .with_field("tags", "electronics mobile")];
+//! let output = model.predict_records(&records, &field_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::TokenizerOption;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+/// # Input record made of named text fields
+pub struct Record {
+    /// Text of the record fields, indexed by field name
+    pub fields: HashMap<String, String>,
+}
+
+impl Record {
+    /// Creates an empty record
+    pub fn new() -> Record {
+        Record {
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Sets the text of a field, replacing any previous value
+    pub fn with_field(mut self, name: &str, value: &str) -> Record {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+/// # Token budget of a record field
+pub struct FieldBudget {
+    /// Name of the field in the records
+    pub name: String,
+    /// Maximum number of tokens kept for the field, excluding separators and special tokens
+    pub max_tokens: usize,
+}
+
+#[derive(Debug, Clone)]
+/// # Conversion of records to classification inputs
+/// Fields are concatenated in the order of `fields`, joined by `separator`. Fields of a record
+/// missing from the configuration are ignored, as well as configured fields missing from (or
+/// empty in) a record.
+pub struct FieldConfig {
+    /// Budgets of the fields, in concatenation order
+    pub fields: Vec<FieldBudget>,
+    /// Separator inserted between consecutive fields
+    pub separator: String,
+}
+
+impl FieldConfig {
+    /// Creates a new `FieldConfig`
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - `&[(&str, usize)]` field names and token budgets, in concatenation order
+    /// * `separator` - `&str` separator inserted between consecutive fields
+    pub fn new(fields: &[(&str, usize)], separator: &str) -> FieldConfig {
+        FieldConfig {
+            fields: fields
+                .iter()
+                .map(|&(name, max_tokens)| FieldBudget {
+                    name: name.to_string(),
+                    max_tokens,
+                })
+                .collect(),
+            separator: separator.to_string(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), RustBertError> {
+        if self.fields.is_empty() {
+            return Err(RustBertError::ValueError(
+                "The field configuration must contain at least one field".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for field in &self.fields {
+            if !names.insert(field.name.as_str()) {
+                return Err(RustBertError::ValueError(format!(
+                    "Field `{}` is configured more than once",
+                    field.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Truncates a text to its first `max_tokens` tokens, cutting the original text at the end of
+/// the last token kept.
+pub(crate) fn truncate_to_tokens(
+    tokenizer: &TokenizerOption,
+    text: &str,
+    max_tokens: usize,
+) -> String {
+    let tokens = tokenizer.tokenize_with_offsets(text);
+    if tokens.tokens.len() <= max_tokens {
+        return text.to_string();
+    }
+    let end = tokens.offsets[..max_tokens]
+        .iter()
+        .rev()
+        .find_map(|offset| offset.as_ref().map(|offset| offset.end as usize))
+        .unwrap_or(0);
+    text.chars().take(end).collect()
+}
+
+/// Converts records to texts, truncating each field to its budget before concatenation.
+pub(crate) fn build_record_texts(
+    tokenizer: &TokenizerOption,
+    records: &[Record],
+    field_config: &FieldConfig,
+) -> Result<Vec<String>, RustBertError> {
+    field_config.validate()?;
+    records
+        .iter()
+        .enumerate()
+        .map(|(record_index, record)| {
+            let fields = field_config
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    record
+                        .fields
+                        .get(&field.name)
+                        .map(|text| truncate_to_tokens(tokenizer, text.trim(), field.max_tokens))
+                })
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<String>>();
+            if fields.is_empty() {
+                Err(RustBertError::ValueError(format!(
+                    "Record {} has no non-empty configured field",
+                    record_index
+                )))
+            } else {
+                Ok(fields.join(&field_config.separator))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pipelines::common::ModelType;
+
+    fn test_tokenizer(directory: &tempfile::TempDir) -> anyhow::Result<TokenizerOption> {
+        let vocab_path = directory.path().join("vocab.txt");
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "great", "phone", "terrible", "battery",
+            "the", "screen", "is", "bright", "and", "sharp", "but", "dies", "fast", "mobile", "|",
+            ",", ".", "##s",
+        ];
+        std::fs::write(&vocab_path, vocab.join("\n"))?;
+        Ok(TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?)
+    }
+
+    #[test]
+    fn test_field_budgets() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let tokenizer = test_tokenizer(&directory)?;
+        let field_config = FieldConfig::new(&[("title", 3), ("body", 5), ("tags", 1)], " | ");
+        let body = "The screen is bright and sharp, but the battery dies fast. ".repeat(20);
+        let records = [
+            Record::new()
+                .with_field("body", &body)
+                .with_field("title", "Great phone, terrible battery")
+                .with_field("tags", "mobile phones"),
+            Record::new()
+                .with_field("title", "Great phone")
+                .with_field("tags", "")
+                .with_field("rating", "5"),
+        ];
+
+        let texts = build_record_texts(&tokenizer, &records, &field_config)?;
+        //    Over-budget fields are truncated to their budget, the title coming first regardless of
+        //    the length of the body
+        assert_eq!(texts[0], "Great phone, | The screen is bright and | mobile");
+        let field_lengths = texts[0]
+            .split(" | ")
+            .map(|field| tokenizer.tokenize(field).len())
+            .collect::<Vec<usize>>();
+        assert_eq!(field_lengths, vec![3, 5, 1]);
+        //    Fields within budget are kept as is, missing, empty and unknown fields are skipped
+        assert_eq!(texts[1], "Great phone");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_field_config() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let tokenizer = test_tokenizer(&directory)?;
+        let records = [Record::new().with_field("title", "Great phone")];
+
+        let empty_config = FieldConfig::new(&[], " ");
+        assert!(build_record_texts(&tokenizer, &records, &empty_config).is_err());
+        let duplicate_config = FieldConfig::new(&[("title", 3), ("title", 5)], " ");
+        assert!(build_record_texts(&tokenizer, &records, &duplicate_config).is_err());
+        let missing_fields_config = FieldConfig::new(&[("body", 3)], " ");
+        assert!(build_record_texts(&tokenizer, &records, &missing_fields_config).is_err());
+        Ok(())
+    }
+}
@@ -26,7 +26,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_classifier.predict(&input);
+//! let output = sentiment_classifier.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -58,15 +58,19 @@ use crate::common::error::RustBertError;
 use crate::pipelines::sequence_classification::{
     SequenceClassificationConfig, SequenceClassificationModel,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Enum with the possible sentiment polarities. Note that the pre-trained SST2 model does not include neutral sentiment.
 pub enum SentimentPolarity {
     Positive,
     Negative,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Sentiment returned by the model.
 pub struct Sentiment {
     /// Polarity of the sentiment
@@ -114,6 +118,7 @@ impl SentimentModel {
     ///
     /// # Returns
     /// * `Vec<Sentiment>` Sentiments extracted from texts.
+    /// * `RustBertError` if no input is provided or if the tokenizer does not define a padding token
     ///
     /// # Example
     ///
@@ -129,15 +134,15 @@ impl SentimentModel {
     ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
     /// ];
     ///
-    /// let output = sentiment_classifier.predict(&input);
+    /// let output = sentiment_classifier.predict(&input)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Sentiment>
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Sentiment>, RustBertError>
     where
         S: AsRef<[&'a str]>,
     {
-        let labels = self.sequence_classification_model.predict(input);
+        let labels = self.sequence_classification_model.predict(input)?;
         let mut sentiments = Vec::with_capacity(labels.len());
         for label in labels {
             let polarity = if label.id == 1 {
@@ -150,7 +155,7 @@ impl SentimentModel {
                 score: label.score,
             })
         }
-        sentiments
+        Ok(sentiments)
     }
 }
 #[cfg(test)]
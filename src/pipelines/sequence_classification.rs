@@ -40,7 +40,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sequence_classification_model.predict(&input);
+//! let output = sequence_classification_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -57,30 +57,62 @@
 //! ]
 //! # ;
 //! ```
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForSequenceClassification;
+#[cfg(feature = "bart")]
 use crate::bart::BartForSequenceClassification;
+#[cfg(feature = "bert")]
 use crate::bert::BertForSequenceClassification;
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::resources::{RemoteResource, Resource};
+use crate::common::ProblemType;
+#[cfg(feature = "deberta")]
+use crate::deberta::DebertaForSequenceClassification;
 use crate::distilbert::{
     DistilBertConfigResources, DistilBertModelClassifier, DistilBertModelResources,
     DistilBertVocabResources,
 };
+#[cfg(feature = "fnet")]
+use crate::fnet::FNetForSequenceClassification;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForSequenceClassification;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForSequenceClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::chunked_inference::{pin_for_device, run_chunked, ChunkScheduling};
+use crate::pipelines::common::{
+    expect_config, get_extra_padding_mask, get_padded_length, resolve_label_mapping,
+    single_special_tokens, ConfigOption, ModelType, TokenizerOption,
+};
+use crate::pipelines::metrics::{
+    multilabel_report, single_label_report, ClassificationMetrics, EvalReport, MetricsAccumulator,
+    MultilabelEvalReport,
+};
+use crate::pipelines::records::{build_record_texts, FieldConfig, Record};
+use crate::pipelines::state::{read_state, write_state, PipelineSources, RestoredState};
+use crate::pipelines::validation::{validate_resources, PipelineResources, ValidationReport};
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerForSequenceClassification;
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForSequenceClassification;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForSequenceClassification;
 use rust_tokenizers::tokenizer::TruncationStrategy;
-use rust_tokenizers::TokenizedInput;
+use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::cmp::min;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Kind, Tensor};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Name of the pipeline recorded in saved states
+const SEQUENCE_CLASSIFICATION_STATE: &str = "sequence_classification";
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Label generated by a `SequenceClassificationModel`
 pub struct Label {
     /// Label String representation
@@ -90,10 +122,45 @@ pub struct Label {
     /// Label ID
     pub id: i64,
     /// Sentence index
-    #[serde(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
     pub sentence: usize,
 }
 
+#[derive(Debug, Clone, Copy)]
+/// # Decision thresholds of the multi-label classification
+/// Created from a `f64` for a global threshold or from a `&HashMap<String, f64>` of per-label
+/// thresholds, so that either can be passed to `predict_multilabel`.
+pub enum MultilabelThreshold<'a> {
+    /// Threshold shared by all labels
+    Global(f64),
+    /// Threshold of each label name. Labels without an entry use a threshold of 0.5
+    PerLabel(&'a HashMap<String, f64>),
+}
+
+impl MultilabelThreshold<'_> {
+    /// Threshold applied to the label with the given name
+    pub fn for_label(&self, label: &str) -> f64 {
+        match self {
+            MultilabelThreshold::Global(threshold) => *threshold,
+            MultilabelThreshold::PerLabel(thresholds) => {
+                thresholds.get(label).copied().unwrap_or(0.5)
+            }
+        }
+    }
+}
+
+impl From<f64> for MultilabelThreshold<'_> {
+    fn from(threshold: f64) -> Self {
+        MultilabelThreshold::Global(threshold)
+    }
+}
+
+impl<'a> From<&'a HashMap<String, f64>> for MultilabelThreshold<'a> {
+    fn from(thresholds: &'a HashMap<String, f64>) -> Self {
+        MultilabelThreshold::PerLabel(thresholds)
+    }
+}
+
 /// # Configuration for SequenceClassificationModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct SequenceClassificationConfig {
@@ -115,6 +182,29 @@ pub struct SequenceClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Pad the input sequences to a multiple of this value to produce tensor shapes that are more
+    /// efficient on GPU (e.g. 8 for fp16 tensor cores). Padded positions are masked (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of inputs processed in a single forward pass. Each batch is padded to its own
+    /// longest input (default: 64)
+    pub batch_size: usize,
+    /// Label names of the classifier outputs, taking precedence over the id2label mapping of the
+    /// configuration file (e.g. to replace generic `LABEL_0` names). Must cover every class index
+    /// of the classifier head (default: None)
+    pub label_mapping: Option<HashMap<i64, String>>,
+    /// Training objective of the classification head, taking precedence over the `problem_type` of
+    /// the configuration file. It selects the activation of the classifier outputs: softmax for
+    /// single-label classification, sigmoid for multi-label classification and none for regression.
+    /// Without a problem type, the single-label methods use a softmax and `predict_multilabel` a
+    /// sigmoid (default: None)
+    pub problem_type: Option<ProblemType>,
+    /// Maximum number of tokens of an input, including the special tokens (default: 128)
+    pub max_length: usize,
+    /// Processing of the inputs longer than `max_length` (default: `LongInputStrategy::Truncate`)
+    pub long_input_strategy: LongInputStrategy,
+    /// Calibration of the logits applied before the activation, reflected in the scores of the
+    /// returned labels. `predict_logits` returns uncalibrated logits (default: None)
+    pub calibration: Option<Calibration>,
 }
 
 impl SequenceClassificationConfig {
@@ -148,6 +238,13 @@ impl SequenceClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            batch_size: 64,
+            label_mapping: None,
+            problem_type: None,
+            max_length: 128,
+            long_input_strategy: LongInputStrategy::Truncate,
+            calibration: None,
         }
     }
 }
@@ -171,32 +268,116 @@ impl Default for SequenceClassificationConfig {
             strip_accents: None,
             add_prefix_space: None,
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            batch_size: 64,
+            label_mapping: None,
+            problem_type: None,
+            max_length: 128,
+            long_input_strategy: LongInputStrategy::Truncate,
+            calibration: None,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # Processing of the inputs longer than the maximum length of a `SequenceClassificationModel`
+pub enum LongInputStrategy {
+    /// The inputs are truncated to `max_length` tokens
+    Truncate,
+    /// The inputs are split into windows of `max_length` tokens, consecutive windows overlapping by
+    /// `stride` tokens. Each window is classified and the logits of the windows of an input are
+    /// aggregated, returning a single prediction per input.
+    SlidingWindow {
+        stride: usize,
+        aggregation: WindowAggregation,
+    },
+}
+
+impl Default for LongInputStrategy {
+    fn default() -> Self {
+        LongInputStrategy::Truncate
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// # Calibration of the class scores of a `SequenceClassificationModel`
+/// Applied to the logits before the activation of the problem type (not applied to regression models)
+pub enum Calibration {
+    /// Temperature scaling: the logits are divided by the (strictly positive) temperature before
+    /// the activation. Temperatures above 1 soften the scores of an over-confident model.
+    Temperature(f64),
+    /// Platt scaling of each class: the logit `x` of the class `i` becomes `a * x + b`, with
+    /// `(a, b)` the `i`-th pair of parameters, before the activation (typically the sigmoid of
+    /// the multi-label classification). One pair is required for each class of the model.
+    PlattPerClass(Vec<(f64, f64)>),
+}
+
+impl Calibration {
+    /// Returns the calibrated logits of shape (*batch size*, *num_labels*)
+    pub(crate) fn apply(&self, logits: &Tensor) -> Tensor {
+        match self {
+            Calibration::Temperature(temperature) => logits / *temperature,
+            Calibration::PlattPerClass(parameters) => {
+                let (scales, biases): (Vec<f64>, Vec<f64>) = parameters.iter().copied().unzip();
+                let as_logits = |values: &[f64]| {
+                    Tensor::of_slice(values)
+                        .to_kind(logits.kind())
+                        .to_device(logits.device())
+                };
+                logits * as_logits(&scales) + as_logits(&biases)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # Aggregation of the logits of the windows of an input
+pub enum WindowAggregation {
+    /// Average of the logits of all windows
+    Mean,
+    /// Maximum logit of each class over the windows
+    Max,
+    /// Logits of the first window only
+    First,
+}
+
 /// # Abstraction that holds one particular sequence classification model, for any of the supported models
 pub enum SequenceClassificationOption {
     /// Bert for Sequence Classification
+    #[cfg(feature = "bert")]
     Bert(BertForSequenceClassification),
     /// DistilBert for Sequence Classification
     DistilBert(DistilBertModelClassifier),
     /// MobileBert for Sequence Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForSequenceClassification),
     /// Roberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForSequenceClassification),
     /// XLMRoberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForSequenceClassification),
     /// Albert for Sequence Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForSequenceClassification),
     /// XLNet for Sequence Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForSequenceClassification),
     /// Bart for Sequence Classification
+    #[cfg(feature = "bart")]
     Bart(BartForSequenceClassification),
     /// Reformer for Sequence Classification
+    #[cfg(feature = "reformer")]
     Reformer(ReformerForSequenceClassification),
     /// Longformer for Sequence Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForSequenceClassification),
+    /// DeBERTa for Sequence Classification
+    #[cfg(feature = "deberta")]
+    Deberta(DebertaForSequenceClassification),
+    /// FNet for Sequence Classification
+    #[cfg(feature = "fnet")]
+    FNet(FNetForSequenceClassification),
 }
 
 impl SequenceClassificationOption {
@@ -216,116 +397,150 @@ impl SequenceClassificationOption {
     where
         P: Borrow<nn::Path<'p>>,
     {
+        model_type.ensure_enabled()?;
         match model_type {
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(SequenceClassificationOption::Bert(
-                        BertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Bert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bert,
+                    ConfigOption::Bert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Bert(
+                    BertForSequenceClassification::new(p, config),
+                ))
             }
             ModelType::DistilBert => {
-                if let ConfigOption::DistilBert(config) = config {
-                    Ok(SequenceClassificationOption::DistilBert(
-                        DistilBertModelClassifier::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a DistilBertConfig for DistilBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::DistilBert,
+                    ConfigOption::DistilBert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::DistilBert(
+                    DistilBertModelClassifier::new(p, config),
+                ))
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
-                if let ConfigOption::MobileBert(config) = config {
-                    Ok(SequenceClassificationOption::MobileBert(
-                        MobileBertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a MobileBertConfig for MobileBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::MobileBert,
+                    ConfigOption::MobileBert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::MobileBert(
+                    MobileBertForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(SequenceClassificationOption::Roberta(
-                        RobertaForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Roberta,
+                    ConfigOption::Bert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Roberta(
+                    RobertaForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(SequenceClassificationOption::XLMRoberta(
-                        RobertaForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLMRoberta,
+                    ConfigOption::Bert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::XLMRoberta(
+                    RobertaForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
-                if let ConfigOption::Albert(config) = config {
-                    Ok(SequenceClassificationOption::Albert(
-                        AlbertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Albert,
+                    ConfigOption::Albert,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Albert(
+                    AlbertForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
-                if let ConfigOption::XLNet(config) = config {
-                    Ok(SequenceClassificationOption::XLNet(
-                        XLNetForSequenceClassification::new(p, config).unwrap(),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an XLNetConfig for XLNet!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLNet,
+                    ConfigOption::XLNet,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::XLNet(
+                    XLNetForSequenceClassification::new(p, config).unwrap(),
+                ))
             }
+            #[cfg(feature = "bart")]
             ModelType::Bart => {
-                if let ConfigOption::Bart(config) = config {
-                    Ok(SequenceClassificationOption::Bart(
-                        BartForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Bert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bart,
+                    ConfigOption::Bart,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Bart(
+                    BartForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => {
-                if let ConfigOption::Reformer(config) = config {
-                    Ok(SequenceClassificationOption::Reformer(
-                        ReformerForSequenceClassification::new(p, config)?,
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a ReformerConfig for Reformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Reformer,
+                    ConfigOption::Reformer,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Reformer(
+                    ReformerForSequenceClassification::new(p, config)?,
+                ))
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
-                if let ConfigOption::Longformer(config) = config {
-                    Ok(SequenceClassificationOption::Longformer(
-                        LongformerForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a LongformerConfig for Longformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Longformer,
+                    ConfigOption::Longformer,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Longformer(
+                    LongformerForSequenceClassification::new(p, config),
+                ))
+            }
+            #[cfg(feature = "deberta")]
+            ModelType::Deberta => {
+                let config = expect_config!(
+                    config,
+                    ModelType::Deberta,
+                    ConfigOption::Deberta,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::Deberta(
+                    DebertaForSequenceClassification::new(p, config),
+                ))
+            }
+            #[cfg(feature = "fnet")]
+            ModelType::FNet => {
+                let config = expect_config!(
+                    config,
+                    ModelType::FNet,
+                    ConfigOption::FNet,
+                    "Sequence classification"
+                )?;
+                Ok(SequenceClassificationOption::FNet(
+                    FNetForSequenceClassification::new(p, config),
+                ))
             }
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Sequence Classification not implemented for {:?}!",
@@ -334,19 +549,65 @@ impl SequenceClassificationOption {
         }
     }
 
+    /// Checks that the model type is implemented by the pipeline, without building the model
+    pub(crate) fn ensure_supported(model_type: ModelType) -> Result<(), RustBertError> {
+        match model_type {
+            #[cfg(feature = "bert")]
+            ModelType::Bert => Ok(()),
+            ModelType::DistilBert => Ok(()),
+            #[cfg(feature = "mobilebert")]
+            ModelType::MobileBert => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::Roberta => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::XLMRoberta => Ok(()),
+            #[cfg(feature = "albert")]
+            ModelType::Albert => Ok(()),
+            #[cfg(feature = "xlnet")]
+            ModelType::XLNet => Ok(()),
+            #[cfg(feature = "bart")]
+            ModelType::Bart => Ok(()),
+            #[cfg(feature = "reformer")]
+            ModelType::Reformer => Ok(()),
+            #[cfg(feature = "longformer")]
+            ModelType::Longformer => Ok(()),
+            #[cfg(feature = "deberta")]
+            ModelType::Deberta => Ok(()),
+            #[cfg(feature = "fnet")]
+            ModelType::FNet => Ok(()),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Sequence Classification not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::Roberta,
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "bart")]
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
+            #[cfg(feature = "deberta")]
+            Self::Deberta(_) => ModelType::Deberta,
+            #[cfg(feature = "fnet")]
+            Self::FNet(_) => ModelType::FNet,
         }
     }
 
@@ -361,6 +622,7 @@ impl SequenceClassificationOption {
         train: bool,
     ) -> Tensor {
         match *self {
+            #[cfg(feature = "bart")]
             Self::Bart(ref model) => {
                 model
                     .forward_t(
@@ -373,6 +635,7 @@ impl SequenceClassificationOption {
                     )
                     .decoder_output
             }
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -391,6 +654,7 @@ impl SequenceClassificationOption {
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(
@@ -404,6 +668,7 @@ impl SequenceClassificationOption {
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -416,6 +681,7 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -428,6 +694,7 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -442,12 +709,14 @@ impl SequenceClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => {
                 model
                     .forward_t(input_ids.as_ref(), None, None, mask.as_ref(), None, train)
                     .expect("Error in Reformer forward pass.")
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
@@ -462,8 +731,281 @@ impl SequenceClassificationOption {
                     .expect("Error in Longformer forward pass.")
                     .logits
             }
+            #[cfg(feature = "deberta")]
+            Self::Deberta(ref model) => {
+                model
+                    .forward_t(
+                        input_ids.as_ref(),
+                        mask.as_ref(),
+                        token_type_ids.as_ref(),
+                        position_ids.as_ref(),
+                        input_embeds.as_ref(),
+                        train,
+                    )
+                    .expect("Error in DeBERTa forward pass.")
+                    .logits
+            }
+            #[cfg(feature = "fnet")]
+            Self::FNet(ref model) => {
+                model
+                    .forward_t(
+                        input_ids.as_ref(),
+                        token_type_ids.as_ref(),
+                        position_ids.as_ref(),
+                        input_embeds.as_ref(),
+                        train,
+                    )
+                    .expect("Error in FNet forward pass.")
+                    .logits
+            }
+        }
+    }
+
+    /// Interface method to the forward pass over packed sequences of the models supporting it
+    /// (see `BertForSequenceClassification::forward_packed_t`). Returns logits of shape
+    /// (*batch size*, *max sequences per row*, *num_labels*).
+    pub fn forward_packed_t(
+        &self,
+        input_ids: &Tensor,
+        mask: &Tensor,
+        position_ids: Option<Tensor>,
+        sequence_starts: &Tensor,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        match *self {
+            #[cfg(feature = "bert")]
+            Self::Bert(ref model) => {
+                Ok(model.forward_packed_t(input_ids, mask, position_ids, sequence_starts, train))
+            }
+            _ => Err(RustBertError::UnsupportedModelType(format!(
+                "Packed inputs are not supported for {:?} models",
+                self.model_type()
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// # Attention between the inputs packed in a row by `SequenceClassificationModel::predict_packed`
+pub enum PackingMode {
+    /// The inputs packed in a row attend to each other. The predictions differ slightly from the
+    /// predictions of inputs encoded separately, as each input is contextualized by its neighbours.
+    Shared,
+    /// Each input only attends to its own tokens (block-diagonal attention mask): the predictions
+    /// are identical to the predictions of inputs encoded separately, at the cost of a
+    /// (*sequence_length*, *sequence_length*) attention mask per row.
+    BlockDiagonal,
+}
+
+/// Packs sequences into rows of at most `max_length` tokens, in the input order, returning the
+/// indices of the sequences of each row. A row is started when the next sequence does not fit in
+/// the current row.
+fn pack_sequences(sequence_lengths: &[usize], max_length: usize) -> Vec<Vec<usize>> {
+    let mut rows: Vec<Vec<usize>> = vec![];
+    let mut row_length = 0;
+    for (index, &length) in sequence_lengths.iter().enumerate() {
+        match rows.last_mut() {
+            Some(row) if row_length + length <= max_length => {
+                row.push(index);
+                row_length += length;
+            }
+            _ => {
+                rows.push(vec![index]);
+                row_length = length;
+            }
+        }
+    }
+    rows
+}
+
+/// Validates a single input text before it is added to a batch
+pub(crate) fn validate_input(input: &str) -> Result<(), RustBertError> {
+    if input.trim().is_empty() {
+        Err(RustBertError::ValueError(
+            "Input text is empty or only contains whitespace".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates the inputs of a batch individually, returning the valid inputs and the validation
+/// result for each position of the original batch
+pub(crate) fn split_valid_inputs<'a>(
+    inputs: &[&'a str],
+) -> (Vec<&'a str>, Vec<Result<(), RustBertError>>) {
+    let validation = inputs
+        .iter()
+        .map(|input| validate_input(input))
+        .collect::<Vec<Result<(), RustBertError>>>();
+    let valid_inputs = inputs
+        .iter()
+        .zip(validation.iter())
+        .filter(|(_, result)| result.is_ok())
+        .map(|(input, _)| *input)
+        .collect::<Vec<&str>>();
+    (valid_inputs, validation)
+}
+
+/// Re-aligns the outputs computed for the valid inputs with the positions of the original batch
+pub(crate) fn align_outputs<T>(
+    validation: Vec<Result<(), RustBertError>>,
+    outputs: Vec<T>,
+) -> Vec<Result<T, RustBertError>> {
+    let mut outputs = outputs.into_iter();
+    validation
+        .into_iter()
+        .map(|result| {
+            result.map(|_| {
+                outputs
+                    .next()
+                    .expect("Missing output for a valid input of the batch")
+            })
+        })
+        .collect()
+}
+
+/// Tokenizes and pads a batch of texts, creating the input tensors on the given device. Only
+/// requires the tokenizer, allowing batches to be prepared on a different thread than the model.
+fn prepare_batch(
+    tokenizer: &TokenizerOption,
+    pad_to_multiple_of: Option<usize>,
+    max_length: usize,
+    input: &[&str],
+    device: Device,
+) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+    let tokenized_input: Vec<TokenizedInput> =
+        tokenizer.encode_list(input, max_length, &TruncationStrategy::LongestFirst, 0);
+    pad_token_ids(
+        tokenizer,
+        pad_to_multiple_of,
+        tokenized_input
+            .into_iter()
+            .map(|input| input.token_ids)
+            .collect(),
+        device,
+    )
+}
+
+/// Pads encoded sequences to the length of the longest one, creating the input tensors on the
+/// given device
+fn pad_token_ids(
+    tokenizer: &TokenizerOption,
+    pad_to_multiple_of: Option<usize>,
+    token_ids: Vec<Vec<i64>>,
+    device: Device,
+) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+    let pad_id = tokenizer
+        .get_pad_id()
+        .ok_or(RustBertError::MissingPadTokenError)?;
+    let max_len = token_ids.iter().map(|input| input.len()).max().unwrap();
+    let padded_len = get_padded_length(max_len, pad_to_multiple_of);
+    let num_inputs = token_ids.len();
+    let tokenized_input_tensors: Vec<tch::Tensor> = token_ids
+        .into_iter()
+        .map(|mut input| {
+            input.extend(vec![pad_id; padded_len - input.len()]);
+            input
+        })
+        .map(|input| Tensor::of_slice(&(input)))
+        .collect::<Vec<_>>();
+    let attention_mask = get_extra_padding_mask(num_inputs, max_len, padded_len, device);
+    Ok((
+        Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(device),
+        attention_mask,
+    ))
+}
+
+/// Pipeline options persisted in saved states
+#[derive(Serialize, Deserialize)]
+struct SequenceClassificationStateOptions {
+    pad_to_multiple_of: Option<usize>,
+    batch_size: usize,
+    label_mapping: Option<HashMap<i64, String>>,
+    problem_type: Option<ProblemType>,
+    max_length: usize,
+    long_input_strategy: LongInputStrategy,
+    calibration: Option<Calibration>,
+}
+
+impl SequenceClassificationStateOptions {
+    fn from_config(
+        config: &SequenceClassificationConfig,
+        model_config: &ConfigOption,
+    ) -> SequenceClassificationStateOptions {
+        SequenceClassificationStateOptions {
+            pad_to_multiple_of: model_config.resolve_pad_to_multiple_of(config.pad_to_multiple_of),
+            batch_size: config.batch_size,
+            label_mapping: config.label_mapping.clone(),
+            problem_type: config
+                .problem_type
+                .or_else(|| model_config.get_problem_type()),
+            max_length: config.max_length,
+            long_input_strategy: config.long_input_strategy,
+            calibration: config.calibration.clone(),
+        }
+    }
+}
+
+/// Checks the pipeline options that do not depend on the model resources
+fn validate_options(config: &SequenceClassificationConfig) -> Result<(), RustBertError> {
+    if config.batch_size == 0 {
+        return Err(RustBertError::InvalidConfigurationError(
+            "The batch size must be strictly positive".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the calibration parameters are valid for a model with `num_labels` classes
+pub(crate) fn check_calibration(
+    calibration: Option<&Calibration>,
+    num_labels: usize,
+) -> Result<(), RustBertError> {
+    match calibration {
+        Some(Calibration::Temperature(temperature))
+            if !(temperature.is_finite() && *temperature > 0.0) =>
+        {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "The calibration temperature must be strictly positive, got {}",
+                temperature
+            )))
+        }
+        Some(Calibration::PlattPerClass(parameters)) if parameters.len() != num_labels => {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "Platt calibration requires one pair of parameters per class: got {} pairs for {} classes",
+                parameters.len(),
+                num_labels
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the maximum length leaves room for the input tokens, and that the sliding windows
+/// progress through the inputs
+fn check_long_input_strategy(
+    tokenizer: &TokenizerOption,
+    max_length: usize,
+    long_input_strategy: LongInputStrategy,
+) -> Result<(), RustBertError> {
+    let num_special_tokens = single_special_tokens(tokenizer);
+    if max_length <= num_special_tokens {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The maximum length ({}) must exceed the number of special tokens ({})",
+            max_length, num_special_tokens
+        )));
+    }
+    if let LongInputStrategy::SlidingWindow { stride, .. } = long_input_strategy {
+        let window_length = max_length - num_special_tokens;
+        if stride >= window_length {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The sliding window stride ({}) must be lower than the number of input tokens of a window ({})",
+                stride, window_length
+            )));
         }
     }
+    Ok(())
 }
 
 /// # SequenceClassificationModel for Classification (e.g. Sentiment Analysis)
@@ -471,7 +1013,16 @@ pub struct SequenceClassificationModel {
     tokenizer: TokenizerOption,
     sequence_classifier: SequenceClassificationOption,
     label_mapping: HashMap<i64, String>,
+    problem_type: Option<ProblemType>,
+    max_length: usize,
+    long_input_strategy: LongInputStrategy,
+    calibration: Option<Calibration>,
+    pad_to_multiple_of: Option<usize>,
+    batch_size: usize,
     var_store: VarStore,
+    load_report: LoadReport,
+    tie_word_embeddings: bool,
+    sources: Option<PipelineSources>,
 }
 
 impl SequenceClassificationModel {
@@ -494,6 +1045,8 @@ impl SequenceClassificationModel {
     pub fn new(
         config: SequenceClassificationConfig,
     ) -> Result<SequenceClassificationModel, RustBertError> {
+        ensure_initialized();
+        validate_options(&config)?;
         let config_path = config.config_resource.get_local_path()?;
         let vocab_path = config.vocab_resource.get_local_path()?;
         let weights_path = config.model_resource.get_local_path()?;
@@ -512,201 +1065,1950 @@ impl SequenceClassificationModel {
             config.strip_accents,
             config.add_prefix_space,
         )?;
-        let mut var_store = VarStore::new(device);
-        let model_config = ConfigOption::from_file(config.model_type, config_path);
-        let sequence_classifier =
-            SequenceClassificationOption::new(config.model_type, &var_store.root(), &model_config)?;
-        let label_mapping = model_config.get_label_mapping();
-        var_store.load(weights_path)?;
-        Ok(SequenceClassificationModel {
+        let model_config = ConfigOption::try_from_file(config.model_type, &config_path)?;
+        let options = SequenceClassificationStateOptions::from_config(&config, &model_config);
+        let sources = PipelineSources {
+            model_type: config.model_type,
+            config_path,
+            vocab_path,
+            merges_path,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+        };
+        SequenceClassificationModel::from_components(
+            config.model_type,
+            Some(sources),
             tokenizer,
-            sequence_classifier,
-            label_mapping,
-            var_store,
-        })
-    }
-
-    fn prepare_for_model<'a, S>(&self, input: S) -> Tensor
-    where
-        S: AsRef<[&'a str]>,
-    {
-        let tokenized_input: Vec<TokenizedInput> =
-            self.tokenizer
-                .encode_list(input.as_ref(), 128, &TruncationStrategy::LongestFirst, 0);
-        let max_len = tokenized_input
-            .iter()
-            .map(|input| input.token_ids.len())
-            .max()
-            .unwrap();
-        let tokenized_input_tensors: Vec<tch::Tensor> = tokenized_input
-            .iter()
-            .map(|input| input.token_ids.clone())
-            .map(|mut input| {
-                input.extend(vec![
-                    self.tokenizer.get_pad_id().expect(
-                        "The Tokenizer used for sequence classification should contain a PAD id"
-                    );
-                    max_len - input.len()
-                ]);
-                input
-            })
-            .map(|input| Tensor::of_slice(&(input)))
-            .collect::<Vec<_>>();
-        Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device())
+            &model_config,
+            options,
+            weights_path,
+            device,
+        )
     }
 
-    /// Classify texts
+    /// Build a new `SequenceClassificationModel` with a tokenizer built by the caller (e.g. from an
+    /// in-memory vocabulary or with additional special tokens). The vocabulary and merges resources
+    /// of the configuration are not used (nor downloaded), and the tokenizer must match the model
+    /// type of the configuration. Pipelines built with an injected tokenizer cannot be saved with
+    /// `save_state`.
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `config` - `SequenceClassificationConfig` object containing the model and configuration resources and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` used to encode the inputs
     ///
     /// # Returns
     ///
-    /// * `Vec<Label>` containing labels for input texts
+    /// * `InvalidConfigurationError` if the tokenizer cannot be used by `config.model_type`
     ///
     /// # Example
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
     ///
-    /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
-    /// let input = [
-    ///     "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
-    ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
-    ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
-    /// ];
-    /// let output = sequence_classification_model.predict(&input);
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::DistilBert,
+    ///     "path/to/vocab.txt",
+    ///     None,
+    ///     false,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let model =
+    ///     SequenceClassificationModel::new_with_tokenizer(Default::default(), tokenizer)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Label>
-    where
-        S: AsRef<[&'a str]>,
-    {
-        let input_tensor = self.prepare_for_model(input.as_ref());
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
-        });
-        let label_indices = output.as_ref().argmax(-1, true).squeeze1(1);
-        let scores = output
-            .gather(1, &label_indices.unsqueeze(-1), false)
-            .squeeze1(1);
-        let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
-        let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+    pub fn new_with_tokenizer(
+        config: SequenceClassificationConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        ensure_initialized();
+        validate_options(&config)?;
+        tokenizer.ensure_compatible(config.model_type)?;
+        let config_path = config.config_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let model_config = ConfigOption::try_from_file(config.model_type, &config_path)?;
+        let options = SequenceClassificationStateOptions::from_config(&config, &model_config);
+        SequenceClassificationModel::from_components(
+            config.model_type,
+            None,
+            tokenizer,
+            &model_config,
+            options,
+            weights_path,
+            config.device,
+        )
+    }
 
-        let mut labels: Vec<Label> = vec![];
-        for sentence_idx in 0..label_indices.len() {
-            let label_string = self
-                .label_mapping
-                .get(&label_indices[sentence_idx])
-                .unwrap()
-                .clone();
-            let label = Label {
-                text: label_string,
-                score: scores[sentence_idx],
-                id: label_indices[sentence_idx],
-                sentence: sentence_idx,
-            };
-            labels.push(label)
+    /// Validates a configuration without downloading or loading the model weights. The configuration
+    /// and vocabulary files are fetched and parsed, and the checks run when building the pipeline
+    /// (supported model type, loadable tokenizer, label mapping) are collected in the returned
+    /// `ValidationReport` instead of failing on the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `SequenceClassificationConfig` to validate
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let report = SequenceClassificationModel::validate_config(&Default::default())?;
+    /// if !report.is_valid() {
+    ///     println!("{:?}", report.errors);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_config(
+        config: &SequenceClassificationConfig,
+    ) -> Result<ValidationReport, RustBertError> {
+        let mut report = ValidationReport::new("sequence classification", config.model_type);
+        report.check(validate_options(config));
+        if config.model_type.is_enabled() {
+            report.check(SequenceClassificationOption::ensure_supported(
+                config.model_type,
+            ));
         }
-        labels
+        let (model_config, tokenizer) = validate_resources(
+            &mut report,
+            &PipelineResources {
+                model_type: config.model_type,
+                model_resource: &config.model_resource,
+                config_resource: &config.config_resource,
+                vocab_resource: &config.vocab_resource,
+                merges_resource: config.merges_resource.as_ref(),
+                lower_case: config.lower_case,
+                strip_accents: config.strip_accents,
+                add_prefix_space: config.add_prefix_space,
+            },
+        );
+        if let Some(model_config) = &model_config {
+            if let Some(label_mapping) = report.check(resolve_label_mapping(
+                model_config,
+                config.label_mapping.as_ref(),
+            )) {
+                report.check(check_calibration(
+                    config.calibration.as_ref(),
+                    label_mapping.len(),
+                ));
+            }
+        }
+        if let Some(tokenizer) = &tokenizer {
+            report.check(check_long_input_strategy(
+                tokenizer,
+                config.max_length,
+                config.long_input_strategy,
+            ));
+        }
+        Ok(report)
     }
 
-    /// Multi-label classification of texts
+    /// Build a new `SequenceClassificationModel` from a state saved with `save_state`, skipping the
+    /// parsing of the tokenizer and configuration files. The weights are loaded from `model_resource`.
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
-    /// * `threshold` - `f64` threshold above which a label will be considered true by the classifier
+    /// * `state_path` - path to the state written by `save_state`
+    /// * `model_resource` - `Resource` pointing to the model weights
+    /// * `device` - `Device` to place the model on
     ///
     /// # Returns
     ///
-    /// * `Vec<Vec<Label>>` containing a vector of true labels for each input text
+    /// * `InvalidStateError` if the state was written with another format version, by another pipeline, or if its source files were modified since
     ///
     /// # Example
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::distilbert::DistilBertModelResources;
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::resources::{RemoteResource, Resource};
+    /// use tch::Device;
     ///
-    /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
-    /// let input = [
-    ///     "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
-    ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
-    ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
-    /// ];
-    /// let output = sequence_classification_model.predict_multilabel(&input, 0.5);
+    /// let model = SequenceClassificationModel::load_from_state(
+    ///     "sentiment.state",
+    ///     Resource::Remote(RemoteResource::from_pretrained(
+    ///         DistilBertModelResources::DISTIL_BERT_SST2,
+    ///     )),
+    ///     Device::cuda_if_available(),
+    /// )?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict_multilabel(
-        &self,
-        input: &[&str],
-        threshold: f64,
-    ) -> Result<Vec<Vec<Label>>, RustBertError> {
-        let input_tensor = self.prepare_for_model(input.to_vec());
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.sigmoid().detach().to(Device::Cpu)
-        });
-        let label_indices = output.as_ref().ge(threshold).nonzero();
-
-        let mut labels: Vec<Vec<Label>> = vec![];
-        let mut sequence_labels: Vec<Label> = vec![];
-
-        for sentence_idx in 0..label_indices.size()[0] {
-            let label_index_tensor = label_indices.get(sentence_idx);
-            let sentence_label = label_index_tensor
-                .iter::<i64>()
-                .unwrap()
-                .collect::<Vec<i64>>();
-            let (sentence, id) = (sentence_label[0], sentence_label[1]);
-            if sentence as usize > labels.len() {
-                labels.push(sequence_labels);
-                sequence_labels = vec![];
-            }
-            let score = output.double_value(sentence_label.as_slice());
-            let label_string = self.label_mapping.get(&id).unwrap().to_owned();
-            let label = Label {
-                text: label_string,
-                score,
-                id,
-                sentence: sentence as usize,
-            };
-            sequence_labels.push(label);
-        }
-        if !sequence_labels.is_empty() {
-            labels.push(sequence_labels);
-        }
-        Ok(labels)
+    pub fn load_from_state<P: AsRef<Path>>(
+        state_path: P,
+        model_resource: Resource,
+        device: Device,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        ensure_initialized();
+        let weights_path = model_resource.get_local_path()?;
+        let state: RestoredState<SequenceClassificationStateOptions> =
+            read_state(state_path.as_ref(), SEQUENCE_CLASSIFICATION_STATE)?;
+        SequenceClassificationModel::from_components(
+            state.sources.model_type,
+            Some(state.sources),
+            state.tokenizer,
+            &state.model_config,
+            state.options,
+            weights_path,
+            device,
+        )
     }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
 
-    #[test]
+    fn from_components(
+        model_type: ModelType,
+        sources: Option<PipelineSources>,
+        tokenizer: TokenizerOption,
+        model_config: &ConfigOption,
+        options: SequenceClassificationStateOptions,
+        weights_path: PathBuf,
+        device: Device,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        let label_mapping = resolve_label_mapping(model_config, options.label_mapping.as_ref())?;
+        check_calibration(options.calibration.as_ref(), label_mapping.len())?;
+        check_long_input_strategy(&tokenizer, options.max_length, options.long_input_strategy)?;
+        let mut var_store = VarStore::new(device);
+        let sequence_classifier =
+            SequenceClassificationOption::new(model_type, &var_store.root(), model_config)?;
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
+        Ok(SequenceClassificationModel {
+            tokenizer,
+            sequence_classifier,
+            label_mapping,
+            problem_type: options.problem_type,
+            max_length: options.max_length,
+            long_input_strategy: options.long_input_strategy,
+            calibration: options.calibration,
+            pad_to_multiple_of: options.pad_to_multiple_of,
+            batch_size: options.batch_size,
+            var_store,
+            load_report,
+            tie_word_embeddings,
+            sources,
+        })
+    }
+
+    /// Saves the compiled tokenizer, the model configuration and the pipeline options to a single
+    /// file, restored by `load_from_state` without parsing the source files again. The weights are
+    /// not included. The tokenizer and configuration files the pipeline was built from must still
+    /// be available.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - destination of the state file
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidStateError` if the pipeline was built with an injected tokenizer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let model = SequenceClassificationModel::new(Default::default())?;
+    /// model.save_state("sentiment.state")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> Result<(), RustBertError> {
+        let sources = self.sources.as_ref().ok_or_else(|| {
+            RustBertError::InvalidStateError(
+                "Pipelines built with an injected tokenizer cannot be saved".to_string(),
+            )
+        })?;
+        write_state(
+            path.as_ref(),
+            SEQUENCE_CLASSIFICATION_STATE,
+            sources,
+            &self.tokenizer,
+            &SequenceClassificationStateOptions {
+                pad_to_multiple_of: self.pad_to_multiple_of,
+                batch_size: self.batch_size,
+                label_mapping: Some(self.label_mapping.clone()),
+                problem_type: self.problem_type,
+                max_length: self.max_length,
+                long_input_strategy: self.long_input_strategy,
+                calibration: self.calibration.clone(),
+            },
+        )
+    }
+
+    /// Returns the report of the weights loaded when building the model, listing the tying status
+    /// of the output projections, the repaired tensor shapes and the unused checkpoint tensors.
+    /// The report is updated when the weights are replaced with `swap_weights`.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Replaces the weights of the model with a checkpoint sharing its architecture (e.g. another
+    /// fine-tuning of the same base model), keeping the tokenizer and configuration. The checkpoint
+    /// is validated against the model variables before any weight is overwritten: if the swap
+    /// fails, the current weights remain in use. The load report is replaced by the report of the
+    /// new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `Resource` pointing to the new weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = SequenceClassificationModel::new(Default::default())?;
+    /// model.swap_weights(Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/retrained/rust_model.ot"),
+    /// }))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn swap_weights(&mut self, model_resource: Resource) -> Result<(), RustBertError> {
+        let weights_path = model_resource.get_local_path()?;
+        self.load_report =
+            load_with_report(&mut self.var_store, weights_path, self.tie_word_embeddings)?;
+        Ok(())
+    }
+
+    /// Returns the device the model is placed on
+    pub fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+
+    /// Moves the model to another device. The input tensors of the subsequent predictions are
+    /// created on the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - `Device` to move the model to
+    pub fn set_device(&mut self, device: Device) {
+        self.var_store.set_device(device);
+    }
+
+    fn prepare_single_for_model(
+        &self,
+        input: &str,
+    ) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .ok_or(RustBertError::MissingPadTokenError)?;
+        let mut token_ids = self
+            .tokenizer
+            .encode_pair(
+                input,
+                None,
+                self.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )
+            .token_ids;
+        let max_len = token_ids.len();
+        let padded_len = get_padded_length(max_len, self.pad_to_multiple_of);
+        if padded_len > max_len {
+            token_ids.extend(vec![pad_id; padded_len - max_len]);
+        }
+        let attention_mask =
+            get_extra_padding_mask(1, max_len, padded_len, self.var_store.device());
+        Ok((
+            Tensor::of_slice(&token_ids)
+                .unsqueeze(0)
+                .to(self.var_store.device()),
+            attention_mask,
+        ))
+    }
+
+    fn prepare_for_model<'a, S>(&self, input: S) -> Result<(Tensor, Option<Tensor>), RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        //    Single inputs (the most common case for online serving) skip the batching logic
+        if let [input] = input.as_ref() {
+            return self.prepare_single_for_model(input);
+        }
+        prepare_batch(
+            &self.tokenizer,
+            self.pad_to_multiple_of,
+            self.max_length,
+            input.as_ref(),
+            self.var_store.device(),
+        )
+    }
+
+    /// Classify texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input texts
+    /// * `RustBertError::EmptyInputError` if no input is provided, `RustBertError::MissingPadTokenError`
+    ///   if the tokenizer does not define a padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+    ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
+    /// ];
+    /// let output = sequence_classification_model.predict(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let output = self.batched_scores(input.as_ref(), false)?;
+        Ok(self.labels_from_scores(output, 0))
+    }
+
+    /// Classify texts, returning the `k` most likely labels of each input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `k` - `usize` number of labels returned for each input. All the labels are returned if `k`
+    /// exceeds the number of classes of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing the `k` most likely labels of each input text, sorted by
+    /// decreasing probability
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie.", "A boring film."];
+    /// let output = sequence_classification_model.predict_topk(&input, 3)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_topk(&self, input: &[&str], k: usize) -> Result<Vec<Vec<Label>>, RustBertError> {
+        let output = self.batched_scores(input, false)?;
+        let k = min(k as i64, output.size()[1]);
+        let (scores, label_indices) = output.topk(k, -1, true, true);
+
+        Ok((0..input.len())
+            .map(|sentence_idx| {
+                let scores = scores.get(sentence_idx as i64);
+                let label_indices = label_indices.get(sentence_idx as i64);
+                scores
+                    .iter::<f64>()
+                    .unwrap()
+                    .zip(label_indices.iter::<i64>().unwrap())
+                    .map(|(score, id)| Label {
+                        text: self.label_mapping.get(&id).unwrap().clone(),
+                        score,
+                        id,
+                        sentence: sentence_idx,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Classify texts, returning the probability of every class for each input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing a label for every class of the model for each input text, in
+    /// the class id order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie.", "A boring film."];
+    /// let output = sequence_classification_model.predict_full(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_full(&self, input: &[&str]) -> Result<Vec<Vec<Label>>, RustBertError> {
+        let output = self.batched_scores(input, false)?;
+
+        Ok((0..input.len())
+            .map(|sentence_idx| {
+                output
+                    .get(sentence_idx as i64)
+                    .iter::<f64>()
+                    .unwrap()
+                    .enumerate()
+                    .map(|(id, score)| Label {
+                        text: self.label_mapping.get(&(id as i64)).unwrap().clone(),
+                        score,
+                        id: id as i64,
+                        sentence: sentence_idx,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Runs the tokenization and forward pass of the pipeline and returns the raw logits of the
+    /// classification head, before any calibration or activation (e.g. for ensembling or fitting a
+    /// temperature). The long input strategy of the pipeline is applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*number of inputs*, *num_labels*), moved to the CPU
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie.", "A boring film."];
+    /// let logits = sequence_classification_model.predict_logits(&input)?;
+    /// let calibrated_probabilities = (logits / 1.5).softmax(-1, tch::Kind::Float);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_logits(&self, input: &[&str]) -> Result<Tensor, RustBertError> {
+        self.batched_logits(input)
+    }
+
+    /// Classify texts in batches of `batch_size` texts. With a pipelined `ChunkScheduling`, the
+    /// tokenization of the next batches runs on a worker thread while the model processes the
+    /// current batch. Tensors prepared for a CUDA device are pinned to speed up their transfer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `batch_size` - maximum number of texts per forward pass
+    /// * `scheduling` - `ChunkScheduling` of the tokenization and forward passes
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input texts, identical to the output of `predict` for
+    ///   inputs of at most `max_length` tokens (longer inputs are truncated)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::chunked_inference::ChunkScheduling;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie.", "A boring film."];
+    /// let output = sequence_classification_model.predict_batched(
+    ///     &input,
+    ///     32,
+    ///     ChunkScheduling::Pipelined { prefetch: 2 },
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_batched<'a, S>(
+        &self,
+        input: S,
+        batch_size: usize,
+        scheduling: ChunkScheduling,
+    ) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let device = self.var_store.device();
+        let tokenizer = &self.tokenizer;
+        let pad_to_multiple_of = self.pad_to_multiple_of;
+        let max_length = self.max_length;
+        let mut sentence_offset = 0;
+        run_chunked(
+            input.as_ref(),
+            batch_size,
+            scheduling,
+            |batch: &[&str]| {
+                let (input_tensor, attention_mask) = prepare_batch(
+                    tokenizer,
+                    pad_to_multiple_of,
+                    max_length,
+                    batch,
+                    Device::Cpu,
+                )?;
+                Ok((
+                    pin_for_device(input_tensor, device),
+                    attention_mask.map(|mask| pin_for_device(mask, device)),
+                ))
+            },
+            |(input_tensor, attention_mask): (Tensor, Option<Tensor>)| {
+                let labels = self.classify(
+                    input_tensor.to(device),
+                    attention_mask.map(|mask| mask.to(device)),
+                    sentence_offset,
+                );
+                sentence_offset += labels.len();
+                Ok(labels)
+            },
+        )
+    }
+
+    /// Classify short texts, packing several texts in each row of the batch to reduce the padding.
+    ///
+    /// Each text is encoded with its special tokens and the encoded texts are concatenated in rows
+    /// of at most `max_length` tokens. The position ids restart at the beginning of each text, and
+    /// each text is classified from the hidden state of its first token. With `PackingMode::Shared`,
+    /// the texts of a row attend to each other and the predictions differ slightly from `predict`:
+    /// this trades some accuracy for throughput. `PackingMode::BlockDiagonal` restricts the attention
+    /// of each text to its own tokens and gives the same predictions as `predict`.
+    /// Only supported for BERT models.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `max_length` - `usize` Maximum number of tokens per row. Longer texts are truncated to this length.
+    /// * `mode` - `PackingMode` defining the attention between the texts of a row
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input texts, in the input order
+    /// * `RustBertError` if the model does not support packed inputs or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::sequence_classification::PackingMode;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Great movie.", "A boring film.", "Loved it!"];
+    /// let output =
+    ///     sequence_classification_model.predict_packed(&input, 128, PackingMode::BlockDiagonal)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_packed<'a, S>(
+        &self,
+        input: S,
+        max_length: usize,
+        mode: PackingMode,
+    ) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .ok_or(RustBertError::MissingPadTokenError)?;
+        let tokenized_input =
+            self.tokenizer
+                .encode_list(input, max_length, &TruncationStrategy::LongestFirst, 0);
+        let rows = pack_sequences(
+            &tokenized_input
+                .iter()
+                .map(|input| input.token_ids.len())
+                .collect::<Vec<usize>>(),
+            max_length,
+        );
+        let max_row_length = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&index| tokenized_input[index].token_ids.len())
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap();
+        let padded_len = get_padded_length(max_row_length, self.pad_to_multiple_of);
+        let max_sequences = rows.iter().map(|row| row.len()).max().unwrap();
+
+        let (mut input_ids, mut position_ids, mut sequence_starts, mut mask) =
+            (vec![], vec![], vec![], vec![]);
+        for row in rows.iter() {
+            //    Index of the sequence of each position of the row, padding positions having none
+            let mut sequence_ids: Vec<Option<usize>> = Vec::with_capacity(padded_len);
+            for (sequence_index, &index) in row.iter().enumerate() {
+                let token_ids = &tokenized_input[index].token_ids;
+                sequence_starts.push(sequence_ids.len() as i64);
+                input_ids.extend_from_slice(token_ids);
+                position_ids.extend(0..token_ids.len() as i64);
+                sequence_ids.extend(vec![Some(sequence_index); token_ids.len()]);
+            }
+            sequence_starts.extend(vec![0; max_sequences - row.len()]);
+            let num_padding = padded_len - sequence_ids.len();
+            input_ids.extend(vec![pad_id; num_padding]);
+            position_ids.extend(vec![0; num_padding]);
+            sequence_ids.extend(vec![None; num_padding]);
+            match mode {
+                PackingMode::Shared => mask.extend(
+                    sequence_ids
+                        .iter()
+                        .map(|sequence_id| sequence_id.is_some() as i64),
+                ),
+                PackingMode::BlockDiagonal => {
+                    for query in sequence_ids.iter() {
+                        mask.extend(
+                            sequence_ids
+                                .iter()
+                                .map(|key| (query.is_some() & (query == key)) as i64),
+                        );
+                    }
+                }
+            }
+        }
+
+        let device = self.var_store.device();
+        let num_rows = rows.len() as i64;
+        let mask_shape = match mode {
+            PackingMode::Shared => vec![num_rows, padded_len as i64],
+            PackingMode::BlockDiagonal => vec![num_rows, padded_len as i64, padded_len as i64],
+        };
+        let logits = no_grad(|| {
+            self.sequence_classifier.forward_packed_t(
+                &Tensor::of_slice(&input_ids).view((num_rows, -1)).to(device),
+                &Tensor::of_slice(&mask)
+                    .view(mask_shape.as_slice())
+                    .to(device),
+                Some(
+                    Tensor::of_slice(&position_ids)
+                        .view((num_rows, -1))
+                        .to(device),
+                ),
+                &Tensor::of_slice(&sequence_starts)
+                    .view((num_rows, -1))
+                    .to(device),
+                false,
+            )
+        })?;
+
+        //    Positions of the logits of each input in the flattened (row, sequence) dimension
+        let mut logit_positions = vec![0i64; input.len()];
+        for (row_index, row) in rows.iter().enumerate() {
+            for (sequence_index, &index) in row.iter().enumerate() {
+                logit_positions[index] = (row_index * max_sequences + sequence_index) as i64;
+            }
+        }
+        let num_labels = *logits.size().last().unwrap();
+        let output = logits
+            .view((-1, num_labels))
+            .index_select(0, &Tensor::of_slice(&logit_positions).to(device));
+        let output = self.activation(&output, false).detach().to(Device::Cpu);
+        Ok(self.labels_from_scores(output, 0))
+    }
+
+    fn classify(
+        &self,
+        input_tensor: Tensor,
+        attention_mask: Option<Tensor>,
+        sentence_offset: usize,
+    ) -> Vec<Label> {
+        let output = self.forward_scores(input_tensor, attention_mask);
+        self.labels_from_scores(output, sentence_offset)
+    }
+
+    /// Classifies the inputs in batches of at most `batch_size` inputs (or windows of long inputs
+    /// with a sliding window strategy), each padded to its own longest input. Returns the class
+    /// scores of shape (*batch size*, *num_labels*) on the CPU, see `activation`.
+    fn batched_scores(&self, input: &[&str], multi_label: bool) -> Result<Tensor, RustBertError> {
+        let logits = self.batched_logits(input)?;
+        Ok(no_grad(|| self.activation(&logits, multi_label)))
+    }
+
+    /// Computes the logits of the inputs before the activation (see `batched_scores`), returning
+    /// logits of shape (*batch size*, *num_labels*) on the CPU
+    fn batched_logits(&self, input: &[&str]) -> Result<Tensor, RustBertError> {
+        if input.is_empty() {
+            return Err(RustBertError::EmptyInputError);
+        }
+        match self.long_input_strategy {
+            LongInputStrategy::Truncate => {
+                let logits = input
+                    .chunks(self.batch_size)
+                    .map(|batch| {
+                        let (input_tensor, attention_mask) = self.prepare_for_model(batch)?;
+                        Ok(self.forward_logits(input_tensor, attention_mask))
+                    })
+                    .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+                Ok(Tensor::cat(&logits, 0))
+            }
+            LongInputStrategy::SlidingWindow {
+                stride,
+                aggregation,
+            } => self.sliding_window_logits(input, stride, aggregation),
+        }
+    }
+
+    /// Classifies the windows of the inputs and aggregates the logits of the windows of each input,
+    /// returning logits of shape (*batch size*, *num_labels*) on the CPU
+    fn sliding_window_logits(
+        &self,
+        input: &[&str],
+        stride: usize,
+        aggregation: WindowAggregation,
+    ) -> Result<Tensor, RustBertError> {
+        let input_windows = input
+            .iter()
+            .map(|text| self.window_token_ids(text, stride))
+            .collect::<Vec<Vec<Vec<i64>>>>();
+        let num_windows = input_windows
+            .iter()
+            .map(|windows| windows.len() as i64)
+            .collect::<Vec<i64>>();
+        let windows = input_windows
+            .into_iter()
+            .flatten()
+            .collect::<Vec<Vec<i64>>>();
+        let window_logits = windows
+            .chunks(self.batch_size)
+            .map(|batch| {
+                let (input_tensor, attention_mask) = pad_token_ids(
+                    &self.tokenizer,
+                    self.pad_to_multiple_of,
+                    batch.to_vec(),
+                    self.var_store.device(),
+                )?;
+                Ok(self.forward_logits(input_tensor, attention_mask))
+            })
+            .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+        let window_logits = Tensor::cat(&window_logits, 0);
+
+        let mut start = 0;
+        let logits = num_windows
+            .iter()
+            .map(|&num_windows| {
+                let logits = window_logits.narrow(0, start, num_windows);
+                start += num_windows;
+                match aggregation {
+                    WindowAggregation::Mean => logits.mean1(&[0], false, Kind::Float),
+                    WindowAggregation::Max => logits.max2(0, false).0,
+                    WindowAggregation::First => logits.get(0),
+                }
+            })
+            .collect::<Vec<Tensor>>();
+        Ok(Tensor::stack(&logits, 0))
+    }
+
+    /// Splits the tokens of a text into windows of at most `max_length` tokens including the
+    /// special tokens, consecutive windows overlapping by `stride` tokens
+    fn window_token_ids(&self, input: &str, stride: usize) -> Vec<Vec<i64>> {
+        let tokenized_input = self.tokenizer.tokenize_with_offsets(input);
+        let encoded_input = TokenIdsWithOffsets {
+            ids: self
+                .tokenizer
+                .convert_tokens_to_ids(&tokenized_input.tokens),
+            offsets: tokenized_input.offsets,
+            reference_offsets: tokenized_input.reference_offsets,
+            masks: tokenized_input.masks,
+        };
+        let window_length = self.max_length - single_special_tokens(&self.tokenizer);
+        let num_tokens = encoded_input.ids.len();
+
+        let mut windows = vec![];
+        let mut start_token = 0;
+        loop {
+            let end_token = min(start_token + window_length, num_tokens);
+            let window = TokenIdsWithOffsets {
+                ids: encoded_input.ids[start_token..end_token].to_vec(),
+                offsets: encoded_input.offsets[start_token..end_token].to_vec(),
+                reference_offsets: encoded_input.reference_offsets[start_token..end_token].to_vec(),
+                masks: encoded_input.masks[start_token..end_token].to_vec(),
+            };
+            windows.push(
+                self.tokenizer
+                    .build_input_with_special_tokens(window, None)
+                    .token_ids,
+            );
+            if end_token == num_tokens {
+                break;
+            }
+            start_token = end_token - stride;
+        }
+        windows
+    }
+
+    /// Returns the logits of shape (*batch size*, *num_labels*) on the CPU
+    fn forward_logits(&self, input_tensor: Tensor, attention_mask: Option<Tensor>) -> Tensor {
+        no_grad(|| {
+            self.sequence_classifier
+                .forward_t(
+                    Some(input_tensor.copy()),
+                    attention_mask,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .detach()
+                .to(Device::Cpu)
+        })
+    }
+
+    /// Returns the class scores of shape (*batch size*, *num_labels*) on the CPU, see `activation`
+    fn forward_scores(&self, input_tensor: Tensor, attention_mask: Option<Tensor>) -> Tensor {
+        let logits = self.forward_logits(input_tensor, attention_mask);
+        no_grad(|| self.activation(&logits, false))
+    }
+
+    /// Applies the calibration and the activation of the problem type of the model to the logits.
+    /// Models without a problem type use a softmax for the single-label methods and a sigmoid for
+    /// the multi-label ones.
+    fn activation(&self, logits: &Tensor, multi_label: bool) -> Tensor {
+        if self.problem_type == Some(ProblemType::Regression) {
+            return logits.shallow_clone();
+        }
+        let logits = match &self.calibration {
+            Some(calibration) => calibration.apply(logits),
+            None => logits.shallow_clone(),
+        };
+        match self.problem_type {
+            Some(ProblemType::MultiLabelClassification) => logits.sigmoid(),
+            None if multi_label => logits.sigmoid(),
+            _ => logits.softmax(-1, Kind::Float),
+        }
+    }
+
+    /// Builds the most likely label of each input from the scores of shape (*batch size*, *num_labels*)
+    fn labels_from_scores(&self, output: Tensor, sentence_offset: usize) -> Vec<Label> {
+        let label_indices = output.as_ref().argmax(-1, true).squeeze1(1);
+        let scores = output
+            .gather(1, &label_indices.unsqueeze(-1), false)
+            .squeeze1(1);
+        let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
+        let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+
+        let mut labels: Vec<Label> = vec![];
+        for sentence_idx in 0..label_indices.len() {
+            let label_string = self
+                .label_mapping
+                .get(&label_indices[sentence_idx])
+                .unwrap()
+                .clone();
+            let label = Label {
+                text: label_string,
+                score: scores[sentence_idx],
+                id: label_indices[sentence_idx],
+                sentence: sentence_offset + sentence_idx,
+            };
+            labels.push(label)
+        }
+        labels
+    }
+
+    /// Classify texts, returning an individual result for each input
+    ///
+    /// Inputs are validated one by one before batching: invalid inputs (e.g. empty texts) do not
+    /// fail the entire batch but are excluded from the forward pass and carry their own error.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Result<Label, RustBertError>>` containing the label or error for each input text, in the input order
+    /// * `RustBertError` if the classification of the valid inputs fails (e.g. missing padding token)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["Probably my all-time favorite movie.", "", "A boring film."];
+    /// let output = sequence_classification_model.predict_each(&input)?;
+    /// assert!(output[1].is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_each<'a, S>(
+        &self,
+        input: S,
+    ) -> Result<Vec<Result<Label, RustBertError>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let (valid_inputs, validation) = split_valid_inputs(input.as_ref());
+        let labels = if valid_inputs.is_empty() {
+            vec![]
+        } else {
+            self.predict(&valid_inputs)?
+        };
+        Ok(align_outputs(validation, labels)
+            .into_iter()
+            .enumerate()
+            .map(|(sentence_idx, label)| {
+                label.map(|mut label| {
+                    label.sentence = sentence_idx;
+                    label
+                })
+            })
+            .collect())
+    }
+
+    /// Classify structured records made of several text fields
+    ///
+    /// Each field is truncated to its token budget before the fields are concatenated, following
+    /// the order and separator of the `FieldConfig`. The texts built are then classified as with `predict`.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - `&[Record]` Array of records to classify.
+    /// * `field_config` - `&FieldConfig` token budget of each field, concatenation order and separator
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input records
+    /// * `RustBertError` if the field configuration is invalid or if a record has no configured field
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::records::{FieldConfig, Record};
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let field_config = FieldConfig::new(&[("title", 16), ("body", 96)], ". ");
+    /// let records = [Record::new()
+    ///     .with_field("title", "Probably my all-time favorite movie")
+    ///     .with_field("body", "A story of selflessness, sacrifice and dedication to a noble cause.")];
+    /// let output = sequence_classification_model.predict_records(&records, &field_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_records(
+        &self,
+        records: &[Record],
+        field_config: &FieldConfig,
+    ) -> Result<Vec<Label>, RustBertError> {
+        let texts = build_record_texts(&self.tokenizer, records, field_config)?;
+        let texts = texts.iter().map(String::as_str).collect::<Vec<&str>>();
+        self.predict(&texts)
+    }
+
+    /// Evaluates the model on a labelled dataset, returning the accuracy, the F1 scores, the
+    /// confusion matrix and the misclassified examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - `&[(String, String)]` texts with their gold label
+    ///
+    /// # Returns
+    ///
+    /// * `EvalReport` of the predictions on the dataset
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let dataset = [
+    ///     ("A boring film.".to_string(), "NEGATIVE".to_string()),
+    ///     ("I loved it.".to_string(), "POSITIVE".to_string()),
+    /// ];
+    /// let report = sequence_classification_model.evaluate(&dataset)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn evaluate(&self, dataset: &[(String, String)]) -> Result<EvalReport, RustBertError> {
+        let texts = dataset
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<&str>>();
+        let predictions = if texts.is_empty() {
+            vec![]
+        } else {
+            self.predict(&texts)?
+        };
+        single_label_report(dataset, &predictions)
+    }
+
+    /// Evaluates the model on a labelled dataset of any size, returning the accuracy, the macro F1
+    /// score, the precision and recall of each class and the confusion matrix, indexed by class id.
+    /// The examples are consumed and predicted `batch_size` at a time: the dataset is never held in
+    /// memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - texts with their gold class id (e.g. an iterator over the lines of a file)
+    /// * `batch_size` - `usize` number of examples read and predicted at a time, each batch being
+    ///   processed in forward passes of at most the `batch_size` of the configuration
+    ///
+    /// # Returns
+    ///
+    /// * `ClassificationMetrics` of the predictions on the dataset, all set to 0 for an empty dataset
+    /// * `ValueError` if the batch size is 0 or if a gold class id is out of range
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let dataset = vec![
+    ///     ("A boring film.".to_string(), 0),
+    ///     ("I loved it.".to_string(), 1),
+    /// ];
+    /// let metrics = sequence_classification_model.evaluate_iter(dataset, 64)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn evaluate_iter<I>(
+        &self,
+        dataset: I,
+        batch_size: usize,
+    ) -> Result<ClassificationMetrics, RustBertError>
+    where
+        I: IntoIterator<Item = (String, i64)>,
+    {
+        if batch_size == 0 {
+            return Err(RustBertError::ValueError(
+                "The batch size must be strictly positive".to_string(),
+            ));
+        }
+        let mut accumulator = MetricsAccumulator::new(self.label_mapping.len());
+        let mut dataset = dataset.into_iter();
+        loop {
+            let batch = dataset
+                .by_ref()
+                .take(batch_size)
+                .collect::<Vec<(String, i64)>>();
+            if batch.is_empty() {
+                break;
+            }
+            let texts = batch
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<Vec<&str>>();
+            for ((_, gold), prediction) in batch.iter().zip(self.predict(&texts)?) {
+                accumulator.update(*gold, prediction.id)?;
+            }
+        }
+        Ok(accumulator.metrics())
+    }
+
+    /// Evaluates the multi-label classification of the model on a labelled dataset, returning the
+    /// exact match accuracy, the F1 scores, the counts of each label and the misclassified examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - `&[(String, Vec<String>)]` texts with their gold labels
+    /// * `threshold` - `f64` threshold above which a label will be considered true by the classifier
+    ///
+    /// # Returns
+    ///
+    /// * `MultilabelEvalReport` of the predictions on the dataset
+    pub fn evaluate_multilabel(
+        &self,
+        dataset: &[(String, Vec<String>)],
+        threshold: f64,
+    ) -> Result<MultilabelEvalReport, RustBertError> {
+        let texts = dataset
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<&str>>();
+        let predictions = if texts.is_empty() {
+            vec![]
+        } else {
+            self.predict_multilabel(&texts, threshold)?
+        };
+        multilabel_report(dataset, &predictions)
+    }
+
+    /// Multi-label classification of texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `threshold` - `f64` threshold above which a label will be considered true by the classifier,
+    ///   or `&HashMap<String, f64>` of per-label thresholds (labels without an entry use 0.5)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing a vector of true labels for each input text, empty for the
+    ///   texts without any label reaching its threshold
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// # use std::collections::HashMap;
+    ///
+    /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+    ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
+    /// ];
+    /// let output = sequence_classification_model.predict_multilabel(&input, 0.5);
+    ///
+    /// let mut thresholds = HashMap::new();
+    /// thresholds.insert("POSITIVE".to_string(), 0.9);
+    /// let output = sequence_classification_model.predict_multilabel(&input, &thresholds);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multilabel<'t, T>(
+        &self,
+        input: &[&str],
+        threshold: T,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        T: Into<MultilabelThreshold<'t>>,
+    {
+        let output = self.batched_scores(input, true)?;
+        let threshold = threshold.into();
+        let mut labels: Vec<Vec<Label>> = Vec::with_capacity(input.len());
+        for sentence in 0..input.len() {
+            let scores = output.get(sentence as i64);
+            let mut sequence_labels: Vec<Label> = vec![];
+            for (id, score) in scores.iter::<f64>().unwrap().enumerate() {
+                let id = id as i64;
+                let label_string = self.label_mapping.get(&id).unwrap();
+                if score >= threshold.for_label(label_string) {
+                    sequence_labels.push(Label {
+                        text: label_string.to_owned(),
+                        score,
+                        id,
+                        sentence,
+                    });
+                }
+            }
+            labels.push(sequence_labels);
+        }
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
     fn test() {
         let config = SequenceClassificationConfig::default();
         let _: Box<dyn Send> = Box::new(SequenceClassificationModel::new(config));
     }
+
+    #[test]
+    fn test_pack_sequences() {
+        assert_eq!(
+            pack_sequences(&[4, 5, 3, 8, 2, 2], 10),
+            vec![vec![0, 1], vec![2], vec![3, 4], vec![5]]
+        );
+        assert_eq!(pack_sequences(&[10, 10], 10), vec![vec![0], vec![1]]);
+        assert!(pack_sequences(&[], 10).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "bert")]
+    fn test_predict_packed() -> anyhow::Result<()> {
+        use crate::fixtures::tiny_bert_resources;
+
+        let directory = tempfile::tempdir()?;
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris",
+            "great", "boring", "movie", ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) =
+            tiny_bert_resources(directory.path(), &vocab, &["NEGATIVE", "POSITIVE"])?;
+        let mut config = SequenceClassificationConfig::new(
+            ModelType::Bert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        let model = SequenceClassificationModel::new(config)?;
+
+        let inputs = [
+            "great movie.",
+            "the dog is in paris.",
+            "boring.",
+            "the movie is great. the dog is boring.",
+            "paris",
+        ];
+        let expected = model.predict(&inputs)?;
+        let scores =
+            |labels: &[Label]| labels.iter().map(|label| label.score).collect::<Vec<f64>>();
+
+        //    Isolated inputs: identical predictions, for rows of one or several inputs
+        for &max_length in [16, 32, 64].iter() {
+            let output = model.predict_packed(&inputs, max_length, PackingMode::BlockDiagonal)?;
+            assert_eq!(output.len(), inputs.len());
+            for (sentence_idx, (label, expected_label)) in
+                output.iter().zip(expected.iter()).enumerate()
+            {
+                assert_eq!(label.sentence, sentence_idx);
+                assert_eq!(label.id, expected_label.id);
+                assert!((label.score - expected_label.score).abs() < 1e-5);
+            }
+        }
+
+        //    Shared attention: the inputs are contextualized by the other inputs of their row
+        let output = model.predict_packed(&inputs, 64, PackingMode::Shared)?;
+        assert_eq!(output.len(), inputs.len());
+        assert_ne!(scores(&output), scores(&expected));
+        let no_inputs: [&str; 0] = [];
+        assert!(model
+            .predict_packed(&no_inputs, 64, PackingMode::Shared)?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let vocab_path = directory.path().join("vocab.txt");
+        std::fs::write(
+            &vocab_path,
+            "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\ngreat\nmovie",
+        )?;
+        let weights_path = directory.path().join("rust_model.ot");
+        std::fs::write(&weights_path, "")?;
+        let write_config = |labels: &str| -> anyhow::Result<SequenceClassificationConfig> {
+            let config_path = directory.path().join("config.json");
+            std::fs::write(
+                &config_path,
+                format!(
+                    r#"{{
+                        "activation": "gelu",
+                        "attention_dropout": 0.1,
+                        "dim": 16,
+                        "dropout": 0.1,
+                        "hidden_dim": 32,
+                        "initializer_range": 0.02,
+                        "max_position_embeddings": 64,
+                        "n_heads": 2,
+                        "n_layers": 2,
+                        "qa_dropout": 0.1,
+                        "seq_classif_dropout": 0.2,
+                        "sinusoidal_pos_embds": false,
+                        "tie_weights_": false,
+                        "vocab_size": 7{}
+                    }}"#,
+                    labels
+                ),
+            )?;
+            Ok(SequenceClassificationConfig::new(
+                ModelType::DistilBert,
+                Resource::Local(LocalResource {
+                    local_path: weights_path.clone(),
+                }),
+                Resource::Local(LocalResource {
+                    local_path: config_path,
+                }),
+                Resource::Local(LocalResource {
+                    local_path: vocab_path.clone(),
+                }),
+                None,
+                true,
+                None,
+                None,
+            ))
+        };
+
+        //    Valid configuration: the empty weights file is not loaded
+        let config = write_config(
+            r#", "id2label": {"0": "NEGATIVE", "1": "POSITIVE"},
+            "label2id": {"NEGATIVE": 0, "POSITIVE": 1}"#,
+        )?;
+        let report = SequenceClassificationModel::validate_config(&config)?;
+        assert!(report.is_valid(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.num_labels, Some(2));
+        assert_eq!(report.max_positions, Some(64));
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_string(&report)?;
+            let deserialized: ValidationReport = serde_json::from_str(&serialized)?;
+            assert_eq!(deserialized.num_labels, Some(2));
+        }
+
+        //    Inconsistent label mappings are reported as a warning
+        let config = write_config(
+            r#", "id2label": {"0": "NEGATIVE", "1": "POSITIVE"},
+            "label2id": {"POSITIVE": 0, "NEGATIVE": 1}"#,
+        )?;
+        let report = SequenceClassificationModel::validate_config(&config)?;
+        assert!(report.is_valid());
+        assert_eq!(report.warnings.len(), 1);
+
+        //    Missing label mapping
+        let config = write_config("")?;
+        let report = SequenceClassificationModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("id2label"));
+
+        //    Missing resources: every problem is reported
+        let mut config = write_config("")?;
+        config.vocab_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_vocab.txt"),
+        });
+        config.model_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_model.ot"),
+        });
+        let report = SequenceClassificationModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 3);
+        assert!(report.errors[0].contains("missing_model.ot"));
+        Ok(())
+    }
+
+    fn tiny_distilbert_config(
+        directory: &std::path::Path,
+    ) -> anyhow::Result<SequenceClassificationConfig> {
+        use crate::fixtures::tiny_distilbert_resources;
+
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris",
+            "great", "boring", "movie", ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) = tiny_distilbert_resources(
+            directory,
+            &vocab,
+            Some(&["negative", "neutral", "positive"]),
+        )?;
+        let mut config = SequenceClassificationConfig::new(
+            ModelType::DistilBert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_predict_topk() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["great movie.", "the dog is in paris."];
+        let expected = model.predict(&inputs)?;
+
+        let output = model.predict_topk(&inputs, 2)?;
+        assert_eq!(output.len(), inputs.len());
+        for (sentence_idx, labels) in output.iter().enumerate() {
+            assert_eq!(labels.len(), 2);
+            assert!(labels[0].score >= labels[1].score);
+            assert_eq!(labels[0].id, expected[sentence_idx].id);
+            for label in labels {
+                assert_eq!(label.sentence, sentence_idx);
+                assert_eq!(model.label_mapping[&label.id], label.text);
+            }
+        }
+
+        //    All the classes are returned if k exceeds their number
+        let output = model.predict_topk(&inputs, 10)?;
+        for labels in output.iter() {
+            assert_eq!(labels.len(), 3);
+            let total = labels.iter().map(|label| label.score).sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_full() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["great movie.", "the dog is in paris.", "boring."];
+        let expected = model.predict(&inputs)?;
+
+        let output = model.predict_full(&inputs)?;
+        assert_eq!(output.len(), inputs.len());
+        for (sentence_idx, labels) in output.iter().enumerate() {
+            assert_eq!(
+                labels.iter().map(|label| label.id).collect::<Vec<i64>>(),
+                vec![0, 1, 2]
+            );
+            let total = labels.iter().map(|label| label.score).sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-5);
+            let best = &labels[expected[sentence_idx].id as usize];
+            assert_eq!(best.text, expected[sentence_idx].text);
+            assert!((best.score - expected[sentence_idx].score).abs() < 1e-6);
+            assert!(labels.iter().all(|label| label.score <= best.score));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_logits() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["great movie.", "the dog is in paris.", "boring."];
+        let logits = model.predict_logits(&inputs)?;
+        assert_eq!(logits.size(), vec![3, 3]);
+        assert_eq!(logits.device(), Device::Cpu);
+
+        //    The softmax of the logits matches the probabilities of `predict_full`
+        let probabilities = logits.softmax(-1, Kind::Float);
+        let output = model.predict_full(&inputs)?;
+        for (sentence_idx, labels) in output.iter().enumerate() {
+            for label in labels {
+                let expected = probabilities.double_value(&[sentence_idx as i64, label.id]);
+                assert!((label.score - expected).abs() < 1e-6);
+            }
+        }
+        assert!(matches!(
+            model.predict_logits(&[]),
+            Err(RustBertError::EmptyInputError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_calibration() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let inputs = ["great movie.", "the dog is in paris.", "boring."];
+        let logits = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?
+            .predict_logits(&inputs)?;
+
+        //    Temperature scaling of the single-label scores, the logits remain uncalibrated
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.calibration = Some(Calibration::Temperature(2.0));
+        let model = SequenceClassificationModel::new(config)?;
+        assert!(model.predict_logits(&inputs)?.equal(&logits));
+        let probabilities = (&logits / 2.0).softmax(-1, Kind::Float);
+        for (sentence_idx, labels) in model.predict_full(&inputs)?.iter().enumerate() {
+            for label in labels {
+                let expected = probabilities.double_value(&[sentence_idx as i64, label.id]);
+                assert!((label.score - expected).abs() < 1e-6);
+            }
+        }
+
+        //    Platt scaling of each class before the sigmoid of the multi-label scores
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.calibration = Some(Calibration::PlattPerClass(vec![
+            (2.0, 0.0),
+            (1.0, -1.0),
+            (0.5, 1.0),
+        ]));
+        let model = SequenceClassificationModel::new(config)?;
+        let scales = Tensor::of_slice(&[2.0f32, 1.0, 0.5]);
+        let biases = Tensor::of_slice(&[0.0f32, -1.0, 1.0]);
+        let probabilities = (&logits * scales + biases).sigmoid();
+        for labels in model.predict_multilabel(&inputs, 0.0)? {
+            assert_eq!(labels.len(), 3);
+            for label in labels {
+                let expected = probabilities.double_value(&[label.sentence as i64, label.id]);
+                assert!((label.score - expected).abs() < 1e-6);
+            }
+        }
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.calibration = Some(Calibration::Temperature(0.0));
+        assert!(matches!(
+            SequenceClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.calibration = Some(Calibration::PlattPerClass(vec![(1.0, 0.0); 2]));
+        assert!(matches!(
+            SequenceClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_iter() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let texts = [
+            "great movie.",
+            "the dog is in paris.",
+            "boring.",
+            "the movie is great.",
+            "paris",
+        ];
+        let predictions = model.predict(&texts)?;
+        //    The first two examples are misclassified
+        let dataset = texts
+            .iter()
+            .zip(predictions.iter())
+            .enumerate()
+            .map(|(index, (text, prediction))| {
+                let gold = if index < 2 {
+                    (prediction.id + 1) % 3
+                } else {
+                    prediction.id
+                };
+                (text.to_string(), gold)
+            })
+            .collect::<Vec<(String, i64)>>();
+
+        //    The metrics do not depend on the number of examples read at a time
+        let expected = model.evaluate_iter(dataset.clone(), 64)?;
+        assert_eq!(expected.num_examples, 5);
+        assert!((expected.accuracy - 0.6).abs() < 1e-9);
+        assert_eq!(expected.confusion_matrix.len(), 3);
+        for &batch_size in [1, 2, 5].iter() {
+            assert_eq!(model.evaluate_iter(dataset.clone(), batch_size)?, expected);
+        }
+
+        assert_eq!(model.evaluate_iter(vec![], 8)?.num_examples, 0);
+        assert!(model.evaluate_iter(dataset.clone(), 0).is_err());
+        assert!(model
+            .evaluate_iter(vec![("paris".to_string(), 3)], 8)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_input() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        assert!(matches!(
+            model.predict(&[]),
+            Err(RustBertError::EmptyInputError)
+        ));
+        assert!(matches!(
+            model.predict_multilabel(&[], 0.5),
+            Err(RustBertError::EmptyInputError)
+        ));
+        assert!(model.predict_each(&["", " "])?.iter().all(Result::is_err));
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_tokenizer() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let config = tiny_distilbert_config(directory.path())?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+        let inputs = ["the dog is in paris.", "great movie."];
+        let expected = SequenceClassificationModel::new(config)?.predict_full(&inputs)?;
+
+        //    The vocabulary resource is not read when the tokenizer is injected
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.vocab_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_vocab.txt"),
+        });
+        let model = SequenceClassificationModel::new_with_tokenizer(config, tokenizer)?;
+        let output = model.predict_full(&inputs)?;
+        for (labels, expected_labels) in output.iter().zip(expected.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert_eq!(label.text, expected_label.text);
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+        assert!(matches!(
+            model.save_state(directory.path().join("sequence_classification.state")),
+            Err(RustBertError::InvalidStateError(_))
+        ));
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.model_type = ModelType::XLMRoberta;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+        assert!(matches!(
+            SequenceClassificationModel::new_with_tokenizer(config, tokenizer),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_size() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let config = tiny_distilbert_config(directory.path())?;
+        let model = SequenceClassificationModel::new(config)?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.batch_size = 2;
+        let batched_model = SequenceClassificationModel::new(config)?;
+        let inputs = [
+            "great movie.",
+            "the dog is in paris. the dog is in paris. the dog is in paris.",
+            "boring.",
+            "the movie is great. the dog is boring.",
+            "paris",
+        ];
+
+        //    Each batch is padded to its own longest input, the padding being masked
+        let expected = model.predict(&inputs)?;
+        let output = batched_model.predict(&inputs)?;
+        assert_eq!(output.len(), inputs.len());
+        for (sentence_idx, (label, expected_label)) in
+            output.iter().zip(expected.iter()).enumerate()
+        {
+            assert_eq!(label.sentence, sentence_idx);
+            assert_eq!(label.id, expected_label.id);
+            assert!((label.score - expected_label.score).abs() < 1e-5);
+        }
+
+        let expected = model.predict_multilabel(&inputs, 0.0)?;
+        let output = batched_model.predict_multilabel(&inputs, 0.0)?;
+        assert_eq!(output.len(), inputs.len());
+        for (labels, expected_labels) in output.iter().zip(expected.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert_eq!(label.sentence, expected_label.sentence);
+                assert!((label.score - expected_label.score).abs() < 1e-5);
+            }
+        }
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.batch_size = 0;
+        assert!(SequenceClassificationModel::new(config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_mapping() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let label_mapping = [(0, "bad"), (1, "average"), (2, "good")]
+            .iter()
+            .map(|&(id, label)| (id, label.to_string()))
+            .collect::<HashMap<i64, String>>();
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.label_mapping = Some(label_mapping.clone());
+        let model = SequenceClassificationModel::new(config)?;
+        for label in model
+            .predict_full(&["great movie.", "boring."])?
+            .iter()
+            .flatten()
+        {
+            assert_eq!(label.text, label_mapping[&label.id]);
+        }
+
+        //    The mapping must cover exactly the 3 classes of the classifier head
+        let mut incomplete_mapping = label_mapping.clone();
+        incomplete_mapping.remove(&1);
+        let mut extended_mapping = label_mapping;
+        extended_mapping.insert(3, "excellent".to_string());
+        for invalid_mapping in &[incomplete_mapping, extended_mapping] {
+            let mut config = tiny_distilbert_config(directory.path())?;
+            config.label_mapping = Some(invalid_mapping.clone());
+            let report = SequenceClassificationModel::validate_config(&config)?;
+            assert!(!report.is_valid());
+            assert!(report.errors[0].contains("label mapping"));
+            assert!(matches!(
+                SequenceClassificationModel::new(config),
+                Err(RustBertError::InvalidConfigurationError(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_problem_type() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let inputs = ["great movie.", "boring."];
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let sigmoid_scores = model.predict_multilabel(&inputs, 0.0)?;
+        let softmax_scores = model.predict_full(&inputs)?;
+
+        //    The problem type of the configuration file selects the activation of every method
+        let mut config = tiny_distilbert_config(directory.path())?;
+        let config_json = std::fs::read_to_string(config.config_resource.get_local_path()?)?
+            .replacen('{', r#"{"problem_type": "multi_label_classification","#, 1);
+        let config_path = directory.path().join("multi_label_config.json");
+        std::fs::write(&config_path, config_json)?;
+        config.config_resource = Resource::Local(crate::resources::LocalResource {
+            local_path: config_path,
+        });
+        let multi_label_model = SequenceClassificationModel::new(config)?;
+        assert_eq!(
+            multi_label_model.problem_type,
+            Some(ProblemType::MultiLabelClassification)
+        );
+        let output = multi_label_model.predict_full(&inputs)?;
+        for (labels, expected_labels) in output.iter().zip(sigmoid_scores.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+
+        //    The pipeline option takes precedence over the configuration file
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.problem_type = Some(ProblemType::SingleLabelClassification);
+        let single_label_model = SequenceClassificationModel::new(config)?;
+        let output = single_label_model.predict_multilabel(&inputs, 0.0)?;
+        for (labels, expected_labels) in output.iter().zip(softmax_scores.iter()) {
+            assert_eq!(labels.len(), expected_labels.len());
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sliding_window() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        //    Windows of 6 input tokens between the [CLS] and [SEP] tokens
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.max_length = 8;
+        let truncating_model = SequenceClassificationModel::new(config)?;
+        let window_model = |aggregation| -> anyhow::Result<SequenceClassificationModel> {
+            let mut config = tiny_distilbert_config(directory.path())?;
+            config.max_length = 8;
+            config.long_input_strategy = LongInputStrategy::SlidingWindow {
+                stride: 2,
+                aggregation,
+            };
+            Ok(SequenceClassificationModel::new(config)?)
+        };
+        let mean_model = window_model(WindowAggregation::Mean)?;
+        let first_model = window_model(WindowAggregation::First)?;
+        let max_model = window_model(WindowAggregation::Max)?;
+
+        //    A document exactly filling a window is classified in a single pass
+        let boundary_document = "the dog is in paris .";
+        assert_eq!(mean_model.window_token_ids(boundary_document, 2).len(), 1);
+        let expected = truncating_model.predict_full(&[boundary_document])?;
+        for model in [&mean_model, &first_model, &max_model].iter() {
+            let output = model.predict_full(&[boundary_document])?;
+            for (label, expected_label) in output[0].iter().zip(expected[0].iter()) {
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+
+        //    14 tokens: windows [0, 6), [4, 10) and [8, 14)
+        let document = "the dog is in paris . the movie is great . the dog .";
+        let windows = [
+            "the dog is in paris .",
+            "paris . the movie is great",
+            "is great . the dog .",
+        ];
+        assert_eq!(mean_model.window_token_ids(document, 2).len(), 3);
+        let window_logits = windows
+            .iter()
+            .map(|window| {
+                let (input_tensor, attention_mask) =
+                    truncating_model.prepare_for_model(&[*window])?;
+                Ok(truncating_model.forward_logits(input_tensor, attention_mask))
+            })
+            .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+        let expected_scores = Tensor::cat(&window_logits, 0)
+            .mean1(&[0], false, Kind::Float)
+            .softmax(-1, Kind::Float);
+        let output = mean_model.predict_full(&[document, boundary_document])?;
+        assert_eq!(output.len(), 2);
+        for label in output[0].iter() {
+            assert!((label.score - expected_scores.double_value(&[label.id])).abs() < 1e-6);
+        }
+
+        let expected = truncating_model.predict(&[windows[0]])?;
+        let output = first_model.predict(&[document])?;
+        assert_eq!(output[0].id, expected[0].id);
+        assert!((output[0].score - expected[0].score).abs() < 1e-6);
+        assert_eq!(max_model.predict(&[document, boundary_document])?.len(), 2);
+
+        //    The windows must progress through the documents
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.max_length = 8;
+        config.long_input_strategy = LongInputStrategy::SlidingWindow {
+            stride: 6,
+            aggregation: WindowAggregation::Mean,
+        };
+        assert!(SequenceClassificationModel::new(config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilabel_thresholds() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = SequenceClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["great movie.", "the dog is in paris.", "boring."];
+
+        //    Sigmoid scores never exceed 1: every input gets an empty vector of labels
+        let output = model.predict_multilabel(&inputs, 1.1)?;
+        assert_eq!(output.len(), inputs.len());
+        assert!(output.iter().all(|labels| labels.is_empty()));
+
+        let all_labels = model.predict_multilabel(&inputs, 0.0)?;
+        let mut thresholds = HashMap::new();
+        thresholds.insert("negative".to_string(), 0.0);
+        thresholds.insert("neutral".to_string(), 1.1);
+        thresholds.insert("positive".to_string(), 1.1);
+        let output = model.predict_multilabel(&inputs, &thresholds)?;
+        assert_eq!(output.len(), inputs.len());
+        for (sentence, (labels, all_labels)) in output.iter().zip(all_labels.iter()).enumerate() {
+            assert_eq!(labels.len(), 1);
+            assert_eq!(labels[0].text, "negative");
+            assert_eq!(labels[0].sentence, sentence);
+            assert!((labels[0].score - all_labels[0].score).abs() < 1e-9);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_outputs() {
+        let inputs = ["first", "", "third", "   ", "fifth"];
+        let (valid_inputs, validation) = split_valid_inputs(&inputs);
+        assert_eq!(valid_inputs, ["first", "third", "fifth"]);
+
+        let outputs = valid_inputs
+            .iter()
+            .map(|input| input.to_uppercase())
+            .collect::<Vec<String>>();
+        let aligned = align_outputs(validation, outputs);
+        assert_eq!(aligned.len(), inputs.len());
+        assert_eq!(aligned[0].as_ref().unwrap(), "FIRST");
+        assert!(aligned[1].is_err());
+        assert_eq!(aligned[2].as_ref().unwrap(), "THIRD");
+        assert!(aligned[3].is_err());
+        assert_eq!(aligned[4].as_ref().unwrap(), "FIFTH");
+    }
 }
@@ -0,0 +1,1578 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Pipeline construction from a spec file
+//! Builds any of the pipelines from a single JSON (`.json`) or YAML (`.yaml`, `.yml`) file. The
+//! `task` field selects the pipeline, the remaining fields mirror its configuration. Omitting the
+//! `model` section loads the default model of the task, a custom model is described by its
+//! `model_type` and either a Hugging Face model hub identifier or the location of its files. URLs
+//! are downloaded to the cache, relative paths are resolved against the directory of the spec file.
+//!
+//! ```yaml
+//! task: text_generation
+//! model:
+//!   model_type: GPT2
+//!   hub_id: gpt2
+//! device: cuda:0
+//! generation:
+//!   max_length: 64
+//!   top_p: 0.95
+//! ```
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::spec::{from_spec, PipelineHandle};
+//!
+//! if let PipelineHandle::TextGeneration(model) = from_spec("text_generation.yaml")? {
+//!     let output = model.generate(&["The dog"], None);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Spec files are checked before any resource is loaded. Validation errors name the offending
+//! field, e.g. ``"`generation.top_p` must be between 0 and 1 (got 1.5)"``.
+
+use crate::common::error::RustBertError;
+use crate::common::resources::{LocalResource, RemoteResource, Resource};
+use crate::pipelines::common::ModelType;
+#[cfg(feature = "conversation")]
+use crate::pipelines::conversation::{ConversationConfig, ConversationModel};
+#[cfg(feature = "feature-extraction")]
+use crate::pipelines::feature_extraction::{
+    FeatureExtractionConfig, FeatureExtractionModel, LayerSelection,
+};
+#[cfg(feature = "ner")]
+use crate::pipelines::ner::NERModel;
+#[cfg(feature = "pos-tagging")]
+use crate::pipelines::pos_tagging::{POSConfig, POSModel};
+#[cfg(feature = "question-answering")]
+use crate::pipelines::question_answering::{QuestionAnsweringConfig, QuestionAnsweringModel};
+#[cfg(feature = "sentiment")]
+use crate::pipelines::sentiment::SentimentModel;
+#[cfg(feature = "sequence-classification")]
+use crate::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+#[cfg(feature = "summarization")]
+use crate::pipelines::summarization::{SummarizationConfig, SummarizationModel};
+#[cfg(feature = "text-generation")]
+use crate::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+#[cfg(feature = "token-classification")]
+use crate::pipelines::token_classification::{
+    LabelAggregationOption, TokenClassificationConfig, TokenClassificationModel,
+};
+#[cfg(feature = "translation")]
+use crate::pipelines::translation::{Language, TranslationConfig, TranslationModel};
+#[cfg(feature = "zero-shot")]
+use crate::pipelines::zero_shot_classification::{
+    validate_hypothesis_template, ZeroShotClassificationConfig, ZeroShotClassificationModel,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::path::Path;
+use tch::Device;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// # Task performed by a pipeline
+pub enum TaskType {
+    SequenceClassification,
+    Sentiment,
+    TokenClassification,
+    Ner,
+    PosTagging,
+    QuestionAnswering,
+    ZeroShotClassification,
+    TextGeneration,
+    Summarization,
+    Translation,
+    Conversation,
+    FeatureExtraction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+/// # Device to place the model on
+/// Written `auto` (CUDA/GPU when available), `cpu`, `cuda` or `cuda:<index>` in spec files.
+pub enum DeviceSpec {
+    Auto,
+    Cpu,
+    Cuda(usize),
+}
+
+impl Default for DeviceSpec {
+    fn default() -> DeviceSpec {
+        DeviceSpec::Auto
+    }
+}
+
+impl TryFrom<String> for DeviceSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<DeviceSpec, String> {
+        match value.as_str() {
+            "auto" => Ok(DeviceSpec::Auto),
+            "cpu" => Ok(DeviceSpec::Cpu),
+            "cuda" => Ok(DeviceSpec::Cuda(0)),
+            device => device
+                .strip_prefix("cuda:")
+                .and_then(|index| index.parse::<usize>().ok())
+                .map(DeviceSpec::Cuda)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid device `{}`, expected `auto`, `cpu`, `cuda` or `cuda:<index>`",
+                        device
+                    )
+                }),
+        }
+    }
+}
+
+impl From<DeviceSpec> for String {
+    fn from(device: DeviceSpec) -> String {
+        match device {
+            DeviceSpec::Auto => "auto".to_string(),
+            DeviceSpec::Cpu => "cpu".to_string(),
+            DeviceSpec::Cuda(index) => format!("cuda:{}", index),
+        }
+    }
+}
+
+impl DeviceSpec {
+    /// Returns the `Device` to place the model on, checking that the requested GPU is available
+    pub fn to_device(self) -> Result<Device, RustBertError> {
+        match self {
+            DeviceSpec::Auto => Ok(Device::cuda_if_available()),
+            DeviceSpec::Cpu => Ok(Device::Cpu),
+            DeviceSpec::Cuda(index) => {
+                if (index as i64) < tch::Cuda::device_count() {
+                    Ok(Device::Cuda(index))
+                } else {
+                    Err(spec_error(format!(
+                        "`device` cuda:{} is not available ({} CUDA device(s) found)",
+                        index,
+                        tch::Cuda::device_count()
+                    )))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Model to load
+/// All fields are optional: an empty model section loads the default model of the task. Custom
+/// models require a `model_type`, the files not provided are looked up in the `hub_id` repository
+/// of the Hugging Face model hub. Files are given as URLs or local paths.
+pub struct ModelSpec {
+    /// Model type (e.g. `Bert`, `GPT2`)
+    pub model_type: Option<ModelType>,
+    /// Hugging Face model hub identifier (e.g. `distilbert-base-uncased`)
+    pub hub_id: Option<String>,
+    /// Model weights (default: `rust_model.ot` from the hub)
+    pub weights: Option<String>,
+    /// Model configuration (default: `config.json` from the hub)
+    pub config: Option<String>,
+    /// Tokenizer vocabulary (default: the standard vocabulary file of the model type from the hub)
+    pub vocab: Option<String>,
+    /// Tokenizer merges or SentencePiece model, needed for BPE tokenizers (default: the standard merges file of the model type from the hub)
+    pub merges: Option<String>,
+    /// Lower case all input upon tokenization (default: false)
+    pub lower_case: Option<bool>,
+    /// Strip accents upon tokenization. Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+}
+
+/// Resources and tokenizer settings of a custom model
+struct ResolvedModel {
+    model_type: ModelType,
+    model_resource: Resource,
+    config_resource: Resource,
+    vocab_resource: Resource,
+    merges_resource: Option<Resource>,
+    lower_case: bool,
+    strip_accents: Option<bool>,
+    add_prefix_space: Option<bool>,
+}
+
+impl ResolvedModel {
+    /// Returns the merges resource, falling back to the vocabulary for the generation pipelines
+    /// of models using a single SentencePiece file.
+    #[cfg(any(
+        feature = "conversation",
+        feature = "summarization",
+        feature = "text-generation",
+        feature = "translation"
+    ))]
+    fn merges_or_vocab(&self) -> Resource {
+        self.merges_resource
+            .clone()
+            .unwrap_or_else(|| self.vocab_resource.clone())
+    }
+}
+
+impl ModelSpec {
+    fn is_default(&self) -> bool {
+        *self == ModelSpec::default()
+    }
+
+    /// Resolves the resources of a custom model, returning `None` if the task default model should be used
+    fn resolve(&self, base_directory: &Path) -> Result<Option<ResolvedModel>, RustBertError> {
+        let model_type = match self.model_type {
+            Some(model_type) => model_type,
+            None if self.is_default() => return Ok(None),
+            None => {
+                return Err(spec_error(
+                    "`model.model_type` must be set to load a custom model",
+                ))
+            }
+        };
+        model_type.ensure_enabled()?;
+        if let Some(hub_id) = &self.hub_id {
+            if hub_id.trim().is_empty() || hub_id.starts_with('/') || hub_id.ends_with('/') {
+                return Err(spec_error(format!(
+                    "`model.hub_id` must be a model hub identifier (got `{}`)",
+                    hub_id
+                )));
+            }
+        }
+
+        let model_resource =
+            self.required("weights", &self.weights, "rust_model.ot", base_directory)?;
+        let config_resource =
+            self.required("config", &self.config, "config.json", base_directory)?;
+        let vocab_resource = self.required(
+            "vocab",
+            &self.vocab,
+            default_vocab_file(model_type),
+            base_directory,
+        )?;
+        let merges_resource = self.locate(
+            "merges",
+            &self.merges,
+            default_merges_file(model_type),
+            base_directory,
+        )?;
+        if merges_resource.is_none() && default_merges_file(model_type).is_some() {
+            return Err(spec_error(format!(
+                "`model.merges` must be set for {:?} models when `model.hub_id` is not",
+                model_type
+            )));
+        }
+
+        Ok(Some(ResolvedModel {
+            model_type,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            merges_resource,
+            lower_case: self.lower_case.unwrap_or(false),
+            strip_accents: self.strip_accents,
+            add_prefix_space: self.add_prefix_space,
+        }))
+    }
+
+    fn required(
+        &self,
+        field: &str,
+        value: &Option<String>,
+        hub_file: &str,
+        base_directory: &Path,
+    ) -> Result<Resource, RustBertError> {
+        self.locate(field, value, Some(hub_file), base_directory)?
+            .ok_or_else(|| {
+                spec_error(format!(
+                    "`model.{}` must be set when `model.hub_id` is not",
+                    field
+                ))
+            })
+    }
+
+    fn locate(
+        &self,
+        field: &str,
+        value: &Option<String>,
+        hub_file: Option<&str>,
+        base_directory: &Path,
+    ) -> Result<Option<Resource>, RustBertError> {
+        match (value, &self.hub_id, hub_file) {
+            (Some(value), _, _) if value.trim().is_empty() => {
+                Err(spec_error(format!("`model.{}` must not be empty", field)))
+            }
+            (Some(value), hub_id, _) => Ok(Some(resolve_resource(
+                value,
+                hub_id.as_deref(),
+                base_directory,
+            ))),
+            (None, Some(hub_id), Some(hub_file)) => {
+                Ok(Some(Resource::Remote(RemoteResource::new(
+                    &format!(
+                        "https://huggingface.co/{}/resolve/main/{}",
+                        hub_id, hub_file
+                    ),
+                    hub_id,
+                ))))
+            }
+            (None, _, _) => Ok(None),
+        }
+    }
+}
+
+/// Name of the vocabulary file of a model type on the model hub
+fn default_vocab_file(model_type: ModelType) -> &'static str {
+    match model_type {
+        ModelType::Bert | ModelType::DistilBert | ModelType::Electra | ModelType::MobileBert => {
+            "vocab.txt"
+        }
+        ModelType::Roberta
+        | ModelType::Bart
+        | ModelType::Longformer
+        | ModelType::Marian
+        | ModelType::GPT2
+        | ModelType::OpenAiGpt
+        | ModelType::GPTNeo
+        | ModelType::Deberta => "vocab.json",
+        ModelType::XLMRoberta | ModelType::MBart => "sentencepiece.bpe.model",
+        ModelType::T5
+        | ModelType::Albert
+        | ModelType::XLNet
+        | ModelType::Reformer
+        | ModelType::Pegasus
+        | ModelType::FNet => "spiece.model",
+        ModelType::ProphetNet => "prophetnet.tokenizer",
+    }
+}
+
+/// Name of the merges file of a model type on the model hub, for the model types requiring one
+fn default_merges_file(model_type: ModelType) -> Option<&'static str> {
+    match model_type {
+        ModelType::Roberta
+        | ModelType::Bart
+        | ModelType::Longformer
+        | ModelType::GPT2
+        | ModelType::OpenAiGpt
+        | ModelType::GPTNeo
+        | ModelType::Deberta => Some("merges.txt"),
+        ModelType::Marian => Some("source.spm"),
+        _ => None,
+    }
+}
+
+/// URLs are downloaded to the cache (in a sub-directory named after the hub identifier or the URL
+/// location), other values are local paths, relative to the base directory.
+fn resolve_resource(value: &str, hub_id: Option<&str>, base_directory: &Path) -> Resource {
+    if value.starts_with("http://") || value.starts_with("https://") {
+        let cache_subdir = match hub_id {
+            Some(hub_id) => hub_id.to_string(),
+            None => {
+                let location = value
+                    .split_once("://")
+                    .map_or(value, |(_, location)| location);
+                location
+                    .rsplit_once('/')
+                    .map_or(location, |(directory, _)| directory)
+                    .replace(':', "_")
+            }
+        };
+        Resource::Remote(RemoteResource::new(value, &cache_subdir))
+    } else {
+        Resource::Local(LocalResource {
+            local_path: base_directory.join(value),
+        })
+    }
+}
+
+fn spec_error<S: Into<String>>(message: S) -> RustBertError {
+    RustBertError::InvalidConfigurationError(message.into())
+}
+
+fn check_positive<T: PartialOrd + Default + std::fmt::Display>(
+    field: &str,
+    value: Option<T>,
+) -> Result<(), RustBertError> {
+    match value {
+        Some(value) if value <= T::default() => Err(spec_error(format!(
+            "`{}` must be strictly positive (got {})",
+            field, value
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(any(
+    feature = "conversation",
+    feature = "summarization",
+    feature = "text-generation",
+    feature = "translation"
+))]
+fn check_non_negative<T: PartialOrd + Default + std::fmt::Display>(
+    field: &str,
+    value: Option<T>,
+) -> Result<(), RustBertError> {
+    match value {
+        Some(value) if value < T::default() => Err(spec_error(format!(
+            "`{}` must not be negative (got {})",
+            field, value
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the sequence classification and sentiment analysis pipelines
+pub struct ClassificationSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Pad the input sequences to a multiple of this value (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of inputs processed in a single forward pass (default: 64)
+    pub batch_size: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Sub-tokens label aggregation method
+pub enum LabelAggregationSpec {
+    First,
+    Last,
+    Mode,
+}
+
+impl Default for LabelAggregationSpec {
+    fn default() -> LabelAggregationSpec {
+        LabelAggregationSpec::First
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the token classification, NER and POS tagging pipelines
+pub struct TokenClassificationSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Pad the input sequences to a multiple of this value (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of inputs processed in a single forward pass (default: 64)
+    pub batch_size: Option<usize>,
+    /// Sub-tokens label aggregation method (default: `First`)
+    pub label_aggregation: LabelAggregationSpec,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the question answering pipeline
+pub struct QuestionAnsweringSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Pad the input sequences to a multiple of this value (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum sequence length for the combined query and context (default: 384)
+    pub max_seq_length: Option<usize>,
+    /// Number of overlapping tokens between sliding windows over long contexts (default: 128)
+    pub doc_stride: Option<usize>,
+    /// Maximum length for the query (default: 64)
+    pub max_query_length: Option<usize>,
+    /// Maximum length for the answer (default: 15)
+    pub max_answer_length: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the zero-shot classification pipeline
+pub struct ZeroShotClassificationSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Pad the input sequences to a multiple of this value (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of candidate labels processed in a single forward pass (default: None)
+    pub label_chunk_size: Option<usize>,
+    /// Maximum number of input and label pairs processed in a single forward pass (default: None)
+    pub batch_size: Option<usize>,
+    /// Truncate each input to the same number of tokens for all candidate labels (default: false)
+    pub equalize_premise: bool,
+    /// Template of the hypothesis with a single `{}` placeholder for the label (default: None)
+    pub hypothesis_template: Option<String>,
+    /// Index of the entailment class in the model logits (default: None, read from the model configuration)
+    pub entailment_id: Option<i64>,
+    /// Index of the contradiction class in the model logits (default: None, read from the model configuration)
+    pub contradiction_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Generation settings
+/// Unset fields keep the default value of the pipeline.
+pub struct GenerationOptionsSpec {
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub do_sample: Option<bool>,
+    pub early_stopping: Option<bool>,
+    pub num_beams: Option<i64>,
+    pub temperature: Option<f64>,
+    pub top_k: Option<i64>,
+    pub top_p: Option<f64>,
+    pub repetition_penalty: Option<f64>,
+    pub length_penalty: Option<f64>,
+    pub no_repeat_ngram_size: Option<i64>,
+    pub num_return_sequences: Option<i64>,
+    pub num_beam_groups: Option<i64>,
+    pub diversity_penalty: Option<f64>,
+    /// Include the prompt in the generated texts (text generation only, the outputs of the
+    /// encoder-decoder pipelines never include their input)
+    pub include_prompt: Option<bool>,
+}
+
+#[cfg(any(
+    feature = "conversation",
+    feature = "summarization",
+    feature = "text-generation",
+    feature = "translation"
+))]
+impl GenerationOptionsSpec {
+    fn validate(&self) -> Result<(), RustBertError> {
+        check_non_negative("generation.min_length", self.min_length)?;
+        check_positive("generation.max_length", self.max_length)?;
+        if let (Some(min_length), Some(max_length)) = (self.min_length, self.max_length) {
+            if min_length > max_length {
+                return Err(spec_error(format!(
+                    "`generation.min_length` must not exceed `generation.max_length` (got {} > {})",
+                    min_length, max_length
+                )));
+            }
+        }
+        check_positive("generation.num_beams", self.num_beams)?;
+        check_positive("generation.temperature", self.temperature)?;
+        check_non_negative("generation.top_k", self.top_k)?;
+        if let Some(top_p) = self.top_p {
+            if !(0f64..=1f64).contains(&top_p) {
+                return Err(spec_error(format!(
+                    "`generation.top_p` must be between 0 and 1 (got {})",
+                    top_p
+                )));
+            }
+        }
+        check_positive("generation.repetition_penalty", self.repetition_penalty)?;
+        check_positive("generation.length_penalty", self.length_penalty)?;
+        check_non_negative("generation.no_repeat_ngram_size", self.no_repeat_ngram_size)?;
+        check_positive("generation.num_return_sequences", self.num_return_sequences)?;
+        check_positive("generation.num_beam_groups", self.num_beam_groups)?;
+        check_non_negative("generation.diversity_penalty", self.diversity_penalty)?;
+        Ok(())
+    }
+}
+
+/// Overrides the generation settings of a pipeline configuration with the values set in a `GenerationOptionsSpec`
+#[cfg(any(
+    feature = "conversation",
+    feature = "summarization",
+    feature = "text-generation",
+    feature = "translation"
+))]
+macro_rules! apply_generation_options {
+    ($options:expr, $config:expr) => {
+        apply_generation_options!(
+            $options,
+            $config,
+            min_length,
+            max_length,
+            do_sample,
+            early_stopping,
+            num_beams,
+            temperature,
+            top_k,
+            top_p,
+            repetition_penalty,
+            length_penalty,
+            no_repeat_ngram_size,
+            num_return_sequences
+        );
+        if $options.num_beam_groups.is_some() {
+            $config.num_beam_groups = $options.num_beam_groups;
+        }
+        if $options.diversity_penalty.is_some() {
+            $config.diversity_penalty = $options.diversity_penalty;
+        }
+    };
+    ($options:expr, $config:expr, $($field:ident),+) => {
+        $(
+            if let Some(value) = $options.$field {
+                $config.$field = value;
+            }
+        )+
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the text generation, summarization and conversation pipelines
+pub struct GenerationSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Generation settings (default: task default settings)
+    pub generation: GenerationOptionsSpec,
+}
+
+#[cfg(feature = "translation")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the translation pipeline
+/// Exactly one of `language` (pretrained model) or `model` (custom Marian or T5 model) must be set.
+pub struct TranslationSpec {
+    /// Language pair of a pretrained model
+    pub language: Option<Language>,
+    /// Custom model to load
+    pub model: ModelSpec,
+    /// Prefix added to the inputs (e.g. `>>fr<<` for multilingual Marian models)
+    pub prefix: Option<String>,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Generation settings (default: translation default settings)
+    pub generation: GenerationOptionsSpec,
+}
+
+#[cfg(feature = "feature-extraction")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// # Spec of the feature extraction pipeline
+pub struct FeatureExtractionSpec {
+    /// Model to load (default: task default model)
+    pub model: ModelSpec,
+    /// Device to place the model on (default: `auto`)
+    pub device: DeviceSpec,
+    /// Layers to extract the features from (default: sum of the last 4 layers)
+    pub layers: Option<Vec<LayerSelection>>,
+    /// Average the features of the sub-tokens of each word (default: false)
+    pub word_pooling: bool,
+    /// Return the features as a single tensor (default: false)
+    pub output_tensor: bool,
+    /// Maximum sequence length for the inputs (default: 128)
+    pub max_length: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "task", rename_all = "snake_case")]
+/// # Spec of a pipeline, tagged by its task
+pub enum PipelineSpec {
+    #[cfg(feature = "sequence-classification")]
+    SequenceClassification(ClassificationSpec),
+    #[cfg(feature = "sentiment")]
+    Sentiment(ClassificationSpec),
+    #[cfg(feature = "token-classification")]
+    TokenClassification(TokenClassificationSpec),
+    #[cfg(feature = "ner")]
+    Ner(TokenClassificationSpec),
+    #[cfg(feature = "pos-tagging")]
+    PosTagging(TokenClassificationSpec),
+    #[cfg(feature = "question-answering")]
+    QuestionAnswering(QuestionAnsweringSpec),
+    #[cfg(feature = "zero-shot")]
+    ZeroShotClassification(ZeroShotClassificationSpec),
+    #[cfg(feature = "text-generation")]
+    TextGeneration(GenerationSpec),
+    #[cfg(feature = "summarization")]
+    Summarization(GenerationSpec),
+    #[cfg(feature = "translation")]
+    Translation(TranslationSpec),
+    #[cfg(feature = "conversation")]
+    Conversation(GenerationSpec),
+    #[cfg(feature = "feature-extraction")]
+    FeatureExtraction(FeatureExtractionSpec),
+}
+
+impl PipelineSpec {
+    /// Reads and validates a pipeline spec from a JSON (`.json`) or YAML (`.yaml`, `.yml`) file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<PipelineSpec, RustBertError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let spec: PipelineSpec = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|error| {
+                spec_error(format!("Invalid spec {}: {}", path.display(), error))
+            })?,
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents).map_err(|error| {
+                spec_error(format!("Invalid spec {}: {}", path.display(), error))
+            })?,
+            _ => {
+                return Err(spec_error(format!(
+                    "Unsupported spec file {}, expected a .json, .yaml or .yml file",
+                    path.display()
+                )))
+            }
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Returns the task of the pipeline
+    pub fn task(&self) -> TaskType {
+        match self {
+            #[cfg(feature = "sequence-classification")]
+            PipelineSpec::SequenceClassification(_) => TaskType::SequenceClassification,
+            #[cfg(feature = "sentiment")]
+            PipelineSpec::Sentiment(_) => TaskType::Sentiment,
+            #[cfg(feature = "token-classification")]
+            PipelineSpec::TokenClassification(_) => TaskType::TokenClassification,
+            #[cfg(feature = "ner")]
+            PipelineSpec::Ner(_) => TaskType::Ner,
+            #[cfg(feature = "pos-tagging")]
+            PipelineSpec::PosTagging(_) => TaskType::PosTagging,
+            #[cfg(feature = "question-answering")]
+            PipelineSpec::QuestionAnswering(_) => TaskType::QuestionAnswering,
+            #[cfg(feature = "zero-shot")]
+            PipelineSpec::ZeroShotClassification(_) => TaskType::ZeroShotClassification,
+            #[cfg(feature = "text-generation")]
+            PipelineSpec::TextGeneration(_) => TaskType::TextGeneration,
+            #[cfg(feature = "summarization")]
+            PipelineSpec::Summarization(_) => TaskType::Summarization,
+            #[cfg(feature = "translation")]
+            PipelineSpec::Translation(_) => TaskType::Translation,
+            #[cfg(feature = "conversation")]
+            PipelineSpec::Conversation(_) => TaskType::Conversation,
+            #[cfg(feature = "feature-extraction")]
+            PipelineSpec::FeatureExtraction(_) => TaskType::FeatureExtraction,
+        }
+    }
+
+    /// Checks the spec without loading any resource
+    ///
+    /// # Returns
+    ///
+    /// * `RustBertError::InvalidConfigurationError` naming the offending field if the spec is invalid
+    pub fn validate(&self) -> Result<(), RustBertError> {
+        let no_base_directory = Path::new("");
+        match self {
+            #[cfg(feature = "sequence-classification")]
+            PipelineSpec::SequenceClassification(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("batch_size", spec.batch_size)
+            }
+            #[cfg(feature = "sentiment")]
+            PipelineSpec::Sentiment(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("batch_size", spec.batch_size)
+            }
+            #[cfg(feature = "token-classification")]
+            PipelineSpec::TokenClassification(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("batch_size", spec.batch_size)
+            }
+            #[cfg(feature = "ner")]
+            PipelineSpec::Ner(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("batch_size", spec.batch_size)
+            }
+            #[cfg(feature = "pos-tagging")]
+            PipelineSpec::PosTagging(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("batch_size", spec.batch_size)
+            }
+            #[cfg(feature = "question-answering")]
+            PipelineSpec::QuestionAnswering(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("max_seq_length", spec.max_seq_length)?;
+                check_positive("doc_stride", spec.doc_stride)?;
+                check_positive("max_query_length", spec.max_query_length)?;
+                check_positive("max_answer_length", spec.max_answer_length)?;
+                let defaults = QuestionAnsweringConfig::default();
+                let max_seq_length = spec.max_seq_length.unwrap_or(defaults.max_seq_length);
+                let max_query_length = spec.max_query_length.unwrap_or(defaults.max_query_length);
+                let doc_stride = spec.doc_stride.unwrap_or(defaults.doc_stride);
+                if doc_stride >= max_seq_length.saturating_sub(max_query_length) {
+                    return Err(spec_error(format!(
+                        "`doc_stride` must be lower than `max_seq_length` minus `max_query_length` (got {} >= {} - {})",
+                        doc_stride, max_seq_length, max_query_length
+                    )));
+                }
+                Ok(())
+            }
+            #[cfg(feature = "zero-shot")]
+            PipelineSpec::ZeroShotClassification(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                check_positive("pad_to_multiple_of", spec.pad_to_multiple_of)?;
+                check_positive("label_chunk_size", spec.label_chunk_size)?;
+                check_positive("batch_size", spec.batch_size)?;
+                match &spec.hypothesis_template {
+                    Some(hypothesis_template) => validate_hypothesis_template(hypothesis_template),
+                    None => Ok(()),
+                }
+            }
+            #[cfg(feature = "text-generation")]
+            PipelineSpec::TextGeneration(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                spec.generation.validate()
+            }
+            #[cfg(feature = "summarization")]
+            PipelineSpec::Summarization(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                spec.generation.validate()
+            }
+            #[cfg(feature = "translation")]
+            PipelineSpec::Translation(spec) => {
+                match (
+                    &spec.language,
+                    spec.model.resolve(no_base_directory)?,
+                ) {
+                    (Some(_), Some(_)) => {
+                        return Err(spec_error(
+                            "`language` and `model` cannot both be set, `language` selects a pretrained model",
+                        ))
+                    }
+                    (None, None) => {
+                        return Err(spec_error(
+                            "`language` or `model` must be set for translation",
+                        ))
+                    }
+                    (None, Some(model)) => {
+                        if !matches!(model.model_type, ModelType::Marian | ModelType::T5) {
+                            return Err(spec_error(format!(
+                                "`model.model_type` must be Marian or T5 for translation (got {:?})",
+                                model.model_type
+                            )));
+                        }
+                    }
+                    (Some(_), None) => {}
+                }
+                spec.generation.validate()
+            }
+            #[cfg(feature = "conversation")]
+            PipelineSpec::Conversation(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                spec.generation.validate()
+            }
+            #[cfg(feature = "feature-extraction")]
+            PipelineSpec::FeatureExtraction(spec) => {
+                spec.model.resolve(no_base_directory)?;
+                if matches!(&spec.layers, Some(layers) if layers.is_empty()) {
+                    return Err(spec_error("`layers` must not be empty"));
+                }
+                check_positive("max_length", spec.max_length)
+            }
+        }
+    }
+
+    /// Builds the pipeline described by the spec
+    ///
+    /// # Arguments
+    ///
+    /// * `base_directory` - Directory the relative paths of the spec are resolved against
+    ///
+    /// # Returns
+    ///
+    /// * `PipelineHandle` holding the pipeline built
+    pub fn build<P: AsRef<Path>>(
+        &self,
+        base_directory: P,
+    ) -> Result<PipelineHandle, RustBertError> {
+        self.validate()?;
+        let base_directory = base_directory.as_ref();
+        Ok(match self {
+            #[cfg(feature = "sequence-classification")]
+            PipelineSpec::SequenceClassification(spec) => {
+                PipelineHandle::SequenceClassification(SequenceClassificationModel::new(
+                    spec.sequence_classification_config(base_directory)?,
+                )?)
+            }
+            #[cfg(feature = "sentiment")]
+            PipelineSpec::Sentiment(spec) => PipelineHandle::Sentiment(SentimentModel::new(
+                spec.sequence_classification_config(base_directory)?,
+            )?),
+            #[cfg(feature = "token-classification")]
+            PipelineSpec::TokenClassification(spec) => {
+                PipelineHandle::TokenClassification(TokenClassificationModel::new(
+                    spec.token_classification_config(base_directory, Default::default())?,
+                )?)
+            }
+            #[cfg(feature = "ner")]
+            PipelineSpec::Ner(spec) => PipelineHandle::Ner(NERModel::new(
+                spec.token_classification_config(base_directory, Default::default())?,
+            )?),
+            #[cfg(feature = "pos-tagging")]
+            PipelineSpec::PosTagging(spec) => {
+                PipelineHandle::PosTagging(POSModel::new(POSConfig::from(
+                    spec.token_classification_config(base_directory, POSConfig::default().into())?,
+                ))?)
+            }
+            #[cfg(feature = "question-answering")]
+            PipelineSpec::QuestionAnswering(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => QuestionAnsweringConfig::new(
+                        model.model_type,
+                        model.model_resource,
+                        model.config_resource,
+                        model.vocab_resource,
+                        model.merges_resource,
+                        model.lower_case,
+                        model.strip_accents,
+                        model.add_prefix_space,
+                    ),
+                    None => QuestionAnsweringConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                config.pad_to_multiple_of = spec.pad_to_multiple_of;
+                config.max_seq_length = spec.max_seq_length.unwrap_or(config.max_seq_length);
+                config.doc_stride = spec.doc_stride.unwrap_or(config.doc_stride);
+                config.max_query_length = spec.max_query_length.unwrap_or(config.max_query_length);
+                config.max_answer_length =
+                    spec.max_answer_length.unwrap_or(config.max_answer_length);
+                PipelineHandle::QuestionAnswering(QuestionAnsweringModel::new(config)?)
+            }
+            #[cfg(feature = "zero-shot")]
+            PipelineSpec::ZeroShotClassification(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => ZeroShotClassificationConfig::new(
+                        model.model_type,
+                        model.model_resource,
+                        model.config_resource,
+                        model.vocab_resource,
+                        model.merges_resource,
+                        model.lower_case,
+                        model.strip_accents,
+                        model.add_prefix_space,
+                    ),
+                    None => ZeroShotClassificationConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                config.pad_to_multiple_of = spec.pad_to_multiple_of;
+                config.label_chunk_size = spec.label_chunk_size;
+                config.batch_size = spec.batch_size;
+                config.equalize_premise = spec.equalize_premise;
+                config.hypothesis_template = spec.hypothesis_template.clone();
+                config.entailment_id = spec.entailment_id;
+                config.contradiction_id = spec.contradiction_id;
+                PipelineHandle::ZeroShotClassification(ZeroShotClassificationModel::new(config)?)
+            }
+            #[cfg(feature = "text-generation")]
+            PipelineSpec::TextGeneration(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => TextGenerationConfig::new(
+                        model.model_type,
+                        model.model_resource.clone(),
+                        model.config_resource.clone(),
+                        model.vocab_resource.clone(),
+                        model.merges_or_vocab(),
+                    ),
+                    None => TextGenerationConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                apply_generation_options!(spec.generation, config);
+                apply_generation_options!(spec.generation, config, include_prompt);
+                PipelineHandle::TextGeneration(TextGenerationModel::new(config)?)
+            }
+            #[cfg(feature = "summarization")]
+            PipelineSpec::Summarization(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => SummarizationConfig::new(
+                        model.model_type,
+                        model.model_resource.clone(),
+                        model.config_resource.clone(),
+                        model.vocab_resource.clone(),
+                        model.merges_or_vocab(),
+                    ),
+                    None => SummarizationConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                apply_generation_options!(spec.generation, config);
+                PipelineHandle::Summarization(SummarizationModel::new(config)?)
+            }
+            #[cfg(feature = "translation")]
+            PipelineSpec::Translation(spec) => {
+                let device = spec.device.to_device()?;
+                let mut config = match (spec.language, spec.model.resolve(base_directory)?) {
+                    (_, Some(model)) => TranslationConfig::new_from_resources(
+                        model.model_resource.clone(),
+                        model.config_resource.clone(),
+                        model.vocab_resource.clone(),
+                        model.merges_or_vocab(),
+                        None,
+                        device,
+                        model.model_type,
+                    ),
+                    (Some(language), None) => TranslationConfig::new(language, device),
+                    (None, None) => unreachable!("validated spec"),
+                };
+                if spec.prefix.is_some() {
+                    config.prefix = spec.prefix.clone();
+                }
+                apply_generation_options!(spec.generation, config);
+                PipelineHandle::Translation(TranslationModel::new(config)?)
+            }
+            #[cfg(feature = "conversation")]
+            PipelineSpec::Conversation(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => ConversationConfig {
+                        model_type: model.model_type,
+                        model_resource: model.model_resource.clone(),
+                        config_resource: model.config_resource.clone(),
+                        vocab_resource: model.vocab_resource.clone(),
+                        merges_resource: model.merges_or_vocab(),
+                        ..Default::default()
+                    },
+                    None => ConversationConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                apply_generation_options!(spec.generation, config);
+                PipelineHandle::Conversation(ConversationModel::new(config)?)
+            }
+            #[cfg(feature = "feature-extraction")]
+            PipelineSpec::FeatureExtraction(spec) => {
+                let mut config = match spec.model.resolve(base_directory)? {
+                    Some(model) => FeatureExtractionConfig::new(
+                        model.model_type,
+                        model.model_resource,
+                        model.config_resource,
+                        model.vocab_resource,
+                        model.merges_resource,
+                        model.lower_case,
+                        model.strip_accents,
+                        model.add_prefix_space,
+                        vec![LayerSelection::SumLastFour],
+                    ),
+                    None => FeatureExtractionConfig::default(),
+                };
+                config.device = spec.device.to_device()?;
+                if let Some(layers) = &spec.layers {
+                    config.layers = layers.clone();
+                }
+                config.word_pooling = spec.word_pooling;
+                config.output_tensor = spec.output_tensor;
+                config.max_length = spec.max_length.unwrap_or(config.max_length);
+                PipelineHandle::FeatureExtraction(FeatureExtractionModel::new(config)?)
+            }
+        })
+    }
+}
+
+#[cfg(feature = "sequence-classification")]
+impl ClassificationSpec {
+    fn sequence_classification_config(
+        &self,
+        base_directory: &Path,
+    ) -> Result<SequenceClassificationConfig, RustBertError> {
+        let mut config = match self.model.resolve(base_directory)? {
+            Some(model) => SequenceClassificationConfig::new(
+                model.model_type,
+                model.model_resource,
+                model.config_resource,
+                model.vocab_resource,
+                model.merges_resource,
+                model.lower_case,
+                model.strip_accents,
+                model.add_prefix_space,
+            ),
+            None => SequenceClassificationConfig::default(),
+        };
+        config.device = self.device.to_device()?;
+        config.pad_to_multiple_of = self.pad_to_multiple_of;
+        config.batch_size = self.batch_size.unwrap_or(config.batch_size);
+        Ok(config)
+    }
+}
+
+#[cfg(feature = "token-classification")]
+impl TokenClassificationSpec {
+    fn token_classification_config(
+        &self,
+        base_directory: &Path,
+        default_config: TokenClassificationConfig,
+    ) -> Result<TokenClassificationConfig, RustBertError> {
+        let label_aggregation_function = match self.label_aggregation {
+            LabelAggregationSpec::First => LabelAggregationOption::First,
+            LabelAggregationSpec::Last => LabelAggregationOption::Last,
+            LabelAggregationSpec::Mode => LabelAggregationOption::Mode,
+        };
+        let mut config = match self.model.resolve(base_directory)? {
+            Some(model) => TokenClassificationConfig::new(
+                model.model_type,
+                model.model_resource,
+                model.config_resource,
+                model.vocab_resource,
+                model.merges_resource,
+                model.lower_case,
+                model.strip_accents,
+                model.add_prefix_space,
+                label_aggregation_function,
+            ),
+            None => TokenClassificationConfig {
+                label_aggregation_function,
+                ..default_config
+            },
+        };
+        config.device = self.device.to_device()?;
+        config.pad_to_multiple_of = self.pad_to_multiple_of;
+        config.batch_size = self.batch_size.unwrap_or(config.batch_size);
+        Ok(config)
+    }
+}
+
+/// # Pipeline built from a spec
+pub enum PipelineHandle {
+    #[cfg(feature = "sequence-classification")]
+    SequenceClassification(SequenceClassificationModel),
+    #[cfg(feature = "sentiment")]
+    Sentiment(SentimentModel),
+    #[cfg(feature = "token-classification")]
+    TokenClassification(TokenClassificationModel),
+    #[cfg(feature = "ner")]
+    Ner(NERModel),
+    #[cfg(feature = "pos-tagging")]
+    PosTagging(POSModel),
+    #[cfg(feature = "question-answering")]
+    QuestionAnswering(QuestionAnsweringModel),
+    #[cfg(feature = "zero-shot")]
+    ZeroShotClassification(ZeroShotClassificationModel),
+    #[cfg(feature = "text-generation")]
+    TextGeneration(TextGenerationModel),
+    #[cfg(feature = "summarization")]
+    Summarization(SummarizationModel),
+    #[cfg(feature = "translation")]
+    Translation(TranslationModel),
+    #[cfg(feature = "conversation")]
+    Conversation(ConversationModel),
+    #[cfg(feature = "feature-extraction")]
+    FeatureExtraction(FeatureExtractionModel),
+}
+
+impl PipelineHandle {
+    /// Returns the task of the pipeline
+    pub fn task(&self) -> TaskType {
+        match self {
+            #[cfg(feature = "sequence-classification")]
+            PipelineHandle::SequenceClassification(_) => TaskType::SequenceClassification,
+            #[cfg(feature = "sentiment")]
+            PipelineHandle::Sentiment(_) => TaskType::Sentiment,
+            #[cfg(feature = "token-classification")]
+            PipelineHandle::TokenClassification(_) => TaskType::TokenClassification,
+            #[cfg(feature = "ner")]
+            PipelineHandle::Ner(_) => TaskType::Ner,
+            #[cfg(feature = "pos-tagging")]
+            PipelineHandle::PosTagging(_) => TaskType::PosTagging,
+            #[cfg(feature = "question-answering")]
+            PipelineHandle::QuestionAnswering(_) => TaskType::QuestionAnswering,
+            #[cfg(feature = "zero-shot")]
+            PipelineHandle::ZeroShotClassification(_) => TaskType::ZeroShotClassification,
+            #[cfg(feature = "text-generation")]
+            PipelineHandle::TextGeneration(_) => TaskType::TextGeneration,
+            #[cfg(feature = "summarization")]
+            PipelineHandle::Summarization(_) => TaskType::Summarization,
+            #[cfg(feature = "translation")]
+            PipelineHandle::Translation(_) => TaskType::Translation,
+            #[cfg(feature = "conversation")]
+            PipelineHandle::Conversation(_) => TaskType::Conversation,
+            #[cfg(feature = "feature-extraction")]
+            PipelineHandle::FeatureExtraction(_) => TaskType::FeatureExtraction,
+        }
+    }
+}
+
+/// Builds a pipeline from a JSON (`.json`) or YAML (`.yaml`, `.yml`) spec file. Relative paths
+/// of the spec are resolved against the directory of the spec file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the spec file
+///
+/// # Returns
+///
+/// * `PipelineHandle` holding the pipeline built
+/// * `RustBertError::InvalidConfigurationError` naming the offending field if the spec is invalid
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::spec::{from_spec, TaskType};
+///
+/// let pipeline = from_spec("path/to/ner.json")?;
+/// assert_eq!(pipeline.task(), TaskType::Ner);
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_spec<P: AsRef<Path>>(path: P) -> Result<PipelineHandle, RustBertError> {
+    let path = path.as_ref();
+    let spec = PipelineSpec::from_file(path)?;
+    spec.build(path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_round_trip(spec: PipelineSpec, task: TaskType) {
+        assert_eq!(spec.task(), task);
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(serde_json::from_str::<PipelineSpec>(&json).unwrap(), spec);
+        let yaml = serde_yaml::to_string(&spec).unwrap();
+        assert_eq!(serde_yaml::from_str::<PipelineSpec>(&yaml).unwrap(), spec);
+    }
+
+    #[cfg(any(
+        feature = "sentiment",
+        all(feature = "ner", feature = "pos-tagging"),
+        feature = "feature-extraction"
+    ))]
+    fn custom_bert_model() -> ModelSpec {
+        ModelSpec {
+            model_type: Some(ModelType::Bert),
+            weights: Some("model/rust_model.ot".to_string()),
+            config: Some("model/config.json".to_string()),
+            vocab: Some("model/vocab.txt".to_string()),
+            lower_case: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(any(
+        feature = "question-answering",
+        feature = "sentiment",
+        feature = "text-generation",
+        feature = "translation"
+    ))]
+    fn validation_error(spec: &str) -> String {
+        let spec: PipelineSpec = serde_json::from_str(spec).unwrap();
+        match spec.validate() {
+            Err(RustBertError::InvalidConfigurationError(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other.is_ok()),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sentiment")]
+    fn test_classification_round_trip() {
+        let spec = ClassificationSpec {
+            model: custom_bert_model(),
+            device: DeviceSpec::Cpu,
+            pad_to_multiple_of: Some(8),
+            batch_size: Some(16),
+        };
+        assert_round_trip(
+            PipelineSpec::SequenceClassification(spec.clone()),
+            TaskType::SequenceClassification,
+        );
+        assert_round_trip(PipelineSpec::Sentiment(spec), TaskType::Sentiment);
+    }
+
+    #[test]
+    #[cfg(all(feature = "ner", feature = "pos-tagging"))]
+    fn test_token_classification_round_trip() {
+        let spec = TokenClassificationSpec {
+            model: custom_bert_model(),
+            device: DeviceSpec::Cuda(1),
+            pad_to_multiple_of: None,
+            batch_size: Some(16),
+            label_aggregation: LabelAggregationSpec::Mode,
+        };
+        assert_round_trip(
+            PipelineSpec::TokenClassification(spec.clone()),
+            TaskType::TokenClassification,
+        );
+        assert_round_trip(PipelineSpec::Ner(spec.clone()), TaskType::Ner);
+        assert_round_trip(PipelineSpec::PosTagging(spec), TaskType::PosTagging);
+    }
+
+    #[test]
+    #[cfg(feature = "question-answering")]
+    fn test_question_answering_round_trip() {
+        let spec = QuestionAnsweringSpec {
+            model: ModelSpec {
+                model_type: Some(ModelType::Roberta),
+                hub_id: Some("deepset/roberta-base-squad2".to_string()),
+                add_prefix_space: Some(true),
+                ..Default::default()
+            },
+            max_seq_length: Some(256),
+            doc_stride: Some(64),
+            ..Default::default()
+        };
+        assert_round_trip(
+            PipelineSpec::QuestionAnswering(spec),
+            TaskType::QuestionAnswering,
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "zero-shot")]
+    fn test_zero_shot_classification_round_trip() {
+        let spec = ZeroShotClassificationSpec {
+            label_chunk_size: Some(4),
+            equalize_premise: true,
+            hypothesis_template: Some("This review is about {}.".to_string()),
+            entailment_id: Some(0),
+            contradiction_id: Some(2),
+            ..Default::default()
+        };
+        assert_round_trip(
+            PipelineSpec::ZeroShotClassification(spec),
+            TaskType::ZeroShotClassification,
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "conversation",
+        feature = "summarization",
+        feature = "text-generation"
+    ))]
+    fn test_generation_round_trip() {
+        let spec = GenerationSpec {
+            model: ModelSpec {
+                model_type: Some(ModelType::GPT2),
+                hub_id: Some("distilgpt2".to_string()),
+                ..Default::default()
+            },
+            device: DeviceSpec::Auto,
+            generation: GenerationOptionsSpec {
+                max_length: Some(32),
+                do_sample: Some(false),
+                top_p: Some(0.95),
+                num_beam_groups: Some(2),
+                ..Default::default()
+            },
+        };
+        assert_round_trip(
+            PipelineSpec::TextGeneration(spec.clone()),
+            TaskType::TextGeneration,
+        );
+        assert_round_trip(
+            PipelineSpec::Summarization(spec.clone()),
+            TaskType::Summarization,
+        );
+        assert_round_trip(PipelineSpec::Conversation(spec), TaskType::Conversation);
+    }
+
+    #[test]
+    #[cfg(feature = "translation")]
+    fn test_translation_round_trip() {
+        let spec = TranslationSpec {
+            language: Some(Language::EnglishToGerman),
+            prefix: Some(">>de<<".to_string()),
+            generation: GenerationOptionsSpec {
+                num_beams: Some(4),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_round_trip(PipelineSpec::Translation(spec), TaskType::Translation);
+    }
+
+    #[test]
+    #[cfg(feature = "feature-extraction")]
+    fn test_feature_extraction_round_trip() {
+        let spec = FeatureExtractionSpec {
+            model: custom_bert_model(),
+            layers: Some(vec![LayerSelection::Layer(2), LayerSelection::SumLastFour]),
+            word_pooling: true,
+            ..Default::default()
+        };
+        assert_round_trip(
+            PipelineSpec::FeatureExtraction(spec),
+            TaskType::FeatureExtraction,
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "text-generation")]
+    fn test_yaml_spec() {
+        let spec: PipelineSpec = serde_yaml::from_str(
+            r#"
+            task: text_generation
+            model:
+              model_type: GPT2
+              hub_id: gpt2
+            device: cuda:0
+            generation:
+              max_length: 64
+              top_p: 0.95
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            spec,
+            PipelineSpec::TextGeneration(GenerationSpec {
+                model: ModelSpec {
+                    model_type: Some(ModelType::GPT2),
+                    hub_id: Some("gpt2".to_string()),
+                    ..Default::default()
+                },
+                device: DeviceSpec::Cuda(0),
+                generation: GenerationOptionsSpec {
+                    max_length: Some(64),
+                    top_p: Some(0.95),
+                    ..Default::default()
+                },
+            })
+        );
+        assert!(spec.validate().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "text-generation")]
+    fn test_parsing_errors() {
+        let unknown_field = serde_json::from_str::<PipelineSpec>(
+            r#"{"task": "text_generation", "generation": {"top_q": 0.5}}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(unknown_field.contains("top_q"));
+
+        let invalid_device =
+            serde_json::from_str::<PipelineSpec>(r#"{"task": "text_generation", "device": "gpu"}"#)
+                .unwrap_err()
+                .to_string();
+        assert!(invalid_device.contains("invalid device `gpu`"));
+
+        assert!(serde_json::from_str::<PipelineSpec>(r#"{"task": "dancing"}"#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "text-generation")]
+    fn test_generation_validation() {
+        assert_eq!(
+            validation_error(r#"{"task": "text_generation", "generation": {"top_p": 1.5}}"#),
+            "`generation.top_p` must be between 0 and 1 (got 1.5)"
+        );
+        assert_eq!(
+            validation_error(
+                r#"{"task": "text_generation", "generation": {"min_length": 30, "max_length": 20}}"#
+            ),
+            "`generation.min_length` must not exceed `generation.max_length` (got 30 > 20)"
+        );
+        assert_eq!(
+            validation_error(r#"{"task": "text_generation", "generation": {"num_beams": 0}}"#),
+            "`generation.num_beams` must be strictly positive (got 0)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sentiment")]
+    fn test_model_validation() {
+        assert_eq!(
+            validation_error(r#"{"task": "sentiment", "model": {"vocab": "vocab.txt"}}"#),
+            "`model.model_type` must be set to load a custom model"
+        );
+        assert_eq!(
+            validation_error(
+                r#"{"task": "sentiment", "model": {"model_type": "DistilBert", "weights": "model.ot", "vocab": "vocab.txt"}}"#
+            ),
+            "`model.config` must be set when `model.hub_id` is not"
+        );
+        assert_eq!(
+            validation_error(
+                r#"{"task": "sentiment", "model": {"model_type": "DistilBert", "hub_id": "distilbert-base-cased", "vocab": ""}}"#
+            ),
+            "`model.vocab` must not be empty"
+        );
+        assert_eq!(
+            validation_error(r#"{"task": "sentiment", "pad_to_multiple_of": 0}"#),
+            "`pad_to_multiple_of` must be strictly positive (got 0)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "question-answering")]
+    fn test_question_answering_validation() {
+        assert_eq!(
+            validation_error(
+                r#"{"task": "question_answering", "max_seq_length": 128, "max_query_length": 64, "doc_stride": 64}"#
+            ),
+            "`doc_stride` must be lower than `max_seq_length` minus `max_query_length` (got 64 >= 128 - 64)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "translation")]
+    fn test_translation_validation() {
+        assert_eq!(
+            validation_error(r#"{"task": "translation"}"#),
+            "`language` or `model` must be set for translation"
+        );
+        assert_eq!(
+            validation_error(
+                r#"{"task": "translation", "model": {"model_type": "Bart", "hub_id": "facebook/bart-large"}}"#
+            ),
+            "`model.model_type` must be Marian or T5 for translation (got Bart)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "roberta")]
+    fn test_resource_resolution() -> anyhow::Result<()> {
+        let model = ModelSpec {
+            model_type: Some(ModelType::Roberta),
+            hub_id: Some("roberta-base".to_string()),
+            weights: Some("weights/model.ot".to_string()),
+            ..Default::default()
+        };
+        let resolved = model.resolve(Path::new("/specs"))?.unwrap();
+        assert!(
+            resolved.model_resource
+                == Resource::Local(LocalResource {
+                    local_path: Path::new("/specs/weights/model.ot").to_path_buf()
+                })
+        );
+        assert!(
+            resolved.merges_resource
+                == Some(Resource::Remote(RemoteResource::new(
+                    "https://huggingface.co/roberta-base/resolve/main/merges.txt",
+                    "roberta-base"
+                )))
+        );
+
+        let model = ModelSpec {
+            model_type: Some(ModelType::Roberta),
+            weights: Some("https://example.com/models/roberta/rust_model.ot".to_string()),
+            config: Some("/models/roberta/config.json".to_string()),
+            vocab: Some("vocab.json".to_string()),
+            ..Default::default()
+        };
+        let error = model.resolve(Path::new("")).err().unwrap().to_string();
+        assert!(error.contains("`model.merges` must be set for Roberta models"));
+
+        let model = ModelSpec {
+            merges: Some("merges.txt".to_string()),
+            ..model
+        };
+        let resolved = model.resolve(Path::new("/specs"))?.unwrap();
+        assert!(
+            resolved.model_resource
+                == Resource::Remote(RemoteResource::new(
+                    "https://example.com/models/roberta/rust_model.ot",
+                    "example.com/models/roberta"
+                ))
+        );
+        assert!(
+            resolved.config_resource
+                == Resource::Local(LocalResource {
+                    local_path: Path::new("/models/roberta/config.json").to_path_buf()
+                })
+        );
+        assert!(ModelSpec::default().resolve(Path::new(""))?.is_none());
+        Ok(())
+    }
+}
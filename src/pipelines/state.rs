@@ -0,0 +1,515 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Persisted pipeline state
+//! Building a pipeline parses the tokenizer vocabulary (and merges for BPE tokenizers) and the
+//! model configuration from their source files, which dominates the cold start time of pipelines
+//! with large vocabularies. `save_state` writes the compiled tokenizer, the model configuration and
+//! the pipeline options to a single binary snapshot, restored by `load_from_state` without parsing
+//! the source files again. Only the weights are read from the model resource.
+//!
+//! Snapshots record a format version and a checksum of each source file. Snapshots written with a
+//! different format version are rejected, as well as snapshots whose source files still exist but
+//! were modified after the snapshot was written. Removed source files (e.g. after cleaning the
+//! download cache) do not invalidate a snapshot. Saving a pipeline reads its source files, which
+//! must therefore still be available.
+//!
+//! Snapshots are available for WordPiece (BERT, DistilBERT, Electra, MobileBERT) and byte-level BPE
+//! (RoBERTa, BART, Longformer, GPT2, GPT-Neo) tokenizers.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::bart::BartModelResources;
+//! use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+//! use rust_bert::resources::{RemoteResource, Resource};
+//! use tch::Device;
+//!
+//! let model = ZeroShotClassificationModel::new(Default::default())?;
+//! model.save_state("zero_shot.state")?;
+//!
+//! let model = ZeroShotClassificationModel::load_from_state(
+//!     "zero_shot.state",
+//!     Resource::Remote(RemoteResource::from_pretrained(
+//!         BartModelResources::BART_MNLI,
+//!     )),
+//!     Device::cuda_if_available(),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use rust_tokenizers::tokenizer::{BertTokenizer, Gpt2Tokenizer, RobertaTokenizer, Tokenizer};
+use rust_tokenizers::vocab::{BertVocab, BpePairVocab, Gpt2Vocab, RobertaVocab, Vocab};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the snapshot format, incremented on any change of the serialized layout
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Source files and tokenizer options a pipeline was built from
+pub(crate) struct PipelineSources {
+    pub model_type: ModelType,
+    pub config_path: PathBuf,
+    pub vocab_path: PathBuf,
+    pub merges_path: Option<PathBuf>,
+    pub lower_case: bool,
+    pub strip_accents: Option<bool>,
+    pub add_prefix_space: Option<bool>,
+}
+
+impl PipelineSources {
+    fn paths(&self) -> Vec<&Path> {
+        let mut paths = vec![self.config_path.as_path(), self.vocab_path.as_path()];
+        if let Some(merges_path) = &self.merges_path {
+            paths.push(merges_path.as_path());
+        }
+        paths
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StateHeader {
+    format_version: u32,
+    pipeline: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SourceChecksum {
+    path: PathBuf,
+    checksum: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Compiled tokenizer vocabularies. Decoding maps are rebuilt from the encoding maps on restore.
+enum TokenizerState {
+    WordPiece {
+        values: HashMap<String, i64>,
+        special_values: HashMap<String, i64>,
+    },
+    RobertaBpe {
+        values: HashMap<String, i64>,
+        special_values: HashMap<String, i64>,
+        merges: HashMap<(String, String), i64>,
+    },
+    Gpt2Bpe {
+        values: HashMap<String, i64>,
+        special_values: HashMap<String, i64>,
+        merges: HashMap<(String, String), i64>,
+    },
+}
+
+impl TokenizerState {
+    fn from_tokenizer(
+        tokenizer: &TokenizerOption,
+        merges_path: Option<&Path>,
+    ) -> Result<TokenizerState, RustBertError> {
+        Ok(match tokenizer {
+            TokenizerOption::Bert(tokenizer) => TokenizerState::WordPiece {
+                values: tokenizer.vocab().values.clone(),
+                special_values: tokenizer.vocab().special_values.clone(),
+            },
+            TokenizerOption::Roberta(tokenizer) => TokenizerState::RobertaBpe {
+                values: tokenizer.vocab().values.clone(),
+                special_values: tokenizer.vocab().special_values.clone(),
+                merges: read_merges(merges_path)?,
+            },
+            TokenizerOption::GPT2(tokenizer) => TokenizerState::Gpt2Bpe {
+                values: tokenizer.vocab().values.clone(),
+                special_values: tokenizer.vocab().special_values.clone(),
+                merges: read_merges(merges_path)?,
+            },
+            _ => {
+                return Err(RustBertError::InvalidStateError(
+                    "State snapshots are only available for WordPiece and byte-level BPE tokenizers"
+                        .to_string(),
+                ));
+            }
+        })
+    }
+
+    fn into_tokenizer(self, sources: &PipelineSources) -> TokenizerOption {
+        match self {
+            TokenizerState::WordPiece {
+                values,
+                special_values,
+            } => {
+                let vocab = BertVocab {
+                    indices: swap_key_values(&values),
+                    special_indices: swap_key_values(&special_values),
+                    values,
+                    special_values,
+                    unknown_value: BertVocab::unknown_value(),
+                };
+                TokenizerOption::Bert(BertTokenizer::from_existing_vocab(
+                    vocab,
+                    sources.lower_case,
+                    sources.strip_accents.unwrap_or(sources.lower_case),
+                ))
+            }
+            TokenizerState::RobertaBpe {
+                values,
+                special_values,
+                merges,
+            } => {
+                let vocab = RobertaVocab {
+                    indices: swap_key_values(&values),
+                    special_indices: swap_key_values(&special_values),
+                    values,
+                    special_values,
+                    unknown_value: RobertaVocab::unknown_value(),
+                };
+                TokenizerOption::Roberta(RobertaTokenizer::from_existing_vocab_and_merges(
+                    vocab,
+                    BpePairVocab { values: merges },
+                    sources.lower_case,
+                    sources.add_prefix_space.unwrap_or(false),
+                ))
+            }
+            TokenizerState::Gpt2Bpe {
+                values,
+                special_values,
+                merges,
+            } => {
+                let vocab = Gpt2Vocab {
+                    indices: swap_key_values(&values),
+                    special_indices: swap_key_values(&special_values),
+                    values,
+                    special_values,
+                    unknown_value: Gpt2Vocab::unknown_value(),
+                };
+                TokenizerOption::GPT2(Gpt2Tokenizer::from_existing_vocab_and_merges(
+                    vocab,
+                    BpePairVocab { values: merges },
+                    sources.lower_case,
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PipelineState<O> {
+    sources: PipelineSources,
+    checksums: Vec<SourceChecksum>,
+    tokenizer: TokenizerState,
+    config_json: String,
+    options: O,
+}
+
+/// Pipeline components restored from a snapshot
+pub(crate) struct RestoredState<O> {
+    pub sources: PipelineSources,
+    pub tokenizer: TokenizerOption,
+    pub model_config: ConfigOption,
+    pub options: O,
+}
+
+fn read_merges(
+    merges_path: Option<&Path>,
+) -> Result<HashMap<(String, String), i64>, RustBertError> {
+    let merges_path = merges_path.ok_or_else(|| {
+        RustBertError::InvalidStateError("BPE tokenizers require a merges file".to_string())
+    })?;
+    Ok(BpePairVocab::from_file(&merges_path.to_string_lossy())?.values)
+}
+
+fn swap_key_values<K: Clone, V: Clone + Eq + Hash>(map: &HashMap<K, V>) -> HashMap<V, K> {
+    map.iter()
+        .map(|(key, value)| (value.clone(), key.clone()))
+        .collect()
+}
+
+/// 64-bit FNV-1a hash of the file content
+fn file_checksum(path: &Path) -> Result<u64, RustBertError> {
+    Ok(std::fs::read(path)?
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        }))
+}
+
+fn state_error(error: bincode::Error) -> RustBertError {
+    RustBertError::InvalidStateError(format!("Could not read the pipeline state: {}", error))
+}
+
+/// Writes the state of a pipeline to `path`, reading the configuration and merges files again
+/// from the source paths.
+///
+/// # Arguments
+///
+/// * `path` - destination of the snapshot
+/// * `pipeline` - name of the pipeline, checked when the snapshot is loaded
+/// * `sources` - `PipelineSources` the pipeline was built from
+/// * `tokenizer` - `TokenizerOption` of the pipeline
+/// * `options` - pipeline specific options
+pub(crate) fn write_state<O: Serialize>(
+    path: &Path,
+    pipeline: &str,
+    sources: &PipelineSources,
+    tokenizer: &TokenizerOption,
+    options: &O,
+) -> Result<(), RustBertError> {
+    let checksums = sources
+        .paths()
+        .into_iter()
+        .map(|source_path| {
+            Ok(SourceChecksum {
+                path: source_path.to_path_buf(),
+                checksum: file_checksum(source_path)?,
+            })
+        })
+        .collect::<Result<Vec<SourceChecksum>, RustBertError>>()?;
+    let state = PipelineState {
+        sources: sources.clone(),
+        checksums,
+        tokenizer: TokenizerState::from_tokenizer(tokenizer, sources.merges_path.as_deref())?,
+        config_json: std::fs::read_to_string(&sources.config_path)?,
+        options,
+    };
+    let header = StateHeader {
+        format_version: STATE_FORMAT_VERSION,
+        pipeline: pipeline.to_string(),
+    };
+    let mut writer = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(&mut writer, &header).map_err(state_error)?;
+    bincode::serialize_into(&mut writer, &state).map_err(state_error)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the state of a pipeline from `path`, rejecting snapshots written with another format
+/// version, for another pipeline or from source files modified since.
+///
+/// The checksum of a source file is only validated if the file still exists: the snapshot holds
+/// everything needed to restore the pipeline, and removed source files (e.g. after cleaning the
+/// download cache) are skipped. Source paths that exist but cannot be read (e.g. replaced by a
+/// directory, or without read permission) return an `IOError`.
+///
+/// # Arguments
+///
+/// * `path` - location of the snapshot
+/// * `pipeline` - name of the pipeline loading the snapshot
+pub(crate) fn read_state<O: DeserializeOwned>(
+    path: &Path,
+    pipeline: &str,
+) -> Result<RestoredState<O>, RustBertError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header: StateHeader = bincode::deserialize_from(&mut reader).map_err(state_error)?;
+    if header.format_version != STATE_FORMAT_VERSION {
+        return Err(RustBertError::InvalidStateError(format!(
+            "State format version {} is not supported (expected version {})",
+            header.format_version, STATE_FORMAT_VERSION
+        )));
+    }
+    if header.pipeline != pipeline {
+        return Err(RustBertError::InvalidStateError(format!(
+            "State was saved by a {} pipeline and cannot be loaded by a {} pipeline",
+            header.pipeline, pipeline
+        )));
+    }
+    let state: PipelineState<O> = bincode::deserialize_from(&mut reader).map_err(state_error)?;
+    for source in &state.checksums {
+        if !source.path.exists() {
+            continue;
+        }
+        if file_checksum(&source.path)? != source.checksum {
+            return Err(RustBertError::InvalidStateError(format!(
+                "Source file {} was modified after the state was saved",
+                source.path.display()
+            )));
+        }
+    }
+    let model_config = ConfigOption::try_from_str(state.sources.model_type, &state.config_json)?;
+    let tokenizer = state.tokenizer.into_tokenizer(&state.sources);
+    Ok(RestoredState {
+        sources: state.sources,
+        tokenizer,
+        model_config,
+        options: state.options,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG_JSON: &str = r#"{"vocab_size": 16, "dim": 8, "n_layers": 1, "n_heads": 2, "hidden_dim": 16, "max_position_embeddings": 32, "dropout": 0.1, "attention_dropout": 0.1, "activation": "gelu", "initializer_range": 0.02, "qa_dropout": 0.1, "seq_classif_dropout": 0.2, "sinusoidal_pos_embds": false, "tie_weights_": true, "output_attentions": false, "output_hidden_states": false, "pad_token_id": 0}"#;
+
+    fn wordpiece_sources(directory: &tempfile::TempDir) -> anyhow::Result<PipelineSources> {
+        let vocab_path = directory.path().join("vocab.txt");
+        let config_path = directory.path().join("config.json");
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "movie", "was", "great", "not",
+            "bad", "##s", "##ly", "!", ",", ".",
+        ];
+        std::fs::write(&vocab_path, vocab.join("\n"))?;
+        std::fs::write(&config_path, CONFIG_JSON)?;
+        Ok(PipelineSources {
+            model_type: ModelType::DistilBert,
+            config_path,
+            vocab_path,
+            merges_path: None,
+            lower_case: true,
+            strip_accents: None,
+            add_prefix_space: None,
+        })
+    }
+
+    fn source_tokenizer(sources: &PipelineSources) -> anyhow::Result<TokenizerOption> {
+        Ok(TokenizerOption::from_file(
+            sources.model_type,
+            sources.vocab_path.to_str().unwrap(),
+            sources
+                .merges_path
+                .as_deref()
+                .map(|path| path.to_str().unwrap()),
+            sources.lower_case,
+            sources.strip_accents,
+            sources.add_prefix_space,
+        )?)
+    }
+
+    fn assert_same_tokenization(
+        original: &TokenizerOption,
+        restored: &TokenizerOption,
+        texts: &[&str],
+    ) {
+        for text in texts {
+            assert_eq!(original.tokenize(text), restored.tokenize(text));
+            let original_ids = original.convert_tokens_to_ids(&original.tokenize(text));
+            let restored_ids = restored.convert_tokens_to_ids(&restored.tokenize(text));
+            assert_eq!(original_ids, restored_ids);
+            assert_eq!(
+                original.decode(original_ids.clone(), true, true),
+                restored.decode(restored_ids, true, true)
+            );
+        }
+        assert_eq!(original.get_pad_id(), restored.get_pad_id());
+        assert_eq!(original.get_sep_id(), restored.get_sep_id());
+    }
+
+    #[test]
+    fn test_wordpiece_round_trip() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let sources = wordpiece_sources(&directory)?;
+        let tokenizer = source_tokenizer(&sources)?;
+        let state_path = directory.path().join("pipeline.state");
+
+        write_state(&state_path, "test", &sources, &tokenizer, &Some(8usize))?;
+        let restored: RestoredState<Option<usize>> = read_state(&state_path, "test")?;
+
+        assert_eq!(restored.options, Some(8));
+        assert!(matches!(restored.model_config, ConfigOption::DistilBert(_)));
+        assert_same_tokenization(
+            &tokenizer,
+            &restored.tokenizer,
+            &[
+                "The movies were GREAT!",
+                "Not bad, the movie was badly great.",
+                "unknown words",
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bpe_round_trip() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let vocab_path = directory.path().join("vocab.json");
+        let merges_path = directory.path().join("merges.txt");
+        let config_path = directory.path().join("config.json");
+        std::fs::write(
+            &vocab_path,
+            r#"{"<s>": 0, "<pad>": 1, "</s>": 2, "<unk>": 3, "<mask>": 4, "t": 5, "h": 6, "e": 7, "th": 8, "the": 9, "Ġ": 10, "Ġthe": 11, "c": 12, "a": 13, "Ġc": 14, "Ġca": 15, "Ġcat": 16}"#,
+        )?;
+        std::fs::write(
+            &merges_path,
+            "#version: 0.2\nt h\nth e\nĠ t\nĠ c\nĠc a\nĠca t\nĠt he\n",
+        )?;
+        std::fs::write(&config_path, "{}")?;
+        let sources = PipelineSources {
+            model_type: ModelType::Roberta,
+            config_path,
+            vocab_path,
+            merges_path: Some(merges_path),
+            lower_case: false,
+            strip_accents: None,
+            add_prefix_space: Some(true),
+        };
+        let tokenizer = source_tokenizer(&sources)?;
+        let state = PipelineState {
+            checksums: vec![],
+            tokenizer: TokenizerState::from_tokenizer(&tokenizer, sources.merges_path.as_deref())?,
+            sources,
+            config_json: String::new(),
+            options: (),
+        };
+        let restored = bincode::deserialize::<PipelineState<()>>(&bincode::serialize(&state)?)?
+            .tokenizer
+            .into_tokenizer(&state.sources);
+
+        assert_same_tokenization(&tokenizer, &restored, &["the cat", "The cat the", "a tac"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_states() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let sources = wordpiece_sources(&directory)?;
+        let tokenizer = source_tokenizer(&sources)?;
+        let state_path = directory.path().join("pipeline.state");
+        write_state(&state_path, "test", &sources, &tokenizer, &())?;
+
+        //    Snapshots saved by another pipeline
+        assert!(matches!(
+            read_state::<()>(&state_path, "other"),
+            Err(RustBertError::InvalidStateError(_))
+        ));
+
+        //    Snapshots saved with another format version
+        let mut content = std::fs::read(&state_path)?;
+        content[..4].copy_from_slice(&(STATE_FORMAT_VERSION + 1).to_le_bytes());
+        let other_version_path = directory.path().join("other_version.state");
+        std::fs::write(&other_version_path, content)?;
+        assert!(matches!(
+            read_state::<()>(&other_version_path, "test"),
+            Err(RustBertError::InvalidStateError(_))
+        ));
+
+        //    Removed sources do not invalidate the snapshot, modified sources do
+        std::fs::remove_file(&sources.config_path)?;
+        assert!(read_state::<()>(&state_path, "test").is_ok());
+        std::fs::write(
+            &sources.vocab_path,
+            "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nthe",
+        )?;
+        assert!(matches!(
+            read_state::<()>(&state_path, "test"),
+            Err(RustBertError::InvalidStateError(_))
+        ));
+
+        //    Unreadable sources are not skipped
+        std::fs::create_dir(&sources.config_path)?;
+        assert!(matches!(
+            read_state::<()>(&state_path, "test"),
+            Err(RustBertError::IOError(_))
+        ));
+        Ok(())
+    }
+}
@@ -68,11 +68,17 @@ use crate::bart::{
     BartConfigResources, BartGenerator, BartMergesResources, BartModelResources, BartVocabResources,
 };
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
 use crate::common::resources::{RemoteResource, Resource};
+#[cfg(feature = "pegasus")]
 use crate::pegasus::PegasusConditionalGenerator;
-use crate::pipelines::common::ModelType;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::common::{split_sentences, ModelType, TokenizerOption};
+use crate::pipelines::denylist::DenyList;
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{DecodingStrategy, GenerateConfig, LanguageGenerator};
+#[cfg(feature = "prophetnet")]
 use crate::prophetnet::ProphetNetConditionalGenerator;
+#[cfg(feature = "t5")]
 use crate::t5::T5Generator;
 
 /// # Configuration for text summarization
@@ -117,8 +123,12 @@ pub struct SummarizationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Explicit decoding strategy. If provided, takes precedence over `do_sample`, `num_beams`, `temperature`, `top_k` and `top_p` (default: None)
+    pub decoding_strategy: Option<DecodingStrategy>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Optional denylist of terms that may not appear in the summaries (default: None)
+    pub output_denylist: Option<DenyList>,
 }
 
 impl SummarizationConfig {
@@ -180,7 +190,9 @@ impl Default for SummarizationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
             device: Device::cuda_if_available(),
+            output_denylist: None,
         }
     }
 }
@@ -206,33 +218,111 @@ impl From<SummarizationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            decoding_strategy: config.decoding_strategy,
+            decoder_step_hook: None,
             device: config.device,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Formatting of the generated summaries
+/// Post-processing stage applied to the summaries returned by the model, set with
+/// `SummarizationModel::set_output_format`. The summaries are split into sentences after a `.`,
+/// `!` or `?` followed by a whitespace, closing quotes and brackets being kept with the sentence.
+/// The rule does not know about abbreviations: "Dr. Smith" is split after "Dr.", while decimal
+/// numbers such as "3.5" (no whitespace after the period) are left whole.
+pub enum OutputFormat {
+    /// Summary returned as generated by the model
+    Plain,
+    /// One sentence per line, prefixed with a bullet (`- `)
+    Bullets,
+    /// Summary truncated to its first sentences
+    MaxSentences(usize),
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Plain
+    }
+}
+
+impl OutputFormat {
+    /// Adjusts the minimum generation length for the output format. Bullet points are made of
+    /// short sentences: the minimum length is halved to avoid forcing run-on sentences.
+    fn min_length(&self, min_length: i64) -> i64 {
+        match self {
+            OutputFormat::Bullets => min_length / 2,
+            OutputFormat::Plain | OutputFormat::MaxSentences(_) => min_length,
+        }
+    }
+
+    /// Formats a generated summary
+    ///
+    /// # Arguments
+    ///
+    /// * `summary` - Summary generated by the model
+    ///
+    /// # Returns
+    /// * `String` formatted summary
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::summarization::OutputFormat;
+    ///
+    /// let summary = "Water was found on K2-18b. The planet is 110 light-years away.";
+    /// assert_eq!(
+    ///     OutputFormat::Bullets.apply(summary),
+    ///     "- Water was found on K2-18b.\n- The planet is 110 light-years away."
+    /// );
+    /// ```
+    pub fn apply(&self, summary: &str) -> String {
+        match self {
+            OutputFormat::Plain => summary.to_string(),
+            OutputFormat::Bullets => split_sentences(summary)
+                .iter()
+                .map(|sentence| format!("- {}", sentence))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            OutputFormat::MaxSentences(max_sentences) => split_sentences(summary)
+                .into_iter()
+                .take(*max_sentences)
+                .collect::<Vec<&str>>()
+                .join(" "),
+        }
+    }
+}
+
 /// # Abstraction that holds one particular summarization model, for any of the supported models
 pub enum SummarizationOption {
     /// Summarizer based on BART model
     Bart(BartGenerator),
     /// Summarizer based on T5 model
+    #[cfg(feature = "t5")]
     T5(T5Generator),
     /// Summarizer based on ProphetNet model
+    #[cfg(feature = "prophetnet")]
     ProphetNet(ProphetNetConditionalGenerator),
     /// Summarizer based on Pegasus model
+    #[cfg(feature = "pegasus")]
     Pegasus(PegasusConditionalGenerator),
 }
 
 impl SummarizationOption {
     pub fn new(config: SummarizationConfig) -> Result<Self, RustBertError> {
+        config.model_type.ensure_enabled()?;
         match config.model_type {
             ModelType::Bart => Ok(SummarizationOption::Bart(BartGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "t5")]
             ModelType::T5 => Ok(SummarizationOption::T5(T5Generator::new(config.into())?)),
+            #[cfg(feature = "prophetnet")]
             ModelType::ProphetNet => Ok(SummarizationOption::ProphetNet(
                 ProphetNetConditionalGenerator::new(config.into())?,
             )),
+            #[cfg(feature = "pegasus")]
             ModelType::Pegasus => Ok(SummarizationOption::Pegasus(
                 PegasusConditionalGenerator::new(config.into())?,
             )),
@@ -247,17 +337,61 @@ impl SummarizationOption {
     pub fn model_type(&self) -> ModelType {
         match *self {
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "t5")]
             Self::T5(_) => ModelType::T5,
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(_) => ModelType::ProphetNet,
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(_) => ModelType::Pegasus,
         }
     }
 
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        match self {
+            Self::Bart(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "t5")]
+            Self::T5(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(model_ref) => model_ref._get_tokenizer(),
+        }
+    }
+
+    /// Interface method to access the vocabulary size of the model
+    pub fn get_vocab_size(&self) -> i64 {
+        match self {
+            Self::Bart(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "t5")]
+            Self::T5(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "prophetnet")]
+            Self::ProphetNet(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "pegasus")]
+            Self::Pegasus(model_ref) => model_ref.get_vocab_size(),
+        }
+    }
+
     /// Interface method to generate() of the particular models.
     pub fn generate<'a, S>(
         &self,
         prompt_texts: Option<S>,
         attention_mask: Option<Tensor>,
+        prefix_allowed_tokens_fn: Option<&dyn Fn(i64, &Tensor) -> Vec<i64>>,
+    ) -> Vec<String>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        self.generate_with_min_length(prompt_texts, attention_mask, None, prefix_allowed_tokens_fn)
+    }
+
+    /// `generate` with a minimum length overriding the one of the configuration if set
+    fn generate_with_min_length<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+        min_length: Option<i64>,
+        prefix_allowed_tokens_fn: Option<&dyn Fn(i64, &Tensor) -> Vec<i64>>,
     ) -> Vec<String>
     where
         S: AsRef<[&'a str]>,
@@ -267,53 +401,56 @@ impl SummarizationOption {
                 .generate(
                     prompt_texts,
                     attention_mask,
+                    min_length,
                     None,
                     None,
                     None,
-                    None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "t5")]
             Self::T5(ref model) => model
                 .generate(
                     prompt_texts,
                     attention_mask,
+                    min_length,
                     None,
                     None,
                     None,
-                    None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "prophetnet")]
             Self::ProphetNet(ref model) => model
                 .generate(
                     prompt_texts,
                     attention_mask,
+                    min_length,
                     None,
                     None,
                     None,
-                    None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.text)
                 .collect(),
+            #[cfg(feature = "pegasus")]
             Self::Pegasus(ref model) => model
                 .generate(
                     prompt_texts,
                     attention_mask,
+                    min_length,
                     None,
                     None,
                     None,
-                    None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
@@ -327,6 +464,10 @@ impl SummarizationOption {
 pub struct SummarizationModel {
     model: SummarizationOption,
     prefix: Option<String>,
+    output_format: OutputFormat,
+    min_length: i64,
+    num_return_sequences: i64,
+    output_denylist: Option<DenyList>,
 }
 
 impl SummarizationModel {
@@ -347,15 +488,49 @@ impl SummarizationModel {
     /// # }
     /// ```
     pub fn new(
-        summarization_config: SummarizationConfig,
+        mut summarization_config: SummarizationConfig,
     ) -> Result<SummarizationModel, RustBertError> {
+        ensure_initialized();
         let prefix = match summarization_config.model_type {
             ModelType::T5 => Some("summarize: ".to_string()),
             _ => None,
         };
+        let min_length = summarization_config.min_length;
+        let num_return_sequences = summarization_config.num_return_sequences;
+        let output_denylist = summarization_config.output_denylist.take();
         let model = SummarizationOption::new(summarization_config)?;
 
-        Ok(SummarizationModel { model, prefix })
+        Ok(SummarizationModel {
+            model,
+            prefix,
+            output_format: OutputFormat::Plain,
+            min_length,
+            num_return_sequences,
+            output_denylist,
+        })
+    }
+
+    /// Sets the formatting applied to the generated summaries (`OutputFormat::Plain` by default).
+    /// With `OutputFormat::Bullets`, the summaries are generated with half the minimum length of
+    /// the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_format` - `OutputFormat` applied to the following summaries
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::{OutputFormat, SummarizationModel};
+    ///
+    /// let mut model = SummarizationModel::new(Default::default())?;
+    /// model.set_output_format(OutputFormat::MaxSentences(2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.output_format = output_format;
     }
 
     /// Summarize texts provided
@@ -365,7 +540,8 @@ impl SummarizationModel {
     /// * `input` - `&[&str]` Array of texts to summarize.
     ///
     /// # Returns
-    /// * `Vec<String>` Summarized texts
+    /// * `Vec<String>` Summarized texts, formatted according to the `OutputFormat` set with
+    ///   `set_output_format`
     ///
     /// # Example
     ///
@@ -406,19 +582,58 @@ impl SummarizationModel {
     where
         S: AsRef<[&'a str]>,
     {
-        match &self.prefix {
-            None => self.model.generate(Some(texts), None),
-            Some(prefix) => {
-                let texts = texts
-                    .as_ref()
-                    .iter()
-                    .map(|text| format!("{}{}", prefix, text))
-                    .collect::<Vec<String>>();
-                self.model.generate(
-                    Some(texts.iter().map(|x| &**x).collect::<Vec<&str>>()),
-                    None,
+        let texts = match &self.prefix {
+            None => texts
+                .as_ref()
+                .iter()
+                .map(|text| text.to_string())
+                .collect::<Vec<String>>(),
+            Some(prefix) => texts
+                .as_ref()
+                .iter()
+                .map(|text| format!("{}{}", prefix, text))
+                .collect::<Vec<String>>(),
+        };
+        let texts = texts.iter().map(|x| &**x).collect::<Vec<&str>>();
+        let min_length = Some(self.output_format.min_length(self.min_length));
+        let summaries = self
+            .model
+            .generate_with_min_length(Some(&texts), None, min_length, None);
+        let summaries = match &self.output_denylist {
+            None => summaries,
+            Some(deny_list) => {
+                let vocab_size = self.model.get_vocab_size();
+                deny_list.filter_outputs(
+                    summaries,
+                    self.model.get_tokenizer(),
+                    |index, _, _, banned_sequences| {
+                        let allowed_tokens_fn = |_batch_id: i64, previous_token_ids: &Tensor| {
+                            let previous_token_ids = previous_token_ids
+                                .iter::<i64>()
+                                .unwrap()
+                                .collect::<Vec<i64>>();
+                            banned_sequences.allowed_tokens(&previous_token_ids, vocab_size)
+                        };
+                        //    The summary is regenerated entirely as the decoder cannot be primed with the text preceding the match
+                        self.model
+                            .generate_with_min_length(
+                                Some(&[texts[index / self.num_return_sequences as usize]]),
+                                None,
+                                min_length,
+                                Some(&allowed_tokens_fn),
+                            )
+                            .into_iter()
+                            .next()
+                    },
                 )
             }
+        };
+        match self.output_format {
+            OutputFormat::Plain => summaries,
+            output_format => summaries
+                .iter()
+                .map(|summary| output_format.apply(summary))
+                .collect(),
         }
     }
 }
@@ -433,4 +648,28 @@ mod test {
         let config = SummarizationConfig::default();
         let _: Box<dyn Send> = Box::new(SummarizationModel::new(config));
     }
+
+    #[test]
+    fn test_output_format() {
+        let summary =
+            "Water was found on K2-18b. The planet is 110 light-years away. It orbits a red dwarf.";
+        assert_eq!(OutputFormat::Plain.apply(summary), summary);
+        assert_eq!(
+            OutputFormat::Bullets.apply(summary),
+            "- Water was found on K2-18b.\n- The planet is 110 light-years away.\n- It orbits a red dwarf."
+        );
+        assert_eq!(
+            OutputFormat::MaxSentences(2).apply(summary),
+            "Water was found on K2-18b. The planet is 110 light-years away."
+        );
+        assert_eq!(OutputFormat::MaxSentences(5).apply(summary), summary);
+        assert_eq!(OutputFormat::MaxSentences(0).apply(summary), "");
+    }
+
+    #[test]
+    fn test_output_format_min_length() {
+        assert_eq!(OutputFormat::Plain.min_length(56), 56);
+        assert_eq!(OutputFormat::Bullets.min_length(56), 28);
+        assert_eq!(OutputFormat::MaxSentences(2).min_length(56), 56);
+    }
 }
@@ -23,21 +23,29 @@
 //! - Reformer
 //!
 //! Customized text generation models models can be loaded by overwriting the resources in the configuration.
+//! The generated texts include the prompts unless `include_prompt` is set to false in the configuration, in which
+//! case only the tokens generated after each prompt are decoded.
 //! The dependencies will be downloaded to the user's home directory, e.g. under ~/.cache/.rustbert/gpt2
 use tch::{Device, Tensor};
 
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
 use crate::common::resources::RemoteResource;
 use crate::gpt2::{
     GPT2Generator, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
+#[cfg(feature = "gpt-neo")]
 use crate::gpt_neo::GptNeoGenerator;
+#[cfg(feature = "openai-gpt")]
 use crate::openai_gpt::OpenAIGenerator;
 use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::denylist::{BannedSequences, DenyList};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::generation_utils::{DecodingStrategy, GenerateConfig, LanguageGenerator};
+#[cfg(feature = "reformer")]
 use crate::reformer::ReformerGenerator;
 use crate::resources::Resource;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetGenerator;
 
 /// # Configuration for text generation
@@ -82,8 +90,15 @@ pub struct TextGenerationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Explicit decoding strategy. If provided, takes precedence over `do_sample`, `num_beams`, `temperature`, `top_k` and `top_p` (default: None)
+    pub decoding_strategy: Option<DecodingStrategy>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Optional denylist of terms that may not appear in the generated texts (default: None)
+    pub output_denylist: Option<DenyList>,
+    /// Include the prompt in the generated texts. If false, only the tokens generated after the
+    /// prompt are decoded (default: true)
+    pub include_prompt: bool,
 }
 
 impl TextGenerationConfig {
@@ -145,7 +160,10 @@ impl Default for TextGenerationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
             device: Device::cuda_if_available(),
+            output_denylist: None,
+            include_prompt: true,
         }
     }
 }
@@ -171,6 +189,8 @@ impl From<TextGenerationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            decoding_strategy: config.decoding_strategy,
+            decoder_step_hook: None,
             device: config.device,
         }
     }
@@ -181,30 +201,39 @@ pub enum TextGenerationOption {
     /// Text Generator based on GPT2 model
     GPT2(GPT2Generator),
     /// Text Generator based on GPT model
+    #[cfg(feature = "openai-gpt")]
     GPT(OpenAIGenerator),
     /// Text Generator based on GPT-Neo model
+    #[cfg(feature = "gpt-neo")]
     GPTNeo(GptNeoGenerator),
     /// Text Generator based on XLNet model
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetGenerator),
     /// Text Generator based on Reformer model
+    #[cfg(feature = "reformer")]
     Reformer(ReformerGenerator),
 }
 
 impl TextGenerationOption {
     pub fn new(config: TextGenerationConfig) -> Result<Self, RustBertError> {
+        config.model_type.ensure_enabled()?;
         match config.model_type {
             ModelType::GPT2 => Ok(TextGenerationOption::GPT2(GPT2Generator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "openai-gpt")]
             ModelType::OpenAiGpt => Ok(TextGenerationOption::GPT(OpenAIGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => Ok(TextGenerationOption::XLNet(XLNetGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "reformer")]
             ModelType::Reformer => Ok(TextGenerationOption::Reformer(ReformerGenerator::new(
                 config.into(),
             )?)),
+            #[cfg(feature = "gpt-neo")]
             ModelType::GPTNeo => Ok(TextGenerationOption::GPTNeo(GptNeoGenerator::new(
                 config.into(),
             )?)),
@@ -218,10 +247,14 @@ impl TextGenerationOption {
     /// Returns the `ModelType` for this TextGenerationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
+            #[cfg(feature = "openai-gpt")]
             Self::GPT(_) => ModelType::OpenAiGpt,
             Self::GPT2(_) => ModelType::GPT2,
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(_) => ModelType::GPTNeo,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "reformer")]
             Self::Reformer(_) => ModelType::Reformer,
         }
     }
@@ -229,14 +262,33 @@ impl TextGenerationOption {
     /// Interface method to access tokenizer
     pub fn get_tokenizer(&self) -> &TokenizerOption {
         match self {
+            #[cfg(feature = "openai-gpt")]
             Self::GPT(model_ref) => model_ref._get_tokenizer(),
             Self::GPT2(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(model_ref) => model_ref._get_tokenizer(),
+            #[cfg(feature = "reformer")]
             Self::Reformer(model_ref) => model_ref._get_tokenizer(),
         }
     }
 
+    /// Interface method to access the vocabulary size of the model
+    pub fn get_vocab_size(&self) -> i64 {
+        match self {
+            #[cfg(feature = "openai-gpt")]
+            Self::GPT(model_ref) => model_ref.get_vocab_size(),
+            Self::GPT2(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "gpt-neo")]
+            Self::GPTNeo(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "xlnet")]
+            Self::XLNet(model_ref) => model_ref.get_vocab_size(),
+            #[cfg(feature = "reformer")]
+            Self::Reformer(model_ref) => model_ref.get_vocab_size(),
+        }
+    }
+
     /// Interface method to generate() of the particular models.
     pub fn generate_indices<'a, S>(
         &self,
@@ -244,11 +296,13 @@ impl TextGenerationOption {
         attention_mask: Option<Tensor>,
         min_length: Option<i64>,
         max_length: Option<i64>,
+        prefix_allowed_tokens_fn: Option<&dyn Fn(i64, &Tensor) -> Vec<i64>>,
     ) -> Vec<Vec<i64>>
     where
         S: AsRef<[&'a str]>,
     {
         match *self {
+            #[cfg(feature = "openai-gpt")]
             Self::GPT(ref model) => model
                 .generate_indices(
                     prompt_texts,
@@ -257,7 +311,7 @@ impl TextGenerationOption {
                     max_length,
                     None,
                     None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
@@ -271,12 +325,13 @@ impl TextGenerationOption {
                     max_length,
                     None,
                     None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "gpt-neo")]
             Self::GPTNeo(ref model) => model
                 .generate_indices(
                     prompt_texts,
@@ -285,12 +340,13 @@ impl TextGenerationOption {
                     max_length,
                     None,
                     None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => model
                 .generate_indices(
                     prompt_texts,
@@ -299,12 +355,13 @@ impl TextGenerationOption {
                     max_length,
                     None,
                     None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            #[cfg(feature = "reformer")]
             Self::Reformer(ref model) => model
                 .generate_indices(
                     prompt_texts,
@@ -313,7 +370,7 @@ impl TextGenerationOption {
                     max_length,
                     None,
                     None,
-                    None,
+                    prefix_allowed_tokens_fn,
                     false,
                 )
                 .into_iter()
@@ -330,6 +387,9 @@ pub struct TextGenerationModel {
     prefix_length: Option<i64>,
     min_length: i64,
     max_length: i64,
+    num_return_sequences: i64,
+    output_denylist: Option<DenyList>,
+    include_prompt: bool,
 }
 
 impl TextGenerationModel {
@@ -352,8 +412,9 @@ impl TextGenerationModel {
     /// # }
     /// ```
     pub fn new(
-        generation_config: TextGenerationConfig,
+        mut generation_config: TextGenerationConfig,
     ) -> Result<TextGenerationModel, RustBertError> {
+        ensure_initialized();
         let prefix = match generation_config.model_type {
             ModelType::XLNet => Some(
                 "In 1991, the remains of Russian Tsar Nicholas II and his family \
@@ -373,6 +434,9 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
 
         let min_length = generation_config.min_length;
         let max_length = generation_config.max_length;
+        let num_return_sequences = generation_config.num_return_sequences;
+        let output_denylist = generation_config.output_denylist.take();
+        let include_prompt = generation_config.include_prompt;
         let model = TextGenerationOption::new(generation_config)?;
         let prefix_length = prefix
             .as_ref()
@@ -384,6 +448,9 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
             prefix_length,
             min_length,
             max_length,
+            num_return_sequences,
+            output_denylist,
+            include_prompt,
         })
     }
 
@@ -395,7 +462,8 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
     /// * `prefix` - `impl Into<Option<&'a str>>`: Optional string to pass as a prefix for generation. Will be excluded from generated sequences.
     ///
     /// # Returns
-    /// * `Vec<String>` Generated texts
+    /// * `Vec<String>` Generated texts, including the prompts unless `include_prompt` is false in
+    /// the pipeline configuration
     ///
     /// # Example
     ///
@@ -425,38 +493,145 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
             (None, Some(pipeline_prefix)) => (Some(pipeline_prefix.as_str()), self.prefix_length),
             (None, None) => (None, None),
         };
-        let generated_indices = match (prefix, prefix_length) {
-            (None, _) => self.model.generate_indices(Some(texts), None, None, None),
-            (Some(prefix), Some(prefix_length)) => {
-                let texts = texts
-                    .as_ref()
+        let texts = texts.as_ref();
+        let output = self.generate_constrained(texts, prefix, prefix_length, None);
+        match &self.output_denylist {
+            None => output,
+            Some(deny_list) => deny_list.filter_outputs(
+                output,
+                self.model.get_tokenizer(),
+                |index, output, match_start, banned_sequences| {
+                    self.regenerate_region(
+                        texts[index / self.num_return_sequences as usize],
+                        output,
+                        match_start,
+                        banned_sequences,
+                        prefix,
+                        prefix_length,
+                    )
+                },
+            ),
+        }
+    }
+
+    /// Regenerates the output from the text preceding the first denied term, banning the token
+    /// sequences of the denied terms found so far.
+    fn regenerate_region(
+        &self,
+        prompt: &str,
+        output: &str,
+        match_start: usize,
+        banned_sequences: &BannedSequences,
+        prefix: Option<&str>,
+        prefix_length: Option<i64>,
+    ) -> Option<String> {
+        //    Denied terms in the prompt cannot be removed by regenerating the output
+        if self.include_prompt && match_start < prompt.len() {
+            return None;
+        }
+        let vocab_size = self.model.get_vocab_size();
+        let allowed_tokens_fn = |_batch_id: i64, previous_token_ids: &Tensor| {
+            let previous_token_ids = previous_token_ids
+                .iter::<i64>()
+                .unwrap()
+                .collect::<Vec<i64>>();
+            banned_sequences.allowed_tokens(&previous_token_ids, vocab_size)
+        };
+        //    Outputs without prompt are regenerated from the prompt followed by the kept output
+        let context = if self.include_prompt {
+            output[..match_start].trim_end().to_string()
+        } else {
+            format!("{}{}", prompt, &output[..match_start])
+                .trim_end()
+                .to_string()
+        };
+        let regenerated = self
+            .generate_constrained(
+                &[context.as_str()],
+                prefix,
+                prefix_length,
+                Some(&allowed_tokens_fn),
+            )
+            .into_iter()
+            .next()?;
+        if self.include_prompt {
+            Some(regenerated)
+        } else {
+            Some(format!(
+                "{}{}",
+                context.get(prompt.len()..).unwrap_or(""),
+                regenerated
+            ))
+        }
+    }
+
+    fn generate_constrained(
+        &self,
+        texts: &[&str],
+        prefix: Option<&str>,
+        prefix_length: Option<i64>,
+        prefix_allowed_tokens_fn: Option<&dyn Fn(i64, &Tensor) -> Vec<i64>>,
+    ) -> Vec<String> {
+        let (prompts, max_length) = match (prefix, prefix_length) {
+            (None, _) => (
+                texts.iter().map(|text| text.to_string()).collect(),
+                self.max_length,
+            ),
+            (Some(prefix), Some(prefix_length)) => (
+                texts
                     .iter()
                     .map(|text| format!("{} {}", prefix, text))
-                    .collect::<Vec<String>>();
-                self.model.generate_indices(
-                    Some(texts.iter().map(|x| &**x).collect::<Vec<&str>>()),
-                    None,
-                    Some(self.min_length + prefix_length),
-                    Some(self.max_length + prefix_length),
-                )
-            }
+                    .collect::<Vec<String>>(),
+                self.max_length + prefix_length,
+            ),
             _ => panic!("Prefix length not defined but prefix provided!"),
         };
+        let prompts = prompts.iter().map(|x| &**x).collect::<Vec<&str>>();
+        let generated_indices = match prefix_length {
+            None => self.model.generate_indices(
+                Some(&prompts),
+                None,
+                None,
+                None,
+                prefix_allowed_tokens_fn,
+            ),
+            Some(prefix_length) => self.model.generate_indices(
+                Some(&prompts),
+                None,
+                Some(self.min_length + prefix_length),
+                Some(max_length),
+                prefix_allowed_tokens_fn,
+            ),
+        };
+
+        //    Prompts are truncated to the maximum length and left-padded to the longest prompt
+        let prompt_lengths = self
+            .model
+            .get_tokenizer()
+            .tokenize_list(&prompts)
+            .iter()
+            .map(|tokens| tokens.len().min(max_length as usize))
+            .collect::<Vec<usize>>();
+        let input_length = prompt_lengths.iter().copied().max().unwrap_or(0);
 
         let mut output = Vec::with_capacity(generated_indices.len());
-        for generated_sequence in generated_indices {
-            output.push(self.model.get_tokenizer().decode(
-                if prefix_length.is_some() {
+        for (sequence_index, generated_sequence) in generated_indices.into_iter().enumerate() {
+            let prompt_length = prompt_lengths[sequence_index / self.num_return_sequences as usize];
+            let skipped_tokens = if self.include_prompt {
+                input_length - prompt_length + prefix_length.unwrap_or(0) as usize
+            } else {
+                input_length
+            };
+            output.push(
+                self.model.get_tokenizer().decode(
                     generated_sequence
                         .into_iter()
-                        .skip(prefix_length.unwrap_or(0) as usize)
-                        .collect::<Vec<i64>>()
-                } else {
-                    generated_sequence
-                },
-                true,
-                true,
-            ));
+                        .skip(skipped_tokens)
+                        .collect::<Vec<i64>>(),
+                    true,
+                    true,
+                ),
+            );
         }
         output
     }
@@ -110,32 +110,53 @@
 //! # ;
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForTokenClassification;
 use crate::bert::{
     BertConfigResources, BertForTokenClassification, BertModelResources, BertVocabResources,
 };
+use crate::common::crf::Crf;
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::resources::{RemoteResource, Resource};
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertForTokenClassification;
+#[cfg(feature = "electra")]
 use crate::electra::ElectraForTokenClassification;
-use crate::longformer::LongformerForTokenClassification;
+#[cfg(feature = "longformer")]
+use crate::longformer::{
+    LongformerForTokenClassification, LongformerMergesResources, LongformerVocabResources,
+};
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForTokenClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
-use crate::roberta::RobertaForTokenClassification;
+use crate::pipelines::common::{
+    expect_config, get_padded_length, resolve_label_mapping, single_special_tokens, ConfigOption,
+    ModelType, TokenizerOption,
+};
+use crate::pipelines::validation::{validate_resources, PipelineResources, ValidationReport};
+#[cfg(feature = "roberta")]
+use crate::roberta::{
+    RobertaConfigResources, RobertaForTokenClassification, RobertaModelResources,
+    RobertaVocabResources,
+};
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForTokenClassification;
 use rust_tokenizers::tokenizer::{Tokenizer, TruncationStrategy};
 use rust_tokenizers::{
-    ConsolidatableTokens, ConsolidatedTokenIterator, Mask, Offset, TokenTrait, TokenizedInput,
+    ConsolidatableTokens, ConsolidatedTokenIterator, Mask, Offset, TokenIdsWithOffsets, TokenTrait,
+    TokenizedInput,
 };
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::cmp::min;
+use std::cmp::{min, Ordering};
 use std::collections::HashMap;
 use tch::kind::Kind::Float;
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Tensor};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// # Token generated by a `TokenClassificationModel`
 pub struct Token {
     /// String representation of the Token
@@ -152,12 +173,25 @@ pub struct Token {
     pub index: u16,
     /// Token word position index
     pub word_index: u16,
-    /// Token offsets
+    /// Token offsets, in characters (not bytes or UTF-16 code units) of the input text. See
+    /// `pipelines::common::char_to_byte` and `pipelines::common::char_to_utf16` for conversions.
     pub offset: Option<Offset>,
     /// Token mask
     pub mask: Mask,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Token with the scores of all labels, generated by `TokenClassificationModel::predict_with_scores`
+pub struct TokenScores {
+    /// Token with its predicted label and score
+    pub token: Token,
+    /// Label and probability of every label of the model, ordered by label id (following the
+    /// `label_mapping` of the pipeline configuration, or the `id2label` mapping of the model
+    /// configuration)
+    pub label_scores: Vec<(String, f64)>,
+}
+
 impl TokenTrait for Token {
     fn offset(&self) -> Option<Offset> {
         self.offset
@@ -193,6 +227,75 @@ pub enum LabelAggregationOption {
     Custom(LabelAggregationFunction),
 }
 
+/// # Enum defining how tokens are aggregated into entities
+/// Mirrors the aggregation strategies of the Python token classification pipeline. The strategies
+/// other than `None` and `Simple` first aggregate the sub-tokens of each word into a single
+/// prediction, so that a word is never split across entities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AggregationStrategy {
+    /// Every (sub-)token is returned as a separate entity
+    None,
+    /// Consecutive tokens sharing the same entity group are merged, using the sub-token predictions
+    Simple,
+    /// Words take the label and score of their first sub-token
+    First,
+    /// Words take the label with the highest probability averaged over their sub-tokens
+    Average,
+    /// Words take the label and score of their sub-token with the highest score
+    Max,
+}
+
+impl Default for AggregationStrategy {
+    fn default() -> Self {
+        AggregationStrategy::None
+    }
+}
+
+/// # Enum defining the tagging scheme of the entity labels
+/// Used by `NERModel::predict_full_entities` to decode the entity spans from the labels of
+/// consecutive tokens. Illegal transitions (e.g. `I-LOC` following `O` or `I-PER`) are repaired by
+/// starting a new entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaggingScheme {
+    /// IOB1: entity tokens are tagged `I-`, `B-` only starts an entity following an entity of the
+    /// same type
+    Iob1,
+    /// IOB2: the first token of an entity is tagged `B-` and the following tokens `I-`
+    Iob2,
+    /// BILOU: the tokens of multi-token entities are tagged `B-` (beginning), `I-` (inside) and
+    /// `L-` (last), single-token entities are tagged `U-` (unit)
+    Bilou,
+    /// Labels without prefix: consecutive tokens sharing the same label form an entity
+    None,
+}
+
+/// # Enum defining how the predictions of the sub-tokens of a word are aggregated
+/// Used by `TokenClassificationModel::predict_words` to return a single prediction per word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WordAggregationStrategy {
+    /// Words take the label and score of their first sub-token
+    First,
+    /// Words take the label and score of their sub-token with the highest score
+    Max,
+    /// Words take the label with the highest probability averaged over their sub-tokens
+    Average,
+    /// Words take the label with the highest logit averaged over their sub-tokens, the score is
+    /// the probability obtained from the averaged logits
+    AverageLogits,
+}
+
+impl Default for WordAggregationStrategy {
+    fn default() -> Self {
+        WordAggregationStrategy::First
+    }
+}
+
+impl Default for TaggingScheme {
+    fn default() -> Self {
+        TaggingScheme::Iob2
+    }
+}
+
 /// # Configuration for TokenClassificationModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct TokenClassificationConfig {
@@ -214,8 +317,51 @@ pub struct TokenClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Pad the input sequences to a multiple of this value to produce tensor shapes that are more
+    /// efficient on GPU (e.g. 8 for fp16 tensor cores). Padded positions are masked (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of inputs processed in a single forward pass. The inputs are sorted by length
+    /// before batching and each batch is padded to its own longest input (default: 64)
+    pub batch_size: usize,
+    /// Maximum number of tokens (including the special tokens) of the sequences processed by the
+    /// model (default: 128)
+    pub max_length: usize,
+    /// Processing of the inputs longer than `max_length`: `None` truncates the inputs, `Some(stride)`
+    /// splits them into windows of `max_length` tokens, consecutive windows overlapping by `stride`
+    /// tokens. Each token takes the prediction of the window where it is furthest from the window
+    /// edges, and the offsets refer to the original input (default: None)
+    pub sliding_window_stride: Option<usize>,
+    /// Label names of the classifier outputs, taking precedence over the id2label mapping of the
+    /// configuration file (e.g. to replace generic `LABEL_0` names). Must cover every class index
+    /// of the classifier head. The entities are decoded from these names following the
+    /// `label_aggregation_scheme` (default: None)
+    pub label_mapping: Option<HashMap<i64, String>>,
     /// Sub-tokens aggregation method (default: `LabelAggregationOption::First`)
     pub label_aggregation_function: LabelAggregationOption,
+    /// Aggregation of the tokens into entities used by `NERModel::predict_full_entities`
+    /// (default: `AggregationStrategy::None`)
+    pub aggregation_strategy: AggregationStrategy,
+    /// Tagging scheme of the labels, used to decode the entities aggregated by
+    /// `NERModel::predict_full_entities` (default: `TaggingScheme::Iob2`)
+    pub label_aggregation_scheme: TaggingScheme,
+    /// Aggregation of the sub-tokens predictions used by `TokenClassificationModel::predict_words`
+    /// (default: `WordAggregationStrategy::First`)
+    pub word_aggregation_strategy: WordAggregationStrategy,
+    /// Minimum score of the returned tokens and entities, between 0 and 1. The threshold applies
+    /// to the score of the consolidated words when sub-tokens are consolidated, and to the score
+    /// of the aggregated entities for `NERModel::predict_full_entities`, rather than to the
+    /// individual sub-tokens (default: None)
+    pub min_score: Option<f64>,
+    /// Flag indicating that the classification head was trained multi-label, with an independent
+    /// sigmoid per label. The label scores are then the sigmoid of the logits instead of a softmax
+    /// over the labels, and `NERModel::predict_nested` can decode overlapping entities
+    /// (default: false)
+    pub multi_label: bool,
+    /// Flag indicating that the checkpoint includes a CRF layer on top of the classification head
+    /// (`crf.transitions` transition scores between labels). The labels of the tokens are then
+    /// decoded with the Viterbi algorithm instead of the best label of each token. Cannot be
+    /// combined with `multi_label` (default: false)
+    pub use_crf: bool,
 }
 
 impl TokenClassificationConfig {
@@ -250,7 +396,94 @@ impl TokenClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            batch_size: 64,
+            max_length: 128,
+            sliding_window_stride: None,
+            label_mapping: None,
             label_aggregation_function,
+            aggregation_strategy: AggregationStrategy::None,
+            label_aggregation_scheme: TaggingScheme::Iob2,
+            word_aggregation_strategy: WordAggregationStrategy::First,
+            min_score: None,
+            multi_label: false,
+            use_crf: false,
+        }
+    }
+
+    /// Provides an English NER model (PER, ORG, LOC and MISC entities) based on XLM-RoBERTa
+    /// large fine-tuned on the English CoNLL-2003 annotations (`XLM_ROBERTA_NER_EN`). The model
+    /// is not trained on annotations of other languages: the German, Spanish and Dutch models
+    /// (`XLM_ROBERTA_NER_DE`, `XLM_ROBERTA_NER_ES` and `XLM_ROBERTA_NER_NL`) should be preferred
+    /// for these languages.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    /// use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    ///
+    /// let ner_model = NERModel::new(TokenClassificationConfig::xlm_roberta_english_ner())?;
+    /// let output = ner_model.predict(&["My name is Amy. I live in Paris."])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "roberta")]
+    pub fn xlm_roberta_english_ner() -> TokenClassificationConfig {
+        TokenClassificationConfig::new(
+            ModelType::XLMRoberta,
+            Resource::Remote(RemoteResource::from_pretrained(
+                RobertaModelResources::XLM_ROBERTA_NER_EN,
+            )),
+            Resource::Remote(RemoteResource::from_pretrained(
+                RobertaConfigResources::XLM_ROBERTA_NER_EN,
+            )),
+            Resource::Remote(RemoteResource::from_pretrained(
+                RobertaVocabResources::XLM_ROBERTA_NER_EN,
+            )),
+            None,
+            false,
+            None,
+            None,
+            LabelAggregationOption::First,
+        )
+    }
+
+    /// Instantiate a token classification configuration for a Longformer checkpoint fine-tuned
+    /// for token classification (e.g. NER on long documents). The tokenizer files of the
+    /// pretrained `longformer-base-4096` model are used, and the inputs are processed up to
+    /// 4096 tokens instead of being truncated to 128 tokens. The pipeline sets global attention
+    /// on the CLS token of each input.
+    ///
+    /// # Arguments
+    ///
+    /// * model - The `Resource` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `Resource' pointing to the model configuration to load (e.g. config.json)
+    /// * label_aggregation_function - Sub-tokens aggregation method
+    #[cfg(feature = "longformer")]
+    pub fn new_longformer(
+        model_resource: Resource,
+        config_resource: Resource,
+        label_aggregation_function: LabelAggregationOption,
+    ) -> TokenClassificationConfig {
+        TokenClassificationConfig {
+            max_length: 4096,
+            ..TokenClassificationConfig::new(
+                ModelType::Longformer,
+                model_resource,
+                config_resource,
+                Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerVocabResources::LONGFORMER_BASE_4096,
+                )),
+                Some(Resource::Remote(RemoteResource::from_pretrained(
+                    LongformerMergesResources::LONGFORMER_BASE_4096,
+                ))),
+                false,
+                None,
+                None,
+                label_aggregation_function,
+            )
         }
     }
 }
@@ -274,7 +507,18 @@ impl Default for TokenClassificationConfig {
             strip_accents: None,
             add_prefix_space: None,
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            batch_size: 64,
+            max_length: 128,
+            sliding_window_stride: None,
+            label_mapping: None,
             label_aggregation_function: LabelAggregationOption::First,
+            aggregation_strategy: AggregationStrategy::None,
+            label_aggregation_scheme: TaggingScheme::Iob2,
+            word_aggregation_strategy: WordAggregationStrategy::First,
+            min_score: None,
+            multi_label: false,
+            use_crf: false,
         }
     }
 }
@@ -284,20 +528,28 @@ pub enum TokenClassificationOption {
     /// Bert for Token Classification
     Bert(BertForTokenClassification),
     /// DistilBert for Token Classification
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertForTokenClassification),
     /// MobileBert for Token Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForTokenClassification),
     /// Roberta for Token Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForTokenClassification),
     /// XLM Roberta for Token Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForTokenClassification),
     /// Electra for Token Classification
+    #[cfg(feature = "electra")]
     Electra(ElectraForTokenClassification),
     /// Albert for Token Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForTokenClassification),
     /// XLNet for Token Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForTokenClassification),
     /// Longformer for Token Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForTokenClassification),
 }
 
@@ -318,105 +570,114 @@ impl TokenClassificationOption {
     where
         P: Borrow<nn::Path<'p>>,
     {
+        model_type.ensure_enabled()?;
         match model_type {
             ModelType::Bert => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(TokenClassificationOption::Bert(
-                        BertForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Bert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bert,
+                    ConfigOption::Bert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::Bert(
+                    BertForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
-                if let ConfigOption::DistilBert(config) = config {
-                    Ok(TokenClassificationOption::DistilBert(
-                        DistilBertForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a DistilBertConfig for DistilBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::DistilBert,
+                    ConfigOption::DistilBert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::DistilBert(
+                    DistilBertForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
-                if let ConfigOption::MobileBert(config) = config {
-                    Ok(TokenClassificationOption::MobileBert(
-                        MobileBertForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a MobileBertConfig for MobileBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::MobileBert,
+                    ConfigOption::MobileBert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::MobileBert(
+                    MobileBertForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(TokenClassificationOption::Roberta(
-                        RobertaForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Roberta,
+                    ConfigOption::Bert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::Roberta(
+                    RobertaForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(TokenClassificationOption::XLMRoberta(
-                        RobertaForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for XLMRoberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLMRoberta,
+                    ConfigOption::Bert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::XLMRoberta(
+                    RobertaForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "electra")]
             ModelType::Electra => {
-                if let ConfigOption::Electra(config) = config {
-                    Ok(TokenClassificationOption::Electra(
-                        ElectraForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Electra,
+                    ConfigOption::Electra,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::Electra(
+                    ElectraForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
-                if let ConfigOption::Albert(config) = config {
-                    Ok(TokenClassificationOption::Albert(
-                        AlbertForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Albert,
+                    ConfigOption::Albert,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::Albert(
+                    AlbertForTokenClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
-                if let ConfigOption::XLNet(config) = config {
-                    Ok(TokenClassificationOption::XLNet(
-                        XLNetForTokenClassification::new(p, config).unwrap(),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLNet,
+                    ConfigOption::XLNet,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::XLNet(
+                    XLNetForTokenClassification::new(p, config).unwrap(),
+                ))
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
-                if let ConfigOption::Longformer(config) = config {
-                    Ok(TokenClassificationOption::Longformer(
-                        LongformerForTokenClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a LongformerConfig for Longformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Longformer,
+                    ConfigOption::Longformer,
+                    "Token classification"
+                )?;
+                Ok(TokenClassificationOption::Longformer(
+                    LongformerForTokenClassification::new(p, config),
+                ))
             }
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Token classification not implemented for {:?}!",
@@ -425,25 +686,63 @@ impl TokenClassificationOption {
         }
     }
 
+    /// Checks that the model type is implemented by the pipeline, without building the model
+    pub(crate) fn ensure_supported(model_type: ModelType) -> Result<(), RustBertError> {
+        match model_type {
+            ModelType::Bert => Ok(()),
+            #[cfg(feature = "distilbert")]
+            ModelType::DistilBert => Ok(()),
+            #[cfg(feature = "mobilebert")]
+            ModelType::MobileBert => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::Roberta => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::XLMRoberta => Ok(()),
+            #[cfg(feature = "electra")]
+            ModelType::Electra => Ok(()),
+            #[cfg(feature = "albert")]
+            ModelType::Albert => Ok(()),
+            #[cfg(feature = "xlnet")]
+            ModelType::XLNet => Ok(()),
+            #[cfg(feature = "longformer")]
+            ModelType::Longformer => Ok(()),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Token classification not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this TokenClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::XLMRoberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "electra")]
             Self::Electra(_) => ModelType::Electra,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
         }
     }
 
+    /// Interface method to forward_t() of the particular models. The `global_attention_mask` is
+    /// only used by Longformer models and ignored by the other architectures.
     fn forward_t(
         &self,
         input_ids: Option<Tensor>,
         mask: Option<Tensor>,
+        global_attention_mask: Option<Tensor>,
         token_type_ids: Option<Tensor>,
         position_ids: Option<Tensor>,
         input_embeds: Option<Tensor>,
@@ -462,12 +761,14 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(
@@ -481,6 +782,7 @@ impl TokenClassificationOption {
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -493,6 +795,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "electra")]
             Self::Electra(ref model) => {
                 model
                     .forward_t(
@@ -505,6 +808,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -517,6 +821,7 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -531,12 +836,13 @@ impl TokenClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
                         input_ids.as_ref(),
                         mask.as_ref(),
-                        None,
+                        global_attention_mask.as_ref(),
                         token_type_ids.as_ref(),
                         position_ids.as_ref(),
                         input_embeds.as_ref(),
@@ -556,6 +862,18 @@ pub struct TokenClassificationModel {
     label_mapping: HashMap<i64, String>,
     var_store: VarStore,
     label_aggregation_function: LabelAggregationOption,
+    aggregation_strategy: AggregationStrategy,
+    label_aggregation_scheme: TaggingScheme,
+    word_aggregation_strategy: WordAggregationStrategy,
+    min_score: Option<f64>,
+    multi_label: bool,
+    crf: Option<Crf>,
+    pad_to_multiple_of: Option<usize>,
+    batch_size: usize,
+    max_length: usize,
+    sliding_window_stride: Option<usize>,
+    load_report: LoadReport,
+    tie_word_embeddings: bool,
 }
 
 impl TokenClassificationModel {
@@ -578,17 +896,14 @@ impl TokenClassificationModel {
     pub fn new(
         config: TokenClassificationConfig,
     ) -> Result<TokenClassificationModel, RustBertError> {
-        let config_path = config.config_resource.get_local_path()?;
+        ensure_initialized();
+        validate_options(&config)?;
         let vocab_path = config.vocab_resource.get_local_path()?;
-        let weights_path = config.model_resource.get_local_path()?;
         let merges_path = if let Some(merges_resource) = &config.merges_resource {
             Some(merges_resource.get_local_path()?)
         } else {
             None
         };
-        let device = config.device;
-        let label_aggregation_function = config.label_aggregation_function;
-
         let tokenizer = TokenizerOption::from_file(
             config.model_type,
             vocab_path.to_str().unwrap(),
@@ -597,51 +912,332 @@ impl TokenClassificationModel {
             config.strip_accents,
             config.add_prefix_space,
         )?;
+        TokenClassificationModel::from_tokenizer(config, tokenizer)
+    }
+
+    /// Build a new `TokenClassificationModel` with a tokenizer built by the caller (e.g. with added
+    /// domain tokens that must not be split). The vocabulary and merges resources of the
+    /// configuration are not used (nor downloaded), and the tokenizer must match the model type of
+    /// the configuration. The tokenizer is used as provided to encode the inputs and compute the
+    /// token offsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TokenClassificationConfig` object containing the model and configuration resources and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` used to encode the inputs
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidConfigurationError` if the tokenizer cannot be used by `config.model_type`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::Bert,
+    ///     "path/to/vocab.txt",
+    ///     None,
+    ///     false,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let model = TokenClassificationModel::new_with_tokenizer(Default::default(), tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        config: TokenClassificationConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<TokenClassificationModel, RustBertError> {
+        ensure_initialized();
+        validate_options(&config)?;
+        tokenizer.ensure_compatible(config.model_type)?;
+        TokenClassificationModel::from_tokenizer(config, tokenizer)
+    }
+
+    fn from_tokenizer(
+        config: TokenClassificationConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<TokenClassificationModel, RustBertError> {
+        let config_path = config.config_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let device = config.device;
+        let label_aggregation_function = config.label_aggregation_function;
+        let aggregation_strategy = config.aggregation_strategy;
+        let label_aggregation_scheme = config.label_aggregation_scheme;
+        let word_aggregation_strategy = config.word_aggregation_strategy;
+        let min_score = config.min_score;
+        let multi_label = config.multi_label;
+        let batch_size = config.batch_size;
+        let max_length = config.max_length;
+        let sliding_window_stride = config.sliding_window_stride;
+
+        check_max_length(&tokenizer, max_length, sliding_window_stride)?;
         let mut var_store = VarStore::new(device);
-        let model_config = ConfigOption::from_file(config.model_type, config_path);
+        let model_config = ConfigOption::try_from_file(config.model_type, config_path)?;
         let token_sequence_classifier =
             TokenClassificationOption::new(config.model_type, &var_store.root(), &model_config)?;
-        let label_mapping = model_config.get_label_mapping();
-        var_store.load(weights_path)?;
+        let pad_to_multiple_of = model_config.resolve_pad_to_multiple_of(config.pad_to_multiple_of);
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let label_mapping = resolve_label_mapping(&model_config, config.label_mapping.as_ref())?;
+        let crf = if config.use_crf {
+            Some(Crf::new(
+                &var_store.root() / "crf",
+                label_mapping.len() as i64,
+            ))
+        } else {
+            None
+        };
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
         Ok(TokenClassificationModel {
             tokenizer,
             token_sequence_classifier,
             label_mapping,
             var_store,
+            load_report,
+            tie_word_embeddings,
             label_aggregation_function,
+            aggregation_strategy,
+            label_aggregation_scheme,
+            word_aggregation_strategy,
+            min_score,
+            multi_label,
+            crf,
+            pad_to_multiple_of,
+            batch_size,
+            max_length,
+            sliding_window_stride,
         })
     }
 
-    fn prepare_for_model<'a, S>(&self, input: S) -> (Vec<TokenizedInput>, Tensor)
-    where
-        S: AsRef<[&'a str]>,
-    {
-        let tokenized_input: Vec<TokenizedInput> =
+    /// Validates a configuration without downloading or loading the model weights. The configuration
+    /// and vocabulary files are fetched and parsed, and the checks run when building the pipeline
+    /// (supported model type, loadable tokenizer, label mapping) are collected in the returned
+    /// `ValidationReport` instead of failing on the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `TokenClassificationConfig` to validate
+    pub fn validate_config(
+        config: &TokenClassificationConfig,
+    ) -> Result<ValidationReport, RustBertError> {
+        let mut report = ValidationReport::new("token classification", config.model_type);
+        report.check(validate_options(config));
+        if config.model_type.is_enabled() {
+            report.check(TokenClassificationOption::ensure_supported(
+                config.model_type,
+            ));
+        }
+        let (model_config, tokenizer) = validate_resources(
+            &mut report,
+            &PipelineResources {
+                model_type: config.model_type,
+                model_resource: &config.model_resource,
+                config_resource: &config.config_resource,
+                vocab_resource: &config.vocab_resource,
+                merges_resource: config.merges_resource.as_ref(),
+                lower_case: config.lower_case,
+                strip_accents: config.strip_accents,
+                add_prefix_space: config.add_prefix_space,
+            },
+        );
+        if let Some(model_config) = &model_config {
+            report.check(resolve_label_mapping(
+                model_config,
+                config.label_mapping.as_ref(),
+            ));
+        }
+        if let Some(tokenizer) = &tokenizer {
+            report.check(check_max_length(
+                tokenizer,
+                config.max_length,
+                config.sliding_window_stride,
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Returns the report of the weights loaded when building the model, listing the tying status
+    /// of the output projections, the repaired tensor shapes and the unused checkpoint tensors.
+    /// The report is updated when the weights are replaced with `swap_weights`.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Replaces the weights of the model with a checkpoint sharing its architecture (e.g. another
+    /// fine-tuning of the same base model), keeping the tokenizer and configuration. The checkpoint
+    /// is validated against the model variables before any weight is overwritten: if the swap
+    /// fails, the current weights remain in use. The load report is replaced by the report of the
+    /// new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `Resource` pointing to the new weights
+    pub fn swap_weights(&mut self, model_resource: Resource) -> Result<(), RustBertError> {
+        let weights_path = model_resource.get_local_path()?;
+        self.load_report =
+            load_with_report(&mut self.var_store, weights_path, self.tie_word_embeddings)?;
+        Ok(())
+    }
+
+    /// Returns the strategy used to aggregate tokens into entities
+    pub fn aggregation_strategy(&self) -> AggregationStrategy {
+        self.aggregation_strategy
+    }
+
+    /// Returns the tagging scheme of the labels
+    pub fn label_aggregation_scheme(&self) -> TaggingScheme {
+        self.label_aggregation_scheme
+    }
+
+    /// Returns the label names of the classifier outputs, indexed by label id
+    pub fn label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
+    /// Returns the minimum score of the returned tokens and entities
+    pub fn min_score(&self) -> Option<f64> {
+        self.min_score
+    }
+
+    /// Returns true if the classification head is multi-label (independent sigmoid per label)
+    pub fn multi_label(&self) -> bool {
+        self.multi_label
+    }
+
+    /// Returns the device the model is placed on
+    pub fn get_device(&self) -> Device {
+        self.var_store.device()
+    }
+
+    /// Moves the model to another device. The input tensors of the subsequent predictions are
+    /// created on the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - `Device` to move the model to
+    pub fn set_device(&mut self, device: Device) {
+        self.var_store.set_device(device);
+    }
+
+    fn tokenize_inputs(&self, input: &[&str]) -> Vec<TokenizedInput> {
+        //    Single inputs (the most common case for online serving) skip the batching logic
+        if let [input] = input {
+            return vec![self.tokenizer.encode_pair(
+                input,
+                None,
+                self.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )];
+        }
+        self.tokenizer
+            .encode_list(input, self.max_length, &TruncationStrategy::LongestFirst, 0)
+    }
+
+    /// Splits an input into windows of at most `max_length` tokens including the special tokens,
+    /// consecutive windows overlapping by `stride` tokens. Returns the tokenized input without
+    /// truncation, and the windows with the position of their first token in the input tokens
+    /// (no windows if the input fits in a single sequence).
+    fn window_input(
+        &self,
+        input: &str,
+        stride: usize,
+    ) -> (TokenizedInput, Vec<(usize, TokenizedInput)>) {
+        let tokenized_input = self.tokenizer.tokenize_with_offsets(input);
+        let encoded_input = TokenIdsWithOffsets {
+            ids: self
+                .tokenizer
+                .convert_tokens_to_ids(&tokenized_input.tokens),
+            offsets: tokenized_input.offsets,
+            reference_offsets: tokenized_input.reference_offsets,
+            masks: tokenized_input.masks,
+        };
+        let window_length = self.max_length - single_special_tokens(&self.tokenizer);
+        let num_tokens = encoded_input.ids.len();
+
+        let mut windows = vec![];
+        if num_tokens > window_length {
+            let mut start_token = 0;
+            loop {
+                let end_token = min(start_token + window_length, num_tokens);
+                let window = TokenIdsWithOffsets {
+                    ids: encoded_input.ids[start_token..end_token].to_vec(),
+                    offsets: encoded_input.offsets[start_token..end_token].to_vec(),
+                    reference_offsets: encoded_input.reference_offsets[start_token..end_token]
+                        .to_vec(),
+                    masks: encoded_input.masks[start_token..end_token].to_vec(),
+                };
+                windows.push((
+                    start_token,
+                    self.tokenizer.build_input_with_special_tokens(window, None),
+                ));
+                if end_token == num_tokens {
+                    break;
+                }
+                start_token = end_token - stride;
+            }
+        }
+        (
             self.tokenizer
-                .encode_list(input.as_ref(), 128, &TruncationStrategy::LongestFirst, 0);
-        let max_len = tokenized_input
-            .iter()
-            .map(|input| input.token_ids.len())
-            .max()
-            .unwrap();
-        let tokenized_input_tensors: Vec<tch::Tensor> = tokenized_input
+                .build_input_with_special_tokens(encoded_input, None),
+            windows,
+        )
+    }
+
+    /// Pads the token ids of a batch to its longest input (rounded up to `pad_to_multiple_of`),
+    /// returning the input tensor and the attention mask of the padding (`None` without padding)
+    fn prepare_batch(
+        &self,
+        token_ids: &[&[i64]],
+    ) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .ok_or(RustBertError::MissingPadTokenError)?;
+        let max_len = token_ids.iter().map(|ids| ids.len()).max().unwrap();
+        let padded_len = get_padded_length(max_len, self.pad_to_multiple_of);
+        let device = self.var_store.device();
+        let input_tensors = token_ids
             .iter()
-            .map(|input| input.token_ids.clone())
-            .map(|mut input| {
-                input.extend(vec![
-                    self.tokenizer.get_pad_id().expect(
-                        "The Tokenizer used for token classification should contain a PAD id"
-                    );
-                    max_len - input.len()
-                ]);
-                input
+            .map(|ids| {
+                let mut ids = ids.to_vec();
+                ids.resize(padded_len, pad_id);
+                Tensor::of_slice(&ids)
             })
-            .map(|input| Tensor::of_slice(&(input)))
-            .collect::<Vec<_>>();
-        (
-            tokenized_input,
-            Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device()),
-        )
+            .collect::<Vec<Tensor>>();
+        let attention_mask = if token_ids.iter().all(|ids| ids.len() == padded_len) {
+            None
+        } else {
+            let masks = token_ids
+                .iter()
+                .map(|ids| {
+                    let mut mask = vec![1i64; ids.len()];
+                    mask.resize(padded_len, 0);
+                    Tensor::of_slice(&mask)
+                })
+                .collect::<Vec<Tensor>>();
+            Some(Tensor::stack(&masks, 0).to(device))
+        };
+        Ok((Tensor::stack(&input_tensors, 0).to(device), attention_mask))
+    }
+
+    /// Global attention mask of a batch for Longformer models, setting global attention on the
+    /// first token (CLS) of each sequence so that it attends (and is attended by) the full input.
+    /// Returns `None` for the other architectures.
+    fn global_attention_mask(&self, input_tensor: &Tensor) -> Option<Tensor> {
+        match self.token_sequence_classifier.model_type() {
+            ModelType::Longformer => {
+                let global_attention_mask = input_tensor.zeros_like();
+                let _ = global_attention_mask.narrow(1, 0, 1).fill_(1);
+                Some(global_attention_mask)
+            }
+            _ => None,
+        }
     }
 
     /// Classify tokens in a text sequence
@@ -655,6 +1251,9 @@ impl TokenClassificationModel {
     /// # Returns
     ///
     /// * `Vec<Vec<Token>>` containing Tokens with associated labels (for example POS tags) for each input provided
+    /// * `RustBertError::EmptyInputError` if no input is provided, `RustBertError::MissingPadTokenError`
+    ///   if the tokenizer does not define a padding token, `RustBertError::ValueError` if an input
+    ///   is empty after tokenization or if a predicted label id is missing from the label mapping
     ///
     /// # Example
     ///
@@ -667,7 +1266,7 @@ impl TokenClassificationModel {
     ///     "My name is Amy. I live in Paris.",
     ///     "Paris is a city in France.",
     /// ];
-    /// let output = ner_model.predict(&input, true, true);
+    /// let output = ner_model.predict(&input, true, true)?;
     /// # Ok(())
     /// # }
     /// ```
@@ -676,32 +1275,424 @@ impl TokenClassificationModel {
         input: S,
         consolidate_sub_tokens: bool,
         return_special: bool,
-    ) -> Vec<Vec<Token>>
+    ) -> Result<Vec<Vec<Token>>, RustBertError>
     where
         S: AsRef<[&'a str]>,
     {
-        let (tokenized_input, input_tensor) = self.prepare_for_model(input.as_ref());
-        let output = no_grad(|| {
-            self.token_sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            )
-        });
-        let output = output.detach().to(Device::Cpu);
-        let score: Tensor = output.exp() / output.exp().sum1(&[-1], true, Float);
-        let labels_idx = &score.argmax(-1, true);
+        let (mut tokens, _) = self.predict_tokens(input.as_ref(), return_special)?;
+        if consolidate_sub_tokens {
+            self.consolidate_tokens(
+                &mut tokens,
+                input.as_ref(),
+                &self.label_aggregation_function,
+            );
+        }
+        if let Some(min_score) = self.min_score {
+            for sequence_tokens in tokens.iter_mut() {
+                sequence_tokens.retain(|token| token.score >= min_score);
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Classify the tokens of a stream of texts, e.g. the lines of a corpus too large to be held in
+    /// memory. The texts are buffered in batches of `batch_size` texts, each batch being classified
+    /// as in `predict` (with consolidated sub-tokens and without special tokens), and the tokens of
+    /// each text are yielded in the input order. The `sentence` of the tokens is the index of their
+    /// text in the stream. If the classification of a batch fails, its texts are classified one by
+    /// one so that each text carries its own result.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Iterator over the texts to classify
+    /// * `batch_size` - Number of texts buffered and classified together (a batch size of 0 is
+    ///   treated as 1)
+    ///
+    /// # Returns
+    ///
+    /// * Iterator over the tokens (or the error) of each text, in the input order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    /// use std::fs::File;
+    /// use std::io::{BufRead, BufReader};
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let lines = BufReader::new(File::open("corpus.txt")?).lines();
+    /// for tokens in ner_model.predict_iter(lines.map(Result::unwrap), 32) {
+    ///     println!("{:?}", tokens?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_iter<'a, I>(
+        &'a self,
+        inputs: I,
+        batch_size: usize,
+    ) -> impl Iterator<Item = Result<Vec<Token>, RustBertError>> + 'a
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        let mut inputs = inputs.into_iter();
+        let batch_size = batch_size.max(1);
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            let batch = inputs.by_ref().take(batch_size).collect::<Vec<String>>();
+            if batch.is_empty() {
+                return None;
+            }
+            let batch_offset = offset;
+            offset += batch.len();
+            let texts = batch.iter().map(String::as_str).collect::<Vec<&str>>();
+            let outputs = match self.predict(&texts, true, false) {
+                Ok(output) => output.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(_) => texts
+                    .iter()
+                    .map(|text| {
+                        self.predict(&[*text], true, false)
+                            .map(|mut output| output.remove(0))
+                    })
+                    .collect::<Vec<_>>(),
+            };
+            Some(outputs.into_iter().enumerate().map(move |(index, output)| {
+                output.map(|mut tokens| {
+                    for token in tokens.iter_mut() {
+                        token.sentence = batch_offset + index;
+                    }
+                    tokens
+                })
+            }))
+        })
+        .flatten()
+    }
+
+    /// Classify tokens in a text sequence, passing the tokens of each batch of `batch_size` texts
+    /// (from the configuration) to a callback as soon as the batch is classified instead of
+    /// collecting the tokens of all texts. The batches are classified as in `predict` (with
+    /// consolidated sub-tokens and without special tokens), and the `sentence` of the tokens is
+    /// the index of their text in `input`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `f` - Callback called with the index of the text and each of its tokens, in the input order
+    ///
+    /// # Returns
+    ///
+    /// * `RustBertError` in the cases described for `predict`. The classification stops at the
+    ///   first batch that fails, the tokens of the previous batches having been passed to `f`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// ner_model.predict_with_callback(&input, |index, token| {
+    ///     println!("{}: {} ({})", index, token.text, token.label)
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_callback<'a, S, F>(&self, input: S, mut f: F) -> Result<(), RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+        F: FnMut(usize, Token),
+    {
+        let input = input.as_ref();
+        if input.is_empty() {
+            return Err(RustBertError::EmptyInputError);
+        }
+        for (batch_index, batch) in input.chunks(self.batch_size).enumerate() {
+            let batch_offset = batch_index * self.batch_size;
+            for (index, tokens) in self.predict(batch, true, false)?.into_iter().enumerate() {
+                for mut token in tokens {
+                    token.sentence = batch_offset + index;
+                    f(batch_offset + index, token);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Classify tokens in a text sequence, returning the probabilities of all labels for each
+    /// token in addition to the predicted label. The probabilities are read from the output of
+    /// the forward pass used for the prediction. Sub-tokens are not consolidated, and the
+    /// `min_score` of the configuration is not applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenScores>>` containing the tokens with the scores of all labels for each input provided
+    /// * `RustBertError` in the cases described for `predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_with_scores(&input, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_scores<'a, S>(
+        &self,
+        input: S,
+        return_special: bool,
+    ) -> Result<Vec<Vec<TokenScores>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let (tokens, scores) = self.predict_tokens(input.as_ref(), return_special)?;
+        let mut label_ids = self.label_mapping.keys().copied().collect::<Vec<i64>>();
+        label_ids.sort_unstable();
+        Ok(tokens
+            .into_iter()
+            .map(|sequence_tokens| {
+                sequence_tokens
+                    .into_iter()
+                    .map(|token| {
+                        let label_scores = label_ids
+                            .iter()
+                            .map(|label_id| {
+                                (
+                                    self.label_mapping[label_id].clone(),
+                                    scores[token.sentence]
+                                        .double_value(&[token.index as i64, *label_id]),
+                                )
+                            })
+                            .collect::<Vec<(String, f64)>>();
+                        TokenScores {
+                            token,
+                            label_scores,
+                        }
+                    })
+                    .collect::<Vec<TokenScores>>()
+            })
+            .collect::<Vec<Vec<TokenScores>>>())
+    }
+
+    /// Classify the words of a text sequence, returning a single token per word of the input. A
+    /// word is a sequence of characters delimited by whitespace (e.g. "New-York" or "don't" are
+    /// single words): the predictions of its sub-tokens are aggregated following the
+    /// `word_aggregation_strategy` of the configuration, and its text is the full word read from
+    /// the input. Special tokens are not returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Token>>` containing a token per word for each input provided
+    /// * `RustBertError` in the cases described for `predict`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::{
+    ///     TokenClassificationConfig, TokenClassificationModel, WordAggregationStrategy,
+    /// };
+    ///
+    /// let model = TokenClassificationModel::new(TokenClassificationConfig {
+    ///     word_aggregation_strategy: WordAggregationStrategy::AverageLogits,
+    ///     ..Default::default()
+    /// })?;
+    /// let output = model.predict_words(&["Unhappiness spread across New-York."])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_words<'a, S>(&self, input: S) -> Result<Vec<Vec<Token>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        let (tokens, scores) = self.predict_tokens(input, false)?;
+        let mut words = tokens
+            .iter()
+            .zip(input.iter())
+            .map(|(sequence_tokens, text)| {
+                split_whitespace_words(sequence_tokens, text)
+                    .into_iter()
+                    .map(|sub_tokens| {
+                        self.aggregate_word(
+                            sub_tokens,
+                            &scores,
+                            input,
+                            self.word_aggregation_strategy,
+                        )
+                    })
+                    .collect::<Result<Vec<Token>, RustBertError>>()
+            })
+            .collect::<Result<Vec<Vec<Token>>, RustBertError>>()?;
+        if let Some(min_score) = self.min_score {
+            for sequence_words in words.iter_mut() {
+                sequence_words.retain(|word| word.score >= min_score);
+            }
+        }
+        Ok(words)
+    }
+
+    /// Classify the words of a text sequence following the model `AggregationStrategy`: the
+    /// sub-tokens of each word are merged into a single token for the `First`, `Average` and `Max`
+    /// strategies, and returned as-is for the `None` and `Simple` strategies. Special tokens are
+    /// not returned. The minimum score is not applied, so that it can be applied to the entities.
+    pub(crate) fn predict_entity_tokens(
+        &self,
+        input: &[&str],
+    ) -> Result<Vec<Vec<Token>>, RustBertError> {
+        let (tokens, scores) = self.predict_tokens(input, false)?;
+        let word_aggregation_strategy = match self.aggregation_strategy {
+            AggregationStrategy::None | AggregationStrategy::Simple => return Ok(tokens),
+            AggregationStrategy::First => WordAggregationStrategy::First,
+            AggregationStrategy::Average => WordAggregationStrategy::Average,
+            AggregationStrategy::Max => WordAggregationStrategy::Max,
+        };
+        tokens
+            .iter()
+            .map(|sequence_tokens| {
+                sequence_tokens
+                    .iter_consolidate_tokens()
+                    .map(|sub_tokens| {
+                        self.aggregate_word(sub_tokens, &scores, input, word_aggregation_strategy)
+                    })
+                    .collect::<Result<Vec<Token>, RustBertError>>()
+            })
+            .collect()
+    }
+
+    /// Computes the scores (probabilities of shape (*sequence length*, *num_labels*) on the CPU)
+    /// of the tokens of each sequence, in forward passes of at most `batch_size` sequences. The
+    /// sequences are sorted by length before batching, so that each batch is padded to sequences
+    /// of similar lengths. The scores follow the order of the sequences.
+    fn forward_scores(&self, token_ids: &[&[i64]]) -> Result<Vec<Tensor>, RustBertError> {
+        let mut order = (0..token_ids.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&index| token_ids[index].len());
+        let mut scores: Vec<Option<Tensor>> = (0..token_ids.len()).map(|_| None).collect();
+        for batch in order.chunks(self.batch_size) {
+            let batch_token_ids = batch
+                .iter()
+                .map(|&index| token_ids[index])
+                .collect::<Vec<&[i64]>>();
+            let (input_tensor, attention_mask) = self.prepare_batch(&batch_token_ids)?;
+            let global_attention_mask = self.global_attention_mask(&input_tensor);
+            let output = no_grad(|| {
+                self.token_sequence_classifier.forward_t(
+                    Some(input_tensor),
+                    attention_mask,
+                    global_attention_mask,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+            });
+            let output = output.detach().to(Device::Cpu);
+            let batch_scores: Tensor = if self.multi_label {
+                output.sigmoid()
+            } else {
+                output.exp() / output.exp().sum1(&[-1], true, Float)
+            };
+            for (batch_index, &index) in batch.iter().enumerate() {
+                scores[index] = Some(batch_scores.get(batch_index as i64).narrow(
+                    0,
+                    0,
+                    token_ids[index].len() as i64,
+                ));
+            }
+        }
+        Ok(scores.into_iter().map(Option::unwrap).collect())
+    }
+
+    /// Classifies the tokens of the inputs, returning the tokens and the scores (probabilities of
+    /// shape (*sequence length*, *num_labels*) on the CPU) of each input. The inputs longer than
+    /// `max_length` are truncated, or split into windows if a `sliding_window_stride` is set.
+    fn predict_tokens(
+        &self,
+        input: &[&str],
+        return_special: bool,
+    ) -> Result<(Vec<Vec<Token>>, Vec<Tensor>), RustBertError> {
+        if input.is_empty() {
+            return Err(RustBertError::EmptyInputError);
+        }
+        let (tokenized_input, windows) = match self.sliding_window_stride {
+            None => {
+                let tokenized_input = self.tokenize_inputs(input);
+                let windows = vec![vec![]; tokenized_input.len()];
+                (tokenized_input, windows)
+            }
+            Some(stride) => input
+                .iter()
+                .map(|text| self.window_input(text, stride))
+                .unzip(),
+        };
+        let token_ids = tokenized_input
+            .iter()
+            .zip(windows.iter())
+            .flat_map(|(sentence_tokens, sentence_windows)| {
+                if sentence_windows.is_empty() {
+                    vec![sentence_tokens.token_ids.as_slice()]
+                } else {
+                    sentence_windows
+                        .iter()
+                        .map(|(_, window)| window.token_ids.as_slice())
+                        .collect()
+                }
+            })
+            .collect::<Vec<&[i64]>>();
+        if let Some(input_index) = tokenized_input
+            .iter()
+            .position(|sentence_tokens| content_positions(sentence_tokens).is_empty())
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Input {} is empty after tokenization",
+                input_index
+            )));
+        }
+        let mut sequence_scores = self.forward_scores(&token_ids)?.into_iter();
+        let scores = tokenized_input
+            .iter()
+            .zip(windows.iter())
+            .map(|(sentence_tokens, sentence_windows)| {
+                if sentence_windows.is_empty() {
+                    sequence_scores.next().unwrap()
+                } else {
+                    let window_scores = sequence_scores
+                        .by_ref()
+                        .take(sentence_windows.len())
+                        .collect::<Vec<Tensor>>();
+                    Self::merge_window_scores(sentence_tokens, sentence_windows, &window_scores)
+                }
+            })
+            .collect::<Vec<Tensor>>();
+
         let mut tokens: Vec<Vec<Token>> = vec![];
-        for sentence_idx in 0..labels_idx.size()[0] {
+        for (sentence_idx, (sentence_tokens, score)) in
+            tokenized_input.iter().zip(scores.iter()).enumerate()
+        {
             let mut sequence_tokens = vec![];
-            let labels = labels_idx.get(sentence_idx);
-            let sentence_tokens = &tokenized_input[sentence_idx as usize];
-            let original_chars = input.as_ref()[sentence_idx as usize]
-                .chars()
-                .collect::<Vec<char>>();
+            let labels = match &self.crf {
+                Some(crf) => Self::crf_labels(crf, sentence_tokens, score),
+                None => score.argmax(-1, false),
+            };
+            let original_chars = input[sentence_idx].chars().collect::<Vec<char>>();
             let mut word_idx: u16 = 0;
             for position_idx in 0..sentence_tokens.token_ids.len() {
                 let mask = sentence_tokens.mask[position_idx];
@@ -711,60 +1702,122 @@ impl TokenClassificationModel {
                 if !(mask == Mask::Continuation) {
                     word_idx += 1;
                 }
-                let token = {
-                    self.decode_token(
-                        &original_chars,
-                        sentence_tokens,
-                        &input_tensor,
-                        &labels,
-                        &score,
-                        sentence_idx,
-                        position_idx as i64,
-                        word_idx - 1,
-                    )
-                };
+                let token = self.decode_token(
+                    &original_chars,
+                    sentence_tokens,
+                    &labels,
+                    score,
+                    sentence_idx,
+                    position_idx,
+                    word_idx - 1,
+                )?;
                 sequence_tokens.push(token);
             }
             tokens.push(sequence_tokens);
         }
-        if consolidate_sub_tokens {
-            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        Ok((tokens, scores))
+    }
+
+    /// Decodes the labels of the input tokens with the CRF layer, the special tokens taking their
+    /// best label. The log-probabilities differ from the logits by a constant per token, which
+    /// does not change the decoded path.
+    fn crf_labels(crf: &Crf, tokenized_input: &TokenizedInput, scores: &Tensor) -> Tensor {
+        let best_labels = scores.argmax(-1, false);
+        let mut labels = (0..scores.size()[0])
+            .map(|position| best_labels.int64_value(&[position]))
+            .collect::<Vec<i64>>();
+        let positions = content_positions(tokenized_input);
+        let position_ids = Tensor::of_slice(
+            &positions
+                .iter()
+                .map(|&position| position as i64)
+                .collect::<Vec<i64>>(),
+        );
+        let path = crf.decode(&scores.index_select(0, &position_ids).log());
+        for (position, label) in positions.into_iter().zip(path) {
+            labels[position] = label;
         }
-        tokens
+        Tensor::of_slice(&labels)
+    }
+
+    /// Merges the scores of the windows of an input: each token takes the scores of the window
+    /// where it is furthest from the window edges. The special tokens preceding (following) the
+    /// input tokens take the scores of the special tokens of the first (last) window.
+    fn merge_window_scores(
+        tokenized_input: &TokenizedInput,
+        windows: &[(usize, TokenizedInput)],
+        window_scores: &[Tensor],
+    ) -> Tensor {
+        //    (distance to the window edges, window index, position in the window) of each token
+        let mut best_windows: Vec<Option<(usize, usize, usize)>> = vec![];
+        for (window_index, (start_token, window)) in windows.iter().enumerate() {
+            let positions = content_positions(window);
+            for (token_index, &position) in positions.iter().enumerate() {
+                let distance = min(token_index, positions.len() - 1 - token_index);
+                let input_index = start_token + token_index;
+                if input_index == best_windows.len() {
+                    best_windows.push(None);
+                }
+                let best_window = &mut best_windows[input_index];
+                if best_window.map_or(true, |(best_distance, _, _)| distance > best_distance) {
+                    *best_window = Some((distance, window_index, position));
+                }
+            }
+        }
+
+        let input_positions = content_positions(tokenized_input);
+        let first_position = input_positions[0];
+        let last_position = *input_positions.last().unwrap();
+        let last_window = windows.len() - 1;
+        let last_window_position = *content_positions(&windows[last_window].1).last().unwrap();
+        let mut best_windows = best_windows.into_iter().map(Option::unwrap);
+        let rows = (0..tokenized_input.token_ids.len())
+            .map(|position| {
+                if position < first_position {
+                    window_scores[0].get(position as i64)
+                } else if position > last_position {
+                    let window_position = last_window_position + position - last_position;
+                    window_scores[last_window].get(window_position as i64)
+                } else {
+                    let (_, window_index, window_position) = best_windows.next().unwrap();
+                    window_scores[window_index].get(window_position as i64)
+                }
+            })
+            .collect::<Vec<Tensor>>();
+        Tensor::stack(&rows, 0)
     }
 
     fn decode_token(
         &self,
         original_sentence_chars: &[char],
         sentence_tokens: &TokenizedInput,
-        input_tensor: &Tensor,
         labels: &Tensor,
         score: &Tensor,
-        sentence_idx: i64,
-        position_idx: i64,
+        sentence_idx: usize,
+        position_idx: usize,
         word_index: u16,
-    ) -> Token {
+    ) -> Result<Token, RustBertError> {
         let label_id = labels.int64_value(&[position_idx as i64]);
-        let token_id = input_tensor.int64_value(&[sentence_idx, position_idx as i64]);
+        let token_id = sentence_tokens.token_ids[position_idx];
 
-        let offsets = &sentence_tokens.token_offsets[position_idx as usize];
+        let offsets = &sentence_tokens.token_offsets[position_idx];
 
         let text = match offsets {
             None => match self.tokenizer {
                 TokenizerOption::Bert(ref tokenizer) => {
-                    Tokenizer::decode(tokenizer, vec![token_id], false, false)
+                    Tokenizer::decode(tokenizer, &[token_id], false, false)
                 }
                 TokenizerOption::Roberta(ref tokenizer) => {
-                    Tokenizer::decode(tokenizer, vec![token_id], false, false)
+                    Tokenizer::decode(tokenizer, &[token_id], false, false)
                 }
                 TokenizerOption::XLMRoberta(ref tokenizer) => {
-                    Tokenizer::decode(tokenizer, vec![token_id], false, false)
+                    Tokenizer::decode(tokenizer, &[token_id], false, false)
                 }
                 TokenizerOption::Albert(ref tokenizer) => {
-                    Tokenizer::decode(tokenizer, vec![token_id], false, false)
+                    Tokenizer::decode(tokenizer, &[token_id], false, false)
                 }
                 TokenizerOption::XLNet(ref tokenizer) => {
-                    Tokenizer::decode(tokenizer, vec![token_id], false, false)
+                    Tokenizer::decode(tokenizer, &[token_id], false, false)
                 }
                 _ => panic!(
                     "Token classification not implemented for {:?}!",
@@ -774,6 +1827,7 @@ impl TokenClassificationModel {
             Some(offsets) => {
                 let (start_char, end_char) = (offsets.begin as usize, offsets.end as usize);
                 let end_char = min(end_char, original_sentence_chars.len());
+                let start_char = min(start_char, end_char);
                 let text = original_sentence_chars[start_char..end_char]
                     .iter()
                     .collect();
@@ -781,26 +1835,34 @@ impl TokenClassificationModel {
             }
         };
 
-        Token {
+        Ok(Token {
             text,
-            score: score.double_value(&[sentence_idx, position_idx, label_id]),
-            label: self
-                .label_mapping
-                .get(&label_id)
-                .expect("Index out of vocabulary bounds.")
-                .to_owned(),
+            score: score.double_value(&[position_idx as i64, label_id]),
+            label: self.label(label_id)?,
             label_index: label_id,
-            sentence: sentence_idx as usize,
+            sentence: sentence_idx,
             index: position_idx as u16,
             word_index,
             offset: offsets.to_owned(),
-            mask: sentence_tokens.mask[position_idx as usize],
-        }
+            mask: sentence_tokens.mask[position_idx],
+        })
+    }
+
+    /// Returns the name of a label predicted by the model. Checkpoints with gaps in their id2label
+    /// mapping may predict label ids without a name.
+    fn label(&self, label_id: i64) -> Result<String, RustBertError> {
+        self.label_mapping.get(&label_id).cloned().ok_or_else(|| {
+            RustBertError::ValueError(format!(
+                "The label id {} predicted by the model is missing from the label mapping",
+                label_id
+            ))
+        })
     }
 
     fn consolidate_tokens(
         &self,
         tokens: &mut Vec<Vec<Token>>,
+        input: &[&str],
         label_aggregation_function: &LabelAggregationOption,
     ) {
         for sequence_tokens in tokens {
@@ -812,49 +1874,15 @@ impl TokenClassificationModel {
                 if sub_tokens.len() > 1 {
                     let (label_index, label) =
                         self.consolidate_labels(sub_tokens, label_aggregation_function);
-                    let sentence = (sub_tokens[0]).sentence;
-                    let index = (sub_tokens[0]).index;
-                    let word_index = (sub_tokens[0]).word_index;
-                    let offset_start = sub_tokens
-                        .first()
-                        .unwrap()
-                        .offset
-                        .as_ref()
-                        .map(|offset| offset.begin);
-                    let offset_end = sub_tokens
-                        .last()
-                        .unwrap()
-                        .offset
-                        .as_ref()
-                        .map(|offset| offset.end);
-                    let offset = if let (Some(offset_start), Some(offset_end)) =
-                        (offset_start, offset_end)
-                    {
-                        Some(Offset::new(offset_start, offset_end))
-                    } else {
-                        None
-                    };
-                    let mut text = String::new();
                     let mut score = 1f64;
                     for current_sub_token in sub_tokens.iter() {
-                        text.push_str(current_sub_token.text.as_str());
                         score *= if current_sub_token.label_index == label_index {
                             current_sub_token.score
                         } else {
                             1.0 - current_sub_token.score
                         };
                     }
-                    let token = Token {
-                        text,
-                        score,
-                        label,
-                        label_index,
-                        sentence,
-                        index,
-                        word_index,
-                        offset,
-                        mask: Default::default(),
-                    };
+                    let token = word_token(sub_tokens, input, label, label_index, score);
                     tokens_to_replace.push(((cursor, cursor + sub_tokens.len()), token));
                 }
                 cursor += sub_tokens.len();
@@ -865,6 +1893,55 @@ impl TokenClassificationModel {
         }
     }
 
+    fn aggregate_word(
+        &self,
+        sub_tokens: &[Token],
+        scores: &[Tensor],
+        input: &[&str],
+        strategy: WordAggregationStrategy,
+    ) -> Result<Token, RustBertError> {
+        if sub_tokens.len() == 1 {
+            return Ok(sub_tokens[0].clone());
+        }
+        let sub_token_scores = || {
+            let positions = sub_tokens
+                .iter()
+                .map(|token| token.index as i64)
+                .collect::<Vec<i64>>();
+            scores[sub_tokens[0].sentence].index_select(0, &Tensor::of_slice(&positions))
+        };
+        let (label_index, label_score) = match strategy {
+            WordAggregationStrategy::First => (sub_tokens[0].label_index, sub_tokens[0].score),
+            WordAggregationStrategy::Max => sub_tokens
+                .iter()
+                .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal))
+                .map(|token| (token.label_index, token.score))
+                .unwrap(),
+            WordAggregationStrategy::Average | WordAggregationStrategy::AverageLogits => {
+                let probabilities = if strategy == WordAggregationStrategy::Average {
+                    sub_token_scores().mean1(&[0], false, Float)
+                } else {
+                    //    The log-probabilities differ from the logits by a constant per token,
+                    //    which cancels out in the softmax of their average
+                    sub_token_scores()
+                        .log()
+                        .mean1(&[0], false, Float)
+                        .softmax(-1, Float)
+                };
+                let label_index = probabilities.argmax(-1, false).int64_value(&[]);
+                (label_index, probabilities.double_value(&[label_index]))
+            }
+        };
+        let label = self.label(label_index)?;
+        Ok(word_token(
+            sub_tokens,
+            input,
+            label,
+            label_index,
+            label_score,
+        ))
+    }
+
     fn consolidate_labels(
         &self,
         tokens: &[Token],
@@ -894,3 +1971,641 @@ impl TokenClassificationModel {
         }
     }
 }
+
+/// Splits the tokens of an input into the words delimited by whitespace in the input text.
+/// Sub-tokens continuing a word always belong to the word of the previous token.
+fn split_whitespace_words<'a>(tokens: &'a [Token], input: &str) -> Vec<&'a [Token]> {
+    let input_chars = input.chars().collect::<Vec<char>>();
+    let mut words = vec![];
+    let mut word_start = 0;
+    for position in 1..tokens.len() {
+        let starts_word = (tokens[position].mask != Mask::Continuation)
+            & match (tokens[position - 1].offset, tokens[position].offset) {
+                (Some(previous_offset), Some(offset)) => {
+                    let gap_start = min(previous_offset.end as usize, input_chars.len());
+                    let gap_end = min(offset.begin as usize, input_chars.len()).max(gap_start);
+                    input_chars[gap_start..gap_end]
+                        .iter()
+                        .any(|character| character.is_whitespace())
+                }
+                _ => true,
+            };
+        if starts_word {
+            words.push(&tokens[word_start..position]);
+            word_start = position;
+        }
+    }
+    if !tokens.is_empty() {
+        words.push(&tokens[word_start..]);
+    }
+    words
+}
+
+/// Positions of the input tokens (excluding the special tokens) of a tokenized input
+fn content_positions(tokenized_input: &TokenizedInput) -> Vec<usize> {
+    tokenized_input
+        .special_tokens_mask
+        .iter()
+        .enumerate()
+        .filter(|(_, &special)| special == 0)
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// Checks that the maximum length leaves room for the input tokens, and that the sliding windows
+/// progress through the inputs
+fn check_max_length(
+    tokenizer: &TokenizerOption,
+    max_length: usize,
+    sliding_window_stride: Option<usize>,
+) -> Result<(), RustBertError> {
+    let num_special_tokens = single_special_tokens(tokenizer);
+    if max_length <= num_special_tokens {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The maximum length ({}) must exceed the number of special tokens ({})",
+            max_length, num_special_tokens
+        )));
+    }
+    if let Some(stride) = sliding_window_stride {
+        let window_length = max_length - num_special_tokens;
+        if stride >= window_length {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The sliding window stride ({}) must be lower than the number of input tokens of a window ({})",
+                stride, window_length
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the pipeline options that do not depend on the model resources
+fn validate_options(config: &TokenClassificationConfig) -> Result<(), RustBertError> {
+    if config.batch_size == 0 {
+        return Err(RustBertError::InvalidConfigurationError(
+            "The batch size must be strictly positive".to_string(),
+        ));
+    }
+    //    The Viterbi decoding scores a single label per token from the softmax log-probabilities,
+    //    which does not apply to independent sigmoids
+    if config.use_crf & config.multi_label {
+        return Err(RustBertError::InvalidConfigurationError(
+            "CRF decoding is not supported for multi-label classification heads".to_string(),
+        ));
+    }
+    match config.min_score {
+        Some(min_score) if !(0.0..=1.0).contains(&min_score) => {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "The minimum score must be between 0 and 1, got {}",
+                min_score
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn word_token(
+    sub_tokens: &[Token],
+    input: &[&str],
+    label: String,
+    label_index: i64,
+    score: f64,
+) -> Token {
+    let first_token = sub_tokens.first().unwrap();
+    let offset = match (first_token.offset, sub_tokens.last().unwrap().offset) {
+        (Some(first_offset), Some(last_offset)) => {
+            Some(Offset::new(first_offset.begin, last_offset.end))
+        }
+        _ => None,
+    };
+    //    The text of the word is read from the input rather than concatenated from the sub-tokens,
+    //    as characters dropped by the tokenizer (e.g. zero-width joiners or variation selectors)
+    //    would otherwise be missing.
+    let text = match &offset {
+        Some(offset) => input[first_token.sentence]
+            .chars()
+            .skip(offset.begin as usize)
+            .take(offset.end.saturating_sub(offset.begin) as usize)
+            .collect::<String>(),
+        None => sub_tokens
+            .iter()
+            .map(|sub_token| sub_token.text.as_str())
+            .collect::<String>(),
+    };
+    Token {
+        text,
+        score,
+        label,
+        label_index,
+        sentence: first_token.sentence,
+        index: first_token.index,
+        word_index: first_token.word_index,
+        offset,
+        mask: Default::default(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::fixtures::tiny_bert_resources;
+
+    //    Builds a tiny BERT token classification model. With a `classifier_bias`, the model
+    //    predicts the same label scores for all tokens: the weights of the classifier are zeroed
+    //    and its bias set to `classifier_bias`.
+    pub(crate) fn tiny_bert_config(
+        directory: &std::path::Path,
+        classifier_bias: Option<&[f64]>,
+    ) -> anyhow::Result<TokenClassificationConfig> {
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "new", "york",
+            "pa", "##ris", ".",
+        ];
+        let labels = ["O", "B-LOC", "I-LOC", "B-PER", "I-PER"];
+        let (model_resource, config_resource, vocab_resource) =
+            tiny_bert_resources(directory, &vocab, &labels)?;
+        if let Some(classifier_bias) = classifier_bias {
+            let weights_path = model_resource.get_local_path()?;
+            let mut weights = Tensor::load_multi(&weights_path)?;
+            for (name, tensor) in weights.iter_mut() {
+                match name.as_str() {
+                    "classifier.weight" => *tensor = tensor.zeros_like(),
+                    "classifier.bias" => *tensor = Tensor::of_slice(classifier_bias).to_kind(Float),
+                    _ => {}
+                }
+            }
+            Tensor::save_multi(&weights, &weights_path)?;
+        }
+
+        let mut config = TokenClassificationConfig::new(
+            ModelType::Bert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+            LabelAggregationOption::First,
+        );
+        config.device = Device::Cpu;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_min_score() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        //    All tokens are tagged as I-LOC with a score of e / (e + 4) ~ 0.405
+        let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+        let input = ["the dog is in paris."];
+
+        let model = TokenClassificationModel::new(TokenClassificationConfig {
+            min_score: Some(0.4),
+            ..config
+        })?;
+        let tokens = model.predict(&input, false, false)?;
+        assert_eq!(tokens[0].len(), 7);
+        //    The threshold applies to the score of the consolidated word (~0.405 x 0.405 for
+        //    "paris"), not to its sub-tokens
+        let tokens = model.predict(&input, true, false)?;
+        assert_eq!(
+            tokens[0]
+                .iter()
+                .map(|token| token.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["the", "dog", "is", "in", "."]
+        );
+
+        let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+        let model = TokenClassificationModel::new(TokenClassificationConfig {
+            min_score: Some(0.5),
+            ..config
+        })?;
+        assert!(model.predict(&input, true, false)?[0].is_empty());
+
+        let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+        let invalid_config = TokenClassificationConfig {
+            min_score: Some(1.5),
+            ..config
+        };
+        assert!(!TokenClassificationModel::validate_config(&invalid_config)?.is_valid());
+        assert!(matches!(
+            TokenClassificationModel::new(invalid_config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+    #[test]
+    fn test_predict_with_scores() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+        let model = TokenClassificationModel::new(config)?;
+
+        let output = model.predict_with_scores(&["the dog is in paris."], false)?;
+        assert_eq!(output[0].len(), 7);
+        for token_scores in &output[0] {
+            assert_eq!(token_scores.token.label, "I-LOC");
+            assert_eq!(
+                token_scores
+                    .label_scores
+                    .iter()
+                    .map(|(label, _)| label.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["O", "B-LOC", "I-LOC", "B-PER", "I-PER"]
+            );
+            let total = token_scores
+                .label_scores
+                .iter()
+                .map(|(_, score)| score)
+                .sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-4);
+            let expected = 1f64.exp() / (1f64.exp() + 4.0);
+            assert!((token_scores.label_scores[2].1 - expected).abs() < 1e-4);
+            assert!((token_scores.token.score - expected).abs() < 1e-4);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_predict_words() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let input = ["the  new-york dog is in paris."];
+        let expected_score = 1f64.exp() / (1f64.exp() + 4.0);
+        for &word_aggregation_strategy in &[
+            WordAggregationStrategy::First,
+            WordAggregationStrategy::Max,
+            WordAggregationStrategy::Average,
+            WordAggregationStrategy::AverageLogits,
+        ] {
+            let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+            let model = TokenClassificationModel::new(TokenClassificationConfig {
+                word_aggregation_strategy,
+                ..config
+            })?;
+            let words = model.predict_words(&input)?;
+            assert_eq!(
+                words[0]
+                    .iter()
+                    .map(|word| (word.text.as_str(), word.label.as_str()))
+                    .collect::<Vec<(&str, &str)>>(),
+                vec![
+                    ("the", "I-LOC"),
+                    ("new-york", "I-LOC"),
+                    ("dog", "I-LOC"),
+                    ("is", "I-LOC"),
+                    ("in", "I-LOC"),
+                    ("paris.", "I-LOC")
+                ]
+            );
+            assert_eq!(words[0][1].offset, Some(Offset::new(5, 13)));
+            for word in &words[0] {
+                assert!((word.score - expected_score).abs() < 1e-4);
+            }
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_batch_size() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = TokenClassificationModel::new(tiny_bert_config(directory.path(), None)?)?;
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.batch_size = 2;
+        let batched_model = TokenClassificationModel::new(config)?;
+        let inputs = [
+            "the dog is in paris. the dog is in new york. the dog is in paris.",
+            "paris.",
+            "the dog is in new york.",
+            "dog",
+            "the dog is in paris.",
+        ];
+
+        //    The inputs are sorted by length and batched in pairs, the padding being masked
+        let expected = model.predict(&inputs, true, false)?;
+        let output = batched_model.predict(&inputs, true, false)?;
+        assert_eq!(output.len(), inputs.len());
+        for (sentence_idx, (tokens, expected_tokens)) in
+            output.iter().zip(expected.iter()).enumerate()
+        {
+            assert_eq!(tokens.len(), expected_tokens.len());
+            for (token, expected_token) in tokens.iter().zip(expected_tokens.iter()) {
+                assert_eq!(token.sentence, sentence_idx);
+                assert_eq!(token.text, expected_token.text);
+                assert_eq!(token.label, expected_token.label);
+                assert!((token.score - expected_token.score).abs() < 1e-5);
+            }
+        }
+        //    Single inputs are not padded
+        let single_output = model.predict(&[inputs[3]], true, false)?;
+        assert!((single_output[0][0].score - output[3][0].score).abs() < 1e-5);
+
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.batch_size = 0;
+        assert!(matches!(
+            TokenClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sliding_window() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = TokenClassificationModel::new(tiny_bert_config(directory.path(), None)?)?;
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.max_length = 8;
+        config.sliding_window_stride = Some(2);
+        let windowed_model = TokenClassificationModel::new(config)?;
+
+        //    Inputs fitting in a single window are processed as a single sequence
+        let short_input = ["the dog is in paris."];
+        let expected = model.predict(&short_input, true, true)?;
+        let output = windowed_model.predict(&short_input, true, true)?;
+        assert_eq!(output[0].len(), expected[0].len());
+        for (token, expected_token) in output[0].iter().zip(expected[0].iter()) {
+            assert_eq!(token.text, expected_token.text);
+            assert_eq!(token.label, expected_token.label);
+            assert!((token.score - expected_token.score).abs() < 1e-5);
+        }
+
+        //    Longer inputs are split into windows of 6 tokens overlapping by 2 tokens, the tokens
+        //    past the first window keeping offsets in the original input
+        let long_input = "the dog is in paris. the dog is in new york.";
+        let output = windowed_model.predict(&[long_input], false, true)?;
+        let texts = output[0]
+            .iter()
+            .map(|token| token.text.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            texts,
+            [
+                "[CLS]", "the", "dog", "is", "in", "pa", "##ris", ".", "the", "dog", "is", "in",
+                "new", "york", ".", "[SEP]"
+            ]
+        );
+        let last_token = &output[0][output[0].len() - 2];
+        assert_eq!(
+            last_token.offset.map(|offset| (offset.begin, offset.end)),
+            Some((43, 44))
+        );
+        let entities = windowed_model.predict(&[long_input], true, false)?;
+        assert_eq!(entities[0].len(), 13);
+        assert_eq!(
+            entities[0]
+                .last()
+                .unwrap()
+                .offset
+                .map(|offset| offset.begin),
+            Some(43)
+        );
+
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.max_length = 8;
+        config.sliding_window_stride = Some(6);
+        assert!(matches!(
+            TokenClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_tokenizer() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let config = tiny_bert_config(directory.path(), None)?;
+        let vocab_path = directory.path().join("vocab.txt");
+        let vocab_path = vocab_path.to_str().unwrap();
+        let reference = TokenClassificationModel::new(config)?;
+
+        let tokenizer =
+            TokenizerOption::from_file(ModelType::Bert, vocab_path, None, true, None, None)?;
+        //    The vocabulary resource is not read when the tokenizer is provided
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.vocab_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_vocab.txt"),
+        });
+        let model = TokenClassificationModel::new_with_tokenizer(config, tokenizer)?;
+        let input = ["the dog is in new york.", "paris"];
+        let expected = reference.predict(&input, true, false)?;
+        let output = model.predict(&input, true, false)?;
+        assert_eq!(output.len(), expected.len());
+        for (tokens, expected_tokens) in output.iter().zip(expected.iter()) {
+            assert_eq!(tokens.len(), expected_tokens.len());
+            for (token, expected_token) in tokens.iter().zip(expected_tokens.iter()) {
+                assert_eq!(token.text, expected_token.text);
+                assert_eq!(token.label, expected_token.label);
+                assert_eq!(token.offset, expected_token.offset);
+            }
+        }
+
+        let tokenizer =
+            TokenizerOption::from_file(ModelType::Bert, vocab_path, None, true, None, None)?;
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.model_type = ModelType::Roberta;
+        assert!(matches!(
+            TokenClassificationModel::new_with_tokenizer(config, tokenizer),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_mapping() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let label_mapping = ["O", "B-PER", "I-PER", "B-ORG", "I-ORG"]
+            .iter()
+            .enumerate()
+            .map(|(id, label)| (id as i64, label.to_string()))
+            .collect::<HashMap<i64, String>>();
+        let mut config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 1.0, 0.0, 0.0]))?;
+        config.label_mapping = Some(label_mapping.clone());
+        let model = TokenClassificationModel::new(config)?;
+        let output = model.predict(&["the dog is in new york."], true, false)?;
+        for token in output[0].iter() {
+            assert_eq!(token.label, "I-PER");
+            assert_eq!(token.label_index, 2);
+        }
+        let output = model.predict_with_scores(&["dog"], false)?;
+        let labels = output[0][0]
+            .label_scores
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(labels, ["O", "B-PER", "I-PER", "B-ORG", "I-ORG"]);
+
+        //    The mapping must cover exactly the 5 classes of the classifier head
+        let mut incomplete_mapping = label_mapping.clone();
+        incomplete_mapping.remove(&4);
+        let mut extended_mapping = label_mapping;
+        extended_mapping.insert(5, "B-MISC".to_string());
+        for invalid_mapping in &[incomplete_mapping, extended_mapping] {
+            let mut config = tiny_bert_config(directory.path(), None)?;
+            config.label_mapping = Some(invalid_mapping.clone());
+            let report = TokenClassificationModel::validate_config(&config)?;
+            assert!(!report.is_valid());
+            assert!(report.errors[0].contains("label mapping"));
+            assert!(matches!(
+                TokenClassificationModel::new(config),
+                Err(RustBertError::InvalidConfigurationError(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_prediction_errors() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = TokenClassificationModel::new(tiny_bert_config(directory.path(), None)?)?;
+        let no_input: [&str; 0] = [];
+        assert!(matches!(
+            model.predict(&no_input, true, false),
+            Err(RustBertError::EmptyInputError)
+        ));
+        assert!(matches!(
+            model.predict(&["the dog", " "], true, false),
+            Err(RustBertError::ValueError(message)) if message.contains("Input 1")
+        ));
+
+        //    The checkpoint has no name for the label id 4, predicted for all tokens
+        let config = tiny_bert_config(directory.path(), Some(&[0.0, 0.0, 0.0, 0.0, 1.0]))?;
+        let config_path = config.config_resource.get_local_path()?;
+        let config_json =
+            std::fs::read_to_string(&config_path)?.replace(r#""4": "I-PER""#, r#""5": "I-PER""#);
+        std::fs::write(&config_path, config_json)?;
+        let model = TokenClassificationModel::new(config)?;
+        assert!(matches!(
+            model.predict(&["the dog"], true, false),
+            Err(RustBertError::ValueError(message)) if message.contains("label id 4")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_with_callback() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_bert_config(directory.path(), None)?;
+        config.batch_size = 2;
+        let model = TokenClassificationModel::new(config)?;
+        let inputs = [
+            "the dog is in paris.",
+            "dog",
+            "the dog is in new york.",
+            "paris.",
+        ];
+        let expected = model.predict(&inputs, true, false)?;
+
+        let mut output = vec![vec![]; inputs.len()];
+        model.predict_with_callback(&inputs, |index, token| output[index].push(token))?;
+        for (index, (tokens, expected_tokens)) in output.iter().zip(expected.iter()).enumerate() {
+            assert_eq!(tokens.len(), expected_tokens.len());
+            for (token, expected_token) in tokens.iter().zip(expected_tokens.iter()) {
+                assert_eq!(token.sentence, index);
+                assert_eq!(token.text, expected_token.text);
+                assert_eq!(token.label, expected_token.label);
+                assert!((token.score - expected_token.score).abs() < 1e-5);
+            }
+        }
+
+        //    The tokens of the batches preceding a failed batch are passed to the callback
+        let mut indices = vec![];
+        let result = model.predict_with_callback(&["dog", "paris.", " ", "dog"], |index, _| {
+            indices.push(index)
+        });
+        assert!(matches!(result, Err(RustBertError::ValueError(_))));
+        assert!(indices.contains(&1));
+        assert!(indices.iter().all(|index| *index < 2));
+        let no_input: [&str; 0] = [];
+        assert!(matches!(
+            model.predict_with_callback(&no_input, |_, _| {}),
+            Err(RustBertError::EmptyInputError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_crf() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let classifier_bias = [0.0, 0.0, 1.0, 0.0, 0.0];
+        let input = ["the dog is in new york."];
+        let mut config = tiny_bert_config(directory.path(), Some(&classifier_bias))?;
+        config.use_crf = true;
+        assert!(matches!(
+            TokenClassificationModel::new(config),
+            Err(RustBertError::ValueError(_))
+        ));
+        let mut multi_label_config = tiny_bert_config(directory.path(), Some(&classifier_bias))?;
+        multi_label_config.use_crf = true;
+        multi_label_config.multi_label = true;
+        assert!(matches!(
+            TokenClassificationModel::new(multi_label_config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        //    I-LOC cannot follow any label, and B-PER is favoured after any label
+        let argmax_config = tiny_bert_config(directory.path(), Some(&classifier_bias))?;
+        let mut crf_config = tiny_bert_config(directory.path(), Some(&classifier_bias))?;
+        crf_config.use_crf = true;
+        let weights_path = directory.path().join("rust_model.ot");
+        let mut tensors = Tensor::load_multi(&weights_path)?;
+        let transitions = Tensor::of_slice(&[0.0f32, 0.0, -10.0, 5.0, 0.0])
+            .view([1, 5])
+            .repeat(&[5, 1]);
+        tensors.push(("crf.transitions".to_string(), transitions));
+        Tensor::save_multi(&tensors, &weights_path)?;
+
+        let model = TokenClassificationModel::new(argmax_config)?;
+        assert_eq!(model.last_load_report().unused_tensors, ["crf.transitions"]);
+        let output = model.predict(&input, false, true)?;
+        assert!(output[0].iter().all(|token| token.label == "I-LOC"));
+
+        let model = TokenClassificationModel::new(crf_config)?;
+        assert!(model.last_load_report().unused_tensors.is_empty());
+        let output = model.predict(&input, false, true)?;
+        let labels = output[0]
+            .iter()
+            .map(|token| token.label.as_str())
+            .collect::<Vec<&str>>();
+        //    The special tokens keep their best label
+        assert_eq!(
+            labels,
+            ["I-LOC", "I-LOC", "B-PER", "B-PER", "B-PER", "B-PER", "B-PER", "B-PER", "I-LOC"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_iter() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = TokenClassificationModel::new(tiny_bert_config(directory.path(), None)?)?;
+        let inputs = [
+            "the dog is in paris.",
+            "dog",
+            "the dog is in new york.",
+            " ",
+            "paris.",
+        ];
+        let expected = inputs
+            .iter()
+            .map(|input| model.predict(&[*input], true, false))
+            .collect::<Vec<_>>();
+
+        //    The empty input fails on its own, without failing the rest of its batch
+        let output = model
+            .predict_iter(inputs.iter().map(|input| input.to_string()), 2)
+            .collect::<Vec<_>>();
+        assert_eq!(output.len(), inputs.len());
+        for (index, (tokens, expected_tokens)) in output.iter().zip(expected.iter()).enumerate() {
+            match (tokens, expected_tokens) {
+                (Ok(tokens), Ok(expected_tokens)) => {
+                    assert_eq!(tokens.len(), expected_tokens[0].len());
+                    for (token, expected_token) in tokens.iter().zip(expected_tokens[0].iter()) {
+                        assert_eq!(token.sentence, index);
+                        assert_eq!(token.text, expected_token.text);
+                        assert_eq!(token.label, expected_token.label);
+                        assert!((token.score - expected_token.score).abs() < 1e-5);
+                    }
+                }
+                (Err(_), Err(_)) => assert_eq!(index, 3),
+                _ => panic!("Unexpected result for input {}", index),
+            }
+        }
+        Ok(())
+    }
+}
@@ -57,15 +57,19 @@
 use tch::{Device, Tensor};
 
 use crate::common::error::RustBertError;
+use crate::common::initialization::ensure_initialized;
 use crate::common::resources::{RemoteResource, Resource};
 use crate::marian::{
     MarianConfigResources, MarianGenerator, MarianModelResources, MarianPrefix, MarianSpmResources,
     MarianVocabResources,
 };
-use crate::pipelines::common::ModelType;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::common::{split_sentences, ModelType};
+use crate::pipelines::generation_utils::{DecodingStrategy, GenerateConfig, LanguageGenerator};
 use crate::t5::{T5ConfigResources, T5Generator, T5ModelResources, T5Prefix, T5VocabResources};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 /// Pretrained languages available for direct use
 pub enum Language {
     FrenchToEnglish,
@@ -103,6 +107,46 @@ pub enum Language {
     GermanToFrench,
 }
 
+impl Language {
+    /// Returns the ISO 639-1 code of the target language (e.g. `fr` for `EnglishToFrench`)
+    pub fn target_language_code(&self) -> &'static str {
+        match self {
+            Language::FrenchToEnglish
+            | Language::CatalanToEnglish
+            | Language::SpanishToEnglish
+            | Language::PortugueseToEnglish
+            | Language::ItalianToEnglish
+            | Language::RomanianToEnglish
+            | Language::GermanToEnglish
+            | Language::RussianToEnglish
+            | Language::DutchToEnglish
+            | Language::ChineseToEnglish
+            | Language::SwedishToEnglish
+            | Language::ArabicToEnglish
+            | Language::HindiToEnglish
+            | Language::HebrewToEnglish => "en",
+            Language::EnglishToFrench | Language::EnglishToFrenchV2 | Language::GermanToFrench => {
+                "fr"
+            }
+            Language::EnglishToCatalan => "ca",
+            Language::EnglishToSpanish => "es",
+            Language::EnglishToPortuguese => "pt",
+            Language::EnglishToItalian => "it",
+            Language::EnglishToRomanian => "ro",
+            Language::EnglishToGerman | Language::EnglishToGermanV2 | Language::FrenchToGerman => {
+                "de"
+            }
+            Language::EnglishToRussian => "ru",
+            Language::EnglishToDutch => "nl",
+            Language::EnglishToChineseSimplified | Language::EnglishToChineseTraditional => "zh",
+            Language::EnglishToSwedish => "sv",
+            Language::EnglishToArabic => "ar",
+            Language::EnglishToHindi => "hi",
+            Language::EnglishToHebrew => "he",
+        }
+    }
+}
+
 struct RemoteTranslationResources {
     model_resource: (&'static str, &'static str),
     config_resource: (&'static str, &'static str),
@@ -423,8 +467,13 @@ pub struct TranslationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Explicit decoding strategy. If provided, takes precedence over `do_sample`, `num_beams`, `temperature`, `top_k` and `top_p` (default: None)
+    pub decoding_strategy: Option<DecodingStrategy>,
     /// Model type used for translation
     pub model_type: ModelType,
+    /// Tag of the inline markers delimiting spans to keep untranslated with `translate_each`, e.g.
+    /// `<keep>ProductName</keep>` (default: `keep`). No marker is parsed if `None`.
+    pub keep_tag: Option<String>,
 }
 
 impl TranslationConfig {
@@ -524,7 +573,9 @@ impl TranslationConfig {
             prefix,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
             model_type: translation_resource.model_type,
+            keep_tag: Some("keep".to_string()),
         }
     }
 
@@ -603,7 +654,9 @@ impl TranslationConfig {
             prefix,
             num_beam_groups: None,
             diversity_penalty: None,
+            decoding_strategy: None,
             model_type,
+            keep_tag: Some("keep".to_string()),
         }
     }
 }
@@ -629,6 +682,8 @@ impl From<TranslationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            decoding_strategy: config.decoding_strategy,
+            decoder_step_hook: None,
             device: config.device,
         }
     }
@@ -706,10 +761,324 @@ impl TranslationOption {
     }
 }
 
+/// # Inline markers delimiting spans to keep untranslated
+/// Spans enclosed in the markers (e.g. `<keep>ProductName</keep>`) are replaced by sentinels before
+/// translation and restored with their exact casing in the translated text.
+#[derive(Clone, Debug)]
+pub struct KeepMarkers {
+    tag: String,
+    opening: String,
+    closing: String,
+}
+
+/// # Input text with the spans to keep replaced by sentinels
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtectedText {
+    /// Text with markers stripped and spans replaced by sentinels, to pass to the translation model
+    pub text: String,
+    /// Original spans, in the order of their sentinels
+    pub spans: Vec<String>,
+}
+
+impl KeepMarkers {
+    /// Create new markers for the given tag (e.g. `keep` for `<keep>...</keep>`)
+    pub fn new(tag: &str) -> KeepMarkers {
+        KeepMarkers {
+            tag: tag.to_string(),
+            opening: format!("<{}>", tag),
+            closing: format!("</{}>", tag),
+        }
+    }
+
+    fn sentinel(&self, index: usize) -> String {
+        format!("__{}{}__", self.tag.to_uppercase(), index)
+    }
+
+    /// Strips the markers from the input and replaces the spans they enclose with sentinels.
+    /// Returns a `ValueError` for nested or unbalanced markers.
+    pub fn protect(&self, text: &str) -> Result<ProtectedText, RustBertError> {
+        let mut protected = String::with_capacity(text.len());
+        let mut spans = vec![];
+        let mut remaining = text;
+        loop {
+            let opening_position = remaining.find(self.opening.as_str());
+            let closing_position = remaining.find(self.closing.as_str());
+            match (opening_position, closing_position) {
+                (None, None) => {
+                    protected.push_str(remaining);
+                    break;
+                }
+                (Some(opening), Some(closing)) if opening < closing => {
+                    let span_start = opening + self.opening.len();
+                    let span = &remaining[span_start..closing];
+                    if span.contains(self.opening.as_str()) {
+                        return Err(RustBertError::ValueError(format!(
+                            "Nested {} markers in input: {}",
+                            self.opening, text
+                        )));
+                    }
+                    protected.push_str(&remaining[..opening]);
+                    protected.push_str(&self.sentinel(spans.len()));
+                    spans.push(span.to_string());
+                    remaining = &remaining[closing + self.closing.len()..];
+                }
+                (Some(_), None) => {
+                    return Err(RustBertError::ValueError(format!(
+                        "Unclosed {} marker in input: {}",
+                        self.opening, text
+                    )));
+                }
+                (_, Some(_)) => {
+                    return Err(RustBertError::ValueError(format!(
+                        "Unmatched {} marker in input: {}",
+                        self.closing, text
+                    )));
+                }
+            }
+        }
+        Ok(ProtectedText {
+            text: protected,
+            spans,
+        })
+    }
+
+    /// Replaces the sentinels in a translation by the original spans. Returns a `ValueError` if a
+    /// sentinel was not preserved by the translation model.
+    pub fn restore(&self, translation: &str, spans: &[String]) -> Result<String, RustBertError> {
+        let mut restored = translation.to_string();
+        for (index, span) in spans.iter().enumerate() {
+            let sentinel = self.sentinel(index);
+            if !restored.contains(sentinel.as_str()) {
+                return Err(RustBertError::ValueError(format!(
+                    "The translation model dropped the span \"{}\" (sentinel {}): {}",
+                    span, sentinel, translation
+                )));
+            }
+            restored = restored.replacen(sentinel.as_str(), span, 1);
+        }
+        Ok(restored)
+    }
+
+    /// Translates the texts with the provided translation function, keeping the marked spans
+    /// untranslated. Inputs with invalid markers or losing a span during translation get an error,
+    /// other inputs are translated as a single batch.
+    pub fn translate_with<F>(
+        &self,
+        texts: &[&str],
+        translate: F,
+    ) -> Vec<Result<String, RustBertError>>
+    where
+        F: FnOnce(&[&str]) -> Vec<String>,
+    {
+        let protected_texts = texts
+            .iter()
+            .map(|text| self.protect(text))
+            .collect::<Vec<Result<ProtectedText, RustBertError>>>();
+        let valid_texts = protected_texts
+            .iter()
+            .filter_map(|protected| protected.as_ref().ok())
+            .map(|protected| protected.text.as_str())
+            .collect::<Vec<&str>>();
+        let mut translations = if valid_texts.is_empty() {
+            vec![]
+        } else {
+            translate(&valid_texts)
+        }
+        .into_iter();
+        protected_texts
+            .into_iter()
+            .map(|protected| {
+                let protected = protected?;
+                let translation = translations.next().ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "The translation model returned fewer outputs than inputs".to_string(),
+                    )
+                })?;
+                self.restore(&translation, &protected.spans)
+            })
+            .collect()
+    }
+}
+
+/// # Language detected for a text
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code of the language (e.g. `en`)
+    pub language: String,
+    /// Confidence of the detection
+    pub score: f64,
+}
+
+/// # Translation of an input
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TranslationOutput {
+    /// Translated text, or input text if it was passed through
+    pub text: String,
+    /// True if the input was detected as already being in the target language and returned
+    /// unchanged (for per-sentence detection, if all its sentences were)
+    pub passed_through: bool,
+}
+
+/// # Pre-filter passing through the inputs already in the target language
+/// Inputs detected as being in the target language with a confidence greater or equal to the
+/// threshold are not sent to the translation model, which may otherwise alter them. With
+/// per-sentence detection, inputs are split into sentences and only the sentences not in the
+/// target language are translated, the whitespace between sentences being preserved.
+pub struct TargetLanguageFilter {
+    detector: Box<dyn Fn(&str) -> Option<DetectedLanguage> + Send + Sync>,
+    target_language: String,
+    threshold: f64,
+    per_sentence: bool,
+}
+
+impl TargetLanguageFilter {
+    /// Create a new filter
+    ///
+    /// # Arguments
+    ///
+    /// * `detector` - language detector, returning `None` if the language of a text is unknown
+    /// * `target_language` - ISO 639-1 code of the target language (e.g. from `Language::target_language_code`)
+    /// * `threshold` - minimum confidence of a target language detection for a text to be passed through
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::translation::{DetectedLanguage, Language, TargetLanguageFilter};
+    ///
+    /// let filter = TargetLanguageFilter::new(
+    ///     |text: &str| {
+    ///         Some(DetectedLanguage {
+    ///             language: if text.contains(" le ") { "fr" } else { "en" }.to_string(),
+    ///             score: 0.9,
+    ///         })
+    ///     },
+    ///     Language::EnglishToFrench.target_language_code(),
+    ///     0.8,
+    /// )
+    /// .per_sentence(true);
+    /// ```
+    pub fn new<F>(detector: F, target_language: &str, threshold: f64) -> TargetLanguageFilter
+    where
+        F: Fn(&str) -> Option<DetectedLanguage> + Send + Sync + 'static,
+    {
+        TargetLanguageFilter {
+            detector: Box::new(detector),
+            target_language: target_language.to_string(),
+            threshold,
+            per_sentence: false,
+        }
+    }
+
+    /// Enables the detection of the language of each sentence instead of each input
+    pub fn per_sentence(mut self, per_sentence: bool) -> TargetLanguageFilter {
+        self.per_sentence = per_sentence;
+        self
+    }
+
+    /// Returns true if the text is detected as being in the target language with a confidence
+    /// greater or equal to the threshold
+    pub fn is_target_language(&self, text: &str) -> bool {
+        (self.detector)(text).map_or(false, |detected| {
+            (detected.language == self.target_language) & (detected.score >= self.threshold)
+        })
+    }
+
+    /// Splits a text into the segments to translate: the sentences for per-sentence detection,
+    /// the whole text otherwise. Returns the byte range of each segment and whether it is passed through.
+    fn segments(&self, text: &str) -> Vec<(Range<usize>, bool)> {
+        if self.per_sentence {
+            split_sentences(text)
+                .into_iter()
+                .map(|sentence| {
+                    let start = sentence.as_ptr() as usize - text.as_ptr() as usize;
+                    (
+                        start..start + sentence.len(),
+                        self.is_target_language(sentence),
+                    )
+                })
+                .collect()
+        } else {
+            vec![(0..text.len(), self.is_target_language(text))]
+        }
+    }
+
+    /// Translates the texts with the provided translation function, passing through the texts
+    /// (or sentences) already in the target language. The texts to translate are translated as a
+    /// single batch, the outputs are returned in the input order. Returns a `ValueError` if the
+    /// translation function does not return one output per text to translate.
+    pub fn translate_with<F>(
+        &self,
+        texts: &[&str],
+        translate: F,
+    ) -> Result<Vec<TranslationOutput>, RustBertError>
+    where
+        F: FnOnce(&[&str]) -> Vec<String>,
+    {
+        let segments = texts
+            .iter()
+            .map(|text| self.segments(text))
+            .collect::<Vec<Vec<(Range<usize>, bool)>>>();
+        let texts_to_translate = texts
+            .iter()
+            .zip(segments.iter())
+            .flat_map(|(text, text_segments)| {
+                text_segments
+                    .iter()
+                    .filter(|(_, passed_through)| !passed_through)
+                    .map(move |(range, _)| &text[range.clone()])
+            })
+            .collect::<Vec<&str>>();
+        let translations = if texts_to_translate.is_empty() {
+            vec![]
+        } else {
+            translate(&texts_to_translate)
+        };
+        if translations.len() != texts_to_translate.len() {
+            return Err(RustBertError::ValueError(format!(
+                "The translation model returned {} outputs for {} inputs",
+                translations.len(),
+                texts_to_translate.len()
+            )));
+        }
+
+        let mut translations = translations.into_iter();
+        Ok(texts
+            .iter()
+            .zip(segments.into_iter())
+            .map(|(text, text_segments)| {
+                let passed_through = text_segments
+                    .iter()
+                    .all(|(_, passed_through)| *passed_through);
+                let mut output = String::with_capacity(text.len());
+                let mut position = 0;
+                for (range, segment_passed_through) in text_segments {
+                    output.push_str(&text[position..range.start]);
+                    if segment_passed_through {
+                        output.push_str(&text[range.clone()]);
+                    } else {
+                        output.push_str(&translations.next().unwrap());
+                    }
+                    position = range.end;
+                }
+                output.push_str(&text[position..]);
+                TranslationOutput {
+                    text: output,
+                    passed_through,
+                }
+            })
+            .collect())
+    }
+}
+
 /// # TranslationModel to perform translation
 pub struct TranslationModel {
     model: TranslationOption,
     prefix: Option<String>,
+    keep_markers: Option<KeepMarkers>,
+    num_return_sequences: i64,
+    language_filter: Option<TargetLanguageFilter>,
 }
 
 impl TranslationModel {
@@ -733,10 +1102,63 @@ impl TranslationModel {
     /// # }
     /// ```
     pub fn new(translation_config: TranslationConfig) -> Result<TranslationModel, RustBertError> {
+        ensure_initialized();
         let prefix = translation_config.prefix.clone();
+        let keep_markers = translation_config.keep_tag.as_deref().map(KeepMarkers::new);
+        let num_return_sequences = translation_config.num_return_sequences;
         let model = TranslationOption::new(translation_config)?;
 
-        Ok(TranslationModel { model, prefix })
+        Ok(TranslationModel {
+            model,
+            prefix,
+            keep_markers,
+            num_return_sequences,
+            language_filter: None,
+        })
+    }
+
+    /// Sets the filter passing through the inputs already in the target language, applied by
+    /// `translate`, `translate_each` and `translate_with_metadata`. Returns an
+    /// `InvalidConfigurationError` if the model returns more than one sequence per input.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{
+    ///     DetectedLanguage, Language, TargetLanguageFilter, TranslationConfig, TranslationModel,
+    /// };
+    /// use tch::Device;
+    ///
+    /// let translation_config =
+    ///     TranslationConfig::new(Language::EnglishToFrench, Device::cuda_if_available());
+    /// let mut model = TranslationModel::new(translation_config)?;
+    /// let detector = |text: &str| {
+    ///     Some(DetectedLanguage {
+    ///         language: if text.contains(" le ") { "fr" } else { "en" }.to_string(),
+    ///         score: 0.9,
+    ///     })
+    /// };
+    /// model.set_language_filter(Some(TargetLanguageFilter::new(
+    ///     detector,
+    ///     Language::EnglishToFrench.target_language_code(),
+    ///     0.8,
+    /// )))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_language_filter(
+        &mut self,
+        language_filter: Option<TargetLanguageFilter>,
+    ) -> Result<(), RustBertError> {
+        if language_filter.is_some() && (self.num_return_sequences > 1) {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The target language filter requires a single returned sequence per input, got {}",
+                self.num_return_sequences
+            )));
+        }
+        self.language_filter = language_filter;
+        Ok(())
     }
 
     /// Translates texts provided
@@ -770,6 +1192,49 @@ impl TranslationModel {
     where
         S: AsRef<[&'a str]>,
     {
+        match &self.language_filter {
+            Some(language_filter) => language_filter
+                .translate_with(texts.as_ref(), |texts| self.translate_all(texts))
+                .expect("The translation model did not return one output per input")
+                .into_iter()
+                .map(|output| output.text)
+                .collect(),
+            None => self.translate_all(texts.as_ref()),
+        }
+    }
+
+    /// Translates texts provided, returning for each input whether it was passed through by the
+    /// target language filter (see `set_language_filter`). Without filter, all inputs are translated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to translate.
+    ///
+    /// # Returns
+    /// * `Vec<TranslationOutput>` Translated texts, in the input order
+    pub fn translate_with_metadata<'a, S>(
+        &self,
+        texts: S,
+    ) -> Result<Vec<TranslationOutput>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        match &self.language_filter {
+            Some(language_filter) => {
+                language_filter.translate_with(texts.as_ref(), |texts| self.translate_all(texts))
+            }
+            None => Ok(self
+                .translate_all(texts.as_ref())
+                .into_iter()
+                .map(|text| TranslationOutput {
+                    text,
+                    passed_through: false,
+                })
+                .collect()),
+        }
+    }
+
+    fn translate_all(&self, texts: &[&str]) -> Vec<String> {
         match &self.prefix {
             Some(value) => {
                 let texts = texts
@@ -785,6 +1250,46 @@ impl TranslationModel {
             None => self.model.generate(Some(texts), None),
         }
     }
+
+    /// Translates texts provided, keeping spans enclosed in inline markers (e.g.
+    /// `<keep>ProductName</keep>`, the tag is set by `TranslationConfig::keep_tag`) untranslated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to translate.
+    ///
+    /// # Returns
+    /// * `Vec<Result<String, RustBertError>>` Translated texts, or an error for inputs with nested
+    /// or unbalanced markers, or if the translation model did not preserve a marked span
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
+    /// use tch::Device;
+    ///
+    /// let translation_config =
+    ///     TranslationConfig::new(Language::EnglishToFrench, Device::cuda_if_available());
+    /// let model = TranslationModel::new(translation_config)?;
+    ///
+    /// let input = ["<keep>RustBert</keep> is a library for natural language processing"];
+    ///
+    /// let output = model.translate_each(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_each<'a, S>(&self, texts: S) -> Vec<Result<String, RustBertError>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        match &self.keep_markers {
+            Some(keep_markers) => {
+                keep_markers.translate_with(texts.as_ref(), |texts| self.translate(texts))
+            }
+            None => self.translate(texts).into_iter().map(Ok).collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -797,4 +1302,181 @@ mod test {
         let config = TranslationConfig::new(Language::FrenchToEnglish, Device::cuda_if_available());
         let _: Box<dyn Send> = Box::new(TranslationModel::new(config));
     }
+
+    #[test]
+    fn test_keep_markers_protect() {
+        let keep_markers = KeepMarkers::new("keep");
+
+        let protected = keep_markers
+            .protect("Install <keep>RustBert</keep> and <keep>libtorch</keep> first.")
+            .unwrap();
+        assert_eq!(protected.text, "Install __KEEP0__ and __KEEP1__ first.");
+        assert_eq!(protected.spans, vec!["RustBert", "libtorch"]);
+
+        let protected = keep_markers
+            .protect("<keep>ProductName</keep> is great, try <keep>ProductName</keep>")
+            .unwrap();
+        assert_eq!(protected.text, "__KEEP0__ is great, try __KEEP1__");
+        assert_eq!(protected.spans, vec!["ProductName", "ProductName"]);
+
+        let protected = keep_markers.protect("No markers here.").unwrap();
+        assert_eq!(protected.text, "No markers here.");
+        assert!(protected.spans.is_empty());
+
+        let protected = KeepMarkers::new("nt").protect("<nt>iPhone</nt>").unwrap();
+        assert_eq!(protected.text, "__NT0__");
+        assert_eq!(protected.spans, vec!["iPhone"]);
+    }
+
+    #[test]
+    fn test_keep_markers_invalid() {
+        let keep_markers = KeepMarkers::new("keep");
+
+        assert!(keep_markers
+            .protect("<keep>Outer <keep>Inner</keep></keep>")
+            .is_err());
+        assert!(keep_markers.protect("<keep>Unclosed span").is_err());
+        assert!(keep_markers.protect("Unopened span</keep>").is_err());
+        assert!(keep_markers
+            .protect("</keep>Reversed<keep> markers")
+            .is_err());
+    }
+
+    #[test]
+    fn test_keep_markers_translate_with() {
+        let keep_markers = KeepMarkers::new("keep");
+        let input = [
+            "<keep>RustBert</keep> runs on <keep>libtorch</keep>",
+            "<keep>Unbalanced",
+            "Visit <keep>ProductName</keep>",
+        ];
+
+        //    Stub translator upper-casing its inputs and preserving the sentinels
+        let output = keep_markers.translate_with(&input, |texts| {
+            assert_eq!(texts.len(), 2);
+            texts.iter().map(|text| text.to_uppercase()).collect()
+        });
+        assert_eq!(output.len(), 3);
+        assert_eq!(output[0].as_ref().unwrap(), "RustBert RUNS ON libtorch");
+        assert!(output[1].is_err());
+        assert_eq!(output[2].as_ref().unwrap(), "VISIT ProductName");
+
+        //    Stub translator dropping the sentinels
+        let output = keep_markers.translate_with(&input, |texts| {
+            texts
+                .iter()
+                .map(|text| text.replace("__KEEP1__", ""))
+                .collect()
+        });
+        assert!(output[0].is_err());
+        assert!(output[1].is_err());
+        assert_eq!(output[2].as_ref().unwrap(), "Visit ProductName");
+    }
+
+    //    Stub detector: French for texts containing "le", unknown for texts without letters,
+    //    English otherwise, with a low confidence for texts ending with "?"
+    fn stub_filter() -> TargetLanguageFilter {
+        TargetLanguageFilter::new(
+            |text: &str| {
+                if !text.chars().any(char::is_alphabetic) {
+                    return None;
+                }
+                let language = if text
+                    .split_whitespace()
+                    .any(|word| word.to_lowercase() == "le")
+                {
+                    "fr"
+                } else {
+                    "en"
+                };
+                let score = if text.ends_with('?') { 0.5 } else { 0.9 };
+                Some(DetectedLanguage {
+                    language: language.to_string(),
+                    score,
+                })
+            },
+            Language::EnglishToFrench.target_language_code(),
+            0.8,
+        )
+    }
+
+    #[test]
+    fn test_target_language_filter() {
+        let filter = stub_filter();
+        let input = [
+            "The cat sleeps.",
+            "Le chat dort sur le canapé.",
+            "Where is le chat?",
+            "1234",
+            "Good morning.",
+        ];
+
+        //    Stub translator upper-casing its inputs
+        let output = filter
+            .translate_with(&input, |texts| {
+                assert_eq!(
+                    texts,
+                    [
+                        "The cat sleeps.",
+                        "Where is le chat?",
+                        "1234",
+                        "Good morning."
+                    ]
+                );
+                texts.iter().map(|text| text.to_uppercase()).collect()
+            })
+            .unwrap();
+        assert_eq!(
+            output
+                .iter()
+                .map(|output| (output.text.as_str(), output.passed_through))
+                .collect::<Vec<(&str, bool)>>(),
+            vec![
+                ("THE CAT SLEEPS.", false),
+                ("Le chat dort sur le canapé.", true),
+                ("WHERE IS LE CHAT?", false),
+                ("1234", false),
+                ("GOOD MORNING.", false),
+            ]
+        );
+
+        //    The translator is not called if all inputs are passed through
+        let output = filter
+            .translate_with(&["Le chat dort.", "Le chien aussi."], |_| {
+                panic!("no input to translate")
+            })
+            .unwrap();
+        assert!(output.iter().all(|output| output.passed_through));
+        assert_eq!(output[1].text, "Le chien aussi.");
+
+        assert!(filter
+            .translate_with(&input, |texts| vec![texts[0].to_string()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_target_language_filter_per_sentence() {
+        let filter = stub_filter().per_sentence(true);
+        let input = [
+            "Le chat dort.  The dog barks!\nLe soleil brille. ",
+            "Le chat dort. Le chien aussi.",
+            "No terminal punctuation",
+        ];
+
+        let output = filter
+            .translate_with(&input, |texts| {
+                assert_eq!(texts, ["The dog barks!", "No terminal punctuation"]);
+                texts.iter().map(|text| text.to_uppercase()).collect()
+            })
+            .unwrap();
+        assert_eq!(
+            output[0].text,
+            "Le chat dort.  THE DOG BARKS!\nLe soleil brille. "
+        );
+        assert!(!output[0].passed_through);
+        assert_eq!(output[1].text, "Le chat dort. Le chien aussi.");
+        assert!(output[1].passed_through);
+        assert_eq!(output[2].text, "NO TERMINAL PUNCTUATION");
+        assert!(!output[2].passed_through);
+    }
 }
@@ -0,0 +1,182 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Dry-run validation of pipeline configurations
+//! Building a pipeline downloads the model weights and allocates the full model before any
+//! configuration error can surface. The `validate_config` functions of the classification and
+//! question answering pipelines only fetch the configuration and vocabulary files and run the
+//! construction-time checks (supported model type, loadable tokenizer, label mappings, position
+//! limits), collecting the problems found in a serializable `ValidationReport` that can be kept
+//! as a CI artifact. The model weights are neither downloaded nor loaded: only the presence of a
+//! local weights file is checked.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//!
+//! let report = SequenceClassificationModel::validate_config(&Default::default())?;
+//! for warning in &report.warnings {
+//!     println!("warning: {}", warning);
+//! }
+//! assert!(report.is_valid(), "{:?}", report.errors);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::resources::Resource;
+use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Outcome of the dry-run validation of a pipeline configuration
+pub struct ValidationReport {
+    /// Name of the validated pipeline
+    pub pipeline: String,
+    /// Model type of the configuration
+    pub model_type: ModelType,
+    /// Number of labels of the model configuration, if a label mapping is provided
+    pub num_labels: Option<usize>,
+    /// Maximum number of tokens the model can process, if limited by absolute position embeddings
+    pub max_positions: Option<usize>,
+    /// Problems that do not prevent the pipeline from being built
+    pub warnings: Vec<String>,
+    /// Problems that would make the pipeline construction fail
+    pub errors: Vec<String>,
+}
+
+impl ValidationReport {
+    pub(crate) fn new(pipeline: &str, model_type: ModelType) -> ValidationReport {
+        ValidationReport {
+            pipeline: pipeline.to_string(),
+            model_type,
+            num_labels: None,
+            max_positions: None,
+            warnings: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// Returns `true` if no error was found, warnings being allowed
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Records the error of a failed check, returning the value of a successful one
+    pub(crate) fn check<T>(&mut self, result: Result<T, RustBertError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.errors.push(error.to_string());
+                None
+            }
+        }
+    }
+
+    /// Records an error if `length` exceeds the maximum number of positions of the model
+    pub(crate) fn check_max_length(&mut self, name: &str, length: usize) {
+        if let Some(max_positions) = self.max_positions {
+            if length > max_positions {
+                self.errors.push(format!(
+                    "{} ({}) exceeds the maximum number of positions of the model ({})",
+                    name, length, max_positions
+                ));
+            }
+        }
+    }
+}
+
+/// Resources and tokenizer options shared by the pipeline configurations
+pub(crate) struct PipelineResources<'a> {
+    pub model_type: ModelType,
+    pub model_resource: &'a Resource,
+    pub config_resource: &'a Resource,
+    pub vocab_resource: &'a Resource,
+    pub merges_resource: Option<&'a Resource>,
+    pub lower_case: bool,
+    pub strip_accents: Option<bool>,
+    pub add_prefix_space: Option<bool>,
+}
+
+/// Fetches and parses the configuration and vocabulary files, recording the problems found in the
+/// report. Returns the model configuration and the tokenizer that could be loaded.
+pub(crate) fn validate_resources(
+    report: &mut ValidationReport,
+    resources: &PipelineResources,
+) -> (Option<ConfigOption>, Option<TokenizerOption>) {
+    if report
+        .check(resources.model_type.ensure_enabled())
+        .is_none()
+    {
+        return (None, None);
+    }
+    if let Resource::Local(model_resource) = resources.model_resource {
+        if !model_resource.local_path.is_file() {
+            report.errors.push(format!(
+                "Model weights not found at {}",
+                model_resource.local_path.display()
+            ));
+        }
+    }
+
+    let model_config = report
+        .check(resources.config_resource.get_local_path())
+        .and_then(|config_path| {
+            report.check(ConfigOption::try_from_file(
+                resources.model_type,
+                config_path,
+            ))
+        });
+    let vocab_path = report.check(resources.vocab_resource.get_local_path());
+    let merges_path = match resources.merges_resource {
+        Some(merges_resource) => report.check(merges_resource.get_local_path()).map(Some),
+        None => Some(None),
+    };
+    let tokenizer = match (vocab_path, merges_path) {
+        (Some(vocab_path), Some(merges_path)) => report.check(TokenizerOption::from_file(
+            resources.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            resources.lower_case,
+            resources.strip_accents,
+            resources.add_prefix_space,
+        )),
+        _ => None,
+    };
+
+    if let Some(model_config) = &model_config {
+        //    RoBERTa-based models offset the position ids by the padding index
+        let position_offset = match resources.model_type {
+            ModelType::Roberta | ModelType::XLMRoberta | ModelType::Longformer => 2,
+            _ => 0,
+        };
+        report.max_positions = model_config
+            .max_position_embeddings()
+            .map(|max_positions| (max_positions - position_offset).max(0) as usize);
+        let id2label = model_config.get_id2label();
+        report.num_labels = id2label.map(|id2label| id2label.len());
+        if let (Some(id2label), Some(label2id)) = (id2label, model_config.get_label2id()) {
+            if id2label.len() != label2id.len()
+                || label2id
+                    .iter()
+                    .any(|(label, id)| id2label.get(id) != Some(label))
+            {
+                report.warnings.push(
+                    "The id2label and label2id mappings of the configuration are inconsistent"
+                        .to_string(),
+                );
+            }
+        }
+    }
+    (model_config, tokenizer)
+}
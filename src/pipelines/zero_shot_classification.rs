@@ -16,7 +16,8 @@
 //! The default model is a BART model fine-tuned on a MNLI. From a list of input sequences to classify and a list of target labels,
 //! single-class or multi-label classification is performed, translating the classification task to an inference task.
 //! The default template for translation to inference task is `This example is about {}.`. This template can be updated to a more specific
-//! value that may match better the use case, for example `This review is about a {product_class}`.
+//! value that may match better the use case, for example `This review is about a {product_class}`, either with the
+//! `hypothesis_template` of the configuration (e.g. `"This review is about a {}."`) or with a closure passed to the prediction methods.
 //!
 //! - `predict` performs single-class classification (one and exactly one label must be true for each provided input)
 //! - `predict_multilabel` performs multi-label classification (zero, one or more labels may be true for each provided input)
@@ -33,7 +34,7 @@
 //!      candidate_labels,
 //!      None,
 //!      128,
-//!  );
+//!  )?;
 //! # Ok(())
 //! # }
 //! ```
@@ -98,29 +99,56 @@
 //! .to_vec();
 //! ```
 
+#[cfg(feature = "albert")]
 use crate::albert::AlbertForSequenceClassification;
 use crate::bart::{
     BartConfigResources, BartForSequenceClassification, BartMergesResources, BartModelResources,
     BartVocabResources,
 };
+#[cfg(feature = "bert")]
 use crate::bert::BertForSequenceClassification;
+use crate::common::initialization::ensure_initialized;
+use crate::common::load_report::{load_with_report, LoadReport};
+#[cfg(feature = "deberta")]
+use crate::deberta::DebertaForSequenceClassification;
+#[cfg(feature = "distilbert")]
 use crate::distilbert::DistilBertModelClassifier;
+#[cfg(feature = "longformer")]
 use crate::longformer::LongformerForSequenceClassification;
+#[cfg(feature = "mobilebert")]
 use crate::mobilebert::MobileBertForSequenceClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
-use crate::pipelines::sequence_classification::Label;
+use crate::pipelines::chunked_inference::{pin_for_device, run_chunked, ChunkScheduling};
+use crate::pipelines::common::{
+    check_input_length, expect_config, get_padded_length, ConfigOption, ModelType,
+    OverflowStrategy, TokenizerOption,
+};
+use crate::pipelines::metrics::{
+    multilabel_report, single_label_report, EvalReport, MultilabelEvalReport,
+};
+use crate::pipelines::records::{build_record_texts, FieldConfig, Record};
+use crate::pipelines::sequence_classification::{align_outputs, split_valid_inputs, Label};
+use crate::pipelines::state::{read_state, write_state, PipelineSources, RestoredState};
+use crate::pipelines::validation::{validate_resources, PipelineResources, ValidationReport};
 use crate::resources::{RemoteResource, Resource};
+#[cfg(feature = "roberta")]
 use crate::roberta::RobertaForSequenceClassification;
+#[cfg(feature = "xlnet")]
 use crate::xlnet::XLNetForSequenceClassification;
 use crate::RustBertError;
 use rust_tokenizers::tokenizer::TruncationStrategy;
-use rust_tokenizers::TokenizedInput;
+use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput};
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::ops::Deref;
-use tch::kind::Kind::{Bool, Float};
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Deref, Range};
+use std::path::{Path, PathBuf};
+use tch::kind::Kind::{Bool, Double, Float, Half};
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Tensor};
 
+/// Name of the pipeline recorded in saved states
+const ZERO_SHOT_CLASSIFICATION_STATE: &str = "zero_shot_classification";
+
 /// # Configuration for ZeroShotClassificationModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct ZeroShotClassificationConfig {
@@ -142,6 +170,40 @@ pub struct ZeroShotClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Pad the input sequences to a multiple of this value to produce tensor shapes that are more
+    /// efficient on GPU (e.g. 8 for fp16 tensor cores). Padded positions are masked (default: None)
+    pub pad_to_multiple_of: Option<usize>,
+    /// Maximum number of candidate labels processed in a single forward pass. If set, each input
+    /// is processed separately in chunks of labels, bounding the memory used by large label sets
+    /// (default: None, all input and label pairs are processed at once)
+    pub label_chunk_size: Option<usize>,
+    /// Maximum number of input and label pairs processed in a single forward pass. The pairs are
+    /// padded to a common length before being split in batches, so that the scores do not depend on
+    /// the batch size (default: None, all pairs of a forward pass are processed at once)
+    pub batch_size: Option<usize>,
+    /// Truncate each input to the same number of tokens for all candidate labels, leaving room for
+    /// the longest hypothesis. Otherwise, the input is truncated depending on the length of each
+    /// hypothesis and the entailment scores of the labels may be computed on different premises (default: false)
+    pub equalize_premise: bool,
+    /// Template of the hypothesis built for each candidate label, containing exactly one `{}`
+    /// placeholder replaced by the label (e.g. `"This review is about {}."`). Templates passed to the
+    /// prediction methods take precedence (default: None, using `"This example is about {}."`)
+    pub hypothesis_template: Option<String>,
+    /// Index of the entailment class in the model logits. Negative values are counted from the
+    /// last class (default: None, read from the `label2id` of the model configuration, or the last
+    /// class if the configuration has no `entailment` label)
+    pub entailment_id: Option<i64>,
+    /// Index of the contradiction class in the model logits. Negative values are counted from the
+    /// last class (default: None, read from the `label2id` of the model configuration, or the
+    /// non-entailment class of two-class models, or the first class otherwise)
+    pub contradiction_id: Option<i64>,
+    /// Run the model in half precision (fp16), roughly halving the memory used by the weights and
+    /// activations. Only supported on CUDA devices (default: false)
+    pub half_precision: bool,
+    /// Handling of inputs that do not fit within the maximum length alongside the longest hypothesis
+    /// and the special tokens. With `OverflowStrategy::Error`, the prediction methods return a
+    /// `RustBertError::InputTooLong` error for the first such input (default: `OverflowStrategy::Truncate`)
+    pub on_overflow: OverflowStrategy,
 }
 
 impl ZeroShotClassificationConfig {
@@ -175,8 +237,81 @@ impl ZeroShotClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            label_chunk_size: None,
+            batch_size: None,
+            equalize_premise: false,
+            hypothesis_template: None,
+            entailment_id: None,
+            contradiction_id: None,
+            half_precision: false,
+            on_overflow: OverflowStrategy::Truncate,
         }
     }
+
+    /// Multilingual zero-shot classification configuration for an XLM-RoBERTa model fine-tuned on
+    /// XNLI (e.g. `joeddav/xlm-roberta-large-xnli`). Inputs, candidate labels and hypothesis
+    /// templates can be written in any of the languages covered by XLM-RoBERTa, and may differ from
+    /// each other.
+    ///
+    /// No converted weights are published for these checkpoints: the Pytorch weights must be
+    /// converted with `python ./utils/convert_model.py path/to/pytorch_model.bin` and passed as a
+    /// local resource, along with the configuration and the sentencepiece model of the checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - The `Resource` pointing to the converted weights (`rust_model.ot`)
+    /// * `config_resource` - The `Resource` pointing to the model configuration (`config.json`)
+    /// * `vocab_resource` - The `Resource` pointing to the sentencepiece model (`sentencepiece.bpe.model`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     ZeroShotClassificationConfig, ZeroShotClassificationModel,
+    /// };
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let local_resource = |path: &str| {
+    ///     Resource::Local(LocalResource {
+    ///         local_path: PathBuf::from(path),
+    ///     })
+    /// };
+    /// let config = ZeroShotClassificationConfig {
+    ///     hypothesis_template: Some("Dieses Beispiel handelt von {}.".to_string()),
+    ///     ..ZeroShotClassificationConfig::xnli_multilingual(
+    ///         local_resource("path/to/rust_model.ot"),
+    ///         local_resource("path/to/config.json"),
+    ///         local_resource("path/to/sentencepiece.bpe.model"),
+    ///     )
+    /// };
+    /// let model = ZeroShotClassificationModel::new(config)?;
+    ///
+    /// let input = ["Die Bundesregierung hat die Steuern für kleine Unternehmen gesenkt."];
+    /// let candidate_labels = &["Politik", "Sport", "Gesundheit", "Wirtschaft"];
+    /// let output = model.predict(&input, candidate_labels, None, 128)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "roberta")]
+    pub fn xnli_multilingual(
+        model_resource: Resource,
+        config_resource: Resource,
+        vocab_resource: Resource,
+    ) -> ZeroShotClassificationConfig {
+        ZeroShotClassificationConfig::new(
+            ModelType::XLMRoberta,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            false,
+            None,
+            None,
+        )
+    }
 }
 
 impl Default for ZeroShotClassificationConfig {
@@ -200,6 +335,15 @@ impl Default for ZeroShotClassificationConfig {
             strip_accents: None,
             add_prefix_space: None,
             device: Device::cuda_if_available(),
+            pad_to_multiple_of: None,
+            label_chunk_size: None,
+            batch_size: None,
+            equalize_premise: false,
+            hypothesis_template: None,
+            entailment_id: None,
+            contradiction_id: None,
+            half_precision: false,
+            on_overflow: OverflowStrategy::Truncate,
         }
     }
 }
@@ -212,21 +356,32 @@ pub enum ZeroShotClassificationOption {
     /// Bart for Sequence Classification
     Bart(BartForSequenceClassification),
     /// Bert for Sequence Classification
+    #[cfg(feature = "bert")]
     Bert(BertForSequenceClassification),
     /// DistilBert for Sequence Classification
+    #[cfg(feature = "distilbert")]
     DistilBert(DistilBertModelClassifier),
     /// MobileBert for Sequence Classification
+    #[cfg(feature = "mobilebert")]
     MobileBert(MobileBertForSequenceClassification),
     /// Roberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     Roberta(RobertaForSequenceClassification),
     /// XLMRoberta for Sequence Classification
+    #[cfg(feature = "roberta")]
     XLMRoberta(RobertaForSequenceClassification),
     /// Albert for Sequence Classification
+    #[cfg(feature = "albert")]
     Albert(AlbertForSequenceClassification),
     /// XLNet for Sequence Classification
+    #[cfg(feature = "xlnet")]
     XLNet(XLNetForSequenceClassification),
     /// Longformer for Sequence Classification
+    #[cfg(feature = "longformer")]
     Longformer(LongformerForSequenceClassification),
+    /// DeBERTa for Sequence Classification
+    #[cfg(feature = "deberta")]
+    Deberta(DebertaForSequenceClassification),
 }
 
 impl ZeroShotClassificationOption {
@@ -246,105 +401,126 @@ impl ZeroShotClassificationOption {
     where
         P: Borrow<nn::Path<'p>>,
     {
+        model_type.ensure_enabled()?;
         match model_type {
             ModelType::Bart => {
-                if let ConfigOption::Bart(config) = config {
-                    Ok(ZeroShotClassificationOption::Bart(
-                        BartForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BartConfig for Bart!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bart,
+                    ConfigOption::Bart,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Bart(
+                    BartForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "bert")]
             ModelType::Bert => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(ZeroShotClassificationOption::Bert(
-                        BertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Bert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Bert,
+                    ConfigOption::Bert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Bert(
+                    BertForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "distilbert")]
             ModelType::DistilBert => {
-                if let ConfigOption::DistilBert(config) = config {
-                    Ok(ZeroShotClassificationOption::DistilBert(
-                        DistilBertModelClassifier::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a DistilBertConfig for DistilBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::DistilBert,
+                    ConfigOption::DistilBert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::DistilBert(
+                    DistilBertModelClassifier::new(p, config),
+                ))
             }
+            #[cfg(feature = "mobilebert")]
             ModelType::MobileBert => {
-                if let ConfigOption::MobileBert(config) = config {
-                    Ok(ZeroShotClassificationOption::MobileBert(
-                        MobileBertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a MobileBertConfig for MobileBert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::MobileBert,
+                    ConfigOption::MobileBert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::MobileBert(
+                    MobileBertForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::Roberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(ZeroShotClassificationOption::Roberta(
-                        RobertaForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Roberta,
+                    ConfigOption::Bert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Roberta(
+                    RobertaForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "roberta")]
             ModelType::XLMRoberta => {
-                if let ConfigOption::Bert(config) = config {
-                    Ok(ZeroShotClassificationOption::XLMRoberta(
-                        RobertaForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a BertConfig for Roberta!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLMRoberta,
+                    ConfigOption::Bert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::XLMRoberta(
+                    RobertaForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "albert")]
             ModelType::Albert => {
-                if let ConfigOption::Albert(config) = config {
-                    Ok(ZeroShotClassificationOption::Albert(
-                        AlbertForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Albert,
+                    ConfigOption::Albert,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Albert(
+                    AlbertForSequenceClassification::new(p, config),
+                ))
             }
+            #[cfg(feature = "xlnet")]
             ModelType::XLNet => {
-                if let ConfigOption::XLNet(config) = config {
-                    Ok(ZeroShotClassificationOption::XLNet(
-                        XLNetForSequenceClassification::new(p, config).unwrap(),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply an AlbertConfig for Albert!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::XLNet,
+                    ConfigOption::XLNet,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::XLNet(
+                    XLNetForSequenceClassification::new(p, config)?,
+                ))
             }
+            #[cfg(feature = "longformer")]
             ModelType::Longformer => {
-                if let ConfigOption::Longformer(config) = config {
-                    Ok(ZeroShotClassificationOption::Longformer(
-                        LongformerForSequenceClassification::new(p, config),
-                    ))
-                } else {
-                    Err(RustBertError::InvalidConfigurationError(
-                        "You can only supply a LongformerConfig for Longformer!".to_string(),
-                    ))
-                }
+                let config = expect_config!(
+                    config,
+                    ModelType::Longformer,
+                    ConfigOption::Longformer,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Longformer(
+                    LongformerForSequenceClassification::new(p, config),
+                ))
+            }
+            #[cfg(feature = "deberta")]
+            ModelType::Deberta => {
+                let config = expect_config!(
+                    config,
+                    ModelType::Deberta,
+                    ConfigOption::Deberta,
+                    "Zero-shot classification"
+                )?;
+                Ok(ZeroShotClassificationOption::Deberta(
+                    DebertaForSequenceClassification::new(p, config),
+                ))
             }
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Zero shot classification not implemented for {:?}!",
@@ -353,21 +529,70 @@ impl ZeroShotClassificationOption {
         }
     }
 
+    /// Checks that the model type is implemented by the pipeline, without building the model
+    pub(crate) fn ensure_supported(model_type: ModelType) -> Result<(), RustBertError> {
+        match model_type {
+            ModelType::Bart => Ok(()),
+            #[cfg(feature = "bert")]
+            ModelType::Bert => Ok(()),
+            #[cfg(feature = "distilbert")]
+            ModelType::DistilBert => Ok(()),
+            #[cfg(feature = "mobilebert")]
+            ModelType::MobileBert => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::Roberta => Ok(()),
+            #[cfg(feature = "roberta")]
+            ModelType::XLMRoberta => Ok(()),
+            #[cfg(feature = "albert")]
+            ModelType::Albert => Ok(()),
+            #[cfg(feature = "xlnet")]
+            ModelType::XLNet => Ok(()),
+            #[cfg(feature = "longformer")]
+            ModelType::Longformer => Ok(()),
+            #[cfg(feature = "deberta")]
+            ModelType::Deberta => Ok(()),
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Zero shot classification not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
     /// Returns the `ModelType` for this SequenceClassificationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
             Self::Bart(_) => ModelType::Bart,
+            #[cfg(feature = "bert")]
             Self::Bert(_) => ModelType::Bert,
+            #[cfg(feature = "roberta")]
             Self::Roberta(_) => ModelType::Roberta,
+            #[cfg(feature = "roberta")]
             Self::XLMRoberta(_) => ModelType::Roberta,
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(_) => ModelType::DistilBert,
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(_) => ModelType::MobileBert,
+            #[cfg(feature = "albert")]
             Self::Albert(_) => ModelType::Albert,
+            #[cfg(feature = "xlnet")]
             Self::XLNet(_) => ModelType::XLNet,
+            #[cfg(feature = "longformer")]
             Self::Longformer(_) => ModelType::Longformer,
+            #[cfg(feature = "deberta")]
+            Self::Deberta(_) => ModelType::Deberta,
         }
     }
 
+    /// Returns true if the model embeds the segment of each token, distinguishing the premise from
+    /// the hypothesis (BERT, MobileBERT, ALBERT and XLNet). The token type ids of the input pairs
+    /// are only passed to these models.
+    pub fn uses_token_type_ids(&self) -> bool {
+        matches!(
+            self.model_type(),
+            ModelType::Bert | ModelType::MobileBert | ModelType::Albert | ModelType::XLNet
+        )
+    }
+
     /// Interface method to forward_t() of the particular models.
     pub fn forward_t(
         &self,
@@ -391,6 +616,7 @@ impl ZeroShotClassificationOption {
                     )
                     .decoder_output
             }
+            #[cfg(feature = "bert")]
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -403,18 +629,20 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "distilbert")]
             Self::DistilBert(ref model) => {
                 model
                     .forward_t(input_ids, mask, input_embeds, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
+            #[cfg(feature = "mobilebert")]
             Self::MobileBert(ref model) => {
                 model
                     .forward_t(
                         input_ids.as_ref(),
-                        None,
-                        None,
+                        token_type_ids.as_ref(),
+                        position_ids.as_ref(),
                         input_embeds,
                         mask.as_ref(),
                         train,
@@ -422,6 +650,7 @@ impl ZeroShotClassificationOption {
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
+            #[cfg(feature = "roberta")]
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
                 model
                     .forward_t(
@@ -434,6 +663,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "albert")]
             Self::Albert(ref model) => {
                 model
                     .forward_t(
@@ -446,6 +676,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "xlnet")]
             Self::XLNet(ref model) => {
                 model
                     .forward_t(
@@ -460,6 +691,7 @@ impl ZeroShotClassificationOption {
                     )
                     .logits
             }
+            #[cfg(feature = "longformer")]
             Self::Longformer(ref model) => {
                 model
                     .forward_t(
@@ -474,6 +706,20 @@ impl ZeroShotClassificationOption {
                     .expect("Error in Longformer forward pass.")
                     .logits
             }
+            #[cfg(feature = "deberta")]
+            Self::Deberta(ref model) => {
+                model
+                    .forward_t(
+                        input_ids.as_ref(),
+                        mask.as_ref(),
+                        token_type_ids.as_ref(),
+                        position_ids.as_ref(),
+                        input_embeds.as_ref(),
+                        train,
+                    )
+                    .expect("Error in DeBERTa forward pass.")
+                    .logits
+            }
         }
     }
 }
@@ -483,6 +729,71 @@ pub struct ZeroShotClassificationModel {
     tokenizer: TokenizerOption,
     zero_shot_classifier: ZeroShotClassificationOption,
     var_store: VarStore,
+    label_thresholds: Option<HashMap<String, f64>>,
+    pad_to_multiple_of: Option<usize>,
+    label_chunk_size: Option<usize>,
+    batch_size: Option<usize>,
+    equalize_premise: bool,
+    hypothesis_template: Option<String>,
+    load_report: LoadReport,
+    tie_word_embeddings: bool,
+    sources: Option<PipelineSources>,
+    nli_labels: NliLabelIds,
+    half_precision: bool,
+    on_overflow: OverflowStrategy,
+}
+
+/// Pipeline options persisted in saved states
+#[derive(Serialize, Deserialize)]
+struct ZeroShotStateOptions {
+    label_thresholds: Option<HashMap<String, f64>>,
+    pad_to_multiple_of: Option<usize>,
+    label_chunk_size: Option<usize>,
+    batch_size: Option<usize>,
+    equalize_premise: bool,
+    hypothesis_template: Option<String>,
+    entailment_id: Option<i64>,
+    contradiction_id: Option<i64>,
+    #[serde(default)]
+    on_overflow: OverflowStrategy,
+    half_precision: bool,
+}
+
+impl ZeroShotStateOptions {
+    fn from_config(
+        config: &ZeroShotClassificationConfig,
+        model_config: &ConfigOption,
+    ) -> ZeroShotStateOptions {
+        ZeroShotStateOptions {
+            label_thresholds: None,
+            pad_to_multiple_of: model_config.resolve_pad_to_multiple_of(config.pad_to_multiple_of),
+            label_chunk_size: config.label_chunk_size,
+            batch_size: config.batch_size,
+            equalize_premise: config.equalize_premise,
+            hypothesis_template: config.hypothesis_template.clone(),
+            entailment_id: config.entailment_id,
+            contradiction_id: config.contradiction_id,
+            on_overflow: config.on_overflow,
+            half_precision: config.half_precision,
+        }
+    }
+}
+
+fn validate_config(config: &ZeroShotClassificationConfig) -> Result<(), RustBertError> {
+    if let Some(hypothesis_template) = &config.hypothesis_template {
+        validate_hypothesis_template(hypothesis_template)?;
+    }
+    if config.batch_size == Some(0) {
+        return Err(RustBertError::InvalidConfigurationError(
+            "The batch size must be strictly positive".to_string(),
+        ));
+    }
+    if config.half_precision && (config.device == Device::Cpu) {
+        return Err(RustBertError::InvalidConfigurationError(
+            "Half precision inference is only supported on CUDA devices".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 impl ZeroShotClassificationModel {
@@ -505,6 +816,8 @@ impl ZeroShotClassificationModel {
     pub fn new(
         config: ZeroShotClassificationConfig,
     ) -> Result<ZeroShotClassificationModel, RustBertError> {
+        ensure_initialized();
+        validate_config(&config)?;
         let config_path = config.config_resource.get_local_path()?;
         let vocab_path = config.vocab_resource.get_local_path()?;
         let weights_path = config.model_resource.get_local_path()?;
@@ -523,203 +836,394 @@ impl ZeroShotClassificationModel {
             config.strip_accents,
             config.add_prefix_space,
         )?;
+        let model_config = ConfigOption::try_from_file(config.model_type, &config_path)?;
+        let options = ZeroShotStateOptions::from_config(&config, &model_config);
+        let sources = PipelineSources {
+            model_type: config.model_type,
+            config_path,
+            vocab_path,
+            merges_path,
+            lower_case: config.lower_case,
+            strip_accents: config.strip_accents,
+            add_prefix_space: config.add_prefix_space,
+        };
+        ZeroShotClassificationModel::from_components(
+            config.model_type,
+            Some(sources),
+            tokenizer,
+            &model_config,
+            options,
+            weights_path,
+            device,
+        )
+    }
+
+    /// Build a new `ZeroShotClassificationModel` with a tokenizer built by the caller. The vocabulary
+    /// and merges resources of the configuration are not used (nor downloaded), and the tokenizer
+    /// must match the model type of the configuration. Pipelines built with an injected tokenizer
+    /// cannot be saved with `save_state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `ZeroShotClassificationConfig` object containing the model and configuration resources and device placement (CPU/GPU)
+    /// * `tokenizer` - `TokenizerOption` used to encode the premises and hypotheses
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidConfigurationError` if the tokenizer cannot be used by `config.model_type`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     ZeroShotClassificationConfig, ZeroShotClassificationModel,
+    /// };
+    ///
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::Bart,
+    ///     "path/to/vocab.json",
+    ///     Some("path/to/merges.txt"),
+    ///     false,
+    ///     None,
+    ///     true,
+    /// )?;
+    /// let model =
+    ///     ZeroShotClassificationModel::new_with_tokenizer(Default::default(), tokenizer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_tokenizer(
+        config: ZeroShotClassificationConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<ZeroShotClassificationModel, RustBertError> {
+        ensure_initialized();
+        validate_config(&config)?;
+        tokenizer.ensure_compatible(config.model_type)?;
+        let config_path = config.config_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let model_config = ConfigOption::try_from_file(config.model_type, &config_path)?;
+        let options = ZeroShotStateOptions::from_config(&config, &model_config);
+        ZeroShotClassificationModel::from_components(
+            config.model_type,
+            None,
+            tokenizer,
+            &model_config,
+            options,
+            weights_path,
+            config.device,
+        )
+    }
+
+    /// Validates a configuration without downloading or loading the model weights. The configuration
+    /// and vocabulary files are fetched and parsed, and the checks run when building the pipeline
+    /// (supported model type, loadable tokenizer, entailment and contradiction classes, hypothesis
+    /// template) are collected in the returned `ValidationReport` instead of failing on the first
+    /// error.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `ZeroShotClassificationConfig` to validate
+    pub fn validate_config(
+        config: &ZeroShotClassificationConfig,
+    ) -> Result<ValidationReport, RustBertError> {
+        let mut report = ValidationReport::new("zero-shot classification", config.model_type);
+        report.check(validate_config(config));
+        if config.model_type.is_enabled() {
+            report.check(ZeroShotClassificationOption::ensure_supported(
+                config.model_type,
+            ));
+        }
+        let (model_config, _) = validate_resources(
+            &mut report,
+            &PipelineResources {
+                model_type: config.model_type,
+                model_resource: &config.model_resource,
+                config_resource: &config.config_resource,
+                vocab_resource: &config.vocab_resource,
+                merges_resource: config.merges_resource.as_ref(),
+                lower_case: config.lower_case,
+                strip_accents: config.strip_accents,
+                add_prefix_space: config.add_prefix_space,
+            },
+        );
+        if let Some(model_config) = &model_config {
+            report.check(NliLabelIds::from_config(
+                config.entailment_id,
+                config.contradiction_id,
+                model_config,
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Build a new `ZeroShotClassificationModel` from a state saved with `save_state`, skipping the
+    /// parsing of the tokenizer and configuration files. The label thresholds and pipeline options
+    /// (including half precision inference) are restored from the state, the weights are loaded
+    /// from `model_resource`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state_path` - path to the state written by `save_state`
+    /// * `model_resource` - `Resource` pointing to the model weights
+    /// * `device` - `Device` to place the model on
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidStateError` if the state was written with another format version, by another pipeline, or if its source files were modified since
+    /// * `InvalidConfigurationError` if the state was saved from a half precision pipeline and `device` is the CPU
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::bart::BartModelResources;
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    /// use rust_bert::resources::{RemoteResource, Resource};
+    /// use tch::Device;
+    ///
+    /// let model = ZeroShotClassificationModel::load_from_state(
+    ///     "zero_shot.state",
+    ///     Resource::Remote(RemoteResource::from_pretrained(
+    ///         BartModelResources::BART_MNLI,
+    ///     )),
+    ///     Device::cuda_if_available(),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_from_state<P: AsRef<Path>>(
+        state_path: P,
+        model_resource: Resource,
+        device: Device,
+    ) -> Result<ZeroShotClassificationModel, RustBertError> {
+        ensure_initialized();
+        let weights_path = model_resource.get_local_path()?;
+        let state: RestoredState<ZeroShotStateOptions> =
+            read_state(state_path.as_ref(), ZERO_SHOT_CLASSIFICATION_STATE)?;
+        ZeroShotClassificationModel::from_components(
+            state.sources.model_type,
+            Some(state.sources),
+            state.tokenizer,
+            &state.model_config,
+            state.options,
+            weights_path,
+            device,
+        )
+    }
+
+    fn from_components(
+        model_type: ModelType,
+        sources: Option<PipelineSources>,
+        tokenizer: TokenizerOption,
+        model_config: &ConfigOption,
+        options: ZeroShotStateOptions,
+        weights_path: PathBuf,
+        device: Device,
+    ) -> Result<ZeroShotClassificationModel, RustBertError> {
+        if options.half_precision && (device == Device::Cpu) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Half precision inference is only supported on CUDA devices".to_string(),
+            ));
+        }
+        let nli_labels = NliLabelIds::from_config(
+            options.entailment_id,
+            options.contradiction_id,
+            model_config,
+        )?;
         let mut var_store = VarStore::new(device);
-        let model_config = ConfigOption::from_file(config.model_type, config_path);
         let zero_shot_classifier =
-            ZeroShotClassificationOption::new(config.model_type, &var_store.root(), &model_config)?;
-        var_store.load(weights_path)?;
+            ZeroShotClassificationOption::new(model_type, &var_store.root(), model_config)?;
+        let tie_word_embeddings = model_config.tie_word_embeddings();
+        let load_report = load_with_report(&mut var_store, weights_path, tie_word_embeddings)?;
+        if options.half_precision {
+            var_store.half();
+        }
         Ok(ZeroShotClassificationModel {
             tokenizer,
             zero_shot_classifier,
             var_store,
+            load_report,
+            tie_word_embeddings,
+            sources,
+            nli_labels,
+            label_thresholds: options.label_thresholds,
+            pad_to_multiple_of: options.pad_to_multiple_of,
+            label_chunk_size: options.label_chunk_size,
+            batch_size: options.batch_size,
+            equalize_premise: options.equalize_premise,
+            hypothesis_template: options.hypothesis_template,
+            half_precision: options.half_precision,
+            on_overflow: options.on_overflow,
         })
     }
 
-    fn prepare_for_model<'a, S, T>(
-        &self,
-        inputs: S,
-        labels: T,
-        template: Option<Box<dyn Fn(&str) -> String>>,
-        max_len: usize,
-    ) -> (Tensor, Tensor)
-    where
-        S: AsRef<[&'a str]>,
-        T: AsRef<[&'a str]>,
-    {
-        let label_sentences: Vec<String> = match template {
-            Some(function) => labels
-                .as_ref()
-                .iter()
-                .map(|label| function(label))
-                .collect(),
-            None => labels
-                .as_ref()
-                .iter()
-                .map(|label| format!("This example is about {}.", label))
-                .collect(),
+    /// Saves the compiled tokenizer, the model configuration, the label thresholds and the pipeline
+    /// options to a single file, restored by `load_from_state` without parsing the source files
+    /// again. The weights are not included. The tokenizer and configuration files the pipeline was
+    /// built from must still be available.
+    ///
+    /// # Returns
+    ///
+    /// * `InvalidStateError` if the pipeline was built with an injected tokenizer
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - destination of the state file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let model = ZeroShotClassificationModel::new(Default::default())?;
+    /// model.save_state("zero_shot.state")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> Result<(), RustBertError> {
+        let sources = self.sources.as_ref().ok_or_else(|| {
+            RustBertError::InvalidStateError(
+                "Pipelines built with an injected tokenizer cannot be saved".to_string(),
+            )
+        })?;
+        let options = ZeroShotStateOptions {
+            label_thresholds: self.label_thresholds.clone(),
+            pad_to_multiple_of: self.pad_to_multiple_of,
+            label_chunk_size: self.label_chunk_size,
+            batch_size: self.batch_size,
+            equalize_premise: self.equalize_premise,
+            hypothesis_template: self.hypothesis_template.clone(),
+            entailment_id: Some(self.nli_labels.entailment),
+            contradiction_id: Some(self.nli_labels.contradiction),
+            on_overflow: self.on_overflow,
+            half_precision: self.half_precision,
         };
+        write_state(
+            path.as_ref(),
+            ZERO_SHOT_CLASSIFICATION_STATE,
+            sources,
+            &self.tokenizer,
+            &options,
+        )
+    }
 
-        let text_pair_list = inputs
-            .as_ref()
-            .iter()
-            .flat_map(|input| {
-                label_sentences
-                    .iter()
-                    .map(move |label_sentence| (input.deref(), label_sentence.as_str()))
-            })
-            .collect::<Vec<(&str, &str)>>();
-
-        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
-            text_pair_list.as_ref(),
-            max_len,
-            &TruncationStrategy::LongestFirst,
-            0,
-        );
-        let max_len = tokenized_input
-            .iter()
-            .map(|input| input.token_ids.len())
-            .max()
-            .unwrap();
-        let tokenized_input_tensors: Vec<tch::Tensor> =
-            tokenized_input
-                .iter()
-                .map(|input| input.token_ids.clone())
-                .map(|mut input| {
-                    input.extend(vec![self.tokenizer.get_pad_id().expect(
-                        "The Tokenizer used for zero shot classification should contain a PAD id"
-                    ); max_len - input.len()]);
-                    input
-                })
-                .map(|input| Tensor::of_slice(&(input)))
-                .collect::<Vec<_>>();
-
-        let tokenized_input_tensors =
-            Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device());
+    /// Returns the report of the weights loaded when building the model, listing the tying status
+    /// of the output projections, the repaired tensor shapes and the unused checkpoint tensors.
+    /// The report is updated when the weights are replaced with `swap_weights`.
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
 
-        let mask = tokenized_input_tensors
-            .ne(self
-                .tokenizer
-                .get_pad_id()
-                .expect("The Tokenizer used for zero shot classification should contain a PAD id"))
-            .to_kind(Bool);
+    /// Replaces the weights of the model with a checkpoint sharing its architecture (e.g. another
+    /// fine-tuning of the same base model), keeping the tokenizer and configuration. The checkpoint
+    /// is validated against the model variables before any weight is overwritten: if the swap
+    /// fails, the current weights remain in use. The load report is replaced by the report of the
+    /// new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - `Resource` pointing to the new weights
+    pub fn swap_weights(&mut self, model_resource: Resource) -> Result<(), RustBertError> {
+        let weights_path = model_resource.get_local_path()?;
+        self.load_report =
+            load_with_report(&mut self.var_store, weights_path, self.tie_word_embeddings)?;
+        if self.half_precision {
+            self.var_store.half();
+        }
+        Ok(())
+    }
 
-        (tokenized_input_tensors, mask)
+    /// Returns the device the model is placed on
+    pub fn get_device(&self) -> Device {
+        self.var_store.device()
     }
 
-    /// Zero shot classification with 1 (and exactly 1) true label.
+    /// Moves the model to another device. The input tensors of the subsequent predictions are
+    /// created on the new device.
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
-    /// * `labels` - `&[&str]` Possible labels for the inputs.
-    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
-    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `device` - `Device` to move the model to
     ///
     /// # Returns
     ///
-    /// * `Vec<Label>` containing with the most likely label for each input sentence.
+    /// * `InvalidConfigurationError` if the model runs in half precision and `device` is the CPU. The model is not moved.
     ///
     /// # Example
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
-    ///
-    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
-    ///
-    /// let input_sentence = "Who are you voting for in 2020?";
-    /// let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
-    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     ZeroShotClassificationConfig, ZeroShotClassificationModel,
+    /// };
+    /// use tch::Device;
     ///
-    /// let output = sequence_classification_model.predict(
-    ///     &[input_sentence, input_sequence_2],
-    ///     candidate_labels,
-    ///     None,
-    ///     128,
-    /// );
+    /// let config = ZeroShotClassificationConfig {
+    ///     device: Device::Cpu,
+    ///     ..Default::default()
+    /// };
+    /// let mut model = ZeroShotClassificationModel::new(config)?;
+    /// model.set_device(Device::cuda_if_available())?;
     /// # Ok(())
     /// # }
     /// ```
+    pub fn set_device(&mut self, device: Device) -> Result<(), RustBertError> {
+        if self.half_precision && (device == Device::Cpu) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Half precision inference is only supported on CUDA devices".to_string(),
+            ));
+        }
+        self.var_store.set_device(device);
+        Ok(())
+    }
+
+    /// Sets per-label score thresholds applied by `predict_multilabel`
+    ///
+    /// When set, labels with an entry in the map are only returned by `predict_multilabel` if
+    /// their score is greater or equal to the threshold. Labels without an entry are always returned.
+    /// The thresholds can be obtained from a validation set using `calibrate_thresholds`.
+    ///
+    /// # Arguments
+    ///
+    /// * `label_thresholds` - `Option<HashMap<String, f64>>` mapping label names to thresholds. Set to None to disable filtering.
+    ///
+    /// # Example
     ///
-    /// outputs:
     /// ```no_run
-    /// # use rust_bert::pipelines::sequence_classification::Label;
-    /// let output = [
-    ///     Label {
-    ///         text: "politics".to_string(),
-    ///         score: 0.959,
-    ///         id: 0,
-    ///         sentence: 0,
-    ///     },
-    ///     Label {
-    ///         text: "economy".to_string(),
-    ///         score: 0.642,
-    ///         id: 2,
-    ///         sentence: 1,
-    ///     },
-    /// ]
-    /// .to_vec();
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     calibrate_thresholds, ZeroShotClassificationModel,
+    /// };
+    ///
+    /// let mut model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let validation = [(
+    ///     "The central bank raised interest rates.".to_string(),
+    ///     vec!["economics".to_string()],
+    /// )];
+    /// let report = calibrate_thresholds(&model, &validation, &["politics", "economics"], None, 128)?;
+    /// model.set_label_thresholds(Some(report.thresholds));
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn predict<'a, S, T>(
-        &self,
-        inputs: S,
-        labels: T,
-        template: Option<Box<dyn Fn(&str) -> String>>,
-        max_length: usize,
-    ) -> Vec<Label>
-    where
-        S: AsRef<[&'a str]>,
-        T: AsRef<[&'a str]>,
-    {
-        let num_inputs = inputs.as_ref().len();
-        let (input_tensor, mask) =
-            self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length);
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(input_tensor),
-                Some(mask),
-                None,
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
-        });
-
-        let scores = output.softmax(1, Float).select(-1, -1);
-        let label_indices = scores.as_ref().argmax(-1, true).squeeze1(1);
-        let scores = scores
-            .gather(1, &label_indices.unsqueeze(-1), false)
-            .squeeze1(1);
-        let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
-        let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
-
-        let mut output_labels: Vec<Label> = vec![];
-        for sentence_idx in 0..label_indices.len() {
-            let label_string = labels.as_ref()[label_indices[sentence_idx] as usize].to_string();
-            let label = Label {
-                text: label_string,
-                score: scores[sentence_idx],
-                id: label_indices[sentence_idx],
-                sentence: sentence_idx,
-            };
-            output_labels.push(label)
-        }
-        output_labels
+    pub fn set_label_thresholds(&mut self, label_thresholds: Option<HashMap<String, f64>>) {
+        self.label_thresholds = label_thresholds;
     }
 
-    /// Zero shot multi-label classification with 0, 1 or no true label.
+    /// Returns the hypothesis built for each label, as seen by the model: the same template
+    /// resolution as the prediction methods applies (template passed as argument, then template of
+    /// the configuration, then `"This example is about {}."`).
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
-    /// * `labels` - `&[&str]` Possible labels for the inputs.
-    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
-    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `labels` - `&[&str]` Candidate labels
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions, as passed to the prediction methods
     ///
     /// # Returns
     ///
-    /// * `Vec<Vec<Label>>` containing a vector of labels and their probability for each input text
+    /// * `Vec<String>` containing the hypothesis of each label, in the order of the labels
     ///
     /// # Example
     ///
@@ -728,138 +1232,3580 @@ impl ZeroShotClassificationModel {
     /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
     ///
     /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
-    ///
-    /// let input_sentence = "Who are you voting for in 2020?";
-    /// let input_sequence_2 = "The central bank is meeting today to discuss monetary policy.";
-    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
-    ///
-    /// let output = sequence_classification_model.predict_multilabel(
+    /// let hypotheses = sequence_classification_model.debug_hypotheses(&["politics", "sports"], None);
+    /// assert_eq!(hypotheses[0], "This example is about politics.");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn debug_hypotheses(
+        &self,
+        labels: &[&str],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+    ) -> Vec<String> {
+        self.label_sentences(labels, template.as_deref())
+    }
+
+    fn prepare_for_model<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<&dyn Fn(&str) -> String>,
+        max_len: usize,
+        premise_budget: Option<usize>,
+    ) -> Result<(Tensor, Tensor, Option<Tensor>), RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let tokenized_input = self.encode_pairs(
+            &as_str_slices(inputs.as_ref()),
+            &as_str_slices(labels.as_ref()),
+            template,
+            max_len,
+            premise_budget,
+        );
+        self.pad_pairs(&tokenized_input)
+    }
+
+    /// Pads the encoded pairs to a common length, returning the input ids, attention mask and
+    /// token type ids (for models using them) tensors
+    fn pad_pairs(
+        &self,
+        tokenized_input: &[TokenizedInput],
+    ) -> Result<(Tensor, Tensor, Option<Tensor>), RustBertError> {
+        pad_label_pairs(
+            &self.tokenizer,
+            tokenized_input,
+            self.pad_to_multiple_of,
+            self.half_precision,
+            self.zero_shot_classifier.uses_token_type_ids(),
+            self.var_store.device(),
+        )
+    }
+
+    /// Encodes the pairs of every input with the hypothesis of every label (input-major order),
+    /// truncated to `max_len` tokens
+    fn encode_pairs(
+        &self,
+        inputs: &[&str],
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_len: usize,
+        premise_budget: Option<usize>,
+    ) -> Vec<TokenizedInput> {
+        let label_sentences = self.label_sentences(labels, template);
+        encode_label_pairs(
+            &self.tokenizer,
+            inputs,
+            &label_sentences,
+            max_len,
+            premise_budget,
+        )
+    }
+
+    /// Builds the hypothesis of each label, from the template passed to the prediction method if
+    /// any, or from the template of the configuration
+    fn label_sentences(
+        &self,
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+    ) -> Vec<String> {
+        build_label_sentences(labels, template, self.hypothesis_template.as_deref())
+    }
+
+    /// Returns the number of input tokens kept for every label if the premise is equalized: the
+    /// maximum length minus the special tokens and the longest hypothesis over all labels.
+    fn premise_budget(
+        &self,
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+    ) -> Option<usize> {
+        if !self.equalize_premise {
+            return None;
+        }
+        Some(equalized_premise_budget(
+            &self.tokenizer,
+            &self.label_sentences(labels, template),
+            max_length,
+        ))
+    }
+
+    /// Checks that each input fits within `max_length` tokens alongside the longest hypothesis of
+    /// its labels and the special tokens, if the pipeline rejects overflowing inputs
+    fn check_overflow<'a, L>(
+        &self,
+        inputs: &[&str],
+        labels_per_input: L,
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+    ) -> Result<(), RustBertError>
+    where
+        L: Iterator<Item = &'a [&'a str]>,
+    {
+        if self.on_overflow == OverflowStrategy::Truncate {
+            return Ok(());
+        }
+        let reserved_special = pair_special_tokens(&self.tokenizer);
+        for (input_index, (input, labels)) in inputs.iter().zip(labels_per_input).enumerate() {
+            let reserved_for_hypothesis = self
+                .label_sentences(labels, template)
+                .iter()
+                .map(|label_sentence| self.tokenizer.tokenize(label_sentence).len())
+                .max()
+                .unwrap_or(0);
+            check_input_length(
+                input_index,
+                self.tokenizer.tokenize(input).len(),
+                max_length,
+                reserved_for_hypothesis,
+                reserved_special,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn forward_pairs(
+        &self,
+        inputs: &[&str],
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+        premise_budget: Option<usize>,
+    ) -> Result<Tensor, RustBertError> {
+        let (input_tensor, mask, token_type_ids) =
+            self.prepare_for_model(inputs, labels, template, max_length, premise_budget)?;
+        Ok(self
+            .forward_padded_pairs(input_tensor, mask, token_type_ids)
+            .view((inputs.len() as i64, labels.len() as i64, -1i64)))
+    }
+
+    /// Runs the forward pass over padded pairs in batches of `batch_size` pairs, returning logits of
+    /// shape (*num pairs*, *num classes*)
+    fn forward_padded_pairs(
+        &self,
+        input_tensor: Tensor,
+        mask: Tensor,
+        token_type_ids: Option<Tensor>,
+    ) -> Tensor {
+        let num_pairs = input_tensor.size()[0];
+        let batch_size = self.batch_size.map_or(num_pairs, |size| size as i64);
+        no_grad(|| {
+            //    The pairs share the padded length computed over all pairs, the last batch may be smaller
+            let mut token_type_batches = token_type_ids
+                .map(|token_type_ids| token_type_ids.split(batch_size, 0).into_iter());
+            let batch_logits = input_tensor
+                .split(batch_size, 0)
+                .into_iter()
+                .zip(mask.split(batch_size, 0))
+                .map(|(input_batch, mask_batch)| {
+                    self.zero_shot_classifier.forward_t(
+                        Some(input_batch),
+                        Some(mask_batch),
+                        token_type_batches
+                            .as_mut()
+                            .and_then(|batches| batches.next()),
+                        None,
+                        None,
+                        false,
+                    )
+                })
+                .collect::<Vec<Tensor>>();
+            Tensor::cat(&batch_logits, 0)
+        })
+    }
+
+    /// Runs the forward pass for the pairs of every input with its own labels in a single padded
+    /// batch, returning the logits of each input, of shape (*num labels of the input*, *num classes*)
+    fn forward_varied_pairs(
+        &self,
+        inputs: &[&str],
+        labels_per_input: &[&[&str]],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+    ) -> Result<Vec<Tensor>, RustBertError> {
+        if inputs.is_empty() {
+            return Err(RustBertError::EmptyInputError);
+        }
+        if labels_per_input.len() != inputs.len() {
+            return Err(RustBertError::ValueError(format!(
+                "{} label sets provided for {} inputs",
+                labels_per_input.len(),
+                inputs.len()
+            )));
+        }
+        if labels_per_input.iter().any(|labels| labels.is_empty()) {
+            return Err(RustBertError::EmptyLabelsError);
+        }
+        self.check_overflow(
+            inputs,
+            labels_per_input.iter().copied(),
+            template,
+            max_length,
+        )?;
+        let tokenized_input = inputs
+            .iter()
+            .zip(labels_per_input.iter())
+            .flat_map(|(input, labels)| {
+                let premise_budget = self.premise_budget(labels, template, max_length);
+                self.encode_pairs(&[*input], labels, template, max_length, premise_budget)
+            })
+            .collect::<Vec<TokenizedInput>>();
+        let (input_tensor, mask, token_type_ids) = self.pad_pairs(&tokenized_input)?;
+        let logits = self.forward_padded_pairs(input_tensor, mask, token_type_ids);
+
+        let mut start = 0;
+        Ok(labels_per_input
+            .iter()
+            .map(|labels| {
+                let input_logits = logits.narrow(0, start, labels.len() as i64);
+                start += labels.len() as i64;
+                input_logits
+            })
+            .collect())
+    }
+
+    /// Zero shot classification with 1 (and exactly 1) true label.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing with the most likely label for each input sentence.
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let output = sequence_classification_model.predict(
     ///     &[input_sentence, input_sequence_2],
     ///     candidate_labels,
     ///     None,
     ///     128,
-    /// );
+    /// )?;
+    ///
+    /// // Owned strings (e.g. deserialized from JSON) are accepted as well
+    /// let candidate_labels: Vec<String> = serde_json::from_str(r#"["politics", "sports"]"#)?;
+    /// let output = sequence_classification_model.predict(
+    ///     vec![input_sentence.to_string()],
+    ///     &candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
     /// # Ok(())
     /// # }
     /// ```
+    ///
     /// outputs:
     /// ```no_run
     /// # use rust_bert::pipelines::sequence_classification::Label;
     /// let output = [
-    ///     [
-    ///         Label {
-    ///             text: "politics".to_string(),
-    ///             score: 0.972,
-    ///             id: 0,
-    ///             sentence: 0,
-    ///         },
-    ///         Label {
-    ///             text: "public health".to_string(),
-    ///             score: 0.032,
-    ///             id: 1,
-    ///             sentence: 0,
-    ///         },
-    ///         Label {
-    ///             text: "economy".to_string(),
-    ///             score: 0.006,
-    ///             id: 2,
-    ///             sentence: 0,
-    ///         },
-    ///         Label {
-    ///             text: "sports".to_string(),
-    ///             score: 0.004,
-    ///             id: 3,
-    ///             sentence: 0,
-    ///         },
-    ///     ],
-    ///     [
-    ///         Label {
-    ///             text: "politics".to_string(),
-    ///             score: 0.975,
-    ///             id: 0,
-    ///             sentence: 1,
-    ///         },
-    ///         Label {
-    ///             text: "economy".to_string(),
-    ///             score: 0.852,
-    ///             id: 2,
-    ///             sentence: 1,
-    ///         },
-    ///         Label {
-    ///             text: "public health".to_string(),
-    ///             score: 0.0818,
-    ///             id: 1,
-    ///             sentence: 1,
-    ///         },
-    ///         Label {
-    ///             text: "sports".to_string(),
-    ///             score: 0.001,
-    ///             id: 3,
-    ///             sentence: 1,
-    ///         },
-    ///     ],
+    ///     Label {
+    ///         text: "politics".to_string(),
+    ///         score: 0.959,
+    ///         id: 0,
+    ///         sentence: 0,
+    ///     },
+    ///     Label {
+    ///         text: "economy".to_string(),
+    ///         score: 0.642,
+    ///         id: 2,
+    ///         sentence: 1,
+    ///     },
     /// ]
     /// .to_vec();
     /// ```
-    pub fn predict_multilabel<'a, S, T>(
+    pub fn predict<S, T, I, L>(
         &self,
         inputs: S,
         labels: T,
         template: Option<Box<dyn Fn(&str) -> String>>,
         max_length: usize,
-    ) -> Vec<Vec<Label>>
+    ) -> Result<Vec<Label>, RustBertError>
     where
-        S: AsRef<[&'a str]>,
-        T: AsRef<[&'a str]>,
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
     {
-        let num_inputs = inputs.as_ref().len();
-        let (input_tensor, mask) =
-            self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length);
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(input_tensor),
-                Some(mask),
-                None,
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
-        });
-        let scores = output.slice(-1, 0, 3, 2).softmax(-1, Float).select(-1, -1);
+        self.top_labels(
+            &as_str_slices(inputs.as_ref()),
+            &as_str_slices(labels.as_ref()),
+            template.as_deref(),
+            max_length,
+        )
+    }
 
-        let mut output_labels = vec![];
-        for sentence_idx in 0..num_inputs {
-            let mut sentence_labels = vec![];
+    /// Returns the most likely label for each input
+    fn top_labels(
+        &self,
+        inputs: &[&str],
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+    ) -> Result<Vec<Label>, RustBertError> {
+        let scores = self.single_label_scores(inputs, labels, template, max_length)?;
+        Ok(top_labels_from_scores(&scores, labels, 0))
+    }
 
-            for (label_index, score) in scores
-                .select(0, sentence_idx as i64)
-                .iter::<f64>()
-                .unwrap()
-                .enumerate()
-            {
-                let label_string = labels.as_ref()[label_index].to_string();
-                let label = Label {
-                    text: label_string,
-                    score,
-                    id: label_index as i64,
-                    sentence: sentence_idx,
-                };
-                sentence_labels.push(label);
-            }
-            output_labels.push(sentence_labels);
-        }
-        output_labels
+    /// Zero shot classification with 1 (and exactly 1) true label, in batches of `batch_size`
+    /// inputs. With a pipelined `ChunkScheduling`, the tokenization of the next batches runs on a
+    /// worker thread while the model processes the current batch. Tensors prepared for a CUDA
+    /// device are pinned to speed up their transfer.
+    ///
+    /// Each batch holds the pairs of its inputs with all labels: the `label_chunk_size` of the
+    /// configuration is not applied, while its `batch_size` still splits the forward passes.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `batch_size` - `usize` Maximum number of inputs per batch.
+    /// * `scheduling` - `ChunkScheduling` of the tokenization and forward passes
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing the most likely label for each input, identical to the output of `predict`
+    /// * `RustBertError` if no input or label is provided, if the batch size is 0, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::chunked_inference::ChunkScheduling;
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    /// let output = sequence_classification_model.predict_batched(
+    ///     &["Who are you voting for in 2020?", "The prime minister has announced a stimulus package."],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    ///     32,
+    ///     ChunkScheduling::Pipelined { prefetch: 2 },
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_batched<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        batch_size: usize,
+        scheduling: ChunkScheduling,
+    ) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let inputs = as_str_slices(inputs.as_ref());
+        let labels = as_str_slices(labels.as_ref());
+        let template = template.as_deref();
+        validate_inputs(&inputs, &labels)?;
+        self.check_overflow(
+            &inputs,
+            std::iter::repeat(labels.as_slice()),
+            template,
+            max_length,
+        )?;
+        let premise_budget = self.premise_budget(&labels, template, max_length);
+        //    The hypotheses are built on the calling thread: the template may not be shared
+        let label_sentences = self.label_sentences(&labels, template);
+
+        let device = self.var_store.device();
+        let tokenizer = &self.tokenizer;
+        let pad_to_multiple_of = self.pad_to_multiple_of;
+        let half_precision = self.half_precision;
+        let uses_token_type_ids = self.zero_shot_classifier.uses_token_type_ids();
+        let num_labels = labels.len() as i64;
+        let mut sentence_offset = 0;
+        run_chunked(
+            &inputs,
+            batch_size,
+            scheduling,
+            |batch: &[&str]| {
+                let tokenized_input = encode_label_pairs(
+                    tokenizer,
+                    batch,
+                    &label_sentences,
+                    max_length,
+                    premise_budget,
+                );
+                let (input_tensor, mask, token_type_ids) = pad_label_pairs(
+                    tokenizer,
+                    &tokenized_input,
+                    pad_to_multiple_of,
+                    half_precision,
+                    uses_token_type_ids,
+                    Device::Cpu,
+                )?;
+                Ok((
+                    pin_for_device(input_tensor, device),
+                    pin_for_device(mask, device),
+                    token_type_ids.map(|token_type_ids| pin_for_device(token_type_ids, device)),
+                ))
+            },
+            |(input_tensor, mask, token_type_ids): (Tensor, Tensor, Option<Tensor>)| {
+                let batch_length = input_tensor.size()[0] / num_labels;
+                let logits = self
+                    .forward_padded_pairs(
+                        input_tensor.to(device),
+                        mask.to(device),
+                        token_type_ids.map(|token_type_ids| token_type_ids.to(device)),
+                    )
+                    .view((batch_length, num_labels, -1));
+                let scores = self.nli_labels.entailment_logits(&logits).softmax(1, Float);
+                let output = top_labels_from_scores(&scores, &labels, sentence_offset);
+                sentence_offset += output.len();
+                Ok(output)
+            },
+        )
     }
-}
-#[cfg(test)]
-mod test {
-    use super::*;
 
-    #[test]
-    #[ignore] // no need to run, compilation is enough to verify it is Send
-    fn test() {
-        let config = ZeroShotClassificationConfig::default();
-        let _: Box<dyn Send> = Box::new(ZeroShotClassificationModel::new(config));
+    /// Zero shot classification with 1 (and exactly 1) true label of a stream of inputs.
+    ///
+    /// The inputs are consumed lazily, `batch_size` inputs at a time: each batch is tokenized and
+    /// classified when the labels of the previous batch have been consumed, keeping the memory
+    /// bounded regardless of the number of inputs. The labels are yielded in the input order, with
+    /// the `sentence` of each label being the index of its input in the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - iterator over the texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `batch_size` - `usize` Number of inputs classified together.
+    ///
+    /// # Returns
+    ///
+    /// * Iterator over the most likely label of each input. Once an error is yielded (no label
+    /// provided, batch size of 0, or tokenizer without padding token), the iterator stops.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    /// use std::io::{BufRead, BufReader};
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let reader = BufReader::new(std::fs::File::open("inputs.txt")?);
+    /// let inputs = reader.lines().filter_map(Result::ok);
+    /// for label in
+    ///     sequence_classification_model.predict_iter(inputs, candidate_labels, None, 128, 64)
+    /// {
+    ///     let label = label?;
+    ///     println!("{}\t{}", label.sentence, label.text);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_iter<'a, I>(
+        &'a self,
+        inputs: I,
+        labels: &'a [&'a str],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        batch_size: usize,
+    ) -> impl Iterator<Item = Result<Label, RustBertError>> + 'a
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        let mut inputs = inputs.into_iter();
+        let mut batch_labels = VecDeque::new();
+        let mut offset = 0;
+        let mut stopped = false;
+        std::iter::from_fn(move || {
+            if let Some(label) = batch_labels.pop_front() {
+                return Some(Ok(label));
+            }
+            if stopped {
+                return None;
+            }
+            if batch_size == 0 {
+                stopped = true;
+                return Some(Err(RustBertError::ValueError(
+                    "The batch size must be strictly positive".to_string(),
+                )));
+            }
+            let batch = inputs.by_ref().take(batch_size).collect::<Vec<String>>();
+            if batch.is_empty() {
+                stopped = true;
+                return None;
+            }
+            let batch = batch.iter().map(String::as_str).collect::<Vec<&str>>();
+            match self.top_labels(&batch, labels, template.as_deref(), max_length) {
+                Ok(output) => {
+                    batch_labels.extend(output.into_iter().map(|mut label| {
+                        label.sentence += offset;
+                        label
+                    }));
+                    offset += batch.len();
+                    batch_labels.pop_front().map(Ok)
+                }
+                Err(error) => {
+                    stopped = true;
+                    Some(Err(error))
+                }
+            }
+        })
+    }
+
+    /// Zero shot classification with 1 (and exactly 1) true label, reporting the truncation of the
+    /// input and label pairs.
+    ///
+    /// Long inputs are truncated to fit within `max_length` tokens together with the hypothesis
+    /// of each label, which may leave a fragment of the input to classify. The report contains a
+    /// `TruncationInfo` for every input and label pair, indicating the number of tokens dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `(Vec<Label>, Vec<TruncationInfo>)` the most likely label for each input sentence (as
+    /// returned by `predict`), and the truncation of each input and label pair (input-major order).
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let (output, report) = sequence_classification_model.predict_with_report(
+    ///     &[input_sentence],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
+    /// let truncated_inputs = report
+    ///     .iter()
+    ///     .filter(|info| info.truncated())
+    ///     .map(|info| info.sentence)
+    ///     .collect::<Vec<usize>>();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_report<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<(Vec<Label>, Vec<TruncationInfo>), RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let inputs = as_str_slices(inputs.as_ref());
+        let labels = as_str_slices(labels.as_ref());
+        let (inputs, labels) = (inputs.as_slice(), labels.as_slice());
+        validate_inputs(inputs, labels)?;
+        let report = {
+            let template = template.as_deref();
+            let premise_budget = self.premise_budget(labels, template, max_length);
+            self.truncation_report(inputs, labels, template, max_length, premise_budget)
+        };
+        let output = self.predict(inputs, labels, template, max_length)?;
+        Ok((output, report))
+    }
+
+    /// Returns the truncation of each input and label pair, encoded as for the forward pass
+    fn truncation_report(
+        &self,
+        inputs: &[&str],
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+        premise_budget: Option<usize>,
+    ) -> Vec<TruncationInfo> {
+        self.encode_pairs(inputs, labels, template, max_length, premise_budget)
+            .iter()
+            .enumerate()
+            .map(|(pair_index, tokenized_input)| TruncationInfo {
+                sentence: pair_index / labels.len(),
+                label_id: (pair_index % labels.len()) as i64,
+                num_truncated_tokens: tokenized_input.num_truncated_tokens,
+            })
+            .collect()
+    }
+
+    /// Zero shot classification of structured records made of several text fields, with a single true label
+    ///
+    /// Each field is truncated to its token budget before the fields are concatenated, following
+    /// the order and separator of the `FieldConfig`. The texts built are then classified as with `predict`.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - `&[Record]` Array of records to classify.
+    /// * `field_config` - `&FieldConfig` token budget of each field, concatenation order and separator
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing the most likely label for each input record
+    /// * `RustBertError` if the field configuration is invalid or if a record has no configured field
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::records::{FieldConfig, Record};
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let field_config = FieldConfig::new(&[("title", 16), ("body", 64), ("tags", 8)], ". ");
+    /// let records = [Record::new()
+    ///     .with_field("title", "Stimulus package criticized")
+    ///     .with_field("body", "The prime minister has announced a stimulus package...")
+    ///     .with_field("tags", "government")];
+    /// let output = sequence_classification_model.predict_records(
+    ///     &records,
+    ///     &field_config,
+    ///     &["politics", "public health", "economics", "sports"],
+    ///     None,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_records(
+        &self,
+        records: &[Record],
+        field_config: &FieldConfig,
+        labels: &[&str],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Label>, RustBertError> {
+        let texts = build_record_texts(&self.tokenizer, records, field_config)?;
+        self.predict(&texts, labels, template, max_length)
+    }
+
+    /// Evaluates the single-label classification of the model on a labelled dataset, returning the
+    /// accuracy, the F1 scores, the confusion matrix and the misclassified examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - `&[(String, String)]` texts with their gold label
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to the `hypothesis_template` of the configuration or `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `EvalReport` of the predictions on the dataset
+    /// * `RustBertError` if no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let dataset = [
+    ///     ("Who are you voting for in 2020?".to_string(), "politics".to_string()),
+    ///     ("The team won the cup.".to_string(), "sports".to_string()),
+    /// ];
+    /// let report = sequence_classification_model.evaluate(
+    ///     &dataset,
+    ///     &["politics", "economics", "sports"],
+    ///     None,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn evaluate(
+        &self,
+        dataset: &[(String, String)],
+        labels: &[&str],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<EvalReport, RustBertError> {
+        let texts = dataset
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<&str>>();
+        let predictions = if texts.is_empty() {
+            vec![]
+        } else {
+            self.predict(&texts, labels, template, max_length)?
+        };
+        single_label_report(dataset, &predictions)
+    }
+
+    /// Evaluates the multi-label classification of the model on a labelled dataset, returning the
+    /// exact match accuracy, the F1 scores, the counts of each label and the misclassified examples.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - `&[(String, Vec<String>)]` texts with their gold labels
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to the `hypothesis_template` of the configuration or `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `threshold` - `f64` score above which a label is predicted, for the labels without a threshold set on the model
+    ///
+    /// # Returns
+    ///
+    /// * `MultilabelEvalReport` of the predictions on the dataset
+    /// * `RustBertError` if no label is provided, or if the tokenizer has no padding token
+    pub fn evaluate_multilabel(
+        &self,
+        dataset: &[(String, Vec<String>)],
+        labels: &[&str],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        threshold: f64,
+    ) -> Result<MultilabelEvalReport, RustBertError> {
+        let texts = dataset
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<&str>>();
+        let predictions = if texts.is_empty() {
+            vec![]
+        } else {
+            let mut predictions = self.predict_multilabel(&texts, labels, template, max_length)?;
+            for sentence_labels in predictions.iter_mut() {
+                sentence_labels.retain(|label| {
+                    self.label_thresholds
+                        .as_ref()
+                        .map_or(false, |label_thresholds| {
+                            label_thresholds.contains_key(&label.text)
+                        })
+                        || (label.score >= threshold)
+                });
+            }
+            predictions
+        };
+        multilabel_report(dataset, &predictions)
+    }
+
+    /// Zero shot classification with at most 1 true label, abstaining when the most likely label is not confident enough.
+    ///
+    /// The scores are normalized across labels as in `predict`. No label is returned for an input if the score of its
+    /// most likely label is lower than `min_score`, or if the gap between the scores of its two most likely labels
+    /// is lower than `min_margin`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `min_score` - `Option<f64>` minimum score of the most likely label. If None, the score is not checked.
+    /// * `min_margin` - `Option<f64>` minimum gap between the scores of the two most likely labels. If None, the gap is not checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Option<Label>>` containing the most likely label for each input sentence, or None if the model abstained.
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let candidate_labels = &["billing", "technical support", "sales"];
+    /// let output = sequence_classification_model.predict_opt(
+    ///     &["My invoice shows the wrong amount.", "Hello"],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    ///     Some(0.5),
+    ///     Some(0.2),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_opt<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        min_score: Option<f64>,
+        min_margin: Option<f64>,
+    ) -> Result<Vec<Option<Label>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let labels = as_str_slices(labels.as_ref());
+        let scores = self.single_label_scores(
+            &as_str_slices(inputs.as_ref()),
+            &labels,
+            template.as_deref(),
+            max_length,
+        )?;
+        Ok(select_confident_labels(&scores, min_score, min_margin)
+            .into_iter()
+            .enumerate()
+            .map(|(sentence_idx, selection)| {
+                selection.map(|(label_index, score)| Label {
+                    text: labels[label_index as usize].to_string(),
+                    score,
+                    id: label_index,
+                    sentence: sentence_idx,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the entailment scores normalized across labels, of shape (*num inputs*, *num labels*)
+    fn single_label_scores(
+        &self,
+        inputs: &[&str],
+        labels: &[&str],
+        template: Option<&dyn Fn(&str) -> String>,
+        max_length: usize,
+    ) -> Result<Tensor, RustBertError> {
+        validate_inputs(inputs, labels)?;
+        self.check_overflow(inputs, std::iter::repeat(labels), template, max_length)?;
+        let premise_budget = self.premise_budget(labels, template, max_length);
+        //    Entailment logits are gathered for all labels before normalizing across labels
+        let entailment_logits = forward_label_chunks(
+            inputs.len(),
+            labels.len(),
+            self.label_chunk_size,
+            |input_range, label_range| {
+                self.forward_pairs(
+                    &inputs[input_range],
+                    &labels[label_range],
+                    template,
+                    max_length,
+                    premise_budget,
+                )
+            },
+            |logits| self.nli_labels.entailment_logits(&logits),
+        )?;
+        Ok(entailment_logits.softmax(1, Float))
+    }
+
+    /// Zero shot classification with 1 (and exactly 1) true label, returning an individual result for each input.
+    ///
+    /// Inputs are validated one by one before batching: invalid inputs (e.g. empty texts) do not
+    /// fail the entire batch but are excluded from the forward pass and carry their own error.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Result<Label, RustBertError>>` containing the most likely label or an error for each input sentence, in the input order.
+    /// * `RustBertError` if no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    /// let output = sequence_classification_model.predict_each(
+    ///     &["Who are you voting for in 2020?", ""],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
+    /// assert!(output[1].is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_each<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Result<Label, RustBertError>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let (valid_inputs, validation) = split_valid_inputs(&as_str_slices(inputs.as_ref()));
+        let output_labels = if valid_inputs.is_empty() {
+            vec![]
+        } else {
+            self.predict(&valid_inputs, labels, template, max_length)?
+        };
+        Ok(align_outputs(validation, output_labels)
+            .into_iter()
+            .enumerate()
+            .map(|(sentence_idx, label)| {
+                label.map(|mut label| {
+                    label.sentence = sentence_idx;
+                    label
+                })
+            })
+            .collect())
+    }
+
+    /// Zero shot multi-label classification with 0, 1 or no true label.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing a vector of labels and their probability for each input text. If label thresholds
+    /// were set with `set_label_thresholds`, labels scoring below their threshold are omitted.
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let input_sequence_2 = "The central bank is meeting today to discuss monetary policy.";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let output = sequence_classification_model.predict_multilabel(
+    ///     &[input_sentence, input_sequence_2],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// outputs:
+    /// ```no_run
+    /// # use rust_bert::pipelines::sequence_classification::Label;
+    /// let output = [
+    ///     [
+    ///         Label {
+    ///             text: "politics".to_string(),
+    ///             score: 0.972,
+    ///             id: 0,
+    ///             sentence: 0,
+    ///         },
+    ///         Label {
+    ///             text: "public health".to_string(),
+    ///             score: 0.032,
+    ///             id: 1,
+    ///             sentence: 0,
+    ///         },
+    ///         Label {
+    ///             text: "economy".to_string(),
+    ///             score: 0.006,
+    ///             id: 2,
+    ///             sentence: 0,
+    ///         },
+    ///         Label {
+    ///             text: "sports".to_string(),
+    ///             score: 0.004,
+    ///             id: 3,
+    ///             sentence: 0,
+    ///         },
+    ///     ],
+    ///     [
+    ///         Label {
+    ///             text: "politics".to_string(),
+    ///             score: 0.975,
+    ///             id: 0,
+    ///             sentence: 1,
+    ///         },
+    ///         Label {
+    ///             text: "economy".to_string(),
+    ///             score: 0.852,
+    ///             id: 2,
+    ///             sentence: 1,
+    ///         },
+    ///         Label {
+    ///             text: "public health".to_string(),
+    ///             score: 0.0818,
+    ///             id: 1,
+    ///             sentence: 1,
+    ///         },
+    ///         Label {
+    ///             text: "sports".to_string(),
+    ///             score: 0.001,
+    ///             id: 3,
+    ///             sentence: 1,
+    ///         },
+    ///     ],
+    /// ]
+    /// .to_vec();
+    /// ```
+    pub fn predict_multilabel<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let output_labels = self.predict_multilabel_scores(inputs, labels, template, max_length)?;
+        Ok(self.apply_label_thresholds(output_labels))
+    }
+
+    /// Removes the labels scoring below their threshold, if label thresholds were set
+    fn apply_label_thresholds(&self, mut output_labels: Vec<Vec<Label>>) -> Vec<Vec<Label>> {
+        if let Some(label_thresholds) = &self.label_thresholds {
+            for sentence_labels in output_labels.iter_mut() {
+                sentence_labels.retain(|label| {
+                    label_thresholds
+                        .get(&label.text)
+                        .map_or(true, |threshold| label.score >= *threshold)
+                });
+            }
+        }
+        output_labels
+    }
+
+    /// Zero shot multi-label classification keeping the labels scoring at least `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `threshold` - `f64` minimum entailment probability of the labels returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing, for each input text, the labels scoring at least `threshold` sorted by
+    /// decreasing score. Inputs for which no label passes the threshold get an empty vector. Label thresholds set
+    /// with `set_label_thresholds` also apply.
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let input_sequence_2 = "The central bank is meeting today to discuss monetary policy.";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let output = sequence_classification_model.predict_multilabel_with_threshold(
+    ///     &[input_sentence, input_sequence_2],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    ///     0.5,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multilabel_with_threshold<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        threshold: f64,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let output_labels = self.predict_multilabel(inputs, labels, template, max_length)?;
+        Ok(threshold_labels(output_labels, threshold))
+    }
+
+    /// Zero shot classification with 1 (and exactly 1) true label, each input having its own candidate labels.
+    ///
+    /// The pairs of all inputs are classified in a single padded batch (split according to the
+    /// `batch_size` of the configuration), and the scores of an input are normalized over its own labels only.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels_per_input` - `&[&[&str]]` Possible labels of each input, in the input order.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing the most likely label for each input sentence, with `id` the index of the label in the labels of its input.
+    /// * `RustBertError` if no input is provided, if an input has no label or the number of label sets does not match the number of inputs,
+    /// or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let output = sequence_classification_model.predict_varied(
+    ///     &[
+    ///         "My invoice shows the wrong amount.",
+    ///         "The application crashes on startup.",
+    ///     ],
+    ///     &[&["billing", "refund"], &["bug", "feature request", "documentation"]],
+    ///     None,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_varied(
+        &self,
+        inputs: &[&str],
+        labels_per_input: &[&[&str]],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Label>, RustBertError> {
+        let logits =
+            self.forward_varied_pairs(inputs, labels_per_input, template.as_deref(), max_length)?;
+        Ok(logits
+            .iter()
+            .zip(labels_per_input.iter())
+            .enumerate()
+            .map(|(sentence_idx, (logits, labels))| {
+                let scores = self
+                    .nli_labels
+                    .entailment_logits(logits)
+                    .softmax(-1, Float)
+                    .to_kind(Double);
+                let label_index = scores.argmax(-1, false).int64_value(&[]);
+                Label {
+                    text: labels[label_index as usize].to_string(),
+                    score: scores.double_value(&[label_index]),
+                    id: label_index,
+                    sentence: sentence_idx,
+                }
+            })
+            .collect())
+    }
+
+    /// Zero shot multi-label classification with 0, 1 or no true label, each input having its own candidate labels.
+    ///
+    /// The pairs of all inputs are classified in a single padded batch (split according to the
+    /// `batch_size` of the configuration).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels_per_input` - `&[&[&str]]` Possible labels of each input, in the input order.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing, for each input text, its labels and their probability in the order of its labels. If label
+    /// thresholds were set with `set_label_thresholds`, labels scoring below their threshold are omitted.
+    /// * `RustBertError` if no input is provided, if an input has no label or the number of label sets does not match the number of inputs,
+    /// or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let output = sequence_classification_model.predict_multilabel_varied(
+    ///     &[
+    ///         "My invoice shows the wrong amount.",
+    ///         "The application crashes on startup.",
+    ///     ],
+    ///     &[&["billing", "refund"], &["bug", "feature request", "documentation"]],
+    ///     None,
+    ///     128,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multilabel_varied(
+        &self,
+        inputs: &[&str],
+        labels_per_input: &[&[&str]],
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Vec<Label>>, RustBertError> {
+        let logits =
+            self.forward_varied_pairs(inputs, labels_per_input, template.as_deref(), max_length)?;
+        let output_labels = logits
+            .iter()
+            .zip(labels_per_input.iter())
+            .enumerate()
+            .map(|(sentence_idx, (logits, labels))| {
+                let scores = self
+                    .nli_labels
+                    .contradiction_entailment_logits(logits)
+                    .softmax(-1, Float)
+                    .select(-1, 1);
+                scores
+                    .iter::<f64>()
+                    .unwrap()
+                    .zip(labels.iter())
+                    .enumerate()
+                    .map(|(label_index, (score, label))| Label {
+                        text: label.to_string(),
+                        score,
+                        id: label_index as i64,
+                        sentence: sentence_idx,
+                    })
+                    .collect::<Vec<Label>>()
+            })
+            .collect::<Vec<Vec<Label>>>();
+        Ok(self.apply_label_thresholds(output_labels))
+    }
+
+    /// Zero shot classification returning the full Natural Language Inference distribution of each
+    /// input and label pair, along with the hypothesis built for the label. The contradiction,
+    /// neutral and entailment probabilities of a pair sum to 1 and are not normalized across labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to the `hypothesis_template` of the configuration or `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<ZeroShotPrediction>>` containing a prediction for each label of each input, in the order of the labels
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    /// let output = sequence_classification_model.predict_full(
+    ///     &["Who are you voting for in 2020?"],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
+    /// //    Reject the labels for which the model is mostly undecided
+    /// let decided_labels = output[0]
+    ///     .iter()
+    ///     .filter(|prediction| prediction.neutral_score < 0.5)
+    ///     .collect::<Vec<_>>();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_full<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Vec<ZeroShotPrediction>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let inputs = as_str_slices(inputs.as_ref());
+        let labels = as_str_slices(labels.as_ref());
+        let (inputs, labels) = (inputs.as_slice(), labels.as_slice());
+        validate_inputs(inputs, labels)?;
+        let template = template.as_deref();
+        self.check_overflow(inputs, std::iter::repeat(labels), template, max_length)?;
+        let premise_budget = self.premise_budget(labels, template, max_length);
+        let scores = forward_label_chunks(
+            inputs.len(),
+            labels.len(),
+            self.label_chunk_size,
+            |input_range, label_range| {
+                self.forward_pairs(
+                    &inputs[input_range],
+                    &labels[label_range],
+                    template,
+                    max_length,
+                    premise_budget,
+                )
+            },
+            |logits| logits.softmax(-1, Float),
+        )?;
+        Ok(build_full_predictions(
+            &scores,
+            labels,
+            &self.label_sentences(labels, template),
+            self.nli_labels,
+        ))
+    }
+
+    /// Zero shot classification returning the raw Natural Language Inference logits of each input
+    /// and label pair, before any softmax (e.g. for ensembling or temperature scaling). The pairs
+    /// are built, truncated and batched as in `predict_full`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Array of texts to classify (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `labels` - Possible labels for the inputs (e.g. `&[&str]`, `&[String]` or `Vec<String>`).
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to the `hypothesis_template` of the configuration or `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    /// * `Tensor` of shape (*number of inputs*, *number of labels*, *number of NLI classes*) in single precision, moved to the CPU. The class order is the order of the model configuration.
+    /// * `RustBertError` if no input or no label is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    /// let logits = sequence_classification_model.predict_logits(
+    ///     &["Who are you voting for in 2020?"],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// )?;
+    /// assert_eq!(logits.size(), vec![1, 4, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_logits<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Tensor, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let inputs = as_str_slices(inputs.as_ref());
+        let labels = as_str_slices(labels.as_ref());
+        let (inputs, labels) = (inputs.as_slice(), labels.as_slice());
+        validate_inputs(inputs, labels)?;
+        let template = template.as_deref();
+        self.check_overflow(inputs, std::iter::repeat(labels), template, max_length)?;
+        let premise_budget = self.premise_budget(labels, template, max_length);
+        let logits = forward_label_chunks(
+            inputs.len(),
+            labels.len(),
+            self.label_chunk_size,
+            |input_range, label_range| {
+                self.forward_pairs(
+                    &inputs[input_range],
+                    &labels[label_range],
+                    template,
+                    max_length,
+                    premise_budget,
+                )
+            },
+            |logits| logits.to_kind(Float),
+        )?;
+        Ok(logits.to(Device::Cpu))
+    }
+
+    /// Compares two texts against a label, returning which of the two better matches the label.
+    ///
+    /// Both texts of all pairs are scored against the label in a single batch, truncated to the same
+    /// number of tokens. The score of a text is the difference between its entailment and
+    /// contradiction logits, optionally normalized by the number of tokens of the text (see
+    /// `LengthNormalization`), making the scores of premises of different lengths comparable.
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - `&[(&str, &str)]` Pairs of texts to compare
+    /// * `label` - `&str` Label the texts are compared against
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build the label proposition. If None, will default to the `hypothesis_template` of the configuration or `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the texts will be truncated before the label template.
+    /// * `normalization` - `LengthNormalization` applied to the scores before comparison
+    ///
+    /// # Returns
+    /// * `Vec<Comparison>` containing the winner of each pair and the margin between the two scores
+    /// * `RustBertError` if no pair is provided, or if the tokenizer has no padding token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::{
+    ///     LengthNormalization, ZeroShotClassificationModel,
+    /// };
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let output = sequence_classification_model.compare(
+    ///     &[(
+    ///         "The central bank raised its rates by 50 basis points.",
+    ///         "The team won the championship after a late goal.",
+    ///     )],
+    ///     "economics",
+    ///     None,
+    ///     128,
+    ///     LengthNormalization::PerToken,
+    /// )?;
+    /// assert_eq!(output[0].winner, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compare(
+        &self,
+        pairs: &[(&str, &str)],
+        label: &str,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+        normalization: LengthNormalization,
+    ) -> Result<Vec<Comparison>, RustBertError> {
+        let inputs = pairs
+            .iter()
+            .flat_map(|&(first, second)| vec![first, second])
+            .collect::<Vec<&str>>();
+        validate_inputs(&inputs, &[label])?;
+        let template = template.as_deref();
+        self.check_overflow(
+            &inputs,
+            std::iter::repeat(&[label][..]),
+            template,
+            max_length,
+        )?;
+        let premise_budget = equalized_premise_budget(
+            &self.tokenizer,
+            &self.label_sentences(&[label], template),
+            max_length,
+        );
+        let logits = self
+            .forward_pairs(
+                &inputs,
+                &[label],
+                template,
+                max_length,
+                Some(premise_budget),
+            )?
+            .select(1, 0)
+            .to(Device::Cpu);
+        let premise_lengths = inputs
+            .iter()
+            .map(|input| self.tokenizer.tokenize(input).len().min(premise_budget))
+            .collect::<Vec<usize>>();
+        Ok(compare_pairs(
+            &logits,
+            &premise_lengths,
+            normalization,
+            self.nli_labels,
+        ))
+    }
+
+    fn predict_multilabel_scores<S, T, I, L>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<[I]>,
+        T: AsRef<[L]>,
+        I: AsRef<str>,
+        L: AsRef<str>,
+    {
+        let inputs = as_str_slices(inputs.as_ref());
+        let labels = as_str_slices(labels.as_ref());
+        let (inputs, labels) = (inputs.as_slice(), labels.as_slice());
+        validate_inputs(inputs, labels)?;
+        let num_inputs = inputs.len();
+        let template = template.as_deref();
+        self.check_overflow(inputs, std::iter::repeat(labels), template, max_length)?;
+        let premise_budget = self.premise_budget(labels, template, max_length);
+        //    Scores are normalized for each input and label pair and can be computed chunk by chunk
+        let scores = forward_label_chunks(
+            inputs.len(),
+            labels.len(),
+            self.label_chunk_size,
+            |input_range, label_range| {
+                self.forward_pairs(
+                    &inputs[input_range],
+                    &labels[label_range],
+                    template,
+                    max_length,
+                    premise_budget,
+                )
+            },
+            |logits| {
+                self.nli_labels
+                    .contradiction_entailment_logits(&logits)
+                    .softmax(-1, Float)
+                    .select(-1, 1)
+            },
+        )?;
+
+        let mut output_labels = vec![];
+        for sentence_idx in 0..num_inputs {
+            let mut sentence_labels = vec![];
+
+            for (label_index, score) in scores
+                .select(0, sentence_idx as i64)
+                .iter::<f64>()
+                .unwrap()
+                .enumerate()
+            {
+                let label_string = labels[label_index].to_string();
+                let label = Label {
+                    text: label_string,
+                    score,
+                    id: label_index as i64,
+                    sentence: sentence_idx,
+                };
+                sentence_labels.push(label);
+            }
+            output_labels.push(sentence_labels);
+        }
+        Ok(output_labels)
+    }
+}
+
+/// Keeps the labels of each input scoring at least `threshold`, sorted by decreasing score
+fn threshold_labels(output_labels: Vec<Vec<Label>>, threshold: f64) -> Vec<Vec<Label>> {
+    output_labels
+        .into_iter()
+        .map(|sentence_labels| {
+            let mut sentence_labels = sentence_labels
+                .into_iter()
+                .filter(|label| label.score >= threshold)
+                .collect::<Vec<Label>>();
+            sentence_labels.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+            sentence_labels
+        })
+        .collect()
+}
+
+/// Builds the hypothesis of each label with the template closure or, if not provided, the
+/// string template, defaulting to `"This example is about {}."`
+fn build_label_sentences(
+    labels: &[&str],
+    template: Option<&dyn Fn(&str) -> String>,
+    hypothesis_template: Option<&str>,
+) -> Vec<String> {
+    match (template, hypothesis_template) {
+        (Some(function), _) => labels.iter().map(|label| function(label)).collect(),
+        (None, Some(hypothesis_template)) => labels
+            .iter()
+            .map(|label| hypothesis_template.replacen("{}", label, 1))
+            .collect(),
+        (None, None) => labels
+            .iter()
+            .map(|label| format!("This example is about {}.", label))
+            .collect(),
+    }
+}
+
+/// Checks that a hypothesis template contains exactly one `{}` placeholder
+pub(crate) fn validate_hypothesis_template(hypothesis_template: &str) -> Result<(), RustBertError> {
+    match hypothesis_template.matches("{}").count() {
+        1 => Ok(()),
+        count => Err(RustBertError::InvalidConfigurationError(format!(
+            "The hypothesis template must contain exactly one `{{}}` placeholder for the label, found {} in \"{}\"",
+            count, hypothesis_template
+        ))),
+    }
+}
+
+fn empty_token_ids() -> TokenIdsWithOffsets {
+    TokenIdsWithOffsets {
+        ids: vec![],
+        offsets: vec![],
+        reference_offsets: vec![],
+        masks: vec![],
+    }
+}
+
+/// Returns the number of special tokens added to a premise and hypothesis pair
+fn pair_special_tokens(tokenizer: &TokenizerOption) -> usize {
+    tokenizer
+        .build_input_with_special_tokens(empty_token_ids(), Some(empty_token_ids()))
+        .token_ids
+        .len()
+}
+
+fn equalized_premise_budget(
+    tokenizer: &TokenizerOption,
+    label_sentences: &[String],
+    max_length: usize,
+) -> usize {
+    let longest_hypothesis = label_sentences
+        .iter()
+        .map(|label_sentence| tokenizer.tokenize(label_sentence).len())
+        .max()
+        .unwrap_or(0);
+    max_length.saturating_sub(longest_hypothesis + pair_special_tokens(tokenizer))
+}
+
+/// Encodes a text truncated to its first `max_tokens` tokens, returning the number of tokens dropped
+fn encode_truncated(
+    tokenizer: &TokenizerOption,
+    text: &str,
+    max_tokens: usize,
+) -> (TokenIdsWithOffsets, usize) {
+    let mut tokens = tokenizer.tokenize_with_offsets(text);
+    let num_truncated_tokens = tokens.tokens.len().saturating_sub(max_tokens);
+    tokens.tokens.truncate(max_tokens);
+    tokens.offsets.truncate(max_tokens);
+    tokens.reference_offsets.truncate(max_tokens);
+    tokens.masks.truncate(max_tokens);
+    (
+        TokenIdsWithOffsets {
+            ids: tokenizer.convert_tokens_to_ids(&tokens.tokens),
+            offsets: tokens.offsets,
+            reference_offsets: tokens.reference_offsets,
+            masks: tokens.masks,
+        },
+        num_truncated_tokens,
+    )
+}
+
+/// Encodes premise and hypothesis pairs, truncating every premise to `premise_budget` tokens. The
+/// hypotheses are only truncated if they do not fit within the maximum length on their own.
+fn encode_equalized_pairs(
+    tokenizer: &TokenizerOption,
+    text_pair_list: &[(&str, &str)],
+    premise_budget: usize,
+    max_length: usize,
+) -> Vec<TokenizedInput> {
+    let hypothesis_budget = max_length.saturating_sub(pair_special_tokens(tokenizer));
+    text_pair_list
+        .iter()
+        .map(|(premise, hypothesis)| {
+            let (premise, premise_truncated_tokens) =
+                encode_truncated(tokenizer, premise, premise_budget);
+            let (hypothesis, hypothesis_truncated_tokens) =
+                encode_truncated(tokenizer, hypothesis, hypothesis_budget);
+            let mut tokenized_input =
+                tokenizer.build_input_with_special_tokens(premise, Some(hypothesis));
+            tokenized_input.num_truncated_tokens =
+                premise_truncated_tokens + hypothesis_truncated_tokens;
+            tokenized_input
+        })
+        .collect()
+}
+
+/// Selects the most likely label of each input from scores of shape (*num inputs*, *num labels*),
+/// returning its index and score. No label is selected for an input if its top score is lower than
+/// `min_score`, or if the gap between its two top scores is lower than `min_margin` (with a single
+/// label, the gap is the top score itself).
+fn select_confident_labels(
+    scores: &Tensor,
+    min_score: Option<f64>,
+    min_margin: Option<f64>,
+) -> Vec<Option<(i64, f64)>> {
+    let num_labels = scores.size()[1];
+    let (top_scores, top_indices) = scores.topk(num_labels.min(2), -1, true, true);
+    let top_scores = top_scores.to_kind(Double);
+    (0..top_scores.size()[0])
+        .map(|sentence_idx| {
+            let score = top_scores.double_value(&[sentence_idx, 0]);
+            let runner_up_score = if num_labels > 1 {
+                top_scores.double_value(&[sentence_idx, 1])
+            } else {
+                0f64
+            };
+            let confident = min_score.map_or(true, |min_score| score >= min_score)
+                & min_margin.map_or(true, |min_margin| score - runner_up_score >= min_margin);
+            if confident {
+                Some((top_indices.int64_value(&[sentence_idx, 0]), score))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the most likely label of each input from the scores of shape (*num inputs*, *num labels*),
+/// the `sentence` of the labels starting at `sentence_offset`
+fn top_labels_from_scores(scores: &Tensor, labels: &[&str], sentence_offset: usize) -> Vec<Label> {
+    let label_indices = scores.argmax(-1, true).squeeze1(1);
+    let scores = scores
+        .gather(1, &label_indices.unsqueeze(-1), false)
+        .squeeze1(1);
+    let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
+    let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+
+    label_indices
+        .iter()
+        .zip(scores.iter())
+        .enumerate()
+        .map(|(sentence_idx, (&label_index, &score))| Label {
+            text: labels[label_index as usize].to_string(),
+            score,
+            id: label_index,
+            sentence: sentence_offset + sentence_idx,
+        })
+        .collect()
+}
+
+/// Encodes the pairs of every input with every hypothesis (input-major order), truncated to
+/// `max_len` tokens
+fn encode_label_pairs(
+    tokenizer: &TokenizerOption,
+    inputs: &[&str],
+    label_sentences: &[String],
+    max_len: usize,
+    premise_budget: Option<usize>,
+) -> Vec<TokenizedInput> {
+    let text_pair_list = inputs
+        .iter()
+        .flat_map(|input| {
+            label_sentences
+                .iter()
+                .map(move |label_sentence| (input.deref(), label_sentence.as_str()))
+        })
+        .collect::<Vec<(&str, &str)>>();
+
+    match premise_budget {
+        Some(premise_budget) => {
+            encode_equalized_pairs(tokenizer, &text_pair_list, premise_budget, max_len)
+        }
+        None => tokenizer.encode_pair_list(
+            text_pair_list.as_ref(),
+            max_len,
+            &TruncationStrategy::LongestFirst,
+            0,
+        ),
+    }
+}
+
+/// Pads the encoded pairs to a common length on `device`, returning the input ids, attention mask
+/// and token type ids (if `uses_token_type_ids`) tensors
+fn pad_label_pairs(
+    tokenizer: &TokenizerOption,
+    tokenized_input: &[TokenizedInput],
+    pad_to_multiple_of: Option<usize>,
+    half_precision: bool,
+    uses_token_type_ids: bool,
+    device: Device,
+) -> Result<(Tensor, Tensor, Option<Tensor>), RustBertError> {
+    let pad_id = tokenizer
+        .get_pad_id()
+        .ok_or(RustBertError::MissingPadTokenError)?;
+    let max_len = tokenized_input
+        .iter()
+        .map(|input| input.token_ids.len())
+        .max()
+        .ok_or(RustBertError::EmptyInputError)?;
+    let max_len = get_padded_length(max_len, pad_to_multiple_of);
+    let tokenized_input_tensors: Vec<tch::Tensor> = tokenized_input
+        .iter()
+        .map(|input| input.token_ids.clone())
+        .map(|mut input| {
+            input.extend(vec![pad_id; max_len - input.len()]);
+            input
+        })
+        .map(|input| Tensor::of_slice(&(input)))
+        .collect::<Vec<_>>();
+
+    let tokenized_input_tensors = Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(device);
+
+    //    In half precision, the mask is converted by the models to the kind of the attention
+    //    scores: it is provided in half precision rather than as a boolean tensor.
+    let mask_kind = if half_precision { Half } else { Bool };
+    let mask = tokenized_input_tensors.ne(pad_id).to_kind(mask_kind);
+
+    let token_type_ids = if uses_token_type_ids {
+        let token_type_ids = tokenized_input
+            .iter()
+            .map(|input| {
+                let mut segment_ids = input
+                    .segment_ids
+                    .iter()
+                    .map(|&segment_id| segment_id as i64)
+                    .collect::<Vec<i64>>();
+                segment_ids.resize(max_len, 0);
+                Tensor::of_slice(&segment_ids)
+            })
+            .collect::<Vec<_>>();
+        Some(Tensor::stack(token_type_ids.as_slice(), 0).to(device))
+    } else {
+        None
+    };
+
+    Ok((tokenized_input_tensors, mask, token_type_ids))
+}
+
+/// Runs the forward pass for all input and label pairs and reduces the logits of shape
+/// (*num inputs*, *num labels*, *num classes*) to a tensor of shape (*num inputs*, *num labels*, ...).
+/// If a label chunk size is provided, each input is processed separately in chunks of labels and the
+/// reduced chunks are gathered on the CPU, bounding the size of each forward pass to `label_chunk_size`
+/// pairs.
+fn forward_label_chunks<F, R>(
+    num_inputs: usize,
+    num_labels: usize,
+    label_chunk_size: Option<usize>,
+    forward: F,
+    reduce: R,
+) -> Result<Tensor, RustBertError>
+where
+    F: Fn(Range<usize>, Range<usize>) -> Result<Tensor, RustBertError>,
+    R: Fn(Tensor) -> Tensor,
+{
+    match label_chunk_size {
+        Some(chunk_size) if (chunk_size > 0) & (chunk_size < num_labels) => {
+            let input_scores = (0..num_inputs)
+                .map(|input_index| {
+                    let chunk_scores = (0..num_labels)
+                        .step_by(chunk_size)
+                        .map(|chunk_start| {
+                            let chunk_end = (chunk_start + chunk_size).min(num_labels);
+                            let logits =
+                                forward(input_index..input_index + 1, chunk_start..chunk_end)?;
+                            Ok(reduce(logits).to(Device::Cpu))
+                        })
+                        .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+                    Ok(Tensor::cat(&chunk_scores, 1))
+                })
+                .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+            Ok(Tensor::cat(&input_scores, 0))
+        }
+        _ => Ok(reduce(forward(0..num_inputs, 0..num_labels)?)),
+    }
+}
+
+/// Borrows string-like inputs or labels as string slices
+fn as_str_slices<T: AsRef<str>>(values: &[T]) -> Vec<&str> {
+    values.iter().map(AsRef::as_ref).collect()
+}
+
+/// Checks that inputs and candidate labels are provided before building the premise and hypothesis pairs
+fn validate_inputs(inputs: &[&str], labels: &[&str]) -> Result<(), RustBertError> {
+    if inputs.is_empty() {
+        Err(RustBertError::EmptyInputError)
+    } else if labels.is_empty() {
+        Err(RustBertError::EmptyLabelsError)
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Truncation of an input and label pair
+/// Returned by `ZeroShotClassificationModel::predict_with_report`
+pub struct TruncationInfo {
+    /// Sentence index
+    pub sentence: usize,
+    /// Label index in the candidate labels
+    pub label_id: i64,
+    /// Number of tokens dropped from the input and hypothesis pair to fit the maximum length
+    pub num_truncated_tokens: usize,
+}
+
+impl TruncationInfo {
+    /// Returns true if tokens were dropped from the input and hypothesis pair
+    pub fn truncated(&self) -> bool {
+        self.num_truncated_tokens > 0
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Natural Language Inference distribution of an input and label pair
+pub struct ZeroShotPrediction {
+    /// Label
+    pub label: String,
+    /// Label index in the candidate labels
+    pub label_id: i64,
+    /// Sentence index
+    pub sentence: usize,
+    /// Hypothesis built for the label and paired with the input
+    pub hypothesis: String,
+    /// Probability that the input entails the hypothesis
+    pub entailment_score: f64,
+    /// Probability that the input is neutral with respect to the hypothesis (0 for models without a neutral class)
+    pub neutral_score: f64,
+    /// Probability that the input contradicts the hypothesis
+    pub contradiction_score: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Positions of the entailment and contradiction classes in the NLI logits, negative positions
+/// being counted from the last class
+struct NliLabelIds {
+    entailment: i64,
+    contradiction: i64,
+}
+
+impl NliLabelIds {
+    /// Resolves the class positions from the configured values, falling back to the `label2id`
+    /// mapping of the model configuration and then to (contradiction, ..., entailment) ordering.
+    fn resolve(
+        entailment_id: Option<i64>,
+        contradiction_id: Option<i64>,
+        label2id: Option<&HashMap<String, i64>>,
+        num_classes: i64,
+    ) -> Result<NliLabelIds, RustBertError> {
+        let find_label = |name: &str| {
+            label2id.and_then(|label2id| {
+                label2id
+                    .iter()
+                    .find(|(label, _)| label.eq_ignore_ascii_case(name))
+                    .map(|(_, &id)| id)
+            })
+        };
+        if num_classes < 2 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Zero-shot classification requires a Natural Language Inference model with at least 2 classes, got {}",
+                num_classes
+            )));
+        }
+        let entailment = entailment_id
+            .or_else(|| find_label("entailment"))
+            .unwrap_or(-1);
+        let contradiction = contradiction_id
+            .or_else(|| find_label("contradiction"))
+            .or_else(|| match num_classes {
+                //    Two-class models (e.g. entailment and not entailment): the other class
+                //    stands for the contradiction
+                2 => Some(1 - normalize_class_id(entailment, 2)),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        let nli_labels = NliLabelIds {
+            entailment,
+            contradiction,
+        };
+        for &(name, id) in [("entailment", entailment), ("contradiction", contradiction)].iter() {
+            if id >= num_classes || id < -num_classes {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "The {} id {} is out of range for a model with {} classes",
+                    name, id, num_classes
+                )));
+            }
+        }
+        let (entailment, contradiction) = nli_labels.positions(num_classes);
+        if entailment == contradiction {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "The entailment and contradiction ids both point to class {}",
+                entailment
+            )));
+        }
+        Ok(nli_labels)
+    }
+
+    /// Resolves the class positions for a model configuration, whose number of classes is the
+    /// size of the `id2label` mapping the classification head is built from (or of `label2id`)
+    fn from_config(
+        entailment_id: Option<i64>,
+        contradiction_id: Option<i64>,
+        model_config: &ConfigOption,
+    ) -> Result<NliLabelIds, RustBertError> {
+        let num_classes = model_config
+            .get_id2label()
+            .map(HashMap::len)
+            .or_else(|| model_config.get_label2id().map(HashMap::len))
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "The model configuration does not define the classes of the classification head (id2label)".to_string(),
+                )
+            })?;
+        NliLabelIds::resolve(
+            entailment_id,
+            contradiction_id,
+            model_config.get_label2id(),
+            num_classes as i64,
+        )
+    }
+
+    /// Returns the (non-negative) positions of the entailment and contradiction classes
+    fn positions(&self, num_classes: i64) -> (i64, i64) {
+        (
+            normalize_class_id(self.entailment, num_classes),
+            normalize_class_id(self.contradiction, num_classes),
+        )
+    }
+
+    /// Selects the entailment logits, removing the class dimension
+    fn entailment_logits(&self, logits: &Tensor) -> Tensor {
+        logits.select(-1, self.entailment)
+    }
+
+    /// Stacks the contradiction and entailment logits (in this order) along the class dimension,
+    /// ignoring any other class (e.g. neutral)
+    fn contradiction_entailment_logits(&self, logits: &Tensor) -> Tensor {
+        Tensor::stack(
+            &[
+                logits.select(-1, self.contradiction),
+                logits.select(-1, self.entailment),
+            ],
+            -1,
+        )
+    }
+}
+
+fn normalize_class_id(id: i64, num_classes: i64) -> i64 {
+    if id < 0 {
+        id + num_classes
+    } else {
+        id
+    }
+}
+
+/// Converts the NLI probabilities of shape (*num inputs*, *num labels*, *num classes*) to
+/// predictions. Classes other than the entailment and contradiction (e.g. neutral) make up the
+/// neutral score.
+fn build_full_predictions(
+    scores: &Tensor,
+    labels: &[&str],
+    label_sentences: &[String],
+    nli_labels: NliLabelIds,
+) -> Vec<Vec<ZeroShotPrediction>> {
+    let (num_inputs, num_labels, num_classes) = scores.size3().unwrap();
+    let (entailment, contradiction) = nli_labels.positions(num_classes);
+    let scores = scores.to_kind(Double);
+    (0..num_inputs)
+        .map(|sentence_idx| {
+            (0..num_labels)
+                .map(|label_idx| {
+                    let pair_scores = scores.get(sentence_idx).get(label_idx);
+                    ZeroShotPrediction {
+                        label: labels[label_idx as usize].to_string(),
+                        label_id: label_idx,
+                        sentence: sentence_idx as usize,
+                        hypothesis: label_sentences[label_idx as usize].clone(),
+                        entailment_score: pair_scores.double_value(&[entailment]),
+                        neutral_score: (0..num_classes)
+                            .filter(|&class| class != entailment && class != contradiction)
+                            .map(|class| pair_scores.double_value(&[class]))
+                            .sum(),
+                        contradiction_score: pair_scores.double_value(&[contradiction]),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// # Normalization of the scores compared by `ZeroShotClassificationModel::compare`
+pub enum LengthNormalization {
+    /// Texts are compared on the difference between their entailment and contradiction logits
+    None,
+    /// The difference between the entailment and contradiction logits is divided by the number of
+    /// tokens of the text (after truncation), so that longer texts are not favored by accumulating
+    /// evidence for the label
+    PerToken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// # Output of the comparison of two texts against a label
+pub struct Comparison {
+    /// Index of the text of the pair better matching the label (0 or 1). If both texts have the
+    /// same score, the first text (0) wins with a margin of 0.
+    pub winner: usize,
+    /// Difference between the (normalized) scores of the winning and losing texts, greater or equal to 0
+    pub margin: f64,
+}
+
+/// Compares consecutive inputs from their logits of shape (*2 x num pairs*, *num NLI labels*), the
+/// inputs of pair `i` being at rows `2i` and `2i + 1`.
+fn compare_pairs(
+    logits: &Tensor,
+    premise_lengths: &[usize],
+    normalization: LengthNormalization,
+    nli_labels: NliLabelIds,
+) -> Vec<Comparison> {
+    let pair_logits = nli_labels
+        .contradiction_entailment_logits(logits)
+        .to_kind(Double);
+    let scores = (pair_logits.select(-1, 1) - pair_logits.select(-1, 0))
+        .iter::<f64>()
+        .unwrap()
+        .zip(premise_lengths.iter())
+        .map(|(score, &length)| match normalization {
+            LengthNormalization::None => score,
+            LengthNormalization::PerToken => score / length.max(1) as f64,
+        })
+        .collect::<Vec<f64>>();
+    scores
+        .chunks(2)
+        .map(|pair_scores| {
+            let (first, second) = (pair_scores[0], pair_scores[1]);
+            if first >= second {
+                Comparison {
+                    winner: 0,
+                    margin: first - second,
+                }
+            } else {
+                Comparison {
+                    winner: 1,
+                    margin: second - first,
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+/// # Classification metrics for a single label at a given threshold
+pub struct LabelThresholdMetrics {
+    /// Score threshold (a label is predicted if its score is greater or equal to this value)
+    pub threshold: f64,
+    /// Precision at the threshold
+    pub precision: f64,
+    /// Recall at the threshold
+    pub recall: f64,
+    /// F1 score at the threshold
+    pub f1: f64,
+    /// Number of validation examples for which the label is true
+    pub support: usize,
+}
+
+#[derive(Debug, Clone)]
+/// # Report generated by `calibrate_thresholds`
+pub struct ThresholdCalibrationReport {
+    /// Selected threshold for each label, can be passed to `ZeroShotClassificationModel::set_label_thresholds`
+    pub thresholds: HashMap<String, f64>,
+    /// Precision, recall and F1 score on the validation set for each label at the selected threshold
+    pub metrics: HashMap<String, LabelThresholdMetrics>,
+}
+
+/// Calibrates per-label thresholds for multi-label zero-shot classification
+///
+/// Scores a labelled validation set with `predict_multilabel` and selects for each label the threshold
+/// maximizing the F1 score. The candidate thresholds are the observed scores for the label (i.e. every
+/// empirical quantile of the score distribution). Thresholds set on the model are ignored while scoring
+/// the validation set.
+///
+/// # Arguments
+///
+/// * `model` - `ZeroShotClassificationModel` used to score the validation set
+/// * `validation` - `&[(String, Vec<String>)]` validation examples made of a text and the names of the labels that are true for this text
+/// * `labels` - `&[&str]` Possible labels for the inputs.
+/// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+/// * `max_length` -`usize` Maximum sequence length for the inputs.
+///
+/// # Returns
+///
+/// * `ThresholdCalibrationReport` containing the selected thresholds and the associated metrics for each label
+/// * `RustBertError` if no label is provided, if the tokenizer has no padding token, or `RustBertError::ValueError` if the model returns a NaN score
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::zero_shot_classification::{
+///     calibrate_thresholds, ZeroShotClassificationModel,
+/// };
+///
+/// let model = ZeroShotClassificationModel::new(Default::default())?;
+/// let validation = [
+///     (
+///         "Who are you voting for in 2020?".to_string(),
+///         vec!["politics".to_string()],
+///     ),
+///     (
+///         "The central bank raised interest rates.".to_string(),
+///         vec!["economics".to_string(), "politics".to_string()],
+///     ),
+/// ];
+/// let report = calibrate_thresholds(&model, &validation, &["politics", "economics"], None, 128)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn calibrate_thresholds(
+    model: &ZeroShotClassificationModel,
+    validation: &[(String, Vec<String>)],
+    labels: &[&str],
+    template: Option<Box<dyn Fn(&str) -> String>>,
+    max_length: usize,
+) -> Result<ThresholdCalibrationReport, RustBertError> {
+    let inputs = validation
+        .iter()
+        .map(|(text, _)| text.as_str())
+        .collect::<Vec<&str>>();
+    let predictions = if inputs.is_empty() {
+        vec![]
+    } else {
+        model.predict_multilabel_scores(&inputs, labels, template, max_length)?
+    };
+
+    let mut thresholds = HashMap::new();
+    let mut metrics = HashMap::new();
+    for (label_index, label) in labels.iter().enumerate() {
+        let scores = predictions
+            .iter()
+            .map(|sentence_labels| sentence_labels[label_index].score)
+            .collect::<Vec<f64>>();
+        let gold = validation
+            .iter()
+            .map(|(_, gold_labels)| gold_labels.iter().any(|gold_label| gold_label == label))
+            .collect::<Vec<bool>>();
+        let label_metrics = sweep_threshold(&scores, &gold).map_err(|input_index| {
+            RustBertError::ValueError(format!(
+                "NaN score for label {} on validation input {}",
+                label, input_index
+            ))
+        })?;
+        thresholds.insert(label.to_string(), label_metrics.threshold);
+        metrics.insert(label.to_string(), label_metrics);
+    }
+    Ok(ThresholdCalibrationReport {
+        thresholds,
+        metrics,
+    })
+}
+
+fn threshold_metrics(
+    scores: &[f64],
+    gold: &[bool],
+    threshold: f64,
+    support: usize,
+) -> LabelThresholdMetrics {
+    let (mut true_positives, mut false_positives) = (0usize, 0usize);
+    for (score, is_true) in scores.iter().zip(gold.iter()) {
+        if *score >= threshold {
+            if *is_true {
+                true_positives += 1;
+            } else {
+                false_positives += 1;
+            }
+        }
+    }
+    let precision = if true_positives + false_positives > 0 {
+        true_positives as f64 / (true_positives + false_positives) as f64
+    } else {
+        0.0
+    };
+    let recall = if support > 0 {
+        true_positives as f64 / support as f64
+    } else {
+        0.0
+    };
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+    LabelThresholdMetrics {
+        threshold,
+        precision,
+        recall,
+        f1,
+        support,
+    }
+}
+
+/// Selects the threshold maximizing the F1 score for a single label. Ties are resolved in favour of
+/// the highest threshold. If the label is never true in the validation set, the threshold is set to 1.0.
+/// Returns the index of the first NaN score as an error, as NaN scores cannot be ordered.
+fn sweep_threshold(scores: &[f64], gold: &[bool]) -> Result<LabelThresholdMetrics, usize> {
+    if let Some(nan_index) = scores.iter().position(|score| score.is_nan()) {
+        return Err(nan_index);
+    }
+    let support = gold.iter().filter(|is_true| **is_true).count();
+    if support == 0 {
+        return Ok(threshold_metrics(scores, gold, 1.0, support));
+    }
+    let mut candidates = scores.to_vec();
+    candidates.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    candidates.dedup();
+
+    let mut best: Option<LabelThresholdMetrics> = None;
+    for candidate in candidates {
+        let metrics = threshold_metrics(scores, gold, candidate, support);
+        if best.as_ref().map_or(true, |best| metrics.f1 > best.f1) {
+            best = Some(metrics);
+        }
+    }
+    Ok(best.unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = ZeroShotClassificationConfig::default();
+        let _: Box<dyn Send> = Box::new(ZeroShotClassificationModel::new(config));
+    }
+
+    fn tiny_distilbert_config(
+        directory: &std::path::Path,
+    ) -> anyhow::Result<ZeroShotClassificationConfig> {
+        use crate::fixtures::tiny_distilbert_resources;
+
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris",
+            "this", "example", "about", "travel", "pets", "food", "sports", "politics", "music",
+            ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) = tiny_distilbert_resources(
+            directory,
+            &vocab,
+            Some(&["contradiction", "neutral", "entailment"]),
+        )?;
+        let mut config = ZeroShotClassificationConfig::new(
+            ModelType::DistilBert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_batched_pairs() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let inputs = [
+            "the dog is in paris.",
+            "the dog is in paris. the dog is in paris.",
+            "this dog",
+        ];
+        let labels = ["travel", "pets", "food", "sports", "politics"];
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let expected = model.forward_pairs(&inputs, &labels, None, 32, None)?;
+        let expected_scores = model.predict_multilabel(&inputs, &labels, None, 32)?;
+
+        //    Batch sizes dividing the 15 pairs or leaving a smaller last batch
+        for &batch_size in [1, 4, 5, 15, 64].iter() {
+            let mut config = tiny_distilbert_config(directory.path())?;
+            config.batch_size = Some(batch_size);
+            let model = ZeroShotClassificationModel::new(config)?;
+            let logits = model.forward_pairs(&inputs, &labels, None, 32, None)?;
+            assert_eq!(logits.size(), vec![3, 5, 3]);
+            assert!(logits.equal(&expected));
+
+            let scores = model.predict_multilabel(&inputs, &labels, None, 32)?;
+            for (labels, expected_labels) in scores.iter().zip(expected_scores.iter()) {
+                for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                    assert_eq!(label.score, expected_label.score);
+                }
+            }
+        }
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.batch_size = Some(0);
+        assert!(ZeroShotClassificationModel::new(config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_batched() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let inputs = [
+            "the dog is in paris.",
+            "the dog is in paris. the dog is in paris.",
+            "this dog",
+            "this example is about music.",
+            "the dog",
+        ];
+        let labels = ["travel", "pets", "food", "sports"];
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let expected = model.predict(&inputs, &labels, None, 32)?;
+
+        for &scheduling in [
+            ChunkScheduling::Sequential,
+            ChunkScheduling::Pipelined { prefetch: 1 },
+            ChunkScheduling::Pipelined { prefetch: 2 },
+        ]
+        .iter()
+        {
+            for &batch_size in [1, 2, 5, 64].iter() {
+                let output =
+                    model.predict_batched(&inputs, &labels, None, 32, batch_size, scheduling)?;
+                assert_eq!(output.len(), expected.len());
+                for (label, expected_label) in output.iter().zip(expected.iter()) {
+                    assert_eq!(label.text, expected_label.text);
+                    assert_eq!(label.id, expected_label.id);
+                    assert_eq!(label.sentence, expected_label.sentence);
+                    assert!((label.score - expected_label.score).abs() < 1e-6);
+                }
+            }
+        }
+
+        assert!(model
+            .predict_batched(&inputs, &labels, None, 32, 0, ChunkScheduling::Sequential)
+            .is_err());
+        assert!(matches!(
+            model.predict_batched(
+                &inputs,
+                &[] as &[&str],
+                None,
+                32,
+                2,
+                ChunkScheduling::Sequential
+            ),
+            Err(RustBertError::EmptyLabelsError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_logits() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let inputs = ["the dog is in paris.", "this dog"];
+        let labels = ["travel", "pets", "food"];
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let logits = model.predict_logits(&inputs, &labels, None, 32)?;
+        assert_eq!(logits.size(), vec![2, 3, 3]);
+        assert_eq!(logits.device(), Device::Cpu);
+
+        //    The softmax of the logits matches the probabilities of `predict_full`
+        let predictions = model.predict_full(&inputs, &labels, None, 32)?;
+        let probabilities = logits.softmax(-1, Float);
+        let (entailment, _) = model.nli_labels.positions(3);
+        for (input_index, input_predictions) in predictions.iter().enumerate() {
+            for (label_index, prediction) in input_predictions.iter().enumerate() {
+                let expected = probabilities.double_value(&[
+                    input_index as i64,
+                    label_index as i64,
+                    entailment,
+                ]);
+                assert!((prediction.entailment_score - expected).abs() < 1e-6);
+            }
+        }
+
+        //    Processing the labels in chunks does not change the logits
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.label_chunk_size = Some(2);
+        let model = ZeroShotClassificationModel::new(config)?;
+        let chunked_logits = model.predict_logits(&inputs, &labels, None, 32)?;
+        assert!(chunked_logits.allclose(&logits, 1e-5, 1e-6, false));
+
+        assert!(matches!(
+            model.predict_logits(&inputs, &[] as &[&str], None, 32),
+            Err(RustBertError::EmptyLabelsError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_tokenizer() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let config = tiny_distilbert_config(directory.path())?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+        let inputs = ["the dog is in paris."];
+        let labels = ["travel", "pets", "food"];
+        let expected = ZeroShotClassificationModel::new(config)?
+            .forward_pairs(&inputs, &labels, None, 32, None)?;
+
+        //    The vocabulary resource is not read when the tokenizer is injected
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.vocab_resource = Resource::Local(LocalResource {
+            local_path: directory.path().join("missing_vocab.txt"),
+        });
+        let model = ZeroShotClassificationModel::new_with_tokenizer(config, tokenizer)?;
+        let logits = model.forward_pairs(&inputs, &labels, None, 32, None)?;
+        assert!(logits.equal(&expected));
+        assert!(matches!(
+            model.save_state(directory.path().join("zero_shot.state")),
+            Err(RustBertError::InvalidStateError(_))
+        ));
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.model_type = ModelType::XLMRoberta;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+        assert!(matches!(
+            ZeroShotClassificationModel::new_with_tokenizer(config, tokenizer),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "bert")]
+    fn test_bert_token_type_ids() -> anyhow::Result<()> {
+        use crate::fixtures::tiny_bert_resources;
+
+        let directory = tempfile::tempdir()?;
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris",
+            "this", "example", "about", "travel", "pets", "food", ".",
+        ];
+        let (model_resource, config_resource, vocab_resource) = tiny_bert_resources(
+            directory.path(),
+            &vocab,
+            &["contradiction", "neutral", "entailment"],
+        )?;
+        let mut config = ZeroShotClassificationConfig::new(
+            ModelType::Bert,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            None,
+            true,
+            None,
+            None,
+        );
+        config.device = Device::Cpu;
+        let model = ZeroShotClassificationModel::new(config)?;
+        let inputs = ["the dog is in paris.", "this dog"];
+        let labels = ["travel", "pets", "food"];
+
+        //    The hypothesis tokens (and the separator ending it) are in the second segment
+        let (input_ids, mask, token_type_ids) =
+            model.prepare_for_model(&inputs, &labels, None, 32, None)?;
+        let token_type_ids = token_type_ids.expect("BERT models use token type ids");
+        assert_eq!(token_type_ids.size(), input_ids.size());
+        let first_pair = token_type_ids
+            .get(0)
+            .iter::<i64>()
+            .unwrap()
+            .collect::<Vec<i64>>();
+        assert_eq!(
+            first_pair,
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1]
+        );
+
+        //    The logits match the forward pass with the segments the model was trained with, and
+        //    differ from the forward pass without segments
+        let logits = model.forward_pairs(&inputs, &labels, None, 32, None)?;
+        let (with_segments, without_segments) = no_grad(|| {
+            let forward = |token_type_ids| {
+                model
+                    .zero_shot_classifier
+                    .forward_t(
+                        Some(input_ids.copy()),
+                        Some(mask.copy()),
+                        token_type_ids,
+                        None,
+                        None,
+                        false,
+                    )
+                    .view((2, 3, -1))
+            };
+            (forward(Some(token_type_ids)), forward(None))
+        });
+        assert!(logits.allclose(&with_segments, 1e-5, 1e-6, false));
+        assert!(!logits.allclose(&without_segments, 1e-5, 1e-6, false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_equalized_premise() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let vocab_path = directory.path().join("vocab.txt");
+        let vocab = [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris",
+            "this", "example", "about", "a", "very", "long", "label", "with", "many", "words", ".",
+        ];
+        std::fs::write(&vocab_path, vocab.join("\n"))?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            None,
+            None,
+        )?;
+
+        let premise = "the dog is in paris the dog is in paris the dog is in paris";
+        let labels = ["dog", "a very long label with many words in this example"];
+        let label_sentences = build_label_sentences(&labels, None, None);
+        let text_pair_list = label_sentences
+            .iter()
+            .map(|label_sentence| (premise, label_sentence.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        let max_length = 24;
+        let premise_tokens = |encoded: &TokenizedInput| {
+            encoded
+                .token_ids
+                .iter()
+                .zip(encoded.segment_ids.iter())
+                .filter(|(_, &segment_id)| segment_id == 0)
+                .map(|(&token_id, _)| token_id)
+                .collect::<Vec<i64>>()
+        };
+
+        //    By default, the premise is truncated further for longer hypotheses
+        let encoded = tokenizer.encode_pair_list(
+            &text_pair_list,
+            max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        assert_ne!(premise_tokens(&encoded[0]), premise_tokens(&encoded[1]));
+
+        let premise_budget = equalized_premise_budget(&tokenizer, &label_sentences, max_length);
+        //    15 tokens for the longest hypothesis and 3 special tokens
+        assert_eq!(premise_budget, 6);
+        let encoded =
+            encode_equalized_pairs(&tokenizer, &text_pair_list, premise_budget, max_length);
+        assert_eq!(premise_tokens(&encoded[0]), premise_tokens(&encoded[1]));
+        assert_eq!(premise_tokens(&encoded[0]).len(), premise_budget + 2);
+        assert!(encoded
+            .iter()
+            .all(|encoded| encoded.token_ids.len() <= max_length));
+        assert_eq!(encoded[1].token_ids.len(), max_length);
+        //    The premise is 15 tokens long, the hypotheses are not truncated
+        assert!(encoded
+            .iter()
+            .all(|encoded| encoded.num_truncated_tokens == 15 - premise_budget));
+        Ok(())
+    }
+
+    #[test]
+    fn test_half_precision_cpu() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.half_precision = true;
+        assert!(matches!(
+            ZeroShotClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        if tch::Cuda::is_available() {
+            let mut config = tiny_distilbert_config(directory.path())?;
+            config.device = Device::Cuda(0);
+            let expected = ZeroShotClassificationModel::new(config)?.predict_multilabel(
+                &["the dog is in paris."],
+                &["travel", "pets"],
+                None,
+                32,
+            )?;
+            let mut config = tiny_distilbert_config(directory.path())?;
+            config.device = Device::Cuda(0);
+            config.half_precision = true;
+            let output = ZeroShotClassificationModel::new(config)?.predict_multilabel(
+                &["the dog is in paris."],
+                &["travel", "pets"],
+                None,
+                32,
+            )?;
+            for (label, expected_label) in output[0].iter().zip(expected[0].iter()) {
+                assert!((label.score - expected_label.score).abs() < 1e-2);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_round_trip() -> anyhow::Result<()> {
+        use crate::resources::LocalResource;
+
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.hypothesis_template = Some("This text is about {}.".to_string());
+        config.batch_size = Some(2);
+        let weights_path = config.model_resource.get_local_path()?;
+        let mut model = ZeroShotClassificationModel::new(config)?;
+        let state_path = directory.path().join("zero_shot.state");
+        model.save_state(&state_path)?;
+
+        let weights = || {
+            Resource::Local(LocalResource {
+                local_path: weights_path.clone(),
+            })
+        };
+        let restored =
+            ZeroShotClassificationModel::load_from_state(&state_path, weights(), Device::Cpu)?;
+        assert_eq!(restored.hypothesis_template, model.hypothesis_template);
+        assert_eq!(restored.batch_size, Some(2));
+        assert!(!restored.half_precision);
+        let inputs = ["the dog is in paris.", "this example is about music."];
+        let labels = ["travel", "pets", "music"];
+        let expected = model.predict_multilabel(&inputs, &labels, None, 32)?;
+        let output = restored.predict_multilabel(&inputs, &labels, None, 32)?;
+        for (labels, expected_labels) in output.iter().zip(expected.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert_eq!(label.text, expected_label.text);
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+
+        //    Half precision is restored, and rejected on the CPU
+        model.half_precision = true;
+        model.save_state(&state_path)?;
+        assert!(matches!(
+            ZeroShotClassificationModel::load_from_state(&state_path, weights(), Device::Cpu),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+        if tch::Cuda::is_available() {
+            let restored = ZeroShotClassificationModel::load_from_state(
+                &state_path,
+                weights(),
+                Device::Cuda(0),
+            )?;
+            assert!(restored.half_precision);
+            assert_eq!(
+                restored.var_store.variables()["classifier.weight"].kind(),
+                Half
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_iter() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = [
+            "the dog is in paris.",
+            "this dog",
+            "the dog is in paris. the dog is in paris.",
+            "music",
+            "this example is about food.",
+        ];
+        let labels = ["travel", "pets", "food"];
+        let expected = model.predict(&inputs, &labels, None, 32)?;
+
+        for &batch_size in [1, 2, 5, 16].iter() {
+            let output = model
+                .predict_iter(
+                    inputs.iter().map(|input| input.to_string()),
+                    &labels,
+                    None,
+                    32,
+                    batch_size,
+                )
+                .collect::<Result<Vec<Label>, RustBertError>>()?;
+            assert_eq!(output.len(), expected.len());
+            for (label, expected_label) in output.iter().zip(expected.iter()) {
+                assert_eq!(label.sentence, expected_label.sentence);
+                assert_eq!(label.id, expected_label.id);
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+
+        //    Inputs are only consumed when the labels of the previous batch have been yielded
+        let consumed = std::cell::Cell::new(0);
+        let mut output = model.predict_iter(
+            inputs.iter().map(|input| {
+                consumed.set(consumed.get() + 1);
+                input.to_string()
+            }),
+            &labels,
+            None,
+            32,
+            2,
+        );
+        assert!(output.next().unwrap().is_ok());
+        assert_eq!(consumed.get(), 2);
+        assert!(output.next().unwrap().is_ok());
+        assert_eq!(consumed.get(), 2);
+        assert!(output.next().unwrap().is_ok());
+        assert_eq!(consumed.get(), 4);
+
+        assert_eq!(
+            model.predict_iter(Vec::new(), &labels, None, 32, 2).count(),
+            0
+        );
+        let errors = model
+            .predict_iter(vec!["this dog".to_string()], &labels, None, 32, 0)
+            .collect::<Vec<Result<Label, RustBertError>>>();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].is_err());
+        let errors = model
+            .predict_iter(vec!["this dog".to_string(); 3], &[], None, 32, 2)
+            .collect::<Vec<Result<Label, RustBertError>>>();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], Err(RustBertError::EmptyLabelsError)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_predict_varied() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["the dog is in paris.", "this dog is in paris. the dog"];
+        let labels_per_input: [&[&str]; 2] = [&["travel", "pets"], &["food", "sports", "music"]];
+
+        let output = model.predict_varied(&inputs, &labels_per_input, None, 32)?;
+        let multilabel_output =
+            model.predict_multilabel_varied(&inputs, &labels_per_input, None, 32)?;
+        assert_eq!(output.len(), 2);
+        assert_eq!(multilabel_output[0].len(), 2);
+        assert_eq!(multilabel_output[1].len(), 3);
+
+        //    Padding to the longest pair of the batch does not change the scores of an input
+        for (sentence_idx, (input, labels)) in
+            inputs.iter().zip(labels_per_input.iter()).enumerate()
+        {
+            let expected = model.predict(&[*input], *labels, None, 32)?;
+            assert_eq!(output[sentence_idx].text, expected[0].text);
+            assert_eq!(output[sentence_idx].id, expected[0].id);
+            assert_eq!(output[sentence_idx].sentence, sentence_idx);
+            assert!((output[sentence_idx].score - expected[0].score).abs() < 1e-5);
+
+            let expected = model.predict_multilabel(&[*input], *labels, None, 32)?;
+            for (label, expected_label) in multilabel_output[sentence_idx]
+                .iter()
+                .zip(expected[0].iter())
+            {
+                assert_eq!(label.text, expected_label.text);
+                assert_eq!(label.sentence, sentence_idx);
+                assert!((label.score - expected_label.score).abs() < 1e-5);
+            }
+        }
+
+        assert!(matches!(
+            model.predict_varied(&inputs, &labels_per_input[..1], None, 32),
+            Err(RustBertError::ValueError(_))
+        ));
+        let no_labels: [&str; 0] = [];
+        assert!(matches!(
+            model.predict_multilabel_varied(&inputs, &[&["travel"], &no_labels], None, 32),
+            Err(RustBertError::EmptyLabelsError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_too_long() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.on_overflow = OverflowStrategy::Error;
+        let model = ZeroShotClassificationModel::new(config)?;
+        //    6 and 12 tokens, the hypotheses are 6 tokens long and the pairs have 3 special tokens
+        let inputs = [
+            "the dog is in paris.",
+            "the dog is in paris. the dog is in paris.",
+        ];
+        let labels = ["travel", "pets"];
+
+        assert!(model.predict(&inputs, &labels, None, 21).is_ok());
+        match model.predict(&inputs, &labels, None, 20) {
+            Err(RustBertError::InputTooLong {
+                input_index,
+                tokens,
+                limit,
+                reserved_for_hypothesis,
+                reserved_special,
+            }) => {
+                assert_eq!(input_index, 1);
+                assert_eq!(tokens, 12);
+                assert_eq!(limit, 20);
+                assert_eq!(reserved_for_hypothesis, 6);
+                assert_eq!(reserved_special, 3);
+            }
+            _ => panic!("Expected an InputTooLong error"),
+        }
+        assert!(matches!(
+            model.predict_multilabel(&inputs, &labels, None, 20),
+            Err(RustBertError::InputTooLong { input_index: 1, .. })
+        ));
+        assert!(matches!(
+            model.predict_full(&inputs, &labels, None, 20),
+            Err(RustBertError::InputTooLong { input_index: 1, .. })
+        ));
+        //    The budget depends on the labels of each input
+        assert!(model
+            .predict_varied(&inputs, &[&["travel"], &["pets"]], None, 21)
+            .is_ok());
+        assert!(matches!(
+            model.predict_varied(
+                &inputs,
+                &[&["travel"], &["pets"]],
+                Some(Box::new(|label: &str| format!(
+                    "this example is about {} pets.",
+                    label
+                ))),
+                21
+            ),
+            Err(RustBertError::InputTooLong {
+                input_index: 1,
+                tokens: 12,
+                limit: 21,
+                reserved_for_hypothesis: 7,
+                reserved_special: 3,
+            })
+        ));
+
+        //    Inputs are truncated by default
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        assert!(model.predict(&inputs, &labels, None, 20).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_inputs() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["the dog is in paris.", "this example is about food."];
+        let labels = ["travel", "pets", "food"];
+        let owned_inputs = inputs
+            .iter()
+            .map(|input| input.to_string())
+            .collect::<Vec<String>>();
+        let owned_labels = labels
+            .iter()
+            .map(|label| label.to_string())
+            .collect::<Vec<String>>();
+
+        let expected = model.predict(&inputs, &labels, None, 32)?;
+        let from_slices = model.predict(owned_inputs.as_slice(), &owned_labels, None, 32)?;
+        let from_vecs = model.predict(owned_inputs.clone(), owned_labels.clone(), None, 32)?;
+        for output in [from_slices, from_vecs].iter() {
+            assert_eq!(output.len(), expected.len());
+            for (label, expected_label) in output.iter().zip(expected.iter()) {
+                assert_eq!(label.text, expected_label.text);
+                assert_eq!(label.score, expected_label.score);
+            }
+        }
+
+        let expected = model.predict_multilabel(&inputs, &labels, None, 32)?;
+        let output = model.predict_multilabel(&owned_inputs, owned_labels, None, 32)?;
+        for (labels, expected_labels) in output.iter().zip(expected.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert_eq!(label.score, expected_label.score);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_hypotheses() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let labels = ["travel", "pets"];
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        assert_eq!(
+            model.debug_hypotheses(&labels, None),
+            vec![
+                "This example is about travel.",
+                "This example is about pets."
+            ]
+        );
+        assert_eq!(
+            model.debug_hypotheses(&labels, Some(Box::new(|label: &str| format!("{}!", label)))),
+            vec!["travel!", "pets!"]
+        );
+
+        let mut config = tiny_distilbert_config(directory.path())?;
+        config.hypothesis_template = Some("The dog is about {}".to_string());
+        let model = ZeroShotClassificationModel::new(config)?;
+        let hypotheses = model.debug_hypotheses(&labels, None);
+        assert_eq!(
+            hypotheses,
+            vec!["The dog is about travel", "The dog is about pets"]
+        );
+        //    Hypotheses reported by the predictions
+        let predictions = model.predict_full(&["the dog is in paris."], &labels, None, 32)?;
+        for (prediction, hypothesis) in predictions[0].iter().zip(hypotheses.iter()) {
+            assert_eq!(&prediction.hypothesis, hypothesis);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_device() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut model =
+            ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = ["the dog is in paris."];
+        let labels = ["travel", "pets", "food"];
+        assert_eq!(model.get_device(), Device::Cpu);
+        let expected = model.predict_multilabel(&inputs, &labels, None, 32)?;
+
+        //    Round trip through the GPU when one is available
+        for &device in [Device::cuda_if_available(), Device::Cpu].iter() {
+            model.set_device(device)?;
+            assert_eq!(model.get_device(), device);
+            let output = model.predict_multilabel(&inputs, &labels, None, 32)?;
+            for (label, expected_label) in output[0].iter().zip(expected[0].iter()) {
+                assert!((label.score - expected_label.score).abs() < 1e-5);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut config = tiny_distilbert_config(directory.path())?;
+        let report = ZeroShotClassificationModel::validate_config(&config)?;
+        assert!(report.is_valid(), "{:?}", report.errors);
+        assert_eq!(report.num_labels, Some(3));
+
+        //    Entailment class beyond the classes of the model, and misconfigured template
+        config.entailment_id = Some(3);
+        config.hypothesis_template = Some("This example is about".to_string());
+        let report = ZeroShotClassificationModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].contains("placeholder"));
+        assert!(report.errors[1].contains("entailment"));
+
+        //    Missing configuration file
+        config.entailment_id = None;
+        config.hypothesis_template = None;
+        config.config_resource = Resource::Local(crate::resources::LocalResource {
+            local_path: directory.path().join("missing_config.json"),
+        });
+        let report = ZeroShotClassificationModel::validate_config(&config)?;
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.num_labels, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncation_report() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let model = ZeroShotClassificationModel::new(tiny_distilbert_config(directory.path())?)?;
+        let inputs = [
+            "this dog",
+            "the dog is in paris. the dog is in paris. the dog is in paris.",
+        ];
+        let labels = ["travel", "pets"];
+
+        let (output, report) = model.predict_with_report(&inputs, &labels, None, 16)?;
+        let expected_output = model.predict(&inputs, &labels, None, 16)?;
+        for (label, expected_label) in output.iter().zip(expected_output.iter()) {
+            assert_eq!(label.id, expected_label.id);
+            assert_eq!(label.score, expected_label.score);
+        }
+
+        //    The long input (18 tokens) is paired with 5 tokens hypotheses and 3 special tokens
+        let truncation = report
+            .iter()
+            .map(|info| (info.sentence, info.label_id, info.num_truncated_tokens))
+            .collect::<Vec<(usize, i64, usize)>>();
+        assert_eq!(
+            truncation,
+            vec![(0, 0, 0), (0, 1, 0), (1, 0, 10), (1, 1, 10)]
+        );
+        assert_eq!(
+            report
+                .iter()
+                .map(|info| info.truncated())
+                .collect::<Vec<bool>>(),
+            vec![false, false, true, true]
+        );
+
+        let no_labels: [&str; 0] = [];
+        assert!(matches!(
+            model.predict_with_report(&inputs, &no_labels, None, 16),
+            Err(RustBertError::EmptyLabelsError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_confident_labels() {
+        let scores = Tensor::of_slice(&[
+            0.5f64, 0.25, 0.25, //
+            0.125, 0.5, 0.375, //
+            0.3125, 0.375, 0.3125,
+        ])
+        .view((3, 3));
+
+        assert_eq!(
+            select_confident_labels(&scores, None, None),
+            vec![Some((0, 0.5)), Some((1, 0.5)), Some((1, 0.375))]
+        );
+        //    Thresholds are inclusive
+        assert_eq!(
+            select_confident_labels(&scores, Some(0.5), None),
+            vec![Some((0, 0.5)), Some((1, 0.5)), None]
+        );
+        assert_eq!(
+            select_confident_labels(&scores, None, Some(0.25)),
+            vec![Some((0, 0.5)), None, None]
+        );
+        assert_eq!(
+            select_confident_labels(&scores, None, Some(0.125)),
+            vec![Some((0, 0.5)), Some((1, 0.5)), None]
+        );
+        assert_eq!(
+            select_confident_labels(&scores, Some(0.5), Some(0.125)),
+            vec![Some((0, 0.5)), Some((1, 0.5)), None]
+        );
+        assert_eq!(
+            select_confident_labels(&scores, Some(0.625), Some(0.0)),
+            vec![None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_select_confident_labels_single_label() {
+        let scores = Tensor::of_slice(&[1f32, 1.0]).view((2, 1));
+        assert_eq!(
+            select_confident_labels(&scores, Some(1.0), Some(1.0)),
+            vec![Some((0, 1.0)), Some((0, 1.0))]
+        );
+    }
+
+    #[test]
+    fn test_forward_label_chunks() -> anyhow::Result<()> {
+        //    Tiny NLI-like model scoring synthetic input and label features
+        let vs = nn::VarStore::new(Device::Cpu);
+        let model = nn::linear(&vs.root(), 16, 3, Default::default());
+        let (num_inputs, num_labels, chunk_size) = (3usize, 1000usize, 64usize);
+        let input_features = Tensor::randn(&[num_inputs as i64, 8], (Float, Device::Cpu));
+        let label_features = Tensor::randn(&[num_labels as i64, 8], (Float, Device::Cpu));
+        let max_pairs = std::cell::Cell::new(0usize);
+        let forward = |input_range: Range<usize>, label_range: Range<usize>| {
+            let (batch_inputs, batch_labels) = (input_range.len(), label_range.len());
+            max_pairs.set(max_pairs.get().max(batch_inputs * batch_labels));
+            let inputs = input_features
+                .narrow(0, input_range.start as i64, batch_inputs as i64)
+                .unsqueeze(1)
+                .expand(&[batch_inputs as i64, batch_labels as i64, 8], false);
+            let labels = label_features
+                .narrow(0, label_range.start as i64, batch_labels as i64)
+                .unsqueeze(0)
+                .expand(&[batch_inputs as i64, batch_labels as i64, 8], false);
+            Ok(no_grad(|| Tensor::cat(&[inputs, labels], -1).apply(&model)))
+        };
+        let entailment = |logits: Tensor| logits.select(-1, -1);
+        let pair_scores =
+            |logits: Tensor| logits.slice(-1, 0, 3, 2).softmax(-1, Float).select(-1, -1);
+
+        let expected = forward_label_chunks(num_inputs, num_labels, None, &forward, entailment)?;
+        assert_eq!(max_pairs.get(), num_inputs * num_labels);
+        max_pairs.set(0);
+        let chunked = forward_label_chunks(
+            num_inputs,
+            num_labels,
+            Some(chunk_size),
+            &forward,
+            entailment,
+        )?;
+        assert_eq!(max_pairs.get(), chunk_size);
+        assert_eq!(chunked.size(), vec![num_inputs as i64, num_labels as i64]);
+        let difference = (expected.softmax(1, Float) - chunked.softmax(1, Float))
+            .abs()
+            .max()
+            .double_value(&[]);
+        assert!(difference < 1e-6);
+        assert_eq!(
+            expected
+                .argmax(-1, false)
+                .iter::<i64>()
+                .unwrap()
+                .collect::<Vec<i64>>(),
+            chunked
+                .argmax(-1, false)
+                .iter::<i64>()
+                .unwrap()
+                .collect::<Vec<i64>>()
+        );
+
+        let expected = forward_label_chunks(num_inputs, num_labels, None, &forward, pair_scores)?;
+        let chunked = forward_label_chunks(
+            num_inputs,
+            num_labels,
+            Some(chunk_size),
+            &forward,
+            pair_scores,
+        )?;
+        let difference = (expected - chunked).abs().max().double_value(&[]);
+        assert!(difference < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_threshold_labels() {
+        let label = |text: &str, score: f64, id: i64, sentence: usize| Label {
+            text: text.to_string(),
+            score,
+            id,
+            sentence,
+        };
+        let output_labels = vec![
+            vec![
+                label("politics", 0.6, 0, 0),
+                label("economics", 0.9, 1, 0),
+                label("sports", 0.1, 2, 0),
+            ],
+            vec![
+                label("politics", 0.2, 0, 1),
+                label("economics", 0.3, 1, 1),
+                label("sports", 0.1, 2, 1),
+            ],
+            vec![
+                label("politics", 0.5, 0, 2),
+                label("economics", 0.4, 1, 2),
+                label("sports", 0.7, 2, 2),
+            ],
+        ];
+
+        let thresholded = threshold_labels(output_labels, 0.5);
+        //    Inputs without label passing the threshold are kept, with no label
+        assert_eq!(thresholded.len(), 3);
+        let texts = thresholded
+            .iter()
+            .map(|labels| labels.iter().map(|label| label.text.as_str()).collect())
+            .collect::<Vec<Vec<&str>>>();
+        assert_eq!(
+            texts,
+            vec![
+                vec!["economics", "politics"],
+                vec![],
+                vec!["sports", "politics"]
+            ]
+        );
+        assert_eq!(thresholded[2][1].id, 0);
+        assert_eq!(thresholded[2][1].sentence, 2);
+    }
+
+    #[test]
+    fn test_validate_inputs() {
+        assert!(validate_inputs(&["The dog is in Paris."], &["travel", "pets"]).is_ok());
+        assert!(matches!(
+            validate_inputs(&[], &["travel", "pets"]),
+            Err(RustBertError::EmptyInputError)
+        ));
+        assert!(matches!(
+            validate_inputs(&["The dog is in Paris."], &[]),
+            Err(RustBertError::EmptyLabelsError)
+        ));
+        assert!(matches!(
+            validate_inputs(&[], &[]),
+            Err(RustBertError::EmptyInputError)
+        ));
+    }
+
+    #[test]
+    fn test_build_full_predictions() {
+        let logits = Tensor::of_slice(&[0.0f32, 1.0, 2.0, 3.0, 0.0, 0.0]).view((1, 2, 3));
+        let labels = ["sports", "politics"];
+        let label_sentences = build_label_sentences(&labels, None, None);
+        let predictions = build_full_predictions(
+            &logits.softmax(-1, Float),
+            &labels,
+            &label_sentences,
+            NliLabelIds {
+                entailment: -1,
+                contradiction: 0,
+            },
+        );
+
+        assert_eq!(predictions.len(), 1);
+        assert_eq!(predictions[0].len(), 2);
+        let prediction = &predictions[0][1];
+        assert_eq!(prediction.label, "politics");
+        assert_eq!(prediction.label_id, 1);
+        assert_eq!(prediction.sentence, 0);
+        assert_eq!(prediction.hypothesis, "This example is about politics.");
+        assert!((prediction.contradiction_score - 0.9094).abs() < 1e-4);
+        assert!((prediction.neutral_score - 0.0453).abs() < 1e-4);
+        assert!((prediction.entailment_score - 0.0453).abs() < 1e-4);
+        for prediction in predictions[0].iter() {
+            let total = prediction.contradiction_score
+                + prediction.neutral_score
+                + prediction.entailment_score;
+            assert!((total - 1.0).abs() < 1e-6);
+        }
+        assert!(predictions[0][0].entailment_score > predictions[0][0].neutral_score);
+    }
+
+    #[test]
+    fn test_compare_pairs() {
+        let nli_labels = NliLabelIds {
+            entailment: -1,
+            contradiction: 0,
+        };
+        //    Contradiction, neutral and entailment logits of the two texts of 3 pairs
+        let logits = Tensor::of_slice(&[
+            0.0f32, 5.0, 2.0, //
+            0.0, -5.0, 1.0, //
+            1.0, 0.0, 4.0, //
+            0.0, 0.0, 6.0, //
+            0.5, 0.0, 1.5, //
+            -1.0, 3.0, 0.0, //
+        ])
+        .view((6, 3));
+        let premise_lengths = [2, 1, 2, 6, 4, 4];
+
+        //    Scores (entailment - contradiction): 2, 1 | 3, 6 | 1, 1
+        let comparisons = compare_pairs(
+            &logits,
+            &premise_lengths,
+            LengthNormalization::None,
+            nli_labels,
+        );
+        assert_eq!(
+            comparisons,
+            vec![
+                Comparison {
+                    winner: 0,
+                    margin: 1.0
+                },
+                Comparison {
+                    winner: 1,
+                    margin: 3.0
+                },
+                //    Equal scores: the first text wins with a null margin
+                Comparison {
+                    winner: 0,
+                    margin: 0.0
+                },
+            ]
+        );
+
+        //    Normalized scores: 1, 1 | 1.5, 1 | 0.25, 0.25
+        let comparisons = compare_pairs(
+            &logits,
+            &premise_lengths,
+            LengthNormalization::PerToken,
+            nli_labels,
+        );
+        assert_eq!(
+            comparisons,
+            vec![
+                Comparison {
+                    winner: 0,
+                    margin: 0.0
+                },
+                Comparison {
+                    winner: 0,
+                    margin: 0.5
+                },
+                Comparison {
+                    winner: 0,
+                    margin: 0.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hypothesis_template() {
+        let labels = ["sports", "politics"];
+        assert_eq!(
+            build_label_sentences(&labels, None, Some("This review is about {}.")),
+            vec![
+                "This review is about sports.",
+                "This review is about politics."
+            ]
+        );
+        //    Closures passed at prediction time take precedence over the configured template
+        let template = |label: &str| format!("{} is the topic.", label);
+        assert_eq!(
+            build_label_sentences(&labels, Some(&template), Some("This review is about {}.")),
+            vec!["sports is the topic.", "politics is the topic."]
+        );
+        assert_eq!(
+            build_label_sentences(&labels, None, None),
+            vec![
+                "This example is about sports.",
+                "This example is about politics."
+            ]
+        );
+
+        assert!(validate_hypothesis_template("This review is about {}.").is_ok());
+        for template in ["This review is about sports.", "{} or {}?"].iter() {
+            assert!(matches!(
+                validate_hypothesis_template(template),
+                Err(RustBertError::InvalidConfigurationError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sweep_threshold_separable() {
+        let scores = [0.9, 0.8, 0.3, 0.2, 0.1];
+        let gold = [true, true, false, false, false];
+
+        let metrics = sweep_threshold(&scores, &gold).unwrap();
+
+        assert_eq!(metrics.threshold, 0.8);
+        assert_eq!(metrics.precision, 1.0);
+        assert_eq!(metrics.recall, 1.0);
+        assert_eq!(metrics.f1, 1.0);
+        assert_eq!(metrics.support, 2);
+    }
+
+    #[test]
+    fn test_sweep_threshold_overlapping() {
+        let scores = [0.9, 0.7, 0.6, 0.4, 0.1];
+        let gold = [true, false, true, true, false];
+
+        let metrics = sweep_threshold(&scores, &gold).unwrap();
+
+        // Threshold 0.4: 3 true positives, 1 false positive
+        assert_eq!(metrics.threshold, 0.4);
+        assert!((metrics.precision - 0.75).abs() < 1e-9);
+        assert!((metrics.recall - 1.0).abs() < 1e-9);
+        assert!((metrics.f1 - 6.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sweep_threshold_nan() {
+        let scores = [0.9, f64::NAN, 0.2];
+        let gold = [true, false, false];
+
+        assert_eq!(sweep_threshold(&scores, &gold).unwrap_err(), 1);
+        assert_eq!(sweep_threshold(&scores, &[false; 3]).unwrap_err(), 1);
+    }
+
+    #[test]
+    fn test_sweep_threshold_no_positive() {
+        let scores = [0.9, 0.2];
+        let gold = [false, false];
+
+        let metrics = sweep_threshold(&scores, &gold).unwrap();
+
+        assert_eq!(metrics.threshold, 1.0);
+        assert_eq!(metrics.f1, 0.0);
+        assert_eq!(metrics.support, 0);
+    }
+
+    #[test]
+    fn test_nli_label_ids() -> anyhow::Result<()> {
+        let label2id = |labels: &[(&str, i64)]| {
+            labels
+                .iter()
+                .map(|&(label, id)| (label.to_string(), id))
+                .collect::<HashMap<String, i64>>()
+        };
+        let mnli = label2id(&[("contradiction", 0), ("neutral", 1), ("entailment", 2)]);
+        let reversed = label2id(&[("ENTAILMENT", 0), ("NEUTRAL", 1), ("CONTRADICTION", 2)]);
+        let two_classes = label2id(&[("entailment", 0), ("not_entailment", 1)]);
+
+        let resolved =
+            |entailment_id, contradiction_id, label2id: Option<&HashMap<String, i64>>| {
+                NliLabelIds::resolve(entailment_id, contradiction_id, label2id, 3)
+                    .map(|nli_labels| nli_labels.positions(3))
+            };
+        assert_eq!(resolved(None, None, None)?, (2, 0));
+        assert_eq!(resolved(None, None, Some(&mnli))?, (2, 0));
+        assert_eq!(resolved(None, None, Some(&reversed))?, (0, 2));
+        assert_eq!(resolved(Some(1), Some(-1), Some(&mnli))?, (1, 2));
+        let two_class_labels = NliLabelIds::resolve(None, None, Some(&two_classes), 2)?;
+        assert_eq!(two_class_labels.positions(2), (0, 1));
+        assert!(resolved(Some(3), None, Some(&mnli)).is_err());
+        assert!(resolved(Some(-1), Some(2), Some(&mnli)).is_err());
+        assert!(resolved(Some(1), Some(1), None).is_err());
+        //    Single logit models (e.g. regression heads) cannot provide entailment probabilities,
+        //    with or without a label2id mapping
+        let single_class = label2id(&[("LABEL_0", 0)]);
+        for single_class_label2id in [Some(&single_class), None].iter() {
+            assert!(matches!(
+                NliLabelIds::resolve(None, None, *single_class_label2id, 1),
+                Err(RustBertError::InvalidConfigurationError(_))
+            ));
+        }
+        let unnamed_two_classes = label2id(&[("LABEL_0", 0), ("LABEL_1", 1)]);
+        assert_eq!(
+            NliLabelIds::resolve(None, None, Some(&unnamed_two_classes), 2)?.positions(2),
+            (1, 0)
+        );
+        assert_eq!(
+            NliLabelIds::resolve(None, None, None, 2)?.positions(2),
+            (1, 0)
+        );
+
+        //    Reversed class order: the entailment logits come first
+        let logits = Tensor::of_slice(&[3.0f32, 0.0, 1.0]).view((1, 1, 3));
+        let reversed_labels = NliLabelIds::resolve(None, None, Some(&reversed), 3)?;
+        let entailment = reversed_labels.entailment_logits(&logits);
+        assert_eq!(entailment.double_value(&[0, 0]), 3.0);
+        let labels = ["sports"];
+        let predictions = build_full_predictions(
+            &logits.softmax(-1, Float),
+            &labels,
+            &build_label_sentences(&labels, None, None),
+            reversed_labels,
+        );
+        assert!((predictions[0][0].entailment_score - 0.8438).abs() < 1e-4);
+        assert!((predictions[0][0].contradiction_score - 0.1142).abs() < 1e-4);
+
+        //    Two-class models: no neutral score, the pair probabilities use both logits
+        let logits = Tensor::of_slice(&[2.0f32, 0.0]).view((1, 1, 2));
+        let pair_scores = two_class_labels
+            .contradiction_entailment_logits(&logits)
+            .softmax(-1, Float)
+            .select(-1, 1);
+        assert!((pair_scores.double_value(&[0, 0]) - 0.8808).abs() < 1e-4);
+        let predictions = build_full_predictions(
+            &logits.softmax(-1, Float),
+            &labels,
+            &build_label_sentences(&labels, None, None),
+            two_class_labels,
+        );
+        assert_eq!(predictions[0][0].neutral_score, 0.0);
+        assert!((predictions[0][0].entailment_score - 0.8808).abs() < 1e-4);
+        Ok(())
     }
 }
@@ -18,6 +18,7 @@ use rust_tokenizers::vocab::{ProphetNetVocab, Vocab};
 use serde::{Deserialize, Serialize};
 use tch::{nn, Kind, Tensor};
 
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{Gpt2ConfigResources, Gpt2ModelResources, Gpt2VocabResources};
 use crate::pipelines::common::{ModelType, TokenizerOption};
@@ -30,7 +31,7 @@ use crate::pipelines::generation_utils::{
 use crate::prophetnet::attention::LayerState;
 use crate::prophetnet::decoder::ProphetNetDecoder;
 use crate::prophetnet::encoder::ProphetNetEncoder;
-use crate::{Activation, Config, RustBertError};
+use crate::{Activation, Config, ProblemType, RustBertError};
 
 /// # ProphetNet Pretrained model weight files
 pub struct ProphetNetModelResources;
@@ -106,6 +107,7 @@ pub struct ProphetNetConfig {
     pub ngram: i64,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
     pub num_buckets: i64,
     pub num_decoder_attention_heads: i64,
     pub num_decoder_layers: i64,
@@ -865,6 +867,7 @@ pub struct ProphetNetConditionalGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    load_report: LoadReport,
 }
 
 impl ProphetNetConditionalGenerator {
@@ -957,7 +960,7 @@ impl ProphetNetConditionalGenerator {
         )?;
         let config = ProphetNetConfig::from_file(config_path);
         let model = ProphetNetForConditionalGeneration::new(&var_store.root(), &config)?;
-        var_store.load(weights_path)?;
+        let load_report = load_with_report(&mut var_store, weights_path, true)?;
 
         let bos_token_id = Some(config.bos_token_id);
         let eos_token_ids = Some(vec![config.eos_token_id]);
@@ -971,6 +974,7 @@ impl ProphetNetConditionalGenerator {
             model,
             tokenizer,
             var_store,
+            load_report,
             generate_config,
             bos_token_id,
             eos_token_ids,
@@ -981,6 +985,12 @@ impl ProphetNetConditionalGenerator {
             max_position_embeddings,
         })
     }
+
+    /// Returns the report of the weights loaded when building the generator, listing the tying
+    /// status of the language modeling head, the repaired tensor shapes and the unused checkpoint tensors
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
 }
 
 impl
@@ -35,7 +35,7 @@ use crate::reformer::attention::{AttentionType, LayerState};
 use crate::reformer::attention_utils::{get_least_common_mult_chunk_len, get_min_chunk_len};
 use crate::reformer::embeddings::ReformerEmbeddings;
 use crate::reformer::encoder::{ReformerEncoder, ReformerModelOutput};
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 
 /// # Reformer Pretrained model weight files
 pub struct ReformerModelResources;
@@ -111,6 +111,7 @@ pub struct ReformerConfig {
     pub use_cache: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
 }
@@ -14,6 +14,7 @@
 
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
+use crate::common::load_report::{load_with_report, LoadReport};
 use crate::common::summary::{SequenceSummary, SummaryConfig, SummaryType};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
@@ -24,7 +25,7 @@ use crate::pipelines::generation_utils::{
 };
 use crate::xlnet::attention::LayerState;
 use crate::xlnet::encoder::XLNetLayer;
-use crate::{Config, RustBertError};
+use crate::{Config, ProblemType, RustBertError};
 use rust_tokenizers::tokenizer::XLNetTokenizer;
 use rust_tokenizers::vocab::XLNetVocab;
 use serde::{Deserialize, Serialize};
@@ -111,6 +112,7 @@ pub struct XLNetConfig {
     pub pad_token_id: i64,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    pub problem_type: Option<ProblemType>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
     pub chunk_size_feed_forward: Option<i64>,
@@ -1537,6 +1539,7 @@ pub struct XLNetGenerator {
     vocab_size: i64,
     decoder_start_id: Option<i64>,
     max_position_embeddings: i64,
+    load_report: LoadReport,
 }
 
 impl XLNetGenerator {
@@ -1584,7 +1587,7 @@ impl XLNetGenerator {
 
         let config = XLNetConfig::from_file(config_path);
         let model = XLNetLMHeadModel::new(&var_store.root(), &config);
-        var_store.load(weights_path)?;
+        let load_report = load_with_report(&mut var_store, weights_path, true)?;
 
         let bos_token_id = Some(config.bos_token_id);
         let eos_token_ids = Some(vec![config.eos_token_id]);
@@ -1599,6 +1602,7 @@ impl XLNetGenerator {
             model,
             tokenizer,
             var_store,
+            load_report,
             generate_config,
             bos_token_id,
             eos_token_ids,
@@ -1609,6 +1613,12 @@ impl XLNetGenerator {
             max_position_embeddings,
         })
     }
+
+    /// Returns the report of the weights loaded when building the generator, listing the tying
+    /// status of the language modeling head, the repaired tensor shapes and the unused checkpoint tensors
+    pub fn last_load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
 }
 
 impl PrivateLanguageGenerator<XLNetLMHeadModel, XLNetVocab, XLNetTokenizer> for XLNetGenerator {
@@ -1,6 +1,12 @@
+mod common;
+
+use common::fixtures::{fix_weights, tiny_bart_config, SEED};
 use rust_bert::bart::{
-    BartConfig, BartConfigResources, BartMergesResources, BartModel, BartModelResources,
-    BartVocabResources,
+    BartConfig, BartConfigResources, BartForSequenceClassification, BartGenerator,
+    BartMergesResources, BartModel, BartModelResources, BartVocabResources,
+};
+use rust_bert::pipelines::generation_utils::{
+    DecoderStep, DecodingStrategy, GenerateConfig, LanguageGenerator,
 };
 use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
 use rust_bert::pipelines::zero_shot_classification::{
@@ -9,7 +15,8 @@ use rust_bert::pipelines::zero_shot_classification::{
 use rust_bert::resources::{RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{RobertaTokenizer, Tokenizer, TruncationStrategy};
-use tch::{nn, Device, Tensor};
+use std::sync::{Arc, Mutex};
+use tch::{nn, no_grad, Device, Tensor};
 
 #[test]
 fn bart_lm_model() -> anyhow::Result<()> {
@@ -218,7 +225,7 @@ fn bart_zero_shot_classification() -> anyhow::Result<()> {
             format!("This example is about {}.", label)
         })),
         128,
-    );
+    )?;
 
     assert_eq!(output.len(), 2);
 
@@ -230,6 +237,43 @@ fn bart_zero_shot_classification() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_predict_each() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let input_sentence = "Who are you voting for in 2020?";
+    let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    let output = sequence_classification_model.predict_each(
+        &["", input_sentence, "   ", input_sequence_2],
+        candidate_labels,
+        None,
+        128,
+    )?;
+
+    assert_eq!(output.len(), 4);
+    assert!(output[0].is_err());
+    assert!(output[2].is_err());
+
+    // Valid inputs are classified as if they had been submitted alone
+    let label = output[1].as_ref().unwrap();
+    assert_eq!(label.text, "politics");
+    assert_eq!(label.sentence, 1);
+    assert!((label.score - 0.9630).abs() < 1e-4);
+    let label = output[3].as_ref().unwrap();
+    assert_eq!(label.text, "economy");
+    assert_eq!(label.sentence, 3);
+    assert!((label.score - 0.6416).abs() < 1e-4);
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn bart_zero_shot_classification_multilabel() -> anyhow::Result<()> {
@@ -251,7 +295,7 @@ fn bart_zero_shot_classification_multilabel() -> anyhow::Result<()> {
             format!("This example is about {}.", label)
         })),
         128,
-    );
+    )?;
 
     assert_eq!(output.len(), 2);
     assert_eq!(output[0].len(), candidate_labels.len());
@@ -276,3 +320,101 @@ fn bart_zero_shot_classification_multilabel() -> anyhow::Result<()> {
     assert!((output[1][3].score - 0.0004).abs() < 1e-4);
     Ok(())
 }
+
+#[test]
+fn bart_sequence_classification_padded_eos_pooling() -> anyhow::Result<()> {
+    //    Tiny model, the pooled representation should not depend on the padding
+    let config = tiny_bart_config(Some(&["contradiction", "neutral", "entailment"]));
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = BartForSequenceClassification::new(&vs.root(), &config);
+    fix_weights(&vs, SEED);
+
+    let long_input = [
+        0, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 2, 2, 20, 21, 2,
+    ];
+    let short_input = [0, 5, 6, 2, 2, 20, 2];
+    let mut padded_short_input = short_input.to_vec();
+    padded_short_input.extend(vec![1; long_input.len() - short_input.len()]);
+
+    let input_ids = Tensor::stack(
+        &[
+            Tensor::of_slice(&long_input),
+            Tensor::of_slice(&padded_short_input),
+        ],
+        0,
+    );
+    let mask = input_ids.ne(1);
+    let batched_logits = no_grad(|| {
+        model
+            .forward_t(&input_ids, Some(&mask), None, None, None, false)
+            .decoder_output
+    });
+
+    let short_input_ids = Tensor::of_slice(&short_input).unsqueeze(0);
+    let short_mask = short_input_ids.ne(1);
+    let short_logits = no_grad(|| {
+        model
+            .forward_t(&short_input_ids, Some(&short_mask), None, None, None, false)
+            .decoder_output
+    });
+
+    assert_eq!(batched_logits.size(), vec![2, 3]);
+    assert!(batched_logits
+        .get(1)
+        .allclose(&short_logits.get(0), 1e-5, 1e-6, false));
+
+    Ok(())
+}
+
+#[test]
+fn bart_greedy_generation_uses_cache() -> anyhow::Result<()> {
+    let decoder_steps = Arc::new(Mutex::new(Vec::new()));
+    let hook_decoder_steps = decoder_steps.clone();
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        )),
+        min_length: 8,
+        max_length: 12,
+        decoding_strategy: Some(DecodingStrategy::Greedy),
+        decoder_step_hook: Some(Box::new(move |decoder_step: &DecoderStep| {
+            hook_decoder_steps.lock().unwrap().push(*decoder_step)
+        })),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    let output = model.generate(
+        Some(&["The quick brown fox jumps over the lazy dog."]),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+    );
+    assert_eq!(output.len(), 1);
+
+    //    The decoder start token is fed at the first step, each following step only feeds the last
+    //    generated token and extends the cache by one position
+    let decoder_steps = decoder_steps.lock().unwrap();
+    assert!(decoder_steps.len() >= 8);
+    for (step_index, decoder_step) in decoder_steps.iter().enumerate() {
+        assert_eq!(decoder_step.step, step_index as i64);
+        assert_eq!(decoder_step.input_length, 1);
+        assert_eq!(decoder_step.cached_length, step_index as i64);
+    }
+
+    Ok(())
+}
@@ -364,7 +364,7 @@ fn bert_pre_trained_ner() -> anyhow::Result<()> {
     ];
 
     //    Run model
-    let output = ner_model.predict(&input);
+    let output = ner_model.predict(&input)?;
 
     assert_eq!(output.len(), 2);
     assert_eq!(output[0].len(), 2);
@@ -389,6 +389,27 @@ fn bert_pre_trained_ner() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_pre_trained_ner_single_input() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Single inputs take a dedicated path and should match the batched path
+    let input = "My name is Amy. I live in Paris.";
+    let output = ner_model.predict(&[input])?;
+    let batched_output = ner_model.predict(&[input, input])?;
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].len(), batched_output[0].len());
+    for (entity, batched_entity) in output[0].iter().zip(batched_output[0].iter()) {
+        assert_eq!(entity.word, batched_entity.word);
+        assert_eq!(entity.label, batched_entity.label);
+        assert!((entity.score - batched_entity.score).abs() < 1e-6);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn bert_question_answering() -> anyhow::Result<()> {
     //    Set-up question answering model
@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+
+#[path = "../../src/fixtures.rs"]
+pub mod fixtures;
+
+/// Writes a tiny BERT model (weights, configuration and vocabulary) to `directory`. The single
+/// weights file serves the sequence classification, token classification and question answering
+/// pipelines.
+#[cfg(feature = "bert")]
+pub fn write_tiny_bert(directory: &std::path::Path) -> anyhow::Result<()> {
+    let vocab = [
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "the", "dog", "is", "in", "paris", "where",
+        "?", ".", "pa", "##ris", "##s", "are",
+    ];
+    fixtures::tiny_bert_resources(directory, &vocab, &["O", "B-LOC", "I-LOC"])?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::fixtures::{fix_weights, tiny_deberta_config, SEED};
+use rust_bert::deberta::{
+    DebertaConfigResources, DebertaForSequenceClassification, DebertaMergesResources,
+    DebertaModelResources, DebertaVocabResources,
+};
+use rust_bert::pipelines::common::{ConfigOption, ModelType};
+use rust_bert::pipelines::sequence_classification::SequenceClassificationOption;
+use rust_bert::pipelines::zero_shot_classification::{
+    ZeroShotClassificationConfig, ZeroShotClassificationModel,
+};
+use rust_bert::resources::{RemoteResource, Resource};
+use tch::{nn, no_grad, Device, Tensor};
+
+#[test]
+fn deberta_sequence_classification_padding() -> anyhow::Result<()> {
+    //    Tiny model, the logits should not depend on the padding
+    let config = tiny_deberta_config(&["CONTRADICTION", "NEUTRAL", "ENTAILMENT"]);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = DebertaForSequenceClassification::new(&vs.root(), &config);
+    fix_weights(&vs, SEED);
+
+    let long_input = [1, 5, 6, 7, 8, 9, 10, 11, 12, 2, 20, 21, 2];
+    let short_input = [1, 5, 6, 2, 20, 2];
+    let mut padded_short_input = short_input.to_vec();
+    padded_short_input.extend(vec![0; long_input.len() - short_input.len()]);
+
+    let input_ids = Tensor::stack(
+        &[
+            Tensor::of_slice(&long_input),
+            Tensor::of_slice(&padded_short_input),
+        ],
+        0,
+    );
+    let mask = input_ids.ne(0);
+    let batched_logits = no_grad(|| {
+        model
+            .forward_t(Some(&input_ids), Some(&mask), None, None, None, false)
+            .map(|output| output.logits)
+    })?;
+
+    let short_input_ids = Tensor::of_slice(&short_input).unsqueeze(0);
+    let short_logits = no_grad(|| {
+        model
+            .forward_t(Some(&short_input_ids), None, None, None, None, false)
+            .map(|output| output.logits)
+    })?;
+
+    assert_eq!(batched_logits.size(), vec![2, 3]);
+    assert!(batched_logits
+        .get(1)
+        .allclose(&short_logits.get(0), 1e-5, 1e-6, false));
+
+    Ok(())
+}
+
+#[test]
+fn deberta_sequence_classification_option() -> anyhow::Result<()> {
+    //    Tiny model loaded through the sequence classification pipeline abstraction
+    let config = tiny_deberta_config(&["NEGATIVE", "POSITIVE"]);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = SequenceClassificationOption::new(
+        ModelType::Deberta,
+        &vs.root(),
+        &ConfigOption::Deberta(config),
+    )?;
+    fix_weights(&vs, SEED);
+    assert_eq!(model.model_type(), ModelType::Deberta);
+
+    let input_ids = Tensor::of_slice(&[1i64, 5, 6, 7, 2, 1, 8, 9, 2, 0]).view((2, 5));
+    let mask = input_ids.ne(0);
+    let logits = no_grad(|| model.forward_t(Some(input_ids), Some(mask), None, None, None, false));
+
+    assert_eq!(logits.size(), vec![2, 2]);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn deberta_zero_shot_classification() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        model_type: ModelType::Deberta,
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            DebertaModelResources::DEBERTA_BASE_MNLI,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            DebertaConfigResources::DEBERTA_BASE_MNLI,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            DebertaVocabResources::DEBERTA_BASE_MNLI,
+        )),
+        merges_resource: Some(Resource::Remote(RemoteResource::from_pretrained(
+            DebertaMergesResources::DEBERTA_BASE_MNLI,
+        ))),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let input_sentence = "Who are you voting for in 2020?";
+    let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    let output = sequence_classification_model.predict(
+        &[input_sentence, input_sequence_2],
+        candidate_labels,
+        None,
+        128,
+    )?;
+
+    //    The label with the highest entailment score is returned for each input
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].text, "politics");
+    assert_eq!(output[1].text, "economy");
+
+    Ok(())
+}
@@ -1,15 +1,23 @@
+mod common;
+
+use common::fixtures::{tiny_distilbert_resources, write_weights};
 use rust_bert::distilbert::{
     DistilBertConfig, DistilBertConfigResources, DistilBertForQuestionAnswering,
-    DistilBertForTokenClassification, DistilBertModelMaskedLM, DistilBertModelResources,
-    DistilBertVocabResources,
+    DistilBertForTokenClassification, DistilBertModelClassifier, DistilBertModelMaskedLM,
+    DistilBertModelResources, DistilBertVocabResources,
 };
+use rust_bert::pipelines::chunked_inference::ChunkScheduling;
 use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
 use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
 use rust_bert::resources::{RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
 use std::collections::HashMap;
+use std::path::Path;
 use tch::{nn, no_grad, Device, Tensor};
 
 extern crate anyhow;
@@ -26,7 +34,7 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
         "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
     ];
 
-    let output = sentiment_classifier.predict(&input);
+    let output = sentiment_classifier.predict(&input)?;
 
     assert_eq!(output.len(), 3usize);
     assert_eq!(output[0].polarity, SentimentPolarity::Positive);
@@ -39,6 +47,233 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn distilbert_sequence_classification_predict_each() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+
+    //    Get labels, invalid inputs carry their own error
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+        " ",
+    ];
+
+    let output = sequence_classification_model.predict_each(&input)?;
+
+    assert_eq!(output.len(), 4usize);
+    let label = output[0].as_ref().unwrap();
+    assert_eq!(label.text, "POSITIVE");
+    assert_eq!(label.sentence, 0);
+    assert!((label.score - 0.9981).abs() < 1e-4);
+    assert!(output[1].is_err());
+    let label = output[2].as_ref().unwrap();
+    assert_eq!(label.text, "NEGATIVE");
+    assert_eq!(label.sentence, 2);
+    assert!((label.score - 0.9927).abs() < 1e-4);
+    assert!(output[3].is_err());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classification_pad_to_multiple_of() -> anyhow::Result<()> {
+    //    Set-up classifiers
+    let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    let padded_sequence_classification_model =
+        SequenceClassificationModel::new(SequenceClassificationConfig {
+            pad_to_multiple_of: Some(8),
+            ..Default::default()
+        })?;
+
+    //    Get labels, the extra padding positions are masked and do not change the output
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+        "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
+    ];
+
+    let output = sequence_classification_model.predict(&input)?;
+    let padded_output = padded_sequence_classification_model.predict(&input)?;
+
+    assert_eq!(output.len(), padded_output.len());
+    for (label, padded_label) in output.iter().zip(padded_output.iter()) {
+        assert_eq!(label.text, padded_label.text);
+        assert!((label.score - padded_label.score).abs() < 1e-6);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classification_single_input() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+
+    //    Single inputs take a dedicated path and should match the batched path (a batch of
+    //    identical inputs requires no padding)
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ];
+
+    for sentence in input.iter() {
+        let output = sequence_classification_model.predict(&[*sentence])?;
+        let batched_output = sequence_classification_model.predict(&[*sentence, *sentence])?;
+        assert_eq!(output.len(), 1usize);
+        assert_eq!(output[0].text, batched_output[0].text);
+        assert!((output[0].score - batched_output[0].score).abs() < 1e-6);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classification_batched() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+        "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
+        "A boring film.",
+        "I loved it.",
+    ];
+
+    //    Run model
+    let output = sequence_classification_model.predict(&input)?;
+    for &scheduling in [
+        ChunkScheduling::Sequential,
+        ChunkScheduling::Pipelined { prefetch: 0 },
+        ChunkScheduling::Pipelined { prefetch: 2 },
+    ]
+    .iter()
+    {
+        let batched_output =
+            sequence_classification_model.predict_batched(&input, 2, scheduling)?;
+        assert_eq!(batched_output.len(), output.len());
+        for (label, batched_label) in output.iter().zip(batched_output.iter()) {
+            assert_eq!(label.text, batched_label.text);
+            assert_eq!(label.sentence, batched_label.sentence);
+            assert!((label.score - batched_label.score).abs() < 1e-6);
+        }
+    }
+    assert!(sequence_classification_model
+        .predict_batched(&input, 0, ChunkScheduling::default())
+        .is_err());
+
+    Ok(())
+}
+
+fn write_tiny_distilbert_checkpoint(
+    directory: &Path,
+    name: &str,
+    config: &DistilBertConfig,
+    seed: u64,
+) -> anyhow::Result<Resource> {
+    write_weights(&directory.join(name), seed, |path| {
+        let _ = DistilBertModelClassifier::new(path, config);
+    })
+}
+
+//    Tiny sentiment classifier
+fn tiny_distilbert_classifier(directory: &Path) -> anyhow::Result<(Resource, Resource, Resource)> {
+    let vocab = [
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "a", "boring", "film", "i", "loved", "it",
+        ".",
+    ];
+    tiny_distilbert_resources(directory, &vocab, Some(&["NEGATIVE", "POSITIVE"]))
+}
+
+#[test]
+fn distilbert_sequence_classification_swap_weights() -> anyhow::Result<()> {
+    //    Tiny classifiers sharing a vocabulary and configuration, with different weights
+    let directory = tempfile::tempdir()?;
+    let (first_weights, config_resource, vocab_resource) =
+        tiny_distilbert_classifier(directory.path())?;
+    let mut config = DistilBertConfig::from_file(config_resource.get_local_path()?);
+    let second_weights =
+        write_tiny_distilbert_checkpoint(directory.path(), "second.ot", &config, 2)?;
+    config.dim = 8;
+    config.hidden_dim = 16;
+    let incompatible_weights =
+        write_tiny_distilbert_checkpoint(directory.path(), "incompatible.ot", &config, 3)?;
+
+    let mut model = SequenceClassificationModel::new(SequenceClassificationConfig {
+        model_resource: first_weights,
+        config_resource,
+        vocab_resource,
+        device: Device::Cpu,
+        ..Default::default()
+    })?;
+    let input = ["A boring film.", "I loved it."];
+    let first_output = model.predict(&input)?;
+
+    //    The new weights are used for the following predictions
+    model.swap_weights(second_weights)?;
+    let second_output = model.predict(&input)?;
+    assert!(first_output
+        .iter()
+        .zip(second_output.iter())
+        .any(|(first, second)| (first.score - second.score).abs() > 1e-6));
+
+    //    A failed swap keeps the current weights
+    assert!(model.swap_weights(incompatible_weights).is_err());
+    let output = model.predict(&input)?;
+    for (label, expected) in output.iter().zip(second_output.iter()) {
+        assert_eq!(label.text, expected.text);
+        assert!((label.score - expected.score).abs() < 1e-6);
+    }
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classification_state_round_trip() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let (model_resource, config_resource, vocab_resource) =
+        tiny_distilbert_classifier(directory.path())?;
+    let vocab_path = vocab_resource.get_local_path()?;
+    let model = SequenceClassificationModel::new(SequenceClassificationConfig {
+        model_resource: model_resource.clone(),
+        config_resource,
+        vocab_resource,
+        device: Device::Cpu,
+        pad_to_multiple_of: Some(8),
+        ..Default::default()
+    })?;
+    let state_path = directory.path().join("model.state");
+    model.save_state(&state_path)?;
+
+    let restored_model = SequenceClassificationModel::load_from_state(
+        &state_path,
+        model_resource.clone(),
+        Device::Cpu,
+    )?;
+    let input = [
+        "A boring film.",
+        "I loved it.",
+        "It was a film I loved, loved, loved.",
+    ];
+    let output = model.predict(&input)?;
+    let restored_output = restored_model.predict(&input)?;
+    for (label, expected) in restored_output.iter().zip(output.iter()) {
+        assert_eq!(label.text, expected.text);
+        assert_eq!(label.id, expected.id);
+        assert!((label.score - expected.score).abs() < 1e-6);
+    }
+
+    //    States built from a vocabulary modified since are rejected
+    std::fs::write(&vocab_path, "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nfilm")?;
+    assert!(
+        SequenceClassificationModel::load_from_state(&state_path, model_resource, Device::Cpu)
+            .is_err()
+    );
+    Ok(())
+}
+
 #[test]
 fn distilbert_masked_lm() -> anyhow::Result<()> {
     //    Resources paths
@@ -0,0 +1,117 @@
+mod common;
+
+use common::fixtures::{fix_weights, tiny_fnet_config, SEED};
+use rust_bert::fnet::{
+    FNetConfigResources, FNetForSequenceClassification, FNetModelResources, FNetVocabResources,
+};
+use rust_bert::pipelines::common::{ConfigOption, ModelType};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel, SequenceClassificationOption,
+};
+use rust_bert::resources::{RemoteResource, Resource};
+use tch::{nn, no_grad, Device, Tensor};
+
+#[test]
+fn fnet_sequence_classification_tiny() -> anyhow::Result<()> {
+    //    Tiny model with fixed weights
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = SequenceClassificationOption::new(
+        ModelType::FNet,
+        &vs.root(),
+        &ConfigOption::FNet(tiny_fnet_config(&["NEGATIVE", "POSITIVE"])),
+    )?;
+    fix_weights(&vs, SEED);
+    assert_eq!(model.model_type(), ModelType::FNet);
+
+    let input_ids = Tensor::of_slice(&[4i64, 5, 6, 7, 5, 4, 8, 9, 5, 3]).view((2, 5));
+    let token_type_ids = Tensor::of_slice(&[0i64, 0, 0, 0, 0, 0, 0, 1, 1, 1]).view((2, 5));
+    let logits = no_grad(|| {
+        model.forward_t(
+            Some(input_ids),
+            None,
+            Some(token_type_ids),
+            None,
+            None,
+            false,
+        )
+    });
+    assert_eq!(logits.size(), vec![2, 2]);
+    assert!(!bool::from(logits.isnan().any()));
+
+    Ok(())
+}
+
+#[test]
+fn fnet_hidden_states() -> anyhow::Result<()> {
+    let mut config = tiny_fnet_config(&["NEGATIVE", "POSITIVE"]);
+    config.output_hidden_states = Some(true);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = FNetForSequenceClassification::new(&vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[4i64, 5, 6, 7, 5]).view((1, 5));
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, false))?;
+
+    assert_eq!(output.logits.size(), vec![1, 2]);
+    let all_hidden_states = output.all_hidden_states.unwrap();
+    assert_eq!(all_hidden_states.len(), 2);
+    assert_eq!(all_hidden_states[0].size(), vec![1, 5, 16]);
+    assert!(model
+        .forward_t(
+            Some(&input_ids),
+            None,
+            None,
+            Some(&Tensor::zeros(&[1, 5, 16], (tch::Kind::Float, Device::Cpu))),
+            false
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn fnet_mismatched_config() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = ConfigOption::try_from_str(
+        ModelType::DistilBert,
+        r#"{"activation": "gelu", "attention_dropout": 0.1, "dim": 8, "dropout": 0.1, "hidden_dim": 16, "initializer_range": 0.02, "max_position_embeddings": 16, "n_heads": 2, "n_layers": 1, "qa_dropout": 0.1, "seq_classif_dropout": 0.2, "sinusoidal_pos_embds": false, "tie_weights_": false, "vocab_size": 16}"#,
+    )?;
+    assert!(SequenceClassificationOption::new(ModelType::FNet, &vs.root(), &config).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn fnet_sentiment_analysis() -> anyhow::Result<()> {
+    //    Set-up model
+    let config = SequenceClassificationConfig::new(
+        ModelType::FNet,
+        Resource::Remote(RemoteResource::from_pretrained(
+            FNetModelResources::BASE_SST2,
+        )),
+        Resource::Remote(RemoteResource::from_pretrained(
+            FNetConfigResources::BASE_SST2,
+        )),
+        Resource::Remote(RemoteResource::from_pretrained(
+            FNetVocabResources::BASE_SST2,
+        )),
+        None,
+        false,
+        None,
+        None,
+    );
+    let sentiment_model = SequenceClassificationModel::new(config)?;
+
+    //    Get sentiments
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ];
+
+    let output = sentiment_model.predict(&input)?;
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].id, 1);
+    assert_eq!(output[1].id, 0);
+
+    Ok(())
+}
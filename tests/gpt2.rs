@@ -1,3 +1,6 @@
+mod common;
+
+use common::fixtures::tiny_gpt2_resources;
 use rust_bert::gpt2::{
     GPT2Generator, GPT2LMHeadModel, Gpt2Config, Gpt2ConfigResources, Gpt2MergesResources,
     Gpt2ModelResources, Gpt2VocabResources,
@@ -6,6 +9,7 @@ use rust_bert::pipelines::common::ModelType;
 use rust_bert::pipelines::conversation::{
     ConversationConfig, ConversationManager, ConversationModel,
 };
+use rust_bert::pipelines::denylist::{DenyList, DenyListPolicy};
 use rust_bert::pipelines::generation_utils::{
     Cache, GenerateConfig, LMHeadModel, LanguageGenerator,
 };
@@ -82,7 +86,7 @@ fn gpt2_lm_model() -> anyhow::Result<()> {
         .get(-1)
         .argmax(-1, true)
         .int64_value(&[0]);
-    let next_word = tokenizer.decode(vec![next_word_id], true, true);
+    let next_word = tokenizer.decode(&[next_word_id], true, true);
 
     assert_eq!(model_output.lm_logits.size(), vec!(1, 4, 50257));
     match model_output.cache {
@@ -687,3 +691,104 @@ fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> any
 
     Ok(())
 }
+
+fn tiny_gpt2_generation_config(
+    directory: &std::path::Path,
+    output_denylist: Option<DenyList>,
+) -> anyhow::Result<TextGenerationConfig> {
+    let (model_resource, config_resource, vocab_resource, merges_resource) =
+        tiny_gpt2_resources(directory, &[])?;
+    Ok(TextGenerationConfig {
+        model_type: ModelType::GPT2,
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 0,
+        device: Device::Cpu,
+        output_denylist,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn gpt2_generation_denylist() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let input_context = "the dog";
+
+    //    Pick a letter generated by the model (and absent from the prompt) to deny
+    let model = TextGenerationModel::new(tiny_gpt2_generation_config(directory.path(), None)?)?;
+    let reference = model.generate(&[input_context], None).remove(0);
+    let denied_letter = reference[input_context.len()..]
+        .chars()
+        .find(|character| character.is_ascii_lowercase() && !input_context.contains(*character))
+        .expect("the model should generate a letter absent from the prompt")
+        .to_string();
+
+    let deny_list = DenyList::new(&[denied_letter.as_str()], true, DenyListPolicy::Redact)?;
+    let model = TextGenerationModel::new(tiny_gpt2_generation_config(
+        directory.path(),
+        Some(deny_list.clone()),
+    )?)?;
+    let output = model.generate(&[input_context], None);
+    assert_eq!(output[0], deny_list.redact(&reference));
+    assert!(output[0].contains('*'));
+
+    let deny_list = DenyList::new(
+        &[denied_letter.as_str()],
+        true,
+        DenyListPolicy::Regenerate { retries: 2 },
+    )?;
+    let model = TextGenerationModel::new(tiny_gpt2_generation_config(
+        directory.path(),
+        Some(deny_list),
+    )?)?;
+    let output = model.generate(&[input_context], None);
+    assert!(output[0].starts_with(input_context));
+    assert!(!output[0].contains(denied_letter.as_str()));
+    assert!(!output[0].contains('*'));
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_without_prompt() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    //    Byte-level characters of the UTF-8 encoding of the dog face emoji (F0 9F 90 B6)
+    let (model_resource, config_resource, vocab_resource, merges_resource) =
+        tiny_gpt2_resources(directory.path(), &["ð", "Ł", "Ĳ", "¶"])?;
+    let generation_config = |include_prompt| TextGenerationConfig {
+        model_type: ModelType::GPT2,
+        model_resource: model_resource.clone(),
+        config_resource: config_resource.clone(),
+        vocab_resource: vocab_resource.clone(),
+        merges_resource: merges_resource.clone(),
+        max_length: 24,
+        do_sample: false,
+        num_beams: 1,
+        no_repeat_ngram_size: 0,
+        num_return_sequences: 1,
+        device: Device::Cpu,
+        include_prompt,
+        ..Default::default()
+    };
+    let full_model = TextGenerationModel::new(generation_config(true))?;
+    let continuation_model = TextGenerationModel::new(generation_config(false))?;
+
+    //    Prompts ending with a partial word, an emoji and trailing spaces, of different lengths
+    let prompts = ["the quick bro", "my dog 🐶", "the cat sat  ", "a"];
+    let full_outputs = full_model.generate(&prompts, None);
+    let continuations = continuation_model.generate(&prompts, None);
+
+    assert_eq!(continuations.len(), prompts.len());
+    for ((prompt, full_output), continuation) in
+        prompts.iter().zip(&full_outputs).zip(&continuations)
+    {
+        assert!(full_output.starts_with(prompt));
+        assert_eq!(&format!("{}{}", prompt, continuation), full_output);
+    }
+    Ok(())
+}
@@ -11,7 +11,10 @@ use rust_bert::pipelines::common::ModelType;
 use rust_bert::pipelines::question_answering::{
     QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
 };
-use rust_bert::resources::{RemoteResource, Resource};
+use rust_bert::pipelines::token_classification::{
+    LabelAggregationOption, TokenClassificationConfig, TokenClassificationModel,
+};
+use rust_bert::resources::{LocalResource, RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{MultiThreadedTokenizer, RobertaTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::{RobertaVocab, Vocab};
@@ -274,7 +277,7 @@ fn longformer_for_multiple_choice() -> anyhow::Result<()> {
     let prompt = "In Italy, pizza served in formal settings, such as at a restaurant, is presented unsliced.";
     let inputs = ["Very positive sentence", "Second sentence input"];
     let tokenized_input = tokenizer.encode_pair_list(
-        inputs
+        &inputs
             .iter()
             .map(|&inp| (prompt, inp))
             .collect::<Vec<(&str, &str)>>(),
@@ -444,3 +447,81 @@ fn longformer_for_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn longformer_question_answering_long_context() -> anyhow::Result<()> {
+    let qa_model = QuestionAnsweringModel::new(QuestionAnsweringConfig::longformer_squad())?;
+
+    //    The answer is located after more than 512 tokens of a context of more than 1500 tokens,
+    //    processed in a single window with global attention on the question
+    let filler = "The weather was mild and the streets were quiet during the whole afternoon.";
+    let context = format!(
+        "{} Amy lives in Amsterdam. {}",
+        vec![filler; 50].join(" "),
+        vec![filler; 70].join(" ")
+    );
+    let answer_start = context.find("Amsterdam").unwrap();
+    let qa_input = QaInput {
+        question: String::from("Where does Amy live ?"),
+        context,
+    };
+
+    let answers = qa_model.predict(&[qa_input], 1, 1);
+
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0].len(), 1);
+    let answer = &answers[0][0];
+    assert_eq!(answer.answer.trim(), "Amsterdam");
+    assert_eq!(answer.end, answer_start + "Amsterdam".len());
+
+    Ok(())
+}
+
+#[test]
+fn longformer_token_classification_long_input() -> anyhow::Result<()> {
+    //    Randomly initialized token classification head on the base Longformer configuration
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        LongformerConfigResources::LONGFORMER_BASE_4096,
+    ));
+    let directory = tempfile::tempdir()?;
+    let mut config_json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(config_resource.get_local_path()?)?)?;
+    config_json["id2label"] = serde_json::json!({"0": "O", "1": "LOC", "2": "PER", "3": "ORG"});
+    config_json["label2id"] = serde_json::json!({"O": 0, "LOC": 1, "PER": 2, "ORG": 3});
+    let config_path = directory.path().join("config.json");
+    std::fs::write(&config_path, config_json.to_string())?;
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = LongformerForTokenClassification::new(
+        &vs.root(),
+        &LongformerConfig::from_file(&config_path),
+    );
+    let weights_path = directory.path().join("rust_model.ot");
+    vs.save(&weights_path)?;
+
+    let mut config = TokenClassificationConfig::new_longformer(
+        Resource::Local(LocalResource {
+            local_path: weights_path,
+        }),
+        Resource::Local(LocalResource {
+            local_path: config_path,
+        }),
+        LabelAggregationOption::First,
+    );
+    config.device = Device::Cpu;
+    let model = TokenClassificationModel::new(config)?;
+
+    //    Inputs longer than 1024 tokens are processed without truncation
+    let input = vec!["Amy lives in Amsterdam."; 250].join(" ");
+    let output = model.predict(&[input.as_str()], false, false)?;
+
+    assert_eq!(output.len(), 1);
+    assert!(output[0].len() > 1024);
+    let last_token = output[0].last().unwrap();
+    assert_eq!(last_token.text, ".");
+    assert_eq!(
+        last_token.offset.map(|offset| offset.end as usize),
+        Some(input.chars().count())
+    );
+
+    Ok(())
+}
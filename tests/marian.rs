@@ -1,4 +1,13 @@
+use rust_bert::marian::{
+    MarianConfigResources, MarianGenerator, MarianModelResources, MarianSpmResources,
+    MarianVocabResources,
+};
+use rust_bert::pipelines::generation_utils::{
+    DecoderStep, DecodingStrategy, GenerateConfig, LanguageGenerator,
+};
 use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
+use rust_bert::resources::{RemoteResource, Resource};
+use std::sync::{Arc, Mutex};
 use tch::Device;
 
 #[test]
@@ -22,3 +31,56 @@ fn test_translation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn marian_greedy_generation_uses_cache() -> anyhow::Result<()> {
+    let decoder_steps = Arc::new(Mutex::new(Vec::new()));
+    let hook_decoder_steps = decoder_steps.clone();
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            MarianModelResources::ENGLISH2ROMANCE,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            MarianConfigResources::ENGLISH2ROMANCE,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            MarianVocabResources::ENGLISH2ROMANCE,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            MarianSpmResources::ENGLISH2ROMANCE,
+        )),
+        min_length: 8,
+        max_length: 12,
+        decoding_strategy: Some(DecodingStrategy::Greedy),
+        decoder_step_hook: Some(Box::new(move |decoder_step: &DecoderStep| {
+            hook_decoder_steps.lock().unwrap().push(*decoder_step)
+        })),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = MarianGenerator::new(generate_config)?;
+
+    let output = model.generate(
+        Some(&["The quick brown fox jumps over the lazy dog."]),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+    );
+    assert_eq!(output.len(), 1);
+
+    //    The decoder start token is fed at the first step, each following step only feeds the last
+    //    generated token and extends the cache by one position
+    let decoder_steps = decoder_steps.lock().unwrap();
+    assert!(decoder_steps.len() >= 8);
+    for (step_index, decoder_step) in decoder_steps.iter().enumerate() {
+        assert_eq!(decoder_step.step, step_index as i64);
+        assert_eq!(decoder_step.input_length, 1);
+        assert_eq!(decoder_step.cached_length, step_index as i64);
+    }
+
+    Ok(())
+}
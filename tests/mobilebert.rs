@@ -182,7 +182,7 @@ fn mobilebert_for_multiple_choice() -> anyhow::Result<()> {
     let prompt = "In Italy, pizza served in formal settings, such as at a restaurant, is presented unsliced.";
     let inputs = ["Very positive sentence", "Second sentence input"];
     let tokenized_input = tokenizer.encode_pair_list(
-        inputs
+        &inputs
             .iter()
             .map(|&inp| (prompt, inp))
             .collect::<Vec<(&str, &str)>>(),
@@ -364,7 +364,7 @@ fn mobilebert_part_of_speech_tagging() -> anyhow::Result<()> {
         ],
     ];
 
-    let answers = pos_model.predict(&input);
+    let answers = pos_model.predict(&input)?;
 
     assert_eq!(answers.len(), 2_usize);
     assert_eq!(answers[0].len(), expected_outputs[0].len());
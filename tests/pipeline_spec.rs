@@ -0,0 +1,265 @@
+mod common;
+
+use common::fixtures::{tiny_bart_resources, tiny_gpt2_resources};
+use common::write_tiny_bert;
+use rust_bert::pipelines::from_spec;
+use rust_bert::pipelines::spec::{PipelineHandle, TaskType};
+use std::path::{Path, PathBuf};
+
+//    Tiny models, written next to the spec files to exercise the resolution of relative paths. The
+//    translation pipeline is not covered: its models require a SentencePiece model that cannot be
+//    generated offline.
+
+fn write_spec(directory: &Path, file_name: &str, contents: &str) -> anyhow::Result<PathBuf> {
+    let path = directory.join(file_name);
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+const BERT_MODEL_JSON: &str = r#""model": {
+        "model_type": "Bert",
+        "weights": "bert/rust_model.ot",
+        "config": "bert/config.json",
+        "vocab": "bert/vocab.txt",
+        "lower_case": true
+    },
+    "device": "cpu""#;
+
+const BERT_MODEL_YAML: &str = "model:
+  model_type: Bert
+  weights: bert/rust_model.ot
+  config: bert/config.json
+  vocab: bert/vocab.txt
+  lower_case: true
+device: cpu
+";
+
+fn bert_directory() -> anyhow::Result<tempfile::TempDir> {
+    let directory = tempfile::tempdir()?;
+    std::fs::create_dir(directory.path().join("bert"))?;
+    write_tiny_bert(&directory.path().join("bert"))?;
+    Ok(directory)
+}
+
+#[test]
+fn spec_sequence_classification() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.json",
+        &format!(
+            r#"{{"task": "sequence_classification", {}, "pad_to_multiple_of": 8}}"#,
+            BERT_MODEL_JSON
+        ),
+    )?;
+    let pipeline = from_spec(&spec)?;
+    assert_eq!(pipeline.task(), TaskType::SequenceClassification);
+    if let PipelineHandle::SequenceClassification(model) = pipeline {
+        let output = model.predict(&["the dog is in paris."])?;
+        assert_eq!(output.len(), 1);
+    } else {
+        panic!("unexpected pipeline");
+    }
+    Ok(())
+}
+
+#[test]
+fn spec_sentiment() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!("task: sentiment\n{}", BERT_MODEL_YAML),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::Sentiment);
+    Ok(())
+}
+
+#[test]
+fn spec_token_classification() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yml",
+        &format!(
+            "task: token_classification\n{}label_aggregation: Mode\n",
+            BERT_MODEL_YAML
+        ),
+    )?;
+    let pipeline = from_spec(&spec)?;
+    assert_eq!(pipeline.task(), TaskType::TokenClassification);
+    if let PipelineHandle::TokenClassification(model) = pipeline {
+        let output = model.predict(&["the dog is in paris."], true, false);
+        assert!(!output.is_empty());
+    } else {
+        panic!("unexpected pipeline");
+    }
+    Ok(())
+}
+
+#[test]
+fn spec_ner() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.json",
+        &format!(r#"{{"task": "ner", {}}}"#, BERT_MODEL_JSON),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::Ner);
+    Ok(())
+}
+
+#[test]
+fn spec_pos_tagging() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!("task: pos_tagging\n{}", BERT_MODEL_YAML),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::PosTagging);
+    Ok(())
+}
+
+#[test]
+fn spec_question_answering() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.json",
+        &format!(
+            r#"{{
+                "task": "question_answering",
+                {},
+                "max_seq_length": 64,
+                "doc_stride": 16,
+                "max_query_length": 16
+            }}"#,
+            BERT_MODEL_JSON
+        ),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::QuestionAnswering);
+    Ok(())
+}
+
+#[test]
+fn spec_zero_shot_classification() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!(
+            "task: zero_shot_classification\n{}label_chunk_size: 2\n",
+            BERT_MODEL_YAML
+        ),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::ZeroShotClassification);
+    Ok(())
+}
+
+#[test]
+fn spec_feature_extraction() -> anyhow::Result<()> {
+    let directory = bert_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.json",
+        &format!(
+            r#"{{"task": "feature_extraction", {}, "layers": [{{"Layer": 2}}], "word_pooling": true}}"#,
+            BERT_MODEL_JSON
+        ),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::FeatureExtraction);
+    Ok(())
+}
+
+const GPT2_MODEL_YAML: &str = "model:
+  model_type: GPT2
+  weights: gpt2/rust_model.ot
+  config: gpt2/config.json
+  vocab: gpt2/vocab.json
+  merges: gpt2/merges.txt
+device: cpu
+generation:
+  max_length: 12
+  do_sample: false
+  num_beams: 1
+";
+
+fn gpt2_directory() -> anyhow::Result<tempfile::TempDir> {
+    let directory = tempfile::tempdir()?;
+    std::fs::create_dir(directory.path().join("gpt2"))?;
+    tiny_gpt2_resources(&directory.path().join("gpt2"), &[])?;
+    Ok(directory)
+}
+
+#[test]
+fn spec_text_generation() -> anyhow::Result<()> {
+    let directory = gpt2_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!("task: text_generation\n{}", GPT2_MODEL_YAML),
+    )?;
+    let pipeline = from_spec(&spec)?;
+    assert_eq!(pipeline.task(), TaskType::TextGeneration);
+    if let PipelineHandle::TextGeneration(model) = pipeline {
+        let output = model.generate(&["the dog"], None);
+        assert_eq!(output.len(), 1);
+    } else {
+        panic!("unexpected pipeline");
+    }
+    Ok(())
+}
+
+#[test]
+fn spec_conversation() -> anyhow::Result<()> {
+    let directory = gpt2_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!("task: conversation\n{}", GPT2_MODEL_YAML),
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::Conversation);
+    Ok(())
+}
+
+#[test]
+fn spec_summarization() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    std::fs::create_dir(directory.path().join("bart"))?;
+    tiny_bart_resources(&directory.path().join("bart"))?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.json",
+        r#"{
+            "task": "summarization",
+            "model": {
+                "model_type": "Bart",
+                "weights": "bart/rust_model.ot",
+                "config": "bart/config.json",
+                "vocab": "bart/vocab.json",
+                "merges": "bart/merges.txt"
+            },
+            "device": "cpu",
+            "generation": {"min_length": 2, "max_length": 8, "num_beams": 1}
+        }"#,
+    )?;
+    assert_eq!(from_spec(&spec)?.task(), TaskType::Summarization);
+    Ok(())
+}
+
+#[test]
+fn spec_validation_error() -> anyhow::Result<()> {
+    let directory = gpt2_directory()?;
+    let spec = write_spec(
+        directory.path(),
+        "spec.yaml",
+        &format!("task: text_generation\n{}  top_p: 1.5\n", GPT2_MODEL_YAML),
+    )?;
+    let error = from_spec(&spec).err().unwrap().to_string();
+    assert!(error.contains("`generation.top_p` must be between 0 and 1"));
+
+    let spec = write_spec(directory.path(), "spec.toml", "task = \"text_generation\"")?;
+    assert!(from_spec(&spec).is_err());
+    Ok(())
+}
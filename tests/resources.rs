@@ -0,0 +1,34 @@
+use rust_bert::resources::{clear_mirror_hook, set_mirror_prefixes, RemoteResource};
+
+//    The mirror hook is global to the process: this test runs in its own test binary so that it
+//    does not affect the downloads of concurrent tests.
+#[test]
+fn mirror_prefixes() {
+    set_mirror_prefixes(
+        "https://models.example.com/",
+        &[
+            "https://mirror-a.example.com/models/",
+            "https://mirror-b.example.com/",
+        ],
+    );
+    let resource = RemoteResource::new(
+        "https://models.example.com/distilbert/config.json",
+        "distilbert/config",
+    )
+    .with_mirrors(&["https://mirror-b.example.com/distilbert/config.json"]);
+    let other = RemoteResource::new("https://other.example.com/config.json", "other/config");
+    let urls = resource.candidate_urls();
+    let other_urls = other.candidate_urls();
+    clear_mirror_hook();
+
+    assert_eq!(
+        urls,
+        vec![
+            "https://models.example.com/distilbert/config.json",
+            "https://mirror-b.example.com/distilbert/config.json",
+            "https://mirror-a.example.com/models/distilbert/config.json",
+        ]
+    );
+    assert_eq!(other_urls, vec!["https://other.example.com/config.json"]);
+    assert_eq!(resource.candidate_urls().len(), 2);
+}
@@ -400,7 +400,7 @@ fn xlm_roberta_german_ner() -> anyhow::Result<()> {
         "Chongqing ist eine Stadt in China.",
     ];
 
-    let output = ner_model.predict(&input);
+    let output = ner_model.predict(&input)?;
 
     assert_eq!(output.len(), 2);
     assert_eq!(output[0].len(), 2);
@@ -0,0 +1,161 @@
+use rust_bert::pipelines::conversation::Conversation;
+use rust_bert::pipelines::generation_utils::{GeneratedIndicesOutput, GeneratedTextOutput};
+use rust_bert::pipelines::ner::Entity;
+use rust_bert::pipelines::pos_tagging::POSTag;
+use rust_bert::pipelines::question_answering::Answer;
+use rust_bert::pipelines::sentiment::{Sentiment, SentimentPolarity};
+use rust_bert::pipelines::sequence_classification::Label;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+//    The JSON shape of the pipeline outputs is relied upon by API consumers: the field names are
+//    checked explicitly before the round-trip.
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T, expected: Value) -> anyhow::Result<T> {
+    let serialized = serde_json::to_value(value)?;
+    assert_eq!(serialized, expected);
+    Ok(serde_json::from_str(&serde_json::to_string(value)?)?)
+}
+
+#[test]
+fn label_serialization() -> anyhow::Result<()> {
+    let label = Label {
+        text: "POSITIVE".to_string(),
+        score: 0.5,
+        id: 1,
+        sentence: 2,
+    };
+    let restored = round_trip(
+        &label,
+        json!({"text": "POSITIVE", "score": 0.5, "id": 1, "sentence": 2}),
+    )?;
+    assert_eq!(restored.text, label.text);
+    assert_eq!(restored.score, label.score);
+    assert_eq!(restored.id, label.id);
+    assert_eq!(restored.sentence, label.sentence);
+    Ok(())
+}
+
+#[test]
+fn sentiment_serialization() -> anyhow::Result<()> {
+    let sentiment = Sentiment {
+        polarity: SentimentPolarity::Negative,
+        score: 0.75,
+    };
+    let restored = round_trip(&sentiment, json!({"polarity": "Negative", "score": 0.75}))?;
+    assert_eq!(restored.polarity, sentiment.polarity);
+    assert_eq!(restored.score, sentiment.score);
+    Ok(())
+}
+
+#[test]
+fn entity_serialization() -> anyhow::Result<()> {
+    let entity = Entity {
+        word: "Paris".to_string(),
+        score: 0.25,
+        label: "I-LOC".to_string(),
+        start: 26,
+        end: 31,
+    };
+    let restored = round_trip(
+        &entity,
+        json!({"word": "Paris", "score": 0.25, "label": "I-LOC", "start": 26, "end": 31}),
+    )?;
+    assert_eq!(restored.word, entity.word);
+    assert_eq!(restored.score, entity.score);
+    assert_eq!(restored.label, entity.label);
+    assert_eq!((restored.start, restored.end), (entity.start, entity.end));
+    Ok(())
+}
+
+#[test]
+fn pos_tag_serialization() -> anyhow::Result<()> {
+    let pos_tag = POSTag {
+        word: "dog".to_string(),
+        score: 0.5,
+        label: "NN".to_string(),
+    };
+    let restored = round_trip(
+        &pos_tag,
+        json!({"word": "dog", "score": 0.5, "label": "NN"}),
+    )?;
+    assert_eq!(restored.word, pos_tag.word);
+    assert_eq!(restored.score, pos_tag.score);
+    assert_eq!(restored.label, pos_tag.label);
+    Ok(())
+}
+
+#[test]
+fn answer_serialization() -> anyhow::Result<()> {
+    let answer = Answer {
+        score: 0.125,
+        start: 13,
+        end: 21,
+        answer: "Amsterdam".to_string(),
+        context_index: 2,
+    };
+    let restored = round_trip(
+        &answer,
+        json!({
+            "score": 0.125,
+            "start": 13,
+            "end": 21,
+            "answer": "Amsterdam",
+            "context_index": 2
+        }),
+    )?;
+    assert_eq!(restored, answer);
+    assert_eq!(restored.score, answer.score);
+    //    Answers serialized without a context index are extracted from a single context
+    let restored: Answer = serde_json::from_value(
+        json!({"score": 0.125, "start": 13, "end": 21, "answer": "Amsterdam"}),
+    )?;
+    assert_eq!(restored.context_index, 0);
+    Ok(())
+}
+
+#[test]
+fn conversation_serialization() -> anyhow::Result<()> {
+    let mut conversation = Conversation::new("Hello, how are you?");
+    conversation.history = vec![vec![1, 2, 3]];
+    let restored = round_trip(
+        &conversation,
+        json!({
+            "past_user_inputs": [],
+            "generated_responses": [],
+            "new_user_input": "Hello, how are you?",
+            "history": [[1, 2, 3]]
+        }),
+    )?;
+    assert_eq!(restored.past_user_inputs, conversation.past_user_inputs);
+    assert_eq!(
+        restored.generated_responses,
+        conversation.generated_responses
+    );
+    assert_eq!(restored.new_user_input, conversation.new_user_input);
+    assert_eq!(restored.history, conversation.history);
+    Ok(())
+}
+
+#[test]
+fn generated_output_serialization() -> anyhow::Result<()> {
+    let text_output = GeneratedTextOutput {
+        text: "The dog".to_string(),
+        score: Some(-1.5),
+    };
+    let restored = round_trip(&text_output, json!({"text": "The dog", "score": -1.5}))?;
+    assert_eq!(restored.text, text_output.text);
+    assert_eq!(restored.score, text_output.score);
+
+    let indices_output = GeneratedIndicesOutput {
+        indices: vec![464, 3290],
+        score: None,
+    };
+    let restored = round_trip(
+        &indices_output,
+        json!({"indices": [464, 3290], "score": null}),
+    )?;
+    assert_eq!(restored.indices, indices_output.indices);
+    assert_eq!(restored.score, indices_output.score);
+    Ok(())
+}
@@ -0,0 +1,238 @@
+mod common;
+#[path = "../examples/serve.rs"]
+#[allow(dead_code)]
+mod serve;
+
+use common::write_tiny_bert;
+use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::token_classification::{
+    LabelAggregationOption, TokenClassificationConfig,
+};
+use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationConfig;
+use rust_bert::resources::{LocalResource, Resource};
+use serde_json::{json, Value};
+use serve::{InferenceServer, Pipelines, ServerConfig};
+use std::path::Path;
+use std::thread;
+use tch::Device;
+
+const SHUTDOWN_TOKEN: &str = "test-token";
+
+fn local_resource(directory: &Path, file_name: &str) -> Resource {
+    Resource::Local(LocalResource {
+        local_path: directory.join(file_name),
+    })
+}
+
+fn post(url: &str, headers: &[(&str, &str)], body: String) -> anyhow::Result<(u16, String)> {
+    let mut request = reqwest::blocking::Client::new().post(url).body(body);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send()?;
+    Ok((response.status().as_u16(), response.text()?))
+}
+
+fn get(url: &str) -> anyhow::Result<(u16, Value)> {
+    let response = reqwest::blocking::get(url)?;
+    Ok((
+        response.status().as_u16(),
+        serde_json::from_str(&response.text()?)?,
+    ))
+}
+
+fn start_tiny_server(
+    directory: &Path,
+    shutdown_token: Option<&str>,
+) -> anyhow::Result<InferenceServer> {
+    write_tiny_bert(directory)?;
+    let ner_config = TokenClassificationConfig {
+        device: Device::Cpu,
+        ..TokenClassificationConfig::new(
+            ModelType::Bert,
+            local_resource(directory, "rust_model.ot"),
+            local_resource(directory, "config.json"),
+            local_resource(directory, "vocab.txt"),
+            None,
+            true,
+            None,
+            None,
+            LabelAggregationOption::First,
+        )
+    };
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..ZeroShotClassificationConfig::new(
+            ModelType::Bert,
+            local_resource(directory, "rust_model.ot"),
+            local_resource(directory, "config.json"),
+            local_resource(directory, "vocab.txt"),
+            None,
+            true,
+            None,
+            None,
+        )
+    };
+    Ok(InferenceServer::start(
+        "127.0.0.1:0",
+        Pipelines::new(ner_config, zero_shot_config)?,
+        ServerConfig {
+            zero_shot_max_batch_inputs: 6,
+            shutdown_token: shutdown_token.map(String::from),
+            ..Default::default()
+        },
+    )?)
+}
+
+#[test]
+fn serve_concurrent_requests() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let server = start_tiny_server(directory.path(), None)?;
+    let base_url = format!("http://{}", server.address());
+
+    let handles = (0..8)
+        .map(|index| {
+            let base_url = base_url.clone();
+            thread::spawn(move || -> anyhow::Result<()> {
+                let inputs = vec!["the dog is in paris."; index + 1];
+                let tenant = format!("tenant_{}", index % 3);
+
+                let (status, body) = post(
+                    &format!("{}/ner", base_url),
+                    &[("X-Tenant", &tenant)],
+                    json!({ "inputs": inputs }).to_string(),
+                )?;
+                assert_eq!(status, 200);
+                let entities: Vec<Value> = serde_json::from_str(&body)?;
+                assert_eq!(entities.len(), inputs.len());
+
+                //    Requests batched together keep their own candidate labels and input indices
+                let labels = if index % 2 == 0 {
+                    ["dog", "paris"]
+                } else {
+                    ["is", "in"]
+                };
+                let (status, body) = post(
+                    &format!("{}/zero-shot", base_url),
+                    &[],
+                    json!({ "inputs": inputs, "labels": labels }).to_string(),
+                )?;
+                assert_eq!(status, 200);
+                let output: Vec<Value> = serde_json::from_str(&body)?;
+                assert_eq!(output.len(), inputs.len());
+                for (sentence, label) in output.iter().enumerate() {
+                    assert!(labels.contains(&label["text"].as_str().unwrap()));
+                    assert_eq!(label["sentence"], sentence);
+                }
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap()?;
+    }
+
+    //    All requests are answered: no work is left in any pipeline
+    let (status, health) = get(&format!("{}/healthz", base_url))?;
+    assert_eq!(status, 200);
+    for pipeline in ["ner", "zero_shot"].iter() {
+        assert_eq!(
+            health["queues"][pipeline],
+            json!({ "queued": 0, "in_flight": 0 })
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn serve_health() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let server = start_tiny_server(directory.path(), None)?;
+
+    let (status, health) = get(&format!("http://{}/healthz", server.address()))?;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "ok");
+    assert_eq!(
+        health["runtime"]["rust_bert_version"],
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(health["runtime"]["num_threads"].as_i64().unwrap() > 0);
+    assert!(health["runtime"]["cuda_available"].is_boolean());
+    for pipeline in ["ner", "zero_shot"].iter() {
+        assert_eq!(
+            health["models"][pipeline],
+            json!({ "model_type": "Bert", "device": "Cpu" })
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn serve_invalid_requests() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let server = start_tiny_server(directory.path(), None)?;
+    let base_url = format!("http://{}", server.address());
+
+    let (status, _) = post(&format!("{}/ner", base_url), &[], "not json".to_string())?;
+    assert_eq!(status, 400);
+    let (status, _) = post(
+        &format!("{}/zero-shot", base_url),
+        &[],
+        json!({ "inputs": ["the dog"] }).to_string(),
+    )?;
+    assert_eq!(status, 400);
+    let (status, _) = post(
+        &format!("{}/zero-shot", base_url),
+        &[],
+        json!({ "inputs": ["the dog"], "labels": [] }).to_string(),
+    )?;
+    assert_eq!(status, 400);
+    //    The zero-shot worker keeps serving after an invalid request
+    let (status, _) = post(
+        &format!("{}/zero-shot", base_url),
+        &[],
+        json!({ "inputs": ["the dog"], "labels": ["dog"] }).to_string(),
+    )?;
+    assert_eq!(status, 200);
+    let (status, _) = get(&format!("{}/unknown", base_url))?;
+    assert_eq!(status, 404);
+
+    //    Without a configured token, the shutdown endpoint is disabled
+    let (status, _) = post(&format!("{}/shutdown", base_url), &[], String::new())?;
+    assert_eq!(status, 404);
+    let (status, health) = get(&format!("{}/healthz", base_url))?;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "ok");
+    Ok(())
+}
+
+#[test]
+fn serve_shutdown() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let server = start_tiny_server(directory.path(), Some(SHUTDOWN_TOKEN))?;
+    let base_url = format!("http://{}", server.address());
+
+    let (status, _) = post(&format!("{}/shutdown", base_url), &[], String::new())?;
+    assert_eq!(status, 401);
+    let (status, _) = post(
+        &format!("{}/shutdown", base_url),
+        &[("Authorization", "Bearer wrong-token")],
+        String::new(),
+    )?;
+    assert_eq!(status, 401);
+    let (status, health) = get(&format!("{}/healthz", base_url))?;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "ok");
+
+    let authorization = format!("Bearer {}", SHUTDOWN_TOKEN);
+    let (status, _) = post(
+        &format!("{}/shutdown", base_url),
+        &[("Authorization", &authorization)],
+        String::new(),
+    )?;
+    assert_eq!(status, 202);
+
+    //    Returns once every worker stopped
+    server.wait();
+    Ok(())
+}
@@ -0,0 +1,280 @@
+mod common;
+
+use common::write_tiny_bert;
+use rust_bert::pipelines::common::{char_to_byte, ModelType};
+use rust_bert::pipelines::ner::NERModel;
+use rust_bert::pipelines::question_answering::{
+    Answer, QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
+};
+use rust_bert::pipelines::token_classification::{
+    AggregationStrategy, LabelAggregationOption, TokenClassificationConfig,
+    TokenClassificationModel,
+};
+use rust_bert::resources::{LocalResource, Resource};
+use std::path::Path;
+use tch::Tensor;
+
+//    Inputs mixing zero-width joiners, variation selectors, combining marks and characters outside
+//    of the basic multilingual plane (2 UTF-16 code units each). Offsets returned by the pipelines
+//    are character offsets: slicing the input by them must give back the text of the output.
+const ADVERSARIAL_INPUTS: [&str; 6] = [
+    "the dog\u{200d}s are in paris.",
+    "\u{1f469}\u{200d}\u{1f52c} the dog is in paris.",
+    "the dog\u{fe0f} is in pa\u{301}ris \u{2764}\u{fe0f}.",
+    "\u{1f1eb}\u{1f1f7} paris\u{1f600}\u{1f600} is where the dog is.",
+    "where is the \u{200b}dog\u{200b}?",
+    "the \u{1d4c5}\u{1d4b6}ris dog is in paris\u{e0067}.",
+];
+
+fn set_classifier_bias(directory: &Path, bias: &[f64]) -> anyhow::Result<()> {
+    let weights_path = directory.join("rust_model.ot");
+    let mut weights = Tensor::load_multi(&weights_path)?;
+    for (name, tensor) in weights.iter_mut() {
+        if name == "classifier.bias" {
+            *tensor = Tensor::of_slice(bias).to_kind(tensor.kind());
+        }
+    }
+    Tensor::save_multi(&weights, &weights_path)?;
+    Ok(())
+}
+
+fn local_resource(directory: &Path, file_name: &str) -> Resource {
+    Resource::Local(LocalResource {
+        local_path: directory.join(file_name),
+    })
+}
+
+fn token_classification_config(directory: &Path) -> TokenClassificationConfig {
+    TokenClassificationConfig::new(
+        ModelType::Bert,
+        local_resource(directory, "rust_model.ot"),
+        local_resource(directory, "config.json"),
+        local_resource(directory, "vocab.txt"),
+        None,
+        true,
+        None,
+        None,
+        LabelAggregationOption::First,
+    )
+}
+
+fn slice_chars(text: &str, start: usize, end: usize) -> &str {
+    &text[char_to_byte(text, start).unwrap()..char_to_byte(text, end).unwrap()]
+}
+
+#[test]
+fn token_classification_unicode_offsets() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    let model = TokenClassificationModel::new(token_classification_config(directory.path()))?;
+
+    for &consolidate_sub_tokens in &[false, true] {
+        let output = model.predict(&ADVERSARIAL_INPUTS, consolidate_sub_tokens, false)?;
+        assert_eq!(output.len(), ADVERSARIAL_INPUTS.len());
+        for (input, tokens) in ADVERSARIAL_INPUTS.iter().zip(output.iter()) {
+            for token in tokens {
+                let offset = token.offset.unwrap();
+                assert_eq!(
+                    slice_chars(input, offset.begin as usize, offset.end as usize),
+                    token.text
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn ner_unicode_entities() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    let model = NERModel::new(token_classification_config(directory.path()))?;
+
+    let output = model.predict(&ADVERSARIAL_INPUTS)?;
+    assert_eq!(output.len(), ADVERSARIAL_INPUTS.len());
+    for (input, entities) in ADVERSARIAL_INPUTS.iter().zip(output.iter()) {
+        for entity in entities {
+            assert!(input.contains(entity.word.as_str()));
+            assert_eq!(slice_chars(input, entity.start, entity.end), entity.word);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn ner_entity_offsets() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    //    Every token is tagged as I-LOC, so that every token is returned as an entity
+    set_classifier_bias(directory.path(), &[-20.0, -20.0, 20.0])?;
+    let inputs = [
+        "  the dog is in paris.",
+        "\u{e9}t\u{e9} \u{1f600} paris",
+        "\t\u{65e5}\u{672c} dog",
+    ];
+
+    let model = NERModel::new(token_classification_config(directory.path()))?;
+    let output = model.predict(&inputs)?;
+    for (input, entities) in inputs.iter().zip(output.iter()) {
+        assert!(!entities.is_empty());
+        for entity in entities {
+            assert_eq!(slice_chars(input, entity.start, entity.end), entity.word);
+        }
+    }
+    let first = &output[0][0];
+    assert_eq!((first.word.as_str(), first.start, first.end), ("the", 2, 5));
+    let last = output[1].last().unwrap();
+    assert_eq!((last.word.as_str(), last.start, last.end), ("paris", 6, 11));
+    let last = output[2].last().unwrap();
+    assert_eq!((last.word.as_str(), last.start, last.end), ("dog", 4, 7));
+
+    let model = NERModel::new(TokenClassificationConfig {
+        aggregation_strategy: AggregationStrategy::Simple,
+        ..token_classification_config(directory.path())
+    })?;
+    let output = model.predict_full_entities(&inputs)?;
+    let entity = &output[0][0];
+    assert_eq!(
+        (entity.word.as_str(), entity.start, entity.end),
+        ("the dog is in paris.", 2, 22)
+    );
+    let entity = &output[1][0];
+    assert_eq!(
+        (entity.word.as_str(), entity.start, entity.end),
+        ("\u{e9}t\u{e9} \u{1f600} paris", 0, 11)
+    );
+    Ok(())
+}
+
+#[test]
+fn ner_unicode_full_entities() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    for &aggregation_strategy in &[
+        AggregationStrategy::None,
+        AggregationStrategy::Simple,
+        AggregationStrategy::First,
+        AggregationStrategy::Average,
+        AggregationStrategy::Max,
+    ] {
+        let model = NERModel::new(TokenClassificationConfig {
+            aggregation_strategy,
+            ..token_classification_config(directory.path())
+        })?;
+        let output = model.predict_full_entities(&ADVERSARIAL_INPUTS)?;
+        assert_eq!(output.len(), ADVERSARIAL_INPUTS.len());
+        for (input, entities) in ADVERSARIAL_INPUTS.iter().zip(output.iter()) {
+            for entity in entities {
+                assert_eq!(slice_chars(input, entity.start, entity.end), entity.word);
+                if aggregation_strategy != AggregationStrategy::None {
+                    assert_eq!(entity.label, "LOC");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn question_answering_unicode_offsets() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    let model = QuestionAnsweringModel::new(QuestionAnsweringConfig::new(
+        ModelType::Bert,
+        local_resource(directory.path(), "rust_model.ot"),
+        local_resource(directory.path(), "config.json"),
+        local_resource(directory.path(), "vocab.txt"),
+        None,
+        true,
+        None,
+        None,
+    ))?;
+
+    let inputs = ADVERSARIAL_INPUTS
+        .iter()
+        .map(|&context| QaInput {
+            question: "where is the dog\u{1f600}?".to_string(),
+            context: context.to_string(),
+        })
+        .collect::<Vec<QaInput>>();
+    let output = model.predict(&inputs, 5, 2);
+
+    assert_eq!(output.len(), inputs.len());
+    for (input, answers) in inputs.iter().zip(output.iter()) {
+        assert!(!answers.is_empty());
+        for answer in answers {
+            assert!(answer.start <= answer.end);
+            assert_eq!(
+                slice_chars(&input.context, answer.start, answer.end),
+                answer.answer
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn question_answering_original_context_offsets() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    write_tiny_bert(directory.path())?;
+    let model = QuestionAnsweringModel::new(QuestionAnsweringConfig::new(
+        ModelType::Bert,
+        local_resource(directory.path(), "rust_model.ot"),
+        local_resource(directory.path(), "config.json"),
+        local_resource(directory.path(), "vocab.txt"),
+        None,
+        true,
+        None,
+        None,
+    ))?;
+
+    //    Emoji, non-breaking spaces, repeated whitespace and CRLF line endings are normalized away
+    //    by the tokenizer but must be kept in the answer offsets
+    let contexts = [
+        "the dog \u{1f436} is in\u{a0}paris.",
+        "\u{1f436}\u{1f436}  the\u{a0}\u{a0}dog\u{a0}is in  paris \u{1f1eb}\u{1f1f7}.",
+        "the dog\r\nis in\r\n\r\nparis.\r\n",
+        "\r\n\u{a0}\u{1f600} where\r\n\u{a0}is the dog\u{1f600}?\u{a0}in paris\r\n",
+    ];
+    let inputs = contexts
+        .iter()
+        .map(|&context| QaInput {
+            question: "where is the dog?".to_string(),
+            context: context.to_string(),
+        })
+        .collect::<Vec<QaInput>>();
+    let output = model.predict(&inputs, 20, 4);
+
+    assert_eq!(output.len(), inputs.len());
+    for (input, answers) in inputs.iter().zip(output.iter()) {
+        assert!(!answers.is_empty());
+        for answer in answers {
+            let (byte_start, byte_end) = answer.byte_offsets(&input.context).unwrap();
+            assert_eq!(&input.context[byte_start..byte_end], answer.answer);
+            assert_eq!(
+                slice_chars(&input.context, answer.start, answer.end),
+                answer.answer
+            );
+            assert_eq!(answer.answer.trim(), answer.answer);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn question_answering_byte_offsets() {
+    let context = "\u{1f436}\u{a0}the dog\r\nis in paris";
+    let answer = Answer {
+        score: 0.5,
+        start: 17,
+        end: 22,
+        answer: "paris".to_string(),
+        context_index: 0,
+    };
+    assert_eq!(answer.byte_offsets(context), Some((22, 27)));
+    assert_eq!(&context[22..27], "paris");
+    assert_eq!(slice_chars(context, answer.start, answer.end), "paris");
+
+    let out_of_bounds = Answer { end: 23, ..answer };
+    assert_eq!(out_of_bounds.byte_offsets(context), None);
+}